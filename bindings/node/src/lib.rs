@@ -0,0 +1,289 @@
+//! Node.js bindings for IOTA Streams channels
+//!
+//! The module exposes a [`User`](streams::User) driving a channel over a Tangle node with the
+//! native [`tangle::Client`], which performs proof of work locally and connects to the node over
+//! TLS. Every operation reaching the node returns a `Promise`:
+//!
+//! ```js
+//! const { User } = require('@iota/streams-node')
+//!
+//! const reader = await User.fromSeed('READER9SEED', 'https://chrysalis-nodes.iota.org')
+//! await reader.receiveMessage(announcement)
+//! for (const message of await reader.fetchNextMessages()) {
+//!   console.log(message.sequence, message.publicPayload?.toString())
+//! }
+//! ```
+
+// Rust
+use std::str::FromStr;
+
+// 3rd-party
+use napi::bindgen_prelude::{Buffer, Error, Result};
+use napi_derive::napi;
+
+// IOTA
+
+// Streams
+use streams::{id::Ed25519, transport::tangle, Address, MessageContent, SharedUser, User};
+
+// Local
+
+/// Transport used by the bindings
+type Transport = tangle::Client;
+
+/// Converts a Streams error into a JavaScript error
+///
+/// # Arguments
+/// * `error`: The error to convert
+fn js_err<E>(error: E) -> Error
+where
+    E: ToString,
+{
+    Error::from_reason(error.to_string())
+}
+
+/// Parses the string representation of an [`Address`]
+///
+/// # Arguments
+/// * `address`: The address, as returned by the bindings
+fn parse_address(address: &str) -> Result<Address> {
+    Address::from_str(address).map_err(|e| js_err(format!("invalid address '{}': {}", address, e)))
+}
+
+/// Returns the name of the type of the content of a message
+///
+/// # Arguments
+/// * `content`: The content of the message
+fn content_kind(content: &MessageContent) -> &'static str {
+    match content {
+        MessageContent::Announcement(_) => "announcement",
+        MessageContent::BranchAnnouncement(_) => "branchAnnouncement",
+        MessageContent::BranchClosed(_) => "branchClosed",
+        MessageContent::Keyload(_) => "keyload",
+        MessageContent::SignedPacket(_) => "signedPacket",
+        MessageContent::TaggedPacket(_) => "taggedPacket",
+        MessageContent::SelectivePacket(_) => "selectivePacket",
+        MessageContent::BatchPacket(_) => "batchPacket",
+        MessageContent::Subscription(_) => "subscription",
+        MessageContent::Unsubscription(_) => "unsubscription",
+        MessageContent::KeyUpdate(_) => "keyUpdate",
+        MessageContent::SubstreamAnnounced(_) => "substreamAnnounced",
+        MessageContent::ReadMarker(_) => "readMarker",
+        MessageContent::Tombstone(_) => "tombstone",
+        MessageContent::AuthorRotation(_) => "authorRotation",
+        MessageContent::Countersignature(_) => "countersignature",
+        MessageContent::Commitment(_) => "commitment",
+        MessageContent::Reveal(_) => "reveal",
+        MessageContent::Custom(_) => "custom",
+        MessageContent::Rejected(_) => "rejected",
+        MessageContent::Redacted(_) => "redacted",
+        MessageContent::AccessExpired(_) => "access_expired",
+        MessageContent::Orphan(_) => "orphan",
+        MessageContent::OutOfOrder(_) => "outOfOrder",
+        MessageContent::DuplicateReceived(_) => "duplicateReceived",
+        MessageContent::Legacy(_) => "legacy",
+    }
+}
+
+/// A message of a channel, as read by a [`JsUser`]
+#[napi(object, js_name = "Message")]
+pub struct JsMessage {
+    /// The address of the message
+    pub address: String,
+    /// The address of the message the message is linked to, if any
+    pub linked_address: Option<String>,
+    /// The identifier of the publisher of the message
+    pub publisher: String,
+    /// The sequence number of the message among the messages of its publisher
+    pub sequence: u32,
+    /// The topic of the branch of the message, if known by the user
+    pub topic: Option<String>,
+    /// The type of the content of the message, like `signedPacket`
+    pub kind: String,
+    /// The public payload of packets
+    pub public_payload: Option<Buffer>,
+    /// The masked payload of packets
+    pub masked_payload: Option<Buffer>,
+}
+
+impl JsMessage {
+    /// Converts a [`Message`](streams::Message) read by a [`User`]
+    ///
+    /// # Arguments
+    /// * `user`: The [`User`] that read the message, resolving its topic
+    /// * `message`: The [`Message`](streams::Message) to convert
+    fn new(user: &User<Transport>, message: &streams::Message) -> Self {
+        Self {
+            address: message.address().to_string(),
+            linked_address: message.linked_address().map(|address| address.to_string()),
+            publisher: message.publisher().to_string(),
+            sequence: message.sequence() as u32,
+            topic: user.topic_by_hash(message.topic_hash()).map(|topic| topic.to_string()),
+            kind: content_kind(message.content()).to_string(),
+            public_payload: message.public_payload().map(|payload| payload.to_vec().into()),
+            masked_payload: message.masked_payload().map(|payload| payload.to_vec().into()),
+        }
+    }
+}
+
+/// A user of Streams channels, publishing and reading messages through a Tangle node
+#[napi(js_name = "User")]
+pub struct JsUser(SharedUser<Transport>);
+
+#[napi]
+impl JsUser {
+    /// Creates a new user with an `Ed25519` identity derived from a seed, connected to a node
+    ///
+    /// # Arguments
+    /// * `seed`: The seed of the identity of the user
+    /// * `node_url`: The URL of the Tangle node
+    #[napi]
+    pub async fn from_seed(seed: String, node_url: String) -> Result<JsUser> {
+        let transport = Transport::for_node(&node_url).await.map_err(js_err)?;
+        let user = User::builder()
+            .with_identity(Ed25519::from_seed(seed))
+            .with_transport(transport)
+            .build();
+        Ok(Self(SharedUser::new(user)))
+    }
+
+    /// Restores a user from a backup made with [`JsUser::backup()`]
+    ///
+    /// # Arguments
+    /// * `backup`: The encrypted backup
+    /// * `password`: The password the backup is encrypted with
+    /// * `node_url`: The URL of the Tangle node
+    #[napi]
+    pub async fn restore(backup: Buffer, password: String, node_url: String) -> Result<JsUser> {
+        let transport = Transport::for_node(&node_url).await.map_err(js_err)?;
+        let user = User::restore(backup, password, transport).await.map_err(js_err)?;
+        Ok(Self(SharedUser::new(user)))
+    }
+
+    /// Returns an encrypted backup of the state of the user
+    ///
+    /// # Arguments
+    /// * `password`: The password to encrypt the backup with
+    #[napi]
+    pub async fn backup(&self, password: String) -> Result<Buffer> {
+        let backup = self.0.lock().await.backup(password).await.map_err(js_err)?;
+        Ok(backup.into())
+    }
+
+    /// The identifier of the user
+    #[napi(getter)]
+    pub fn identifier(&self) -> Result<Option<String>> {
+        let user = self.0.try_lock().ok_or_else(|| js_err("the user is busy"))?;
+        Ok(user.identifier().map(|identifier| identifier.to_string()))
+    }
+
+    /// The address of the announcement of the channel of the user, once created or received
+    #[napi(getter)]
+    pub fn stream_address(&self) -> Result<Option<String>> {
+        let user = self.0.try_lock().ok_or_else(|| js_err("the user is busy"))?;
+        Ok(user.stream_address().map(|address| address.to_string()))
+    }
+
+    /// Creates a new channel, returning the address of its announcement
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the base branch of the channel
+    #[napi]
+    pub async fn create_stream(&self, topic: String) -> Result<String> {
+        let response = self.0.lock().await.create_stream(topic).await.map_err(js_err)?;
+        Ok(response.address().to_string())
+    }
+
+    /// Reads the message at an address, returning it
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message, like the announcement of a channel
+    #[napi]
+    pub async fn receive_message(&self, address: String) -> Result<JsMessage> {
+        let address = parse_address(&address)?;
+        let mut user = self.0.lock().await;
+        let message = user.receive_message(address).await.map_err(js_err)?;
+        Ok(JsMessage::new(&user, &message))
+    }
+
+    /// Subscribes to the channel of the received announcement, returning the address of the
+    /// subscription
+    #[napi]
+    pub async fn subscribe(&self) -> Result<String> {
+        let response = self.0.lock().await.subscribe().await.map_err(js_err)?;
+        Ok(response.address().to_string())
+    }
+
+    /// Grants the known subscribers access to a branch, returning the address of the keyload
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the branch
+    /// * `write`: Whether the subscribers are granted write access as well as read access
+    #[napi]
+    pub async fn send_keyload(&self, topic: String, write: Option<bool>) -> Result<String> {
+        let mut user = self.0.lock().await;
+        let response = match write.unwrap_or(false) {
+            true => user.send_keyload_for_all_rw(topic).await,
+            false => user.send_keyload_for_all(topic).await,
+        }
+        .map_err(js_err)?;
+        Ok(response.address().to_string())
+    }
+
+    /// Publishes a signed packet, returning its address
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the branch
+    /// * `public_payload`: The payload readable by anyone
+    /// * `masked_payload`: The payload readable by the users with access to the branch
+    #[napi]
+    pub async fn send_signed_packet(
+        &self,
+        topic: String,
+        public_payload: Buffer,
+        masked_payload: Buffer,
+    ) -> Result<String> {
+        let response = self
+            .0
+            .send_signed_packet(topic, public_payload.to_vec(), masked_payload.to_vec())
+            .await
+            .map_err(js_err)?;
+        Ok(response.address().to_string())
+    }
+
+    /// Publishes a tagged packet, authenticated by the key of the branch, returning its address
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the branch
+    /// * `public_payload`: The payload readable by anyone
+    /// * `masked_payload`: The payload readable by the users with access to the branch
+    #[napi]
+    pub async fn send_tagged_packet(
+        &self,
+        topic: String,
+        public_payload: Buffer,
+        masked_payload: Buffer,
+    ) -> Result<String> {
+        let response = self
+            .0
+            .send_tagged_packet(topic, public_payload.to_vec(), masked_payload.to_vec())
+            .await
+            .map_err(js_err)?;
+        Ok(response.address().to_string())
+    }
+
+    /// Fetches the messages published since the last read, returning how many were read
+    #[napi]
+    pub async fn sync(&self) -> Result<u32> {
+        let read = self.0.sync().await.map_err(js_err)?;
+        Ok(read as u32)
+    }
+
+    /// Fetches the messages published since the last read, returning them
+    #[napi]
+    pub async fn fetch_next_messages(&self) -> Result<Vec<JsMessage>> {
+        let mut user = self.0.lock().await;
+        let messages = user.fetch_next_messages().await.map_err(js_err)?;
+        Ok(messages.iter().map(|message| JsMessage::new(&user, message)).collect())
+    }
+}
@@ -0,0 +1,401 @@
+//! Python bindings for IOTA Streams channels
+//!
+//! The `iota_streams` module exposes a [`User`](streams::User) driving a channel over a Tangle
+//! node. Every operation reaching the node is a coroutine, run on the `tokio` runtime of
+//! `pyo3-asyncio`. Messages are read with an async iterator and can be turned into plain records,
+//! so that channel data can be loaded into `pandas` without a Rust service in between:
+//!
+//! ```python
+//! user = iota_streams.User("SUBSCRIBER9SEED", "https://chrysalis-nodes.iota.org")
+//! await user.receive_message(announcement)
+//! frame = pandas.DataFrame([message.to_dict() async for message in user.messages()])
+//! ```
+
+// Rust
+use std::{collections::VecDeque, str::FromStr, sync::Arc};
+
+// 3rd-party
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyStopAsyncIteration},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+use tokio::sync::Mutex;
+
+// IOTA
+
+// Streams
+use streams::{id::Ed25519, transport::utangle, Address, Message, MessageContent, SharedUser, User};
+
+// Local
+
+/// Transport used by the bindings
+type Transport = utangle::Client;
+
+create_exception!(
+    iota_streams,
+    StreamsError,
+    PyException,
+    "Error raised by a Streams operation"
+);
+
+/// Converts a Streams error into a Python exception
+///
+/// # Arguments
+/// * `error`: The error to convert
+fn py_err<E>(error: E) -> PyErr
+where
+    E: ToString,
+{
+    StreamsError::new_err(error.to_string())
+}
+
+/// Parses the string representation of an [`Address`]
+///
+/// # Arguments
+/// * `address`: The address, as returned by the bindings
+fn parse_address(address: &str) -> PyResult<Address> {
+    Address::from_str(address).map_err(|e| py_err(format!("invalid address '{}': {}", address, e)))
+}
+
+/// Returns the name of the type of the content of a message
+///
+/// # Arguments
+/// * `content`: The content of the message
+fn content_kind(content: &MessageContent) -> &'static str {
+    match content {
+        MessageContent::Announcement(_) => "announcement",
+        MessageContent::BranchAnnouncement(_) => "branch_announcement",
+        MessageContent::BranchClosed(_) => "branch_closed",
+        MessageContent::Keyload(_) => "keyload",
+        MessageContent::SignedPacket(_) => "signed_packet",
+        MessageContent::TaggedPacket(_) => "tagged_packet",
+        MessageContent::SelectivePacket(_) => "selective_packet",
+        MessageContent::BatchPacket(_) => "batch_packet",
+        MessageContent::Subscription(_) => "subscription",
+        MessageContent::Unsubscription(_) => "unsubscription",
+        MessageContent::KeyUpdate(_) => "key_update",
+        MessageContent::SubstreamAnnounced(_) => "substream_announced",
+        MessageContent::ReadMarker(_) => "read_marker",
+        MessageContent::Tombstone(_) => "tombstone",
+        MessageContent::AuthorRotation(_) => "author_rotation",
+        MessageContent::Countersignature(_) => "countersignature",
+        MessageContent::Commitment(_) => "commitment",
+        MessageContent::Reveal(_) => "reveal",
+        MessageContent::Custom(_) => "custom",
+        MessageContent::Rejected(_) => "rejected",
+        MessageContent::Redacted(_) => "redacted",
+        MessageContent::AccessExpired(_) => "access_expired",
+        MessageContent::Orphan(_) => "orphan",
+        MessageContent::OutOfOrder(_) => "out_of_order",
+        MessageContent::DuplicateReceived(_) => "duplicate_received",
+        MessageContent::Legacy(_) => "legacy",
+    }
+}
+
+/// A message of a channel, as read by a [`PyUser`]
+#[pyclass(name = "Message")]
+#[derive(Clone)]
+struct PyMessage {
+    /// The address of the message
+    #[pyo3(get)]
+    address: String,
+    /// The address of the message the message is linked to, if any
+    #[pyo3(get)]
+    linked_address: Option<String>,
+    /// The identifier of the publisher of the message
+    #[pyo3(get)]
+    publisher: String,
+    /// The sequence number of the message among the messages of its publisher
+    #[pyo3(get)]
+    sequence: usize,
+    /// The topic of the branch of the message, if known by the user
+    #[pyo3(get)]
+    topic: Option<String>,
+    /// The type of the content of the message, like `signed_packet`
+    #[pyo3(get)]
+    kind: String,
+    /// The public payload of packets
+    public_payload: Option<Vec<u8>>,
+    /// The masked payload of packets
+    masked_payload: Option<Vec<u8>>,
+}
+
+impl PyMessage {
+    /// Converts a [`Message`] read by a [`User`]
+    ///
+    /// # Arguments
+    /// * `user`: The [`User`] that read the message, resolving its topic
+    /// * `message`: The [`Message`] to convert
+    fn new(user: &User<Transport>, message: &Message) -> Self {
+        Self {
+            address: message.address().to_string(),
+            linked_address: message.linked_address().map(|address| address.to_string()),
+            publisher: message.publisher().to_string(),
+            sequence: message.sequence(),
+            topic: user.topic_by_hash(message.topic_hash()).map(|topic| topic.to_string()),
+            kind: content_kind(message.content()).to_string(),
+            public_payload: message.public_payload().map(<[u8]>::to_vec),
+            masked_payload: message.masked_payload().map(<[u8]>::to_vec),
+        }
+    }
+}
+
+#[pymethods]
+impl PyMessage {
+    /// The public payload of packets, `None` for other messages
+    #[getter]
+    fn public_payload<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
+        self.public_payload.as_ref().map(|payload| PyBytes::new(py, payload))
+    }
+
+    /// The masked payload of packets, `None` for other messages
+    #[getter]
+    fn masked_payload<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
+        self.masked_payload.as_ref().map(|payload| PyBytes::new(py, payload))
+    }
+
+    /// Returns the message as a dictionary, one record of a `pandas.DataFrame`
+    fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let record = PyDict::new(py);
+        record.set_item("address", &self.address)?;
+        record.set_item("linked_address", &self.linked_address)?;
+        record.set_item("publisher", &self.publisher)?;
+        record.set_item("sequence", self.sequence)?;
+        record.set_item("topic", &self.topic)?;
+        record.set_item("kind", &self.kind)?;
+        record.set_item("public_payload", self.public_payload(py))?;
+        record.set_item("masked_payload", self.masked_payload(py))?;
+        Ok(record)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Message(address='{}', kind='{}')", self.address, self.kind)
+    }
+}
+
+/// Async iterator over the messages published since the last read, returned by
+/// [`PyUser::messages()`]. The iteration ends once the user has caught up with the channel.
+#[pyclass(name = "Messages")]
+struct PyMessages {
+    /// The user reading the messages
+    user: SharedUser<Transport>,
+    /// The messages fetched and not yet yielded
+    pending: Arc<Mutex<VecDeque<PyMessage>>>,
+}
+
+#[pymethods]
+impl PyMessages {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyAny>> {
+        let user = self.user.clone();
+        let pending = self.pending.clone();
+        let next = pyo3_asyncio::tokio::future_into_py(py, async move {
+            let mut pending = pending.lock().await;
+            if pending.is_empty() {
+                let mut user = user.lock().await;
+                let messages = user.fetch_next_messages().await.map_err(py_err)?;
+                pending.extend(messages.iter().map(|message| PyMessage::new(&user, message)));
+            }
+            match pending.pop_front() {
+                Some(message) => Ok(message),
+                None => Err(PyStopAsyncIteration::new_err("the user has caught up with the channel")),
+            }
+        })?;
+        Ok(Some(next))
+    }
+}
+
+/// A user of Streams channels, publishing and reading messages through a Tangle node
+#[pyclass(name = "User")]
+#[derive(Clone)]
+struct PyUser(SharedUser<Transport>);
+
+#[pymethods]
+impl PyUser {
+    /// Creates a new user with an `Ed25519` identity derived from a seed
+    ///
+    /// # Arguments
+    /// * `seed`: The seed of the identity of the user
+    /// * `node_url`: The URL of the Tangle node
+    #[new]
+    fn new(seed: &str, node_url: &str) -> Self {
+        let user = User::builder()
+            .with_identity(Ed25519::from_seed(seed))
+            .with_transport(Transport::new(node_url))
+            .build();
+        Self(SharedUser::new(user))
+    }
+
+    /// Restores a user from a backup made with [`PyUser::backup()`]
+    ///
+    /// # Arguments
+    /// * `backup`: The encrypted backup
+    /// * `password`: The password the backup is encrypted with
+    /// * `node_url`: The URL of the Tangle node
+    #[staticmethod]
+    fn restore<'p>(py: Python<'p>, backup: &[u8], password: String, node_url: String) -> PyResult<&'p PyAny> {
+        let backup = backup.to_vec();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let user = User::restore(backup, password, Transport::new(&node_url))
+                .await
+                .map_err(py_err)?;
+            Ok(Self(SharedUser::new(user)))
+        })
+    }
+
+    /// Returns an encrypted backup of the state of the user, as `bytes`
+    ///
+    /// # Arguments
+    /// * `password`: The password to encrypt the backup with
+    fn backup<'p>(&self, py: Python<'p>, password: String) -> PyResult<&'p PyAny> {
+        let user = self.0.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let backup = user.lock().await.backup(password).await.map_err(py_err)?;
+            Ok(Python::with_gil(|py| PyObject::from(PyBytes::new(py, &backup))))
+        })
+    }
+
+    /// The identifier of the user
+    #[getter]
+    fn identifier(&self) -> PyResult<Option<String>> {
+        let user = self.0.try_lock().ok_or_else(|| py_err("the user is busy"))?;
+        Ok(user.identifier().map(|identifier| identifier.to_string()))
+    }
+
+    /// The address of the announcement of the channel of the user, once created or received
+    #[getter]
+    fn stream_address(&self) -> PyResult<Option<String>> {
+        let user = self.0.try_lock().ok_or_else(|| py_err("the user is busy"))?;
+        Ok(user.stream_address().map(|address| address.to_string()))
+    }
+
+    /// Creates a new channel, returning the address of its announcement
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the base branch of the channel
+    fn create_stream<'p>(&self, py: Python<'p>, topic: String) -> PyResult<&'p PyAny> {
+        let user = self.0.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let response = user.lock().await.create_stream(topic).await.map_err(py_err)?;
+            Ok(response.address().to_string())
+        })
+    }
+
+    /// Reads the message at an address, returning it
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message, like the announcement of a channel
+    fn receive_message<'p>(&self, py: Python<'p>, address: &str) -> PyResult<&'p PyAny> {
+        let address = parse_address(address)?;
+        let user = self.0.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let mut user = user.lock().await;
+            let message = user.receive_message(address).await.map_err(py_err)?;
+            Ok(PyMessage::new(&user, &message))
+        })
+    }
+
+    /// Subscribes to the channel of the received announcement, returning the address of the
+    /// subscription
+    fn subscribe<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let user = self.0.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let response = user.lock().await.subscribe().await.map_err(py_err)?;
+            Ok(response.address().to_string())
+        })
+    }
+
+    /// Grants the known subscribers access to a branch, returning the address of the keyload
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the branch
+    /// * `write`: Whether the subscribers are granted write access as well as read access
+    #[args(write = "false")]
+    fn send_keyload<'p>(&self, py: Python<'p>, topic: String, write: bool) -> PyResult<&'p PyAny> {
+        let user = self.0.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let mut user = user.lock().await;
+            let response = match write {
+                true => user.send_keyload_for_all_rw(topic).await,
+                false => user.send_keyload_for_all(topic).await,
+            }
+            .map_err(py_err)?;
+            Ok(response.address().to_string())
+        })
+    }
+
+    /// Publishes a signed packet, returning its address
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the branch
+    /// * `public_payload`: The payload readable by anyone
+    /// * `masked_payload`: The payload readable by the users with access to the branch
+    fn send_signed_packet<'p>(
+        &self,
+        py: Python<'p>,
+        topic: String,
+        public_payload: &[u8],
+        masked_payload: &[u8],
+    ) -> PyResult<&'p PyAny> {
+        let (user, public_payload, masked_payload) = (self.0.clone(), public_payload.to_vec(), masked_payload.to_vec());
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let response = user
+                .send_signed_packet(topic, public_payload, masked_payload)
+                .await
+                .map_err(py_err)?;
+            Ok(response.address().to_string())
+        })
+    }
+
+    /// Publishes a tagged packet, authenticated by the key of the branch, returning its address
+    ///
+    /// # Arguments
+    /// * `topic`: The topic of the branch
+    /// * `public_payload`: The payload readable by anyone
+    /// * `masked_payload`: The payload readable by the users with access to the branch
+    fn send_tagged_packet<'p>(
+        &self,
+        py: Python<'p>,
+        topic: String,
+        public_payload: &[u8],
+        masked_payload: &[u8],
+    ) -> PyResult<&'p PyAny> {
+        let (user, public_payload, masked_payload) = (self.0.clone(), public_payload.to_vec(), masked_payload.to_vec());
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let response = user
+                .send_tagged_packet(topic, public_payload, masked_payload)
+                .await
+                .map_err(py_err)?;
+            Ok(response.address().to_string())
+        })
+    }
+
+    /// Fetches the messages published since the last read, returning how many were read
+    fn sync<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let user = self.0.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move { user.sync().await.map_err(py_err) })
+    }
+
+    /// Returns an async iterator over the messages published since the last read
+    fn messages(&self) -> PyMessages {
+        PyMessages {
+            user: self.0.clone(),
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+}
+
+/// Python bindings for IOTA Streams channels
+#[pymodule]
+fn iota_streams(py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyUser>()?;
+    module.add_class::<PyMessage>()?;
+    module.add_class::<PyMessages>()?;
+    module.add("StreamsError", py.get_type::<StreamsError>())?;
+    Ok(())
+}
@@ -0,0 +1,271 @@
+//! Command line client for IOTA Streams channels
+//!
+//! Every invocation restores the [`User`] from an encrypted state file, runs a single channel
+//! operation against a Tangle node and writes the updated state back, so channels can be driven
+//! from scripts without writing Rust. The state file is a regular [`User::backup()`].
+
+// Rust
+use std::{fs, path::PathBuf, str::FromStr};
+
+// 3rd-party
+use anyhow::anyhow;
+use clap::{Parser, Subcommand};
+
+// IOTA
+
+// Streams
+use streams::{id::Ed25519, transport::utangle, Address, Message, MessageContent, Result, User};
+
+/// Transport used by the command line client
+type Transport = utangle::Client;
+
+#[derive(Parser)]
+#[clap(
+    name = "streams-cli",
+    version,
+    about = "Command line client for IOTA Streams channels"
+)]
+struct Cli {
+    /// File the encrypted state of the user is kept in between invocations
+    #[clap(long, env = "STREAMS_STATE", default_value = "streams-cli.state")]
+    state: PathBuf,
+    /// Password the state file is encrypted with
+    #[clap(long, env = "STREAMS_PASSWORD")]
+    password: String,
+    /// URL of the Tangle node
+    #[clap(long, env = "URL", default_value = "https://chrysalis-nodes.iota.org")]
+    node: String,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new channel, printing the address of its announcement
+    Announce {
+        /// Seed of the identity of the author
+        #[clap(long, env = "STREAMS_SEED")]
+        seed: String,
+        /// Topic of the base branch of the channel
+        #[clap(long, default_value = "BASE_BRANCH")]
+        topic: String,
+    },
+    /// Subscribe to a channel, printing the address of the subscription
+    Subscribe {
+        /// Seed of the identity of the subscriber
+        #[clap(long, env = "STREAMS_SEED")]
+        seed: String,
+        /// Address of the announcement of the channel
+        announcement: String,
+    },
+    /// Grant the known subscribers access to a branch
+    Keyload {
+        /// Topic of the branch
+        #[clap(long, default_value = "BASE_BRANCH")]
+        topic: String,
+        /// Grant write access as well as read access
+        #[clap(long)]
+        write: bool,
+    },
+    /// Publish a packet, printing its address
+    Send {
+        /// Topic of the branch
+        #[clap(long, default_value = "BASE_BRANCH")]
+        topic: String,
+        /// Payload readable by anyone
+        #[clap(long, default_value = "")]
+        public: String,
+        /// Payload readable by the users with access to the branch
+        #[clap(long, default_value = "")]
+        masked: String,
+        /// Send a tagged packet, authenticated by the branch key instead of a signature
+        #[clap(long)]
+        tagged: bool,
+    },
+    /// Fetch and print the messages published since the last fetch
+    Fetch,
+    /// Export the state of the user to a backup file encrypted with its own password
+    Backup {
+        /// File to write the backup to
+        path: PathBuf,
+        /// Password to encrypt the backup with
+        #[clap(long)]
+        backup_password: String,
+    },
+    /// Replace the state of the user with a backup file
+    Restore {
+        /// File to read the backup from
+        path: PathBuf,
+        /// Password the backup is encrypted with
+        #[clap(long)]
+        backup_password: String,
+    },
+}
+
+/// Restores the [`User`] from the state file
+///
+/// # Arguments
+/// * `cli`: The parsed command line
+async fn load(cli: &Cli) -> Result<User<Transport>> {
+    let backup =
+        fs::read(&cli.state).map_err(|e| anyhow!("cannot read state file '{}': {}", cli.state.display(), e))?;
+    User::restore(backup, &cli.password, Transport::new(&cli.node)).await
+}
+
+/// Writes the state of the [`User`] to the state file
+///
+/// # Arguments
+/// * `cli`: The parsed command line
+/// * `user`: The [`User`] to persist
+async fn save(cli: &Cli, user: &mut User<Transport>) -> Result<()> {
+    let backup = user.backup(&cli.password).await?;
+    fs::write(&cli.state, backup).map_err(|e| anyhow!("cannot write state file '{}': {}", cli.state.display(), e))?;
+    Ok(())
+}
+
+/// Prints a one-line summary of a [`Message`]
+///
+/// # Arguments
+/// * `message`: The [`Message`] to print
+fn print_message(message: &Message) {
+    let publisher = message.header.publisher();
+    let summary = match &message.content {
+        MessageContent::Announcement(announcement) => match &announcement.quorum {
+            Some(quorum) => format!(
+                "announcement approved by {} of {} author keys",
+                quorum.threshold(),
+                quorum.members().len()
+            ),
+            None => "announcement".to_string(),
+        },
+        MessageContent::BranchAnnouncement(branch) => format!("branch announcement of '{}'", branch.topic),
+        MessageContent::BranchClosed(closed) => format!("closure of branch '{}'", closed.topic),
+        MessageContent::Keyload(keyload) => format!("keyload for {} subscribers", keyload.subscribers.len()),
+        MessageContent::SignedPacket(packet) => format!(
+            "signed packet public='{}' masked='{}'",
+            String::from_utf8_lossy(&packet.public_payload),
+            String::from_utf8_lossy(&packet.masked_payload)
+        ),
+        MessageContent::TaggedPacket(packet) => format!(
+            "tagged packet public='{}' masked='{}'",
+            String::from_utf8_lossy(&packet.public_payload),
+            String::from_utf8_lossy(&packet.masked_payload)
+        ),
+        MessageContent::SelectivePacket(packet) => format!(
+            "selective packet with {} readable fields",
+            packet.fields.iter().filter(|field| field.is_some()).count()
+        ),
+        MessageContent::BatchPacket(packet) => format!("batch packet of {} records", packet.records.len()),
+        MessageContent::Subscription(_) => "subscription".to_string(),
+        MessageContent::Unsubscription(_) => "unsubscription".to_string(),
+        MessageContent::KeyUpdate(update) => format!("key update to generation {}", update.generation),
+        MessageContent::SubstreamAnnounced(substream) => {
+            format!("substream '{}' announced at {}", substream.topic, substream.address)
+        }
+        MessageContent::ReadMarker(read_marker) => format!("read up to {}", read_marker.marked_address),
+        MessageContent::Tombstone(tombstone) => format!("redacted {}: {}", tombstone.redacted, tombstone.reason),
+        MessageContent::AuthorRotation(rotation) => format!("authorship handed over to {}", rotation.new_author),
+        MessageContent::Countersignature(countersignature) => format!(
+            "countersignature of {} by {}",
+            countersignature.countersigned, countersignature.witness
+        ),
+        MessageContent::Commitment(_) => "commitment to a payload revealed later".to_string(),
+        MessageContent::Reveal(reveal) => format!(
+            "revealed '{}' committed to in {}",
+            String::from_utf8_lossy(&reveal.payload),
+            reveal.commitment
+        ),
+        MessageContent::Custom(custom) => format!("custom message of type {}", custom.message_type),
+        MessageContent::Rejected(rejected) => format!("rejected packet: {}", rejected.reason),
+        MessageContent::Redacted(redacted) => format!("redacted packet: {}", redacted.reason),
+        MessageContent::AccessExpired(expired) => format!("packet read after access expired at {}", expired.expired_at),
+        MessageContent::Orphan(_) => "orphan".to_string(),
+        MessageContent::OutOfOrder(out_of_order) => format!("received before {}", out_of_order.awaiting),
+        MessageContent::DuplicateReceived(_) => "message received again".to_string(),
+        MessageContent::Legacy(legacy) => format!("legacy message of type {}", legacy.message_type),
+    };
+    println!("{} {} {}", message.address, publisher, summary);
+}
+
+/// Runs the command of the parsed command line
+///
+/// # Arguments
+/// * `cli`: The parsed command line
+async fn run(cli: &Cli) -> Result<()> {
+    match &cli.command {
+        Command::Announce { seed, topic } => {
+            let mut user = User::builder()
+                .with_identity(Ed25519::from_seed(seed))
+                .with_transport(Transport::new(&cli.node))
+                .build();
+            let announcement = user.create_stream(topic.as_str()).await?;
+            save(cli, &mut user).await?;
+            println!("{}", announcement.address());
+        }
+        Command::Subscribe { seed, announcement } => {
+            let announcement =
+                Address::from_str(announcement).map_err(|e| anyhow!("invalid announcement address: {}", e))?;
+            let mut user = User::builder()
+                .with_identity(Ed25519::from_seed(seed))
+                .with_transport(Transport::new(&cli.node))
+                .build();
+            user.receive_message(announcement).await?;
+            let subscription = user.subscribe().await?;
+            save(cli, &mut user).await?;
+            println!("{}", subscription.address());
+        }
+        Command::Keyload { topic, write } => {
+            let mut user = load(cli).await?;
+            user.sync().await?;
+            let keyload = if *write {
+                user.send_keyload_for_all_rw(topic.as_str()).await?
+            } else {
+                user.send_keyload_for_all(topic.as_str()).await?
+            };
+            save(cli, &mut user).await?;
+            println!("{}", keyload.address());
+        }
+        Command::Send {
+            topic,
+            public,
+            masked,
+            tagged,
+        } => {
+            let mut user = load(cli).await?;
+            user.sync().await?;
+            let packet = if *tagged {
+                user.send_tagged_packet(topic.as_str(), public, masked).await?
+            } else {
+                user.send_signed_packet(topic.as_str(), public, masked).await?
+            };
+            save(cli, &mut user).await?;
+            println!("{}", packet.address());
+        }
+        Command::Fetch => {
+            let mut user = load(cli).await?;
+            let messages = user.fetch_next_messages().await?;
+            save(cli, &mut user).await?;
+            messages.iter().for_each(print_message);
+        }
+        Command::Backup { path, backup_password } => {
+            let mut user = load(cli).await?;
+            let backup = user.backup(backup_password).await?;
+            fs::write(path, backup).map_err(|e| anyhow!("cannot write backup '{}': {}", path.display(), e))?;
+        }
+        Command::Restore { path, backup_password } => {
+            let backup = fs::read(path).map_err(|e| anyhow!("cannot read backup '{}': {}", path.display(), e))?;
+            let mut user = User::restore(backup, backup_password, Transport::new(&cli.node)).await?;
+            save(cli, &mut user).await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(&cli).await {
+        eprintln!("streams-cli: {}", e);
+        std::process::exit(1);
+    }
+}
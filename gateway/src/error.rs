@@ -0,0 +1,117 @@
+// Rust
+
+// 3rd-party
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+// IOTA
+
+// Streams
+
+// Local
+
+/// Failure of a request, answered with its status and a JSON body `{"error": <message>}`, with the
+/// `code` and `hint` of the error for failures of the library
+#[derive(Debug)]
+pub(crate) struct ApiError {
+    /// The status of the response
+    status: StatusCode,
+    /// The description of the failure
+    message: String,
+    /// The code and remediation hint of the [`streams::Error`] causing the failure, if any
+    cause: Option<(u16, &'static str)>,
+}
+
+impl ApiError {
+    /// The request is malformed
+    pub(crate) fn bad_request<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// The request does not carry the API token of the gateway
+    pub(crate) fn unauthorized<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// The request is refused, like a webhook posting to a host that is not allowed
+    pub(crate) fn forbidden<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// The requested user or webhook does not exist
+    pub(crate) fn not_found<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// The user to create already exists
+    pub(crate) fn conflict<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// The gateway cannot serve requests until the node it connects to is healthy
+    pub(crate) fn unavailable<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: message.into(),
+            cause: None,
+        }
+    }
+
+    /// The gateway failed to serve the request
+    pub(crate) fn internal<M: Into<String>>(message: M) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.into(),
+            cause: None,
+        }
+    }
+}
+
+impl From<streams::Error> for ApiError {
+    fn from(error: streams::Error) -> Self {
+        Self {
+            cause: Some((error.code(), error.hint())),
+            ..Self::internal(error.to_string())
+        }
+    }
+}
+
+impl core::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} ({})", self.message, self.status)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = match self.cause {
+            Some((code, hint)) => json!({ "error": self.message, "code": code, "hint": hint }),
+            None => json!({ "error": self.message }),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
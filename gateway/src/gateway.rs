@@ -0,0 +1,394 @@
+// Rust
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+// 3rd-party
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    sync::{Mutex, RwLock},
+};
+
+// IOTA
+
+// Streams
+use streams::{
+    id::Ed25519,
+    transport::{utangle, Transport as _},
+    Metrics, SharedUser, User,
+};
+
+// Local
+use crate::{error::ApiError, routes::MessageView};
+
+/// Transport used by the hosted users
+pub(crate) type Transport = utangle::Client;
+
+/// Extension of the files holding the encrypted state of the hosted users
+const STATE_EXTENSION: &str = "state";
+/// Extension of the files holding the webhooks of the hosted users
+const WEBHOOKS_EXTENSION: &str = "webhooks";
+/// Messages of a hosted user kept for each of its consumers until they take them, the oldest ones
+/// being dropped first
+const MAILBOX_CAPACITY: usize = 1024;
+
+/// Endpoint notified of the messages arriving on the channels of a hosted user
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Webhook {
+    /// Identifier of the webhook among the webhooks of the gateway
+    pub(crate) id: u64,
+    /// URL the new messages are posted to
+    pub(crate) url: String,
+}
+
+/// Messages fetched for a hosted user, waiting to be taken by its consumers
+#[derive(Default)]
+struct Mailbox {
+    /// Messages waiting to be returned by `GET /users/:name/messages`
+    api: VecDeque<MessageView>,
+    /// Messages waiting to be posted to the webhooks of the user
+    webhooks: VecDeque<MessageView>,
+}
+
+/// The users hosted by the gateway, with their webhooks. The state of every user is persisted in
+/// the state directory as a [`User::backup()`] after each operation, and reloaded on startup.
+pub(crate) struct Gateway {
+    /// URL of the Tangle node
+    node: String,
+    /// Directory the users and their webhooks are persisted in
+    state_dir: PathBuf,
+    /// Password the state files are encrypted with
+    password: String,
+    /// The hosted users, by name
+    users: RwLock<HashMap<String, SharedUser<Transport>>>,
+    /// The webhooks of the hosted users, by user name
+    webhooks: RwLock<HashMap<String, Vec<Webhook>>>,
+    /// Hosts the webhooks can be registered for
+    webhook_hosts: Vec<String>,
+    /// The messages fetched for the hosted users and not taken yet, by user name
+    mailboxes: Mutex<HashMap<String, Mailbox>>,
+    /// Identifier of the next registered webhook
+    next_webhook: AtomicU64,
+    /// Metrics of the hosted users, summed over all of them
+    metrics: Arc<Metrics>,
+}
+
+impl Gateway {
+    /// Opens the state directory, restoring the users and webhooks persisted in it
+    ///
+    /// # Arguments
+    /// * `node`: URL of the Tangle node
+    /// * `state_dir`: Directory the users and their webhooks are persisted in
+    /// * `password`: Password the state files are encrypted with
+    /// * `webhook_hosts`: Hosts the webhooks can be registered for
+    pub(crate) async fn open(
+        node: String,
+        state_dir: PathBuf,
+        password: String,
+        webhook_hosts: Vec<String>,
+    ) -> anyhow::Result<Self> {
+        fs::create_dir_all(&state_dir)
+            .await
+            .map_err(|e| anyhow!("cannot create state directory '{}': {}", state_dir.display(), e))?;
+        let metrics = Arc::new(Metrics::new());
+        let mut users = HashMap::new();
+        let mut webhooks = HashMap::new();
+        let mut entries = fs::read_dir(&state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some(STATE_EXTENSION) => {
+                    let backup = fs::read(&path).await?;
+                    let mut user = User::restore(backup, &password, Transport::new(&node))
+                        .await
+                        .map_err(|e| anyhow!("cannot restore user '{}': {}", name, e))?;
+                    user.set_metrics(metrics.clone());
+                    users.insert(name, SharedUser::new(user));
+                }
+                Some(WEBHOOKS_EXTENSION) => {
+                    let hooks: Vec<Webhook> = serde_json::from_slice(&fs::read(&path).await?)
+                        .map_err(|e| anyhow!("cannot read webhooks of user '{}': {}", name, e))?;
+                    webhooks.insert(name, hooks);
+                }
+                _ => continue,
+            }
+        }
+        let next_webhook = webhooks.values().flatten().map(|hook| hook.id + 1).max().unwrap_or(0);
+        Ok(Self {
+            node,
+            state_dir,
+            password,
+            users: RwLock::new(users),
+            webhooks: RwLock::new(webhooks),
+            webhook_hosts,
+            mailboxes: Mutex::new(HashMap::new()),
+            next_webhook: AtomicU64::new(next_webhook),
+            metrics,
+        })
+    }
+
+    /// Returns the path of a file of a hosted user in the state directory
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    /// * `extension`: The extension of the file
+    fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.state_dir.join(Path::new(name).with_extension(extension))
+    }
+
+    /// Creates and hosts a new user with an `Ed25519` identity derived from a seed
+    ///
+    /// # Arguments
+    /// * `name`: The name the user is hosted under, made of ASCII letters, digits, `-` and `_`
+    /// * `seed`: The seed of the identity of the user
+    pub(crate) async fn create_user(&self, name: &str, seed: &str) -> Result<SharedUser<Transport>, ApiError> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(ApiError::bad_request(format!("invalid user name '{}'", name)));
+        }
+        let mut users = self.users.write().await;
+        if users.contains_key(name) {
+            return Err(ApiError::conflict(format!("user '{}' already exists", name)));
+        }
+        let mut user = User::builder()
+            .with_identity(Ed25519::from_seed(seed))
+            .with_transport(Transport::new(&self.node))
+            .with_metrics(self.metrics.clone())
+            .build();
+        self.save(name, &mut user).await?;
+        let user = SharedUser::new(user);
+        users.insert(name.to_string(), user.clone());
+        Ok(user)
+    }
+
+    /// Returns a hosted user
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    pub(crate) async fn user(&self, name: &str) -> Result<SharedUser<Transport>, ApiError> {
+        self.users
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ApiError::not_found(format!("unknown user '{}'", name)))
+    }
+
+    /// Checks that the Tangle node the hosted users send and fetch their messages through is healthy
+    pub(crate) async fn health_check(&self) -> Result<(), ApiError> {
+        Transport::new(&self.node)
+            .health_check()
+            .await
+            .map_err(|e| ApiError::unavailable(format!("node '{}' is not ready: {}", self.node, e)))
+    }
+
+    /// Returns the metrics of the hosted users, summed over all of them
+    pub(crate) fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Returns the hosted users, sorted by name
+    pub(crate) async fn users(&self) -> Vec<(String, SharedUser<Transport>)> {
+        let mut users: Vec<_> = self
+            .users
+            .read()
+            .await
+            .iter()
+            .map(|(name, user)| (name.clone(), user.clone()))
+            .collect();
+        users.sort_by(|(a, _), (b, _)| a.cmp(b));
+        users
+    }
+
+    /// Persists the state of a hosted user
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    /// * `user`: The [`User`] to persist
+    pub(crate) async fn save(&self, name: &str, user: &mut User<Transport>) -> Result<(), ApiError> {
+        let backup = user.backup(&self.password).await?;
+        fs::write(self.path(name, STATE_EXTENSION), backup)
+            .await
+            .map_err(|e| ApiError::internal(format!("cannot write state of user '{}': {}", name, e)))
+    }
+
+    /// Registers a webhook notified of the messages arriving on the channels of a hosted user
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    /// * `url`: The URL the new messages are posted to
+    pub(crate) async fn add_webhook(&self, name: &str, url: String) -> Result<Webhook, ApiError> {
+        self.user(name).await?;
+        self.check_webhook_url(&url)?;
+        let webhook = Webhook {
+            id: self.next_webhook.fetch_add(1, Ordering::Relaxed),
+            url,
+        };
+        let mut webhooks = self.webhooks.write().await;
+        let hooks = webhooks.entry(name.to_string()).or_default();
+        hooks.push(webhook.clone());
+        self.save_webhooks(name, hooks).await?;
+        Ok(webhook)
+    }
+
+    /// Unregisters a webhook of a hosted user
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    /// * `id`: The identifier of the webhook
+    pub(crate) async fn remove_webhook(&self, name: &str, id: u64) -> Result<(), ApiError> {
+        let mut webhooks = self.webhooks.write().await;
+        let hooks = webhooks
+            .get_mut(name)
+            .filter(|hooks| hooks.iter().any(|hook| hook.id == id));
+        let hooks = hooks.ok_or_else(|| ApiError::not_found(format!("unknown webhook {} of user '{}'", id, name)))?;
+        hooks.retain(|hook| hook.id != id);
+        self.save_webhooks(name, hooks).await
+    }
+
+    /// Checks that a webhook URL is an `http` or `https` URL of one of the allowed hosts, so that
+    /// the gateway cannot be made to post to the services of its own network
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the webhook
+    pub(crate) fn check_webhook_url(&self, url: &str) -> Result<(), ApiError> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| ApiError::bad_request(format!("invalid webhook URL '{}': {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(ApiError::bad_request(format!(
+                "webhook URL '{}' is not an http or https URL",
+                url
+            )));
+        }
+        match parsed.host_str() {
+            Some(host)
+                if self
+                    .webhook_hosts
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(host)) =>
+            {
+                Ok(())
+            }
+            _ => Err(ApiError::forbidden(format!(
+                "the host of webhook URL '{}' is not allowed",
+                url
+            ))),
+        }
+    }
+
+    /// Returns the webhooks of a hosted user
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    pub(crate) async fn webhooks_of(&self, name: &str) -> Vec<Webhook> {
+        self.webhooks.read().await.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Returns the hosted users with at least one webhook, with their webhooks
+    pub(crate) async fn webhooks(&self) -> Vec<(String, Vec<Webhook>)> {
+        self.webhooks
+            .read()
+            .await
+            .iter()
+            .filter(|(_, hooks)| !hooks.is_empty())
+            .map(|(name, hooks)| (name.clone(), hooks.clone()))
+            .collect()
+    }
+
+    /// Persists the webhooks of a hosted user
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    /// * `hooks`: The webhooks of the user
+    async fn save_webhooks(&self, name: &str, hooks: &[Webhook]) -> Result<(), ApiError> {
+        let serialized = serde_json::to_vec(hooks).map_err(|e| ApiError::internal(e.to_string()))?;
+        fs::write(self.path(name, WEBHOOKS_EXTENSION), serialized)
+            .await
+            .map_err(|e| ApiError::internal(format!("cannot write webhooks of user '{}': {}", name, e)))
+    }
+
+    /// Fetches the new messages of a hosted user, handing each of them out to both the REST API and
+    /// the webhooks of the user. The REST API and the webhooks take the messages from their own
+    /// mailbox, so that the messages fetched on behalf of one are not lost to the other.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    pub(crate) async fn fetch(&self, name: &str) -> Result<(), ApiError> {
+        let user = self.user(name).await?;
+        // The user stays locked until the messages are handed out, so that concurrent fetches hand
+        // them out in order
+        let mut user = user.lock().await;
+        let messages = user.fetch_next_messages().await?;
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.save(name, &mut user).await?;
+        let views = messages
+            .iter()
+            .map(|message| MessageView::new(&user, message))
+            .collect();
+        self.dispatch(name, views).await;
+        Ok(())
+    }
+
+    /// Hands messages of a hosted user out to the REST API and, if the user has webhooks, to its
+    /// webhooks
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    /// * `views`: The messages to hand out
+    pub(crate) async fn dispatch(&self, name: &str, views: Vec<MessageView>) {
+        let has_webhooks = !self.webhooks_of(name).await.is_empty();
+        let mut mailboxes = self.mailboxes.lock().await;
+        let mailbox = mailboxes.entry(name.to_string()).or_default();
+        if has_webhooks {
+            deliver(&mut mailbox.webhooks, views.clone());
+        }
+        deliver(&mut mailbox.api, views);
+    }
+
+    /// Takes the messages of a hosted user waiting to be returned by the REST API
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    pub(crate) async fn take_messages(&self, name: &str) -> Vec<MessageView> {
+        let mut mailboxes = self.mailboxes.lock().await;
+        mailboxes
+            .get_mut(name)
+            .map(|mailbox| mailbox.api.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// Takes the messages of a hosted user waiting to be posted to its webhooks
+    ///
+    /// # Arguments
+    /// * `name`: The name of the user
+    pub(crate) async fn take_notifications(&self, name: &str) -> Vec<MessageView> {
+        let mut mailboxes = self.mailboxes.lock().await;
+        mailboxes
+            .get_mut(name)
+            .map(|mailbox| mailbox.webhooks.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Appends messages to a mailbox, dropping its oldest messages past [`MAILBOX_CAPACITY`]
+///
+/// # Arguments
+/// * `mailbox`: The messages waiting for a consumer
+/// * `views`: The new messages
+fn deliver(mailbox: &mut VecDeque<MessageView>, views: Vec<MessageView>) {
+    mailbox.extend(views);
+    let excess = mailbox.len().saturating_sub(MAILBOX_CAPACITY);
+    mailbox.drain(..excess);
+}
@@ -0,0 +1,88 @@
+//! REST gateway hosting IOTA Streams users
+//!
+//! `streams-gatewayd` hosts any number of [users](streams::User), persisted as encrypted backups in
+//! a state directory, and exposes their channel operations as JSON endpoints. Webhooks registered
+//! for a user are posted the messages arriving on its channels, so services in any language can
+//! publish to and follow channels without embedding the library.
+
+// Rust
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+// 3rd-party
+use anyhow::anyhow;
+use clap::Parser;
+
+// IOTA
+
+// Streams
+
+// Local
+use crate::gateway::Gateway;
+
+/// Failures of the requests
+mod error;
+/// The hosted users and their persistence
+mod gateway;
+/// The REST API
+mod routes;
+/// The delivery of the new messages to webhooks
+mod webhooks;
+
+#[derive(Parser)]
+#[clap(
+    name = "streams-gatewayd",
+    version,
+    about = "REST gateway hosting IOTA Streams users"
+)]
+struct Cli {
+    /// Address the REST API listens on
+    #[clap(long, env = "STREAMS_LISTEN", default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Directory the state of the hosted users is kept in
+    #[clap(long, env = "STREAMS_STATE_DIR", default_value = "streams-gateway")]
+    state_dir: PathBuf,
+    /// Password the state of the hosted users is encrypted with
+    #[clap(long, env = "STREAMS_PASSWORD")]
+    password: String,
+    /// Token the requests must carry in an `Authorization: Bearer <token>` header
+    #[clap(long, env = "STREAMS_API_TOKEN")]
+    api_token: String,
+    /// Host webhooks can be registered for, repeated or comma separated. Webhooks are refused
+    /// without any.
+    #[clap(long = "webhook-host", env = "STREAMS_WEBHOOK_HOSTS", value_delimiter = ',')]
+    webhook_hosts: Vec<String>,
+    /// URL of the Tangle node
+    #[clap(long, env = "URL", default_value = "https://chrysalis-nodes.iota.org")]
+    node: String,
+    /// Seconds between two polls of the channels of the users with webhooks
+    #[clap(long, default_value = "10")]
+    poll_interval: u64,
+}
+
+/// Restores the hosted users and serves the REST API until the process is stopped
+///
+/// # Arguments
+/// * `cli`: The parsed command line
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    if cli.api_token.is_empty() {
+        return Err(anyhow!("the API token cannot be empty"));
+    }
+    let gateway = Arc::new(Gateway::open(cli.node, cli.state_dir, cli.password, cli.webhook_hosts).await?);
+    tokio::spawn(webhooks::notify_forever(
+        gateway.clone(),
+        Duration::from_secs(cli.poll_interval.max(1)),
+    ));
+    axum::Server::try_bind(&cli.listen)
+        .map_err(|e| anyhow!("cannot listen on {}: {}", cli.listen, e))?
+        .serve(routes::router(gateway, &cli.api_token).into_make_service())
+        .await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("streams-gatewayd: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,575 @@
+// Rust
+use std::{str::FromStr, sync::Arc};
+
+// 3rd-party
+use axum::{
+    body::Body,
+    extract::{Extension, Path},
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::Response,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+// IOTA
+
+// Streams
+use streams::{Address, Message, MessageContent, User};
+
+// Local
+use crate::{
+    error::ApiError,
+    gateway::{Gateway, Transport, Webhook},
+};
+
+/// Result of the handlers of the gateway
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Builds the REST API of the gateway. Every route but the health probes requires the API token.
+///
+/// # Arguments
+/// * `gateway`: The users hosted by the gateway
+/// * `api_token`: The token the requests must carry as a bearer token
+pub(crate) fn router(gateway: Arc<Gateway>, api_token: &str) -> Router {
+    let api_token: Arc<str> = api_token.into();
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/users", get(list_users).post(create_user))
+        .route("/users/:name", get(get_user))
+        .route("/users/:name/streams", post(create_stream))
+        .route("/users/:name/subscriptions", post(subscribe))
+        .route("/users/:name/keyloads", post(send_keyload))
+        .route("/users/:name/messages", get(fetch_messages).post(send_packet))
+        .route("/users/:name/webhooks", get(list_webhooks).post(add_webhook))
+        .route("/users/:name/webhooks/:id", delete(remove_webhook))
+        .route_layer(middleware::from_fn(move |request: Request<Body>, next: Next<Body>| {
+            authorize(api_token.clone(), request, next)
+        }))
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .layer(Extension(gateway))
+}
+
+/// Passes the requests carrying the API token on to their handler, and refuses the others
+///
+/// # Arguments
+/// * `api_token`: The token the requests must carry as a bearer token
+/// * `request`: The request
+/// * `next`: The handler of the request
+async fn authorize(api_token: Arc<str>, request: Request<Body>, next: Next<Body>) -> Result<Response, ApiError> {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or(false, |token| constant_time_eq(token.as_bytes(), api_token.as_bytes()));
+    match authorized {
+        true => Ok(next.run(request).await),
+        false => Err(ApiError::unauthorized("missing or invalid API token")),
+    }
+}
+
+/// Compares two byte strings in a time that does not depend on where they differ, so that the
+/// API token cannot be guessed byte by byte from the response times
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+/// A hosted user
+#[derive(Serialize)]
+struct UserView {
+    /// The name the user is hosted under
+    name: String,
+    /// The identifier of the user
+    identifier: Option<String>,
+    /// The address of the announcement of the channel of the user, once created or received
+    stream_address: Option<String>,
+}
+
+impl UserView {
+    /// # Arguments
+    /// * `name`: The name the user is hosted under
+    /// * `user`: The hosted [`User`]
+    fn new(name: String, user: &User<Transport>) -> Self {
+        Self {
+            name,
+            identifier: user.identifier().map(|identifier| identifier.to_string()),
+            stream_address: user.stream_address().map(|address| address.to_string()),
+        }
+    }
+}
+
+/// A message read by a hosted user. Payloads are decoded as UTF-8, replacing invalid sequences.
+#[derive(Clone, Serialize)]
+pub(crate) struct MessageView {
+    /// The address of the message
+    address: String,
+    /// The identifier of the publisher of the message
+    publisher: String,
+    /// The sequence number of the message among the messages of its publisher
+    sequence: usize,
+    /// The topic of the branch of the message, if known by the user
+    topic: Option<String>,
+    /// The type of the content of the message, like `signed_packet`
+    kind: &'static str,
+    /// The public payload of packets
+    public: Option<String>,
+    /// The masked payload of packets
+    masked: Option<String>,
+}
+
+impl MessageView {
+    /// # Arguments
+    /// * `user`: The [`User`] that read the message, resolving its topic
+    /// * `message`: The [`Message`] read
+    pub(crate) fn new(user: &User<Transport>, message: &Message) -> Self {
+        let kind = match message.content() {
+            MessageContent::Announcement(_) => "announcement",
+            MessageContent::BranchAnnouncement(_) => "branch_announcement",
+            MessageContent::BranchClosed(_) => "branch_closed",
+            MessageContent::Keyload(_) => "keyload",
+            MessageContent::SignedPacket(_) => "signed_packet",
+            MessageContent::TaggedPacket(_) => "tagged_packet",
+            MessageContent::SelectivePacket(_) => "selective_packet",
+            MessageContent::BatchPacket(_) => "batch_packet",
+            MessageContent::Subscription(_) => "subscription",
+            MessageContent::Unsubscription(_) => "unsubscription",
+            MessageContent::KeyUpdate(_) => "key_update",
+            MessageContent::SubstreamAnnounced(_) => "substream_announced",
+            MessageContent::ReadMarker(_) => "read_marker",
+            MessageContent::Tombstone(_) => "tombstone",
+            MessageContent::AuthorRotation(_) => "author_rotation",
+            MessageContent::Countersignature(_) => "countersignature",
+            MessageContent::Commitment(_) => "commitment",
+            MessageContent::Reveal(_) => "reveal",
+            MessageContent::Custom(_) => "custom",
+            MessageContent::Rejected(_) => "rejected",
+            MessageContent::Redacted(_) => "redacted",
+            MessageContent::AccessExpired(_) => "access_expired",
+            MessageContent::Orphan(_) => "orphan",
+            MessageContent::OutOfOrder(_) => "out_of_order",
+            MessageContent::DuplicateReceived(_) => "duplicate_received",
+            MessageContent::Legacy(_) => "legacy",
+        };
+        Self {
+            address: message.address().to_string(),
+            publisher: message.publisher().to_string(),
+            sequence: message.sequence(),
+            topic: user.topic_by_hash(message.topic_hash()).map(|topic| topic.to_string()),
+            kind,
+            public: message
+                .public_payload()
+                .map(|payload| String::from_utf8_lossy(payload).into_owned()),
+            masked: message
+                .masked_payload()
+                .map(|payload| String::from_utf8_lossy(payload).into_owned()),
+        }
+    }
+}
+
+/// Address of a message sent by a hosted user
+#[derive(Serialize)]
+struct Sent {
+    /// The address of the message
+    address: String,
+}
+
+#[derive(Deserialize)]
+struct CreateUser {
+    /// The name the user is hosted under
+    name: String,
+    /// The seed of the identity of the user
+    seed: String,
+}
+
+#[derive(Deserialize)]
+struct CreateStream {
+    /// The topic of the base branch of the channel
+    #[serde(default = "base_branch")]
+    topic: String,
+}
+
+#[derive(Deserialize)]
+struct Subscribe {
+    /// The address of the announcement of the channel
+    announcement: String,
+}
+
+#[derive(Deserialize)]
+struct SendKeyload {
+    /// The topic of the branch
+    #[serde(default = "base_branch")]
+    topic: String,
+    /// Whether the subscribers are granted write access as well as read access
+    #[serde(default)]
+    write: bool,
+}
+
+#[derive(Deserialize)]
+struct SendPacket {
+    /// The topic of the branch
+    #[serde(default = "base_branch")]
+    topic: String,
+    /// The payload readable by anyone
+    #[serde(default)]
+    public: String,
+    /// The payload readable by the users with access to the branch
+    #[serde(default)]
+    masked: String,
+    /// Whether a tagged packet, authenticated by the branch key, is sent instead of a signed one
+    #[serde(default)]
+    tagged: bool,
+}
+
+#[derive(Deserialize)]
+struct AddWebhook {
+    /// The URL the new messages are posted to
+    url: String,
+}
+
+/// Topic of the branches when requests do not specify one
+fn base_branch() -> String {
+    "BASE_BRANCH".to_string()
+}
+
+async fn health() -> StatusCode {
+    StatusCode::OK
+}
+
+async fn ready(Extension(gateway): Extension<Arc<Gateway>>) -> Result<StatusCode, ApiError> {
+    gateway.health_check().await?;
+    Ok(StatusCode::OK)
+}
+
+async fn metrics(Extension(gateway): Extension<Arc<Gateway>>) -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        gateway.metrics().render(),
+    )
+}
+
+async fn list_users(Extension(gateway): Extension<Arc<Gateway>>) -> ApiResult<Vec<UserView>> {
+    let mut views = Vec::new();
+    for (name, user) in gateway.users().await {
+        views.push(UserView::new(name, &*user.lock().await));
+    }
+    Ok(Json(views))
+}
+
+async fn create_user(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Json(request): Json<CreateUser>,
+) -> Result<(StatusCode, Json<UserView>), ApiError> {
+    let user = gateway.create_user(&request.name, &request.seed).await?;
+    let view = UserView::new(request.name, &*user.lock().await);
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+async fn get_user(Extension(gateway): Extension<Arc<Gateway>>, Path(name): Path<String>) -> ApiResult<UserView> {
+    let user = gateway.user(&name).await?;
+    let view = UserView::new(name, &*user.lock().await);
+    Ok(Json(view))
+}
+
+async fn create_stream(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+    Json(request): Json<CreateStream>,
+) -> ApiResult<Sent> {
+    let user = gateway.user(&name).await?;
+    let mut user = user.lock().await;
+    let announcement = user.create_stream(request.topic).await?;
+    gateway.save(&name, &mut user).await?;
+    Ok(Json(Sent {
+        address: announcement.address().to_string(),
+    }))
+}
+
+async fn subscribe(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+    Json(request): Json<Subscribe>,
+) -> ApiResult<Sent> {
+    let announcement = Address::from_str(&request.announcement)
+        .map_err(|e| ApiError::bad_request(format!("invalid announcement address: {}", e)))?;
+    let user = gateway.user(&name).await?;
+    let mut user = user.lock().await;
+    user.receive_message(announcement).await?;
+    let subscription = user.subscribe().await?;
+    gateway.save(&name, &mut user).await?;
+    Ok(Json(Sent {
+        address: subscription.address().to_string(),
+    }))
+}
+
+async fn send_keyload(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+    Json(request): Json<SendKeyload>,
+) -> ApiResult<Sent> {
+    let user = gateway.user(&name).await?;
+    let mut user = user.lock().await;
+    user.sync().await?;
+    let keyload = match request.write {
+        true => user.send_keyload_for_all_rw(request.topic).await?,
+        false => user.send_keyload_for_all(request.topic).await?,
+    };
+    gateway.save(&name, &mut user).await?;
+    Ok(Json(Sent {
+        address: keyload.address().to_string(),
+    }))
+}
+
+async fn send_packet(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+    Json(request): Json<SendPacket>,
+) -> ApiResult<Sent> {
+    let user = gateway.user(&name).await?;
+    let mut user = user.lock().await;
+    user.sync().await?;
+    let packet = match request.tagged {
+        true => {
+            user.send_tagged_packet(request.topic, request.public, request.masked)
+                .await?
+        }
+        false => {
+            user.send_signed_packet(request.topic, request.public, request.masked)
+                .await?
+        }
+    };
+    gateway.save(&name, &mut user).await?;
+    Ok(Json(Sent {
+        address: packet.address().to_string(),
+    }))
+}
+
+async fn fetch_messages(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+) -> ApiResult<Vec<MessageView>> {
+    gateway.fetch(&name).await?;
+    Ok(Json(gateway.take_messages(&name).await))
+}
+
+async fn list_webhooks(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+) -> ApiResult<Vec<Webhook>> {
+    gateway.user(&name).await?;
+    Ok(Json(gateway.webhooks_of(&name).await))
+}
+
+async fn add_webhook(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path(name): Path<String>,
+    Json(request): Json<AddWebhook>,
+) -> Result<(StatusCode, Json<Webhook>), ApiError> {
+    let webhook = gateway.add_webhook(&name, request.url).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+async fn remove_webhook(
+    Extension(gateway): Extension<Arc<Gateway>>,
+    Path((name, id)): Path<(String, u64)>,
+) -> Result<StatusCode, ApiError> {
+    gateway.remove_webhook(&name, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        Router,
+    };
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use crate::gateway::Gateway;
+
+    use super::{router, MessageView};
+
+    /// API token of the gateways of the tests
+    const TOKEN: &str = "secret-token";
+
+    /// Opens a gateway in a fresh state directory, connecting to no node and accepting webhooks for
+    /// `hooks.example.com` only
+    ///
+    /// # Arguments
+    /// * `test`: The name of the test, naming the state directory
+    async fn gateway_fixture(test: &str) -> (Arc<Gateway>, Router) {
+        let state_dir = std::env::temp_dir().join(format!("streams-gateway-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&state_dir);
+        let gateway = Gateway::open(
+            "http://127.0.0.1:1".to_string(),
+            state_dir,
+            "password".to_string(),
+            vec!["hooks.example.com".to_string()],
+        )
+        .await
+        .unwrap();
+        let gateway = Arc::new(gateway);
+        (gateway.clone(), router(gateway, TOKEN))
+    }
+
+    /// Sends a request to the REST API, returning the status and the JSON body of the response
+    ///
+    /// # Arguments
+    /// * `router`: The REST API
+    /// * `method`: The method of the request
+    /// * `uri`: The path of the request
+    /// * `token`: The bearer token of the request, if any
+    /// * `body`: The JSON body of the request, if any
+    async fn request(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = router.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_api_token_are_refused() {
+        let (_, router) = gateway_fixture("token").await;
+
+        assert_eq!(request(&router, "GET", "/health", None, None).await.0, StatusCode::OK);
+        assert_eq!(
+            request(&router, "GET", "/users", None, None).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            request(&router, "GET", "/users", Some("guess"), None).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            request(&router, "GET", "/metrics", None, None).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        let (status, body) = request(&router, "GET", "/users", Some(TOKEN), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!([]));
+    }
+
+    #[tokio::test]
+    async fn users_are_created_once_and_looked_up_by_name() {
+        let (_, router) = gateway_fixture("users").await;
+        let alice = json!({"name": "alice", "seed": "alice seed"});
+
+        let (status, body) = request(&router, "POST", "/users", Some(TOKEN), Some(alice.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["name"], "alice");
+        assert!(body["identifier"].is_string());
+        assert_eq!(body["stream_address"], Value::Null);
+
+        let (status, _) = request(&router, "POST", "/users", Some(TOKEN), Some(alice)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let invalid = json!({"name": "../alice", "seed": "alice seed"});
+        let (status, body) = request(&router, "POST", "/users", Some(TOKEN), Some(invalid)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+
+        let (status, body) = request(&router, "GET", "/users/alice", Some(TOKEN), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["name"], "alice");
+        let (status, _) = request(&router, "GET", "/users/bob", Some(TOKEN), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn webhooks_are_only_registered_for_allowed_hosts() {
+        let (_, router) = gateway_fixture("webhooks").await;
+        let alice = json!({"name": "alice", "seed": "alice seed"});
+        request(&router, "POST", "/users", Some(TOKEN), Some(alice)).await;
+        let add = |url: &str| {
+            let router = router.clone();
+            let body = json!({ "url": url });
+            async move { request(&router, "POST", "/users/alice/webhooks", Some(TOKEN), Some(body)).await }
+        };
+
+        assert_eq!(
+            add("http://169.254.169.254/latest/meta-data").await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(add("http://localhost:8080/users").await.0, StatusCode::FORBIDDEN);
+        assert_eq!(
+            add("http://hooks.example.com.attacker.net/").await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(add("file:///etc/passwd").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(add("not a url").await.0, StatusCode::BAD_REQUEST);
+
+        let (status, webhook) = add("https://HOOKS.example.com/streams").await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, webhooks) = request(&router, "GET", "/users/alice/webhooks", Some(TOKEN), None).await;
+        assert_eq!(webhooks, json!([webhook]));
+
+        let uri = format!("/users/alice/webhooks/{}", webhook["id"]);
+        assert_eq!(
+            request(&router, "DELETE", &uri, Some(TOKEN), None).await.0,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            request(&router, "DELETE", &uri, Some(TOKEN), None).await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn fetched_messages_are_handed_out_to_both_the_api_and_the_webhooks() {
+        let (gateway, router) = gateway_fixture("fan-out").await;
+        for name in ["alice", "bob"] {
+            let user = json!({"name": name, "seed": name});
+            request(&router, "POST", "/users", Some(TOKEN), Some(user)).await;
+        }
+        let webhook = json!({"url": "https://hooks.example.com/alice"});
+        request(&router, "POST", "/users/alice/webhooks", Some(TOKEN), Some(webhook)).await;
+        let view = MessageView {
+            address: "address".to_string(),
+            publisher: "publisher".to_string(),
+            sequence: 2,
+            topic: Some("BASE_BRANCH".to_string()),
+            kind: "signed_packet",
+            public: Some("hello".to_string()),
+            masked: Some(String::new()),
+        };
+        gateway.dispatch("alice", vec![view.clone()]).await;
+        gateway.dispatch("bob", vec![view]).await;
+
+        // Taking the messages through the API leaves them to the webhooks, and the other way around
+        let (status, body) = request(&router, "GET", "/users/alice/messages", Some(TOKEN), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["public"], "hello");
+        assert_eq!(body.as_array().map(Vec::len), Some(1));
+        let (_, body) = request(&router, "GET", "/users/alice/messages", Some(TOKEN), None).await;
+        assert_eq!(body, json!([]));
+        assert_eq!(gateway.take_notifications("alice").await.len(), 1);
+        assert!(gateway.take_notifications("alice").await.is_empty());
+
+        // Messages are not kept for the webhooks of users without any
+        assert!(gateway.take_notifications("bob").await.is_empty());
+        let (_, body) = request(&router, "GET", "/users/bob/messages", Some(TOKEN), None).await;
+        assert_eq!(body.as_array().map(Vec::len), Some(1));
+    }
+}
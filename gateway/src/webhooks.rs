@@ -0,0 +1,91 @@
+// Rust
+use std::{sync::Arc, time::Duration};
+
+// 3rd-party
+use reqwest::redirect::Policy;
+use serde::Serialize;
+
+// IOTA
+
+// Streams
+
+// Local
+use crate::{
+    error::ApiError,
+    gateway::{Gateway, Webhook},
+    routes::MessageView,
+};
+
+/// Body posted to the webhooks of a hosted user
+#[derive(Serialize)]
+struct Notification<'a> {
+    /// The name of the hosted user
+    user: &'a str,
+    /// The messages that arrived since the previous notification
+    messages: &'a [MessageView],
+}
+
+/// Polls the channels of the hosted users with webhooks, posting the new messages to their
+/// webhooks. Messages are delivered once: failed deliveries are logged and not retried. Redirects
+/// are not followed, so that an allowed host cannot send the notifications to another one.
+///
+/// # Arguments
+/// * `gateway`: The users hosted by the gateway
+/// * `interval`: The delay between two polls
+pub(crate) async fn notify_forever(gateway: Arc<Gateway>, interval: Duration) {
+    let client = match reqwest::Client::builder().redirect(Policy::none()).build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("streams-gatewayd: cannot create the webhooks client: {}", e);
+            return;
+        }
+    };
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        for (name, hooks) in gateway.webhooks().await {
+            if let Err(e) = notify(&gateway, &client, &name, &hooks).await {
+                eprintln!("streams-gatewayd: cannot poll messages of user '{}': {}", name, e);
+            }
+        }
+    }
+}
+
+/// Fetches the new messages of a hosted user, posting the messages waiting for its webhooks to
+/// them. Webhooks whose host is no longer allowed are skipped.
+///
+/// # Arguments
+/// * `gateway`: The users hosted by the gateway
+/// * `client`: The HTTP client posting the notifications
+/// * `name`: The name of the hosted user
+/// * `hooks`: The webhooks of the user
+async fn notify(gateway: &Gateway, client: &reqwest::Client, name: &str, hooks: &[Webhook]) -> Result<(), ApiError> {
+    gateway.fetch(name).await?;
+    let views = gateway.take_notifications(name).await;
+    if views.is_empty() {
+        return Ok(());
+    }
+    let notification = Notification {
+        user: name,
+        messages: &views,
+    };
+    for hook in hooks {
+        if let Err(e) = gateway.check_webhook_url(&hook.url) {
+            eprintln!("streams-gatewayd: skipping webhook {}: {}", hook.id, e);
+            continue;
+        }
+        let delivery = client
+            .post(&hook.url)
+            .json(&notification)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = delivery {
+            eprintln!(
+                "streams-gatewayd: cannot notify webhook {} ({}): {}",
+                hook.id, hook.url, e
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+//! Harness of the end-to-end tests of Streams
+//!
+//! [`PrivateTangle::start()`] spins up a Hornet node in docker, issuing milestones for a network of
+//! its own, and [`author_subscriber_flow()`] runs a channel between an author and a subscriber
+//! over any transport connected to it.
+
+// Rust
+use std::{fs, future::Future, path::Path, time::Duration};
+
+// 3rd-party
+use anyhow::{anyhow, ensure, Result};
+use testcontainers::{clients::Cli, core::WaitFor, Container, Image, ImageArgs, RunnableImage};
+
+// IOTA
+
+// Streams
+use streams::{id::Ed25519, transport::Transport, TransportMessage, User};
+
+// Local
+
+/// Hornet image run when `HORNET_IMAGE` is not set
+const DEFAULT_HORNET_IMAGE: &str = "gohornet/hornet:1.2.4";
+/// Configuration of the node, mounted in its working directory
+const CONFIG: &str = include_str!("../config/config_private_tangle.json");
+const CONFIG_FILE: &str = "config_private_tangle.json";
+/// Working directory of the node in the image
+const WORKDIR: &str = "/app";
+/// Port of the REST API of the node
+const API_PORT: u16 = 14265;
+/// Network of the private tangle, matching the configuration of the node
+const NETWORK_ID: &str = "private_tangle1";
+/// Address the snapshot mints the tokens of the network to
+const MINT_ADDRESS: &str = "60200bad8137a704216e84f8f9acfe65b972d9f4155becb4815282b03cef99fe";
+/// Key of the coordinator, matching the public key range of the configuration. Only meant for
+/// throwaway test networks.
+const COO_PRV_KEY: &str = "651941eddb3e68cb1f6ef4ef5b04625dcf5c70de1fdc4b1c9eadb2c219c074e0ed3c3f1a319ff4e909cf2771d79fece0ac9bd9fd2ee49ea6c0885c9cb3b1248c";
+/// Time given to the snapshot generation, and to the node to become healthy
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+/// Time given to the messages to be found by the subscriber
+const SYNC_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Hornet node image
+#[derive(Clone, Debug)]
+struct Hornet {
+    /// Name of the image
+    name: String,
+    /// Tag of the image
+    tag: String,
+    /// Environment variables of the container
+    env_vars: Vec<(String, String)>,
+    /// Host paths mounted in the container, with their path in the container
+    volumes: Vec<(String, String)>,
+}
+
+impl Hornet {
+    /// Creates a new [`Hornet`] image, from `HORNET_IMAGE` or [`DEFAULT_HORNET_IMAGE`]
+    fn new() -> Self {
+        let image = std::env::var("HORNET_IMAGE").unwrap_or_else(|_| DEFAULT_HORNET_IMAGE.to_string());
+        let (name, tag) = image.rsplit_once(':').unwrap_or((&image, "latest"));
+        Self {
+            name: name.to_string(),
+            tag: tag.to_string(),
+            env_vars: Vec::new(),
+            volumes: Vec::new(),
+        }
+    }
+
+    /// # Arguments
+    /// * `host`: The path mounted
+    /// * `container`: The path of the mount in the container, relative to its working directory
+    fn with_volume(mut self, host: &Path, container: &str) -> Self {
+        self.volumes.push((
+            host.to_string_lossy().into_owned(),
+            format!("{}/{}", WORKDIR, container),
+        ));
+        self
+    }
+
+    /// # Arguments
+    /// * `name`: The name of the variable
+    /// * `value`: The value of the variable
+    fn with_env_var(mut self, name: &str, value: &str) -> Self {
+        self.env_vars.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl Image for Hornet {
+    type Args = HornetArgs;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn tag(&self) -> String {
+        self.tag.clone()
+    }
+
+    fn ready_conditions(&self) -> Vec<WaitFor> {
+        // Readiness is polled from the outside: the snapshot file, or the health of the node
+        vec![WaitFor::Nothing]
+    }
+
+    fn env_vars(&self) -> Box<dyn Iterator<Item = (&String, &String)> + '_> {
+        Box::new(self.env_vars.iter().map(|(name, value)| (name, value)))
+    }
+
+    fn volumes(&self) -> Box<dyn Iterator<Item = (&String, &String)> + '_> {
+        Box::new(self.volumes.iter().map(|(host, container)| (host, container)))
+    }
+
+    fn expose_ports(&self) -> Vec<u16> {
+        vec![API_PORT]
+    }
+}
+
+/// Command line arguments of the Hornet executable
+#[derive(Clone, Debug, Default)]
+struct HornetArgs(Vec<String>);
+
+impl HornetArgs {
+    /// # Arguments
+    /// * `args`: The arguments passed to the executable
+    fn new(args: &[&str]) -> Self {
+        Self(args.iter().map(|arg| arg.to_string()).collect())
+    }
+}
+
+impl ImageArgs for HornetArgs {
+    fn into_iterator(self) -> Box<dyn Iterator<Item = String>> {
+        Box::new(self.0.into_iter())
+    }
+}
+
+/// A private tangle of a single Hornet node, acting as its own coordinator. The node and its data
+/// are removed when dropped.
+pub struct PrivateTangle<'d> {
+    /// The container of the node
+    _node: Container<'d, Hornet>,
+    /// Directory holding the configuration and the database of the node
+    _dir: tempfile::TempDir,
+    /// URL of the REST API of the node
+    url: String,
+}
+
+impl<'d> PrivateTangle<'d> {
+    /// Generates the snapshot of a new network and starts a node bootstrapping its coordinator,
+    /// returning once the node is healthy
+    ///
+    /// # Arguments
+    /// * `docker`: The docker client running the containers
+    pub async fn start(docker: &'d Cli) -> Result<PrivateTangle<'d>> {
+        let dir = tempfile::tempdir()?;
+        let data = dir.path().join("data");
+        fs::create_dir_all(data.join("snapshots"))?;
+        allow_writes(&data)?;
+        allow_writes(&data.join("snapshots"))?;
+        let config = dir.path().join(CONFIG_FILE);
+        fs::write(&config, CONFIG)?;
+
+        let snapshot = data.join("snapshots").join("full_snapshot.bin");
+        {
+            let snapgen = Hornet::new().with_volume(&data, "data");
+            let args = HornetArgs::new(&[
+                "tool",
+                "snapgen",
+                NETWORK_ID,
+                MINT_ADDRESS,
+                "data/snapshots/full_snapshot.bin",
+            ]);
+            let _snapgen = docker.run(RunnableImage::from((snapgen, args)));
+            let snapshot = &snapshot;
+            poll("snapshot generation", STARTUP_TIMEOUT, || async move {
+                Ok(fs::metadata(snapshot).map_or(false, |metadata| metadata.len() > 0))
+            })
+            .await?;
+            // Leave time for the last writes once the file shows up
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        let node = Hornet::new()
+            .with_volume(&data, "data")
+            .with_volume(&config, CONFIG_FILE)
+            .with_env_var("COO_PRV_KEYS", COO_PRV_KEY);
+        let args = HornetArgs::new(&["--config", CONFIG_FILE, "--cooBootstrap", "--cooStartIndex", "0"]);
+        let node = docker.run(RunnableImage::from((node, args)));
+        let url = format!("http://127.0.0.1:{}", node.get_host_port_ipv4(API_PORT));
+
+        // The node reports healthy once it is synced with the milestones of its coordinator
+        let health = &format!("{}/health", url);
+        let client = &reqwest::Client::new();
+        poll("node health", STARTUP_TIMEOUT, || async move {
+            Ok(client
+                .get(health)
+                .send()
+                .await
+                .map_or(false, |response| response.status().is_success()))
+        })
+        .await?;
+
+        Ok(Self {
+            _node: node,
+            _dir: dir,
+            url,
+        })
+    }
+
+    /// Returns the URL of the REST API of the node
+    pub fn node_url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Runs a channel between an author and a subscriber: the subscriber subscribes to the announced
+/// stream, is granted access by a keyload and reads the packets of the author
+///
+/// # Arguments
+/// * `transport`: The transport connected to the private tangle, shared by both users
+pub async fn author_subscriber_flow<T>(transport: T) -> Result<()>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage> + Clone,
+{
+    let mut author = User::builder()
+        .with_identity(Ed25519::from_seed("it-tests author"))
+        .with_transport(transport.clone())
+        .build();
+    let mut subscriber = User::builder()
+        .with_identity(Ed25519::from_seed("it-tests subscriber"))
+        .with_transport(transport)
+        .build();
+
+    let announcement = author.create_stream("BASE_BRANCH").await?;
+    subscriber.receive_message(announcement.address()).await?;
+    let subscription = subscriber.subscribe().await?;
+    author.receive_message(subscription.address()).await?;
+    author.send_keyload_for_all("BASE_BRANCH").await?;
+    author
+        .send_signed_packet("BASE_BRANCH", b"public payload", b"masked payload")
+        .await?;
+
+    // The messages may take a while to be indexed by the node
+    let mut messages = Vec::new();
+    for _ in 0..SYNC_TIMEOUT.as_millis() / POLL_INTERVAL.as_millis() {
+        messages.extend(subscriber.fetch_next_messages().await?);
+        if messages.len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    ensure!(
+        messages.len() >= 2,
+        "timed out after {:?} waiting for the messages of the author",
+        SYNC_TIMEOUT
+    );
+    ensure!(
+        messages[0].is_keyload(),
+        "expected the keyload, found {:?}",
+        messages[0]
+    );
+    ensure!(
+        messages[1].masked_payload() == Some(&b"masked payload"[..]),
+        "the subscriber could not read the masked payload of the packet"
+    );
+    Ok(())
+}
+
+/// Grants write access to a directory mounted in a container, as the node runs as an
+/// unprivileged user
+///
+/// # Arguments
+/// * `path`: The path of the directory
+fn allow_writes(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o777))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Polls a condition until it holds, failing once the timeout elapsed
+///
+/// # Arguments
+/// * `what`: Description of the awaited condition, reported on timeout
+/// * `timeout`: How long to poll the condition
+/// * `condition`: Returns whether the condition holds
+async fn poll<F, Fut>(what: &str, timeout: Duration, mut condition: F) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let polls = timeout.as_millis() / POLL_INTERVAL.as_millis();
+    for _ in 0..polls {
+        if condition().await? {
+            return Ok(());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Err(anyhow!("timed out after {:?} waiting for {}", timeout, what))
+}
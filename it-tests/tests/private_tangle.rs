@@ -0,0 +1,32 @@
+// Rust
+use std::{cell::RefCell, rc::Rc};
+
+// 3rd-party
+use anyhow::Result;
+use testcontainers::clients::Cli;
+
+// IOTA
+
+// Streams
+use streams::transport::{tangle, utangle};
+
+// Local
+use it_tests::{author_subscriber_flow, PrivateTangle};
+
+#[tokio::test]
+#[cfg_attr(not(feature = "docker"), ignore = "requires docker, run with `--features docker`")]
+async fn author_subscriber_flow_over_utangle_client() -> Result<()> {
+    let docker = Cli::default();
+    let private_tangle = PrivateTangle::start(&docker).await?;
+    let transport: utangle::Client = utangle::Client::for_node(private_tangle.node_url()).await?;
+    author_subscriber_flow(transport).await
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "docker"), ignore = "requires docker, run with `--features docker`")]
+async fn author_subscriber_flow_over_tangle_client() -> Result<()> {
+    let docker = Cli::default();
+    let private_tangle = PrivateTangle::start(&docker).await?;
+    let transport: tangle::Client = tangle::Client::for_node(private_tangle.node_url()).await?;
+    author_subscriber_flow(Rc::new(RefCell::new(transport))).await
+}
@@ -30,6 +30,7 @@ use crate::{
     error::{Error, Result},
     id::Identifier,
     message::Topic,
+    sync::{MaybeSend, MaybeSync},
 };
 
 /// Abstract representation of a Message Address
@@ -245,6 +246,15 @@ impl MsgId {
         Self(bytes)
     }
 
+    /// Derives the [`MsgId`] of a message from the [`AppAddr`] of the stream, and the publisher,
+    /// branch and sequence number of the message. The derivation does not involve any timestamp,
+    /// so that readers find the messages of publishers whose clock is skewed.
+    ///
+    /// # Arguments
+    /// * `appaddr`: The [`AppAddr`] of the stream
+    /// * `identifier`: The [`Identifier`] of the publisher of the message
+    /// * `topic`: The [`Topic`] of the branch of the message
+    /// * `seq_num`: The sequence number of the message among the messages of its publisher
     pub fn gen(appaddr: AppAddr, identifier: &Identifier, topic: &Topic, seq_num: usize) -> MsgId {
         let mut s = Spongos::<KeccakF1600>::init();
         s.absorb(appaddr);
@@ -314,6 +324,79 @@ impl From<[u8; 12]> for MsgId {
     }
 }
 
+/// Derivation of the [`AppAddr`] of a stream and the [`MsgId`] of its messages
+///
+/// Readers find the messages of a stream by deriving their addresses, so every participant of a
+/// stream must use the same generator. The default methods derive the addresses with
+/// [`AppAddr::gen`] and [`MsgId::gen`], which is what IOTA Tangle transports expect. Other
+/// transports, like a database, may prefer identifiers that fit their own indexes. Only the
+/// derivation is pluggable, the link type is not: a generator still produces a 40 byte
+/// [`AppAddr`] and a 12 byte [`MsgId`], so a wider identifier, like a 16 byte UUID, has to be
+/// fitted into these bytes (e.g. hashed) and the transport has to index the messages by them.
+/// Generated [`MsgId`]s must be unique within the stream for every combination of publisher,
+/// topic and sequence number.
+///
+/// Generated links must only depend on the arguments of the generator. A component read from the
+/// local clock, like a timestamp, would differ between the publisher and the readers of a message
+/// whenever their clocks are skewed, and the readers would look the message up at the wrong
+/// address.
+pub trait LinkGenerator: MaybeSend + MaybeSync {
+    /// Derives the [`AppAddr`] of a stream from the [`Identifier`] of its author and the [`Topic`]
+    /// of its base branch
+    fn gen_app_addr(&self, identifier: &Identifier, base_topic: &Topic) -> AppAddr {
+        AppAddr::gen(identifier, base_topic)
+    }
+
+    /// Derives the [`MsgId`] of a message from the [`AppAddr`] of the stream, and the publisher,
+    /// branch and sequence number of the message
+    fn gen_msg_id(&self, appaddr: AppAddr, identifier: &Identifier, topic: &Topic, seq_num: usize) -> MsgId {
+        MsgId::gen(appaddr, identifier, topic, seq_num)
+    }
+}
+
+/// [`LinkGenerator`] deriving addresses pseudo-randomly from the publisher, branch and sequence
+/// number of the messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DefaultLinkGenerator;
+
+impl LinkGenerator for DefaultLinkGenerator {}
+
+/// Inputs a [`LinkGenerator`] derives the [`MsgId`] of a message from. Distinct inputs must not
+/// lead to the same `msg_index` (see [`Address::to_msg_index()`]), otherwise the messages cannot be
+/// told apart by the transport.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LinkInputs {
+    /// The [`AppAddr`] of the stream
+    pub appaddr: AppAddr,
+    /// The [`Identifier`] of the publisher of the message
+    pub publisher: Identifier,
+    /// The [`Topic`] of the branch of the message
+    pub topic: Topic,
+    /// The sequence number of the message among the messages of its publisher
+    pub seq_num: usize,
+}
+
+impl LinkInputs {
+    /// Derives the [`Address`] of the message with the provided [`LinkGenerator`]
+    ///
+    /// # Arguments
+    /// * `link_generator`: The [`LinkGenerator`] of the stream
+    pub fn derive(&self, link_generator: &dyn LinkGenerator) -> Address {
+        let msgid = link_generator.gen_msg_id(self.appaddr, &self.publisher, &self.topic, self.seq_num);
+        Address::new(self.appaddr, msgid)
+    }
+}
+
+impl Display for LinkInputs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "publisher {} in branch '{}' at sequence {} of stream {:x}",
+            self.publisher, self.topic, self.seq_num, self.appaddr
+        )
+    }
+}
+
 impl Absorb<&MsgId> for sizeof::Context {
     fn absorb(&mut self, msgid: &MsgId) -> SpongosResult<&mut Self> {
         self.absorb(NBytes::new(msgid))
@@ -5,7 +5,7 @@ use alloc::{
     boxed::Box,
     string::{FromUtf8Error, String},
 };
-use core::fmt::Debug;
+use core::fmt::{self, Debug, Display, Formatter};
 
 // 3rd-party
 use hex::FromHexError;
@@ -79,6 +79,10 @@ impl From<String> for IdentityError {
 
 #[derive(Debug, Error)]
 /// Error type of the LETS crate.
+///
+/// Every error carries a stable numeric [code](Error::code), in the `1000` range, and a
+/// [remediation hint](Error::hint), so that applications and foreign language bindings can react to
+/// categories of errors without parsing their descriptions. Transport errors use the `1100` range.
 #[allow(clippy::large_enum_variant)]
 pub enum Error {
     #[error("Crypto error hile attempting to {0}: {1}")]
@@ -97,6 +101,10 @@ pub enum Error {
     #[error("{0} must be {1} bytes long, but is {2} bytes long instead")]
     InvalidSize(&'static str, usize, u64),
 
+    #[cfg(feature = "mnemonic")]
+    #[error("Key derivation error while attempting to {0}: {1}")]
+    KeyDerivation(&'static str, String),
+
     #[error("Malformed {0}: missing '{1}' for {2}")]
     Malformed(&'static str, &'static str, String),
 
@@ -106,11 +114,21 @@ pub enum Error {
     #[error("Internal Spongos error: {0}")]
     Spongos(SpongosError),
 
+    #[error("no identifier type is known for the multicodec {0:#x}")]
+    UnknownMulticodec(u64),
+
     /// Transport
 
     #[error("Transport error for address {1}: {0}")]
     AddressError(&'static str, Address),
 
+    #[error("no message found at address '{0}'")]
+    MessageNotFound(Address),
+
+    /// The count is a lower bound when only a page of the messages at the address was received
+    #[error("{1} messages found at address '{0}', where a single one is expected")]
+    MultipleMessagesFound(Address, usize),
+
     #[cfg(any(feature = "tangle-client", feature = "tangle-client-wasm"))]
     #[error("Iota client error for {0}: {1}")]
     IotaClient(&'static str, iota_client::Error),
@@ -118,25 +136,262 @@ pub enum Error {
     #[error("message '{0}' not found in {1}")]
     MessageMissing(Address, &'static str),
 
+    #[error("message '{0}' is {1} bytes long, but the transport accepts at most {2} bytes")]
+    MessageTooLarge(Address, usize, usize),
+
+    #[error("no payload codec is known for the prefix byte {0:#04x}")]
+    UnknownPayloadCodec(u8),
+
     #[error("Nonce is not in the range 0..u32::MAX range for target score: {0}")]
     Nonce(f64),
 
+    #[error("node '{0}' reports itself unhealthy")]
+    Unhealthy(String),
+
+    #[error("the transport cannot look messages up by their content digest")]
+    DigestLookupUnsupported,
+
+    #[error("no message found with content digest '{0}'")]
+    DigestNotFound(String),
+
     #[cfg(feature = "utangle-client")]
     #[error("Request HTTP error: {0}")]
     Request(reqwest::Error),
 }
 
 impl Error {
+    /// Returns the stable numeric code of the error. Codes are never reused for another kind of
+    /// error, even when a variant is removed.
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Crypto(..) => 1001,
+            #[cfg(feature = "did")]
+            Self::Did(..) => 1002,
+            Self::Encoding(..) => 1003,
+            Self::External(..) => 1004,
+            Self::InvalidSize(..) => 1005,
+            #[cfg(feature = "mnemonic")]
+            Self::KeyDerivation(..) => 1006,
+            Self::Malformed(..) => 1007,
+            Self::Signature(..) => 1008,
+            Self::Spongos(..) => 1009,
+            Self::UnknownMulticodec(..) => 1010,
+            Self::AddressError(..) => 1101,
+            #[cfg(any(feature = "tangle-client", feature = "tangle-client-wasm"))]
+            Self::IotaClient(..) => 1102,
+            Self::MessageMissing(..) => 1103,
+            Self::MessageTooLarge(..) => 1106,
+            Self::MessageNotFound(..) => 1107,
+            Self::MultipleMessagesFound(..) => 1108,
+            Self::UnknownPayloadCodec(..) => 1109,
+            Self::Unhealthy(..) => 1110,
+            Self::DigestLookupUnsupported => 1111,
+            Self::DigestNotFound(..) => 1112,
+            Self::Nonce(..) => 1104,
+            #[cfg(feature = "utangle-client")]
+            Self::Request(..) => 1105,
+        }
+    }
+
+    /// Returns a hint on how to remedy the error
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::Crypto(..) => "check that the keys used are valid and were not corrupted",
+            #[cfg(feature = "did")]
+            Self::Did(..) => "check that the DID document is published and that the DID network is reachable",
+            Self::Encoding(..) => "check the encoding of the value, it was probably corrupted or truncated",
+            Self::External(..) => "see the source of the error for details",
+            Self::InvalidSize(..) => "check that the value was not truncated or padded",
+            #[cfg(feature = "mnemonic")]
+            Self::KeyDerivation(..) => "check the mnemonic phrase and the derivation path",
+            Self::Malformed(..) => "check the value against the documented format",
+            Self::Signature(..) => "check that the message was signed by the expected identity and was not altered",
+            Self::Spongos(..) => "the message is malformed or was altered, or the wrong keys are used to read it",
+            Self::UnknownMulticodec(..) => {
+                "enable the feature of the identifier type (`did`, `post-quantum`) or upgrade"
+            }
+            Self::AddressError(..) => "check that the address is correct and that the message was published",
+            #[cfg(any(feature = "tangle-client", feature = "tangle-client-wasm"))]
+            Self::IotaClient(..) => "check that the node is reachable and synced, then retry",
+            Self::MessageMissing(..) => "the message may not be published yet, retry later or check the address",
+            Self::MessageTooLarge(..) => "split the payload over several messages",
+            Self::MessageNotFound(..) => "the message may not be published yet, retry later or check the address",
+            Self::MultipleMessagesFound(..) => {
+                "the address is contested, possibly by spam: screen the messages with a message filter"
+            }
+            Self::UnknownPayloadCodec(..) => "register the codec of the publisher with the encoding client",
+            Self::Nonce(..) => "check the minimum proof of work score reported by the node",
+            Self::Unhealthy(..) => "wait for the node to sync, or connect to another node",
+            Self::DigestLookupUnsupported => "look the message up by its address, as indexed by the transport",
+            Self::DigestNotFound(..) => "the message may not be published yet, retry later or check the digest",
+            #[cfg(feature = "utangle-client")]
+            Self::Request(..) => "check that the node is reachable and synced, then retry",
+        }
+    }
+
+    /// Returns a static summary of the error, without the values it carries. Unlike the description
+    /// of the error, the summary is rendered without any formatting machinery or allocation, which
+    /// makes it suitable for embedded builds. See [`Error::compact()`].
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Self::Crypto(..) => "crypto error",
+            #[cfg(feature = "did")]
+            Self::Did(..) => "DID error",
+            Self::Encoding(..) => "invalid encoding",
+            Self::External(..) => "external error",
+            Self::InvalidSize(..) => "invalid size",
+            #[cfg(feature = "mnemonic")]
+            Self::KeyDerivation(..) => "key derivation error",
+            Self::Malformed(..) => "malformed value",
+            Self::Signature(..) => "invalid signature",
+            Self::Spongos(..) => "spongos error",
+            Self::UnknownMulticodec(..) => "unknown multicodec",
+            Self::AddressError(..) => "transport error",
+            #[cfg(any(feature = "tangle-client", feature = "tangle-client-wasm"))]
+            Self::IotaClient(..) => "iota client error",
+            Self::MessageMissing(..) => "message missing",
+            Self::MessageTooLarge(..) => "message too large",
+            Self::MessageNotFound(..) => "message not found",
+            Self::MultipleMessagesFound(..) => "multiple messages found",
+            Self::UnknownPayloadCodec(..) => "unknown payload codec",
+            Self::Unhealthy(..) => "node unhealthy",
+            Self::DigestLookupUnsupported => "digest lookup unsupported",
+            Self::DigestNotFound(..) => "digest not found",
+            Self::Nonce(..) => "nonce out of range",
+            #[cfg(feature = "utangle-client")]
+            Self::Request(..) => "HTTP request error",
+        }
+    }
+
+    /// Returns the compact rendering of the error: its code and its [summary](Error::summary).
+    ///
+    /// The [`Display`](core::fmt::Display) implementation of the error describes it with the values
+    /// it carries, which pulls the formatting machinery of `core` in the binary. Embedded builds
+    /// rendering their errors only through [`CompactError`] leave that machinery out.
+    pub fn compact(&self) -> CompactError {
+        CompactError::new(self.code(), self.summary())
+    }
+
     #[cfg(feature = "did")]
     pub fn did<T: Into<IdentityError>>(did: &'static str, e: T) -> Self {
         Self::Did(did, e.into())
     }
 
+    /// Returns true if the error reports that no message was found at an address, as opposed to a
+    /// failure of the transport
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Self::MessageNotFound(..) | Self::MessageMissing(..) | Self::DigestNotFound(..)
+        )
+    }
+
     pub fn utf(m: &'static str, error: FromUtf8Error) -> Self {
         Self::Encoding(m, "utf8", Box::new(Self::External(error.into())))
     }
 }
 
+/// Catalog of localized error messages, mapping the [code](Error::code) of an error to its message
+/// in the language of the application. Codes missing from the catalog fall back to the English
+/// [summary](Error::summary) of the error.
+pub type ErrorCatalog = fn(u16) -> Option<&'static str>;
+
+/// Compact rendering of an error, made of its code and a static message, and of those of the LETS
+/// error causing it, if any. It is rendered as `E<code>: <message>` without any allocation nor
+/// formatting of values, for embedded builds.
+#[derive(Clone, Copy, Debug)]
+pub struct CompactError {
+    /// The code and the summary of the error
+    error: (u16, &'static str),
+    /// The code and the summary of the error causing it
+    cause: Option<(u16, &'static str)>,
+    /// The catalog the messages are localized with
+    catalog: Option<ErrorCatalog>,
+}
+
+impl CompactError {
+    /// Creates a new [`CompactError`]
+    ///
+    /// # Arguments
+    /// * `code`: The code of the error
+    /// * `summary`: The English summary of the error
+    pub fn new(code: u16, summary: &'static str) -> Self {
+        Self {
+            error: (code, summary),
+            cause: None,
+            catalog: None,
+        }
+    }
+
+    /// Adds the error causing the error, rendered after it
+    ///
+    /// # Arguments
+    /// * `cause`: The compact rendering of the cause
+    pub fn with_cause(mut self, cause: CompactError) -> Self {
+        self.cause = Some(cause.error);
+        self
+    }
+
+    /// Localizes the messages of the error and of its cause with a catalog
+    ///
+    /// # Arguments
+    /// * `catalog`: The [`ErrorCatalog`] mapping codes to localized messages
+    pub fn localized(mut self, catalog: ErrorCatalog) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    /// Returns the code of the error
+    pub fn code(&self) -> u16 {
+        self.error.0
+    }
+
+    /// Returns the code of the error causing the error, if any
+    pub fn cause_code(&self) -> Option<u16> {
+        self.cause.map(|(code, _)| code)
+    }
+
+    /// Returns the message of the error, localized if the catalog knows its code
+    pub fn message(&self) -> &'static str {
+        self.localize(self.error)
+    }
+
+    fn localize(&self, (code, summary): (u16, &'static str)) -> &'static str {
+        self.catalog.and_then(|catalog| catalog(code)).unwrap_or(summary)
+    }
+
+    fn write_entry(&self, f: &mut Formatter<'_>, (code, summary): (u16, &'static str)) -> fmt::Result {
+        // Codes have at most 5 digits, written without the integer formatting of `core`
+        let mut digits = [0u8; 5];
+        let mut start = digits.len();
+        let mut rest = code;
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (rest % 10) as u8;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+        f.write_str("E")?;
+        f.write_str(core::str::from_utf8(&digits[start..]).map_err(|_| fmt::Error)?)?;
+        f.write_str(": ")?;
+        f.write_str(self.localize((code, summary)))
+    }
+}
+
+impl Display for CompactError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_entry(f, self.error)?;
+        if let Some(cause) = self.cause {
+            f.write_str(" (")?;
+            self.write_entry(f, cause)?;
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
 impl From<SpongosError> for Error {
     fn from(error: SpongosError) -> Self {
         Self::Spongos(error)
@@ -166,4 +421,45 @@ impl From<reqwest::Error> for Error {
 extern crate std;
 
 #[cfg(feature = "std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Encoding(_, _, error) => Some(error.as_ref()),
+            Self::External(error) => Some(&**error),
+            Self::Spongos(error) => Some(error),
+            #[cfg(any(feature = "tangle-client", feature = "tangle-client-wasm"))]
+            Self::IotaClient(_, error) => Some(error),
+            #[cfg(feature = "utangle-client")]
+            Self::Request(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::address::Address;
+
+    use super::Error;
+
+    #[test]
+    fn errors_render_compactly_and_localized() {
+        let error = Error::MessageNotFound(Address::default());
+        assert_eq!(error.compact().to_string(), "E1107: message not found");
+
+        let catalog = |code: u16| match code {
+            1107 => Some("aucun message trouvé"),
+            _ => None,
+        };
+        assert_eq!(
+            error.compact().localized(catalog).to_string(),
+            "E1107: aucun message trouvé"
+        );
+        assert_eq!(
+            Error::DigestLookupUnsupported.compact().localized(catalog).message(),
+            "digest lookup unsupported"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// Rust
+use alloc::{format, vec::Vec};
+
+// 3rd-party
+
+// IOTA
+use crypto::keys::{
+    bip39,
+    slip10::{Chain, Curve, Seed},
+};
+
+// Streams
+
+// Local
+use crate::{
+    error::{Error, Result},
+    id::ed25519::Ed25519,
+};
+
+/// Hardened SLIP-10 path prefix used by [`KeyDerivation::derive_channel`]: BIP-44 purpose and the
+/// IOTA coin type
+const CHANNEL_PATH_PREFIX: &str = "m/44'/4218'";
+
+/// Hardened index flag of a SLIP-10 path segment
+const HARDENED: u32 = 1 << 31;
+
+/// Deterministic derivation of [`Ed25519`] identities from a single master seed, following
+/// BIP-39 for mnemonic phrases and SLIP-10 for hierarchical key derivation. Only hardened
+/// derivation is supported, as mandated by SLIP-10 for the ed25519 curve.
+pub struct KeyDerivation(Seed);
+
+impl KeyDerivation {
+    /// Creates a new [`KeyDerivation`] from a BIP-39 mnemonic phrase. The phrase is checked against
+    /// the english wordlist before being converted into the master seed.
+    ///
+    /// # Arguments
+    /// * `phrase`: The mnemonic phrase
+    /// * `passphrase`: Optional BIP-39 passphrase, empty if unused
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self> {
+        bip39::wordlist::verify(phrase, &bip39::wordlist::ENGLISH)
+            .map_err(|e| Error::KeyDerivation("verify mnemonic", format!("{:?}", e)))?;
+        let mut seed = [0u8; 64];
+        bip39::mnemonic_to_seed(phrase, passphrase, &mut seed);
+        Ok(Self::from_seed(seed))
+    }
+
+    /// Creates a new [`KeyDerivation`] from a raw master seed
+    ///
+    /// # Arguments
+    /// * `seed`: The master seed bytes
+    pub fn from_seed<T>(seed: T) -> Self
+    where
+        T: AsRef<[u8]>,
+    {
+        Self(Seed::from_bytes(seed.as_ref()))
+    }
+
+    /// Derives the [`Ed25519`] identity found at the provided SLIP-10 path
+    ///
+    /// # Arguments
+    /// * `path`: A path of hardened segments, like `m/44'/4218'/0'/0'`
+    pub fn derive(&self, path: &str) -> Result<Ed25519> {
+        let chain = Self::parse_path(path)?;
+        let key = self
+            .0
+            .derive(Curve::Ed25519, &chain)
+            .map_err(|e| Error::Crypto("derive key", e))?;
+        Ok(Ed25519::new(key.secret_key()))
+    }
+
+    /// Derives the [`Ed25519`] identity of a channel, using the path `m/44'/4218'/{channel}'/0'`.
+    /// Each channel index yields a distinct identity, so that a single mnemonic can be shared by
+    /// every channel of a device.
+    ///
+    /// # Arguments
+    /// * `channel`: Index of the channel the identity is derived for
+    pub fn derive_channel(&self, channel: u32) -> Result<Ed25519> {
+        self.derive(&format!("{}/{}'/0'", CHANNEL_PATH_PREFIX, channel))
+    }
+
+    /// Parses a path of the form `m/a'/b'/c'` into a SLIP-10 [`Chain`]
+    ///
+    /// # Arguments
+    /// * `path`: The path to be parsed
+    fn parse_path(path: &str) -> Result<Chain> {
+        let mut segments = path.split('/');
+        if segments.next() != Some("m") {
+            return Err(Error::KeyDerivation(
+                "parse path",
+                format!("'{}' must start with 'm'", path),
+            ));
+        }
+        let indexes = segments
+            .map(|segment| {
+                segment
+                    .strip_suffix('\'')
+                    .and_then(|index| index.parse::<u32>().ok())
+                    .filter(|index| index & HARDENED == 0)
+                    .ok_or_else(|| {
+                        Error::KeyDerivation(
+                            "parse path",
+                            format!("segment '{}' of '{}' is not a valid hardened index", segment, path),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<u32>>>()?;
+        Ok(Chain::from_u32_hardened(indexes))
+    }
+}
+
+impl core::fmt::Debug for KeyDerivation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "KeyDerivation(<hidden>)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn slip10_ed25519_test_vector_1() -> Result<()> {
+        let derivation = KeyDerivation::from_seed(hex::decode("000102030405060708090a0b0c0d0e0f")?);
+        let key = derivation.derive("m/0'/1'/2'")?;
+        assert_eq!(
+            hex::encode(key.inner().as_slice()),
+            "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9"
+        );
+        assert_eq!(
+            hex::encode(key.inner().public_key().as_slice()),
+            "ae98736566d30ed0e9d2f4486a64bc95740d89c7db33f52121f8ea8f76ff0fc1"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn identities_derive_from_a_mnemonic() -> Result<()> {
+        let key = Ed25519::from_mnemonic(MNEMONIC, "m/44'/4218'/0'/0'")?;
+        assert_eq!(
+            hex::encode(key.inner().public_key().as_slice()),
+            "e48b1e2b860a43cd31192be78bf8efdc6bf3339394d398a03ca492ab34ce0acf"
+        );
+        assert!(matches!(
+            Ed25519::from_mnemonic("abandon abandon streams", "m/44'/4218'/0'/0'"),
+            Err(Error::KeyDerivation("verify mnemonic", _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn channel_identities_are_deterministic() -> Result<()> {
+        let derivation = KeyDerivation::from_mnemonic(MNEMONIC, "")?;
+        let channel = derivation.derive_channel(0)?;
+        assert!(channel == KeyDerivation::from_mnemonic(MNEMONIC, "")?.derive_channel(0)?);
+        assert!(channel == Ed25519::from_mnemonic(MNEMONIC, "m/44'/4218'/0'/0'")?);
+        assert!(channel != derivation.derive_channel(1)?);
+        assert!(channel != KeyDerivation::from_mnemonic(MNEMONIC, "passphrase")?.derive_channel(0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_paths_are_rejected() {
+        for path in ["0'/1'", "m/0'/1", "m/2147483648'", "m//1'"] {
+            assert!(
+                matches!(
+                    KeyDerivation::parse_path(path),
+                    Err(Error::KeyDerivation("parse path", _))
+                ),
+                "{} should be rejected",
+                path
+            );
+        }
+    }
+}
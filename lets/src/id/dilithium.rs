@@ -0,0 +1,216 @@
+// Rust
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+// 3rd-party
+use pqcrypto_dilithium::dilithium3;
+use pqcrypto_traits::sign::{DetachedSignature as _, PublicKey as _, SecretKey as _};
+
+// IOTA
+use crypto::keys::x25519;
+
+// Streams
+use spongos::{KeccakF1600, SpongosRng};
+
+// Local
+use crate::error::{Error, Result};
+
+/// Length of a [`Dilithium`] public key
+pub const DILITHIUM_PUBLIC_KEY_LENGTH: usize = dilithium3::public_key_bytes();
+/// Length of a [`Dilithium`] secret key
+pub const DILITHIUM_SECRET_KEY_LENGTH: usize = dilithium3::secret_key_bytes();
+/// Length of a [`Dilithium`] signature
+pub const DILITHIUM_SIGNATURE_LENGTH: usize = dilithium3::signature_bytes();
+
+/// Post-quantum signing identity using CRYSTALS-Dilithium (ML-DSA), security level 3.
+///
+/// Dilithium keys cannot be used for key exchange, so the identity also holds an
+/// [`x25519::SecretKey`] deterministically derived from the Dilithium secret key. Its public part
+/// is carried alongside the Dilithium public key in the [`Identifier`](crate::id::Identifier) of
+/// the identity.
+pub struct Dilithium {
+    public_key: dilithium3::PublicKey,
+    secret_key: dilithium3::SecretKey,
+}
+
+impl Dilithium {
+    /// Creates a new [`Dilithium`] wrapper around the provided keypair
+    ///
+    /// # Arguments
+    /// * `public_key`: The [`dilithium3::PublicKey`] of the keypair
+    /// * `secret_key`: The [`dilithium3::SecretKey`] of the keypair
+    pub fn new(public_key: dilithium3::PublicKey, secret_key: dilithium3::SecretKey) -> Self {
+        Self { public_key, secret_key }
+    }
+
+    /// Generates a new [`Dilithium`] keypair using the randomness source of the operating system
+    pub fn generate() -> Self {
+        let (public_key, secret_key) = dilithium3::keypair();
+        Self::new(public_key, secret_key)
+    }
+
+    /// Restores a [`Dilithium`] keypair from the bytes of its keys
+    ///
+    /// # Arguments
+    /// * `public_key`: The bytes of the public key
+    /// * `secret_key`: The bytes of the secret key
+    pub fn try_from_slices(public_key: &[u8], secret_key: &[u8]) -> Result<Self> {
+        let public_key = dilithium3::PublicKey::from_bytes(public_key).map_err(|_| {
+            Error::InvalidSize(
+                "dilithium public key",
+                DILITHIUM_PUBLIC_KEY_LENGTH,
+                public_key.len() as u64,
+            )
+        })?;
+        let secret_key = dilithium3::SecretKey::from_bytes(secret_key).map_err(|_| {
+            Error::InvalidSize(
+                "dilithium secret key",
+                DILITHIUM_SECRET_KEY_LENGTH,
+                secret_key.len() as u64,
+            )
+        })?;
+        Ok(Self::new(public_key, secret_key))
+    }
+
+    /// Returns the public part of the identity
+    pub fn public_key(&self) -> DilithiumPublicKey {
+        DilithiumPublicKey::new(self.public_key, self.exchange_key().public_key())
+    }
+
+    /// Returns the bytes of the public key
+    pub(crate) fn public_key_bytes(&self) -> &[u8] {
+        self.public_key.as_bytes()
+    }
+
+    /// Returns the bytes of the secret key
+    pub(crate) fn secret_key_bytes(&self) -> &[u8] {
+        self.secret_key.as_bytes()
+    }
+
+    /// Returns the key exchange secret key of the identity, derived from the Dilithium secret key
+    pub(crate) fn exchange_key(&self) -> x25519::SecretKey {
+        x25519::SecretKey::generate_with(&mut SpongosRng::<KeccakF1600>::new(self.secret_key.as_bytes()))
+    }
+
+    /// Signs a message hash, returning the detached signature
+    ///
+    /// # Arguments
+    /// * `hash`: The message hash to sign
+    pub(crate) fn sign(&self, hash: &[u8]) -> Vec<u8> {
+        dilithium3::detached_sign(hash, &self.secret_key).as_bytes().to_vec()
+    }
+}
+
+impl PartialEq for Dilithium {
+    fn eq(&self, other: &Self) -> bool {
+        self.secret_key.as_bytes() == other.secret_key.as_bytes()
+    }
+}
+
+impl Eq for Dilithium {}
+
+impl PartialOrd for Dilithium {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dilithium {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.secret_key.as_bytes().cmp(other.secret_key.as_bytes())
+    }
+}
+
+impl Hash for Dilithium {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.secret_key.as_bytes().hash(state);
+    }
+}
+
+/// Public part of a [`Dilithium`] identity: the key verifying its signatures and the
+/// [`x25519::PublicKey`] recipients encrypt to
+#[derive(Clone, Copy)]
+pub struct DilithiumPublicKey {
+    signing: dilithium3::PublicKey,
+    exchange: [u8; x25519::PUBLIC_KEY_LENGTH],
+}
+
+impl DilithiumPublicKey {
+    /// Creates a new [`DilithiumPublicKey`] from its signature verification and key exchange keys
+    ///
+    /// # Arguments
+    /// * `signing`: The [`dilithium3::PublicKey`] verifying the signatures of the identity
+    /// * `exchange`: The [`x25519::PublicKey`] used for key exchange with the identity
+    pub fn new(signing: dilithium3::PublicKey, exchange: x25519::PublicKey) -> Self {
+        Self {
+            signing,
+            exchange: exchange.to_bytes(),
+        }
+    }
+
+    /// Restores a [`DilithiumPublicKey`] from the bytes of its signature verification key
+    ///
+    /// # Arguments
+    /// * `signing`: The bytes of the signature verification key
+    /// * `exchange`: The [`x25519::PublicKey`] used for key exchange with the identity
+    pub fn try_from_slice(signing: &[u8], exchange: x25519::PublicKey) -> Result<Self> {
+        let signing = dilithium3::PublicKey::from_bytes(signing).map_err(|_| {
+            Error::InvalidSize(
+                "dilithium public key",
+                DILITHIUM_PUBLIC_KEY_LENGTH,
+                signing.len() as u64,
+            )
+        })?;
+        Ok(Self::new(signing, exchange))
+    }
+
+    /// Returns the [`x25519::PublicKey`] used for key exchange with the identity
+    pub fn exchange_key(&self) -> x25519::PublicKey {
+        x25519::PublicKey::from(self.exchange)
+    }
+
+    /// Returns the bytes of the signature verification key
+    pub fn as_slice(&self) -> &[u8] {
+        self.signing.as_bytes()
+    }
+
+    /// Verifies a detached signature of a message hash
+    ///
+    /// # Arguments
+    /// * `signature`: The bytes of the detached signature
+    /// * `hash`: The signed message hash
+    pub(crate) fn verify(&self, signature: &[u8], hash: &[u8]) -> bool {
+        dilithium3::DetachedSignature::from_bytes(signature)
+            .map(|signature| dilithium3::verify_detached_signature(&signature, hash, &self.signing).is_ok())
+            .unwrap_or(false)
+    }
+}
+
+impl PartialEq for DilithiumPublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice() && self.exchange == other.exchange
+    }
+}
+
+impl Eq for DilithiumPublicKey {}
+
+impl PartialOrd for DilithiumPublicKey {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DilithiumPublicKey {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_slice()
+            .cmp(other.as_slice())
+            .then_with(|| self.exchange.cmp(&other.exchange))
+    }
+}
+
+impl Hash for DilithiumPublicKey {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+        self.exchange.hash(state);
+    }
+}
@@ -10,6 +10,8 @@ use crypto::signatures::ed25519;
 use spongos::{KeccakF1600, SpongosRng};
 
 // Local
+#[cfg(feature = "mnemonic")]
+use crate::{error::Result, id::KeyDerivation};
 
 /// Wrapper for [`ed25519::SecretKey`]
 pub struct Ed25519(ed25519::SecretKey);
@@ -37,6 +39,17 @@ impl Ed25519 {
         )))
     }
 
+    /// Derives an [`Ed25519`] identity from a BIP-39 mnemonic phrase and a SLIP-10 path. See
+    /// [`KeyDerivation`] to derive several identities from the same phrase.
+    ///
+    /// # Arguments
+    /// * `phrase`: The mnemonic phrase
+    /// * `path`: A path of hardened segments, like `m/44'/4218'/0'/0'`
+    #[cfg(feature = "mnemonic")]
+    pub fn from_mnemonic(phrase: &str, path: &str) -> Result<Self> {
+        KeyDerivation::from_mnemonic(phrase, "")?.derive(path)
+    }
+
     /// Returns a reference to the inner [`ed25519::SecretKey`]
     pub(crate) fn inner(&self) -> &ed25519::SecretKey {
         &self.0
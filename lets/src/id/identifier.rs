@@ -1,10 +1,14 @@
 // Rust
-use alloc::boxed::Box;
-use core::convert::{TryFrom, TryInto};
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    convert::{TryFrom, TryInto},
+    str::FromStr,
+};
 use spongos::ddml::commands::X25519;
 
 // 3rd-party
 use async_trait::async_trait;
+use multibase::Base;
 
 // IOTA
 use crypto::{keys::x25519, signatures::ed25519};
@@ -22,18 +26,30 @@ use spongos::{
 };
 
 // Local
+#[cfg(any(feature = "did", feature = "post-quantum"))]
+use crate::alloc::string::ToString;
 #[cfg(feature = "did")]
-use crate::{
-    alloc::string::ToString,
-    error::Error,
-    id::did::{resolve_document, DIDUrlInfo},
-};
+use crate::id::did::{resolve_document, DIDUrlInfo};
+#[cfg(feature = "post-quantum")]
+use crate::id::dilithium::{DilithiumPublicKey, DILITHIUM_PUBLIC_KEY_LENGTH, DILITHIUM_SIGNATURE_LENGTH};
 
 use crate::{
-    error::Result,
+    error::{Error, Result},
     message::{ContentEncrypt, ContentEncryptSizeOf, ContentVerify},
+    sync::MaybeSend,
 };
 
+/// Multicodec of Ed25519 public keys
+const ED25519_MULTICODEC: u64 = 0xed;
+/// Multicodec, in the private use range, of `DID` identifiers: the `DID`, the node URL and the
+/// exchange and signing fragments follow as length-prefixed UTF-8 strings
+#[cfg(feature = "did")]
+const DID_MULTICODEC: u64 = 0x30_0001;
+/// Multicodec of ML-DSA-65 (Dilithium3) public keys. The X25519 exchange key of the identifier
+/// follows the signing key
+#[cfg(feature = "post-quantum")]
+const DILITHIUM_MULTICODEC: u64 = 0x1211;
+
 /// User Identification types
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Identifier {
@@ -42,6 +58,9 @@ pub enum Identifier {
     /// IOTA DID based identifier
     #[cfg(feature = "did")]
     DID(DIDUrlInfo),
+    /// Post-quantum Dilithium based identifier
+    #[cfg(feature = "post-quantum")]
+    Dilithium(DilithiumPublicKey),
 }
 
 impl core::fmt::Debug for Identifier {
@@ -55,6 +74,11 @@ impl core::fmt::Debug for Identifier {
                 .field(&url_info.exchange_fragment())
                 .field(&url_info.signing_fragment())
                 .finish(),
+            #[cfg(feature = "post-quantum")]
+            Self::Dilithium(public_key) => f
+                .debug_tuple("Dilithium")
+                .field(&hex::encode(public_key.as_slice()))
+                .finish(),
         }
     }
 }
@@ -66,6 +90,8 @@ impl Identifier {
             Identifier::Ed25519(public_key) => public_key.as_slice(),
             #[cfg(feature = "did")]
             Identifier::DID(url_info) => url_info.as_ref(),
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => public_key.as_slice(),
         }
     }
 
@@ -96,13 +122,76 @@ impl Identifier {
                     )),
                 }
             }
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => Ok(public_key.exchange_key()),
+        }
+    }
+
+    /// Verifies a detached signature created by the [`Identity`](crate::id::Identity) of the
+    /// [`Identifier`] with `sign_detached()`. `DID` identifiers never verify, as `DID` identities
+    /// cannot sign detached hashes.
+    ///
+    /// # Arguments
+    /// * `hash`: The signed hash
+    /// * `signature`: The bytes of the detached signature
+    pub fn verify_detached(&self, hash: &[u8], signature: &[u8]) -> Result<()> {
+        let verified = match self {
+            Identifier::Ed25519(public_key) => <[u8; ed25519::SIGNATURE_LENGTH]>::try_from(signature)
+                .map(|signature| public_key.verify(&ed25519::Signature::from_bytes(signature), hash))
+                .unwrap_or(false),
+            #[cfg(feature = "did")]
+            Identifier::DID(_) => false,
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => public_key.verify(signature, hash),
+        };
+        match verified {
+            true => Ok(()),
+            false => Err(Error::Signature("verifying", "match it with the identifier")),
+        }
+    }
+
+    /// Encodes the [`Identifier`] as its multicodec followed by its public key material, as
+    /// formatted by [`Display`](core::fmt::Display) and parsed by [`FromStr`]
+    fn to_multicodec(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Identifier::Ed25519(public_key) => {
+                write_varint(&mut bytes, ED25519_MULTICODEC);
+                bytes.extend_from_slice(public_key.as_slice());
+            }
+            #[cfg(feature = "did")]
+            Identifier::DID(url_info) => {
+                write_varint(&mut bytes, DID_MULTICODEC);
+                for field in [
+                    url_info.did(),
+                    url_info.client_url(),
+                    url_info.exchange_fragment(),
+                    url_info.signing_fragment(),
+                ] {
+                    write_varint(&mut bytes, field.len() as u64);
+                    bytes.extend_from_slice(field.as_bytes());
+                }
+            }
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => {
+                write_varint(&mut bytes, DILITHIUM_MULTICODEC);
+                bytes.extend_from_slice(public_key.as_slice());
+                bytes.extend_from_slice(&public_key.exchange_key().to_bytes());
+            }
         }
+        bytes
     }
 
     /// Returns whether the [`Identifier`] type is Ed25519 or not
     pub fn is_ed25519(&self) -> bool {
         matches!(self, Self::Ed25519(_))
     }
+
+    /// Returns whether the [`Identifier`] type is Dilithium or not
+    #[cfg(feature = "post-quantum")]
+    pub fn is_dilithium(&self) -> bool {
+        matches!(self, Self::Dilithium(_))
+    }
 }
 
 impl Default for Identifier {
@@ -118,6 +207,13 @@ impl From<ed25519::PublicKey> for Identifier {
     }
 }
 
+#[cfg(feature = "post-quantum")]
+impl From<DilithiumPublicKey> for Identifier {
+    fn from(pk: DilithiumPublicKey) -> Self {
+        Identifier::Dilithium(pk)
+    }
+}
+
 impl AsRef<[u8]> for Identifier {
     fn as_ref(&self) -> &[u8] {
         self.as_bytes()
@@ -136,10 +232,101 @@ impl core::fmt::UpperHex for Identifier {
     }
 }
 
+/// Formats the [`Identifier`] as a base58btc multibase string of its multicodec and public key
+/// material, e.g. `z6Mk...` for Ed25519 identifiers, as `did:key` does. Use the
+/// [`LowerHex`](core::fmt::LowerHex) format for the raw bytes of the identifier.
 impl core::fmt::Display for Identifier {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        core::fmt::LowerHex::fmt(self, f)
+        f.write_str(&multibase::encode(Base::Base58Btc, self.to_multicodec()))
+    }
+}
+
+/// Parses an [`Identifier`] formatted by [`Display`](core::fmt::Display). Any multibase encoding
+/// is accepted.
+impl FromStr for Identifier {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Self> {
+        let (_, bytes) = multibase::decode(string).map_err(|e| {
+            Error::Encoding(
+                "identifier",
+                "multibase",
+                Box::new(Error::External(anyhow::Error::msg(e))),
+            )
+        })?;
+        let mut bytes = bytes.as_slice();
+        match read_varint(&mut bytes)? {
+            ED25519_MULTICODEC => {
+                let public_key = <[u8; ed25519::PUBLIC_KEY_LENGTH]>::try_from(bytes).map_err(|_| {
+                    Error::InvalidSize("Ed25519 identifier", ed25519::PUBLIC_KEY_LENGTH, bytes.len() as u64)
+                })?;
+                let public_key = ed25519::PublicKey::try_from_bytes(public_key)
+                    .map_err(|e| Error::Crypto("create the public key from bytes", e))?;
+                Ok(Identifier::Ed25519(public_key))
+            }
+            #[cfg(feature = "did")]
+            DID_MULTICODEC => {
+                let mut url_info = DIDUrlInfo::default();
+                *url_info.did_mut() = read_string(&mut bytes, "did")?;
+                *url_info.client_url_mut() = read_string(&mut bytes, "client url")?;
+                *url_info.exchange_fragment_mut() = read_string(&mut bytes, "exchange fragment")?;
+                *url_info.signing_fragment_mut() = read_string(&mut bytes, "signing fragment")?;
+                match bytes.is_empty() {
+                    true => Ok(Identifier::DID(url_info)),
+                    false => Err(Error::Malformed("DID identifier", "end", string.to_string())),
+                }
+            }
+            #[cfg(feature = "post-quantum")]
+            DILITHIUM_MULTICODEC => {
+                let length = DILITHIUM_PUBLIC_KEY_LENGTH + x25519::PUBLIC_KEY_LENGTH;
+                if bytes.len() != length {
+                    return Err(Error::InvalidSize("Dilithium identifier", length, bytes.len() as u64));
+                }
+                let (signing, exchange) = bytes.split_at(DILITHIUM_PUBLIC_KEY_LENGTH);
+                let exchange = x25519::PublicKey::try_from_slice(exchange)
+                    .map_err(|e| Error::Crypto("create the public key from slice", e))?;
+                Ok(Identifier::Dilithium(DilithiumPublicKey::try_from_slice(
+                    signing, exchange,
+                )?))
+            }
+            codec => Err(Error::UnknownMulticodec(codec)),
+        }
+    }
+}
+
+/// Appends an unsigned varint, as used by multicodec prefixes
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
     }
+    bytes.push(value as u8);
+}
+
+/// Reads an unsigned varint off the front of `bytes`
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let slice: &[u8] = *bytes;
+    let mut value = 0;
+    for (i, byte) in slice.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &slice[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(Error::Malformed("multicodec", "varint end", hex::encode(slice)))
+}
+
+/// Reads a varint length-prefixed UTF-8 string off the front of `bytes`
+#[cfg(feature = "did")]
+fn read_string(bytes: &mut &[u8], field: &'static str) -> Result<alloc::string::String> {
+    let length = read_varint(bytes)? as usize;
+    if bytes.len() < length {
+        return Err(Error::InvalidSize(field, length, bytes.len() as u64));
+    }
+    let (string, rest) = bytes.split_at(length);
+    *bytes = rest;
+    alloc::string::String::from_utf8(string.to_vec()).map_err(|e| Error::utf(field, e))
 }
 
 impl Mask<&Identifier> for sizeof::Context {
@@ -156,6 +343,14 @@ impl Mask<&Identifier> for sizeof::Context {
                 self.mask(oneof)?.mask(url_info)?;
                 Ok(self)
             }
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => {
+                let oneof = Uint8::new(2);
+                self.mask(oneof)?
+                    .mask(NBytes::new(public_key.as_slice()))?
+                    .mask(&public_key.exchange_key())?;
+                Ok(self)
+            }
         }
     }
 }
@@ -178,6 +373,14 @@ where
                 self.mask(oneof)?.mask(url_info)?;
                 Ok(self)
             }
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => {
+                let oneof = Uint8::new(2);
+                self.mask(oneof)?
+                    .mask(NBytes::new(public_key.as_slice()))?
+                    .mask(&public_key.exchange_key())?;
+                Ok(self)
+            }
         }
     }
 }
@@ -202,23 +405,34 @@ where
                 self.mask(&mut url_info)?;
                 *identifier = Identifier::DID(url_info);
             }
+            #[cfg(feature = "post-quantum")]
+            2 => {
+                let mut signing = vec![0; DILITHIUM_PUBLIC_KEY_LENGTH];
+                let mut exchange = x25519::PublicKey::from([0; x25519::PUBLIC_KEY_LENGTH]);
+                self.mask(NBytes::new(&mut signing))?.mask(&mut exchange)?;
+                let public_key = DilithiumPublicKey::try_from_slice(&signing, exchange)
+                    .map_err(|e| SpongosError::Context("unwrap dilithium identifier", e.to_string()))?;
+                *identifier = Identifier::Dilithium(public_key);
+            }
             o => return Err(SpongosError::InvalidOption("identifier", o)),
         }
         Ok(self)
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<IS, F> ContentVerify<Identifier> for unwrap::Context<IS, F>
 where
-    F: PRP,
-    IS: io::IStream,
+    F: PRP + MaybeSend,
+    IS: io::IStream + MaybeSend,
 {
     /// Verifies the signature of the message based on the type of [`Identifier`] of the signing
     /// user. If the sender [`Identifier`] is of type [`Identifier::Ed25519`], then the public
     /// key is used to verify the message signature. If it is of type [`Identifier::DID`], then
     /// the `DID` document is retrieved and the signature is verified using the appropriately
-    /// tagged `Verification Method`.
+    /// tagged `Verification Method`. If it is of type [`Identifier::Dilithium`], the detached
+    /// post-quantum signature is verified using the Dilithium public key.
     ///
     /// # Arguments
     /// * `verifier`: The [`Identifier`] of the signer.
@@ -234,7 +448,7 @@ where
                         .ed25519(public_key, hash.as_ref())?;
                     Ok(self)
                 }
-                #[cfg(feature = "did")]
+                #[cfg(any(feature = "did", feature = "post-quantum"))]
                 o => Err(SpongosError::InvalidAction(
                     "verify data",
                     o.to_string(),
@@ -278,13 +492,33 @@ where
                     verifier.to_string(),
                 )),
             },
+            #[cfg(feature = "post-quantum")]
+            2 => match verifier {
+                Identifier::Dilithium(public_key) => {
+                    let mut hash = [0; 64];
+                    let mut signature = vec![0; DILITHIUM_SIGNATURE_LENGTH];
+                    self.commit()?
+                        .squeeze(External::new(&mut NBytes::new(&mut hash)))?
+                        .absorb(NBytes::new(&mut signature))?;
+                    match public_key.verify(&signature, &hash) {
+                        true => Ok(self),
+                        false => Err(SpongosError::SignatureMismatch),
+                    }
+                }
+                o => Err(SpongosError::InvalidAction(
+                    "verify data",
+                    o.to_string(),
+                    verifier.to_string(),
+                )),
+            },
             o => Err(SpongosError::InvalidOption("identity", o)),
         }
     }
 }
 
 // TODO: Find a better way to represent this logic without the need for an additional trait
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl ContentEncryptSizeOf<Identifier> for sizeof::Context {
     async fn encrypt_sizeof(&mut self, recipient: &Identifier, key: &[u8]) -> SpongosResult<&mut Self> {
         // TODO: Replace with separate logic for EdPubKey and DID instances (pending Identity xkey
@@ -313,15 +547,18 @@ impl ContentEncryptSizeOf<Identifier> for sizeof::Context {
                 .map_err(|e| SpongosError::Context("ContentEncrypt x25519::PublicKey try_from_slice", e.to_string()))?;
                 self.x25519(&xkey, NBytes::new(key))
             }
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => self.x25519(&public_key.exchange_key(), NBytes::new(key)),
         }
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<OS, F> ContentEncrypt<Identifier> for wrap::Context<OS, F>
 where
-    F: PRP,
-    OS: io::OStream,
+    F: PRP + MaybeSend,
+    OS: io::OStream + MaybeSend,
 {
     async fn encrypt(&mut self, recipient: &Identifier, key: &[u8]) -> SpongosResult<&mut Self> {
         // TODO: Replace with separate logic for EdPubKey and DID instances (pending Identity xkey
@@ -351,6 +588,36 @@ where
                 .map_err(|e| SpongosError::Context("ContentEncrypt x25519::PublicKey try_from_slice", e.to_string()))?;
                 self.x25519(&xkey, NBytes::new(key))
             }
+            #[cfg(feature = "post-quantum")]
+            Identifier::Dilithium(public_key) => self.x25519(&public_key.exchange_key(), NBytes::new(key)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_round_trip_through_their_multibase_format() -> Result<()> {
+        let identifier = Identifier::from(crate::id::Ed25519::from_seed("alice").inner().public_key());
+        let formatted = identifier.to_string();
+        assert!(formatted.starts_with("z6Mk"));
+        assert_eq!(formatted.parse::<Identifier>()?, identifier);
+
+        // Any multibase encoding of the same bytes parses to the same identifier
+        let base32 = multibase::encode(Base::Base32Lower, identifier.to_multicodec());
+        assert_eq!(base32.parse::<Identifier>()?, identifier);
+
+        assert!(matches!(
+            "z".parse::<Identifier>(),
+            Err(Error::Malformed("multicodec", ..))
+        ));
+        let unknown = multibase::encode(Base::Base58Btc, [0x12, 0, 0]);
+        assert!(matches!(
+            unknown.parse::<Identifier>(),
+            Err(Error::UnknownMulticodec(0x12))
+        ));
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 // Rust
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use core::{hash::Hash, ops::Deref};
 
 // 3rd-party
@@ -29,9 +29,14 @@ use spongos::{
 };
 
 // Local
+#[cfg(any(feature = "did", feature = "post-quantum"))]
+use crate::alloc::string::ToString;
+#[cfg(feature = "post-quantum")]
+use crate::id::dilithium::{
+    Dilithium, DILITHIUM_PUBLIC_KEY_LENGTH, DILITHIUM_SECRET_KEY_LENGTH, DILITHIUM_SIGNATURE_LENGTH,
+};
 #[cfg(feature = "did")]
 use crate::{
-    alloc::string::ToString,
     error::Error,
     id::did::{DataWrapper, DID},
 };
@@ -40,6 +45,7 @@ use crate::{
     error::Result,
     id::{ed25519::Ed25519, identifier::Identifier},
     message::{ContentDecrypt, ContentSign, ContentSignSizeof},
+    sync::MaybeSend,
 };
 
 /// Wrapper around [`Identifier`], specifying which type of [`Identity`] is being used. An
@@ -104,6 +110,13 @@ impl From<DID> for Identity {
     }
 }
 
+#[cfg(feature = "post-quantum")]
+impl From<Dilithium> for Identity {
+    fn from(dilithium: Dilithium) -> Self {
+        Self::new(IdentityKind::Dilithium(dilithium))
+    }
+}
+
 /// Wrapper for [`Identity`] details
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(clippy::large_enum_variant)]
@@ -113,6 +126,9 @@ pub enum IdentityKind {
     /// An IOTA `DID` type [`Identity`] using a `DID` document stored in the tangle
     #[cfg(feature = "did")]
     DID(DID),
+    /// A post-quantum Dilithium type [`Identity`] using a private key
+    #[cfg(feature = "post-quantum")]
+    Dilithium(Dilithium),
 }
 
 impl Default for IdentityKind {
@@ -132,16 +148,36 @@ impl IdentityKind {
             Self::DID(DID::PrivateKey(info)) => Ok(info.exchange_key()?),
             #[cfg(feature = "did")]
             Self::DID(DID::Default) => unreachable!(),
+            #[cfg(feature = "post-quantum")]
+            Self::Dilithium(dilithium) => Ok(dilithium.exchange_key()),
             // TODO: Account implementation
         }
     }
 
+    /// Signs a hash outside of any message, returning a detached signature that can be verified
+    /// with [`Identifier::verify_detached()`] without the [`Spongos`](spongos::Spongos) state of a
+    /// message. `DID` identities cannot sign detached hashes.
+    ///
+    /// # Arguments
+    /// * `hash`: The hash to sign
+    pub fn sign_detached(&self, hash: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Ed25519(ed25519) => Ok(ed25519.inner().sign(hash).to_bytes().to_vec()),
+            #[cfg(feature = "did")]
+            Self::DID(_) => Err(Error::Signature("creating", "sign a detached hash with a DID identity")),
+            #[cfg(feature = "post-quantum")]
+            Self::Dilithium(dilithium) => Ok(dilithium.sign(hash)),
+        }
+    }
+
     /// Converts the [`IdentityKind`] instance into an [`Identifier`]
     pub fn to_identifier(&self) -> Identifier {
         match self {
             Self::Ed25519(ed25519) => ed25519.inner().public_key().into(),
             #[cfg(feature = "did")]
             Self::DID(did) => Identifier::DID(did.info().url_info().clone()),
+            #[cfg(feature = "post-quantum")]
+            Self::Dilithium(dilithium) => dilithium.public_key().into(),
         }
     }
 }
@@ -152,6 +188,11 @@ impl Mask<&Identity> for sizeof::Context {
             IdentityKind::Ed25519(ed25519) => self.mask(Uint8::new(0))?.mask(NBytes::new(ed25519)),
             #[cfg(feature = "did")]
             IdentityKind::DID(did) => self.mask(Uint8::new(1))?.mask(did),
+            #[cfg(feature = "post-quantum")]
+            IdentityKind::Dilithium(dilithium) => self
+                .mask(Uint8::new(2))?
+                .mask(NBytes::new(dilithium.public_key_bytes()))?
+                .mask(NBytes::new(dilithium.secret_key_bytes())),
         }
     }
 }
@@ -166,6 +207,11 @@ where
             IdentityKind::Ed25519(ed25519) => self.mask(Uint8::new(0))?.mask(NBytes::new(ed25519)),
             #[cfg(feature = "did")]
             IdentityKind::DID(did) => self.mask(Uint8::new(1))?.mask(did),
+            #[cfg(feature = "post-quantum")]
+            IdentityKind::Dilithium(dilithium) => self
+                .mask(Uint8::new(2))?
+                .mask(NBytes::new(dilithium.public_key_bytes()))?
+                .mask(NBytes::new(dilithium.secret_key_bytes())),
         }
     }
 }
@@ -190,6 +236,16 @@ where
                 self.mask(&mut did)?;
                 IdentityKind::DID(did)
             }
+            #[cfg(feature = "post-quantum")]
+            2 => {
+                let mut public_key = vec![0; DILITHIUM_PUBLIC_KEY_LENGTH];
+                let mut secret_key = vec![0; DILITHIUM_SECRET_KEY_LENGTH];
+                self.mask(NBytes::new(&mut public_key))?
+                    .mask(NBytes::new(&mut secret_key))?;
+                let dilithium = Dilithium::try_from_slices(&public_key, &secret_key)
+                    .map_err(|e| SpongosError::Context("unwrap dilithium identity", e.to_string()))?;
+                IdentityKind::Dilithium(dilithium)
+            }
             o => return Err(SpongosError::InvalidOption("identitykind", o)),
         };
 
@@ -198,7 +254,8 @@ where
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl ContentSignSizeof<Identity> for sizeof::Context {
     async fn sign_sizeof(&mut self, signer: &Identity) -> SpongosResult<&mut Self> {
         match &signer.identitykind {
@@ -225,15 +282,25 @@ impl ContentSignSizeof<Identity> for sizeof::Context {
                 }
                 DID::Default => unreachable!(),
             },
+
+            #[cfg(feature = "post-quantum")]
+            IdentityKind::Dilithium(_) => {
+                let hash = [0; 64];
+                self.absorb(Uint8::new(2))?
+                    .commit()?
+                    .squeeze(External::new(&NBytes::new(&hash)))?
+                    .absorb(NBytes::new([0; DILITHIUM_SIGNATURE_LENGTH]))
+            }
         }
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<OS, F> ContentSign<IdentityKind> for wrap::Context<OS, F>
 where
-    F: PRP,
-    OS: io::OStream,
+    F: PRP + MaybeSend,
+    OS: io::OStream + MaybeSend,
 {
     async fn sign(&mut self, signer: &IdentityKind) -> SpongosResult<&mut Self> {
         match signer {
@@ -294,15 +361,26 @@ where
                     // TODO: Implement Account logic
                 }
             }
+
+            #[cfg(feature = "post-quantum")]
+            IdentityKind::Dilithium(dilithium) => {
+                let mut hash = [0; 64];
+                self.absorb(Uint8::new(2))?
+                    .commit()?
+                    .squeeze(External::new(&mut NBytes::new(&mut hash)))?;
+                let signature = dilithium.sign(&hash);
+                self.absorb(NBytes::new(signature))
+            }
         }
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<IS, F> ContentDecrypt<Identity> for unwrap::Context<IS, F>
 where
-    F: PRP,
-    IS: io::IStream,
+    F: PRP + MaybeSend,
+    IS: io::IStream + MaybeSend,
 {
     async fn decrypt(&mut self, recipient: &Identity, key: &mut [u8]) -> SpongosResult<&mut Self> {
         // TODO: Replace with separate logic for EdPubKey and DID instances (pending Identity xkey
@@ -316,6 +394,8 @@ where
                     .map_err(|e| SpongosError::Context("ContentDecrypt", e.to_string()))?,
                 NBytes::new(key),
             ),
+            #[cfg(feature = "post-quantum")]
+            IdentityKind::Dilithium(dilithium) => self.x25519(&dilithium.exchange_key(), NBytes::new(key)),
         }
     }
 }
@@ -0,0 +1,79 @@
+// Rust
+use alloc::{boxed::Box, string::ToString};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+use crypto::keys::x25519;
+
+// IOTA-Streams
+use spongos::{
+    ddml::{
+        commands::{unwrap, Absorb, Commit, Mask},
+        io,
+        modifiers::External,
+        types::NBytes,
+    },
+    error::{Error as SpongosError, Result as SpongosResult},
+    PRP,
+};
+
+// Local
+use crate::{
+    error::Result,
+    message::ContentDecrypt,
+    sync::{MaybeSend, MaybeSync},
+};
+
+/// Holder of an X25519 key exchange key performing the Diffie-Hellman key agreement of the keys
+/// encrypted to it, so that the secret scalar never has to be exported, for example a key held by a
+/// KMS or an HSM.
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+pub trait KeyExchange: MaybeSend + MaybeSync {
+    /// Returns the X25519 public key of the key exchange key
+    fn public_key(&self) -> x25519::PublicKey;
+
+    /// Computes the X25519 shared secret of the key exchange key and a public key
+    ///
+    /// # Arguments
+    /// * `public_key`: The ephemeral public key of the sender
+    async fn diffie_hellman(&self, public_key: &x25519::PublicKey) -> Result<[u8; 32]>;
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl KeyExchange for x25519::SecretKey {
+    fn public_key(&self) -> x25519::PublicKey {
+        self.public_key()
+    }
+
+    async fn diffie_hellman(&self, public_key: &x25519::PublicKey) -> Result<[u8; 32]> {
+        Ok(*self.diffie_hellman(public_key).as_bytes())
+    }
+}
+
+/// Reads an ephemeral public key from the [`Context`](unwrap::Context) stream, and computes the
+/// shared key with the [`KeyExchange`]. The key is decrypted as by the
+/// [`X25519`](spongos::ddml::commands::X25519) command.
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'k, IS, F> ContentDecrypt<dyn KeyExchange + 'k> for unwrap::Context<IS, F>
+where
+    F: PRP + MaybeSend,
+    IS: io::IStream + MaybeSend,
+{
+    async fn decrypt(&mut self, recipient: &(dyn KeyExchange + 'k), key: &mut [u8]) -> SpongosResult<&mut Self> {
+        let mut ephemeral_public_key = x25519::PublicKey::from([0u8; x25519::PUBLIC_KEY_LENGTH]);
+        self.absorb(&mut ephemeral_public_key)?;
+        let shared_key = recipient
+            .diffie_hellman(&ephemeral_public_key)
+            .await
+            .map_err(|e| SpongosError::Context("ContentDecrypt", e.to_string()))?;
+        self.absorb(External::new(&NBytes::new(shared_key)))?
+            .commit()?
+            .mask(NBytes::new(key))?;
+        Ok(self)
+    }
+}
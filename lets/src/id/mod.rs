@@ -1,17 +1,38 @@
+/// Mnemonic based key derivation functions and types
+#[cfg(feature = "mnemonic")]
+mod derivation;
+/// Post-quantum Dilithium functions and types
+#[cfg(feature = "post-quantum")]
+mod dilithium;
 /// Ed25519 functions and types
 mod ed25519;
 /// User Identifier functions and types
 mod identifier;
 /// User Identity functions and types
 mod identity;
+/// Key exchange keys held outside of the user
+mod key_exchange;
 mod permission;
 mod psk;
+/// Hierarchical derivation of pre-shared keys
+mod psk_tree;
+/// Verification of Ed25519 signatures at once
+mod signature_batch;
 
 pub use self::identity::Identity;
+#[cfg(feature = "mnemonic")]
+pub use derivation::KeyDerivation;
+#[cfg(feature = "post-quantum")]
+pub use dilithium::{
+    Dilithium, DilithiumPublicKey, DILITHIUM_PUBLIC_KEY_LENGTH, DILITHIUM_SECRET_KEY_LENGTH, DILITHIUM_SIGNATURE_LENGTH,
+};
 pub use ed25519::Ed25519;
 pub use identifier::Identifier;
+pub use key_exchange::KeyExchange;
 pub use permission::{PermissionDuration, Permissioned};
 pub use psk::{Psk, PskId};
+pub use psk_tree::PskTree;
+pub use signature_batch::SignatureBatch;
 
 /// Iota Identity functions and types
 #[cfg(feature = "did")]
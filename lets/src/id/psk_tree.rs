@@ -0,0 +1,143 @@
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+use crypto::macs::hmac::HMAC_SHA256;
+
+// Streams
+
+// Local
+use crate::id::psk::{Psk, PskId};
+
+/// Salt of the extraction of the root key of a [`PskTree`] from its master secret
+const TREE_SALT: &[u8] = b"streams-psk-tree";
+/// Info prefix of the expansion of the key of a child node from the key of its parent
+const NODE_INFO: &[u8] = b"node:";
+/// Info of the expansion of the [`Psk`] of a node from the key of the node
+const PSK_INFO: &[u8] = b"psk";
+
+/// Hierarchy of [Pre-Shared Keys](`Psk`) derived from a single group master secret with HKDF
+/// (HMAC-SHA256), so that the keys of a fleet of devices do not have to be managed one by one.
+///
+/// Nodes are addressed by slash separated paths, like `fleet/line1/device42`. The key of a node is
+/// derived from the key of its parent, and the [`Psk`] of a node from the key of the node, so that:
+/// - the holder of the master secret derives the [`Psk`] of any device when addressing a keyload,
+///   while each device is provisioned with its own [`Psk`] only;
+/// - the [`PskTree`] of a subtree, returned by [`PskTree::subtree()`], can be delegated to a
+///   gateway managing that part of the fleet, without revealing the rest of the hierarchy.
+///
+/// Devices are revoked by excluding their path, or the path of one of their ancestors, from the
+/// [`Psk`]s addressed by the next keyloads (see [`PskTree::derive_all()`]).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PskTree([u8; 32]);
+
+impl PskTree {
+    /// Creates a new [`PskTree`] from a group master secret
+    ///
+    /// # Arguments
+    /// * `master_secret`: The secret shared by the administrators of the group
+    pub fn new<T>(master_secret: T) -> Self
+    where
+        T: AsRef<[u8]>,
+    {
+        // HKDF-Extract
+        let mut root = [0; 32];
+        HMAC_SHA256(master_secret.as_ref(), TREE_SALT, &mut root);
+        Self(root)
+    }
+
+    /// Returns the [`PskTree`] rooted at a node, deriving the keys of its descendants only
+    ///
+    /// # Arguments
+    /// * `path`: The slash separated path of the node, relative to the root of the tree
+    pub fn subtree(&self, path: &str) -> Self {
+        segments(path).fold(*self, |node, segment| node.child(segment))
+    }
+
+    /// Derives the [`Psk`] of a node
+    ///
+    /// # Arguments
+    /// * `path`: The slash separated path of the node, relative to the root of the tree
+    pub fn derive(&self, path: &str) -> Psk {
+        let mut psk = [0; 32];
+        expand(&self.subtree(path).0, &[PSK_INFO], &mut psk);
+        Psk::new(psk)
+    }
+
+    /// Derives the [`Psk`]s of several nodes, skipping the revoked nodes. A node is revoked if its
+    /// path or the path of one of its ancestors is among the revoked paths.
+    ///
+    /// # Arguments
+    /// * `paths`: The slash separated paths of the nodes
+    /// * `revoked`: The slash separated paths of the revoked nodes and subtrees
+    pub fn derive_all<'a, I>(&self, paths: I, revoked: &[&str]) -> Vec<Psk>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        paths
+            .into_iter()
+            .filter(|path| !Self::is_revoked(path, revoked))
+            .map(|path| self.derive(path))
+            .collect()
+    }
+
+    /// Returns true if a node is revoked: its path or the path of one of its ancestors is among the
+    /// revoked paths
+    ///
+    /// # Arguments
+    /// * `path`: The slash separated path of the node
+    /// * `revoked`: The slash separated paths of the revoked nodes and subtrees
+    pub fn is_revoked(path: &str, revoked: &[&str]) -> bool {
+        revoked.iter().any(|revoked| {
+            let mut path = segments(path);
+            segments(revoked).all(|segment| path.next() == Some(segment))
+        })
+    }
+
+    /// Derives the [`PskId`] of a node, to address keyloads or look the [`Psk`] up
+    ///
+    /// # Arguments
+    /// * `path`: The slash separated path of the node, relative to the root of the tree
+    pub fn derive_id(&self, path: &str) -> PskId {
+        self.derive(path).to_pskid()
+    }
+
+    /// Returns the [`PskTree`] of a child node
+    ///
+    /// # Arguments
+    /// * `segment`: The name of the child
+    fn child(&self, segment: &str) -> Self {
+        let mut key = [0; 32];
+        expand(&self.0, &[NODE_INFO, segment.as_bytes()], &mut key);
+        Self(key)
+    }
+}
+
+/// The key of a tree is never printed
+impl core::fmt::Debug for PskTree {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "PskTree")
+    }
+}
+
+/// HKDF-Expand of a single 32 byte block
+///
+/// # Arguments
+/// * `key`: The pseudo-random key
+/// * `info`: The parts of the context information, concatenated
+/// * `okm`: The output keying material
+fn expand(key: &[u8; 32], info: &[&[u8]], okm: &mut [u8; 32]) {
+    let mut data: Vec<u8> = info.concat();
+    data.push(1);
+    HMAC_SHA256(&data, key, okm);
+}
+
+/// Returns the non-empty segments of a slash separated path
+///
+/// # Arguments
+/// * `path`: The slash separated path
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
@@ -0,0 +1,133 @@
+// Rust
+use alloc::{vec, vec::Vec};
+
+// 3rd-party
+#[cfg(feature = "batch-verification")]
+use rand::{rngs::StdRng, SeedableRng};
+
+// IOTA
+use crypto::signatures::ed25519;
+
+// Streams
+
+// Local
+
+/// An Ed25519 signature waiting for verification
+struct PendingSignature {
+    /// The public key of the signer
+    public_key: ed25519::PublicKey,
+    /// The hash of the spongos state that was signed
+    hash: [u8; 64],
+    /// The signature of the hash
+    signature: [u8; ed25519::SIGNATURE_LENGTH],
+}
+
+impl PendingSignature {
+    /// Verifies the signature on its own
+    fn verify(&self) -> bool {
+        self.public_key
+            .verify(&ed25519::Signature::from_bytes(self.signature), &self.hash)
+    }
+}
+
+/// Ed25519 signatures collected to be verified at once. With the `batch-verification` feature, the
+/// signatures are verified in a single batch, much cheaper than verifying each of them, and one
+/// by one only if the batch fails, to identify the invalid ones. Without it, they are verified one
+/// by one.
+#[derive(Default)]
+pub struct SignatureBatch {
+    /// The signatures, in the order they were pushed
+    signatures: Vec<PendingSignature>,
+}
+
+impl SignatureBatch {
+    /// Creates an empty [`SignatureBatch`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a signature to the batch
+    ///
+    /// # Arguments
+    /// * `public_key`: The public key of the signer
+    /// * `hash`: The hash of the spongos state that was signed
+    /// * `signature`: The signature of the hash
+    pub fn push(&mut self, public_key: ed25519::PublicKey, hash: [u8; 64], signature: [u8; ed25519::SIGNATURE_LENGTH]) {
+        self.signatures.push(PendingSignature {
+            public_key,
+            hash,
+            signature,
+        });
+    }
+
+    /// Returns the number of signatures in the batch
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Returns true if there are no signatures in the batch
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Verifies the signatures of the batch, returning whether each of them is valid, in the order
+    /// they were pushed
+    pub fn verify(&self) -> Vec<bool> {
+        if self.verify_at_once() {
+            return vec![true; self.signatures.len()];
+        }
+        self.signatures.iter().map(PendingSignature::verify).collect()
+    }
+
+    /// Verifies all the signatures of the batch together, returning true if all of them are valid
+    #[cfg(feature = "batch-verification")]
+    fn verify_at_once(&self) -> bool {
+        use ed25519_zebra::{batch, Signature, VerificationKeyBytes};
+
+        let mut verifier = batch::Verifier::new();
+        for pending in &self.signatures {
+            verifier.queue((
+                VerificationKeyBytes::from(pending.public_key.to_bytes()),
+                Signature::from(pending.signature),
+                &pending.hash,
+            ));
+        }
+        verifier.verify(StdRng::from_entropy()).is_ok()
+    }
+
+    /// Signatures are only verified one by one without the `batch-verification` feature
+    #[cfg(not(feature = "batch-verification"))]
+    fn verify_at_once(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::Ed25519;
+
+    #[test]
+    fn a_forged_signature_only_invalidates_itself() {
+        let mut batch = SignatureBatch::new();
+        for (index, seed) in ["alice", "bob", "charlie"].iter().enumerate() {
+            let secret_key = Ed25519::from_seed(seed);
+            let hash = [index as u8; 64];
+            let mut signature = secret_key.inner().sign(&hash).to_bytes();
+            if index == 1 {
+                signature[0] ^= 1;
+            }
+            batch.push(secret_key.inner().public_key(), hash, signature);
+        }
+        assert_eq!(batch.verify(), [true, false, true]);
+
+        // A valid signature of another hash is as invalid as a forged one
+        let mut batch = SignatureBatch::new();
+        let secret_key = Ed25519::from_seed("alice");
+        let signature = secret_key.inner().sign(&[0; 64]).to_bytes();
+        batch.push(secret_key.inner().public_key(), [0; 64], signature);
+        batch.push(secret_key.inner().public_key(), [1; 64], signature);
+        batch.push(Ed25519::from_seed("bob").inner().public_key(), [0; 64], signature);
+        assert_eq!(batch.verify(), [true, false, false]);
+    }
+}
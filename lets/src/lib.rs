@@ -34,3 +34,6 @@ pub mod id;
 
 /// Errors specific for LETS
 pub mod error;
+
+/// Thread-safety markers and primitives
+pub mod sync;
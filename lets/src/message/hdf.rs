@@ -21,6 +21,7 @@ use crate::{
         topic::{Topic, TopicHash},
         version::{HDF_ID, STREAMS_VER, UTF8},
     },
+    sync::MaybeSend,
 };
 
 /// [`Mac`] for content verification
@@ -36,7 +37,8 @@ pub struct HDF {
     /// Streams version
     pub version: u8,
     /// Message type identifier
-    // content type is 4 bits
+    // content type is 6 bits, the 2 high bits taking the place of the reserved bits of the 4 bits
+    // versions, so that the headers of the types below 16 are unchanged
     pub message_type: u8,
     /// Length of the payload of the message (can be set to 0)
     // payload length is 10 bits
@@ -83,8 +85,8 @@ impl HDF {
     /// * `topic`: Reference to branch [`Topic`]
     pub fn new(message_type: u8, sequence: usize, publisher: Identifier, topic: &Topic) -> Self {
         debug_assert!(
-            message_type >> 4 == 0,
-            "invalid content-type '{}': content-type value cannot be greater than 4 bits",
+            message_type >> 6 == 0,
+            "invalid content-type '{}': content-type value cannot be greater than 6 bits",
             message_type
         );
         Self {
@@ -160,7 +162,17 @@ impl HDF {
     }
 }
 
-#[async_trait(?Send)]
+/// Returns the bits of the first byte of the encoded [`HDF`] holding a message type: its 4 low bits
+/// followed by its 2 high bits
+///
+/// # Arguments
+/// * `message_type`: The message type identifier, at most 6 bits long
+fn message_type_bits(message_type: u8) -> u8 {
+    ((message_type & 0b1111) << 4) | ((message_type >> 4 & 0b11) << 2)
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl ContentSizeof<HDF> for sizeof::Context {
     async fn sizeof(&mut self, hdf: &HDF) -> SpongosResult<&mut Self> {
         let message_type_and_payload_length = NBytes::<[u8; 2]>::default();
@@ -168,7 +180,7 @@ impl ContentSizeof<HDF> for sizeof::Context {
         self.absorb(Uint8::new(hdf.encoding))?
             .absorb(Uint8::new(hdf.version))?
             .skip(message_type_and_payload_length)?
-            .absorb(External::new(Uint8::new(hdf.message_type << 4)))?
+            .absorb(External::new(Uint8::new(message_type_bits(hdf.message_type))))?
             .absorb(Uint8::new(hdf.frame_type))?
             .skip(payload_frame_count)?
             .absorb(Maybe::new(hdf.linked_msg_address.as_ref()))?
@@ -182,16 +194,17 @@ impl ContentSizeof<HDF> for sizeof::Context {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<F, OS> ContentWrap<HDF> for wrap::Context<OS, F>
 where
-    F: PRP,
-    OS: io::OStream,
+    F: PRP + MaybeSend,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, hdf: &mut HDF) -> SpongosResult<&mut Self> {
         let message_type_and_payload_length = {
             let mut nbytes = NBytes::<[u8; 2]>::default();
-            nbytes[0] = (hdf.message_type << 4) | ((hdf.payload_length >> 8) as u8 & 0b0011);
+            nbytes[0] = message_type_bits(hdf.message_type) | ((hdf.payload_length >> 8) as u8 & 0b0011);
             nbytes[1] = hdf.payload_length as u8;
             nbytes
         };
@@ -207,7 +220,7 @@ where
         self.absorb(Uint8::new(hdf.encoding))?
             .absorb(Uint8::new(hdf.version))?
             .skip(message_type_and_payload_length)?
-            .absorb(External::new(Uint8::new(hdf.message_type << 4)))?
+            .absorb(External::new(Uint8::new(message_type_bits(hdf.message_type))))?
             .absorb(Uint8::new(hdf.frame_type))?
             .skip(payload_frame_count)?
             .absorb(Maybe::new(hdf.linked_msg_address.as_ref()))?
@@ -221,17 +234,18 @@ where
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<F, IS> ContentUnwrap<HDF> for unwrap::Context<IS, F>
 where
-    F: PRP,
-    IS: io::IStream,
+    F: PRP + MaybeSend,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, mut hdf: &mut HDF) -> SpongosResult<&mut Self> {
         let mut encoding = Uint8::default();
         let mut version = Uint8::default();
-        // [message_type x 4][reserved x 2][payload_length x 2]
-        // [payload_length x 8 -------------------------------]
+        // [message_type low x 4][message_type high x 2][payload_length x 2]
+        // [payload_length x 8 ---------------------------------------------]
         let mut message_type_and_payload_length = NBytes::<[u8; 2]>::default();
         let mut frame_type = Uint8::default();
         let mut payload_frame_count_bytes = NBytes::<[u8; 3]>::default();
@@ -244,13 +258,9 @@ where
                 SpongosError::Version("Msg", version.inner()),
             )?
             .skip(message_type_and_payload_length.as_mut())?
-            .guard(
-                0 == message_type_and_payload_length[0] & 0b1100,
-                SpongosError::Reserved("bits 5 and 6 between content-type and payload-length"),
-            )?
             .absorb(External::new(Uint8::new(
                 // Absorb only message_type
-                message_type_and_payload_length[0] & 0b11110000,
+                message_type_and_payload_length[0] & 0b11111100,
             )))?
             .absorb(&mut frame_type)?
             .guard(
@@ -271,7 +281,8 @@ where
 
         hdf.encoding = encoding.inner();
         hdf.version = version.inner();
-        hdf.message_type = message_type_and_payload_length[0] >> 4;
+        hdf.message_type =
+            (message_type_and_payload_length[0] >> 4) | ((message_type_and_payload_length[0] & 0b1100) << 2);
         hdf.payload_length =
             (((message_type_and_payload_length[0] & 0b0011) as u16) << 8) | (message_type_and_payload_length[1] as u16);
         hdf.frame_type = frame_type.inner();
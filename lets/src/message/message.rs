@@ -1,10 +1,15 @@
 // Rust
+use alloc::vec::Vec;
 
 // IOTA
 
 // Streams
 use spongos::{
-    ddml::commands::{sizeof, wrap, Commit},
+    ddml::{
+        commands::{sizeof, wrap, Commit},
+        layout::{LayoutDescriptor, LayoutStream},
+        pool::BufferPool,
+    },
     Spongos, PRP,
 };
 
@@ -79,18 +84,95 @@ impl<Payload> Message<Payload> {
 
     /// Encodes the message for transport, wrapping the [`HDF`] and [`PCF`] into one binary message,
     /// returning that [`TransportMessage`] and the context [`Spongos`] state.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            skip_all,
+            fields(
+                message_type = self.header.message_type(),
+                topic = %self.header.topic_hash(),
+                sequence = self.header.sequence(),
+            )
+        )
+    )]
     pub async fn wrap<F>(&mut self) -> Result<(TransportMessage, Spongos<F>)>
     where
         F: PRP + Default,
         for<'b> wrap::Context<&'b mut [u8], F>: ContentWrap<HDF> + ContentWrap<PCF<Payload>>,
         sizeof::Context: ContentSizeof<HDF> + ContentSizeof<PCF<Payload>>,
+    {
+        let buf_size = self.sizeof().await?;
+        self.wrap_into(vec![0; buf_size]).await
+    }
+
+    /// Same as [`Message::wrap()`], encoding the message into a buffer taken from a
+    /// [`BufferPool`]. The buffer can be returned to the pool once the [`TransportMessage`] is no
+    /// longer needed.
+    ///
+    /// # Arguments
+    /// * `pool`: The [`BufferPool`] to take the buffer from
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            skip_all,
+            fields(
+                message_type = self.header.message_type(),
+                topic = %self.header.topic_hash(),
+                sequence = self.header.sequence(),
+            )
+        )
+    )]
+    pub async fn wrap_with_pool<F>(&mut self, pool: &mut BufferPool) -> Result<(TransportMessage, Spongos<F>)>
+    where
+        F: PRP + Default,
+        for<'b> wrap::Context<&'b mut [u8], F>: ContentWrap<HDF> + ContentWrap<PCF<Payload>>,
+        sizeof::Context: ContentSizeof<HDF> + ContentSizeof<PCF<Payload>>,
+    {
+        let buf_size = self.sizeof().await?;
+        self.wrap_into(pool.take(buf_size)).await
+    }
+
+    /// Describes the byte layout of the message, wrapping it into a [`LayoutStream`] with a
+    /// `header` and a `payload` section. Messages of the same type share the layout of their
+    /// fixed size fields.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the message type
+    pub async fn layout(&mut self, name: &str) -> Result<LayoutDescriptor>
+    where
+        for<'b> wrap::Context<&'b mut LayoutStream>: ContentWrap<HDF> + ContentWrap<PCF<Payload>>,
+    {
+        let mut stream = LayoutStream::new(name);
+        stream.section("header");
+        let mut ctx: wrap::Context<&mut LayoutStream> = wrap::Context::new(&mut stream);
+        ctx.wrap(&mut self.header).await?.commit()?;
+        stream.section("payload");
+        // The spongos state carried over from the header does not change the layout of the payload
+        let mut ctx: wrap::Context<&mut LayoutStream> = wrap::Context::new(&mut stream);
+        ctx.wrap(&mut self.payload).await?;
+        Ok(stream.into_descriptor())
+    }
+
+    /// Returns the size of the binary message
+    async fn sizeof(&self) -> Result<usize>
+    where
+        sizeof::Context: ContentSizeof<HDF> + ContentSizeof<PCF<Payload>>,
     {
         let mut ctx = sizeof::Context::new();
         ctx.sizeof(&self.header).await?.commit()?.sizeof(&self.payload).await?;
-        let buf_size = ctx.finalize();
-
-        let mut buf = vec![0; buf_size];
+        Ok(ctx.finalize())
+    }
 
+    /// Wraps the message into a buffer of the size of the binary message
+    ///
+    /// # Arguments
+    /// * `buf`: The zeroed buffer the message is encoded into
+    async fn wrap_into<F>(&mut self, mut buf: Vec<u8>) -> Result<(TransportMessage, Spongos<F>)>
+    where
+        F: PRP + Default,
+        for<'b> wrap::Context<&'b mut [u8], F>: ContentWrap<HDF> + ContentWrap<PCF<Payload>>,
+    {
+        let buf_size = buf.len();
         let mut ctx = wrap::Context::new(&mut buf[..]);
         ctx.wrap(&mut self.header)
             .await?
@@ -24,5 +24,5 @@ pub use hdf::HDF;
 pub use message::Message;
 pub use pcf::PCF;
 pub use preparsed::PreparsedMessage;
-pub use topic::{Topic, TopicHash};
-pub use transport::TransportMessage;
+pub use topic::{Topic, TopicHash, TOPIC_SEPARATOR};
+pub use transport::{content_digest, TransportMessage, MESSAGE_DIGEST_SIZE};
@@ -19,6 +19,7 @@ use crate::{
         content::{ContentSizeof, ContentUnwrap, ContentWrap},
         version::{FINAL_PCF_ID, INIT_PCF_ID, INTER_PCF_ID},
     },
+    sync::{MaybeSend, MaybeSync},
 };
 
 /// Payload Carrying Frame. Contains the body of a Streams message
@@ -133,9 +134,11 @@ impl<Content> PCF<Content> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<Content> ContentSizeof<PCF<Content>> for sizeof::Context
 where
+    Content: MaybeSync,
     sizeof::Context: ContentSizeof<Content>,
 {
     async fn sizeof(&mut self, pcf: &PCF<Content>) -> SpongosResult<&mut Self> {
@@ -147,11 +150,13 @@ where
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<F, OS, Content> ContentWrap<PCF<Content>> for wrap::Context<OS, F>
 where
-    F: PRP,
-    OS: io::OStream,
+    F: PRP + MaybeSend,
+    OS: io::OStream + MaybeSend,
+    Content: MaybeSend,
     Self: ContentWrap<Content>,
 {
     async fn wrap(&mut self, pcf: &mut PCF<Content>) -> SpongosResult<&mut Self>
@@ -166,11 +171,13 @@ where
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<F, IS, Content> ContentUnwrap<PCF<Content>> for unwrap::Context<IS, F>
 where
-    F: PRP,
-    IS: io::IStream,
+    F: PRP + MaybeSend,
+    IS: io::IStream + MaybeSend,
+    Content: MaybeSend,
     unwrap::Context<IS, F>: ContentUnwrap<Content>,
 {
     async fn unwrap(&mut self, pcf: &mut PCF<Content>) -> SpongosResult<&mut Self> {
@@ -13,7 +13,7 @@ use crate::{
 };
 
 /// Message context preparsed for unwrapping.
-#[derive(Clone, PartialEq, Eq, Hash, Default)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct PreparsedMessage<F = KeccakF1600> {
     /// The message bytes wrapper
     transport_msg: TransportMessage,
@@ -24,6 +24,20 @@ pub struct PreparsedMessage<F = KeccakF1600> {
     /// Streaming position within `Context`, marking the end of the `HDF` and beginning of the
     /// `PCF`. Used in partial processing.
     cursor: usize,
+    /// Bound on the lengths and item counts read while unwrapping the content
+    size_limit: usize,
+}
+
+impl<F: Default> Default for PreparsedMessage<F> {
+    fn default() -> Self {
+        Self {
+            transport_msg: TransportMessage::default(),
+            header: HDF::default(),
+            spongos: Spongos::default(),
+            cursor: 0,
+            size_limit: unwrap::DEFAULT_SIZE_LIMIT,
+        }
+    }
 }
 
 impl<F> PreparsedMessage<F> {
@@ -35,12 +49,20 @@ impl<F> PreparsedMessage<F> {
     /// * `header`: The `HDF` parsed from the transport message
     /// * `spongos`: The `Context` state following the `HDF` parsing
     /// * `cursor`: The read position of the `Context` stream following the `HDF` parsing
-    pub(crate) fn new(transport_msg: TransportMessage, header: HDF, spongos: Spongos<F>, cursor: usize) -> Self {
+    /// * `size_limit`: The bound on the lengths and item counts read while unwrapping the content
+    pub(crate) fn new(
+        transport_msg: TransportMessage,
+        header: HDF,
+        spongos: Spongos<F>,
+        cursor: usize,
+        size_limit: usize,
+    ) -> Self {
         Self {
             transport_msg,
             header,
             spongos,
             cursor,
+            size_limit,
         }
     }
 
@@ -76,6 +98,17 @@ impl<F> PreparsedMessage<F> {
     ///
     /// # Arguments
     /// * `content` - An implementation of a [`PCF`] [`unwrap::Context`]
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            skip_all,
+            fields(
+                message_type = self.header.message_type(),
+                topic = %self.header.topic_hash(),
+                sequence = self.header.sequence(),
+            )
+        )
+    )]
     pub async fn unwrap<Content>(self, content: Content) -> Result<(Message<Content>, Spongos<F>)>
     where
         for<'a> unwrap::Context<&'a [u8], F>: ContentUnwrap<PCF<Content>>,
@@ -85,7 +118,8 @@ impl<F> PreparsedMessage<F> {
         let spongos = self.spongos;
         let transport_msg = self.transport_msg;
         // Cannot use Self::remaining_message() due to partial move of spongos
-        let mut ctx = unwrap::Context::new_with_spongos(&transport_msg.body()[self.cursor..], spongos);
+        let mut ctx = unwrap::Context::new_with_spongos(&transport_msg.body()[self.cursor..], spongos)
+            .with_size_limit(self.size_limit);
         ctx.unwrap(&mut pcf).await?;
         // discard `self.ctx.stream` that should be empty
         let (spongos, _) = ctx.finalize();
@@ -95,11 +129,13 @@ impl<F> PreparsedMessage<F> {
 
 impl<F> fmt::Debug for PreparsedMessage<F> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Messages read from the network may be shorter than the excerpt
+        let remaining_message = self.remaining_message();
         write!(
             f,
             "{{header: {:?}, ctx: {:?}}}",
             self.header,
-            &self.remaining_message()[..10]
+            &remaining_message[..remaining_message.len().min(10)]
         )
     }
 }
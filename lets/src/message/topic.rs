@@ -19,7 +19,14 @@ use spongos::{
 
 use crate::error::Result;
 
+/// Separator of the segments of a hierarchical [`Topic`], such as `factory/line1/temp`
+pub const TOPIC_SEPARATOR: char = '/';
+
 /// A wrapper around a `String` used for identifying a branch within a `Stream`
+///
+/// Topics can be organised in a hierarchy by separating their segments with
+/// [`TOPIC_SEPARATOR`], so that the branches of a hierarchy can be addressed by their common
+/// prefix (see [`Topic::has_prefix()`]).
 #[derive(Clone, PartialEq, Eq, Debug, Default, Hash, serde::Serialize)]
 pub struct Topic(String);
 
@@ -36,6 +43,34 @@ impl Topic {
     pub fn str(&self) -> &str {
         &self.0
     }
+
+    /// Returns an iterator over the segments of the [`Topic`], separated by [`TOPIC_SEPARATOR`]
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split(TOPIC_SEPARATOR)
+    }
+
+    /// Returns the [`Topic`] one level up in the hierarchy, if any
+    pub fn parent(&self) -> Option<Topic> {
+        self.0
+            .rsplit_once(TOPIC_SEPARATOR)
+            .map(|(parent, _)| Topic::from(parent))
+    }
+
+    /// Returns true if the [`Topic`] is the prefix or one of its descendants in the hierarchy.
+    /// Prefixes are matched segment by segment: `factory/line1` is a prefix of `factory/line1` and
+    /// `factory/line1/temp`, but not of `factory/line10`. The empty prefix matches every topic.
+    ///
+    /// # Arguments
+    /// * `prefix`: The [`Topic`] prefix to match against
+    pub fn has_prefix(&self, prefix: &Topic) -> bool {
+        if prefix.0.is_empty() {
+            return true;
+        }
+        match self.0.strip_prefix(prefix.str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with(TOPIC_SEPARATOR),
+            None => false,
+        }
+    }
 }
 
 impl From<&str> for Topic {
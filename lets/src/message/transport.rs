@@ -2,6 +2,7 @@
 use alloc::vec::Vec;
 
 // IOTA
+use crypto::hashes::{blake2b::Blake2b256, Digest};
 
 // Streams
 use spongos::{ddml::commands::unwrap, PRP};
@@ -12,6 +13,9 @@ use crate::{
     message::{content::ContentUnwrap, hdf::HDF, preparsed::PreparsedMessage},
 };
 
+/// Size of the content digest of a message
+pub const MESSAGE_DIGEST_SIZE: usize = 32;
+
 /// Binary network Message representation.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct TransportMessage(Vec<u8>);
@@ -34,6 +38,13 @@ impl TransportMessage {
     pub(crate) fn into_body(self) -> Vec<u8> {
         self.0
     }
+
+    /// Returns the content digest of the message, the `Blake2b256` hash of its wrapped bytes. The
+    /// digest only depends on the bytes of the message, so that it is the same for its sender and
+    /// all of its readers.
+    pub fn digest(&self) -> [u8; MESSAGE_DIGEST_SIZE] {
+        content_digest(self.body())
+    }
 }
 
 impl TransportMessage {
@@ -44,17 +55,39 @@ impl TransportMessage {
     where
         F: PRP + Default,
     {
-        let mut ctx = unwrap::Context::new(self.body().as_ref());
+        self.parse_header_with_size_limit(unwrap::DEFAULT_SIZE_LIMIT).await
+    }
+
+    /// Same as [`TransportMessage::parse_header()`], bounding the lengths and item counts read from
+    /// the message, both in the [`HDF`] and later on in the content of the [`PreparsedMessage`].
+    ///
+    /// # Arguments
+    /// * `size_limit`: The largest length or item count accepted
+    pub async fn parse_header_with_size_limit<F>(self, size_limit: usize) -> Result<PreparsedMessage<F>>
+    where
+        F: PRP + Default,
+    {
+        let mut ctx = unwrap::Context::new(self.body().as_ref()).with_size_limit(size_limit);
         let mut header = HDF::default();
 
         ctx.unwrap(&mut header).await?;
 
         let (spongos, cursor) = ctx.finalize();
 
-        Ok(PreparsedMessage::new(self, header, spongos, cursor))
+        Ok(PreparsedMessage::new(self, header, spongos, cursor, size_limit))
     }
 }
 
+/// Returns the content digest of the wrapped bytes of a message (see [`TransportMessage::digest()`])
+///
+/// # Arguments
+/// * `bytes`: The wrapped bytes of the message
+pub fn content_digest(bytes: &[u8]) -> [u8; MESSAGE_DIGEST_SIZE] {
+    let mut digest = [0; MESSAGE_DIGEST_SIZE];
+    digest.copy_from_slice(&Blake2b256::digest(bytes));
+    digest
+}
+
 impl From<TransportMessage> for Vec<u8> {
     fn from(message: TransportMessage) -> Self {
         message.into_body()
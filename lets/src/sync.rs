@@ -0,0 +1,30 @@
+//! Marker traits and primitives used to make the protocol futures `Send` when the `threadsafe`
+//! feature is enabled. Without the feature, [`MaybeSend`] and [`MaybeSync`] are implemented for
+//! every type, so that bounds on them are a no-op in single-threaded (and wasm) environments.
+
+#[cfg(feature = "threadsafe")]
+pub use futures::lock::{Mutex, MutexGuard};
+
+/// Alias of [`Send`] when the `threadsafe` feature is enabled, implemented for every type otherwise
+#[cfg(feature = "threadsafe")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "threadsafe")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// Alias of [`Send`] when the `threadsafe` feature is enabled, implemented for every type otherwise
+#[cfg(not(feature = "threadsafe"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "threadsafe"))]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// Alias of [`Sync`] when the `threadsafe` feature is enabled, implemented for every type otherwise
+#[cfg(feature = "threadsafe")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "threadsafe")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+/// Alias of [`Sync`] when the `threadsafe` feature is enabled, implemented for every type otherwise
+#[cfg(not(feature = "threadsafe"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "threadsafe"))]
+impl<T: ?Sized> MaybeSync for T {}
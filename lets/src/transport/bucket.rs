@@ -1,5 +1,6 @@
 // Rust
 use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::time::Duration;
 
 // 3rd-party
 use async_trait::async_trait;
@@ -12,17 +13,32 @@ use async_trait::async_trait;
 use crate::{
     address::Address,
     error::{Error, Result},
-    message::TransportMessage,
-    transport::Transport,
+    message::{content_digest, TransportMessage, MESSAGE_DIGEST_SIZE},
+    sync::MaybeSend,
+    transport::{Transport, TransportCapabilities},
 };
 
 /// [`BTreeMap`] wrapper client for testing purposes
+///
+/// A [`Latency`] model delays the messages sent through the client: a message only becomes
+/// receivable once the clock of the client has gone past the time it was sent at plus the latency
+/// drawn for it. The clock is virtual, advanced explicitly with [`Client::advance()`], so that
+/// tests can assert the behavior of the protocol under slow links deterministically, without real
+/// sleeps. With the `tokio-clock` feature, the client can follow the clock of `tokio` instead,
+/// which paused tests advance with `tokio::time::advance()`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Client<Msg = TransportMessage> {
-    /// Mapping of stored [Addresses](`Address`) and `Messages`
+    /// Mapping of stored [Addresses](`Address`) and `Messages`, with the time each message becomes
+    /// receivable at
     // Use BTreeMap instead of HashMap to make BucketTransport nostd without pulling hashbrown
     // (this transport is for hacking purposes only, performance is no concern)
-    bucket: BTreeMap<Address, Vec<Msg>>,
+    bucket: BTreeMap<Address, Vec<(Duration, Msg)>>,
+    /// The [`Latency`] model of the messages sent. None if messages are receivable immediately.
+    latency: Option<Latency>,
+    /// The clock of the client
+    clock: Clock,
+    /// Number of messages sent, drawing the latency of the next one
+    sent: u64,
 }
 
 impl<Msg> Client<Msg> {
@@ -30,6 +46,88 @@ impl<Msg> Client<Msg> {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Delays the messages sent by the client with a [`Latency`] model
+    ///
+    /// # Arguments
+    /// * `latency`: The [`Latency`] model of the messages sent
+    pub fn with_latency(mut self, latency: Latency) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Sets the [`Latency`] model of the messages sent from now on. Messages already sent keep the
+    /// latency they were sent with. None makes the messages receivable immediately.
+    ///
+    /// # Arguments
+    /// * `latency`: The [`Latency`] model of the messages sent
+    pub fn set_latency(&mut self, latency: Option<Latency>) {
+        self.latency = latency;
+    }
+
+    /// Returns the [`Latency`] model of the messages sent, if any
+    pub fn latency(&self) -> Option<Latency> {
+        self.latency
+    }
+
+    /// Makes the client follow the clock of `tokio` instead of its virtual clock, so that tests
+    /// running with a paused clock advance it with `tokio::time::advance()`, or by sleeping
+    #[cfg(feature = "tokio-clock")]
+    pub fn with_tokio_clock(mut self) -> Self {
+        self.clock = Clock::Tokio(tokio::time::Instant::now());
+        self
+    }
+
+    /// Returns the time elapsed on the clock of the client since it was created
+    pub fn now(&self) -> Duration {
+        match self.clock {
+            Clock::Virtual(now) => now,
+            #[cfg(feature = "tokio-clock")]
+            Clock::Tokio(origin) => origin.elapsed(),
+        }
+    }
+
+    /// Advances the virtual clock of the client, making receivable the messages whose latency
+    /// elapses. Clients following the clock of `tokio` are advanced through `tokio` instead.
+    ///
+    /// # Arguments
+    /// * `duration`: The time to advance the clock by
+    pub fn advance(&mut self, duration: Duration) {
+        if let Clock::Virtual(now) = &mut self.clock {
+            *now += duration;
+        }
+    }
+
+    /// Returns the number of messages sent whose latency has not elapsed yet
+    pub fn in_flight(&self) -> usize {
+        let now = self.now();
+        self.bucket
+            .values()
+            .flatten()
+            .filter(|(receivable_at, _)| *receivable_at > now)
+            .count()
+    }
+
+    /// Returns the messages stored at an address that are receivable at the current time, or an
+    /// error if there are none
+    ///
+    /// # Arguments
+    /// * `address`: The address to retrieve messages from.
+    fn receivable(&self, address: Address) -> Result<impl Iterator<Item = &Msg>> {
+        let now = self.now();
+        let mut msgs = self
+            .bucket
+            .get(&address)
+            .into_iter()
+            .flatten()
+            .filter(move |(receivable_at, _)| *receivable_at <= now)
+            .map(|(_, msg)| msg)
+            .peekable();
+        match msgs.peek() {
+            Some(_) => Ok(msgs),
+            None => Err(Error::MessageNotFound(address)),
+        }
+    }
 }
 
 impl<Msg> Default for Client<Msg> {
@@ -37,14 +135,100 @@ impl<Msg> Default for Client<Msg> {
     fn default() -> Self {
         Self {
             bucket: BTreeMap::default(),
+            latency: None,
+            clock: Clock::Virtual(Duration::ZERO),
+            sent: 0,
         }
     }
 }
 
-#[async_trait(?Send)]
+/// Clock of a bucket [`Client`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Clock {
+    /// Virtual clock, holding the time elapsed since the client was created
+    Virtual(Duration),
+    /// Clock of `tokio`, holding the instant the client started following it at
+    #[cfg(feature = "tokio-clock")]
+    Tokio(tokio::time::Instant),
+}
+
+/// Latency model of the messages sent through a bucket [`Client`]
+///
+/// Each message is delayed by the base latency plus a jitter drawn uniformly between zero and the
+/// maximum jitter. Jitters are drawn from a seeded pseudo-random sequence, so that the same
+/// messages sent in the same order are delayed the same way on every run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Latency {
+    /// Latency of every message
+    base: Duration,
+    /// Maximum jitter added to the base latency
+    jitter: Duration,
+    /// Seed of the sequence the jitters are drawn from
+    seed: u64,
+}
+
+impl Latency {
+    /// Creates a [`Latency`] model delaying every message by the same latency
+    ///
+    /// # Arguments
+    /// * `base`: The latency of every message
+    pub fn fixed(base: Duration) -> Self {
+        Self::new(base, Duration::ZERO)
+    }
+
+    /// Creates a [`Latency`] model delaying every message by a base latency plus a jitter
+    ///
+    /// # Arguments
+    /// * `base`: The latency of every message
+    /// * `jitter`: The maximum jitter added to the base latency
+    pub fn new(base: Duration, jitter: Duration) -> Self {
+        Self { base, jitter, seed: 0 }
+    }
+
+    /// Sets the seed of the sequence the jitters are drawn from
+    ///
+    /// # Arguments
+    /// * `seed`: The seed of the sequence
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the latency of every message
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    /// Returns the maximum jitter added to the base latency
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Returns the latency of the n-th message sent
+    ///
+    /// # Arguments
+    /// * `n`: The number of messages sent before the message
+    pub fn sample(&self, n: u64) -> Duration {
+        let jitter = self.jitter.as_nanos() as u64;
+        if jitter == 0 {
+            return self.base;
+        }
+        // SplitMix64 step, deterministic and good enough to spread the jitters
+        let mut z = self
+            .seed
+            .wrapping_add(n.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        self.base + Duration::from_nanos(z % (jitter + 1))
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<Msg> Transport<'_> for Client<Msg>
 where
-    Msg: Clone,
+    Msg: Clone + MaybeSend,
 {
     type Msg = Msg;
     type SendResponse = Msg;
@@ -61,12 +245,15 @@ where
     where
         Self::Msg: 'async_trait,
     {
-        self.bucket.entry(addr).or_default().push(msg.clone());
+        let latency = self.latency.map_or(Duration::ZERO, |latency| latency.sample(self.sent));
+        self.sent += 1;
+        let receivable_at = self.now() + latency;
+        self.bucket.entry(addr).or_default().push((receivable_at, msg.clone()));
         Ok(msg)
     }
 
     /// Returns a vector of messages from the bucket, or an error if the bucket doesn't contain the
-    /// address
+    /// address, or none of its messages is receivable yet
     ///
     /// # Arguments
     /// * `address`: The address to retrieve messages from.
@@ -74,9 +261,126 @@ where
     /// Returns:
     /// A vector of messages.
     async fn recv_messages(&mut self, address: Address) -> Result<Vec<Msg>> {
+        Ok(self.receivable(address)?.cloned().collect())
+    }
+
+    /// Returns a page of the messages from the bucket, or an error if the bucket doesn't contain
+    /// the address, or none of its messages is receivable yet
+    ///
+    /// # Arguments
+    /// * `address`: The address to retrieve messages from.
+    /// * `offset`: The number of messages to skip.
+    /// * `limit`: The maximum number of messages to return.
+    async fn recv_messages_paged(&mut self, address: Address, offset: usize, limit: usize) -> Result<Vec<Msg>> {
+        Ok(self.receivable(address)?.skip(offset).take(limit).cloned().collect())
+    }
+
+    /// Returns the first receivable message of the bucket whose content digest matches, together
+    /// with its address, or an error if there is none
+    ///
+    /// # Arguments
+    /// * `digest`: The content digest of the message
+    async fn recv_message_by_digest(&mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Result<(Address, Msg)>
+    where
+        Msg: AsRef<[u8]>,
+    {
+        let now = self.now();
         self.bucket
-            .get(&address)
-            .cloned()
-            .ok_or(Error::AddressError("No message found", address))
+            .iter()
+            .flat_map(|(address, msgs)| msgs.iter().map(move |msg| (address, msg)))
+            .find(|(_, (receivable_at, msg))| *receivable_at <= now && content_digest(msg.as_ref()) == digest)
+            .map(|(address, (_, msg))| (*address, msg.clone()))
+            .ok_or_else(|| Error::DigestNotFound(hex::encode(digest)))
+    }
+
+    /// The bucket accepts messages of any size without proof of work, and only keeps them in
+    /// memory.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        TransportCapabilities {
+            max_message_size: None,
+            supports_push: false,
+            persists_messages: false,
+            requires_pow: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        address::{Address, AppAddr, MsgId},
+        message::TransportMessage,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn messages_are_receivable_once_their_latency_elapses() -> Result<()> {
+        let latency = Latency::new(Duration::from_millis(100), Duration::from_millis(50)).with_seed(7);
+        let mut client = Client::new().with_latency(latency);
+        let first = Address::new(AppAddr::default(), MsgId::from([1; 12]));
+        let second = Address::new(AppAddr::default(), MsgId::from([2; 12]));
+        client.send_message(first, TransportMessage::new(vec![1])).await?;
+        client.advance(Duration::from_millis(40));
+        client.send_message(second, TransportMessage::new(vec![2])).await?;
+        assert_eq!(client.in_flight(), 2);
+        assert!(matches!(
+            client.recv_message(first).await,
+            Err(Error::MessageNotFound(_))
+        ));
+
+        // The first message is always receivable after the maximum latency, the second after 40ms more
+        client.advance(Duration::from_millis(110));
+        assert_eq!(client.recv_message(first).await?, TransportMessage::new(vec![1]));
+        client.advance(Duration::from_millis(40));
+        assert_eq!(client.recv_message(second).await?, TransportMessage::new(vec![2]));
+        assert_eq!(client.in_flight(), 0);
+        assert_eq!(client.now(), Duration::from_millis(190));
+
+        // The jitters are drawn within their bounds, the same way on every run
+        let samples: Vec<Duration> = (0..100).map(|n| latency.sample(n)).collect();
+        assert!(samples
+            .iter()
+            .all(|sample| (Duration::from_millis(100)..=Duration::from_millis(150)).contains(sample)));
+        assert!(samples.iter().any(|sample| *sample != samples[0]));
+        assert_eq!(samples, (0..100).map(|n| latency.sample(n)).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio-clock")]
+    #[tokio::test(start_paused = true)]
+    async fn latency_follows_the_paused_tokio_clock() -> Result<()> {
+        let mut client = Client::new()
+            .with_latency(Latency::fixed(Duration::from_secs(2)))
+            .with_tokio_clock();
+        let address = Address::new(AppAddr::default(), MsgId::default());
+        client.send_message(address, TransportMessage::new(vec![0])).await?;
+        assert!(client.recv_message(address).await.is_err());
+
+        // The paused clock is advanced instantly, without sleeping for real
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(client.recv_message(address).await?, TransportMessage::new(vec![0]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn recv_messages_paged() -> Result<()> {
+        let mut client = Client::new();
+        let address = Address::new(AppAddr::default(), MsgId::default());
+        for i in 0..5 {
+            client.send_message(address, TransportMessage::new(vec![i])).await?;
+        }
+
+        let page = client.recv_messages_paged(address, 1, 2).await?;
+        assert_eq!(
+            page,
+            vec![TransportMessage::new(vec![1]), TransportMessage::new(vec![2])]
+        );
+        assert!(client.recv_messages_paged(address, 5, 2).await?.is_empty());
+        assert!(matches!(
+            client.recv_message(address).await,
+            Err(Error::MultipleMessagesFound(_, 2))
+        ));
+        Ok(())
     }
 }
@@ -0,0 +1,421 @@
+//! Encoding of the messages between their wrapped form and the payload of the transport
+//!
+//! Some transports do not carry arbitrary bytes, like the text-only ones, and some benefit from
+//! compressing the messages. An encoding [`Client`] wraps another [`Transport`] and encodes every
+//! message it sends with its [`PayloadCodec`], recording the codec in a prefix byte. The messages
+//! received are decoded with the codec their prefix byte designates, so that readers only need to
+//! know the codecs the publishers may use, not which one each of them picked.
+
+// Rust
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::fmt::Debug;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+
+// Local
+use crate::{
+    address::Address,
+    error::{Error, Result},
+    message::TransportMessage,
+    sync::{MaybeSend, MaybeSync},
+    transport::{MirrorStatus, SendReceipt, Transport, TransportCapabilities},
+};
+
+/// Prefix byte of the [`IdentityCodec`]
+pub const IDENTITY_PREFIX: u8 = 0x00;
+/// Prefix byte of the [`ZstdCodec`]
+pub const ZSTD_PREFIX: u8 = 0x01;
+/// Prefix byte of the [`Base64Codec`], the `B` character, so that its payloads are plain text
+pub const BASE64_PREFIX: u8 = b'B';
+
+/// Encoding of the messages into the payloads of a transport, designated by a prefix byte
+pub trait PayloadCodec: Debug + MaybeSend + MaybeSync {
+    /// Returns the byte prefixed to the payloads encoded by the codec. Two codecs registered in the
+    /// same [`Client`] must have different prefixes.
+    fn prefix(&self) -> u8;
+
+    /// Encodes a message into a payload, without the prefix byte
+    ///
+    /// # Arguments
+    /// * `message`: The bytes of the message
+    fn encode(&self, message: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decodes a payload encoded by the codec, without the prefix byte, into a message
+    ///
+    /// # Arguments
+    /// * `payload`: The payload, without its prefix byte
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>>;
+
+    /// Returns the size of the largest message whose payload does not exceed a size, so that the
+    /// size limit of the transport can be enforced on the messages before they are encoded. Codecs
+    /// that never grow the messages return the size unchanged.
+    ///
+    /// # Arguments
+    /// * `max_payload_size`: The size of the largest payload, without the prefix byte
+    fn max_message_size(&self, max_payload_size: usize) -> usize {
+        max_payload_size
+    }
+}
+
+/// [`PayloadCodec`] sending the messages unchanged
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IdentityCodec;
+
+impl PayloadCodec for IdentityCodec {
+    fn prefix(&self) -> u8 {
+        IDENTITY_PREFIX
+    }
+
+    fn encode(&self, message: &[u8]) -> Result<Vec<u8>> {
+        Ok(message.to_vec())
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        Ok(payload.to_vec())
+    }
+}
+
+/// [`PayloadCodec`] encoding the messages in standard, padded base64, for transports that only
+/// carry text, like some HTTP gateways
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Base64Codec;
+
+impl PayloadCodec for Base64Codec {
+    fn prefix(&self) -> u8 {
+        BASE64_PREFIX
+    }
+
+    fn encode(&self, message: &[u8]) -> Result<Vec<u8>> {
+        Ok(base64::encode(message).into_bytes())
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        base64::decode(payload)
+            .map_err(|e| Error::Encoding("payload", "base64", Box::new(Error::External(anyhow::Error::msg(e)))))
+    }
+
+    /// Every 3 bytes of the message take 4 bytes of the payload
+    fn max_message_size(&self, max_payload_size: usize) -> usize {
+        max_payload_size / 4 * 3
+    }
+}
+
+/// [`PayloadCodec`] compressing the messages with `zstd`
+#[cfg(feature = "zstd-codec")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ZstdCodec {
+    /// The compression level
+    level: i32,
+    /// The size of the largest message a payload is decompressed into
+    max_decoded_size: usize,
+}
+
+#[cfg(feature = "zstd-codec")]
+impl ZstdCodec {
+    /// Size of the largest message a payload is decompressed into by default
+    pub const DEFAULT_MAX_DECODED_SIZE: usize = 1 << 20;
+
+    /// Creates a new [`ZstdCodec`] compressing the messages at the provided level, the default
+    /// level of `zstd` being `3`
+    ///
+    /// # Arguments
+    /// * `level`: The compression level, from `1` to `22`
+    pub fn new(level: i32) -> Self {
+        Self {
+            level,
+            max_decoded_size: Self::DEFAULT_MAX_DECODED_SIZE,
+        }
+    }
+
+    /// Sets the size of the largest message a payload is decompressed into, so that a small payload
+    /// cannot exhaust the memory of its readers. Payloads decompressing into larger messages fail
+    /// to decode.
+    ///
+    /// # Arguments
+    /// * `max_decoded_size`: The size of the largest decompressed message
+    pub fn with_max_decoded_size(mut self, max_decoded_size: usize) -> Self {
+        self.max_decoded_size = max_decoded_size;
+        self
+    }
+}
+
+#[cfg(feature = "zstd-codec")]
+impl Default for ZstdCodec {
+    fn default() -> Self {
+        Self::new(zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+}
+
+#[cfg(feature = "zstd-codec")]
+impl PayloadCodec for ZstdCodec {
+    fn prefix(&self) -> u8 {
+        ZSTD_PREFIX
+    }
+
+    fn encode(&self, message: &[u8]) -> Result<Vec<u8>> {
+        zstd::bulk::compress(message, self.level)
+            .map_err(|e| Error::Encoding("message", "zstd", Box::new(Error::External(anyhow::Error::msg(e)))))
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        zstd::bulk::decompress(payload, self.max_decoded_size)
+            .map_err(|e| Error::Encoding("payload", "zstd", Box::new(Error::External(anyhow::Error::msg(e)))))
+    }
+
+    /// Incompressible messages grow by the frame of `zstd`, a few bytes per block of 128KiB
+    fn max_message_size(&self, max_payload_size: usize) -> usize {
+        max_payload_size.saturating_sub(max_payload_size / 128 + 64)
+    }
+}
+
+/// [`Transport`] wrapper encoding the messages sent with a [`PayloadCodec`], recorded in a prefix
+/// byte, and decoding the messages received with the codec designated by their prefix byte. The
+/// [`IdentityCodec`], the [`Base64Codec`] and, with the `zstd-codec` feature, the [`ZstdCodec`]
+/// are always known to the client, other codecs are registered with [`Client::with_codec()`].
+///
+/// Messages that cannot be decoded, because their codec is unknown or their payload is corrupted,
+/// are left out of the messages received, like spam at their address would be.
+#[derive(Clone, Debug)]
+pub struct Client<T> {
+    /// The wrapped transport
+    transport: T,
+    /// The codec of the messages sent
+    codec: Arc<dyn PayloadCodec>,
+    /// The codecs known to decode the messages received, the codec of the messages sent included
+    codecs: Vec<Arc<dyn PayloadCodec>>,
+}
+
+impl<T> Client<T> {
+    /// Creates a new encoding [`Client`] wrapping a transport
+    ///
+    /// # Arguments
+    /// * `transport`: The wrapped transport
+    /// * `codec`: The [`PayloadCodec`] of the messages sent
+    pub fn new<C>(transport: T, codec: C) -> Self
+    where
+        C: PayloadCodec + 'static,
+    {
+        let codec: Arc<dyn PayloadCodec> = Arc::new(codec);
+        let mut codecs: Vec<Arc<dyn PayloadCodec>> = Vec::new();
+        codecs.push(Arc::new(IdentityCodec));
+        codecs.push(Arc::new(Base64Codec));
+        #[cfg(feature = "zstd-codec")]
+        codecs.push(Arc::new(ZstdCodec::default()));
+        // The codec of the messages sent supersedes the default codec with the same prefix
+        codecs.retain(|known| known.prefix() != codec.prefix());
+        codecs.push(codec.clone());
+        Self {
+            transport,
+            codec,
+            codecs,
+        }
+    }
+
+    /// Registers a [`PayloadCodec`] to decode the messages received with, superseding the known
+    /// codec with the same prefix, if any
+    ///
+    /// # Arguments
+    /// * `codec`: The [`PayloadCodec`] to register
+    pub fn with_codec<C>(mut self, codec: C) -> Self
+    where
+        C: PayloadCodec + 'static,
+    {
+        if codec.prefix() != self.codec.prefix() {
+            self.codecs.retain(|known| known.prefix() != codec.prefix());
+            self.codecs.push(Arc::new(codec));
+        }
+        self
+    }
+
+    /// Returns the [`PayloadCodec`] of the messages sent
+    pub fn codec(&self) -> &dyn PayloadCodec {
+        self.codec.as_ref()
+    }
+
+    /// Returns a reference to the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns a mutable reference to the wrapped transport
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Consumes the [`Client`], returning the wrapped transport
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Encodes a message with the codec of the messages sent, prefixed with its prefix byte
+    ///
+    /// # Arguments
+    /// * `message`: The message to encode
+    pub fn encode(&self, message: &TransportMessage) -> Result<TransportMessage> {
+        let mut payload = Vec::with_capacity(message.body().len() + 1);
+        payload.push(self.codec.prefix());
+        payload.extend(self.codec.encode(message.body())?);
+        Ok(TransportMessage::new(payload))
+    }
+
+    /// Decodes a payload with the codec designated by its prefix byte
+    ///
+    /// # Arguments
+    /// * `payload`: The payload to decode
+    pub fn decode(&self, payload: &TransportMessage) -> Result<TransportMessage> {
+        let (prefix, encoded) = payload
+            .body()
+            .split_first()
+            .ok_or_else(|| Error::Malformed("encoded payload", "prefix byte", String::from("an empty payload")))?;
+        let codec = self
+            .codecs
+            .iter()
+            .find(|codec| codec.prefix() == *prefix)
+            .ok_or(Error::UnknownPayloadCodec(*prefix))?;
+        codec.decode(encoded).map(TransportMessage::new)
+    }
+
+    /// Decodes the payloads received, leaving out the ones that cannot be decoded
+    ///
+    /// # Arguments
+    /// * `payloads`: The payloads to decode
+    fn decode_all(&self, payloads: Vec<TransportMessage>) -> Vec<TransportMessage> {
+        payloads
+            .iter()
+            .filter_map(|payload| self.decode(payload).ok())
+            .collect()
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, T> Transport<'a> for Client<T>
+where
+    T: Transport<'a, Msg = TransportMessage> + MaybeSend,
+{
+    type Msg = TransportMessage;
+    type SendResponse = T::SendResponse;
+
+    /// Encodes a message and sends it with the wrapped transport.
+    async fn send_message(&mut self, address: Address, msg: TransportMessage) -> Result<T::SendResponse>
+    where
+        'a: 'async_trait,
+    {
+        let payload = self.encode(&msg)?;
+        self.transport.send_message(address, payload).await
+    }
+
+    /// Encodes a message and sends it with the wrapped transport, returning the receipt of the
+    /// blocks it was published as.
+    async fn send_message_receipt(
+        &mut self,
+        address: Address,
+        msg: TransportMessage,
+    ) -> Result<SendReceipt<T::SendResponse>>
+    where
+        'a: 'async_trait,
+    {
+        let payload = self.encode(&msg)?;
+        self.transport.send_message_receipt(address, payload).await
+    }
+
+    /// Receives the messages at an address with the wrapped transport and decodes them.
+    async fn recv_messages(&mut self, address: Address) -> Result<Vec<TransportMessage>> {
+        let payloads = self.transport.recv_messages(address).await?;
+        Ok(self.decode_all(payloads))
+    }
+
+    /// Receives a page of the messages at an address with the wrapped transport and decodes them.
+    /// The messages that cannot be decoded are left out after paginating, so that the page may be
+    /// shorter than the limit.
+    async fn recv_messages_paged(
+        &mut self,
+        address: Address,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TransportMessage>>
+    where
+        'a: 'async_trait,
+    {
+        let payloads = self.transport.recv_messages_paged(address, offset, limit).await?;
+        Ok(self.decode_all(payloads))
+    }
+
+    /// Receives the messages at several addresses with the wrapped transport and decodes them.
+    async fn recv_messages_batch(&mut self, addresses: Vec<Address>, limit: usize) -> Vec<Result<Vec<TransportMessage>>>
+    where
+        'a: 'async_trait,
+    {
+        let batch = self.transport.recv_messages_batch(addresses, limit).await;
+        batch
+            .into_iter()
+            .map(|payloads| payloads.map(|payloads| self.decode_all(payloads)))
+            .collect()
+    }
+
+    /// Returns the capabilities of the wrapped transport, its message size limit reduced to the
+    /// size of the largest message whose encoded payload fits in it.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        let mut capabilities = self.transport.capabilities().await;
+        capabilities.max_message_size = capabilities
+            .max_message_size
+            .map(|max_payload_size| self.codec.max_message_size(max_payload_size.saturating_sub(1)));
+        capabilities
+    }
+
+    /// Checks the health of the wrapped transport.
+    async fn health_check(&mut self) -> Result<()> {
+        self.transport.health_check().await
+    }
+
+    /// Takes the mirror status of the messages last received at an address by the wrapped
+    /// transport.
+    async fn take_mirror_status(&mut self, address: Address) -> Option<MirrorStatus> {
+        self.transport.take_mirror_status(address).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        address::{Address, AppAddr, MsgId},
+        message::TransportMessage,
+        transport::bucket,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn payloads_are_decoded_by_their_prefix() -> Result<()> {
+        let address = Address::new(AppAddr::default(), MsgId::default());
+        let message = TransportMessage::new(vec![0, 1, 2, 255]);
+        let mut text = Client::new(bucket::Client::new(), Base64Codec);
+        text.send_message(address, message.clone()).await?;
+
+        // The payload is plain text, prefixed by the codec
+        let payload = text.inner_mut().recv_message(address).await?;
+        assert_eq!(payload.as_ref(), b"BAAEC/w==");
+
+        // Readers sending with another codec still decode it, and their own messages
+        let mut binary = Client::new(text.into_inner(), IdentityCodec);
+        binary.send_message(address, message.clone()).await?;
+        assert_eq!(binary.recv_messages(address).await?, vec![message.clone(), message]);
+
+        // Payloads of unknown codecs are left out
+        binary
+            .inner_mut()
+            .send_message(address, TransportMessage::new(vec![0x7f, 1]))
+            .await?;
+        assert_eq!(binary.recv_messages(address).await?.len(), 2);
+        assert!(matches!(
+            binary.decode(&TransportMessage::new(vec![0x7f, 1])),
+            Err(Error::UnknownPayloadCodec(0x7f))
+        ));
+        Ok(())
+    }
+}
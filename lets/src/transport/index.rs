@@ -0,0 +1,134 @@
+// Rust
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+// 3rd-party
+
+// IOTA
+use crypto::hashes::{blake2b::Blake2b256, Digest};
+
+// Streams
+
+// Local
+use crate::{
+    address::Address,
+    error::{Error, Result},
+    sync::{MaybeSend, MaybeSync},
+};
+
+/// Maximum length of the index of a message of the tangle
+pub const MAX_INDEX_SIZE: usize = 64;
+
+/// Derivation of the tag a message is indexed at in the tangle from its [`Address`]. The tangle
+/// clients send and look up the messages at the tag returned by their [`MessageIndex`].
+pub trait MessageIndex: Debug + MaybeSend + MaybeSync {
+    /// Returns the tag a message is indexed at, at most [`MAX_INDEX_SIZE`] bytes long
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    fn get_tag_value(&self, address: Address) -> Vec<u8>;
+}
+
+/// Default [`MessageIndex`], indexing messages at the `Blake2b256` hash of their [`Address`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AddressIndex;
+
+impl MessageIndex for AddressIndex {
+    fn get_tag_value(&self, address: Address) -> Vec<u8> {
+        address.to_msg_index().to_vec()
+    }
+}
+
+/// How a [`PrefixedIndex`] combines its prefix with the [`Address`] of a message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IndexHashing {
+    /// The prefix is kept readable, followed by the `Blake2b256` hash of the [`Address`].
+    /// Collectors can select the messages of a deployment by the prefix of their index.
+    AddressOnly,
+    /// The index is the `Blake2b256` hash of the prefix followed by the [`Address`]. Messages are
+    /// partitioned by prefix without revealing it.
+    Whole,
+}
+
+/// [`MessageIndex`] namespacing the indexes of the messages of a deployment with a prefix, such as
+/// `STREAMSv2:<appaddr>`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PrefixedIndex {
+    /// The prefix of the indexes
+    prefix: Vec<u8>,
+    /// How the prefix is combined with the addresses of the messages
+    hashing: IndexHashing,
+}
+
+impl PrefixedIndex {
+    /// Creates a new [`PrefixedIndex`]. Errors if the prefix does not leave room for the hash of
+    /// the addresses with [`IndexHashing::AddressOnly`].
+    ///
+    /// # Arguments
+    /// * `prefix`: The prefix of the indexes
+    /// * `hashing`: How the prefix is combined with the addresses of the messages
+    pub fn new<P>(prefix: P, hashing: IndexHashing) -> Result<Self>
+    where
+        P: Into<Vec<u8>>,
+    {
+        let prefix = prefix.into();
+        let max_prefix_size = MAX_INDEX_SIZE - Blake2b256::output_size();
+        if hashing == IndexHashing::AddressOnly && prefix.len() > max_prefix_size {
+            return Err(Error::InvalidSize("index prefix", max_prefix_size, prefix.len() as u64));
+        }
+        Ok(Self { prefix, hashing })
+    }
+
+    /// Returns the prefix of the indexes
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Returns how the prefix is combined with the addresses of the messages
+    pub fn hashing(&self) -> IndexHashing {
+        self.hashing
+    }
+}
+
+impl MessageIndex for PrefixedIndex {
+    fn get_tag_value(&self, address: Address) -> Vec<u8> {
+        match self.hashing {
+            IndexHashing::AddressOnly => {
+                let mut tag = self.prefix.clone();
+                tag.extend(address.to_msg_index());
+                tag
+            }
+            IndexHashing::Whole => Blake2b256::new()
+                .chain(&self.prefix)
+                .chain(address.base())
+                .chain(address.relative())
+                .finalize()
+                .to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::{Address, AppAddr, MsgId};
+
+    use super::*;
+
+    #[test]
+    fn prefixed_indexes_are_namespaced() -> Result<()> {
+        let address = Address::new(AppAddr::new([1; 40]), MsgId::new([2; 12]));
+        let readable = PrefixedIndex::new("STREAMSv2:", IndexHashing::AddressOnly)?;
+        let tag = readable.get_tag_value(address);
+        assert!(tag.starts_with(b"STREAMSv2:"));
+        assert_eq!(&tag[10..], AddressIndex.get_tag_value(address));
+
+        let hidden = PrefixedIndex::new("STREAMSv2:", IndexHashing::Whole)?;
+        let other = PrefixedIndex::new("STREAMSv3:", IndexHashing::Whole)?;
+        assert_eq!(hidden.get_tag_value(address).len(), 32);
+        assert_ne!(hidden.get_tag_value(address), other.get_tag_value(address));
+
+        assert!(PrefixedIndex::new([0; 33].to_vec(), IndexHashing::AddressOnly).is_err());
+        assert!(PrefixedIndex::new([0; 33].to_vec(), IndexHashing::Whole).is_ok());
+        Ok(())
+    }
+}
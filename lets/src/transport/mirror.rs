@@ -0,0 +1,267 @@
+//! Publication of the messages on two networks side by side
+//!
+//! Migrating a stream from a network to another is safer when the messages are published on both
+//! of them for a while. A mirror [`Client`] wraps the transports of the two networks: every message
+//! is sent with both, and the messages received from both are compared, so that the operators can
+//! check that the new network holds the same messages as the old one before cutting over.
+
+// Rust
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+
+// Local
+use crate::{
+    address::Address,
+    error::{Error, Result},
+    message::MESSAGE_DIGEST_SIZE,
+    sync::MaybeSend,
+    transport::{MirrorStatus, SendReceipt, Transport, TransportCapabilities},
+};
+
+/// [`Transport`] wrapper publishing every message with a primary and a secondary transport. The
+/// messages are received from both transports, and the messages of the primary transport are
+/// returned, or the ones of the secondary transport if the primary one has none. The
+/// [`MirrorStatus`] of the copies found at each address is recorded until taken with
+/// [`Transport::take_mirror_status()`].
+///
+/// Both transports are queried for every address, so the pagination of the wrapped transports is
+/// not used: the messages at an address are all received, then paginated.
+#[derive(Clone, Debug, Default)]
+pub struct Client<P, S> {
+    /// The transport of the network the stream is published on
+    primary: P,
+    /// The transport of the network the stream is mirrored to
+    secondary: S,
+    /// The [`MirrorStatus`] of the copies last received at each address, not taken yet
+    statuses: BTreeMap<Address, MirrorStatus>,
+}
+
+impl<P, S> Client<P, S> {
+    /// Creates a new mirror [`Client`] wrapping two transports
+    ///
+    /// # Arguments
+    /// * `primary`: The transport of the network the stream is published on
+    /// * `secondary`: The transport of the network the stream is mirrored to
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            statuses: BTreeMap::new(),
+        }
+    }
+
+    /// Returns a reference to the primary transport
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    /// Returns a mutable reference to the primary transport
+    pub fn primary_mut(&mut self) -> &mut P {
+        &mut self.primary
+    }
+
+    /// Returns a reference to the secondary transport
+    pub fn secondary(&self) -> &S {
+        &self.secondary
+    }
+
+    /// Returns a mutable reference to the secondary transport
+    pub fn secondary_mut(&mut self) -> &mut S {
+        &mut self.secondary
+    }
+
+    /// Consumes the [`Client`], returning the primary and the secondary transports
+    pub fn into_inner(self) -> (P, S) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl MirrorStatus {
+    /// Compares the copies of the messages at an address received from the primary and the
+    /// secondary transports. Returns None if neither transport holds a message at the address.
+    ///
+    /// # Arguments
+    /// * `primary`: The messages received from the primary transport
+    /// * `secondary`: The messages received from the secondary transport
+    fn compare<Msg: PartialEq>(primary: &[Msg], secondary: &[Msg]) -> Option<Self> {
+        match (primary.is_empty(), secondary.is_empty()) {
+            (true, true) => None,
+            (false, true) => Some(Self::MissingOnSecondary),
+            (true, false) => Some(Self::MissingOnPrimary),
+            // The transports may return the messages at an address in different orders
+            (false, false) if primary.len() == secondary.len() && primary.iter().all(|msg| secondary.contains(msg)) => {
+                Some(Self::Matching)
+            }
+            (false, false) => Some(Self::Mismatching),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, P, S> Transport<'a> for Client<P, S>
+where
+    P: Transport<'a> + MaybeSend,
+    S: Transport<'a, Msg = P::Msg> + MaybeSend,
+    P::Msg: Clone + PartialEq + MaybeSend,
+    P::SendResponse: MaybeSend,
+    S::SendResponse: MaybeSend,
+{
+    type Msg = P::Msg;
+    type SendResponse = (P::SendResponse, S::SendResponse);
+
+    /// Sends a message with the primary transport, then with the secondary transport. Fails if
+    /// either transport fails to send it.
+    async fn send_message(&mut self, address: Address, msg: P::Msg) -> Result<Self::SendResponse>
+    where
+        'a: 'async_trait,
+    {
+        let primary = self.primary.send_message(address, msg.clone()).await?;
+        let secondary = self.secondary.send_message(address, msg).await?;
+        Ok((primary, secondary))
+    }
+
+    /// Sends a message with both transports, returning the receipt of the blocks it was published
+    /// as: the blocks of the primary transport, then the ones of the secondary transport. The
+    /// message is indexed at the tag of the primary transport.
+    async fn send_message_receipt(&mut self, address: Address, msg: P::Msg) -> Result<SendReceipt<Self::SendResponse>>
+    where
+        'a: 'async_trait,
+    {
+        let primary = self.primary.send_message_receipt(address, msg.clone()).await?;
+        let secondary = self.secondary.send_message_receipt(address, msg).await?;
+        let mut blocks = primary.blocks;
+        blocks.extend(secondary.blocks);
+        Ok(SendReceipt::new(
+            primary.index,
+            blocks,
+            (primary.response, secondary.response),
+        ))
+    }
+
+    /// Receives the messages at an address with both transports, recording the [`MirrorStatus`]
+    /// of their copies. A transport failing to receive them, like when no message is found at the
+    /// address, counts as holding none. Returns the messages of the primary transport, or the
+    /// ones of the secondary transport if the primary one has none, or the error of the primary
+    /// transport if neither has any.
+    async fn recv_messages(&mut self, address: Address) -> Result<Vec<P::Msg>> {
+        let primary = self.primary.recv_messages(address).await;
+        let secondary = self.secondary.recv_messages(address).await.unwrap_or_default();
+        let primary_msgs = primary.as_deref().unwrap_or_default();
+        match MirrorStatus::compare(primary_msgs, &secondary) {
+            Some(status) => {
+                self.statuses.insert(address, status);
+            }
+            None => {
+                self.statuses.remove(&address);
+            }
+        }
+        match primary {
+            Ok(msgs) if !msgs.is_empty() => Ok(msgs),
+            _ if !secondary.is_empty() => Ok(secondary),
+            primary => primary,
+        }
+    }
+
+    /// Returns the capabilities shared by both transports: the smallest message size limit, push
+    /// notifications and persistence only if both transports have them, and proof of work if
+    /// either transport requires it.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        let primary = self.primary.capabilities().await;
+        let secondary = self.secondary.capabilities().await;
+        let max_message_size = match (primary.max_message_size, secondary.max_message_size) {
+            (Some(primary), Some(secondary)) => Some(primary.min(secondary)),
+            (primary, secondary) => primary.or(secondary),
+        };
+        TransportCapabilities {
+            max_message_size,
+            supports_push: primary.supports_push && secondary.supports_push,
+            persists_messages: primary.persists_messages && secondary.persists_messages,
+            requires_pow: primary.requires_pow || secondary.requires_pow,
+        }
+    }
+
+    /// Checks the health of both transports.
+    async fn health_check(&mut self) -> Result<()> {
+        self.primary.health_check().await?;
+        self.secondary.health_check().await
+    }
+
+    /// Receives the message with a content digest with the primary transport, or with the
+    /// secondary transport if the primary one cannot find it.
+    async fn recv_message_by_digest(&mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Result<(Address, P::Msg)>
+    where
+        'a: 'async_trait,
+        P::Msg: AsRef<[u8]>,
+    {
+        match self.primary.recv_message_by_digest(digest).await {
+            Ok(found) => Ok(found),
+            Err(Error::DigestLookupUnsupported) | Err(Error::DigestNotFound(_)) => {
+                self.secondary.recv_message_by_digest(digest).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Takes the [`MirrorStatus`] recorded when the messages at an address were last received.
+    async fn take_mirror_status(&mut self, address: Address) -> Option<MirrorStatus> {
+        self.statuses.remove(&address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        address::{Address, AppAddr, MsgId},
+        message::TransportMessage,
+        transport::bucket,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn copies_received_from_both_networks_are_compared() -> Result<()> {
+        let address = Address::new(AppAddr::default(), MsgId::default());
+        let message = TransportMessage::new(vec![1, 2, 3]);
+        let mut mirror = Client::new(bucket::Client::new(), bucket::Client::new());
+
+        // Messages sent through the mirror are published on both networks
+        mirror.send_message(address, message.clone()).await?;
+        assert_eq!(mirror.recv_messages(address).await?, vec![message.clone()]);
+        assert_eq!(mirror.take_mirror_status(address).await, Some(MirrorStatus::Matching));
+        // The status is only taken once
+        assert_eq!(mirror.take_mirror_status(address).await, None);
+
+        // Messages missing on the primary network are still read from the secondary one
+        let other = Address::new(AppAddr::default(), MsgId::new([1; MsgId::SIZE]));
+        mirror.secondary_mut().send_message(other, message.clone()).await?;
+        assert_eq!(mirror.recv_messages(other).await?, vec![message.clone()]);
+        assert_eq!(
+            mirror.take_mirror_status(other).await,
+            Some(MirrorStatus::MissingOnPrimary)
+        );
+
+        // Copies differing between the networks are reported
+        mirror
+            .primary_mut()
+            .send_message(other, TransportMessage::new(vec![4, 5, 6]))
+            .await?;
+        assert_eq!(
+            mirror.recv_messages(other).await?,
+            vec![TransportMessage::new(vec![4, 5, 6])]
+        );
+        assert_eq!(mirror.take_mirror_status(other).await, Some(MirrorStatus::Mismatching));
+
+        // Addresses empty on both networks have no status
+        let empty = Address::new(AppAddr::default(), MsgId::new([2; MsgId::SIZE]));
+        assert!(mirror.recv_messages(empty).await.is_err());
+        assert_eq!(mirror.take_mirror_status(empty).await, None);
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 // Rust
-use alloc::{boxed::Box, rc::Rc, vec::Vec};
+#[cfg(not(feature = "threadsafe"))]
+use alloc::rc::Rc;
+#[cfg(feature = "threadsafe")]
+use alloc::sync::Arc;
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(not(feature = "threadsafe"))]
 use core::cell::RefCell;
+use core::time::Duration;
 
 // 3rd-party
 use async_trait::async_trait;
@@ -10,15 +16,20 @@ use async_trait::async_trait;
 // Streams
 
 // Local
+#[cfg(feature = "threadsafe")]
+use crate::sync::Mutex;
 use crate::{
     address::Address,
     error::{Error, Result},
+    message::MESSAGE_DIGEST_SIZE,
+    sync::MaybeSend,
 };
 
 /// Network transport abstraction.
 /// Parametrized by the type of message addresss.
 /// Message address is used to identify/locate a message (eg. like URL for HTTP).
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 pub trait Transport<'a> {
     type Msg;
     type SendResponse;
@@ -27,25 +38,129 @@ pub trait Transport<'a> {
     where
         'a: 'async_trait;
 
+    /// Send a message, returning the [`SendReceipt`] of the blocks it was published as. The default
+    /// implementation reports the response of [`Transport::send_message()`] as a single block of
+    /// unknown id, indexed at the `msg_index` of the address. Transports that know the ids of their
+    /// blocks, or publish a message as several blocks, like when chunking or mirroring it, report
+    /// them by overriding it.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message
+    /// * `msg`: The message to send
+    async fn send_message_receipt(
+        &mut self,
+        address: Address,
+        msg: Self::Msg,
+    ) -> Result<SendReceipt<Self::SendResponse>>
+    where
+        'a: 'async_trait,
+        Self::Msg: MaybeSend,
+    {
+        let response = self.send_message(address, msg).await?;
+        Ok(SendReceipt::new(
+            address.to_msg_index().to_vec(),
+            vec![SentBlock::default()],
+            response,
+        ))
+    }
+
     /// Receive messages
     async fn recv_messages(&mut self, address: Address) -> Result<Vec<Self::Msg>>
     where
         'a: 'async_trait;
 
-    /// Receive a single message
+    /// Receive a page of the messages at an address, skipping the first `offset` messages and
+    /// returning at most `limit` of them. Transports that cannot paginate natively receive every
+    /// message and truncate the result.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the messages
+    /// * `offset`: The number of messages to skip
+    /// * `limit`: The maximum number of messages to return
+    async fn recv_messages_paged(&mut self, address: Address, offset: usize, limit: usize) -> Result<Vec<Self::Msg>>
+    where
+        'a: 'async_trait,
+    {
+        let msgs = self.recv_messages(address).await?;
+        Ok(msgs.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Receive the messages at several addresses, returning the result of each address in the order
+    /// of the addresses. At most `limit` messages are received per address. Transports able to
+    /// send several requests at once receive the addresses concurrently, the others receive them
+    /// one after the other.
+    ///
+    /// # Arguments
+    /// * `addresses`: The addresses of the messages
+    /// * `limit`: The maximum number of messages to return per address
+    async fn recv_messages_batch(&mut self, addresses: Vec<Address>, limit: usize) -> Vec<Result<Vec<Self::Msg>>>
+    where
+        'a: 'async_trait,
+        Self::Msg: MaybeSend,
+    {
+        let mut batch = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            batch.push(self.recv_messages_paged(address, 0, limit).await);
+        }
+        batch
+    }
+
+    /// Returns the [`TransportCapabilities`] of the transport, so that its users can adapt to it.
+    /// Transports that do not report them are assumed to have the
+    /// [default](`TransportCapabilities::default`) ones.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        TransportCapabilities::default()
+    }
+
+    /// Checks that the transport can reach the messaging layer, so that services can gate their
+    /// readiness on it. Transports backed by a node ask the node whether it is healthy. Transports
+    /// that do not depend on a remote endpoint are always healthy.
+    async fn health_check(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Receive the message whose content digest, the `Blake2b256` hash of its bytes, is provided,
+    /// together with its address. Only transports whose index supports content addressing can look
+    /// messages up by digest, the tangle clients index the messages by address only. The default
+    /// implementation fails with [`Error::DigestLookupUnsupported`].
+    ///
+    /// # Arguments
+    /// * `digest`: The content digest of the message
+    async fn recv_message_by_digest(&mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Result<(Address, Self::Msg)>
+    where
+        'a: 'async_trait,
+        Self::Msg: AsRef<[u8]>,
+    {
+        let _ = digest;
+        Err(Error::DigestLookupUnsupported)
+    }
+
+    /// Takes the [`MirrorStatus`] of the copies of the messages last received at an address, for
+    /// transports publishing the messages on two networks, like the [mirror](`mirror::Client`)
+    /// client. The default implementation, for transports publishing on a single network, returns
+    /// None.
+    ///
+    /// # Arguments
+    /// * `address`: The address the messages were received at
+    async fn take_mirror_status(&mut self, address: Address) -> Option<MirrorStatus> {
+        let _ = address;
+        None
+    }
+
+    /// Receive a single message. Errors with [`Error::MessageNotFound`] if there is no message at
+    /// the address, and with [`Error::MultipleMessagesFound`] if there are several.
     async fn recv_message(&mut self, address: Address) -> Result<Self::Msg> {
-        let mut msgs = self.recv_messages(address).await?;
-        if let Some(msg) = msgs.pop() {
-            match msgs.is_empty() {
-                true => Ok(msg),
-                false => Err(Error::AddressError("More than one found", address)),
-            }
-        } else {
-            Err(Error::AddressError("not found in transport", address))
+        // A second message is enough to tell that the address is ambiguous
+        let mut msgs = self.recv_messages_paged(address, 0, 2).await?;
+        match msgs.len() {
+            0 => Err(Error::MessageNotFound(address)),
+            1 => Ok(msgs.remove(0)),
+            count => Err(Error::MultipleMessagesFound(address, count)),
         }
     }
 }
 
+#[cfg(not(feature = "threadsafe"))]
 #[async_trait(?Send)]
 impl<'a, Tsp: Transport<'a>> Transport<'a> for Rc<RefCell<Tsp>> {
     type Msg = Tsp::Msg;
@@ -59,14 +174,386 @@ impl<'a, Tsp: Transport<'a>> Transport<'a> for Rc<RefCell<Tsp>> {
         self.borrow_mut().send_message(address, msg).await
     }
 
+    /// Send a message, returning the receipt of the blocks it was published as.
+    async fn send_message_receipt(&mut self, address: Address, msg: Tsp::Msg) -> Result<SendReceipt<Tsp::SendResponse>>
+    where
+        'a: 'async_trait,
+        Tsp::Msg: MaybeSend,
+    {
+        self.borrow_mut().send_message_receipt(address, msg).await
+    }
+
     /// Receive messages with default options.
     async fn recv_messages(&mut self, address: Address) -> Result<Vec<Tsp::Msg>> {
         self.borrow_mut().recv_messages(address).await
     }
+
+    /// Receive a page of the messages at an address.
+    async fn recv_messages_paged(&mut self, address: Address, offset: usize, limit: usize) -> Result<Vec<Tsp::Msg>>
+    where
+        'a: 'async_trait,
+    {
+        self.borrow_mut().recv_messages_paged(address, offset, limit).await
+    }
+
+    /// Receive the messages at several addresses.
+    async fn recv_messages_batch(&mut self, addresses: Vec<Address>, limit: usize) -> Vec<Result<Vec<Tsp::Msg>>>
+    where
+        'a: 'async_trait,
+        Tsp::Msg: MaybeSend,
+    {
+        self.borrow_mut().recv_messages_batch(addresses, limit).await
+    }
+
+    /// Returns the capabilities of the shared transport.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        self.borrow_mut().capabilities().await
+    }
+
+    /// Checks the health of the shared transport.
+    async fn health_check(&mut self) -> Result<()> {
+        self.borrow_mut().health_check().await
+    }
+
+    /// Receive the message with a content digest.
+    async fn recv_message_by_digest(&mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Result<(Address, Tsp::Msg)>
+    where
+        'a: 'async_trait,
+        Tsp::Msg: AsRef<[u8]>,
+    {
+        self.borrow_mut().recv_message_by_digest(digest).await
+    }
+
+    /// Takes the mirror status of the messages last received at an address.
+    async fn take_mirror_status(&mut self, address: Address) -> Option<MirrorStatus> {
+        self.borrow_mut().take_mirror_status(address).await
+    }
+}
+
+#[cfg(feature = "threadsafe")]
+#[async_trait]
+impl<'a, Tsp> Transport<'a> for Arc<Mutex<Tsp>>
+where
+    Tsp: Transport<'a> + Send,
+    Tsp::Msg: Send,
+{
+    type Msg = Tsp::Msg;
+    type SendResponse = Tsp::SendResponse;
+
+    /// Send a message, holding the lock of the shared transport until it is sent.
+    async fn send_message(&mut self, address: Address, msg: Tsp::Msg) -> Result<Tsp::SendResponse>
+    where
+        Self::Msg: 'async_trait,
+    {
+        self.lock().await.send_message(address, msg).await
+    }
+
+    /// Send a message, returning the receipt of the blocks it was published as, holding the lock of
+    /// the shared transport until it is sent.
+    async fn send_message_receipt(&mut self, address: Address, msg: Tsp::Msg) -> Result<SendReceipt<Tsp::SendResponse>>
+    where
+        'a: 'async_trait,
+        Tsp::Msg: MaybeSend,
+    {
+        self.lock().await.send_message_receipt(address, msg).await
+    }
+
+    /// Receive messages with default options.
+    async fn recv_messages(&mut self, address: Address) -> Result<Vec<Tsp::Msg>> {
+        self.lock().await.recv_messages(address).await
+    }
+
+    /// Receive a page of the messages at an address.
+    async fn recv_messages_paged(&mut self, address: Address, offset: usize, limit: usize) -> Result<Vec<Tsp::Msg>>
+    where
+        'a: 'async_trait,
+    {
+        self.lock().await.recv_messages_paged(address, offset, limit).await
+    }
+
+    /// Receive the messages at several addresses, holding the lock of the shared transport until
+    /// they are all received.
+    async fn recv_messages_batch(&mut self, addresses: Vec<Address>, limit: usize) -> Vec<Result<Vec<Tsp::Msg>>>
+    where
+        'a: 'async_trait,
+        Tsp::Msg: MaybeSend,
+    {
+        self.lock().await.recv_messages_batch(addresses, limit).await
+    }
+
+    /// Returns the capabilities of the shared transport.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        self.lock().await.capabilities().await
+    }
+
+    /// Checks the health of the shared transport.
+    async fn health_check(&mut self) -> Result<()> {
+        self.lock().await.health_check().await
+    }
+
+    /// Receive the message with a content digest, holding the lock of the shared transport until
+    /// it is received.
+    async fn recv_message_by_digest(&mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Result<(Address, Tsp::Msg)>
+    where
+        'a: 'async_trait,
+        Tsp::Msg: AsRef<[u8]>,
+    {
+        self.lock().await.recv_message_by_digest(digest).await
+    }
+
+    /// Takes the mirror status of the messages last received at an address.
+    async fn take_mirror_status(&mut self, address: Address) -> Option<MirrorStatus> {
+        self.lock().await.take_mirror_status(address).await
+    }
+}
+
+/// Properties of a [`Transport`] the high-level APIs adapt to, instead of assuming the ones of the
+/// tangle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TransportCapabilities {
+    /// Size of the largest message the transport accepts, in bytes, or `None` if it is not bounded
+    pub max_message_size: Option<usize>,
+    /// Whether the transport notifies the new messages, rather than having them polled
+    pub supports_push: bool,
+    /// Whether the sent messages are kept by the transport, and can be received again later and by
+    /// other instances of the transport
+    pub persists_messages: bool,
+    /// Whether the messages require a proof of work to be accepted
+    pub requires_pow: bool,
+}
+
+/// Unbounded, polled and persisting messages without proof of work
+impl Default for TransportCapabilities {
+    fn default() -> Self {
+        Self {
+            max_message_size: None,
+            supports_push: false,
+            persists_messages: true,
+            requires_pow: false,
+        }
+    }
+}
+
+/// Block a message was published as by a [`Transport`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SentBlock {
+    /// Identifier of the block in the transport, empty if the transport does not report it
+    pub id: Vec<u8>,
+    /// Unix timestamp, in seconds, at which the block was published, if known
+    pub timestamp: Option<u64>,
+}
+
+impl SentBlock {
+    /// Creates a new [`SentBlock`]
+    ///
+    /// # Arguments
+    /// * `id`: The identifier of the block in the transport
+    /// * `timestamp`: The Unix timestamp, in seconds, at which the block was published, if known
+    pub fn new(id: Vec<u8>, timestamp: Option<u64>) -> Self {
+        Self { id, timestamp }
+    }
+}
+
+/// Receipt of a message sent by a [`Transport`]: the tag it was indexed at, every block it was
+/// published as, and the response of the transport, aggregated over the blocks by the transport
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SendReceipt<SR> {
+    /// Tag the message was indexed at
+    pub index: Vec<u8>,
+    /// Blocks the message was published as, in the order they were sent
+    pub blocks: Vec<SentBlock>,
+    /// Response of the transport to the message
+    pub response: SR,
+    /// Content digest of the message, the `Blake2b256` hash of its bytes. None if the sender did
+    /// not record it, transports leave it to the sender.
+    pub digest: Option<[u8; MESSAGE_DIGEST_SIZE]>,
+}
+
+impl<SR> SendReceipt<SR> {
+    /// Creates a new [`SendReceipt`]
+    ///
+    /// # Arguments
+    /// * `index`: The tag the message was indexed at
+    /// * `blocks`: The [`SentBlock`]s the message was published as
+    /// * `response`: The response of the transport to the message
+    pub fn new(index: Vec<u8>, blocks: Vec<SentBlock>, response: SR) -> Self {
+        Self {
+            index,
+            blocks,
+            response,
+            digest: None,
+        }
+    }
+
+    /// Records the content digest of the message in the receipt
+    ///
+    /// # Arguments
+    /// * `digest`: The content digest of the message
+    pub fn with_digest(mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+}
+
+/// Comparison of the copies of the messages at an address published on two networks, reported by
+/// the [mirror](`mirror::Client`) client
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MirrorStatus {
+    /// Both networks hold the same messages at the address
+    Matching,
+    /// Both networks hold messages at the address, but not the same ones
+    Mismatching,
+    /// Only the primary network holds messages at the address
+    MissingOnSecondary,
+    /// Only the secondary network holds messages at the address
+    MissingOnPrimary,
+}
+
+/// Inclusion of a sent message in the ledger of a [`ConfirmedTransport`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Inclusion {
+    /// The message was submitted to the transport but is not referenced yet
+    Submitted,
+    /// The message is not referenced and the transport advises to reattach it, as it will not be
+    /// referenced otherwise
+    Stale,
+    /// The message is referenced by the milestone with the provided index
+    Referenced(u32),
+}
+
+/// Extension of a [`Transport`] reporting the inclusion of the sent messages in its ledger.
+/// A successful [`Transport::send_message`] only means that the message was submitted, a message
+/// that is never referenced is eventually lost.
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+pub trait ConfirmedTransport<'a>: Transport<'a> {
+    /// Waits until a sent message is referenced or stale, returning its [`Inclusion`]. Returns
+    /// [`Inclusion::Submitted`] if the message is still not referenced once the timeout elapsed.
+    ///
+    /// # Arguments
+    /// * `response`: The response of the transport to the sent message
+    /// * `timeout`: How long to wait for the message to be referenced
+    async fn await_inclusion(&mut self, response: &Self::SendResponse, timeout: Duration) -> Result<Inclusion>
+    where
+        'a: 'async_trait;
+}
+
+#[cfg(not(feature = "threadsafe"))]
+#[async_trait(?Send)]
+impl<'a, Tsp: ConfirmedTransport<'a>> ConfirmedTransport<'a> for Rc<RefCell<Tsp>> {
+    /// Waits for the inclusion of a sent message.
+    async fn await_inclusion(&mut self, response: &Tsp::SendResponse, timeout: Duration) -> Result<Inclusion>
+    where
+        'a: 'async_trait,
+    {
+        self.borrow_mut().await_inclusion(response, timeout).await
+    }
+}
+
+#[cfg(feature = "threadsafe")]
+#[async_trait]
+impl<'a, Tsp> ConfirmedTransport<'a> for Arc<Mutex<Tsp>>
+where
+    Tsp: ConfirmedTransport<'a> + Send,
+    Tsp::Msg: Send,
+    Tsp::SendResponse: Sync,
+{
+    /// Waits for the inclusion of a sent message, holding the lock of the shared transport until
+    /// it returns.
+    async fn await_inclusion(&mut self, response: &Tsp::SendResponse, timeout: Duration) -> Result<Inclusion>
+    where
+        'a: 'async_trait,
+    {
+        self.lock().await.await_inclusion(response, timeout).await
+    }
+}
+
+/// Identifier of a message of the tangle, as opposed to the [`Address`] it is indexed at
+#[cfg(any(
+    feature = "tangle-client",
+    feature = "tangle-client-wasm",
+    feature = "utangle-client"
+))]
+pub type TangleMessageId = [u8; 32];
+
+/// Size of the largest message the tangle accepts in the data of an indexation payload: the
+/// `32768` bytes of a block, minus its fields and an index of the maximum `64` bytes
+#[cfg(any(
+    feature = "tangle-client",
+    feature = "tangle-client-wasm",
+    feature = "utangle-client"
+))]
+pub const MAX_TANGLE_MESSAGE_SIZE: usize = 32_418;
+
+/// [`TransportCapabilities`] of the tangle clients
+#[cfg(any(
+    feature = "tangle-client",
+    feature = "tangle-client-wasm",
+    feature = "utangle-client"
+))]
+pub(crate) const TANGLE_CAPABILITIES: TransportCapabilities = TransportCapabilities {
+    max_message_size: Some(MAX_TANGLE_MESSAGE_SIZE),
+    supports_push: false,
+    persists_messages: true,
+    requires_pow: true,
+};
+
+/// Operations of the tangle beyond the [`Transport`] ones, implemented by both the `iota.rs` based
+/// [tangle](`tangle::Client`) client and the [uTangle](`utangle::Client`) client, so that code
+/// written against it can switch clients with a feature flag only.
+#[cfg(any(
+    feature = "tangle-client",
+    feature = "tangle-client-wasm",
+    feature = "utangle-client"
+))]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+pub trait TangleTransportExt<'a>: Transport<'a> {
+    /// Returns the ids of the tips the node selects as parents of new messages
+    async fn tips(&mut self) -> Result<Vec<TangleMessageId>>
+    where
+        'a: 'async_trait;
+
+    /// Returns the ids of every message indexed at the provided [`Address`]
+    ///
+    /// # Arguments
+    /// * `address`: The address the messages are indexed at
+    async fn message_ids(&mut self, address: Address) -> Result<Vec<TangleMessageId>>
+    where
+        'a: 'async_trait;
+
+    /// Sends a message indexed at the provided [`Address`], attached to the provided parents
+    /// instead of the tips selected by the node
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message to send
+    /// * `msg`: The message to send
+    /// * `parents`: The ids of the messages to attach the message to, between 1 and 8
+    async fn send_message_with_parents(
+        &mut self,
+        address: Address,
+        msg: Self::Msg,
+        parents: Vec<TangleMessageId>,
+    ) -> Result<Self::SendResponse>
+    where
+        'a: 'async_trait;
 }
 
 /// Localised mapping for tests and simulations
 pub mod bucket;
+/// Encoding of the messages into the payloads of the transport
+pub mod encoding;
+/// Derivation of the indexes of the messages in the tangle
+pub mod index;
+/// Publication of the messages on two networks side by side
+pub mod mirror;
+/// Proof of work of the messages sent to the tangle
+#[cfg(any(
+    feature = "tangle-client",
+    feature = "tangle-client-wasm",
+    feature = "utangle-client"
+))]
+pub mod pow;
 /// `iota.rs` based tangle client
 #[cfg(any(feature = "tangle-client", feature = "tangle-client-wasm"))]
 pub mod tangle;
@@ -0,0 +1,261 @@
+//! Proof of work of the messages sent to the tangle
+//!
+//! The tangle clients compute the nonce of the messages they send with a [`PowProvider`], selected
+//! when the client is constructed. [`LocalPow`] searches the nonce on the cores of the machine, and
+//! [`RemotePow`] delegates the search to a remote worker over HTTP, for devices that cannot afford
+//! the proof of work of every message they send.
+
+// Rust
+#[cfg(feature = "utangle-client")]
+use alloc::string::String;
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt::Debug;
+
+// 3rd-party
+use async_trait::async_trait;
+#[cfg(feature = "utangle-client")]
+use rayon::prelude::*;
+#[cfg(feature = "utangle-client")]
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+#[cfg(feature = "utangle-client")]
+use serde::{Deserialize, Serialize};
+
+// IOTA
+#[cfg(feature = "utangle-client")]
+use bee_ternary::{b1t6, Btrit, T1B1Buf, TritBuf};
+#[cfg(feature = "utangle-client")]
+use crypto::hashes::{
+    blake2b::Blake2b256,
+    ternary::{self, curl_p},
+    Digest,
+};
+
+// Streams
+
+// Local
+#[cfg(feature = "utangle-client")]
+use crate::error::Error;
+use crate::{
+    error::Result,
+    sync::{MaybeSend, MaybeSync},
+};
+
+/// Size of the nonce ending the messages of the tangle
+pub const NONCE_SIZE: usize = core::mem::size_of::<u64>();
+// Precomputed natural logarithm of 3 for performance reasons.
+// See https://oeis.org/A002391.
+#[cfg(feature = "utangle-client")]
+const LN_3: f64 = 1.098_612_288_668_109;
+
+/// Computation of the nonce of the messages sent to the tangle
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+pub trait PowProvider: Debug + MaybeSend + MaybeSync {
+    /// Returns a nonce making a message reach the minimum proof of work score of the network
+    ///
+    /// # Arguments
+    /// * `data`: The bytes of the message, without the nonce
+    /// * `target_score`: The minimum proof of work score of the network
+    async fn nonce(&self, data: &[u8], target_score: f64) -> Result<u64>;
+}
+
+/// [`PowProvider`] searching the nonce on all the cores of the machine
+#[cfg(feature = "utangle-client")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LocalPow;
+
+#[cfg(feature = "utangle-client")]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl PowProvider for LocalPow {
+    #[cfg_attr(feature = "trace", tracing::instrument(skip(self, data), fields(size = data.len())))]
+    async fn nonce(&self, data: &[u8], target_score: f64) -> Result<u64> {
+        let target_zeros = (((data.len() + NONCE_SIZE) as f64 * target_score).ln() / LN_3).ceil() as usize;
+        let hash = Blake2b256::digest(data);
+        let mut pow_digest = TritBuf::<T1B1Buf>::new();
+        b1t6::encode::<T1B1Buf>(&hash).iter().for_each(|t| pow_digest.push(t));
+        (0..u32::MAX)
+            .into_par_iter()
+            .step_by(curl_p::BATCH_SIZE)
+            .find_map_any(|n| {
+                let mut hasher = curl_p::CurlPBatchHasher::<T1B1Buf>::new(ternary::HASH_LENGTH);
+                for i in 0..curl_p::BATCH_SIZE {
+                    let mut buffer = TritBuf::<T1B1Buf>::zeros(ternary::HASH_LENGTH);
+                    buffer[..pow_digest.len()].copy_from(&pow_digest);
+                    let nonce_trits = b1t6::encode::<T1B1Buf>(&(n as u64 + i as u64).to_le_bytes());
+                    buffer[pow_digest.len()..pow_digest.len() + nonce_trits.len()].copy_from(&nonce_trits);
+                    hasher.add(buffer);
+                }
+                for (i, hash) in hasher.hash().enumerate() {
+                    let trailing_zeros = hash.iter().rev().take_while(|t| *t == Btrit::Zero).count();
+
+                    if trailing_zeros >= target_zeros {
+                        return Some(n as u64 + i as u64);
+                    }
+                }
+                None
+            })
+            .ok_or(Error::Nonce(target_score))
+    }
+}
+
+/// [`PowProvider`] delegating the search of the nonce to a remote worker over HTTP
+///
+/// The message is posted to the worker as a `JSON` object `{"data": <hex>, "targetScore": <f64>}`,
+/// `data` being the hex encoded bytes of the message without its nonce, and the worker replies with
+/// `{"nonce": <u64>}`. The nonce is not checked by the provider, a wrong nonce makes the node
+/// reject the message.
+#[cfg(feature = "utangle-client")]
+#[derive(Clone, Debug)]
+pub struct RemotePow {
+    /// Endpoint URL of the worker
+    url: String,
+    /// HTTP Client
+    client: reqwest::Client,
+    /// Headers sent with every request to the worker
+    headers: HeaderMap,
+}
+
+#[cfg(feature = "utangle-client")]
+impl RemotePow {
+    /// Creates a new [`RemotePow`] posting the messages to the provided URL
+    ///
+    /// # Arguments
+    /// * `url`: Endpoint URL of the worker
+    pub fn new<U>(url: U) -> Self
+    where
+        U: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Adds a header sent with every request to the worker, to authenticate to the worker for
+    /// example
+    ///
+    /// # Arguments
+    /// * `name`: The name of the header
+    /// * `value`: The value of the header
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::External(anyhow::anyhow!("invalid header name '{}': {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::External(anyhow::anyhow!("invalid value for header '{}': {}", name, e)))?;
+        self.headers.insert(name, value);
+        self.client = reqwest::Client::builder()
+            .default_headers(self.headers.clone())
+            .build()?;
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "utangle-client")]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl PowProvider for RemotePow {
+    #[cfg_attr(feature = "trace", tracing::instrument(skip(self, data), fields(size = data.len())))]
+    async fn nonce(&self, data: &[u8], target_score: f64) -> Result<u64> {
+        let request = PowRequest {
+            data: hex::encode(data),
+            target_score,
+        };
+        let response: PowResponse = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.nonce)
+    }
+}
+
+/// Request of a [`RemotePow`] to its worker
+#[cfg(feature = "utangle-client")]
+#[derive(Serialize)]
+struct PowRequest {
+    data: String,
+    #[serde(rename = "targetScore")]
+    target_score: f64,
+}
+
+/// Response of the worker of a [`RemotePow`]
+#[cfg(feature = "utangle-client")]
+#[derive(Deserialize)]
+struct PowResponse {
+    nonce: u64,
+}
+
+/// Returns the bytes of a message of the tangle carrying an indexation payload, without its nonce.
+/// These are the bytes the proof of work of the message is computed over.
+///
+/// # Arguments
+/// * `network_id`: The id of the network, the first 8 bytes of the `Blake2b256` hash of its name
+/// * `parents`: The ids of the messages the message is attached to
+/// * `index`: The index of the payload
+/// * `data`: The data of the payload
+pub(crate) fn pow_input<P>(network_id: u64, parents: &[P], index: &[u8], data: &[u8]) -> Vec<u8>
+where
+    P: AsRef<[u8]>,
+{
+    let mut message_bytes = Vec::new();
+    // Network-ID
+    message_bytes.extend(network_id.to_le_bytes());
+    // Parent Messages
+    message_bytes.extend((parents.len() as u8).to_le_bytes());
+    for parent in parents {
+        message_bytes.extend(parent.as_ref());
+    }
+    // Size of whole payload (payload-type + index-size + index + data-size + data)
+    message_bytes.extend(((4 + 2 + index.len() + 4 + data.len()) as u32).to_le_bytes());
+    // payload-type (Indexation = 2)
+    message_bytes.extend(2_u32.to_le_bytes());
+    // index-size
+    message_bytes.extend((index.len() as u16).to_le_bytes());
+    // index
+    message_bytes.extend(index);
+    // data-size
+    message_bytes.extend((data.len() as u32).to_le_bytes());
+    // data
+    message_bytes.extend(data);
+    message_bytes
+}
+
+/// Returns the id of a network from its name
+///
+/// # Arguments
+/// * `network`: The name of the network, as reported by the nodes
+#[cfg(feature = "utangle-client")]
+pub(crate) fn network_id(network: &str) -> u64 {
+    let mut id = [0; 8];
+    id.copy_from_slice(&Blake2b256::digest(network.as_bytes())[..8]);
+    u64::from_le_bytes(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pow_input_is_the_message_without_nonce() {
+        let parents = [[1; 32], [2; 32]];
+        let bytes = pow_input(7, &parents, b"index", b"data");
+        assert_eq!(bytes.len(), 8 + 1 + 2 * 32 + 4 + 4 + 2 + 5 + 4 + 4);
+        assert_eq!(&bytes[..8], &7_u64.to_le_bytes());
+        assert_eq!(bytes[8], 2);
+        assert!(bytes.ends_with(b"data"));
+    }
+
+    #[cfg(feature = "utangle-client")]
+    #[tokio::test]
+    async fn local_pow_finds_a_nonce() -> Result<()> {
+        let data = pow_input(network_id("chrysalis-mainnet"), &[[0; 32]], b"index", b"data");
+        LocalPow.nonce(&data, 1.0).await?;
+        Ok(())
+    }
+}
@@ -1,19 +1,30 @@
 // Rust
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeSet, sync::Arc, vec::Vec};
 use core::{
     convert::{TryFrom, TryInto},
+    future::Future,
     marker::PhantomData,
+    time::Duration,
 };
 
 // 3rd-party
 use async_trait::async_trait;
 use futures::{
-    future::{ready, try_join_all},
+    future::{join_all, ready, try_join_all},
     TryFutureExt,
 };
+use futures_timer::Delay;
+use spin::Mutex;
 
 // IOTA
-use iota_client::bee_message::{payload::Payload, Message as IotaMessage};
+use iota_client::{
+    bee_message::{
+        parents::Parents,
+        payload::{indexation::IndexationPayload, Payload},
+        Message as IotaMessage, MessageBuilder, MessageId,
+    },
+    bee_pow::providers::{Constant, ConstantBuilder, NonceProviderBuilder},
+};
 
 // Streams
 
@@ -22,21 +33,36 @@ use crate::{
     address::Address,
     error::{Error, Result},
     message::TransportMessage,
-    transport::Transport,
+    sync::MaybeSend,
+    transport::{
+        index::{AddressIndex, MessageIndex},
+        pow::{self, PowProvider},
+        ConfirmedTransport, Inclusion, SendReceipt, SentBlock, TangleMessageId, TangleTransportExt, Transport,
+        TransportCapabilities, TANGLE_CAPABILITIES,
+    },
 };
 
+/// Interval between two requests of the metadata of a message whose inclusion is awaited
+pub const INCLUSION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Ids of the blocks sent by a [`Client`] that are not referenced by a milestone yet
+type PendingBlocks = Arc<Mutex<BTreeSet<MessageId>>>;
+
 /// A [`Transport`] Client for sending and retrieving binary messages from an `IOTA Tangle` node.
 /// This Client uses the [iota.rs](https://github.com/iotaledger/iota.rs) Client implementation.
 #[derive(Debug)]
 pub struct Client<Message = TransportMessage, SendResponse = TransportMessage>(
     iota_client::Client,
+    Option<PendingBlocks>,
+    Arc<dyn MessageIndex>,
+    Option<Arc<dyn PowProvider>>,
     PhantomData<(Message, SendResponse)>,
 );
 
 impl<Message, SendResponse> Client<Message, SendResponse> {
     /// Create an instance of [`Client`] with an  explicit client
     pub fn new(client: iota_client::Client) -> Self {
-        Self(client, PhantomData)
+        Self(client, None, Arc::new(AddressIndex), None, PhantomData)
     }
 
     /// Shortcut to create an instance of [`Client`] connecting to a node with default parameters
@@ -52,10 +78,86 @@ impl<Message, SendResponse> Client<Message, SendResponse> {
                 .finish()
                 .await
                 .map_err(|e| Error::External(e.into()))?,
+            None,
+            Arc::new(AddressIndex),
+            None,
             PhantomData,
         ))
     }
 
+    /// Sets the [`MessageIndex`] deriving the indexes the messages are sent and looked up at,
+    /// [`AddressIndex`] by default
+    ///
+    /// # Arguments
+    /// * `index`: The [`MessageIndex`] of the messages
+    pub fn with_message_index<I>(mut self, index: I) -> Self
+    where
+        I: MessageIndex + 'static,
+    {
+        self.2 = Arc::new(index);
+        self
+    }
+
+    /// Sets the [`PowProvider`] computing the nonce of the messages sent. Without one, the proof of
+    /// work is done by the `IOTA` [Client](`iota_client::Client`), locally or by the node depending
+    /// on its configuration.
+    ///
+    /// # Arguments
+    /// * `pow`: The [`PowProvider`] of the messages
+    pub fn with_pow_provider<P>(mut self, pow: P) -> Self
+    where
+        P: PowProvider + 'static,
+    {
+        self.3 = Some(Arc::new(pow));
+        self
+    }
+
+    /// Tracks the blocks sent by the [`Client`] until they are referenced by a milestone, so that
+    /// the blocks left unreferenced can be reattached or promoted with
+    /// [`Client::reattach_pending()`] or [`Client::reattach_task()`].
+    pub fn with_pending_tracking(mut self) -> Self {
+        self.1 = Some(PendingBlocks::default());
+        self
+    }
+
+    /// Returns the number of tracked blocks that were not referenced by a milestone yet
+    pub fn pending(&self) -> usize {
+        self.1.as_ref().map_or(0, |pending| pending.lock().len())
+    }
+
+    /// Checks the tracked blocks once, forgetting the ones referenced by a milestone, and
+    /// reattaching or promoting the others when the node advises to. Returns the number of blocks
+    /// that were reattached or promoted. Blocks are only tracked by a [`Client`] created
+    /// [`with_pending_tracking()`](Client::with_pending_tracking).
+    pub async fn reattach_pending(&self) -> Result<usize> {
+        match &self.1 {
+            Some(pending) => reattach_blocks(self.client(), pending).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Returns a task checking the tracked blocks every `window`, reattaching or promoting the
+    /// blocks left unreferenced as [`Client::reattach_pending()`] does. The task runs until
+    /// checking the blocks fails, and is meant to be spawned on the executor of the application.
+    /// It completes right away if the [`Client`] does not track its blocks.
+    ///
+    /// # Arguments
+    /// * `window`: How long to wait between two checks of the tracked blocks
+    pub fn reattach_task(&self, window: Duration) -> impl Future<Output = Result<()>> {
+        let client = self.0.clone();
+        let pending = self.1.clone();
+        async move {
+            let pending = match pending {
+                Some(pending) => pending,
+                None => return Ok(()),
+            };
+            loop {
+                Delay::new(window).await;
+                reattach_blocks(&client, &pending).await?;
+            }
+        }
+    }
+
     /// Returns a reference to the `IOTA` [Client](`iota_client::Client`)
     pub fn client(&self) -> &iota_client::Client {
         &self.0
@@ -67,11 +169,12 @@ impl<Message, SendResponse> Client<Message, SendResponse> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<Message, SendResponse> Transport<'_> for Client<Message, SendResponse>
 where
-    Message: Into<Vec<u8>> + TryFrom<IotaMessage, Error = crate::error::Error>,
-    SendResponse: TryFrom<IotaMessage, Error = crate::error::Error>,
+    Message: Into<Vec<u8>> + TryFrom<IotaMessage, Error = crate::error::Error> + MaybeSend,
+    SendResponse: TryFrom<IotaMessage, Error = crate::error::Error> + MaybeSend,
 {
     type Msg = Message;
     type SendResponse = SendResponse;
@@ -81,18 +184,46 @@ where
     /// # Arguments
     /// * `address`: The address of the message to send.
     /// * `msg`: Message - The message to send.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
     async fn send_message(&mut self, address: Address, msg: Message) -> Result<SendResponse>
     where
         Message: 'async_trait,
     {
-        self.client()
-            .message()
-            .with_index(address.to_msg_index())
-            .with_data(msg.into())
-            .finish()
-            .await
-            .map_err(|e| Error::IotaClient("sending message", e))?
-            .try_into()
+        send_indexed(
+            &self.0,
+            self.1.as_ref(),
+            self.3.as_deref(),
+            self.2.get_tag_value(address),
+            msg.into(),
+            None,
+        )
+        .await?
+        .try_into()
+    }
+
+    /// Sends a message indexed at the provided [`Address`] to the tangle, reporting the id of the
+    /// block it was published as.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message to send.
+    /// * `msg`: Message - The message to send.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    async fn send_message_receipt(&mut self, address: Address, msg: Message) -> Result<SendReceipt<SendResponse>>
+    where
+        Message: 'async_trait,
+    {
+        let index = self.2.get_tag_value(address);
+        let message = send_indexed(
+            &self.0,
+            self.1.as_ref(),
+            self.3.as_deref(),
+            index.clone(),
+            msg.into(),
+            None,
+        )
+        .await?;
+        let block = SentBlock::new(message.id().0.as_ref().to_vec(), None);
+        Ok(SendReceipt::new(index, vec![block], message.try_into()?))
     }
 
     /// Retrieves a message indexed at the provided [`Address`] from the tangle. Errors if no
@@ -101,26 +232,376 @@ where
     /// # Arguments
     /// * `address`: The address of the message to retrieve.
     async fn recv_messages(&mut self, address: Address) -> Result<Vec<Message>> {
+        self.recv_messages_paged(address, 0, usize::MAX).await
+    }
+
+    /// Retrieves a page of the messages indexed at the provided [`Address`] from the tangle. The
+    /// ids of the messages are listed first, and only the messages of the page are fetched. Errors
+    /// if no messages are found.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the messages to retrieve.
+    /// * `offset`: The number of messages to skip.
+    /// * `limit`: The maximum number of messages to retrieve.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address, offset, limit)))]
+    async fn recv_messages_paged(&mut self, address: Address, offset: usize, limit: usize) -> Result<Vec<Message>> {
+        recv_indexed(self.client(), address, self.2.get_tag_value(address), offset, limit).await
+    }
+
+    /// Retrieves the messages indexed at several [`Address`]es from the tangle, requesting the
+    /// addresses concurrently.
+    ///
+    /// # Arguments
+    /// * `addresses`: The addresses of the messages to retrieve.
+    /// * `limit`: The maximum number of messages to retrieve per address.
+    async fn recv_messages_batch(&mut self, addresses: Vec<Address>, limit: usize) -> Vec<Result<Vec<Message>>> {
+        let client = self.client();
+        let index = &self.2;
+        join_all(
+            addresses
+                .into_iter()
+                .map(|address| recv_indexed(client, address, index.get_tag_value(address), 0, limit)),
+        )
+        .await
+    }
+
+    /// Messages are limited to the size of an indexation payload, require a proof of work, and are
+    /// polled from the node, which keeps them until they are pruned.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        TANGLE_CAPABILITIES
+    }
+
+    /// Requests the info of the node, which reports whether it is synced and has enough peers.
+    async fn health_check(&mut self) -> Result<()> {
+        let info = self
+            .client()
+            .get_info()
+            .await
+            .map_err(|e| Error::IotaClient("get node info", e))?;
+        match info.nodeinfo.is_healthy {
+            true => Ok(()),
+            false => Err(Error::Unhealthy(info.url)),
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<Message> ConfirmedTransport<'_> for Client<Message, SentMessage>
+where
+    Message: Into<Vec<u8>> + TryFrom<IotaMessage, Error = crate::error::Error> + MaybeSend,
+{
+    /// Polls the metadata of the block a message was sent in, every [`INCLUSION_POLL_INTERVAL`],
+    /// until the block is referenced by a milestone or the node advises to reattach it.
+    ///
+    /// # Arguments
+    /// * `response`: The [`SentMessage`] returned when the message was sent
+    /// * `timeout`: How long to wait for the message to be referenced
+    async fn await_inclusion(&mut self, response: &SentMessage, timeout: Duration) -> Result<Inclusion> {
+        // Polls are counted rather than timed, as there is no clock in wasm environments
+        let polls = timeout.as_millis() / INCLUSION_POLL_INTERVAL.as_millis();
+        let mut poll = 0;
+        loop {
+            let metadata = self
+                .client()
+                .get_message()
+                .metadata(response.message_id())
+                .await
+                .map_err(|e| Error::IotaClient("get message metadata", e))?;
+            if let Some(milestone_index) = metadata.referenced_by_milestone_index {
+                return Ok(Inclusion::Referenced(milestone_index));
+            }
+            if metadata.should_reattach == Some(true) {
+                return Ok(Inclusion::Stale);
+            }
+            if poll >= polls {
+                return Ok(Inclusion::Submitted);
+            }
+            poll += 1;
+            Delay::new(INCLUSION_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<Message, SendResponse> TangleTransportExt<'_> for Client<Message, SendResponse>
+where
+    Message: Into<Vec<u8>> + TryFrom<IotaMessage, Error = crate::error::Error> + MaybeSend,
+    SendResponse: TryFrom<IotaMessage, Error = crate::error::Error> + MaybeSend,
+{
+    /// Returns the ids of the tips the node selects as parents of new messages
+    async fn tips(&mut self) -> Result<Vec<TangleMessageId>> {
+        let tips = self
+            .client()
+            .get_tips()
+            .await
+            .map_err(|e| Error::IotaClient("get tips", e))?;
+        Ok(tips.iter().map(message_id_bytes).collect())
+    }
+
+    /// Returns the ids of every message indexed at the provided [`Address`]
+    ///
+    /// # Arguments
+    /// * `address`: The address the messages are indexed at
+    async fn message_ids(&mut self, address: Address) -> Result<Vec<TangleMessageId>> {
         let msg_ids = self
             .client()
             .get_message()
-            .index(address.to_msg_index())
+            .index(self.2.get_tag_value(address))
             .await
             .map_err(|e| Error::IotaClient("get messages by index", e))?;
+        Ok(msg_ids.iter().map(message_id_bytes).collect())
+    }
+
+    /// Sends a message indexed at the provided [`Address`] to the tangle, attached to the provided
+    /// parents instead of the tips selected by the node.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message to send
+    /// * `msg`: The message to send
+    /// * `parents`: The ids of the messages to attach the message to, between 1 and 8
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    async fn send_message_with_parents(
+        &mut self,
+        address: Address,
+        msg: Message,
+        parents: Vec<TangleMessageId>,
+    ) -> Result<SendResponse>
+    where
+        Message: 'async_trait,
+    {
+        let parents = parents.into_iter().map(MessageId::new).collect();
+        send_indexed(
+            &self.0,
+            self.1.as_ref(),
+            self.3.as_deref(),
+            self.2.get_tag_value(address),
+            msg.into(),
+            Some(parents),
+        )
+        .await?
+        .try_into()
+    }
+}
 
-        if msg_ids.is_empty() {
-            return Err(Error::MessageMissing(address, "transport"));
+/// Sends a message indexed at the provided index to the tangle, tracking its block if the pending
+/// blocks are tracked
+///
+/// # Arguments
+/// * `client`: The `IOTA` [Client](`iota_client::Client`) to send the message with
+/// * `pending`: The ids of the pending blocks, if they are tracked
+/// * `pow`: The [`PowProvider`] computing the nonce of the message, the proof of work of the client
+///   if none is provided
+/// * `index`: The index of the message to send
+/// * `data`: The binary message to send
+/// * `parents`: The ids of the messages to attach the message to, the tips selected by the node if
+///   none are provided
+async fn send_indexed(
+    client: &iota_client::Client,
+    pending: Option<&PendingBlocks>,
+    pow: Option<&dyn PowProvider>,
+    index: Vec<u8>,
+    data: Vec<u8>,
+    parents: Option<Vec<MessageId>>,
+) -> Result<IotaMessage> {
+    let message = match pow {
+        Some(pow) => {
+            let message = pow_message(client, pow, index, data, parents).await?;
+            client
+                .post_message(&message)
+                .await
+                .map_err(|e| Error::IotaClient("sending message", e))?;
+            message
         }
+        None => {
+            let mut builder = client.message().with_index(index).with_data(data);
+            if let Some(parents) = parents {
+                builder = builder
+                    .with_parents(parents)
+                    .map_err(|e| Error::IotaClient("select message parents", e))?;
+            }
+            builder
+                .finish()
+                .await
+                .map_err(|e| Error::IotaClient("sending message", e))?
+        }
+    };
+    if let Some(pending) = pending {
+        pending.lock().insert(message.id().0);
+    }
+    Ok(message)
+}
 
-        let msgs = try_join_all(msg_ids.iter().map(|msg| {
-            self.client()
-                .get_message()
-                .data(msg)
-                .map_err(|e| Error::IotaClient("receiving message", e))
-                .and_then(|iota_message| ready(iota_message.try_into()))
-        }))
+/// Retrieves a page of the messages indexed at the provided index. The ids of the messages are
+/// listed first, and only the messages of the page are fetched.
+///
+/// # Arguments
+/// * `client`: The `IOTA` [Client](`iota_client::Client`) to retrieve the messages with
+/// * `address`: The [`Address`] the index is derived from, reported when no message is found
+/// * `index`: The index of the messages
+/// * `offset`: The number of messages to skip
+/// * `limit`: The maximum number of messages to retrieve
+async fn recv_indexed<Message>(
+    client: &iota_client::Client,
+    address: Address,
+    index: Vec<u8>,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<Message>>
+where
+    Message: TryFrom<IotaMessage, Error = crate::error::Error>,
+{
+    let msg_ids = client
+        .get_message()
+        .index(index)
+        .await
+        .map_err(|e| Error::IotaClient("get messages by index", e))?;
+
+    if msg_ids.is_empty() {
+        return Err(Error::MessageNotFound(address));
+    }
+
+    try_join_all(msg_ids.iter().skip(offset).take(limit).map(|msg| {
+        client
+            .get_message()
+            .data(msg)
+            .map_err(|e| Error::IotaClient("receiving message", e))
+            .and_then(|iota_message| ready(iota_message.try_into()))
+    }))
+    .await
+}
+
+/// Builds a message indexed at the provided index, its nonce computed by a [`PowProvider`]
+///
+/// # Arguments
+/// * `client`: The `IOTA` [Client](`iota_client::Client`) querying the parameters of the network
+/// * `pow`: The [`PowProvider`] computing the nonce of the message
+/// * `index`: The index of the message
+/// * `data`: The binary message
+/// * `parents`: The ids of the messages to attach the message to, the tips selected by the node if
+///   none are provided
+async fn pow_message(
+    client: &iota_client::Client,
+    pow: &dyn PowProvider,
+    index: Vec<u8>,
+    data: Vec<u8>,
+    parents: Option<Vec<MessageId>>,
+) -> Result<IotaMessage> {
+    let mut parents = match parents {
+        Some(parents) => parents,
+        None => client.get_tips().await.map_err(|e| Error::IotaClient("get tips", e))?,
+    };
+    // Parents must be sorted and unique
+    parents.sort_unstable();
+    parents.dedup();
+    let network_id = client
+        .get_network_id()
+        .await
+        .map_err(|e| Error::IotaClient("get network id", e))?;
+    let target_score = client
+        .get_min_pow_score()
+        .await
+        .map_err(|e| Error::IotaClient("get minimum proof of work score", e))?;
+    let nonce = pow
+        .nonce(&pow::pow_input(network_id, &parents, &index, &data), target_score)
         .await?;
-        Ok(msgs)
+
+    let payload = IndexationPayload::new(&index, &data)
+        .map_err(|e| Error::External(anyhow::anyhow!("invalid indexation payload: {}", e)))?;
+    let parents = Parents::new(parents).map_err(|e| Error::External(anyhow::anyhow!("invalid parents: {}", e)))?;
+    MessageBuilder::<Constant>::new()
+        .with_network_id(network_id)
+        .with_parents(parents)
+        .with_payload(Payload::Indexation(Box::new(payload)))
+        .with_nonce_provider(ConstantBuilder::new().with_value(nonce).finish(), target_score)
+        .finish()
+        .map_err(|e| Error::External(anyhow::anyhow!("invalid message: {}", e)))
+}
+
+/// Returns the bytes of a [`MessageId`]
+///
+/// # Arguments
+/// * `id`: The [`MessageId`] of a message of the tangle
+fn message_id_bytes(id: &MessageId) -> TangleMessageId {
+    let mut bytes = TangleMessageId::default();
+    bytes.copy_from_slice(id.as_ref());
+    bytes
+}
+
+/// Checks the pending blocks once, forgetting the ones referenced by a milestone, and reattaching
+/// or promoting the others when the node advises to. Returns the number of blocks that were
+/// reattached or promoted.
+///
+/// # Arguments
+/// * `client`: The `IOTA` [Client](`iota_client::Client`) the blocks were sent with
+/// * `pending`: The ids of the pending blocks
+async fn reattach_blocks(client: &iota_client::Client, pending: &Mutex<BTreeSet<MessageId>>) -> Result<usize> {
+    let blocks: Vec<MessageId> = pending.lock().iter().copied().collect();
+    let mut reattached = 0;
+    for block in blocks {
+        let metadata = client
+            .get_message()
+            .metadata(&block)
+            .await
+            .map_err(|e| Error::IotaClient("get message metadata", e))?;
+        if metadata.referenced_by_milestone_index.is_some() {
+            pending.lock().remove(&block);
+        } else if metadata.should_reattach == Some(true) {
+            let (reattachment, _) = client
+                .reattach_unchecked(&block)
+                .await
+                .map_err(|e| Error::IotaClient("reattach message", e))?;
+            // The reattachment carries the same payload, only its inclusion matters from now on
+            let mut pending = pending.lock();
+            pending.remove(&block);
+            pending.insert(reattachment);
+            reattached += 1;
+        } else if metadata.should_promote == Some(true) {
+            client
+                .promote_unchecked(&block)
+                .await
+                .map_err(|e| Error::IotaClient("promote message", e))?;
+            reattached += 1;
+        }
+    }
+    Ok(reattached)
+}
+
+/// Response of the [`Client`] to a sent message, identifying the block the message was sent in so
+/// that its inclusion can be awaited with [`ConfirmedTransport::await_inclusion`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentMessage {
+    /// The [`MessageId`] of the block holding the message
+    message_id: MessageId,
+    /// The message that was sent
+    message: TransportMessage,
+}
+
+impl SentMessage {
+    /// Returns the [`MessageId`] of the block holding the message
+    pub fn message_id(&self) -> &MessageId {
+        &self.message_id
+    }
+
+    /// Returns a reference to the message that was sent
+    pub fn message(&self) -> &TransportMessage {
+        &self.message
+    }
+
+    /// Consumes the [`SentMessage`], returning the message that was sent
+    pub fn into_message(self) -> TransportMessage {
+        self.message
+    }
+}
+
+impl TryFrom<IotaMessage> for SentMessage {
+    type Error = crate::error::Error;
+    fn try_from(message: IotaMessage) -> Result<Self> {
+        Ok(Self {
+            message_id: message.id().0,
+            message: message.try_into()?,
+        })
     }
 }
 
@@ -172,4 +653,55 @@ mod tests {
         assert_eq!(msg, response);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn await_inclusion() -> Result<()> {
+        let mut client = Client::<TransportMessage, SentMessage>::for_node("https://chrysalis-nodes.iota.org").await?;
+        let msg = TransportMessage::new(vec![12; 1024]);
+        let response = client
+            .send_message(
+                Address::new(
+                    AppAddr::default(),
+                    MsgId::gen(
+                        AppAddr::default(),
+                        &Identifier::default(),
+                        &Topic::default(),
+                        Utc::now().timestamp_millis() as usize,
+                    ),
+                ),
+                msg.clone(),
+            )
+            .await?;
+        assert_eq!(response.message(), &msg);
+        // Messages are usually referenced by one of the next few milestones
+        let inclusion = client.await_inclusion(&response, Duration::from_secs(60)).await?;
+        assert!(matches!(inclusion, Inclusion::Referenced(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reattach_pending() -> Result<()> {
+        let mut client = Client::<TransportMessage, TransportMessage>::for_node("https://chrysalis-nodes.iota.org")
+            .await?
+            .with_pending_tracking();
+        client
+            .send_message(
+                Address::new(
+                    AppAddr::default(),
+                    MsgId::gen(
+                        AppAddr::default(),
+                        &Identifier::default(),
+                        &Topic::default(),
+                        Utc::now().timestamp_millis() as usize,
+                    ),
+                ),
+                TransportMessage::new(vec![12; 1024]),
+            )
+            .await?;
+        assert_eq!(client.pending(), 1);
+        // A block just sent is neither referenced nor advised to be reattached yet
+        client.reattach_pending().await?;
+        assert_eq!(client.pending(), 1);
+        Ok(())
+    }
 }
@@ -1,5 +1,5 @@
 // Rust
-use alloc::{boxed::Box, string::String, vec::Vec};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use core::{
     convert::{TryFrom, TryInto},
     marker::PhantomData,
@@ -7,16 +7,15 @@ use core::{
 
 // 3rd-party
 use async_trait::async_trait;
-use rayon::prelude::*;
+use futures::future::join_all;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Certificate,
+};
 use serde::{de::DeserializeOwned, Deserialize};
 
 // IOTA
-use bee_ternary::{b1t6, Btrit, T1B1Buf, TritBuf};
-use crypto::hashes::{
-    blake2b::Blake2b256,
-    ternary::{self, curl_p},
-    Digest,
-};
+use crypto::hashes::{blake2b::Blake2b256, Digest};
 
 // Streams
 
@@ -25,14 +24,15 @@ use crate::{
     address::Address,
     error::{Error, Result},
     message::TransportMessage,
-    transport::Transport,
+    sync::MaybeSend,
+    transport::{
+        index::{AddressIndex, MessageIndex},
+        pow::{self, LocalPow, PowProvider},
+        SendReceipt, SentBlock, TangleMessageId, TangleTransportExt, Transport, TransportCapabilities,
+        TANGLE_CAPABILITIES,
+    },
 };
 
-const NONCE_SIZE: usize = core::mem::size_of::<u64>();
-// Precomputed natural logarithm of 3 for performance reasons.
-// See https://oeis.org/A002391.
-const LN_3: f64 = 1.098_612_288_668_109;
-
 /// A [`Transport`] Client for sending and retrieving binary messages from an `IOTA Tangle` node.
 /// This Client uses a lightweight [reqwest](`reqwest::Client`) Client implementation.
 #[derive(Debug, Clone)]
@@ -41,16 +41,22 @@ pub struct Client<Message = TransportMessage, SendResponse = Ignored> {
     node_url: String,
     /// HTTP Client
     client: reqwest::Client,
+    /// Headers sent with every request to the node
+    headers: HeaderMap,
+    /// Certificates trusted in addition to the system ones
+    root_certificates: Vec<Certificate>,
+    /// Whether invalid TLS certificates of the node are accepted
+    accept_invalid_certs: bool,
+    /// Derivation of the indexes of the messages
+    index: Arc<dyn MessageIndex>,
+    /// Computation of the nonce of the messages
+    pow: Arc<dyn PowProvider>,
     _phantom: PhantomData<(Message, SendResponse)>,
 }
 
 impl<M, S> Default for Client<M, S> {
     fn default() -> Self {
-        Self {
-            node_url: String::from("https://chrysalis-nodes.iota.org"),
-            client: reqwest::Client::new(),
-            _phantom: PhantomData,
-        }
+        Self::new("https://chrysalis-nodes.iota.org")
     }
 }
 
@@ -66,10 +72,96 @@ impl<Message, SendResponse> Client<Message, SendResponse> {
         Self {
             node_url: node_url.into(),
             client: reqwest::Client::new(),
+            headers: HeaderMap::new(),
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            index: Arc::new(AddressIndex),
+            pow: Arc::new(LocalPow),
             _phantom: PhantomData,
         }
     }
 
+    /// Creates a new `uTangle` [`Client`] connecting to a node, checking that the node is reachable
+    ///
+    /// # Arguments
+    /// * `node_url`: Tangle node endpoint
+    pub async fn for_node(node_url: &str) -> Result<Self> {
+        let client = Self::new(node_url);
+        client.get_network_info().await?;
+        Ok(client)
+    }
+
+    /// Adds a header sent with every request to the node, to authenticate to the node for example
+    ///
+    /// # Arguments
+    /// * `name`: The name of the header
+    /// * `value`: The value of the header
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::External(anyhow::anyhow!("invalid header name '{}': {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::External(anyhow::anyhow!("invalid value for header '{}': {}", name, e)))?;
+        self.headers.insert(name, value);
+        self.rebuild()
+    }
+
+    /// Trusts a certificate in addition to the system ones when connecting to the node, for nodes
+    /// using a self-signed certificate for example
+    ///
+    /// # Arguments
+    /// * `pem`: The PEM encoded certificate
+    pub fn with_root_certificate(mut self, pem: &[u8]) -> Result<Self> {
+        self.root_certificates.push(Certificate::from_pem(pem)?);
+        self.rebuild()
+    }
+
+    /// Sets whether invalid TLS certificates of the node are accepted. Accepting them exposes the
+    /// connection to man-in-the-middle attacks, and should only be done for testing.
+    ///
+    /// # Arguments
+    /// * `accept_invalid_certs`: Whether invalid certificates are accepted
+    pub fn with_danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Result<Self> {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self.rebuild()
+    }
+
+    /// Sets the [`MessageIndex`] deriving the indexes the messages are sent and looked up at,
+    /// [`AddressIndex`] by default
+    ///
+    /// # Arguments
+    /// * `index`: The [`MessageIndex`] of the messages
+    pub fn with_message_index<I>(mut self, index: I) -> Self
+    where
+        I: MessageIndex + 'static,
+    {
+        self.index = Arc::new(index);
+        self
+    }
+
+    /// Sets the [`PowProvider`] computing the nonce of the messages sent, [`LocalPow`] by default
+    ///
+    /// # Arguments
+    /// * `pow`: The [`PowProvider`] of the messages
+    pub fn with_pow_provider<P>(mut self, pow: P) -> Self
+    where
+        P: PowProvider + 'static,
+    {
+        self.pow = Arc::new(pow);
+        self
+    }
+
+    /// Rebuilds the HTTP client with the headers and TLS options of the [`Client`]
+    fn rebuild(mut self) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .default_headers(self.headers.clone())
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        for certificate in &self.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+        self.client = builder.build()?;
+        Ok(self)
+    }
+
     /// Returns basic network details from node request
     async fn get_network_info(&self) -> Result<NetworkInfo> {
         let network_info_path = "api/v1/info";
@@ -83,6 +175,20 @@ impl<Message, SendResponse> Client<Message, SendResponse> {
         Ok(network_info.data)
     }
 
+    /// Requests the health endpoint of the node, failing if the node reports itself unhealthy
+    async fn get_health(&self) -> Result<()> {
+        let health_path = "health";
+        let response = self
+            .client
+            .get(format!("{}/{}", self.node_url, health_path))
+            .send()
+            .await?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(Error::Unhealthy(self.node_url.clone())),
+        }
+    }
+
     /// Returns [`Tips`] from node request
     async fn get_tips(&self) -> Result<Tips> {
         let tips_path = "api/v1/tips";
@@ -96,48 +202,149 @@ impl<Message, SendResponse> Client<Message, SendResponse> {
         Ok(tips.data)
     }
 
-    /// Serialise message contents into single byte array for sending
+    /// Returns the hex encoded ids of the messages indexed at the provided [`Address`]
+    ///
+    /// # Arguments
+    /// * `address`: The address the messages are indexed at
+    async fn get_message_ids(&self, address: Address) -> Result<Vec<String>> {
+        let path = "api/v1/messages";
+        let index_data: Response<IndexResponse> = self
+            .client
+            .get(format!("{}/{}", self.node_url, path))
+            .query(&[("index", hex::encode(self.index.get_tag_value(address)))])
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(index_data.data.message_ids)
+    }
+
+    /// Returns the message with the provided hex encoded id
+    ///
+    /// # Arguments
+    /// * `msg_id`: The hex encoded id of the message
+    async fn get_message(&self, msg_id: &str) -> Result<TangleMessage> {
+        let path = "api/v1/messages";
+        let msg: Response<TangleMessage> = self
+            .client
+            .get(format!("{}/{}/{}", self.node_url, path, msg_id))
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(msg.data)
+    }
+
+    /// Packs a message attached to the provided tips and posts it to the node
+    ///
+    /// # Arguments
+    /// * `tips`: [`Tips`] the message is attached to
+    /// * `address`: Address of the message being sent
+    /// * `msg`: Payload bytes for the message
+    async fn post_message<R>(&self, tips: Tips, address: Address, msg: &[u8]) -> Result<R>
+    where
+        R: DeserializeOwned,
+    {
+        self.post_message_receipt(tips, address, msg)
+            .await
+            .map(|receipt| receipt.response)
+    }
+
+    /// Sends a message to the node like [`Client::post_message()`], returning the [`SendReceipt`]
+    /// of the block it was published as. The id of the block is the `Blake2b256` hash of its
+    /// serialization.
     ///
     /// # Arguments
-    /// * `network_info`: [`NetworkInfo`] response from node
     /// * `tips`: [`Tips`] response from node
     /// * `address`: Address of the message being sent
     /// * `msg`: Payload bytes for the message
-    fn pack_message(&self, network_info: NetworkInfo, tips: Tips, address: Address, msg: &[u8]) -> Result<Vec<u8>> {
-        let mut message_bytes = Vec::new();
-        // Network-ID
-        message_bytes.extend(&Blake2b256::digest(network_info.network_id.as_bytes())[..8]);
-        // Parent Messages
-        message_bytes.extend((tips.ids.len() as u8).to_le_bytes());
-        for tip in tips.ids {
-            message_bytes.extend(hex::decode(tip)?);
-        }
+    async fn post_message_receipt<R>(&self, tips: Tips, address: Address, msg: &[u8]) -> Result<SendReceipt<R>>
+    where
+        R: DeserializeOwned,
+    {
+        let network_info = self.get_network_info().await?;
+        let message_bytes = self.pack_message(network_info, tips, address, msg).await?;
+        let block = SentBlock::new(Blake2b256::digest(&message_bytes).to_vec(), None);
 
-        let index = address.to_msg_index();
-        // Size of whole payload (payload-type + index-size + index + data-size + data)
-        message_bytes.extend(((4 + 2 + index.len() + 4 + msg.len()) as u32).to_le_bytes());
-        // payload-type (Indexation = 2)
-        message_bytes.extend(2_u32.to_le_bytes());
-        // index-size
-        message_bytes.extend((index.len() as u16).to_le_bytes());
-        // index
-        message_bytes.extend(index);
-        // data-size
-        message_bytes.extend((msg.len() as u32).to_le_bytes());
-        // data
-        message_bytes.extend(msg);
+        let path = "api/v1/messages";
+        let response: R = self
+            .client
+            .post(format!("{}/{}", self.node_url, path))
+            .header("Content-Type", "application/octet-stream")
+            .body(message_bytes)
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(SendReceipt::new(
+            self.index.get_tag_value(address),
+            vec![block],
+            response,
+        ))
+    }
+
+    /// Serialise message contents into single byte array for sending, computing its nonce with the
+    /// [`PowProvider`] of the [`Client`]
+    ///
+    /// # Arguments
+    /// * `network_info`: [`NetworkInfo`] response from node
+    /// * `tips`: [`Tips`] response from node
+    /// * `address`: Address of the message being sent
+    /// * `msg`: Payload bytes for the message
+    async fn pack_message(
+        &self,
+        network_info: NetworkInfo,
+        tips: Tips,
+        address: Address,
+        msg: &[u8],
+    ) -> Result<Vec<u8>> {
+        let parents = tips
+            .ids
+            .iter()
+            .map(hex::decode)
+            .collect::<core::result::Result<Vec<_>, _>>()?;
+        let index = self.index.get_tag_value(address);
+        let mut message_bytes = pow::pow_input(pow::network_id(&network_info.network_id), &parents, &index, msg);
         // nonce
-        message_bytes.extend(nonce(&message_bytes, network_info.min_pow_score)?.to_le_bytes());
+        let nonce = self.pow.nonce(&message_bytes, network_info.min_pow_score).await?;
+        message_bytes.extend(nonce.to_le_bytes());
 
         Ok(message_bytes)
     }
 }
 
-#[async_trait(?Send)]
+impl<Message, SendResponse> Client<Message, SendResponse>
+where
+    Message: TryFrom<TangleMessage, Error = crate::error::Error>,
+{
+    /// Retrieves a page of the messages indexed at the provided [`Address`]. The ids of the
+    /// messages are listed first, and only the messages of the page are fetched. Errors if no
+    /// messages are found.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the messages to retrieve
+    /// * `offset`: The number of messages to skip
+    /// * `limit`: The maximum number of messages to retrieve
+    async fn recv_page(&self, address: Address, offset: usize, limit: usize) -> Result<Vec<Message>> {
+        let msg_ids = self.get_message_ids(address).await?;
+        if msg_ids.is_empty() {
+            return Err(Error::MessageNotFound(address));
+        }
+
+        let mut msgs = Vec::new();
+        for msg_id in msg_ids.iter().skip(offset).take(limit) {
+            msgs.push(self.get_message(msg_id).await?.try_into()?);
+        }
+        Ok(msgs)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<Message, SendResponse> Transport<'_> for Client<Message, SendResponse>
 where
-    Message: AsRef<[u8]> + TryFrom<TangleMessage, Error = crate::error::Error>,
-    SendResponse: DeserializeOwned,
+    Message: AsRef<[u8]> + TryFrom<TangleMessage, Error = crate::error::Error> + MaybeSend,
+    SendResponse: DeserializeOwned + MaybeSend,
 {
     type Msg = Message;
     type SendResponse = SendResponse;
@@ -147,87 +354,139 @@ where
     /// # Arguments
     /// * `address`: The address of the message.
     /// * `msg`: Message - The message to send.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
     async fn send_message(&mut self, address: Address, msg: Message) -> Result<SendResponse>
     where
         Message: 'async_trait,
     {
-        let network_info = self.get_network_info().await?;
         let tips = self.get_tips().await?;
+        self.post_message(tips, address, msg.as_ref()).await
+    }
 
-        let message_bytes = self.pack_message(network_info, tips, address, msg.as_ref())?;
-
-        let path = "api/v1/messages";
-        let response: SendResponse = self
-            .client
-            .post(format!("{}/{}", self.node_url, path))
-            .header("Content-Type", "application/octet-stream")
-            .body(message_bytes)
-            .send()
-            .await?
-            .json()
-            .await?;
-        Ok(response)
+    /// Sends a message indexed at the provided [`Address`] to the tangle, reporting the id of the
+    /// block it was published as.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message.
+    /// * `msg`: Message - The message to send.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    async fn send_message_receipt(&mut self, address: Address, msg: Message) -> Result<SendReceipt<SendResponse>>
+    where
+        Message: 'async_trait,
+    {
+        let tips = self.get_tips().await?;
+        self.post_message_receipt(tips, address, msg.as_ref()).await
     }
 
-    /// Retrieves a message indexed at the provided [`Address`] from the tangle. Errors if no
+    /// Retrieves the messages indexed at the provided [`Address`] from the tangle. Errors if no
     /// messages are found.
     ///
     /// # Arguments
     /// * `address`: The address of the message to retrieve.
     async fn recv_messages(&mut self, address: Address) -> Result<Vec<Message>> {
-        let path = "api/v1/messages";
-        let index_data: Response<IndexResponse> = self
-            .client
-            .get(format!("{}/{}", self.node_url, path))
-            .query(&[("index", hex::encode(address.to_msg_index()))])
-            .send()
+        self.recv_messages_paged(address, 0, usize::MAX).await
+    }
+
+    /// Retrieves a page of the messages indexed at the provided [`Address`] from the tangle. The
+    /// ids of the messages are listed first, and only the messages of the page are fetched. Errors
+    /// if no messages are found.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the messages to retrieve.
+    /// * `offset`: The number of messages to skip.
+    /// * `limit`: The maximum number of messages to retrieve.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address, offset, limit)))]
+    async fn recv_messages_paged(&mut self, address: Address, offset: usize, limit: usize) -> Result<Vec<Message>> {
+        self.recv_page(address, offset, limit).await
+    }
+
+    /// Retrieves the messages indexed at several [`Address`]es from the tangle, requesting the
+    /// addresses concurrently.
+    ///
+    /// # Arguments
+    /// * `addresses`: The addresses of the messages to retrieve.
+    /// * `limit`: The maximum number of messages to retrieve per address.
+    async fn recv_messages_batch(&mut self, addresses: Vec<Address>, limit: usize) -> Vec<Result<Vec<Message>>> {
+        let client = &*self;
+        join_all(addresses.into_iter().map(|address| client.recv_page(address, 0, limit))).await
+    }
+
+    /// Reports the constraints of the tangle, like the `iota.rs` based client.
+    async fn capabilities(&mut self) -> TransportCapabilities {
+        TANGLE_CAPABILITIES
+    }
+
+    /// Pings the health endpoint of the node, which fails unless the node is synced and has
+    /// enough peers.
+    async fn health_check(&mut self) -> Result<()> {
+        self.get_health().await
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<Message, SendResponse> TangleTransportExt<'_> for Client<Message, SendResponse>
+where
+    Message: AsRef<[u8]> + TryFrom<TangleMessage, Error = crate::error::Error> + MaybeSend,
+    SendResponse: DeserializeOwned + MaybeSend,
+{
+    /// Returns the ids of the tips the node selects as parents of new messages
+    async fn tips(&mut self) -> Result<Vec<TangleMessageId>> {
+        self.get_tips()
             .await?
-            .json()
-            .await?;
+            .ids
+            .iter()
+            .map(|id| decode_message_id(id))
+            .collect()
+    }
 
-        let msg_id = index_data
-            .data
-            .message_ids
-            .first()
-            .ok_or(Error::AddressError("No message found", address))?;
-        let msg: Response<TangleMessage> = self
-            .client
-            .get(format!("{}/{}/{}", self.node_url, path, msg_id))
-            .send()
+    /// Returns the ids of every message indexed at the provided [`Address`]
+    ///
+    /// # Arguments
+    /// * `address`: The address the messages are indexed at
+    async fn message_ids(&mut self, address: Address) -> Result<Vec<TangleMessageId>> {
+        self.get_message_ids(address)
             .await?
-            .json()
-            .await?;
-        Ok(vec![msg.data.try_into()?])
+            .iter()
+            .map(|id| decode_message_id(id))
+            .collect()
+    }
+
+    /// Sends a message indexed at the provided [`Address`] to the tangle, attached to the provided
+    /// parents instead of the tips selected by the node.
+    ///
+    /// # Arguments
+    /// * `address`: The address of the message to send
+    /// * `msg`: The message to send
+    /// * `parents`: The ids of the messages to attach the message to, between 1 and 8
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    async fn send_message_with_parents(
+        &mut self,
+        address: Address,
+        msg: Message,
+        mut parents: Vec<TangleMessageId>,
+    ) -> Result<SendResponse>
+    where
+        Message: 'async_trait,
+    {
+        // Parents must be sorted and unique
+        parents.sort_unstable();
+        parents.dedup();
+        let tips = Tips {
+            ids: parents.iter().map(hex::encode).collect(),
+        };
+        self.post_message(tips, address, msg.as_ref()).await
     }
 }
 
-fn nonce(data: &[u8], target_score: f64) -> Result<u64> {
-    let target_zeros = (((data.len() + NONCE_SIZE) as f64 * target_score).ln() / LN_3).ceil() as usize;
-    let hash = Blake2b256::digest(data);
-    let mut pow_digest = TritBuf::<T1B1Buf>::new();
-    b1t6::encode::<T1B1Buf>(&hash).iter().for_each(|t| pow_digest.push(t));
-    (0..u32::MAX)
-        .into_par_iter()
-        .step_by(curl_p::BATCH_SIZE)
-        .find_map_any(|n| {
-            let mut hasher = curl_p::CurlPBatchHasher::<T1B1Buf>::new(ternary::HASH_LENGTH);
-            for i in 0..curl_p::BATCH_SIZE {
-                let mut buffer = TritBuf::<T1B1Buf>::zeros(ternary::HASH_LENGTH);
-                buffer[..pow_digest.len()].copy_from(&pow_digest);
-                let nonce_trits = b1t6::encode::<T1B1Buf>(&(n as u64 + i as u64).to_le_bytes());
-                buffer[pow_digest.len()..pow_digest.len() + nonce_trits.len()].copy_from(&nonce_trits);
-                hasher.add(buffer);
-            }
-            for (i, hash) in hasher.hash().enumerate() {
-                let trailing_zeros = hash.iter().rev().take_while(|t| *t == Btrit::Zero).count();
-
-                if trailing_zeros >= target_zeros {
-                    return Some(n as u64 + i as u64);
-                }
-            }
-            None
-        })
-        .ok_or(Error::Nonce(target_score))
+/// Decodes a hex encoded message id returned by the node
+///
+/// # Arguments
+/// * `id`: The hex encoded message id
+fn decode_message_id(id: &str) -> Result<TangleMessageId> {
+    let bytes = hex::decode(id)?;
+    let len = bytes.len();
+    TangleMessageId::try_from(bytes).map_err(|_| Error::InvalidSize("message id", 32, len as u64))
 }
 
 #[derive(Deserialize)]
@@ -306,4 +565,27 @@ mod tests {
         assert_eq!(msg, response);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn send_message_with_parents() -> Result<()> {
+        let mut client = Client::for_node("https://chrysalis-nodes.iota.org").await?;
+        let msg = TransportMessage::new(vec![12; 1024]);
+        let address = Address::new(
+            AppAddr::default(),
+            MsgId::gen(
+                AppAddr::default(),
+                &Identifier::default(),
+                &Topic::default(),
+                Utc::now().timestamp_millis() as usize,
+            ),
+        );
+        let mut parents = client.tips().await?;
+        parents.truncate(1);
+        let _: serde_json::Value = client.send_message_with_parents(address, msg.clone(), parents).await?;
+
+        assert_eq!(client.message_ids(address).await?.len(), 1);
+        let response = client.recv_message(address).await?;
+        assert_eq!(msg, response);
+        Ok(())
+    }
 }
@@ -0,0 +1,85 @@
+// Rust
+
+// 3rd-party
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// IOTA
+
+// Streams
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Mask, Squeeze},
+        pool::BufferPool,
+        types::{Bytes, Mac},
+    },
+    error::Result,
+    KeccakF1600,
+};
+
+/// Length of the authentication tag of the benchmarked messages
+const MAC: Mac = Mac::new(32);
+
+/// Wraps a payload into a fresh buffer and unwraps it into a fresh vector, as done for every
+/// message without a pool
+fn roundtrip_fresh(payload: &[u8]) -> Result<Vec<u8>> {
+    let size = sizeof::Context::new()
+        .mask(Bytes::new(payload))?
+        .commit()?
+        .squeeze(&MAC)?
+        .finalize();
+    let mut buf = vec![0; size];
+    wrap::Context::<_, KeccakF1600>::new(&mut buf[..])
+        .mask(Bytes::new(payload))?
+        .commit()?
+        .squeeze(&MAC)?;
+
+    let mut decoded = Vec::new();
+    unwrap::Context::<_, KeccakF1600>::new(&buf[..])
+        .mask(Bytes::new(&mut decoded))?
+        .commit()?
+        .squeeze(&MAC)?;
+    Ok(decoded)
+}
+
+/// Wraps a payload into a pooled buffer and unwraps it into a pooled vector, recycling both
+fn roundtrip_pooled(pool: &mut BufferPool, payload: &[u8]) -> Result<()> {
+    let size = sizeof::Context::new()
+        .mask(Bytes::new(payload))?
+        .commit()?
+        .squeeze(&MAC)?
+        .finalize();
+    let mut buf = pool.take(size);
+    wrap::Context::<_, KeccakF1600>::new(&mut buf[..])
+        .mask(Bytes::new(payload))?
+        .commit()?
+        .squeeze(&MAC)?;
+
+    let mut decoded = pool.take(0);
+    unwrap::Context::<_, KeccakF1600>::new(&buf[..])
+        .mask(Bytes::new(&mut decoded))?
+        .commit()?
+        .squeeze(&MAC)?;
+    pool.recycle(decoded);
+    pool.recycle(buf);
+    Ok(())
+}
+
+fn bench_contexts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Wrap and Unwrap by Payload Size");
+    for size in [32, 256, 1024, 4096] {
+        let payload = vec![7u8; size];
+        // Report messages per second
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(BenchmarkId::new("fresh buffers", size), &payload, |b, payload| {
+            b.iter(|| roundtrip_fresh(payload).unwrap())
+        });
+        let mut pool = BufferPool::default();
+        group.bench_with_input(BenchmarkId::new("pooled buffers", size), &payload, |b, payload| {
+            b.iter(|| roundtrip_pooled(&mut pool, payload).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_contexts);
+criterion_main!(benches);
@@ -254,6 +254,16 @@ impl<F: PRP> Spongos<F> {
         }
     }
 
+    /// Erase the outer state and transform it. Unlike [`Spongos::commit`], this is not reversible:
+    /// the previous states of the [`Spongos`] cannot be recovered from the resulting one.
+    pub fn ratchet(&mut self) {
+        self.commit();
+        for o in self.s.outer_mut() {
+            *o = 0
+        }
+        self.s.transform();
+    }
+
     /// Check whether [`Spongos`] state is committed.
     pub fn is_committed(&self) -> bool {
         0 == self.pos
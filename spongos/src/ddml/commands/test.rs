@@ -12,10 +12,13 @@ use crate::{
     },
     ddml::{
         commands::{sizeof, unwrap, wrap, Absorb, Commit, Ed25519, Mask, Squeeze, X25519},
+        io,
+        layout::{Command, LayoutStream},
         modifiers::External,
+        pool::BufferPool,
         types::{Bytes, Mac, NBytes, Size, Uint8},
     },
-    error::Result,
+    error::{Error, Result},
 };
 
 fn absorb_mask_u8<F>() -> Result<()>
@@ -228,6 +231,111 @@ fn bytes() {
     assert!(absorb_mask_squeeze_bytes_mac::<KeccakF1600>().is_ok());
 }
 
+fn mask_bytes_size_limit<F>() -> Result<()>
+where
+    F: PRP + Default,
+{
+    let mut prng = SpongosRng::<F>::new("Spongos tests");
+    let tm: Bytes = prng.borrow_mut().sample_iter(Standard).take(100).collect();
+
+    let buf_size = sizeof::Context::new().mask(tm.as_ref())?.finalize();
+    let mut buf = vec![0u8; buf_size];
+    wrap::Context::<_, F>::new(&mut buf[..]).mask(tm.as_ref())?;
+
+    // Within the limit
+    let mut tm2 = Bytes::<Vec<u8>>::default();
+    unwrap::Context::<_, F>::new(&buf[..])
+        .with_size_limit(100)
+        .mask(tm2.as_mut())?;
+    assert_eq!(tm, tm2, "Error comparing Bytes");
+
+    // Beyond the limit
+    let mut tm3 = Bytes::<Vec<u8>>::default();
+    let result = unwrap::Context::<_, F>::new(&buf[..])
+        .with_size_limit(99)
+        .mask(tm3.as_mut())
+        .map(|_| ());
+    assert!(matches!(result, Err(Error::InvalidSize("bytes", 99, 100))));
+
+    // Beyond the input stream, whatever the limit
+    let mut tm4 = Bytes::<Vec<u8>>::default();
+    let result = unwrap::Context::<_, F>::new(&buf[..buf_size - 1])
+        .mask(tm4.as_mut())
+        .map(|_| ());
+    assert!(matches!(result, Err(Error::StreamAllocationExceededIn(100, 99))));
+
+    // Size encodings wider than a usize
+    let wide = [9u8, 1, 1, 1, 1, 1, 1, 1, 1, 1];
+    let mut size = Size::default();
+    let result = unwrap::Context::<_, F>::new(&wide[..]).absorb(&mut size).map(|_| ());
+    assert!(matches!(result, Err(Error::InvalidSize("size encoding", _, 9))));
+
+    Ok(())
+}
+
+#[test]
+fn bytes_size_limit() {
+    assert!(mask_bytes_size_limit::<KeccakF1600>().is_ok());
+}
+
+fn masked_reader_chunks<F>() -> Result<()>
+where
+    F: PRP + Default,
+{
+    let mut prng = SpongosRng::<F>::new("Spongos tests");
+    let tm: Bytes = prng.borrow_mut().sample_iter(Standard).take(1000).collect();
+    let trailer = Uint8::new(7);
+    let mut tag_wrap = [0; 32];
+
+    let buf_size = sizeof::Context::new().mask(tm.as_ref())?.absorb(trailer)?.finalize();
+    let mut buf = vec![0u8; buf_size];
+    wrap::Context::<_, F>::new(&mut buf[..])
+        .mask(tm.as_ref())?
+        .absorb(trailer)?
+        .commit()?
+        .squeeze(External::new(&mut NBytes::new(&mut tag_wrap)))?;
+
+    // Read whole in small chunks, or partially and then skipped when closing the reader
+    for read_up_to in [1000, 10] {
+        let ctx = unwrap::Context::<_, F>::new(io::Chunked::new(&buf[..], buf.len()));
+        let mut reader = unwrap::MaskedReader::new(ctx)?;
+        assert_eq!(reader.len(), 1000);
+
+        let mut tm2 = Vec::new();
+        let mut chunk = [0u8; 7];
+        while tm2.len() < read_up_to {
+            let n = reader.read(&mut chunk)?;
+            assert_ne!(n, 0, "Masked bytes exhausted early");
+            tm2.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(&tm.as_slice()[..tm2.len()], &tm2[..], "Error comparing masked chunks");
+
+        let mut trailer2 = Uint8::default();
+        let mut tag_unwrap = [0; 32];
+        let mut ctx = reader.into_context()?;
+        ctx.absorb(&mut trailer2)?
+            .commit()?
+            .squeeze(External::new(&mut NBytes::new(&mut tag_unwrap)))?;
+        assert_eq!(ctx.stream().remaining(), 0, "Input stream has not been exhausted");
+        assert_eq!(trailer, trailer2, "Error comparing trailer");
+        assert_eq!(tag_wrap, tag_unwrap, "Error comparing tags");
+    }
+
+    // Truncated streams are reported while reading
+    let ctx = unwrap::Context::<_, F>::new(io::Chunked::new(&buf[..500], buf.len()));
+    let mut reader = unwrap::MaskedReader::new(ctx)?;
+    let mut chunk = [0u8; 100];
+    let result = (0..10).try_for_each(|_| reader.read(&mut chunk).map(|_| ()));
+    assert!(matches!(result, Err(Error::StreamAllocationExceededIn(100, _))));
+
+    Ok(())
+}
+
+#[test]
+fn masked_reader() {
+    assert!(masked_reader_chunks::<KeccakF1600>().is_ok());
+}
+
 fn absorb_ed25519<F: PRP + Default>() -> Result<()> {
     let secret = ed25519::SecretKey::from_bytes([7; ed25519::SECRET_KEY_LENGTH]);
 
@@ -321,3 +429,70 @@ fn x25519_transport<F: PRP + Default>() -> Result<()> {
 fn test_x25519() {
     assert!(x25519_transport::<KeccakF1600>().is_ok());
 }
+
+#[test]
+fn buffer_pool_reuses_buffers() {
+    let mut pool = BufferPool::new(1, 1024);
+    let mut buffer = pool.take(100);
+    assert_eq!(buffer, vec![0u8; 100]);
+    buffer.iter_mut().for_each(|byte| *byte = 1);
+    let ptr = buffer.as_ptr();
+    pool.recycle(buffer);
+    assert_eq!(pool.len(), 1);
+
+    // Recycled buffers are handed out zeroed
+    let buffer = pool.take(50);
+    assert_eq!(buffer.as_ptr(), ptr, "The recycled buffer was not reused");
+    assert_eq!(buffer, vec![0u8; 50]);
+    assert!(pool.is_empty());
+
+    // Buffers beyond the pool capacity or the maximum buffer size are dropped
+    pool.recycle(buffer);
+    pool.recycle(Vec::with_capacity(10));
+    pool.recycle(Vec::with_capacity(2048));
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn layout_stream_records_the_wrapped_fields() -> Result<()> {
+    let buf_size = sizeof::Context::new()
+        .absorb(Uint8::new(1))?
+        .mask(Bytes::new([7u8; 3]))?
+        .commit()?
+        .squeeze(&Mac::new(32))?
+        .finalize();
+
+    let mut stream = LayoutStream::new("Test");
+    stream.section("body");
+    let mut ctx = wrap::Context::<_, KeccakF1600>::new(&mut stream);
+    ctx.absorb(Uint8::new(1))?
+        .mask(Bytes::new([7u8; 3]))?
+        .commit()?
+        .squeeze(&Mac::new(32))?;
+    let layout = stream.into_descriptor();
+
+    let fields: Vec<(Command, usize, usize)> = layout
+        .fields()
+        .iter()
+        .map(|field| (field.command, field.offset, field.size))
+        .collect();
+    // The size of the masked bytes is masked before them, as its length byte and its value byte
+    assert_eq!(
+        fields,
+        [
+            (Command::Absorb, 0, 1),
+            (Command::Mask, 1, 1),
+            (Command::Mask, 2, 1),
+            (Command::Mask, 3, 3),
+            (Command::Commit, 6, 0),
+            (Command::Squeeze, 6, 32),
+        ]
+    );
+    assert_eq!(layout.size(), buf_size);
+    let sections: Vec<(&str, usize)> = layout.sections().map(|(name, fields)| (name, fields.len())).collect();
+    assert_eq!(sections, [("body", 6)]);
+    assert!(layout
+        .render()
+        .starts_with("message Test {\n    // body\n    absorb   u8[1]\t// 0..1\n"));
+    Ok(())
+}
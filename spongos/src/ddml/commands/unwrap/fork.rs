@@ -9,6 +9,7 @@ where
     type Forked = Context<&'a mut IS, F>;
     fn fork(&'a mut self) -> Context<&'a mut IS, F> {
         let fork = self.spongos.fork();
-        Context::new_with_spongos(self.stream_mut(), fork)
+        let size_limit = self.size_limit;
+        Context::new_with_spongos(self.stream_mut(), fork).with_size_limit(size_limit)
     }
 }
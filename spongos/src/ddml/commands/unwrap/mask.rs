@@ -100,7 +100,7 @@ impl<'a, F: PRP, IS: io::IStream> Mask<Bytes<&'a mut Vec<u8>>> for Context<IS, F
     fn mask(&mut self, mut bytes: Bytes<&'a mut Vec<u8>>) -> Result<&mut Self> {
         let mut size = Size::default();
         self.mask(&mut size)?;
-        self.stream.ensure_size(size.inner())?;
+        self.check_size("bytes", size.inner())?;
         bytes.resize(size.inner());
         MaskContext::new(self).unwrapn(bytes)?;
         Ok(self)
@@ -13,8 +13,11 @@ use crate::{
     error::Result,
 };
 
+/// Default bound on the lengths and item counts read from the stream by an unwrap [`Context`]
+pub const DEFAULT_SIZE_LIMIT: usize = 16 * 1024 * 1024;
+
 /// Unwrapped state of message. Used to decode `DDML` variables to
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Context<IS, F = KeccakF1600> {
     /// The [`Spongos`] object representing the current state of the unwrap [`Context`]
     spongos: Spongos<F>,
@@ -22,6 +25,18 @@ pub struct Context<IS, F = KeccakF1600> {
     stream: IS,
     /// Position of the current reading state
     cursor: usize,
+    /// Bound on the lengths and item counts read from the stream
+    size_limit: usize,
+}
+
+impl<IS, F> Default for Context<IS, F>
+where
+    IS: Default,
+    F: Default,
+{
+    fn default() -> Self {
+        Self::new(IS::default())
+    }
 }
 
 impl<IS, F> Context<IS, F> {
@@ -34,6 +49,7 @@ impl<IS, F> Context<IS, F> {
             spongos: Spongos::<F>::init(),
             stream,
             cursor: 0,
+            size_limit: DEFAULT_SIZE_LIMIT,
         }
     }
 
@@ -44,7 +60,41 @@ impl<IS, F> Context<IS, F> {
             spongos,
             stream,
             cursor: 0,
+            size_limit: DEFAULT_SIZE_LIMIT,
+        }
+    }
+
+    /// Bounds the lengths and item counts read from the stream, so that a malformed or hostile
+    /// message cannot make the reader allocate or loop without bound. Defaults to
+    /// [`DEFAULT_SIZE_LIMIT`].
+    ///
+    /// # Arguments
+    /// * `size_limit`: The largest length or item count accepted
+    pub fn with_size_limit(mut self, size_limit: usize) -> Self {
+        self.size_limit = size_limit;
+        self
+    }
+
+    /// The bound on the lengths and item counts read from the stream.
+    pub fn size_limit(&self) -> usize {
+        self.size_limit
+    }
+
+    /// Checks a length or item count read from the stream against the size limit of the
+    /// [`Context`] and against the bytes left in the stream, each item taking up at least one byte.
+    ///
+    /// # Arguments
+    /// * `field`: The name of the field the size was read for, reported on error
+    /// * `size`: The length or item count to check
+    pub fn check_size(&mut self, field: &'static str, size: usize) -> Result<&mut Self>
+    where
+        IS: io::IStream,
+    {
+        if size > self.size_limit {
+            return Err(InvalidSize(field, self.size_limit, size));
         }
+        self.stream.ensure_size(size)?;
+        Ok(self)
     }
 
     /// The read stream of the current [`Context`].
@@ -154,9 +204,12 @@ mod fork;
 mod guard;
 mod join;
 mod mask;
+mod reader;
 mod repeated;
 mod skip;
 mod squeeze;
 
 mod ed25519;
 mod x25519;
+
+pub use reader::MaskedReader;
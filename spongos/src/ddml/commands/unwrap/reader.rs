@@ -0,0 +1,109 @@
+use core::{
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use crate::{
+    core::prp::PRP,
+    ddml::{
+        commands::{unwrap::Context, Mask},
+        io,
+        types::Size,
+    },
+    error::Result,
+};
+
+/// Size of the chunks the remainder of the masked bytes is decrypted in when the reader is closed
+const SKIP_CHUNK_SIZE: usize = 1024;
+
+/// Incremental decryption of masked [`Bytes`](crate::ddml::types::Bytes) from a [`Context`].
+///
+/// The reader decrypts the bytes as they are read, so that large payloads can be relayed in chunks
+/// of the caller's choice instead of being decrypted into a single buffer. Combined with a
+/// [`Chunked`](io::Chunked) stream, the masked bytes are never held in memory whole. The
+/// [`Context`] is handed back by [`MaskedReader::into_context()`] to unwrap the remainder of the
+/// message, which must follow before the decrypted bytes are trusted, as they are only
+/// authenticated by the commands that follow them (a signature or a MAC).
+#[derive(Debug)]
+pub struct MaskedReader<IS, F> {
+    /// The [`Context`] the masked bytes are read from
+    ctx: Context<IS, F>,
+    /// Length of the masked bytes
+    len: usize,
+    /// Number of masked bytes not read yet
+    remaining: usize,
+}
+
+impl<IS, F> MaskedReader<IS, F>
+where
+    IS: io::IStream,
+    F: PRP,
+{
+    /// Decrypts the [`Size`] of the masked bytes from the [`Context`], checking it against its size
+    /// limit, and creates a new [`MaskedReader`] for the bytes that follow.
+    ///
+    /// # Arguments
+    /// * `ctx`: The [`Context`] positioned at the start of the masked bytes
+    pub fn new(mut ctx: Context<IS, F>) -> Result<Self> {
+        let mut size = Size::default();
+        ctx.mask(&mut size)?.check_size("bytes", size.inner())?;
+        Ok(Self {
+            ctx,
+            len: size.inner(),
+            remaining: size.inner(),
+        })
+    }
+
+    /// Length of the masked bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the masked bytes are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of masked bytes not read yet.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Decrypts the next masked bytes into a buffer, returning the number of bytes read. Returns 0
+    /// once every masked byte has been read.
+    ///
+    /// # Arguments
+    /// * `buf`: The buffer to fill
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = buf.len().min(self.remaining);
+        if n == 0 {
+            return Ok(0);
+        }
+        let ciphertext = self.ctx.stream.try_advance(n)?;
+        self.ctx.spongos.decrypt_mut(ciphertext, &mut buf[..n])?;
+        self.ctx.cursor += n;
+        self.remaining -= n;
+        Ok(n)
+    }
+
+    /// Same as [`MaskedReader::read()`], following the signature of `AsyncRead::poll_read`. The
+    /// stream is never waited on, so the result is always ready.
+    ///
+    /// # Arguments
+    /// * `cx`: The task context, unused
+    /// * `buf`: The buffer to fill
+    pub fn poll_read(self: Pin<&mut Self>, _cx: &mut task::Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>>
+    where
+        Self: Unpin,
+    {
+        Poll::Ready(self.get_mut().read(buf))
+    }
+
+    /// Consumes the reader, decrypting the masked bytes left unread, and returns the [`Context`]
+    /// positioned after the masked bytes.
+    pub fn into_context(mut self) -> Result<Context<IS, F>> {
+        let mut skipped = [0u8; SKIP_CHUNK_SIZE];
+        while self.read(&mut skipped)? != 0 {}
+        Ok(self.ctx)
+    }
+}
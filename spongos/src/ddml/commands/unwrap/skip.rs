@@ -91,7 +91,7 @@ impl<'a, F, IS: io::IStream> Skip<Bytes<&'a mut Vec<u8>>> for Context<IS, F> {
     fn skip(&mut self, mut bytes: Bytes<&'a mut Vec<u8>>) -> Result<&mut Self> {
         let mut size = Size::default();
         self.skip(&mut size)?;
-        self.stream.ensure_size(size.inner())?;
+        self.check_size("bytes", size.inner())?;
         bytes.resize(size.inner());
         SkipContext::new(self).unwrapn(bytes)?;
         Ok(self)
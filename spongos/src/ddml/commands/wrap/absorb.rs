@@ -8,6 +8,7 @@ use crate::{
             Absorb,
         },
         io,
+        layout::Command,
         types::{Bytes, Maybe, NBytes, Size, Uint16, Uint32, Uint64, Uint8},
     },
     error::Result,
@@ -35,6 +36,7 @@ impl<'a, F: PRP, OS: io::OStream> Wrap for AbsorbContext<'a, F, OS> {
     {
         let bytes = bytes.as_ref();
         self.ctx.spongos.absorb(bytes);
+        self.ctx.stream.annotate(Command::Absorb);
         self.ctx.stream.try_advance(bytes.len())?.copy_from_slice(bytes);
         Ok(self)
     }
@@ -1,12 +1,17 @@
 use crate::{
     core::prp::PRP,
-    ddml::commands::{wrap::Context, Commit},
+    ddml::{
+        commands::{wrap::Context, Commit},
+        io,
+        layout::Command,
+    },
     error::Result,
 };
 
 /// Commit [`Spongos`](`crate::core::spongos::Spongos`) state.
-impl<F: PRP, OS> Commit for Context<OS, F> {
+impl<F: PRP, OS: io::OStream> Commit for Context<OS, F> {
     fn commit(&mut self) -> Result<&mut Self> {
+        self.stream.annotate(Command::Commit);
         self.spongos.commit();
         Ok(self)
     }
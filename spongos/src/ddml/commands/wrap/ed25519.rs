@@ -4,6 +4,7 @@ use crate::{
     ddml::{
         commands::{wrap::Context, Ed25519},
         io,
+        layout::Command,
         modifiers::External,
         types::NBytes,
     },
@@ -15,6 +16,7 @@ use crate::{
 impl<F, OS: io::OStream> Ed25519<&ed25519::SecretKey, External<&NBytes<[u8; 64]>>> for Context<OS, F> {
     fn ed25519(&mut self, secret_key: &ed25519::SecretKey, hash: External<&NBytes<[u8; 64]>>) -> Result<&mut Self> {
         let signature = secret_key.sign(hash.inner().as_slice());
+        self.stream.annotate(Command::Sign);
         self.stream
             .try_advance(ed25519::SIGNATURE_LENGTH)?
             .copy_from_slice(&signature.to_bytes());
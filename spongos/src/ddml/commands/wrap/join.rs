@@ -1,12 +1,17 @@
 use crate::{
     core::{prp::PRP, spongos::Spongos},
-    ddml::commands::{wrap::Context, Join},
+    ddml::{
+        commands::{wrap::Context, Join},
+        io,
+        layout::Command,
+    },
     error::Result,
 };
 
 /// Absorbs the provided [`Spongos`] into the beginning of the current [`Context`] spongos.
-impl<F: PRP, OS> Join<F> for Context<OS, F> {
+impl<F: PRP, OS: io::OStream> Join<F> for Context<OS, F> {
     fn join(&mut self, joinee: &mut Spongos<F>) -> Result<&mut Self> {
+        self.stream.annotate(Command::Join);
         self.spongos.join(joinee);
         Ok(self)
     }
@@ -8,6 +8,7 @@ use crate::{
             Mask,
         },
         io,
+        layout::Command,
         types::{Bytes, Maybe, NBytes, Size, Uint16, Uint32, Uint64, Uint8},
     },
     error::Result,
@@ -34,6 +35,7 @@ impl<'a, F: PRP, OS: io::OStream> Wrap for MaskContext<'a, F, OS> {
         T: AsRef<[u8]>,
     {
         let bytes = bytes.as_ref();
+        self.ctx.stream.annotate(Command::Mask);
         let mut slice = self.ctx.stream.try_advance(bytes.len())?;
         self.ctx.spongos.encrypt_mut(bytes, &mut slice)?;
         Ok(self)
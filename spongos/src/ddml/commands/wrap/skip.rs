@@ -5,6 +5,7 @@ use crate::{
             Skip,
         },
         io,
+        layout::Command,
         types::{Bytes, NBytes, Size, Uint16, Uint32, Uint64, Uint8},
     },
     error::Result,
@@ -30,6 +31,7 @@ impl<'a, F, OS: io::OStream> Wrap for SkipContext<'a, F, OS> {
         T: AsRef<[u8]>,
     {
         let bytes = bytes.as_ref();
+        self.ctx.stream.annotate(Command::Skip);
         self.ctx.stream.try_advance(bytes.len())?.copy_from_slice(bytes);
         Ok(self)
     }
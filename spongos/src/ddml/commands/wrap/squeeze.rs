@@ -3,6 +3,7 @@ use crate::{
     ddml::{
         commands::{wrap::Context, Squeeze},
         io,
+        layout::Command,
         modifiers::External,
         types::{Mac, NBytes},
     },
@@ -12,6 +13,7 @@ use crate::{
 /// Squeeze [`Context`] into a [`Mac`] length hash, using allocated space in context byte stream.
 impl<'a, F: PRP, OS: io::OStream> Squeeze<&'a Mac> for Context<OS, F> {
     fn squeeze(&mut self, mac: &'a Mac) -> Result<&mut Self> {
+        self.stream.annotate(Command::Squeeze);
         self.spongos.squeeze_mut(&mut self.stream.try_advance(mac.length())?);
         Ok(self)
     }
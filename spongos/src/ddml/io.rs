@@ -1,9 +1,12 @@
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use core::ops::{Deref, DerefMut};
 
-use crate::error::{
-    Error::{StreamAllocationExceededIn, StreamAllocationExceededOut},
-    Result,
+use crate::{
+    ddml::layout::Command,
+    error::{
+        Error::{StreamAllocationExceededIn, StreamAllocationExceededOut},
+        Result,
+    },
 };
 
 /// Write
@@ -11,6 +14,11 @@ pub trait OStream {
     /// Try put n bytes into the stream, returning a slice to the buffer.
     fn try_advance(&mut self, bytes: usize) -> Result<&mut [u8]>;
 
+    /// Notes the [`Command`] processing the next bytes put into the stream. Only streams
+    /// describing the layout of messages, like [`LayoutStream`](crate::ddml::layout::LayoutStream),
+    /// make use of it.
+    fn annotate(&mut self, _command: Command) {}
+
     /// Dump stream debug info.
     fn dump(&self) -> String;
 }
@@ -27,6 +35,123 @@ pub trait IStream {
     fn dump(&self) -> String;
 }
 
+/// Source of bytes pulled incrementally by a [`Chunked`] stream
+pub trait Source {
+    /// Reads up to `buf.len()` bytes into `buf`, returning the number of bytes read. Returns 0
+    /// once the source is exhausted.
+    ///
+    /// # Arguments
+    /// * `buf`: The buffer to fill
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+}
+
+/// Implementing the Source trait for a slice of bytes.
+impl Source for &[u8] {
+    /// Copies the first bytes of the slice into the buffer, replacing self with the remainder of
+    /// the slice.
+    ///
+    /// # Arguments
+    /// * `buf`: The buffer to fill
+    ///
+    /// Returns:
+    /// The number of bytes copied
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = buf.len().min(self.len());
+        let (head, tail) = self.split_at(n);
+        buf[..n].copy_from_slice(head);
+        *self = tail;
+        Ok(n)
+    }
+}
+
+/// [`IStream`] over a message of known length, pulling its bytes from a [`Source`] as the stream
+/// is advanced. Only the bytes of the last advancement are held in memory, so a message does not
+/// need to be buffered whole to be unwrapped.
+#[derive(Debug)]
+pub struct Chunked<S> {
+    /// The source the bytes are pulled from
+    source: S,
+    /// Number of bytes of the message not yet pulled from the source
+    remaining: usize,
+    /// The bytes of the last advancement
+    buffer: Vec<u8>,
+}
+
+impl<S> Chunked<S> {
+    /// Creates a new [`Chunked`] stream.
+    ///
+    /// # Arguments
+    /// * `source`: The [`Source`] of the message bytes
+    /// * `len`: The length of the message
+    pub fn new(source: S, len: usize) -> Self {
+        Self {
+            source,
+            remaining: len,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Number of bytes of the message not yet pulled from the source.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Consumes the stream, returning its [`Source`].
+    pub fn into_source(self) -> S {
+        self.source
+    }
+}
+
+/// Implementing the IStream trait for a [`Chunked`] stream.
+impl<S: Source> IStream for Chunked<S> {
+    /// Ensure the bytes of the message not yet pulled from the source are at least as many as the
+    /// number of bytes intended for allocation
+    ///
+    /// # Arguments
+    /// * `n`: the number of bytes we want to allocate
+    ///
+    /// Returns:
+    /// Ok if size does not exceed allocation, Error if it does
+    fn ensure_size(&self, n: usize) -> Result<()> {
+        match n <= self.remaining {
+            true => Ok(()),
+            false => Err(StreamAllocationExceededIn(n, self.remaining)),
+        }
+    }
+
+    /// Pulls the next `n` bytes from the source into the internal buffer, replacing the bytes of
+    /// the previous advancement, and producing an error if the message or the source is shorter
+    /// than the provided length.
+    ///
+    /// # Arguments
+    /// * `n`: The number of bytes to advance the stream by.
+    ///
+    /// Returns:
+    /// A slice of the bytes pulled from the source.
+    fn try_advance(&mut self, n: usize) -> Result<&[u8]> {
+        self.ensure_size(n)?;
+        self.buffer.clear();
+        self.buffer.resize(n, 0);
+        let mut filled = 0;
+        while filled < n {
+            match self.source.read(&mut self.buffer[filled..])? {
+                0 => return Err(StreamAllocationExceededIn(n, filled)),
+                read => filled += read,
+            }
+        }
+        self.remaining -= n;
+        Ok(&self.buffer)
+    }
+
+    /// Returns the number of bytes left in the message, which are not buffered.
+    ///
+    /// Returns:
+    /// A String
+    fn dump(&self) -> String {
+        alloc::format!("<{} bytes left in source>", self.remaining)
+    }
+}
+
 /// Implementing the OStream trait for a mutable slice of bytes.
 impl OStream for &mut [u8] {
     /// `try_advance` takes a mutable reference to a `StreamAllocator` and tries to advance the
@@ -76,6 +201,14 @@ where
         self.deref_mut().try_advance(n)
     }
 
+    /// Notes the [`Command`] processing the next bytes put into the underlying stream.
+    ///
+    /// # Arguments
+    /// * `command`: The [`Command`] processing the stream
+    fn annotate(&mut self, command: Command) {
+        self.deref_mut().annotate(command)
+    }
+
     /// Returns a hexadecimal string representation of the bytes in the slice.
     ///
     /// Returns:
@@ -0,0 +1,233 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{ddml::io, error::Result};
+
+/// `DDML` command processing a field of a message, or the [`Spongos`](crate::Spongos) state at a
+/// position of the message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// The field is encoded and absorbed
+    Absorb,
+    /// The spongos state is committed
+    Commit,
+    /// The spongos state of the linked message is joined
+    Join,
+    /// The field is encrypted
+    Mask,
+    /// The field is an `Ed25519` signature
+    Sign,
+    /// The field is encoded without being absorbed
+    Skip,
+    /// The field is squeezed from the spongos state
+    Squeeze,
+}
+
+impl Command {
+    /// Returns the name of the command, as written in `DDML` definitions
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::Absorb => "absorb",
+            Command::Commit => "commit",
+            Command::Join => "join",
+            Command::Mask => "mask",
+            Command::Sign => "ed25519",
+            Command::Skip => "skip",
+            Command::Squeeze => "squeeze",
+        }
+    }
+
+    /// Returns true if the command writes bytes into the stream
+    pub fn writes_bytes(self) -> bool {
+        !matches!(self, Command::Commit | Command::Join)
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// A field of a [`LayoutDescriptor`], as written by a single advance of the stream. Variable
+/// sized fields are preceded by their [`Size`](crate::ddml::types::Size), which appears as a one
+/// byte field holding the length of the size followed by one byte field per byte of the size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Field {
+    /// The [`Command`] processing the field
+    pub command: Command,
+    /// The position of the field in the binary message
+    pub offset: usize,
+    /// The number of bytes of the field, 0 for the commands that do not write bytes
+    pub size: usize,
+}
+
+/// Byte layout of a binary message, as recorded by a [`LayoutStream`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LayoutDescriptor {
+    /// The name of the message type
+    name: String,
+    /// The names of the sections of the message and the index of their first field
+    sections: Vec<(String, usize)>,
+    /// The fields of the message, in stream order
+    fields: Vec<Field>,
+}
+
+impl LayoutDescriptor {
+    /// Returns the name of the message type
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the fields of the message, in stream order
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Returns an iterator over the named sections of the message, with their fields. Fields
+    /// recorded before the first section are not part of any.
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &[Field])> + '_ {
+        self.sections.iter().enumerate().map(move |(i, (name, start))| {
+            let end = self.sections.get(i + 1).map_or(self.fields.len(), |(_, end)| *end);
+            (name.as_str(), &self.fields[*start..end])
+        })
+    }
+
+    /// Returns the size of the binary message, in bytes
+    pub fn size(&self) -> usize {
+        self.fields.last().map_or(0, |field| field.offset + field.size)
+    }
+
+    /// Renders the layout as a `DDML` listing, with the byte range of every field
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for LayoutDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "message {} {{", self.name)?;
+        let mut sections = self.sections.iter().peekable();
+        for (i, field) in self.fields.iter().enumerate() {
+            while let Some((name, _)) = sections.next_if(|(_, start)| *start == i) {
+                writeln!(f, "    // {}", name)?;
+            }
+            match field.command.writes_bytes() {
+                true => writeln!(
+                    f,
+                    "    {:<8} u8[{}]\t// {}..{}",
+                    field.command,
+                    field.size,
+                    field.offset,
+                    field.offset + field.size
+                )?,
+                false => writeln!(f, "    {};", field.command)?,
+            }
+        }
+        writeln!(f, "}} // {} bytes", self.size())
+    }
+}
+
+/// [`OStream`](io::OStream) recording the [`LayoutDescriptor`] of the message wrapped into it. The
+/// bytes put into the stream are discarded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LayoutStream {
+    /// The layout recorded so far
+    descriptor: LayoutDescriptor,
+    /// The command writing the next bytes
+    command: Option<Command>,
+    /// Buffer handed out to the commands, overwritten by every advance
+    scratch: Vec<u8>,
+}
+
+impl LayoutStream {
+    /// Creates a new empty [`LayoutStream`].
+    ///
+    /// # Arguments
+    /// * `name`: The name of the message type
+    pub fn new<N>(name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            descriptor: LayoutDescriptor {
+                name: name.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Starts a new section of the message, holding the fields written from now on
+    ///
+    /// # Arguments
+    /// * `name`: The name of the section
+    pub fn section<N>(&mut self, name: N)
+    where
+        N: Into<String>,
+    {
+        let start = self.descriptor.fields.len();
+        self.descriptor.sections.push((name.into(), start));
+    }
+
+    /// Consumes the [`LayoutStream`], returning the recorded [`LayoutDescriptor`]
+    pub fn into_descriptor(self) -> LayoutDescriptor {
+        self.descriptor
+    }
+}
+
+impl io::OStream for LayoutStream {
+    /// Records a field of `n` bytes written by the last annotated [`Command`]. Empty writes are
+    /// not recorded.
+    ///
+    /// # Arguments
+    /// * `n`: The number of bytes to advance the stream by.
+    ///
+    /// Returns:
+    /// A mutable slice of a scratch buffer.
+    fn try_advance(&mut self, n: usize) -> Result<&mut [u8]> {
+        if n > 0 {
+            let offset = self.descriptor.size();
+            self.descriptor.fields.push(Field {
+                // Bytes put by a writer that does not annotate them are copied as is
+                command: self.command.unwrap_or(Command::Skip),
+                offset,
+                size: n,
+            });
+        }
+        if self.scratch.len() < n {
+            self.scratch.resize(n, 0);
+        }
+        Ok(&mut self.scratch[..n])
+    }
+
+    /// Notes the [`Command`] writing the next bytes. Commands that do not write bytes are recorded
+    /// as empty fields at the current position.
+    ///
+    /// # Arguments
+    /// * `command`: The [`Command`] processing the stream
+    fn annotate(&mut self, command: Command) {
+        match command.writes_bytes() {
+            true => self.command = Some(command),
+            false => {
+                let offset = self.descriptor.size();
+                self.descriptor.fields.push(Field {
+                    command,
+                    offset,
+                    size: 0,
+                });
+            }
+        }
+    }
+
+    /// Returns the size of the recorded layout.
+    ///
+    /// Returns:
+    /// A String
+    fn dump(&self) -> String {
+        alloc::format!("<{} bytes laid out>", self.descriptor.size())
+    }
+}
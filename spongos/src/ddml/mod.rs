@@ -10,5 +10,11 @@ pub mod modifiers;
 /// Abstractions for input/output buffers. It does not support the actual IO.
 pub mod io;
 
+/// Reuse of the buffers backing `DDML` streams.
+pub mod pool;
+
+/// Byte layout of the messages wrapped with `DDML`.
+pub mod layout;
+
 /// DDML specific types.
 pub mod types;
@@ -0,0 +1,82 @@
+use alloc::vec::Vec;
+
+/// Default number of buffers kept by a [`BufferPool`]
+pub const DEFAULT_POOL_SIZE: usize = 32;
+
+/// Default capacity above which recycled buffers are dropped instead of being kept
+pub const DEFAULT_MAX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Pool of byte buffers reused across `DDML` operations.
+///
+/// `wrap` and `unwrap` contexts do not allocate themselves: they borrow the stream they write to or
+/// read from. The allocations of a wrap or unwrap are the buffers backing those streams, and the
+/// buffers the variable sized fields are decoded into. A [`BufferPool`] hands out zeroed buffers
+/// from the ones recycled by the caller, so that a steady flow of messages of similar sizes is
+/// processed without hitting the allocator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BufferPool {
+    /// Buffers available for reuse
+    buffers: Vec<Vec<u8>>,
+    /// Maximum number of buffers kept
+    max_buffers: usize,
+    /// Capacity above which recycled buffers are dropped
+    max_buffer_size: usize,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_SIZE, DEFAULT_MAX_BUFFER_SIZE)
+    }
+}
+
+impl BufferPool {
+    /// Creates a new empty [`BufferPool`].
+    ///
+    /// # Arguments
+    /// * `max_buffers`: The maximum number of buffers kept for reuse
+    /// * `max_buffer_size`: The capacity above which recycled buffers are dropped, bounding the
+    ///   memory held by the pool
+    pub fn new(max_buffers: usize, max_buffer_size: usize) -> Self {
+        Self {
+            buffers: Vec::new(),
+            max_buffers,
+            max_buffer_size,
+        }
+    }
+
+    /// Returns a zeroed buffer of `size` bytes, reusing a recycled buffer if any. Buffers large
+    /// enough to hold `size` bytes are preferred so that the buffer does not need to grow.
+    ///
+    /// # Arguments
+    /// * `size`: The length of the buffer
+    pub fn take(&mut self, size: usize) -> Vec<u8> {
+        let mut buffer = match self.buffers.iter().position(|buffer| buffer.capacity() >= size) {
+            Some(i) => self.buffers.swap_remove(i),
+            None => self.buffers.pop().unwrap_or_default(),
+        };
+        buffer.clear();
+        buffer.resize(size, 0);
+        buffer
+    }
+
+    /// Returns a buffer to the pool for reuse. The buffer is dropped if the pool is full or if its
+    /// capacity exceeds the maximum buffer size of the pool.
+    ///
+    /// # Arguments
+    /// * `buffer`: The buffer that is no longer in use
+    pub fn recycle(&mut self, buffer: Vec<u8>) {
+        if buffer.capacity() > 0 && buffer.capacity() <= self.max_buffer_size && self.buffers.len() < self.max_buffers {
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// Number of buffers available for reuse.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Whether the pool holds no buffer for reuse.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
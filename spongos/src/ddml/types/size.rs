@@ -1,6 +1,6 @@
 use core::fmt;
 
-use crate::error::Result;
+use crate::error::{Error::InvalidSize, Result};
 
 /// Integer size wrapper for `DDML` operations
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Default)]
@@ -49,6 +49,14 @@ impl Size {
     /// # Arguments
     /// * `codec`: a function for decoding bytes from a [`Spongos`] stream
     pub(crate) fn decode(mut codec: impl FnMut(&mut u8) -> Result<()>, mut num_bytes: u8) -> Result<Self> {
+        // Wider encodings would silently drop their most significant bytes
+        if usize::from(num_bytes) > core::mem::size_of::<usize>() {
+            return Err(InvalidSize(
+                "size encoding",
+                core::mem::size_of::<usize>(),
+                num_bytes.into(),
+            ));
+        }
         let mut result = 0usize;
         while 0 < num_bytes {
             num_bytes -= 1;
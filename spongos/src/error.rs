@@ -38,6 +38,9 @@ pub enum Error {
     #[error("Reserved area was not empty: {0}")]
     Reserved(&'static str),
 
+    #[error("{0} exceeds the size limit (limit: {1}, found: {2})")]
+    InvalidSize(&'static str, usize, usize),
+
     //////////
     // DDML IO
     //////////
@@ -64,3 +67,14 @@ impl From<anyhow::Error> for Error {
         Self::External(error)
     }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::SliceMismatch(error) => Some(error),
+            Self::External(error) => Some(&**error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,208 @@
+// Rust
+use std::{cell::RefCell, rc::Rc};
+
+// 3rd-party
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+
+// IOTA
+
+// Streams
+use streams::{
+    id::{Ed25519, Identifier, Identity},
+    transport::{bucket, Transport},
+    Address, TransportMessage, User,
+};
+
+type Transport = Rc<RefCell<bucket::Client>>;
+
+const BASE_BRANCH: &str = "BASE_BRANCH";
+const PAYLOAD_SIZE: usize = 256;
+const PUBLISHERS: usize = 100;
+const MESSAGES_PER_PUBLISHER: usize = 100;
+const BATCH_SIZE: usize = 1000;
+
+fn identifier(seed: &str) -> Identifier {
+    Identity::from(Ed25519::from_seed(seed)).identifier().clone()
+}
+
+fn user(seed: &str, transport: Transport) -> User<Transport> {
+    User::builder()
+        .with_identity(Ed25519::from_seed(seed))
+        .with_transport(transport)
+        .build()
+}
+
+/// Creates an author whose stream is announced, granting access to `subscribers` subscribers
+fn author_with_subscribers(transport: Transport, subscribers: usize) -> (User<Transport>, Address) {
+    let mut author = user("author", transport);
+    let announcement = block_on(author.create_stream(BASE_BRANCH)).unwrap();
+    for i in 0..subscribers {
+        author.add_subscriber(identifier(&format!("subscriber {}", i)));
+    }
+    (author, announcement.address())
+}
+
+fn bench_wrap(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Wrap");
+    let payload = vec![12u8; PAYLOAD_SIZE];
+
+    group.bench_function("announcement", |b| {
+        b.iter_batched(
+            || user("author", Rc::new(RefCell::new(bucket::Client::new()))),
+            |mut author| block_on(author.create_stream(BASE_BRANCH)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    for subscribers in [1, 10, 100] {
+        group.bench_with_input(
+            BenchmarkId::new("keyload", subscribers),
+            &subscribers,
+            |b, subscribers| {
+                let (mut author, _) =
+                    author_with_subscribers(Rc::new(RefCell::new(bucket::Client::new())), *subscribers);
+                b.iter(|| block_on(author.send_keyload_for_all(BASE_BRANCH)).unwrap())
+            },
+        );
+    }
+
+    group.throughput(Throughput::Bytes(2 * PAYLOAD_SIZE as u64));
+    group.bench_function("signed packet", |b| {
+        let (mut author, _) = author_with_subscribers(Rc::new(RefCell::new(bucket::Client::new())), 1);
+        block_on(author.send_keyload_for_all(BASE_BRANCH)).unwrap();
+        b.iter(|| block_on(author.send_signed_packet(BASE_BRANCH, &payload, &payload)).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_unwrap(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Unwrap");
+    let payload = vec![12u8; PAYLOAD_SIZE];
+    let transport = Rc::new(RefCell::new(bucket::Client::new()));
+    let (mut author, announcement) = author_with_subscribers(transport.clone(), 1);
+    let keyload = block_on(author.send_keyload_for_all(BASE_BRANCH)).unwrap().address();
+    let packet = block_on(author.send_signed_packet(BASE_BRANCH, &payload, &payload))
+        .unwrap()
+        .address();
+
+    // Every iteration reads with a new subscriber, as the messages read once are reported as
+    // duplicates afterwards
+    let subscriber = |read: &[Address]| {
+        let mut subscriber = user("subscriber 0", transport.clone());
+        for address in read {
+            block_on(subscriber.receive_message(*address)).unwrap();
+        }
+        subscriber
+    };
+
+    group.bench_function("announcement", |b| {
+        b.iter_batched(
+            || subscriber(&[]),
+            |mut subscriber| block_on(subscriber.receive_message(announcement)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("keyload", |b| {
+        b.iter_batched(
+            || subscriber(&[announcement]),
+            |mut subscriber| block_on(subscriber.receive_message(keyload)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.throughput(Throughput::Bytes(2 * PAYLOAD_SIZE as u64));
+    group.bench_function("signed packet", |b| {
+        b.iter_batched(
+            || subscriber(&[announcement, keyload]),
+            |mut subscriber| block_on(subscriber.receive_message(packet)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_messages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Messages traversal");
+    let payload = vec![12u8; PAYLOAD_SIZE];
+    let transport = Rc::new(RefCell::new(bucket::Client::new()));
+    let (mut author, announcement) = author_with_subscribers(transport.clone(), PUBLISHERS);
+    author.add_subscriber(identifier("reader"));
+    block_on(author.send_keyload_for_all_rw(BASE_BRANCH)).unwrap();
+    for i in 0..PUBLISHERS {
+        let mut publisher = user(&format!("subscriber {}", i), transport.clone());
+        block_on(publisher.receive_message(announcement)).unwrap();
+        block_on(publisher.sync()).unwrap();
+        for _ in 0..MESSAGES_PER_PUBLISHER {
+            block_on(publisher.send_tagged_packet(BASE_BRANCH, &payload, &payload)).unwrap();
+        }
+    }
+
+    let messages = PUBLISHERS * MESSAGES_PER_PUBLISHER;
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(messages as u64));
+    group.bench_function(BenchmarkId::new("bucket", messages), |b| {
+        b.iter_batched(
+            || {
+                let mut reader = user("reader", transport.clone());
+                block_on(reader.receive_message(announcement)).unwrap();
+                reader
+            },
+            |mut reader| {
+                // The keyload, then the packets of every publisher
+                let read = block_on(reader.sync()).unwrap();
+                assert_eq!(read, messages + 1);
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+/// Compares the processing of a batch of packets as a whole, preprocessed in parallel with the
+/// `parallel-preparse` feature, with the processing of the same packets one by one
+fn bench_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Batch");
+    let payload = vec![12u8; PAYLOAD_SIZE];
+    let transport = Rc::new(RefCell::new(bucket::Client::new()));
+    let (mut author, announcement) = author_with_subscribers(transport.clone(), 1);
+    let mut addresses = vec![block_on(author.send_keyload_for_all(BASE_BRANCH)).unwrap().address()];
+    for _ in 0..BATCH_SIZE {
+        let packet = block_on(author.send_signed_packet(BASE_BRANCH, &payload, &payload)).unwrap();
+        addresses.push(packet.address());
+    }
+    let batch: Vec<(Address, TransportMessage)> = addresses
+        .into_iter()
+        .map(|address| (address, block_on(transport.borrow_mut().recv_message(address)).unwrap()))
+        .collect();
+
+    let subscriber = || {
+        let mut subscriber = user("subscriber 0", transport.clone());
+        block_on(subscriber.receive_message(announcement)).unwrap();
+        subscriber
+    };
+
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(batch.len() as u64));
+    group.bench_function(BenchmarkId::new("one by one", batch.len()), |b| {
+        b.iter_batched(
+            || (subscriber(), batch.clone()),
+            |(mut subscriber, batch)| {
+                for msg in batch {
+                    block_on(subscriber.handle_messages_batch(vec![msg])).unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function(BenchmarkId::new("whole batch", batch.len()), |b| {
+        b.iter_batched(
+            || (subscriber(), batch.clone()),
+            |(mut subscriber, batch)| block_on(subscriber.handle_messages_batch(batch)).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_wrap, bench_unwrap, bench_messages, bench_batch);
+criterion_main!(benches);
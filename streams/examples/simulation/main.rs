@@ -0,0 +1,65 @@
+//! Randomized simulation of a channel with an author and several subscribers
+//!
+//! The simulation runs over the in-memory bucket transport and is fully determined by its seed, so
+//! a failing schedule can be replayed. It is configured with the following environment variables:
+//! * `SIM_SEED`: Seed of the schedule. A random seed is drawn (and printed) if not set.
+//! * `SIM_SUBSCRIBERS`: Number of simulated subscribers (5 by default).
+//! * `SIM_ROUNDS`: Number of simulated rounds (10 by default).
+//! * `SIM_MAX_ACTIONS`: Maximum number of publish or sync actions per round (20 by default).
+
+// Rust
+use std::{cell::RefCell, env, rc::Rc, str::FromStr};
+
+// 3rd-party
+use rand::Rng;
+
+// IOTA
+
+// Streams
+use streams::{transport::bucket, Result};
+
+// Local
+use simulation::{Config, Simulation};
+
+mod simulation;
+
+/// Reads a numeric setting from the environment, falling back to a default value
+///
+/// # Arguments
+/// * `name`: The name of the environment variable
+/// * `default`: The value used if the variable is not set
+fn setting<N: FromStr>(name: &str, default: N) -> N {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number, got '{}'", name, value)),
+        Err(_) => default,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = Config {
+        seed: setting("SIM_SEED", rand::thread_rng().gen()),
+        subscribers: setting("SIM_SUBSCRIBERS", 5),
+        rounds: setting("SIM_ROUNDS", 10),
+        max_actions: setting("SIM_MAX_ACTIONS", 20),
+    };
+    println!(
+        "## Simulating {} subscribers over {} rounds with seed {} ##\n",
+        config.subscribers, config.rounds, config.seed
+    );
+
+    // BucketTransport is an in-memory storage that needs to be shared between all the users,
+    // hence the Rc<RefCell<BucketTransport>>
+    let transport = Rc::new(RefCell::new(bucket::Client::new()));
+    let result = Simulation::new(transport, &config).run().await;
+    match &result {
+        Err(err) => eprintln!("Error in simulation with seed {}: {}", config.seed, err),
+        Ok(published) => println!(
+            "\n## Simulation converged after {} packets (seed {}) ##\n",
+            published, config.seed
+        ),
+    }
+    result.map(|_| ())
+}
@@ -0,0 +1,236 @@
+// Rust
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+// 3rd-party
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// IOTA
+
+// Streams
+use streams::{id::Ed25519, transport::bucket, Address, MessageContent, Result, User};
+
+/// Transport shared by the simulated users
+type Transport = Rc<RefCell<bucket::Client>>;
+
+const BASE_BRANCH: &str = "BASE_BRANCH";
+
+/// Index of the author among the participants
+const AUTHOR: usize = 0;
+
+/// Parameters of a [`Simulation`]
+pub(crate) struct Config {
+    /// Seed of the random schedule
+    pub(crate) seed: u64,
+    /// Number of simulated subscribers
+    pub(crate) subscribers: usize,
+    /// Number of simulated rounds
+    pub(crate) rounds: usize,
+    /// Maximum number of publish or sync actions per round
+    pub(crate) max_actions: usize,
+}
+
+/// A simulated user of the channel
+struct Participant {
+    /// The simulated [`User`]
+    user: User<Transport>,
+    /// The round in which the participant was granted access to the branch, if any
+    admitted: Option<usize>,
+    /// Masked payloads of the packets the participant has read
+    received: HashSet<Vec<u8>>,
+}
+
+/// A packet published during the simulation
+struct Publication {
+    /// The round in which the packet was published
+    round: usize,
+    /// The index of the participant that published it
+    publisher: usize,
+    /// The masked payload of the packet, unique to each publication
+    payload: Vec<u8>,
+}
+
+/// Simulation of a channel with an author and several subscribers, following a random schedule
+///
+/// Every round, some of the subscribers that are not yet members subscribe and the author sends a
+/// keyload granting all subscribers read and write access to the branch. The members then publish
+/// packets and sync in a random order, so packets are frequently read before the messages they are
+/// linked to. At the end of each round every member syncs, and the simulation asserts that all of
+/// them have read every packet published since they were admitted, exactly once.
+pub(crate) struct Simulation {
+    /// Random number generator driving the schedule
+    rng: StdRng,
+    /// Number of simulated rounds
+    rounds: usize,
+    /// Maximum number of publish or sync actions per round
+    max_actions: usize,
+    /// The author followed by the subscribers
+    participants: Vec<Participant>,
+    /// The packets published so far
+    publications: Vec<Publication>,
+}
+
+impl Simulation {
+    /// Creates a new [`Simulation`]
+    ///
+    /// # Arguments
+    /// * `transport`: The transport shared by the simulated users
+    /// * `config`: The parameters of the simulation
+    pub(crate) fn new(transport: Transport, config: &Config) -> Self {
+        let participants = (0..=config.subscribers)
+            .map(|i| Participant {
+                user: User::builder()
+                    .with_identity(Ed25519::from_seed(format!("SIMULATION{}USER{}", config.seed, i)))
+                    .with_transport(transport.clone())
+                    .build(),
+                admitted: None,
+                received: HashSet::new(),
+            })
+            .collect();
+        Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            rounds: config.rounds,
+            max_actions: config.max_actions,
+            participants,
+            publications: Vec::new(),
+        }
+    }
+
+    /// Runs the simulation, returning the number of packets published
+    pub(crate) async fn run(mut self) -> Result<usize> {
+        let announcement = self.participants[AUTHOR].user.create_stream(BASE_BRANCH).await?;
+        self.participants[AUTHOR].admitted = Some(0);
+        for round in 0..self.rounds {
+            self.admit(round, announcement.address()).await?;
+            let actions = self.rng.gen_range(1..=self.max_actions.max(1));
+            for _ in 0..actions {
+                let members = self.members();
+                let member = members[self.rng.gen_range(0..members.len())];
+                if self.rng.gen_bool(0.7) {
+                    self.publish(round, member).await?;
+                } else {
+                    self.drain(member).await?;
+                }
+            }
+            self.converge(round).await?;
+            println!(
+                "> Round {}: {} members, {} packets published",
+                round,
+                self.members().len(),
+                self.publications.len()
+            );
+        }
+        Ok(self.publications.len())
+    }
+
+    /// Returns the indices of the participants that have been granted access to the branch
+    fn members(&self) -> Vec<usize> {
+        (0..self.participants.len())
+            .filter(|&i| self.participants[i].admitted.is_some())
+            .collect()
+    }
+
+    /// Subscribes a random selection of the subscribers that are not yet members, then sends a
+    /// keyload granting every subscriber access to the branch
+    ///
+    /// # Arguments
+    /// * `round`: The current round
+    /// * `announcement`: The [`Address`] of the stream announcement
+    async fn admit(&mut self, round: usize, announcement: Address) -> Result<()> {
+        for i in 1..self.participants.len() {
+            if self.participants[i].admitted.is_some() || !self.rng.gen_bool(0.5) {
+                continue;
+            }
+            let subscriber = &mut self.participants[i];
+            subscriber.user.receive_message(announcement).await?;
+            let subscription = subscriber.user.subscribe().await?;
+            subscriber.admitted = Some(round);
+            self.participants[AUTHOR]
+                .user
+                .receive_message(subscription.address())
+                .await?;
+        }
+        self.participants[AUTHOR]
+            .user
+            .send_keyload_for_all_rw(BASE_BRANCH)
+            .await?;
+        for member in self.members() {
+            self.drain(member).await?;
+        }
+        Ok(())
+    }
+
+    /// Publishes a signed packet with a unique masked payload
+    ///
+    /// # Arguments
+    /// * `round`: The current round
+    /// * `publisher`: The index of the publishing participant
+    async fn publish(&mut self, round: usize, publisher: usize) -> Result<()> {
+        let payload = format!("ROUND{}PUBLISHER{}PACKET{}", round, publisher, self.publications.len()).into_bytes();
+        self.participants[publisher]
+            .user
+            .send_signed_packet(BASE_BRANCH, b"SIMULATION", &payload)
+            .await?;
+        self.publications.push(Publication {
+            round,
+            publisher,
+            payload,
+        });
+        Ok(())
+    }
+
+    /// Fetches messages until a participant has read every message available to it, recording the
+    /// payloads of the packets it reads
+    ///
+    /// # Arguments
+    /// * `participant`: The index of the syncing participant
+    async fn drain(&mut self, participant: usize) -> Result<()> {
+        let participant = &mut self.participants[participant];
+        loop {
+            let messages = participant.user.fetch_next_messages().await?;
+            if messages.is_empty() {
+                return Ok(());
+            }
+            for message in messages {
+                if let MessageContent::SignedPacket(packet) = message.content {
+                    let payload = String::from_utf8_lossy(&packet.masked_payload).into_owned();
+                    assert!(
+                        participant.received.insert(packet.masked_payload),
+                        "packet '{}' was read twice",
+                        payload
+                    );
+                }
+            }
+        }
+    }
+
+    /// Syncs every member and asserts that each of them has read all the packets published by the
+    /// other members since it was admitted
+    ///
+    /// # Arguments
+    /// * `round`: The current round
+    async fn converge(&mut self, round: usize) -> Result<()> {
+        for member in self.members() {
+            self.drain(member).await?;
+        }
+        for member in self.members() {
+            let participant = &self.participants[member];
+            // Ok to unwrap since members have been admitted
+            let admitted = participant.admitted.unwrap();
+            for publication in self
+                .publications
+                .iter()
+                .filter(|publication| publication.round >= admitted && publication.publisher != member)
+            {
+                assert!(
+                    participant.received.contains(&publication.payload),
+                    "member {} has not read packet '{}' by member {} after round {}",
+                    member,
+                    String::from_utf8_lossy(&publication.payload),
+                    publication.publisher,
+                    round
+                );
+            }
+        }
+        Ok(())
+    }
+}
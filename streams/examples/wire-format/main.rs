@@ -0,0 +1,25 @@
+//! Renders the byte layout of every Streams message type, for implementations of the protocol in
+//! other languages
+//!
+//! The layouts are recorded by wrapping sample messages (see `MessageCodec::layouts()`), so the
+//! rendered listing always matches the encoding of this version of the library. Redirect the
+//! output to a file to keep it alongside the interop documentation.
+
+// Rust
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use streams::{MessageCodec, Result};
+
+// Local
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let layouts = MessageCodec::layouts().await?;
+    let rendered: Vec<String> = layouts.iter().map(|layout| layout.render()).collect();
+    print!("{}", rendered.join("\n"));
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! Feeds arbitrary bytes to the author and a subscriber of a stream as if read from the transport,
+//! covering the decoding of every message type
+
+#![no_main]
+
+// Rust
+use std::{cell::RefCell, rc::Rc};
+
+// 3rd-party
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+
+// Streams
+use streams::{
+    id::Ed25519,
+    transport::{bucket, Transport},
+    Address, TransportMessage, User,
+};
+
+fuzz_target!(|data: &[u8]| {
+    block_on(async {
+        let transport = Rc::new(RefCell::new(bucket::Client::new()));
+        let mut author = User::builder()
+            .with_identity(Ed25519::from_seed("FUZZ9AUTHOR"))
+            .with_transport(transport.clone())
+            .build();
+        let mut subscriber = User::builder()
+            .with_identity(Ed25519::from_seed("FUZZ9SUBSCRIBER"))
+            .with_transport(transport.clone())
+            .build();
+        let announcement = author.create_stream("BASE_BRANCH").await.unwrap();
+        subscriber.receive_message(announcement.address()).await.unwrap();
+
+        let address = Address::new(announcement.address().base(), [0xff; 12]);
+        author
+            .transport_mut()
+            .send_message(address, TransportMessage::new(data.to_vec()))
+            .await
+            .unwrap();
+        let _ = author.receive_message(address).await;
+        let _ = subscriber.receive_message(address).await;
+    });
+});
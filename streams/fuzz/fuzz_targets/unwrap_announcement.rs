@@ -0,0 +1,15 @@
+//! Decodes arbitrary bytes as a stream announcement
+
+#![no_main]
+
+// 3rd-party
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+
+// Streams
+use streams::{Address, MessageCodec, TransportMessage};
+
+fuzz_target!(|data: &[u8]| {
+    let msg = TransportMessage::new(data.to_vec());
+    let _ = block_on(MessageCodec::unwrap_announcement(Address::default(), msg));
+});
@@ -0,0 +1,25 @@
+//! Decodes arbitrary bytes as signed and tagged packets linked to a genuine announcement
+
+#![no_main]
+
+// 3rd-party
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+
+// Streams
+use streams::{
+    id::{Ed25519, Identity},
+    MessageCodec, TransportMessage,
+};
+
+fuzz_target!(|data: &[u8]| {
+    block_on(async {
+        let author: Identity = Ed25519::from_seed("FUZZ9AUTHOR").into();
+        let (address, _, spongos) = MessageCodec::wrap_announcement(&author, &"BASE_BRANCH".into())
+            .await
+            .unwrap();
+        let msg = TransportMessage::new(data.to_vec());
+        let _ = MessageCodec::unwrap_signed_packet(address, msg.clone(), spongos).await;
+        let _ = MessageCodec::unwrap_tagged_packet(address, msg, spongos).await;
+    });
+});
@@ -0,0 +1,364 @@
+//! Actor owning a [`User`] on a dedicated task
+//!
+//! A [`User`] built around a transport shared through `Rc<RefCell<_>>` is neither `Send` nor
+//! `Sync`, and its protocol futures are `?Send` unless the `threadsafe` feature is enabled. A
+//! [`UserActor`] owns the user on the task running it, and performs the operations it receives
+//! through a channel. Its [`UserHandle`] is cloneable, `Send` and `Sync`: the tasks of a
+//! multi-threaded executor send, fetch and subscribe concurrently through their own clone of the
+//! handle, each of them awaiting the response to its own command. Commands are performed one at a
+//! time, in the order they are received.
+
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    message::{Topic, TransportMessage},
+    transport::Transport,
+};
+
+// Local
+use crate::{
+    api::{message::Message, send_response::SendResponse, user::User},
+    Error, Result,
+};
+
+extern crate std;
+
+/// Operation run on the [`User`] by [`UserHandle::with_user()`]
+type Operation<T> = Box<dyn FnOnce(&mut User<T>) + Send>;
+
+/// Packet sent by the [`UserActor`]
+struct Packet {
+    /// The [`Topic`] of the branch to send the packet to
+    topic: Topic,
+    /// The unmasked payload of the packet
+    public_payload: Vec<u8>,
+    /// The masked payload of the packet
+    masked_payload: Vec<u8>,
+}
+
+/// Command sent by a [`UserHandle`] to its [`UserActor`], with the sending end of its response
+enum Command<T, TSR> {
+    ReceiveMessage(Address, oneshot::Sender<Result<Message>>),
+    Sync(oneshot::Sender<Result<usize>>),
+    FetchNextMessages(oneshot::Sender<Result<Vec<Message>>>),
+    Subscribe(oneshot::Sender<Result<SendResponse<TSR>>>),
+    SendSignedPacket(Packet, oneshot::Sender<Result<SendResponse<TSR>>>),
+    SendTaggedPacket(Packet, oneshot::Sender<Result<SendResponse<TSR>>>),
+    WithUser(Operation<T>),
+}
+
+/// Owner of a [`User`], performing the commands sent by its [`UserHandle`]s
+///
+/// The actor is run either on a dedicated thread, with [`UserActor::spawn()`], or by the
+/// application with [`UserActor::run()`]. Transports relying on the reactor of `tokio`, like the
+/// uTangle client, are run by the application, on a `tokio` `LocalSet` for instance.
+pub struct UserActor<T, TSR> {
+    /// The [`User`] the commands are performed on
+    user: User<T>,
+    /// Receiving end of the commands
+    commands: mpsc::UnboundedReceiver<Command<T, TSR>>,
+}
+
+impl<T, TSR> UserActor<T, TSR> {
+    /// Creates a new [`UserActor`] owning a [`User`], with the [`UserHandle`] sending it commands.
+    /// The actor performs no command until it is run with [`UserActor::run()`].
+    ///
+    /// # Arguments
+    /// * `user`: The [`User`] the commands are performed on
+    pub fn new(user: User<T>) -> (Self, UserHandle<T, TSR>) {
+        let (sender, commands) = mpsc::unbounded();
+        (Self { user, commands }, UserHandle { commands: sender })
+    }
+}
+
+impl<T, TSR> UserActor<T, TSR>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage, SendResponse = TSR>,
+{
+    /// Performs the commands sent by the [`UserHandle`]s, one at a time, until every handle is
+    /// dropped. Returns the [`User`] once the last command has been performed.
+    pub async fn run(mut self) -> User<T> {
+        while let Some(command) = self.commands.next().await {
+            // A response whose handle stopped waiting for it is discarded
+            match command {
+                Command::ReceiveMessage(address, response) => {
+                    let _ = response.send(self.user.receive_message(address).await);
+                }
+                Command::Sync(response) => {
+                    let _ = response.send(self.user.sync().await);
+                }
+                Command::FetchNextMessages(response) => {
+                    let _ = response.send(self.user.fetch_next_messages().await);
+                }
+                Command::Subscribe(response) => {
+                    let _ = response.send(self.user.subscribe().await);
+                }
+                Command::SendSignedPacket(packet, response) => {
+                    let sent = self
+                        .user
+                        .send_signed_packet(packet.topic, packet.public_payload, packet.masked_payload)
+                        .await;
+                    let _ = response.send(sent);
+                }
+                Command::SendTaggedPacket(packet, response) => {
+                    let sent = self
+                        .user
+                        .send_tagged_packet(packet.topic, packet.public_payload, packet.masked_payload)
+                        .await;
+                    let _ = response.send(sent);
+                }
+                Command::WithUser(operation) => operation(&mut self.user),
+            }
+        }
+        self.user
+    }
+}
+
+impl<T, TSR> UserActor<T, TSR>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage, SendResponse = TSR> + 'static,
+    TSR: Send + 'static,
+{
+    /// Spawns a thread building a [`User`] and running its [`UserActor`] to completion, and returns
+    /// the [`UserHandle`] sending it commands. The user is built on the thread, so that neither the
+    /// user nor its transport need to be `Send`. The thread ends once every handle is dropped.
+    ///
+    /// # Arguments
+    /// * `build`: Builds the [`User`] owned by the actor
+    pub fn spawn<F>(build: F) -> UserHandle<T, TSR>
+    where
+        F: FnOnce() -> User<T> + Send + 'static,
+    {
+        let (sender, commands) = mpsc::unbounded();
+        std::thread::spawn(move || {
+            let actor = UserActor {
+                user: build(),
+                commands,
+            };
+            futures::executor::block_on(actor.run());
+        });
+        UserHandle { commands: sender }
+    }
+}
+
+/// Cloneable handle sending commands to a [`UserActor`] and awaiting their responses
+///
+/// The handle is `Send` and `Sync` as long as the messages and the send responses of the
+/// transport are, whether the [`User`] itself is or not. Every method fails with
+/// [`Error::ActorStopped`] once the actor stopped running.
+pub struct UserHandle<T, TSR> {
+    /// Sending end of the commands
+    commands: mpsc::UnboundedSender<Command<T, TSR>>,
+}
+
+impl<T, TSR> UserHandle<T, TSR> {
+    /// Sends a command to the actor and awaits its response
+    ///
+    /// # Arguments
+    /// * `command`: Builds the command around the sending end of its response
+    async fn request<R>(&self, command: impl FnOnce(oneshot::Sender<Result<R>>) -> Command<T, TSR>) -> Result<R> {
+        let (response, responded) = oneshot::channel();
+        self.commands
+            .unbounded_send(command(response))
+            .map_err(|_| Error::ActorStopped)?;
+        responded.await.map_err(|_| Error::ActorStopped)?
+    }
+
+    /// Returns true while the actor is running
+    pub fn is_running(&self) -> bool {
+        !self.commands.is_closed()
+    }
+
+    /// Receive a raw message packet using the internal [`Transport`] client
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to be retrieved.
+    pub async fn receive_message(&self, address: Address) -> Result<Message> {
+        self.request(|response| Command::ReceiveMessage(address, response))
+            .await
+    }
+
+    /// Iteratively fetches all the next messages until internal state has caught up
+    ///
+    /// If succeeded, returns the number of messages advanced.
+    pub async fn sync(&self) -> Result<usize> {
+        self.request(Command::Sync).await
+    }
+
+    /// Iteratively fetches all the pending messages from the transport
+    pub async fn fetch_next_messages(&self) -> Result<Vec<Message>> {
+        self.request(Command::FetchNextMessages).await
+    }
+
+    /// Create and send a new Subscription message, awaiting the stream author's keyload for any
+    /// further permissions
+    pub async fn subscribe(&self) -> Result<SendResponse<TSR>> {
+        self.request(Command::Subscribe).await
+    }
+
+    /// Create and send a new Signed Packet message to the specified branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    pub async fn send_signed_packet<P, M, Top>(
+        &self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        let packet = Packet::new(topic, public_payload, masked_payload);
+        self.request(|response| Command::SendSignedPacket(packet, response))
+            .await
+    }
+
+    /// Create and send a new Tagged Packet message to the specified branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    pub async fn send_tagged_packet<P, M, Top>(
+        &self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        let packet = Packet::new(topic, public_payload, masked_payload);
+        self.request(|response| Command::SendTaggedPacket(packet, response))
+            .await
+    }
+
+    /// Runs a synchronous operation on the [`User`] owned by the actor, in turn with the other
+    /// commands, and returns its result. Covers the operations of the [`User`] without a
+    /// dedicated method on the handle, like reading its cursors or its stream address.
+    ///
+    /// # Arguments
+    /// * `operation`: The operation to run on the [`User`]
+    pub async fn with_user<F, R>(&self, operation: F) -> Result<R>
+    where
+        F: FnOnce(&mut User<T>) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (response, responded) = oneshot::channel();
+        let operation: Operation<T> = Box::new(move |user| {
+            let _ = response.send(operation(user));
+        });
+        self.commands
+            .unbounded_send(Command::WithUser(operation))
+            .map_err(|_| Error::ActorStopped)?;
+        responded.await.map_err(|_| Error::ActorStopped)
+    }
+}
+
+impl Packet {
+    fn new<P, M, Top>(topic: Top, public_payload: P, masked_payload: M) -> Self
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        Self {
+            topic: topic.into(),
+            public_payload: public_payload.as_ref().to_vec(),
+            masked_payload: masked_payload.as_ref().to_vec(),
+        }
+    }
+}
+
+impl<T, TSR> Clone for UserHandle<T, TSR> {
+    fn clone(&self) -> Self {
+        Self {
+            commands: self.commands.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use futures::future;
+    use lets::{address::Address, id::Ed25519, message::TransportMessage, transport::bucket};
+
+    use crate::{Error, Result, User};
+
+    use super::{UserActor, UserHandle};
+
+    type SharedBucket = Rc<RefCell<bucket::Client>>;
+
+    #[tokio::test]
+    async fn handles_share_a_user_that_is_not_send() -> Result<()> {
+        fn assert_send_sync<H: Send + Sync + Clone>(_: &H) {}
+
+        let transport: SharedBucket = Rc::new(RefCell::new(bucket::Client::new()));
+        let mut author = User::builder()
+            .with_identity(Ed25519::from_seed("author"))
+            .with_transport(transport.clone())
+            .build();
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+
+        let (actor, handle): (_, UserHandle<SharedBucket, TransportMessage>) = UserActor::new(author);
+        assert_send_sync(&handle);
+        let publishers = async move {
+            let other = handle.clone();
+            let (first, second) = future::join(
+                handle.send_signed_packet("BASE_BRANCH", b"", b"first"),
+                other.send_tagged_packet("BASE_BRANCH", b"", b"second"),
+            )
+            .await;
+            first?;
+            second?;
+            let stream_address = handle.with_user(|author| author.stream_address()).await?;
+            assert_eq!(stream_address, Some(announcement.address()));
+            Ok::<_, Error>(())
+        };
+        let (author, published) = future::join(actor.run(), publishers).await;
+        published?;
+
+        let mut reader = User::builder().with_transport(transport).build();
+        reader.receive_message(author.stream_address().unwrap()).await?;
+        assert_eq!(reader.sync().await?, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn actor_spawned_on_a_thread_stops_with_its_handles() -> Result<()> {
+        let handle: UserHandle<SharedBucket, TransportMessage> = UserActor::spawn(|| {
+            User::builder()
+                .with_identity(Ed25519::from_seed("author"))
+                .with_transport(Rc::new(RefCell::new(bucket::Client::new())))
+                .build()
+        });
+        assert_eq!(handle.with_user(|author| author.stream_address()).await?, None);
+        assert!(handle.is_running());
+        assert!(matches!(
+            handle.receive_message(Address::default()).await,
+            Err(Error::Transport(..))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+// Rust
+use core::time::Duration;
+
+// 3rd-party
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{self, Either},
+};
+
+// IOTA
+
+// Streams
+use lets::{message::TransportMessage, sync::MaybeSend, transport::Transport};
+
+// Local
+use crate::{
+    api::{
+        message::Message,
+        messages::DetachedMessages,
+        runtime::{DefaultRuntime, Runtime, TaskSend},
+        shared_user::SharedUser,
+        user::User,
+    },
+    Error, Result,
+};
+
+/// Handle to a background task that periodically syncs a [`User`] with its stream
+///
+/// The task is spawned by [`User::start_auto_sync()`]. Every new message fetched by the task is
+/// buffered into an internal channel, consumable through [`AutoSync::messages()`]. The [`User`]
+/// remains accessible through [`AutoSync::user()`]: the task only locks it while fetching a message,
+/// so operations performed on it are interleaved with the messages fetched by the task. The task
+/// stops when the handle is dropped, or explicitly through [`AutoSync::stop()`].
+pub struct AutoSync<T> {
+    /// Shared handle to the synced [`User`]
+    user: SharedUser<T>,
+    /// Receiving end of the buffered messages
+    messages: mpsc::UnboundedReceiver<Result<Message>>,
+    /// Dropping this sender stops the background task
+    _stop: oneshot::Sender<()>,
+}
+
+impl<T> AutoSync<T> {
+    /// Returns the shared handle to the [`User`] being synced
+    pub fn user(&self) -> &SharedUser<T> {
+        &self.user
+    }
+
+    /// Returns a [`Stream`](futures::Stream) of the messages fetched by the background task since
+    /// the last time they were consumed. Transport failures during a sync round are yielded as
+    /// `Err`, the task retries on the next round.
+    pub fn messages(&mut self) -> &mut mpsc::UnboundedReceiver<Result<Message>> {
+        &mut self.messages
+    }
+
+    /// Stops the background task, returning the shared handle to the [`User`]. Messages already
+    /// buffered and not yet consumed are discarded.
+    pub fn stop(self) -> SharedUser<T> {
+        self.user
+    }
+}
+
+impl<T> User<T>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend + TaskSend + 'static,
+{
+    /// Consumes the [`User`] and spawns a background task that fetches the new messages of the
+    /// stream every `interval`, keeping the cursors of the [`User`] up to date. The task runs on
+    /// the `tokio` runtime the caller runs in, or on the browser event loop when targeting
+    /// `wasm32`. Applications running on another executor use [`User::start_auto_sync_on()`].
+    ///
+    /// Errors with [`Error::RuntimeUnavailable`] when called outside of a `tokio` runtime, dropping
+    /// the [`User`]. Passing [`TokioRuntime::current()`](crate::TokioRuntime::current) to
+    /// [`User::start_auto_sync_on()`] checks the runtime before the [`User`] is consumed.
+    ///
+    /// # Arguments
+    /// * `interval`: Time to wait between the end of a sync round and the start of the next one
+    pub fn start_auto_sync(self, interval: Duration) -> Result<AutoSync<T>> {
+        Ok(self.start_auto_sync_on(DefaultRuntime::current()?, interval))
+    }
+
+    /// Consumes the [`User`] and spawns a background task on the provided [`Runtime`] that fetches
+    /// the new messages of the stream every `interval`, keeping the cursors of the [`User`] up to
+    /// date.
+    ///
+    /// # Arguments
+    /// * `runtime`: The [`Runtime`] the task is spawned on and sleeps with
+    /// * `interval`: Time to wait between the end of a sync round and the start of the next one
+    pub fn start_auto_sync_on<R>(self, runtime: R, interval: Duration) -> AutoSync<T>
+    where
+        R: Runtime,
+    {
+        let user = SharedUser::new(self);
+        let (sender, messages) = mpsc::unbounded();
+        let (stop, stopped) = oneshot::channel();
+        runtime.spawn(auto_sync(runtime.clone(), user.clone(), sender, stopped, interval));
+        AutoSync {
+            user,
+            messages,
+            _stop: stop,
+        }
+    }
+}
+
+/// Sync loop run by the background task
+///
+/// # Arguments
+/// * `runtime`: The [`Runtime`] sleeping between sync rounds
+/// * `user`: Shared handle to the [`User`] being synced
+/// * `sender`: Sending end of the buffered messages
+/// * `stopped`: Resolves once the [`AutoSync`] handle is dropped
+/// * `interval`: Time to wait between sync rounds
+async fn auto_sync<R, T>(
+    runtime: R,
+    user: SharedUser<T>,
+    sender: mpsc::UnboundedSender<Result<Message>>,
+    mut stopped: oneshot::Receiver<()>,
+    interval: Duration,
+) where
+    R: Runtime,
+    T: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
+{
+    loop {
+        // The user is only locked while a message is fetched, the traversal being detached from it
+        // in between
+        let mut messages = DetachedMessages::default();
+        loop {
+            let msg = {
+                let mut user = user.lock().await;
+                messages.next(&mut user).await
+            };
+            match msg {
+                Some(Ok(msg)) if msg.is_out_of_order() => continue,
+                Some(msg) => {
+                    if sender.unbounded_send(msg.map_err(Error::Messages)).is_err() {
+                        // Handle has been dropped, nobody is listening anymore
+                        return;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        if let Either::Right(_) = future::select(runtime.sleep(interval), &mut stopped).await {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{sync::Arc, vec::Vec};
+    use core::time::Duration;
+
+    use futures::StreamExt;
+    use lets::{id::Ed25519, sync::Mutex, transport::bucket};
+
+    use crate::{Error, Result, User};
+
+    type Transport = Arc<Mutex<bucket::Client>>;
+
+    #[test]
+    fn auto_sync_outside_of_a_runtime_is_an_error() {
+        let user = User::builder().with_transport(Transport::default()).build();
+        assert!(matches!(
+            user.start_auto_sync(Duration::from_millis(10)),
+            Err(Error::RuntimeUnavailable("tokio"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn auto_sync_yields_the_new_messages_of_the_stream() -> Result<()> {
+        let transport = Transport::default();
+        let mut author = User::builder()
+            .with_identity(Ed25519::from_seed("author"))
+            .with_transport(transport.clone())
+            .build();
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let mut reader = User::builder().with_transport(transport).build();
+        reader.receive_message(announcement.address()).await?;
+        let mut auto_sync = reader.start_auto_sync(Duration::from_millis(10))?;
+
+        let mut sent = Vec::new();
+        for payload in ["first", "second"] {
+            let packet = author.send_signed_packet("BASE_BRANCH", payload, b"").await?;
+            sent.push(packet.address());
+        }
+        let mut received = Vec::new();
+        while received.len() < sent.len() {
+            let message = auto_sync.messages().next().await.unwrap()?;
+            received.push(message.address());
+        }
+        assert_eq!(received, sent);
+
+        // The user is usable while the task runs, and was kept up to date by it
+        assert_eq!(auto_sync.user().lock().await.sync().await?, 0);
+        let reader = auto_sync.stop();
+        assert_eq!(reader.lock().await.sync().await?, 0);
+        Ok(())
+    }
+}
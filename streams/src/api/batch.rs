@@ -0,0 +1,151 @@
+//! Decoding of the parts of a batch of messages that do not depend on the state of the
+//! [`User`](crate::User)
+//!
+//! The headers of the messages are decoded and the messages hashed independently of each other, so
+//! this preprocessing is run upfront for the whole batch, on the rayon thread pool with the
+//! `parallel-preparse` feature. It is the only parallel part of the processing of a batch: the
+//! contents of the messages, where most of the sponge permutations are, are then decoded in order
+//! by the [`User`](crate::User), as each of them can depend on the messages preceding it.
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    message::{PreparsedMessage, TransportMessage},
+};
+
+// Local
+use crate::{
+    api::notarizer::{self, DIGEST_SIZE},
+    message::legacy,
+    Error, Result,
+};
+
+/// A message of a batch, preprocessed independently of the other messages
+pub(crate) enum Preparsed {
+    /// A legacy (v1) message, decoded whole by the [`User`](crate::User)
+    Legacy(TransportMessage),
+    /// A message with its header decoded, and its hash if the messages read are notarized
+    Message(Result<PreparsedMessage>, Option<[u8; DIGEST_SIZE]>),
+}
+
+/// Hashes a message and decodes its header
+///
+/// # Arguments
+/// * `address`: The [`Address`] of the message
+/// * `msg`: The raw [`TransportMessage`]
+/// * `size_limit`: The largest length or item count accepted in the message
+/// * `notarized`: Whether the messages read are notarized, and must be hashed
+async fn preparse(address: Address, msg: TransportMessage, size_limit: usize, notarized: bool) -> Preparsed {
+    if legacy::is_legacy(&msg) {
+        return Preparsed::Legacy(msg);
+    }
+    let hash = notarized.then(|| notarizer::message_hash(&msg));
+    let preparsed = msg
+        .parse_header_with_size_limit(size_limit)
+        .await
+        .map_err(|e| Error::Unwrapping("header", address, e));
+    Preparsed::Message(preparsed, hash)
+}
+
+/// Preprocesses the messages of a batch one after the other, keeping their order
+///
+/// # Arguments
+/// * `msgs`: The [`Address`] and raw [`TransportMessage`] of each message of the batch
+/// * `size_limit`: The largest length or item count accepted in the messages
+/// * `notarized`: Whether the messages read are notarized, and must be hashed
+#[cfg(not(feature = "parallel-preparse"))]
+pub(crate) async fn preparse_batch(
+    msgs: Vec<(Address, TransportMessage)>,
+    size_limit: usize,
+    notarized: bool,
+) -> Vec<(Address, Preparsed)> {
+    let mut batch = Vec::with_capacity(msgs.len());
+    for (address, msg) in msgs {
+        batch.push((address, preparse(address, msg, size_limit, notarized).await));
+    }
+    batch
+}
+
+/// Preprocesses the messages of a batch in parallel on the rayon thread pool, keeping their order.
+/// The calling task is blocked until the whole batch has been preprocessed.
+///
+/// Decoding a header never waits, so each message is preprocessed synchronously by polling its
+/// preprocessing once, without blocking the threads of the pool on an executor. A preprocessing
+/// that would wait is reported as an error of its message.
+///
+/// # Arguments
+/// * `msgs`: The [`Address`] and raw [`TransportMessage`] of each message of the batch
+/// * `size_limit`: The largest length or item count accepted in the messages
+/// * `notarized`: Whether the messages read are notarized, and must be hashed
+#[cfg(feature = "parallel-preparse")]
+pub(crate) async fn preparse_batch(
+    msgs: Vec<(Address, TransportMessage)>,
+    size_limit: usize,
+    notarized: bool,
+) -> Vec<(Address, Preparsed)> {
+    use futures::FutureExt;
+    use rayon::prelude::*;
+
+    msgs.into_par_iter()
+        .map(|(address, msg)| {
+            let preparsed = preparse(address, msg, size_limit, notarized)
+                .now_or_never()
+                .unwrap_or_else(|| {
+                    let error = anyhow::anyhow!("decoding the header of the message at {} would wait", address);
+                    Preparsed::Message(Err(Error::External(error)), None)
+                });
+            (address, preparsed)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        api::fixtures::{new_reader, new_transport, new_user},
+        BatchRecord, Error, Result,
+    };
+
+    #[tokio::test]
+    async fn batches_are_read_all_together() -> Result<()> {
+        let transport = new_transport();
+        let mut bank = new_user("bank", &transport);
+        let mut auditor = new_reader(&transport);
+
+        let announcement = bank.create_stream("LEDGER").await?;
+        assert!(matches!(
+            bank.send_batch("LEDGER", Vec::<(Vec<u8>, Vec<u8>)>::new()).await,
+            Err(Error::PayloadEmpty)
+        ));
+        bank.send_batch(
+            "LEDGER",
+            [(&b"debit"[..], &b"alice -10"[..]), (&b"credit"[..], &b"bob +10"[..])],
+        )
+        .await?;
+        bank.send_signed_packet("LEDGER", b"", b"closing").await?;
+
+        auditor.receive_message(announcement.address()).await?;
+        let messages = auditor.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        let batch = messages[0].as_batch_packet().unwrap();
+        assert_eq!(&batch.publisher_identifier, bank.identifier().unwrap());
+        assert_eq!(
+            batch.records,
+            [
+                BatchRecord::new(&b"debit"[..], &b"alice -10"[..]),
+                BatchRecord::new(&b"credit"[..], &b"bob +10"[..]),
+            ]
+        );
+        assert_eq!(messages[1].masked_payload(), Some(&b"closing"[..]));
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Human-readable descriptions of branches
+//!
+//! [`BranchMetadata`] is a small map of text entries describing what a branch contains, like its
+//! name, a description or the URI of the schema of its payloads. Keyloads sent with
+//! [`User::send_keyload_with_metadata()`](crate::User::send_keyload_with_metadata) carry the
+//! metadata masked and signed along the key of the branch, so only the recipients of the keyload
+//! read it, with [`Keyload::metadata()`](crate::api::message::Keyload::metadata).
+//!
+//! ```ddml
+//! type BranchMetadata {
+//!     mask        u8      size(n_entries);
+//!     repeated(n_entries):
+//!       mask      bytes   key;
+//!       mask      bytes   value;
+//! }
+//! ```
+
+// Rust
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Mask},
+        io,
+        types::{Bytes, Size},
+    },
+    error::{Error as SpongosError, Result as SpongosResult},
+};
+
+// Local
+
+/// Key of the name of the branch
+pub const NAME: &str = "name";
+/// Key of the description of the branch
+pub const DESCRIPTION: &str = "description";
+/// Key of the URI of the schema of the payloads of the branch
+pub const SCHEMA_URI: &str = "schema_uri";
+
+/// Text entries describing a branch, sorted by key
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BranchMetadata {
+    entries: BTreeMap<String, String>,
+}
+
+impl BranchMetadata {
+    /// Creates an empty [`BranchMetadata`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the branch
+    ///
+    /// # Arguments
+    /// * `name`: A human-readable name of the branch
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        self.with_entry(NAME, name)
+    }
+
+    /// Sets the description of the branch
+    ///
+    /// # Arguments
+    /// * `description`: A human-readable description of the contents of the branch
+    pub fn with_description(self, description: impl Into<String>) -> Self {
+        self.with_entry(DESCRIPTION, description)
+    }
+
+    /// Sets the URI of the schema the payloads of the branch follow
+    ///
+    /// # Arguments
+    /// * `schema_uri`: The URI of the schema
+    pub fn with_schema_uri(self, schema_uri: impl Into<String>) -> Self {
+        self.with_entry(SCHEMA_URI, schema_uri)
+    }
+
+    /// Sets an application defined entry, replacing the previous value of the key
+    ///
+    /// # Arguments
+    /// * `key`: The key of the entry
+    /// * `value`: The value of the entry
+    pub fn with_entry(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.entries.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the name of the branch, if set
+    pub fn name(&self) -> Option<&str> {
+        self.get(NAME)
+    }
+
+    /// Returns the description of the branch, if set
+    pub fn description(&self) -> Option<&str> {
+        self.get(DESCRIPTION)
+    }
+
+    /// Returns the URI of the schema of the payloads of the branch, if set
+    pub fn schema_uri(&self) -> Option<&str> {
+        self.get(SCHEMA_URI)
+    }
+
+    /// Returns the value of an entry, if set
+    ///
+    /// # Arguments
+    /// * `key`: The key of the entry
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Returns an iterator over the entries, sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Converts a decoded entry field into text
+fn into_string(bytes: Vec<u8>) -> SpongosResult<String> {
+    String::from_utf8(bytes).map_err(|e| SpongosError::Context("Mask", e.to_string()))
+}
+
+impl Mask<&BranchMetadata> for sizeof::Context {
+    fn mask(&mut self, metadata: &BranchMetadata) -> SpongosResult<&mut Self> {
+        self.mask(Size::new(metadata.len()))?;
+        for (key, value) in metadata.iter() {
+            self.mask(Bytes::new(key))?.mask(Bytes::new(value))?;
+        }
+        Ok(self)
+    }
+}
+
+impl<OS> Mask<&BranchMetadata> for wrap::Context<OS>
+where
+    OS: io::OStream,
+{
+    fn mask(&mut self, metadata: &BranchMetadata) -> SpongosResult<&mut Self> {
+        self.mask(Size::new(metadata.len()))?;
+        for (key, value) in metadata.iter() {
+            self.mask(Bytes::new(key))?.mask(Bytes::new(value))?;
+        }
+        Ok(self)
+    }
+}
+
+impl<IS> Mask<&mut BranchMetadata> for unwrap::Context<IS>
+where
+    IS: io::IStream,
+{
+    fn mask(&mut self, metadata: &mut BranchMetadata) -> SpongosResult<&mut Self> {
+        let mut n_entries = Size::default();
+        self.mask(&mut n_entries)?
+            .check_size("branch metadata entries", n_entries.inner())?;
+        for _ in 0..n_entries.inner() {
+            let mut key = Vec::new();
+            let mut value = Vec::new();
+            self.mask(Bytes::new(&mut key))?.mask(Bytes::new(&mut value))?;
+            metadata.entries.insert(into_string(key)?, into_string(value)?);
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::id::Permissioned;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Result,
+    };
+
+    use super::BranchMetadata;
+
+    #[tokio::test]
+    async fn keyload_metadata_is_only_readable_by_its_recipients() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut subscriber = new_user("subscriber", &transport);
+        let mut outsider = new_user("outsider", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        outsider.receive_message(announcement.address()).await?;
+        subscriber.subscribe().await?;
+        author.sync().await?;
+
+        let metadata = BranchMetadata::new()
+            .with_name("Telemetry")
+            .with_description("Readings of the sensors of the plant")
+            .with_schema_uri("https://example.com/schemas/telemetry.json")
+            .with_entry("unit", "celsius");
+        let subscriber_id = subscriber.identifier().unwrap().clone();
+        author
+            .send_keyload_with_metadata("BASE_BRANCH", [Permissioned::Read(&subscriber_id)], [], &metadata)
+            .await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+
+        let messages = subscriber.fetch_next_messages().await?;
+        let keyload = messages[0].as_keyload().unwrap();
+        assert_eq!(keyload.metadata(), Some(&metadata));
+        assert_eq!(keyload.metadata().unwrap().name(), Some("Telemetry"));
+        assert_eq!(keyload.metadata().unwrap().get("unit"), Some("celsius"));
+        assert_eq!(messages[1].as_signed_packet().unwrap().masked_payload, b"masked");
+
+        let messages = outsider.fetch_next_messages().await?;
+        assert!(messages[0].as_keyload().unwrap().metadata().is_none());
+
+        // Keyloads sent without metadata carry none
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        assert!(messages[0].as_keyload().unwrap().metadata().is_none());
+
+        // A keyload carries both metadata and an expiry
+        author
+            .send_keyload_with_metadata_and_expiry(
+                "BASE_BRANCH",
+                [Permissioned::Read(&subscriber_id)],
+                [],
+                &metadata,
+                u64::MAX,
+            )
+            .await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        let keyload = messages[0].as_keyload().unwrap();
+        assert_eq!(keyload.metadata(), Some(&metadata));
+        assert_eq!(keyload.expires_at, Some(u64::MAX));
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! Automatic rotation of long-running branches into epoch branches
+//!
+//! A branch that is published to forever keeps growing, and so does the state needed to read it,
+//! while its key never changes. A [`User`](crate::User) given a [`BranchRotation`] for a branch
+//! with [`User::set_branch_rotation()`](crate::User::set_branch_rotation) publishes the packets
+//! sent to the branch in epoch branches instead. Epoch branches are children of the branch, named
+//! after the UTC start of the epoch, such as `telemetry/2024-06`. Once the epoch is over, the next
+//! packet sent to the branch starts a new epoch branch from the previous one, along with a fresh
+//! keyload granting the permissions of the last keyload of the previous epoch, if it had any.
+//! Readers iterate the epochs of a branch with [`User::epochs()`](crate::User::epochs), and the
+//! previous epochs can be pruned without affecting the current one.
+
+// Rust
+use alloc::{format, string::String};
+use core::time::Duration;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::message::{Topic, TOPIC_SEPARATOR};
+
+// Local
+
+/// Seconds in a day, the shortest period of the epochs named after their date
+const DAY: u64 = 86_400;
+/// Seconds in the longest month, the shortest period of the epochs named after their month
+const MONTH: u64 = 31 * DAY;
+
+/// Length of the epochs of a rotated branch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RotationPeriod {
+    /// Time elapsed since the start of the epoch. Without the `std` feature there is no clock to
+    /// read, and the epochs never end.
+    Time(Duration),
+    /// Packets published by the user in the epoch, zero being treated as one
+    Messages(usize),
+}
+
+impl From<Duration> for RotationPeriod {
+    fn from(duration: Duration) -> Self {
+        Self::Time(duration)
+    }
+}
+
+impl From<usize> for RotationPeriod {
+    fn from(messages: usize) -> Self {
+        Self::Messages(messages)
+    }
+}
+
+/// Rotation policy of a branch, applied by [`User::set_branch_rotation()`](crate::User::set_branch_rotation)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BranchRotation {
+    /// The packets are published in the branch itself
+    Never,
+    /// The packets are published in epoch branches lasting the provided period
+    Every(RotationPeriod),
+}
+
+impl Default for BranchRotation {
+    fn default() -> Self {
+        Self::Never
+    }
+}
+
+/// Current epoch of a rotated branch
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Epoch {
+    /// The [`Topic`] of the epoch branch
+    pub(crate) topic: Topic,
+    /// Number of epochs of the rotated branch started before this one
+    pub(crate) index: usize,
+    /// Unix timestamp, in seconds, at which the epoch started. None if no clock was available.
+    pub(crate) started_at: Option<u64>,
+    /// Cursor of the user in the epoch branch once the epoch started
+    pub(crate) first_cursor: usize,
+}
+
+impl Epoch {
+    /// Returns true if the epoch has lasted the rotation period
+    ///
+    /// # Arguments
+    /// * `period`: The [`RotationPeriod`] of the rotated branch
+    /// * `now`: The current Unix timestamp, in seconds, if a clock is available
+    /// * `cursor`: The current cursor of the user in the epoch branch
+    pub(crate) fn is_over(&self, period: RotationPeriod, now: Option<u64>, cursor: usize) -> bool {
+        match period {
+            RotationPeriod::Time(duration) => match (self.started_at, now) {
+                (Some(started_at), Some(now)) => now.saturating_sub(started_at) >= duration.as_secs(),
+                _ => false,
+            },
+            RotationPeriod::Messages(messages) => cursor.saturating_sub(self.first_cursor) >= messages.max(1),
+        }
+    }
+}
+
+/// Returns the name of an epoch branch, the UTC start of the epoch with the precision needed to
+/// tell the epochs apart: the month for epochs lasting a month or more, the date for epochs lasting
+/// a day or more, and the time to the second otherwise. Without a clock, the epochs are numbered.
+///
+/// # Arguments
+/// * `period`: The [`RotationPeriod`] of the rotated branch
+/// * `started_at`: The Unix timestamp, in seconds, at which the epoch starts, if known
+/// * `index`: The number of epochs of the rotated branch started before this one
+pub(crate) fn epoch_label(period: RotationPeriod, started_at: Option<u64>, index: usize) -> String {
+    let started_at = match started_at {
+        Some(started_at) => started_at,
+        None => return format!("{:06}", index),
+    };
+    let (year, month, day) = civil_date(started_at / DAY);
+    let seconds = started_at % DAY;
+    match period {
+        RotationPeriod::Time(duration) if duration.as_secs() >= MONTH => format!("{:04}-{:02}", year, month),
+        RotationPeriod::Time(duration) if duration.as_secs() >= DAY => {
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        }
+        _ => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+    }
+}
+
+/// Returns the [`Topic`] of an epoch branch of a rotated branch
+///
+/// # Arguments
+/// * `topic`: The [`Topic`] of the rotated branch
+/// * `label`: The name of the epoch, as returned by [`epoch_label()`]
+/// * `duplicates`: The number of known epoch branches with the same name, suffixed to the name to
+///   tell the epochs apart
+pub(crate) fn epoch_topic(topic: &Topic, label: &str, duplicates: usize) -> Topic {
+    match duplicates {
+        0 => Topic::new(format!("{}{}{}", topic, TOPIC_SEPARATOR, label)),
+        duplicates => Topic::new(format!("{}{}{}.{}", topic, TOPIC_SEPARATOR, label, duplicates)),
+    }
+}
+
+/// Returns true if the segment of a topic is the name of an epoch branch, as returned by
+/// [`epoch_label()`], possibly followed by a suffix telling apart the epochs started within its
+/// precision
+///
+/// # Arguments
+/// * `segment`: The last segment of the topic
+pub(crate) fn is_epoch_label(segment: &str) -> bool {
+    segment.starts_with(|c: char| c.is_ascii_digit())
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | ':' | 'T' | 'Z' | '.'))
+}
+
+/// Returns the UTC year, month and day of a number of days elapsed since the Unix epoch
+///
+/// # Arguments
+/// * `days`: The days elapsed since 1970-01-01
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Days since 0000-03-01, so that leap days end the years
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    use lets::message::{Topic, TopicHash};
+
+    use crate::{api::fixtures::author_subscriber_fixture, Result, User};
+
+    use super::{epoch_label, is_epoch_label, BranchRotation, RotationPeriod};
+
+    #[test]
+    fn epochs_are_named_after_their_start() {
+        // 2024-02-29T13:45:30Z
+        let started_at = Some(1_709_214_330);
+        let monthly = RotationPeriod::Time(Duration::from_secs(31 * 86_400));
+        let daily = RotationPeriod::Time(Duration::from_secs(86_400));
+        let hourly = RotationPeriod::Time(Duration::from_secs(3_600));
+        assert_eq!(epoch_label(monthly, started_at, 3), "2024-02");
+        assert_eq!(epoch_label(daily, started_at, 3), "2024-02-29");
+        assert_eq!(epoch_label(hourly, started_at, 3), "2024-02-29T13:45:30Z");
+        assert_eq!(
+            epoch_label(RotationPeriod::Messages(100), started_at, 3),
+            "2024-02-29T13:45:30Z"
+        );
+        assert_eq!(epoch_label(monthly, None, 3), "000003");
+
+        assert!(is_epoch_label("2024-02-29T13:45:30Z.1"));
+        assert!(is_epoch_label("000003"));
+        assert!(!is_epoch_label("line1"));
+    }
+
+    #[tokio::test]
+    async fn rotated_branches_are_published_in_epoch_branches() -> Result<()> {
+        let (mut author, mut subscriber, _, transport) = author_subscriber_fixture().await?;
+        author.new_branch("BASE_BRANCH", "telemetry").await?;
+        author.send_keyload_for_all("telemetry").await?;
+        let telemetry = Topic::from("telemetry");
+        author.set_branch_rotation("telemetry", BranchRotation::Every(RotationPeriod::Messages(2)));
+        assert_eq!(
+            author.branch_rotation(&telemetry),
+            BranchRotation::Every(RotationPeriod::Messages(2))
+        );
+
+        let mut packets = Vec::new();
+        for i in 0..5u8 {
+            packets.push(author.send_signed_packet("telemetry", b"", [i]).await?);
+        }
+        // Two packets per epoch, the last epoch being current
+        let epochs: Vec<Topic> = author.epochs(&telemetry).into_iter().cloned().collect();
+        assert_eq!(epochs.len(), 3);
+        assert_eq!(author.current_epoch(&telemetry), epochs.last());
+
+        // The subscriber reads every epoch, each one having a fresh keyload
+        let messages = subscriber.fetch_next_messages().await?;
+        let payloads: Vec<Vec<u8>> = messages
+            .iter()
+            .filter(|msg| msg.is_signed_packet())
+            .filter_map(|msg| msg.masked_payload().map(<[u8]>::to_vec))
+            .collect();
+        assert_eq!(payloads, (0..5).map(|i| vec![i]).collect::<Vec<_>>());
+        assert_eq!(messages.iter().filter(|msg| msg.is_keyload()).count(), 4);
+        assert_eq!(subscriber.epochs(&telemetry), epochs.iter().collect::<Vec<_>>());
+        let topic_hashes: Vec<TopicHash> = packets
+            .iter()
+            .map(|packet| {
+                messages
+                    .iter()
+                    .find(|msg| msg.address() == packet.address())
+                    .map(|msg| *msg.header().topic_hash())
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(topic_hashes[0], TopicHash::from(&epochs[0]));
+        assert_eq!(topic_hashes[2], TopicHash::from(&epochs[1]));
+        assert_eq!(topic_hashes[4], TopicHash::from(&epochs[2]));
+
+        // The epoch survives a backup, the next packet completing it
+        let mut author = User::restore(author.backup("password").await?, "password", transport).await?;
+        author.set_branch_rotation("telemetry", BranchRotation::Every(RotationPeriod::Messages(2)));
+        author.send_signed_packet("telemetry", b"", [5]).await?;
+        assert_eq!(author.epochs(&telemetry).len(), 3);
+        author.send_signed_packet("telemetry", b"", [6]).await?;
+        assert_eq!(author.epochs(&telemetry).len(), 4);
+
+        // Without rotation, packets are published in the branch itself again
+        author.set_branch_rotation("telemetry", BranchRotation::Never);
+        assert_eq!(author.current_epoch(&telemetry), None);
+        author.send_signed_packet("telemetry", b"", [7]).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//! Management of the users of many channels sharing one transport
+//!
+//! Aggregator services follow hundreds of channels, each of them read by its own [`User`]. A
+//! [`ChannelManager`] owns these users by channel name, builds and restores them around clones
+//! of a single transport, so that they share its connections, and merges their [`Messages`]
+//! streams into a single [`ChannelMessages`] stream, whose messages are tagged by channel.
+
+// Rust
+use alloc::{string::String, vec::Vec};
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+// 3rd-party
+use futures::Stream;
+use hashbrown::HashMap;
+
+// IOTA
+
+// Streams
+use lets::{message::TransportMessage, sync::MaybeSend, transport::Transport};
+
+// Local
+use crate::{
+    api::{message::Message, messages::Messages, user::User, user_builder::UserBuilder},
+    Error, Result,
+};
+
+/// Owner of the [`User`] of each of many channels, by channel name, sharing one transport
+///
+/// The users are built with [`ChannelManager::builder()`] or restored with
+/// [`ChannelManager::restore()`] around clones of the transport of the manager. Transports
+/// sharing their connections between clones, like the uTangle client, whose clones share a
+/// connection pool, or transports shared through `Rc<RefCell<_>>` or `Arc<Mutex<_>>`, are thus
+/// shared by all the channels.
+pub struct ChannelManager<T> {
+    /// The transport the users of the channels are built around
+    transport: T,
+    /// The users of the channels, by channel name
+    channels: HashMap<String, User<T>>,
+}
+
+impl<T> ChannelManager<T> {
+    /// Creates a [`ChannelManager`] without channels
+    ///
+    /// # Arguments
+    /// * `transport`: The transport shared by the users of the channels
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Returns a reference to the transport shared by the users of the channels
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Adds the [`User`] of a channel, returning the user previously managed under the same name,
+    /// if any
+    ///
+    /// # Arguments
+    /// * `name`: The name of the channel
+    /// * `user`: The [`User`] reading and publishing in the channel
+    pub fn insert<N: Into<String>>(&mut self, name: N, user: User<T>) -> Option<User<T>> {
+        self.channels.insert(name.into(), user)
+    }
+
+    /// Removes a channel, returning its [`User`] if the channel was managed
+    ///
+    /// # Arguments
+    /// * `name`: The name of the channel
+    pub fn remove(&mut self, name: &str) -> Option<User<T>> {
+        self.channels.remove(name)
+    }
+
+    /// Returns a reference to the [`User`] of a channel, if the channel is managed
+    ///
+    /// # Arguments
+    /// * `name`: The name of the channel
+    pub fn get(&self, name: &str) -> Option<&User<T>> {
+        self.channels.get(name)
+    }
+
+    /// Returns a mutable reference to the [`User`] of a channel, if the channel is managed
+    ///
+    /// # Arguments
+    /// * `name`: The name of the channel
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut User<T>> {
+        self.channels.get_mut(name)
+    }
+
+    /// Returns the names of the managed channels, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.channels.keys().map(String::as_str)
+    }
+
+    /// Returns the number of managed channels
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Returns true if no channel is managed
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+}
+
+impl<T> ChannelManager<T>
+where
+    T: for<'a> Transport<'a> + Clone,
+{
+    /// Returns a [`UserBuilder`] using a clone of the shared transport, for the [`User`] of a new
+    /// channel, added with [`ChannelManager::insert()`] once built
+    pub fn builder(&self) -> UserBuilder<T> {
+        User::builder().with_transport(self.transport.clone())
+    }
+
+    /// Restores the [`User`] of a channel from a backup, around a clone of the shared transport,
+    /// and adds it under the name of the channel, replacing the user previously managed under the
+    /// same name, if any. Returns a mutable reference to the restored [`User`].
+    ///
+    /// # Arguments
+    /// * `name`: The name of the channel
+    /// * `backup`: The backup of the [`User`], created with [`User::backup()`]
+    /// * `pwd`: The password the backup was encrypted with
+    pub async fn restore<N, B, P>(&mut self, name: N, backup: B, pwd: P) -> Result<&mut User<T>>
+    where
+        N: Into<String>,
+        B: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+    {
+        let user = User::restore(backup, pwd, self.transport.clone()).await?;
+        let name = name.into();
+        self.channels.insert(name.clone(), user);
+        Ok(self
+            .channels
+            .get_mut(&name)
+            .expect("the user of the channel was just inserted"))
+    }
+}
+
+impl<T> ChannelManager<T>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
+{
+    /// Returns a [`ChannelMessages`] stream merging the [`Messages`] streams of all the managed
+    /// channels. The stream ends once the messages currently available in every channel have been
+    /// yielded.
+    pub fn messages(&mut self) -> ChannelMessages<'_, T> {
+        let streams = self
+            .channels
+            .iter_mut()
+            .map(|(name, user)| (name.as_str(), user.messages()))
+            .collect();
+        ChannelMessages { streams, next: 0 }
+    }
+
+    /// Fetches and processes the messages currently available in every managed channel, one
+    /// channel after the other. Returns the number of messages processed, over all the channels.
+    /// Stops at the first channel failing to sync.
+    pub async fn sync(&mut self) -> Result<usize> {
+        let mut synced = 0;
+        for user in self.channels.values_mut() {
+            synced += user.sync().await?;
+        }
+        Ok(synced)
+    }
+}
+
+/// [`Stream`] of the messages of all the channels of a [`ChannelManager`], each message tagged
+/// with the name of its channel. The [`Messages`] streams of the channels are polled in turn, so
+/// that a busy channel does not starve the others. A failure is yielded with the name of the
+/// channel it happened in, and does not end the stream of the other channels.
+pub struct ChannelMessages<'a, T> {
+    /// The [`Messages`] streams of the channels that did not end yet, with the name of their channel
+    streams: Vec<(&'a str, Messages<'a, T>)>,
+    /// Index of the stream polled first by the next poll
+    next: usize,
+}
+
+impl<'a, T> Stream for ChannelMessages<'a, T>
+where
+    T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
+{
+    type Item = (&'a str, Result<Message>);
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut pending = 0;
+        while pending < this.streams.len() {
+            let index = this.next % this.streams.len();
+            match Pin::new(&mut this.streams[index].1).poll_next(ctx) {
+                Poll::Ready(Some(message)) => {
+                    this.next = index + 1;
+                    return Poll::Ready(Some((this.streams[index].0, message.map_err(Error::Messages))));
+                }
+                // The following stream takes the place of the ended one
+                Poll::Ready(None) => {
+                    this.streams.remove(index);
+                }
+                Poll::Pending => {
+                    this.next = index + 1;
+                    pending += 1;
+                }
+            }
+        }
+        match this.streams.is_empty() {
+            true => Poll::Ready(None),
+            false => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+
+    use futures::StreamExt;
+    use lets::{id::Ed25519, transport::bucket};
+
+    use crate::{Result, User};
+
+    use super::ChannelManager;
+
+    #[tokio::test]
+    async fn messages_of_all_channels_are_merged_and_tagged() -> Result<()> {
+        let transport = Rc::new(RefCell::new(bucket::Client::new()));
+        let mut manager = ChannelManager::new(transport.clone());
+        for channel in ["temperature", "humidity"] {
+            let mut author = User::builder()
+                .with_identity(Ed25519::from_seed(channel))
+                .with_transport(transport.clone())
+                .build();
+            let announcement = author.create_stream("BASE_BRANCH").await?;
+            author.send_signed_packet("BASE_BRANCH", b"", channel).await?;
+            author.send_signed_packet("BASE_BRANCH", b"", channel).await?;
+
+            let mut reader = manager.builder().build();
+            reader.receive_message(announcement.address()).await?;
+            manager.insert(channel, reader);
+        }
+
+        let mut tagged: Vec<(&str, Vec<u8>)> = manager
+            .messages()
+            .map(|(channel, message)| message.map(|message| (channel, message.masked_payload().unwrap().to_vec())))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+        tagged.sort();
+        assert_eq!(
+            tagged,
+            [
+                ("humidity", b"humidity".to_vec()),
+                ("humidity", b"humidity".to_vec()),
+                ("temperature", b"temperature".to_vec()),
+                ("temperature", b"temperature".to_vec()),
+            ]
+        );
+
+        // Restored users share the transport of the manager as well
+        let backup = manager.get_mut("humidity").unwrap().backup("password").await?;
+        manager.restore("humidity", backup, "password").await?;
+        assert_eq!(manager.len(), 2);
+        assert_eq!(manager.sync().await?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(feature = "std")]
+extern crate std;
+
+/// Returns the current Unix timestamp, in seconds. Without the `std` feature there is no clock to
+/// read, and None is returned.
+#[cfg(feature = "std")]
+pub(crate) fn unix_time() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_secs())
+}
+
+/// Returns the current Unix timestamp, in seconds. Without the `std` feature there is no clock to
+/// read, and None is returned.
+#[cfg(not(feature = "std"))]
+pub(crate) fn unix_time() -> Option<u64> {
+    None
+}
+
+/// Measures the time elapsed since it was started, for the [`Metrics`](crate::Metrics) of a user.
+/// Without the `std` feature, or on `wasm32` where there is no monotonic clock, nothing is
+/// measured.
+pub(crate) struct Stopwatch {
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    /// Starts a new [`Stopwatch`]
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Returns the time elapsed since the [`Stopwatch`] was started, None if it cannot be measured
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub(crate) fn elapsed(&self) -> Option<core::time::Duration> {
+        Some(self.start.elapsed())
+    }
+
+    /// Returns the time elapsed since the [`Stopwatch`] was started, None if it cannot be measured
+    #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+    pub(crate) fn elapsed(&self) -> Option<core::time::Duration> {
+        None
+    }
+}
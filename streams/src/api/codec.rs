@@ -0,0 +1,643 @@
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::{Address, AppAddr, MsgId},
+    id::{Ed25519, Identifier, Identity, PermissionDuration, Permissioned, Psk},
+    message::{ContentWrap, Message as LetsMessage, PreparsedMessage, Topic, TransportMessage, HDF, PCF},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{unwrap, wrap},
+        io,
+        layout::{LayoutDescriptor, LayoutStream},
+    },
+    Spongos,
+};
+
+// Local
+use crate::{
+    api::{
+        branch_metadata::BranchMetadata,
+        commitment::COMMITMENT_DIGEST_SIZE,
+        message::{BatchRecord, Message},
+        packet_reader::SignedPacketReader,
+        quorum::Quorum,
+        reference::Reference,
+        repeated_payloads::RepeatedPayloads,
+        user::{ANN_MESSAGE_NUM, INIT_MESSAGE_NUM, SUB_MESSAGE_NUM},
+    },
+    message::{
+        announcement, author_rotation, batch_packet, branch_announcement, branch_closure, commitment, countersignature,
+        key_update, keyload, message_types, read_marker, reveal, selective_packet, signed_packet, subscription,
+        substream_announcement, tagged_packet, tombstone, unsubscription,
+    },
+    Error, Result,
+};
+
+/// Low-level encoding and decoding of Streams messages, without a [`User`](crate::User) or a
+/// [`Transport`](lets::transport::Transport)
+///
+/// [`MessageCodec`] produces and consumes the same binary messages as a [`User`](crate::User),
+/// so that they can be moved over any channel of the integrator's choice. It keeps no state: every
+/// message is derived from the [`Spongos`] state of the message it is linked to, which is returned
+/// by the codec alongside each encoded or decoded message and must be kept by the caller for as
+/// long as new messages can be linked to it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MessageCodec;
+
+impl MessageCodec {
+    /// Reads the [`HDF`] of a binary message without decoding its content. The header gives the
+    /// message type and the address of the linked message, needed to choose the decoding method
+    /// and its [`Spongos`] state.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `msg`: The binary message
+    pub async fn header(address: Address, msg: TransportMessage) -> Result<HDF> {
+        let preparsed = Self::preparse(address, msg).await?;
+        Ok(preparsed.header().clone())
+    }
+
+    /// Encodes the announcement of a new stream. Returns the [`Address`] of the stream, the binary
+    /// message and its [`Spongos`] state.
+    ///
+    /// # Arguments
+    /// * `user_id`: The [`Identity`] of the author of the stream
+    /// * `topic`: The [`Topic`] of the base branch of the stream
+    pub async fn wrap_announcement(user_id: &Identity, topic: &Topic) -> Result<(Address, TransportMessage, Spongos)> {
+        let identifier = user_id.identifier();
+        let base_address = AppAddr::gen(identifier, topic);
+        let address = Address::new(
+            base_address,
+            MsgId::gen(base_address, identifier, topic, INIT_MESSAGE_NUM),
+        );
+
+        let header = HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, identifier.clone(), topic);
+        let content = PCF::new_final_frame().with_content(announcement::Wrap::new(user_id, topic));
+        let (msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap announce", e))?;
+        Ok((address, msg, spongos))
+    }
+
+    /// Decodes the announcement of a stream. Returns the decoded [`Message`] and its [`Spongos`]
+    /// state.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the announcement
+    /// * `msg`: The binary message
+    pub async fn unwrap_announcement(address: Address, msg: TransportMessage) -> Result<(Message, Spongos)> {
+        let preparsed = Self::preparse_as(address, msg, message_types::ANNOUNCEMENT).await?;
+        let (message, spongos) = preparsed
+            .unwrap(announcement::Unwrap::default())
+            .await
+            .map_err(|e| Error::Unwrapping("announcement", address, e))?;
+        Ok((Message::from_lets_message(address, message), spongos))
+    }
+
+    /// Encodes a signed packet. Returns the [`Address`] of the packet, the binary message and its
+    /// [`Spongos`] state.
+    ///
+    /// # Arguments
+    /// * `linked_msg`: The [`Address`] of the message the packet is linked to
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the linked message
+    /// * `user_id`: The [`Identity`] signing the packet
+    /// * `topic`: The [`Topic`] of the branch of the packet
+    /// * `sequence`: The sequence number of the packet among the messages of the publisher
+    /// * `public_payload`: The unmasked payload of the packet
+    /// * `masked_payload`: The masked payload of the packet
+    pub async fn wrap_signed_packet(
+        linked_msg: Address,
+        mut linked_msg_spongos: Spongos,
+        user_id: &Identity,
+        topic: &Topic,
+        sequence: usize,
+        public_payload: &[u8],
+        masked_payload: &[u8],
+    ) -> Result<(Address, TransportMessage, Spongos)> {
+        let identifier = user_id.identifier();
+        let address = Self::address(linked_msg.base(), identifier, topic, sequence);
+
+        let header = HDF::new(message_types::SIGNED_PACKET, sequence, identifier.clone(), topic)
+            .with_linked_msg_address(linked_msg.relative());
+        let content = PCF::new_final_frame().with_content(signed_packet::Wrap::new(
+            &mut linked_msg_spongos,
+            user_id,
+            public_payload,
+            masked_payload,
+        ));
+        let (msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap signed packet", e))?;
+        Ok((address, msg, spongos))
+    }
+
+    /// Decodes a signed packet, verifying its signature. Returns the decoded [`Message`] and its
+    /// [`Spongos`] state.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the packet
+    /// * `msg`: The binary message
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the message the packet is linked to
+    pub async fn unwrap_signed_packet(
+        address: Address,
+        msg: TransportMessage,
+        mut linked_msg_spongos: Spongos,
+    ) -> Result<(Message, Spongos)> {
+        let preparsed = Self::preparse_as(address, msg, message_types::SIGNED_PACKET).await?;
+        let (message, spongos) = preparsed
+            .unwrap(signed_packet::Unwrap::new(&mut linked_msg_spongos))
+            .await
+            .map_err(|e| Error::Unwrapping("signed packet", address, e))?;
+        Ok((Message::from_lets_message(address, message), spongos))
+    }
+
+    /// Opens a signed packet for incremental decoding: the binary message is pulled from its source
+    /// as the masked payload is read, instead of being held in memory whole. The signature is only
+    /// verified once the masked payload has been read, by [`SignedPacketReader::finish()`].
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the packet
+    /// * `source`: The [`io::Source`] of the binary message
+    /// * `len`: The length of the binary message
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the message the packet is linked to
+    pub async fn open_signed_packet<S>(
+        address: Address,
+        source: S,
+        len: usize,
+        linked_msg_spongos: Spongos,
+    ) -> Result<SignedPacketReader<S>>
+    where
+        S: io::Source + MaybeSend,
+    {
+        SignedPacketReader::open(address, source, len, linked_msg_spongos, unwrap::DEFAULT_SIZE_LIMIT).await
+    }
+
+    /// Encodes a tagged packet. Returns the [`Address`] of the packet, the binary message and its
+    /// [`Spongos`] state.
+    ///
+    /// # Arguments
+    /// * `linked_msg`: The [`Address`] of the message the packet is linked to
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the linked message
+    /// * `publisher`: The [`Identifier`] of the publisher of the packet
+    /// * `topic`: The [`Topic`] of the branch of the packet
+    /// * `sequence`: The sequence number of the packet among the messages of the publisher
+    /// * `public_payload`: The unmasked payload of the packet
+    /// * `masked_payload`: The masked payload of the packet
+    pub async fn wrap_tagged_packet(
+        linked_msg: Address,
+        mut linked_msg_spongos: Spongos,
+        publisher: &Identifier,
+        topic: &Topic,
+        sequence: usize,
+        public_payload: &[u8],
+        masked_payload: &[u8],
+    ) -> Result<(Address, TransportMessage, Spongos)> {
+        let address = Self::address(linked_msg.base(), publisher, topic, sequence);
+
+        let header = HDF::new(message_types::TAGGED_PACKET, sequence, publisher.clone(), topic)
+            .with_linked_msg_address(linked_msg.relative());
+        let content = PCF::new_final_frame().with_content(tagged_packet::Wrap::new(
+            &mut linked_msg_spongos,
+            public_payload,
+            masked_payload,
+        ));
+        let (msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap tagged packet", e))?;
+        Ok((address, msg, spongos))
+    }
+
+    /// Decodes a tagged packet. Returns the decoded [`Message`] and its [`Spongos`] state.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the packet
+    /// * `msg`: The binary message
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the message the packet is linked to
+    pub async fn unwrap_tagged_packet(
+        address: Address,
+        msg: TransportMessage,
+        mut linked_msg_spongos: Spongos,
+    ) -> Result<(Message, Spongos)> {
+        let preparsed = Self::preparse_as(address, msg, message_types::TAGGED_PACKET).await?;
+        let (message, spongos) = preparsed
+            .unwrap(tagged_packet::Unwrap::new(&mut linked_msg_spongos))
+            .await
+            .map_err(|e| Error::Unwrapping("tagged packet", address, e))?;
+        Ok((Message::from_lets_message(address, message), spongos))
+    }
+
+    /// Describes the byte layout of every message type, in the order of their type identifiers,
+    /// followed by the layouts of the messages carrying the optional fields of their type, such as
+    /// the detached signature of a signed packet or the expiry of a keyload. The layouts are recorded from sample messages, wrapped by the same code as the messages of
+    /// a [`User`](crate::User), so they cannot drift from the actual encoding. Fixed size fields
+    /// are laid out the same in every message of a type, while variable sized fields, like
+    /// payloads, and repeated fields, like the recipients of a keyload, are laid out for the
+    /// sample values.
+    pub async fn layouts() -> Result<Vec<LayoutDescriptor>> {
+        let author: Identity = Ed25519::from_seed("layout author").into();
+        let subscriber: Identity = Ed25519::from_seed("layout subscriber").into();
+        let author_id = author.identifier();
+        let subscriber_id = subscriber.identifier();
+        let topic: Topic = "BASE_BRANCH".into();
+        let new_topic: Topic = "BRANCH".into();
+        let link = MsgId::gen(AppAddr::gen(author_id, &topic), author_id, &topic, INIT_MESSAGE_NUM);
+        let header = |message_type, sequence, publisher: &Identifier| {
+            HDF::new(message_type, sequence, publisher.clone(), &topic).with_linked_msg_address(link)
+        };
+        let author_ke_pk = author_id
+            .ke_pk()
+            .await
+            .map_err(|e| Error::Wrapped("derive key exchange key", e))?;
+        let psk = Psk::from_seed("layout psk");
+        let psks = vec![(psk.to_pskid(), &psk)];
+        let fields = vec![
+            (vec![subscriber_id.clone()], b"field".to_vec()),
+            (vec![author_id.clone(), subscriber_id.clone()], b"shared field".to_vec()),
+        ];
+        let keys = [[0; selective_packet::KEY_SIZE]; 2];
+        let invite = [0; 64];
+        let detached_signature = [0; 64];
+        let substream = Address::new(AppAddr::gen(author_id, &new_topic), link);
+        let references = [Reference::new(substream, [0; 32])];
+        let records = [
+            BatchRecord::new(b"public payload".to_vec(), b"masked payload".to_vec()),
+            BatchRecord::new(b"public payload".to_vec(), b"masked payload".to_vec()),
+        ];
+        let metadata = BranchMetadata::new()
+            .with_name("branch")
+            .with_description("description");
+        let quorum = Quorum::new(vec![author_id.clone(), subscriber_id.clone()], 2)?;
+        let approvals = [
+            quorum.announcement_proposal(author_id, &topic).approve(&author)?,
+            quorum.announcement_proposal(author_id, &topic).approve(&subscriber)?,
+        ];
+        // The spongos states of the linked messages do not change the layouts
+        let mut spongos = Spongos::init();
+
+        let layouts = vec![
+            Self::layout(
+                "Announcement",
+                HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, author_id.clone(), &topic),
+                announcement::Wrap::new(&author, &topic),
+            )
+            .await?,
+            Self::layout(
+                "BranchAnnouncement",
+                header(message_types::BRANCH_ANNOUNCEMENT, INIT_MESSAGE_NUM, author_id),
+                branch_announcement::Wrap::new(&mut spongos, &author, &new_topic),
+            )
+            .await?,
+            Self::layout(
+                "Keyload",
+                header(message_types::KEYLOAD, INIT_MESSAGE_NUM, author_id),
+                keyload::Wrap::new(
+                    &mut spongos,
+                    vec![Permissioned::ReadWrite(subscriber_id, PermissionDuration::Perpetual)],
+                    &psks,
+                    [0; 32],
+                    [0; 16],
+                    &author,
+                    false,
+                ),
+            )
+            .await?,
+            Self::layout(
+                "SignedPacket",
+                header(message_types::SIGNED_PACKET, INIT_MESSAGE_NUM, author_id),
+                signed_packet::Wrap::new(&mut spongos, &author, b"public payload", b"masked payload"),
+            )
+            .await?,
+            Self::layout(
+                "TaggedPacket",
+                header(message_types::TAGGED_PACKET, INIT_MESSAGE_NUM, author_id),
+                tagged_packet::Wrap::new(&mut spongos, b"public payload", b"masked payload"),
+            )
+            .await?,
+            Self::layout(
+                "Subscription",
+                header(message_types::SUBSCRIPTION, SUB_MESSAGE_NUM, subscriber_id),
+                subscription::Wrap::new(&mut spongos, [0; 32], &subscriber, &author_ke_pk),
+            )
+            .await?,
+            Self::layout(
+                "Unsubscription",
+                header(message_types::UNSUBSCRIPTION, INIT_MESSAGE_NUM, subscriber_id),
+                unsubscription::Wrap::new(&mut spongos, &subscriber),
+            )
+            .await?,
+            Self::layout(
+                "SelectivePacket",
+                header(message_types::SELECTIVE_PACKET, INIT_MESSAGE_NUM, author_id),
+                selective_packet::Wrap::new(&mut spongos, &author, &fields, &keys),
+            )
+            .await?,
+            Self::layout(
+                "KeyUpdate",
+                header(message_types::KEY_UPDATE, INIT_MESSAGE_NUM, author_id),
+                key_update::Wrap::new(
+                    &mut spongos,
+                    &author,
+                    key_update::ExchangeKey::new(1, author_ke_pk.to_bytes()),
+                ),
+            )
+            .await?,
+            Self::layout(
+                "BranchClosure",
+                header(message_types::BRANCH_CLOSURE, INIT_MESSAGE_NUM, author_id),
+                branch_closure::Wrap::new(&mut spongos, &author),
+            )
+            .await?,
+            Self::layout(
+                "InvitedSubscription",
+                header(message_types::INVITED_SUBSCRIPTION, SUB_MESSAGE_NUM, subscriber_id),
+                subscription::Wrap::new(&mut spongos, [0; 32], &subscriber, &author_ke_pk).with_invite(&invite),
+            )
+            .await?,
+            Self::layout(
+                "SubstreamAnnouncement",
+                header(message_types::SUBSTREAM_ANNOUNCEMENT, INIT_MESSAGE_NUM, author_id),
+                substream_announcement::Wrap::new(&mut spongos, &author, &substream, &new_topic, &[0; 32]),
+            )
+            .await?,
+            Self::layout(
+                "BatchPacket",
+                header(message_types::BATCH_PACKET, INIT_MESSAGE_NUM, author_id),
+                batch_packet::Wrap::new(&mut spongos, &author, &records),
+            )
+            .await?,
+            Self::layout(
+                "ReadMarker",
+                header(message_types::READ_MARKER, INIT_MESSAGE_NUM, author_id),
+                read_marker::Wrap::new(&mut spongos, &author, &substream),
+            )
+            .await?,
+            Self::layout(
+                "Tombstone",
+                header(message_types::TOMBSTONE, INIT_MESSAGE_NUM, author_id),
+                tombstone::Wrap::new(&mut spongos, &author, &link, "redaction reason"),
+            )
+            .await?,
+            Self::layout(
+                "AuthorRotation",
+                header(message_types::AUTHOR_ROTATION, INIT_MESSAGE_NUM, author_id),
+                author_rotation::Wrap::new(&mut spongos, &author, subscriber_id),
+            )
+            .await?,
+            Self::layout(
+                "Countersignature",
+                header(message_types::COUNTERSIGNATURE, INIT_MESSAGE_NUM, author_id),
+                countersignature::Wrap::new(&mut spongos, &author),
+            )
+            .await?,
+            Self::layout(
+                "Commitment",
+                header(message_types::COMMITMENT, INIT_MESSAGE_NUM, author_id),
+                commitment::Wrap::new(&mut spongos, &author, &[0; COMMITMENT_DIGEST_SIZE]),
+            )
+            .await?,
+            Self::layout(
+                "Reveal",
+                header(message_types::REVEAL, INIT_MESSAGE_NUM, author_id),
+                reveal::Wrap::new(&mut spongos, &author, &link, b"revealed payload"),
+            )
+            .await?,
+            // Messages carrying optional fields
+            Self::layout(
+                "DetachedSignedPacket",
+                header(message_types::SIGNED_PACKET, INIT_MESSAGE_NUM, author_id),
+                signed_packet::Wrap::new(&mut spongos, &author, b"public payload", b"masked payload")
+                    .with_detached_signature(&detached_signature),
+            )
+            .await?,
+            Self::layout(
+                "CitingSignedPacket",
+                header(message_types::SIGNED_PACKET, INIT_MESSAGE_NUM, author_id),
+                signed_packet::Wrap::new(&mut spongos, &author, b"public payload", b"masked payload")
+                    .with_references(&references),
+            )
+            .await?,
+            Self::layout(
+                "DescribedKeyload",
+                header(message_types::KEYLOAD, INIT_MESSAGE_NUM, author_id),
+                keyload::Wrap::new(
+                    &mut spongos,
+                    vec![Permissioned::ReadWrite(subscriber_id, PermissionDuration::Perpetual)],
+                    &psks,
+                    [0; 32],
+                    [0; 16],
+                    &author,
+                    false,
+                )
+                .with_metadata(&metadata),
+            )
+            .await?,
+            Self::layout(
+                "RepeatedSignedPacket",
+                header(message_types::SIGNED_PACKET, INIT_MESSAGE_NUM, author_id),
+                signed_packet::Wrap::new(&mut spongos, &author, b"public payload", b"masked payload")
+                    .with_repeated_payloads(RepeatedPayloads::default()),
+            )
+            .await?,
+            Self::layout(
+                "ExpiringKeyload",
+                header(message_types::KEYLOAD, INIT_MESSAGE_NUM, author_id),
+                keyload::Wrap::new(
+                    &mut spongos,
+                    vec![Permissioned::ReadWrite(subscriber_id, PermissionDuration::Perpetual)],
+                    &psks,
+                    [0; 32],
+                    [0; 16],
+                    &author,
+                    false,
+                )
+                .with_expiry(0),
+            )
+            .await?,
+            Self::layout(
+                "FlaggedAnnouncement",
+                HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, author_id.clone(), &topic),
+                announcement::Wrap::new(&author, &topic).with_flags(announcement::STRICT),
+            )
+            .await?,
+            Self::layout(
+                "QuorumAnnouncement",
+                HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, author_id.clone(), &topic),
+                announcement::Wrap::new(&author, &topic).with_quorum(&quorum, &approvals),
+            )
+            .await?,
+            Self::layout(
+                "ApprovedAuthorRotation",
+                header(message_types::AUTHOR_ROTATION, INIT_MESSAGE_NUM, author_id),
+                author_rotation::Wrap::new(&mut spongos, &author, subscriber_id).with_approvals(&approvals),
+            )
+            .await?,
+        ];
+        Ok(layouts)
+    }
+
+    /// Records the layout of a sample message
+    ///
+    /// # Arguments
+    /// * `name`: The name of the message type
+    /// * `header`: The header of the message
+    /// * `content`: The content of the message
+    async fn layout<C>(name: &str, header: HDF, content: C) -> Result<LayoutDescriptor>
+    where
+        for<'b> wrap::Context<&'b mut LayoutStream>: ContentWrap<HDF> + ContentWrap<PCF<C>>,
+    {
+        LetsMessage::new(header, PCF::new_final_frame().with_content(content))
+            .layout(name)
+            .await
+            .map_err(|e| Error::Wrapped("describe message layout", e))
+    }
+
+    /// Derives the [`Address`] of a message from its publisher, branch and sequence number
+    fn address(base_address: AppAddr, publisher: &Identifier, topic: &Topic, sequence: usize) -> Address {
+        Address::new(base_address, MsgId::gen(base_address, publisher, topic, sequence))
+    }
+
+    /// Decodes the header of a binary message
+    async fn preparse(address: Address, msg: TransportMessage) -> Result<PreparsedMessage> {
+        msg.parse_header()
+            .await
+            .map_err(|e| Error::Unwrapping("header", address, e))
+    }
+
+    /// Decodes the header of a binary message, checking that it is of the expected type
+    async fn preparse_as(address: Address, msg: TransportMessage, message_type: u8) -> Result<PreparsedMessage> {
+        let preparsed = Self::preparse(address, msg).await?;
+        match preparsed.header().message_type() {
+            found if found == message_type => Ok(preparsed),
+            found => Err(Error::MessageTypeUnknown(found)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use lets::{
+        id::{Ed25519, Identity},
+        message::Topic,
+    };
+    use spongos::ddml::layout::Command;
+
+    use crate::Result;
+
+    use super::MessageCodec;
+
+    #[tokio::test]
+    async fn signed_packet_roundtrip_without_transport() -> Result<()> {
+        let author: Identity = Ed25519::from_seed("author").into();
+        let topic: Topic = "BASE_BRANCH".into();
+
+        let (ann_address, ann_msg, author_ann_spongos) = MessageCodec::wrap_announcement(&author, &topic).await?;
+        let (announcement, reader_ann_spongos) = MessageCodec::unwrap_announcement(ann_address, ann_msg).await?;
+        assert!(announcement.is_announcement());
+
+        let (packet_address, packet_msg, _) = MessageCodec::wrap_signed_packet(
+            ann_address,
+            author_ann_spongos,
+            &author,
+            &topic,
+            2,
+            b"public",
+            b"masked",
+        )
+        .await?;
+        let header = MessageCodec::header(packet_address, packet_msg.clone()).await?;
+        assert_eq!(header.linked_msg_address(), Some(ann_address.relative()));
+
+        let (packet, _) = MessageCodec::unwrap_signed_packet(packet_address, packet_msg, reader_ann_spongos).await?;
+        let signed_packet = packet.as_signed_packet().unwrap();
+        assert_eq!(signed_packet.public_payload, b"public");
+        assert_eq!(signed_packet.masked_payload, b"masked");
+        assert_eq!(&signed_packet.publisher_identifier, author.identifier());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signed_packet_masked_payload_read_in_chunks() -> Result<()> {
+        let author: Identity = Ed25519::from_seed("author").into();
+        let topic: Topic = "BASE_BRANCH".into();
+        let masked_payload = (0..10_000).map(|i| i as u8).collect::<Vec<u8>>();
+
+        let (ann_address, ann_msg, author_ann_spongos) = MessageCodec::wrap_announcement(&author, &topic).await?;
+        let (_, reader_ann_spongos) = MessageCodec::unwrap_announcement(ann_address, ann_msg).await?;
+        let (packet_address, packet_msg, _) = MessageCodec::wrap_signed_packet(
+            ann_address,
+            author_ann_spongos,
+            &author,
+            &topic,
+            2,
+            b"public",
+            &masked_payload,
+        )
+        .await?;
+        let (_, expected_spongos) =
+            MessageCodec::unwrap_signed_packet(packet_address, packet_msg.clone(), reader_ann_spongos).await?;
+
+        let bytes = packet_msg.as_ref();
+        let mut reader =
+            MessageCodec::open_signed_packet(packet_address, bytes, bytes.len(), reader_ann_spongos).await?;
+        assert_eq!(reader.public_payload(), b"public");
+        assert_eq!(reader.publisher(), author.identifier());
+        assert_eq!(reader.masked_len(), masked_payload.len());
+        let mut read = Vec::new();
+        let mut chunk = [0u8; 333];
+        loop {
+            match reader.read(&mut chunk)? {
+                0 => break,
+                n => read.extend_from_slice(&chunk[..n]),
+            }
+        }
+        assert_eq!(read, masked_payload);
+        assert!(reader.finish().await? == expected_spongos);
+
+        // Altering the masked payload is detected once it has been read
+        let mut tampered = packet_msg.as_ref().to_vec();
+        let position = tampered.len() - 1000;
+        tampered[position] ^= 1;
+        let reader =
+            MessageCodec::open_signed_packet(packet_address, &tampered[..], tampered.len(), reader_ann_spongos).await?;
+        assert!(reader.finish().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn layouts_match_the_wrapped_messages() -> Result<()> {
+        let layouts = MessageCodec::layouts().await?;
+        assert_eq!(layouts.len(), 27);
+
+        let author: Identity = Ed25519::from_seed("layout author").into();
+        let topic: Topic = "BASE_BRANCH".into();
+        let (_, ann_msg, _) = MessageCodec::wrap_announcement(&author, &topic).await?;
+        let announcement = &layouts[0];
+        assert_eq!(announcement.name(), "Announcement");
+        assert_eq!(announcement.size(), ann_msg.as_ref().len());
+        let sections: Vec<&str> = announcement.sections().map(|(name, _)| name).collect();
+        assert_eq!(sections, ["header", "payload"]);
+        // The announcement ends with the signature of the author
+        let (_, payload) = announcement.sections().nth(1).unwrap();
+        let signature = payload.iter().rev().find(|field| field.size > 0).unwrap();
+        assert_eq!((signature.command, signature.size), (Command::Sign, 64));
+
+        let signed_packet = layouts.iter().find(|layout| layout.name() == "SignedPacket").unwrap();
+        assert!(signed_packet
+            .render()
+            .starts_with("message SignedPacket {\n    // header\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+//! Delayed disclosure of payloads
+//!
+//! Some workflows, like sealed-bid auctions or predictions, need a user to be bound to a payload
+//! before the other participants may learn it. The user first publishes the digest of the payload
+//! with [`User::send_commitment()`](crate::User::send_commitment), and discloses the payload later
+//! with [`User::send_reveal()`](crate::User::send_reveal). Readers keep the digests of the
+//! commitments they process, and only accept the reveals whose payload matches the digest of the
+//! commitment of their publisher.
+//!
+//! The digest is deterministic: payloads taken from a small set of values, like bids, should
+//! carry a random nonce, so that the commitment cannot be opened by trying every value.
+
+// Rust
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use spongos::{KeccakF1600, Spongos};
+
+// Local
+
+/// Size of the digest of a committed payload
+pub const COMMITMENT_DIGEST_SIZE: usize = 32;
+
+/// Domain separation tag of the digests of committed payloads
+const COMMITMENT_DOMAIN: &[u8] = b"streams commitment";
+
+/// Computes the digest of a payload, to be committed to with
+/// [`User::send_commitment()`](crate::User::send_commitment)
+///
+/// # Arguments
+/// * `payload`: The payload to be revealed later
+pub fn commitment_digest(payload: &[u8]) -> [u8; COMMITMENT_DIGEST_SIZE] {
+    let mut spongos = Spongos::<KeccakF1600>::init();
+    spongos.absorb(COMMITMENT_DOMAIN);
+    spongos.absorb((payload.len() as u64).to_le_bytes());
+    spongos.absorb(payload);
+    spongos.commit();
+    spongos.squeeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::message::Topic;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Error, Result, User,
+    };
+
+    use super::commitment_digest;
+
+    #[tokio::test]
+    async fn reveals_are_verified_against_the_commitment_of_their_publisher() -> Result<()> {
+        let transport = new_transport();
+        let mut auctioneer = new_user("auctioneer", &transport);
+        let mut bidder = new_user("bidder", &transport);
+
+        let announcement = auctioneer.create_stream("BASE_BRANCH").await?;
+        bidder.receive_message(announcement.address()).await?;
+        bidder.subscribe().await?;
+        auctioneer.sync().await?;
+        auctioneer.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        bidder.sync().await?;
+        let bidder_id = bidder.identifier().unwrap().clone();
+        let topic = Topic::from("BASE_BRANCH");
+        let bid = b"bid=42;nonce=8f1c2a";
+
+        // Nothing can be revealed before committing to it
+        assert!(matches!(
+            bidder.send_reveal("BASE_BRANCH", bid).await,
+            Err(Error::Setup(_))
+        ));
+
+        // The commitment only discloses the digest of the bid
+        let commitment = bidder.send_commitment("BASE_BRANCH", commitment_digest(bid)).await?;
+        let msgid = commitment.address().relative();
+        assert_eq!(bidder.pending_commitment(&topic), Some(msgid));
+        let messages = auctioneer.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        let received = messages[0].as_commitment().unwrap();
+        assert_eq!(received.publisher_identifier, bidder_id);
+        assert_eq!(received.digest, commitment_digest(bid));
+
+        // Another payload cannot be revealed
+        assert!(matches!(
+            bidder.send_reveal("BASE_BRANCH", b"bid=41;nonce=8f1c2a").await,
+            Err(Error::RevealMismatch(..))
+        ));
+
+        // The reveal is matched with the commitment
+        bidder.send_reveal("BASE_BRANCH", bid).await?;
+        assert_eq!(bidder.pending_commitment(&topic), None);
+        let messages = auctioneer.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        let reveal = messages[0].as_reveal().unwrap();
+        assert_eq!(reveal.publisher_identifier, bidder_id);
+        assert_eq!(reveal.commitment, msgid);
+        assert_eq!(reveal.payload, bid);
+
+        // Commitments are kept in backups
+        let backup = auctioneer.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(restored.commitment(&msgid), Some(received));
+        assert_eq!(auctioneer, restored);
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! Portable cursors, for continuing to read a stream on another device
+//!
+//! A [`CursorExport`] lists the cursors of the publishers of every branch a reader has read up
+//! to, without any of the keys or [`Spongos`](crate::Spongos) states of a backup. The reader
+//! obtains it with [`User::export_cursors()`](crate::User::export_cursors) and hands its compact
+//! binary encoding to its other device. There, a user with the same identity receives the
+//! announcement of the stream and imports the cursors with
+//! [`User::import_cursors()`](crate::User::import_cursors). The [`Spongos`](crate::Spongos)
+//! states of the messages already read are rebuilt by fetching them again: the [`Messages`]
+//! streams of the user process them without yielding them, and only yield the messages published
+//! after the imported cursors.
+//!
+//! ```ddml
+//! message CursorExport {
+//!     absorb           u8     version;
+//!     absorb           u8     appaddr[40];
+//!     absorb           u8     msgid[12];
+//!     absorb           size_t n_cursors;
+//!     repeated(n_cursors):
+//!       absorb         bytes  topic;
+//!       mask           u8     publisher;
+//!       absorb         size_t cursor;
+//! }
+//! ```
+//!
+//! [`Messages`]: crate::Messages
+
+// Rust
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{address::Address, id::Identifier, message::Topic};
+use spongos::ddml::{
+    commands::{sizeof, unwrap, wrap, Absorb, Mask},
+    types::{Bytes, Size, Uint8},
+};
+
+// Local
+use crate::{Error, Result};
+
+/// Layout of the encoded cursor exports, written as their first byte
+const CURSOR_EXPORT_VERSION: u8 = 0;
+
+/// Cursors of the publishers of a stream read by a user, exported with
+/// [`User::export_cursors()`](crate::User::export_cursors)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CursorExport {
+    /// The [`Address`] of the announcement of the stream
+    stream_address: Address,
+    /// The cursor of each publisher, with the [`Topic`] of its branch
+    cursors: Vec<(Topic, Identifier, usize)>,
+}
+
+impl CursorExport {
+    /// Creates a new [`CursorExport`] without cursors
+    ///
+    /// # Arguments
+    /// * `stream_address`: The [`Address`] of the announcement of the stream
+    pub fn new(stream_address: Address) -> Self {
+        Self {
+            stream_address,
+            cursors: Vec::new(),
+        }
+    }
+
+    /// Adds the cursor of a publisher in a branch, replacing the cursor previously added for the
+    /// same publisher in the same branch
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `cursor`: The sequence number of the last message of the publisher read in the branch
+    pub fn with_cursor(mut self, topic: Topic, publisher: Identifier, cursor: usize) -> Self {
+        match self.cursors.iter_mut().find(|(t, p, _)| t == &topic && p == &publisher) {
+            Some(entry) => entry.2 = cursor,
+            None => self.cursors.push((topic, publisher, cursor)),
+        }
+        self
+    }
+
+    /// Returns the [`Address`] of the announcement of the stream
+    pub fn stream_address(&self) -> Address {
+        self.stream_address
+    }
+
+    /// Returns an iterator over the cursors, each with the [`Topic`] of its branch and the
+    /// [`Identifier`] of its publisher
+    pub fn cursors(&self) -> impl Iterator<Item = (&Topic, &Identifier, usize)> + ExactSizeIterator {
+        self.cursors
+            .iter()
+            .map(|(topic, publisher, cursor)| (topic, publisher, *cursor))
+    }
+
+    /// Encodes the cursors in their compact binary form
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut ctx = sizeof::Context::new();
+        ctx.absorb(Uint8::new(CURSOR_EXPORT_VERSION))?
+            .absorb(&self.stream_address)?
+            .absorb(Size::new(self.cursors.len()))?;
+        for (topic, publisher, cursor) in &self.cursors {
+            ctx.absorb(Bytes::new(topic))?
+                .mask(publisher)?
+                .absorb(Size::new(*cursor))?;
+        }
+        let mut buf = vec![0; ctx.finalize()];
+
+        let mut ctx = wrap::Context::new(&mut buf[..]);
+        ctx.absorb(Uint8::new(CURSOR_EXPORT_VERSION))?
+            .absorb(&self.stream_address)?
+            .absorb(Size::new(self.cursors.len()))?;
+        for (topic, publisher, cursor) in &self.cursors {
+            ctx.absorb(Bytes::new(topic))?
+                .mask(publisher)?
+                .absorb(Size::new(*cursor))?;
+        }
+        Ok(buf)
+    }
+
+    /// Decodes cursors from their compact binary form
+    ///
+    /// # Arguments
+    /// * `bytes`: The encoded cursors
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&CURSOR_EXPORT_VERSION) => {}
+            Some(_) => return Err(Error::CursorExportInvalid("unsupported cursor export version")),
+            None => return Err(Error::CursorExportInvalid("empty cursor export")),
+        }
+        let mut version = Uint8::default();
+        let mut stream_address = Address::default();
+        let mut cursors_count = Size::default();
+        let mut ctx = unwrap::Context::new(bytes);
+        ctx.absorb(&mut version)?
+            .absorb(&mut stream_address)?
+            .absorb(&mut cursors_count)?;
+        let mut export = Self::new(stream_address);
+        for _ in 0..cursors_count.inner() {
+            let mut topic = Vec::new();
+            let mut publisher = Identifier::default();
+            let mut cursor = Size::default();
+            ctx.absorb(Bytes::new(&mut topic))?
+                .mask(&mut publisher)?
+                .absorb(&mut cursor)?;
+            let topic = Topic::try_from(topic).map_err(|e| Error::Wrapped("decode cursor export topic", e))?;
+            export = export.with_cursor(topic, publisher, cursor.inner());
+        }
+        if !ctx.stream().is_empty() {
+            return Err(Error::CursorExportInvalid("trailing bytes after the cursor export"));
+        }
+        Ok(export)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Error, Result, User,
+    };
+
+    use super::CursorExport;
+
+    #[tokio::test]
+    async fn imported_cursors_continue_reading_on_another_device() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut phone = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        phone.receive_message(announcement.address()).await?;
+        author.send_signed_packet("BASE_BRANCH", b"", b"first").await?;
+        author.send_signed_packet("BASE_BRANCH", b"", b"second").await?;
+        assert_eq!(phone.fetch_next_messages().await?.len(), 2);
+
+        let export = phone.export_cursors().unwrap();
+        let imported = CursorExport::from_bytes(&export.to_bytes()?)?;
+        assert_eq!(imported, export);
+        author.send_signed_packet("BASE_BRANCH", b"", b"third").await?;
+
+        // The laptop rebuilds the state of the messages read on the phone without yielding them
+        let mut laptop = new_user("reader", &transport);
+        laptop.receive_message(announcement.address()).await?;
+        laptop.import_cursors(&imported)?;
+        let backup = laptop.backup("password").await?;
+        assert_eq!(laptop, User::restore(backup, "password", transport.clone()).await?);
+        let messages = laptop.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].masked_payload(), Some(&b"third"[..]));
+        phone.sync().await?;
+        assert_eq!(laptop.export_cursors(), phone.export_cursors());
+
+        // Cursors of another stream are rejected
+        let mut other_author = new_user("other author", &transport);
+        let other_announcement = other_author.create_stream("BASE_BRANCH").await?;
+        let mut other_reader = new_user("reader", &transport);
+        other_reader.receive_message(other_announcement.address()).await?;
+        assert!(matches!(
+            other_reader.import_cursors(&imported),
+            Err(Error::CursorExportInvalid(..))
+        ));
+        Ok(())
+    }
+}
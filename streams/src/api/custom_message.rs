@@ -0,0 +1,231 @@
+//! Message types defined by the applications
+//!
+//! The message types of the protocol are fixed, but applications can define their own messages on
+//! top of them. A custom message type is a message type identifier, within
+//! [`CUSTOM_MESSAGE_TYPES`], and a content type whose encoding is defined by implementing
+//! [`ContentSizeof`](lets::message::ContentSizeof), [`ContentWrap`](lets::message::ContentWrap) and
+//! [`ContentUnwrap`](lets::message::ContentUnwrap) for it. A [`User`](crate::User) publishes custom
+//! messages with [`User::send_custom_message()`](crate::User::send_custom_message): they are linked
+//! to their branch, tracked by the cursors of their publisher, masked under the keyloads of the
+//! branch and signed like the other messages. Readers register the content type of a message type
+//! with [`User::register_message_type()`](crate::User::register_message_type), and read its
+//! messages as [`MessageContent::Custom`](crate::MessageContent::Custom).
+
+// Rust
+use alloc::boxed::Box;
+use core::{
+    any::Any,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::RangeInclusive,
+};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    error::Result as LetsResult,
+    message::{ContentUnwrap, PreparsedMessage},
+    sync::{MaybeSend, MaybeSync},
+};
+use spongos::{ddml::commands::unwrap, Spongos};
+
+// Local
+use crate::{api::message::Message, message::custom_message};
+
+/// Message type identifiers available to the custom message types. The identifiers below are
+/// reserved to the message types of the protocol.
+pub const CUSTOM_MESSAGE_TYPES: RangeInclusive<u8> = 32..=63;
+
+/// Content of a custom message. Implemented for every comparable and hashable type that can be
+/// shared between threads, so that the messages carrying it can be compared, hashed and cloned.
+pub trait CustomContent: Any + Debug + Send + Sync {
+    /// Returns the content as [`Any`], to be downcast to its type
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns true if the content is equal to another content of the same type
+    ///
+    /// # Arguments
+    /// * `other`: The content to compare to
+    fn eq_content(&self, other: &dyn CustomContent) -> bool;
+
+    /// Feeds the content into a [`Hasher`]
+    ///
+    /// # Arguments
+    /// * `state`: The [`Hasher`] to feed
+    fn hash_content(&self, state: &mut dyn Hasher);
+}
+
+impl<C> CustomContent for C
+where
+    C: Any + Debug + Eq + Hash + Send + Sync,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_content(&self, other: &dyn CustomContent) -> bool {
+        other.as_any().downcast_ref::<C>() == Some(self)
+    }
+
+    fn hash_content(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state)
+    }
+}
+
+impl PartialEq for dyn CustomContent {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_content(other)
+    }
+}
+
+impl Eq for dyn CustomContent {}
+
+impl Hash for dyn CustomContent {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash_content(state)
+    }
+}
+
+/// Unwrapping of the messages of a registered custom message type, erasing the type of their
+/// content
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+pub(crate) trait CustomMessageType: MaybeSend + MaybeSync {
+    /// Unwraps a custom message, returning it along with its [`Spongos`] state
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the message it is linked to
+    /// * `preparsed`: The message with its header decoded
+    async fn unwrap(
+        &self,
+        address: Address,
+        linked_msg_spongos: &mut Spongos,
+        preparsed: PreparsedMessage,
+    ) -> LetsResult<(Message, Spongos)>;
+}
+
+/// [`CustomMessageType`] of the messages whose content is a `C`
+pub(crate) struct Registered<C>(PhantomData<fn() -> C>);
+
+impl<C> Registered<C> {
+    /// Creates a new [`Registered`] message type
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<C> CustomMessageType for Registered<C>
+where
+    C: CustomContent + Default,
+    for<'b> unwrap::Context<&'b [u8]>: ContentUnwrap<C>,
+{
+    async fn unwrap(
+        &self,
+        address: Address,
+        linked_msg_spongos: &mut Spongos,
+        preparsed: PreparsedMessage,
+    ) -> LetsResult<(Message, Spongos)> {
+        let message_type = preparsed.header().message_type();
+        let custom = custom_message::Unwrap::<C>::new(linked_msg_spongos, message_type);
+        let (message, spongos) = preparsed.unwrap(custom).await?;
+        Ok((Message::from_lets_message(address, message), spongos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use async_trait::async_trait;
+    use lets::message::{ContentSizeof, ContentUnwrap, ContentWrap};
+    use spongos::{
+        ddml::{
+            commands::{sizeof, unwrap, wrap, Mask},
+            types::Uint64,
+        },
+        error::Result as SpongosResult,
+    };
+
+    use crate::{api::fixtures::author_subscriber_fixture, Error, Result};
+
+    /// Content of the custom message type of the tests, a temperature reading
+    #[derive(Debug, Default, PartialEq, Eq, Hash)]
+    struct Temperature(u64);
+
+    #[async_trait(?Send)]
+    impl ContentSizeof<Temperature> for sizeof::Context {
+        async fn sizeof(&mut self, temperature: &Temperature) -> SpongosResult<&mut Self> {
+            self.mask(Uint64::new(temperature.0))
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl<'a> ContentWrap<Temperature> for wrap::Context<&'a mut [u8]> {
+        async fn wrap(&mut self, temperature: &mut Temperature) -> SpongosResult<&mut Self> {
+            self.mask(Uint64::new(temperature.0))
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl<'a> ContentUnwrap<Temperature> for unwrap::Context<&'a [u8]> {
+        async fn unwrap(&mut self, temperature: &mut Temperature) -> SpongosResult<&mut Self> {
+            let mut reading = Uint64::default();
+            self.mask(&mut reading)?;
+            temperature.0 = reading.inner();
+            Ok(self)
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_message_types_are_read_once_registered() -> Result<()> {
+        const TEMPERATURE: u8 = 40;
+        let (mut author, mut subscriber, _, _) = author_subscriber_fixture().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber.sync().await?;
+
+        // Message types of the protocol cannot be used by the applications
+        assert!(matches!(
+            author.send_custom_message("BASE_BRANCH", 3, Temperature(21)).await,
+            Err(Error::Setup(_))
+        ));
+        assert!(matches!(
+            subscriber.register_message_type::<Temperature>(3),
+            Err(Error::Setup(_))
+        ));
+
+        // Messages of unregistered types cannot be read
+        let reading = author
+            .send_custom_message("BASE_BRANCH", TEMPERATURE, Temperature(21))
+            .await?;
+        assert!(matches!(
+            subscriber.receive_message(reading.address()).await,
+            Err(Error::MessageTypeUnknown(TEMPERATURE))
+        ));
+
+        // Once registered, its content is unwrapped and the publisher verified
+        subscriber.register_message_type::<Temperature>(TEMPERATURE)?;
+        let message = subscriber.receive_message(reading.address()).await?;
+        let custom = message.as_custom().unwrap();
+        assert_eq!(custom.message_type, TEMPERATURE);
+        assert_eq!(&custom.publisher_identifier, author.identifier().unwrap());
+        assert_eq!(custom.content::<Temperature>(), Some(&Temperature(21)));
+        assert_eq!(custom.content::<u64>(), None);
+
+        // Custom messages are linked to their branch like the other messages
+        let packet = author
+            .send_signed_packet("BASE_BRANCH", b"", b"after the reading")
+            .await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        assert_eq!(messages.last().map(|msg| msg.address), Some(packet.address()));
+        Ok(())
+    }
+}
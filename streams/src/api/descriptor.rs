@@ -0,0 +1,244 @@
+//! Compact descriptors of channels, for pairing devices
+//!
+//! A [`ChannelDescriptor`] gathers what a device needs to join a channel: the [`Address`] of its
+//! announcement, the [`Identifier`] of its author and, optionally, the topics of the branches it
+//! should follow. The author obtains the descriptor of its channel with
+//! [`User::descriptor()`](crate::User::descriptor) and shares its compact binary encoding, or its
+//! `base64url` rendition, in a QR code for example. The paired device builds its user with
+//! [`UserBuilder::from_descriptor()`](crate::UserBuilder::from_descriptor), which receives the
+//! announcement and checks that it was published by the author of the descriptor.
+//!
+//! ```ddml
+//! message ChannelDescriptor {
+//!     absorb           u8     version;
+//!     absorb           u8     appaddr[40];
+//!     absorb           u8     msgid[12];
+//!     mask             u8     author;
+//!     absorb           size_t n_topics;
+//!     repeated(n_topics):
+//!       absorb         bytes  topic;
+//! }
+//! ```
+
+// Rust
+use alloc::{string::String, vec::Vec};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{address::Address, id::Identifier, message::Topic};
+use spongos::ddml::{
+    commands::{sizeof, unwrap, wrap, Absorb, Mask},
+    types::{Bytes, Size, Uint8},
+};
+
+// Local
+use crate::{Error, Result};
+
+/// Layout of the encoded descriptors, written as their first byte
+const DESCRIPTOR_VERSION: u8 = 0;
+
+/// Descriptor of a channel, shared out of band with the devices joining it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChannelDescriptor {
+    /// The [`Address`] of the announcement of the channel
+    announcement: Address,
+    /// The [`Identifier`] of the author of the channel
+    author: Identifier,
+    /// The [topics](`Topic`) of the branches the joining devices should follow
+    topics: Vec<Topic>,
+}
+
+impl ChannelDescriptor {
+    /// Creates a new [`ChannelDescriptor`] without topic hints
+    ///
+    /// # Arguments
+    /// * `announcement`: The [`Address`] of the announcement of the channel
+    /// * `author`: The [`Identifier`] of the author of the channel
+    pub fn new(announcement: Address, author: Identifier) -> Self {
+        Self {
+            announcement,
+            author,
+            topics: Vec::new(),
+        }
+    }
+
+    /// Adds the topic of a branch the joining devices should follow
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn with_topic<Top>(mut self, topic: Top) -> Self
+    where
+        Top: Into<Topic>,
+    {
+        let topic = topic.into();
+        if !self.topics.contains(&topic) {
+            self.topics.push(topic);
+        }
+        self
+    }
+
+    /// Returns the [`Address`] of the announcement of the channel
+    pub fn announcement(&self) -> Address {
+        self.announcement
+    }
+
+    /// Returns the [`Identifier`] of the author of the channel
+    pub fn author(&self) -> &Identifier {
+        &self.author
+    }
+
+    /// Returns the [topics](`Topic`) of the branches the joining devices should follow
+    pub fn topics(&self) -> &[Topic] {
+        &self.topics
+    }
+
+    /// Encodes the descriptor in its compact binary form
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut ctx = sizeof::Context::new();
+        ctx.absorb(Uint8::new(DESCRIPTOR_VERSION))?
+            .absorb(&self.announcement)?
+            .mask(&self.author)?
+            .absorb(Size::new(self.topics.len()))?;
+        for topic in &self.topics {
+            ctx.absorb(Bytes::new(topic))?;
+        }
+        let mut buf = vec![0; ctx.finalize()];
+
+        let mut ctx = wrap::Context::new(&mut buf[..]);
+        ctx.absorb(Uint8::new(DESCRIPTOR_VERSION))?
+            .absorb(&self.announcement)?
+            .mask(&self.author)?
+            .absorb(Size::new(self.topics.len()))?;
+        for topic in &self.topics {
+            ctx.absorb(Bytes::new(topic))?;
+        }
+        Ok(buf)
+    }
+
+    /// Decodes a descriptor from its compact binary form
+    ///
+    /// # Arguments
+    /// * `bytes`: The encoded descriptor
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&DESCRIPTOR_VERSION) => {}
+            Some(_) => return Err(Error::DescriptorInvalid("unsupported descriptor version")),
+            None => return Err(Error::DescriptorInvalid("empty descriptor")),
+        }
+        let mut version = Uint8::default();
+        let mut announcement = Address::default();
+        let mut author = Identifier::default();
+        let mut topics_count = Size::default();
+        let mut ctx = unwrap::Context::new(bytes);
+        ctx.absorb(&mut version)?
+            .absorb(&mut announcement)?
+            .mask(&mut author)?
+            .absorb(&mut topics_count)?;
+        let mut descriptor = Self::new(announcement, author);
+        for _ in 0..topics_count.inner() {
+            let mut topic = Vec::new();
+            ctx.absorb(Bytes::new(&mut topic))?;
+            let topic = Topic::try_from(topic).map_err(|e| Error::Wrapped("decode descriptor topic", e))?;
+            descriptor = descriptor.with_topic(topic);
+        }
+        if !ctx.stream().is_empty() {
+            return Err(Error::DescriptorInvalid("trailing bytes after the descriptor"));
+        }
+        Ok(descriptor)
+    }
+
+    /// Encodes the descriptor in `base64url`, without padding, to be embedded in URLs and QR codes
+    pub fn to_base64url(&self) -> Result<String> {
+        Ok(base64::encode_config(self.to_bytes()?, base64::URL_SAFE_NO_PAD))
+    }
+
+    /// Decodes a descriptor from its `base64url` encoding, with or without padding
+    ///
+    /// # Arguments
+    /// * `encoded`: The `base64url` encoded descriptor
+    pub fn from_base64url(encoded: &str) -> Result<Self> {
+        let bytes = base64::decode_config(encoded.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+            .map_err(|_| Error::DescriptorInvalid("invalid base64url encoding"))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Renders the descriptor in `base64url` (see [`ChannelDescriptor::to_base64url()`])
+impl Display for ChannelDescriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let encoded = self.to_base64url().map_err(|_| fmt::Error)?;
+        f.write_str(&encoded)
+    }
+}
+
+/// Parses a descriptor from its `base64url` encoding (see [`ChannelDescriptor::from_base64url()`])
+impl FromStr for ChannelDescriptor {
+    type Err = Error;
+    fn from_str(encoded: &str) -> Result<Self> {
+        Self::from_base64url(encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::{
+        id::{Ed25519, Identity},
+        message::Topic,
+    };
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder, Transport},
+        Error, Result, User,
+    };
+
+    use super::ChannelDescriptor;
+
+    #[tokio::test]
+    async fn devices_pair_from_channel_descriptors() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        assert!(author.descriptor().is_none());
+        let announcement = author.create_stream("BASE").await?;
+
+        let descriptor = author.descriptor().unwrap().with_topic("BASE/SENSORS");
+        assert_eq!(descriptor.announcement(), announcement.address());
+        assert_eq!(descriptor.topics(), [Topic::from("BASE"), Topic::from("BASE/SENSORS")]);
+        let encoded = descriptor.to_base64url()?;
+        assert!(encoded
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+        let scanned: ChannelDescriptor = encoded.parse()?;
+        assert_eq!(scanned, descriptor);
+        assert_eq!(ChannelDescriptor::from_bytes(&descriptor.to_bytes()?)?, descriptor);
+
+        let device: User<Transport> = new_user_builder("device", &transport).from_descriptor(&scanned).await?;
+        assert_eq!(device.stream_address(), Some(announcement.address()));
+
+        // Descriptors pointing to the channel of another author, or altered, are rejected
+        let attacker = Identity::from(Ed25519::from_seed("attacker"));
+        let forged = ChannelDescriptor::new(announcement.address(), attacker.identifier().clone());
+        assert!(matches!(
+            User::builder()
+                .with_transport(transport.clone())
+                .from_descriptor::<Transport>(&forged)
+                .await,
+            Err(Error::DescriptorMismatch(..))
+        ));
+        let mut truncated = descriptor.to_bytes()?;
+        truncated.pop();
+        assert!(ChannelDescriptor::from_bytes(&truncated).is_err());
+        assert!(matches!(
+            ChannelDescriptor::from_base64url("not a descriptor"),
+            Err(Error::DescriptorInvalid(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Detached signatures of the payloads of signed packets
+//!
+//! The signature of a signed packet covers the [`Spongos`] state of the message, which binds it to
+//! the messages it is linked to, so it can only be checked by a [`User`](crate::User) following
+//! the branch. Packets sent with
+//! [`User::send_detached_signed_packet()`](crate::User::send_detached_signed_packet) carry in
+//! addition a detached signature of their masked payload alone, returned to the readers by
+//! [`Message::detached_signature()`](crate::Message::detached_signature). Third parties storing
+//! the payload with its signature can check its authorship later with [`verify_detached()`],
+//! without running the protocol.
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::id::{Identifier, Identity};
+use spongos::{KeccakF1600, Spongos};
+
+// Local
+use crate::{Error, Result};
+
+/// Domain separation tag of the hashes signed by detached signatures
+const DETACHED_SIGNATURE_DOMAIN: &[u8] = b"streams detached signature";
+
+/// Signature of a payload by its publisher, verifiable without the state of the channel
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DetachedSignature(Vec<u8>);
+
+impl DetachedSignature {
+    /// Creates a [`DetachedSignature`] from its bytes, like the bytes stored alongside a payload
+    ///
+    /// # Arguments
+    /// * `bytes`: The bytes of the signature
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the bytes of the signature
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consumes the [`DetachedSignature`], returning its bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for DetachedSignature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Verifies that a payload was signed by the owner of an [`Identifier`]. `DID` identifiers are not
+/// supported, as `DID` identities cannot create detached signatures.
+///
+/// # Arguments
+/// * `payload`: The masked payload of the packet
+/// * `signature`: The [`DetachedSignature`] carried by the packet
+/// * `identifier`: The [`Identifier`] of the publisher of the packet
+pub fn verify_detached<P>(payload: P, signature: &DetachedSignature, identifier: &Identifier) -> Result<()>
+where
+    P: AsRef<[u8]>,
+{
+    identifier
+        .verify_detached(&payload_hash(payload.as_ref()), signature.as_bytes())
+        .map_err(|e| Error::Wrapped("verify detached signature", e))
+}
+
+/// Signs a payload with an [`Identity`]
+///
+/// # Arguments
+/// * `identity`: The [`Identity`] of the publisher
+/// * `payload`: The masked payload of the packet
+pub(crate) fn sign_detached(identity: &Identity, payload: &[u8]) -> Result<DetachedSignature> {
+    identity
+        .sign_detached(&payload_hash(payload))
+        .map(DetachedSignature)
+        .map_err(|e| Error::Wrapped("sign detached signature", e))
+}
+
+/// Returns the hash of a payload signed by detached signatures
+///
+/// # Arguments
+/// * `payload`: The signed payload
+fn payload_hash(payload: &[u8]) -> [u8; 32] {
+    let mut spongos = Spongos::<KeccakF1600>::init();
+    spongos.absorb(DETACHED_SIGNATURE_DOMAIN);
+    spongos.absorb(payload);
+    spongos.commit();
+    spongos.squeeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::id::{Ed25519, Identity};
+
+    use crate::{
+        api::fixtures::{new_reader, new_transport, new_user},
+        Result,
+    };
+
+    use super::{verify_detached, DetachedSignature};
+
+    #[tokio::test]
+    async fn detached_signatures_are_verified_without_the_channel() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_reader(&transport);
+        let author_id = author.identifier().unwrap().clone();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"plain").await?;
+        author
+            .send_detached_signed_packet("BASE_BRANCH", b"public", b"detached")
+            .await?;
+
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].detached_signature().is_none());
+        assert_eq!(messages[1].masked_payload(), Some(&b"detached"[..]));
+
+        // The payload and its signature are verified without the channel, once stored elsewhere
+        let stored = messages[1].detached_signature().unwrap().as_bytes().to_vec();
+        let signature = DetachedSignature::new(stored);
+        verify_detached(b"detached", &signature, &author_id)?;
+        assert!(verify_detached(b"tampered", &signature, &author_id).is_err());
+        let impostor = Identity::from(Ed25519::from_seed("impostor"));
+        assert!(verify_detached(b"detached", &signature, impostor.identifier()).is_err());
+        Ok(())
+    }
+}
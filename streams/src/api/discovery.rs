@@ -0,0 +1,155 @@
+//! Discovery of the streams of an author without exchanging their address out of band
+//!
+//! Every author has a discovery [`Address`], derived from its [`Identifier`] alone, where it
+//! advertises its streams with [`User::advertise_stream()`](crate::User::advertise_stream). The
+//! transport indexes the advertisements at the tag its `MessageIndex` derives from the discovery
+//! address, and [`discover()`] lists them back. As anyone can publish at the discovery address, the
+//! advertisements are only trusted once verified: they must be announcements signed by the author,
+//! published at the address their base branch derives.
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::{Address, AppAddr, MsgId},
+    error::Error as LetsError,
+    id::Identifier,
+    message::{Topic, TransportMessage},
+    transport::Transport,
+};
+
+// Local
+use crate::{
+    api::user::{ANN_MESSAGE_NUM, INIT_MESSAGE_NUM},
+    message::{announcement, message_types},
+    Error, Result,
+};
+
+/// Topic the discovery addresses of the authors are derived from
+const DISCOVERY_TOPIC: &str = "STREAMS_DISCOVERY";
+
+/// Returns the [`Address`] the streams of an author are advertised at
+///
+/// # Arguments
+/// * `author`: The [`Identifier`] of the author
+pub fn discovery_address(author: &Identifier) -> Address {
+    let topic = Topic::from(DISCOVERY_TOPIC);
+    let base_address = AppAddr::gen(author, &topic);
+    Address::new(base_address, MsgId::gen(base_address, author, &topic, ANN_MESSAGE_NUM))
+}
+
+/// Returns the [`Address`]es of the announcements of the streams advertised by an author, in the
+/// order they were advertised. Advertisements that are not announcements signed by the author, or
+/// whose announcement is not published at the address derived from its base branch, are ignored.
+/// Streams created with a custom [`LinkGenerator`](lets::address::LinkGenerator) cannot be
+/// discovered.
+///
+/// # Arguments
+/// * `transport`: The [`Transport`] the streams are published in
+/// * `author`: The [`Identifier`] of the author
+pub async fn discover<T>(transport: &mut T, author: &Identifier) -> Result<Vec<Address>>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage>,
+{
+    let discovery = discovery_address(author);
+    let advertisements = match transport.recv_messages(discovery).await {
+        Ok(advertisements) => advertisements,
+        // No stream was advertised yet
+        Err(e) if e.is_not_found() => return Ok(Vec::new()),
+        Err(e) => return Err(Error::Transport(discovery, "discover streams", e)),
+    };
+    let mut streams = Vec::new();
+    for advertisement in advertisements {
+        if let Some(stream) = verify_advertisement(transport, author, advertisement).await {
+            if !streams.contains(&stream) {
+                streams.push(stream);
+            }
+        }
+    }
+    Ok(streams)
+}
+
+/// Returns the [`Address`] of the stream advertised by a message, if the message is an
+/// announcement signed by the author and published at the address derived from its base branch
+///
+/// # Arguments
+/// * `transport`: The [`Transport`] the stream is published in
+/// * `author`: The [`Identifier`] of the author
+/// * `advertisement`: The message found at the discovery address of the author
+async fn verify_advertisement<T>(
+    transport: &mut T,
+    author: &Identifier,
+    advertisement: TransportMessage,
+) -> Option<Address>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage>,
+{
+    let preparsed = advertisement.clone().parse_header().await.ok()?;
+    if preparsed.header().message_type() != message_types::ANNOUNCEMENT || preparsed.header().publisher() != author {
+        return None;
+    }
+    let (message, _) = preparsed.unwrap(announcement::Unwrap::default()).await.ok()?;
+    let content = message.payload().content();
+    if content.author_id() != author {
+        return None;
+    }
+    let topic = content.topic();
+    let base_address = AppAddr::gen(author, topic);
+    let address = Address::new(base_address, MsgId::gen(base_address, author, topic, INIT_MESSAGE_NUM));
+    let announcement = transport.recv_message(address).await.ok()?;
+    (announcement == advertisement).then(|| address)
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::{message::TransportMessage, transport::Transport as _};
+
+    use crate::{
+        api::fixtures::{new_reader, new_transport, new_user},
+        Error, Result,
+    };
+
+    use super::{discover, discovery_address};
+
+    #[tokio::test]
+    async fn advertised_streams_are_discovered_by_author() -> Result<()> {
+        let transport = new_transport();
+        let mut sensors = new_user("operator", &transport);
+        let mut alerts = new_user("operator", &transport);
+        let mut attacker = new_user("attacker", &transport);
+        let operator = sensors.identifier().unwrap().clone();
+
+        assert!(discover(&mut transport.clone(), &operator).await?.is_empty());
+        let sensors_stream = sensors.create_stream("SENSORS").await?;
+        let alerts_stream = alerts.create_stream("ALERTS").await?;
+        sensors.advertise_stream().await?;
+        alerts.advertise_stream().await?;
+        sensors.advertise_stream().await?;
+
+        // Announcements of other authors and garbage posted at the discovery address are ignored
+        let forged = attacker.create_stream("SENSORS").await?;
+        let forged = transport.borrow_mut().recv_message(forged.address()).await?;
+        let discovery = discovery_address(&operator);
+        transport.borrow_mut().send_message(discovery, forged).await?;
+        transport
+            .borrow_mut()
+            .send_message(discovery, TransportMessage::new(vec![0; 64]))
+            .await?;
+
+        let streams = discover(&mut transport.clone(), &operator).await?;
+        assert_eq!(streams, [sensors_stream.address(), alerts_stream.address()]);
+        let mut consumer = new_reader(&transport);
+        consumer.receive_message(streams[1]).await?;
+        assert_eq!(consumer.stream_address(), Some(alerts_stream.address()));
+        assert!(matches!(
+            new_reader(&transport).advertise_stream().await,
+            Err(Error::Setup(_))
+        ));
+        Ok(())
+    }
+}
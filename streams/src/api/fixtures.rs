@@ -0,0 +1,114 @@
+// Rust
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// Streams
+use lets::{
+    address::Address,
+    error::{Error as LetsError, Result as LetsResult},
+    id::Ed25519,
+    message::TransportMessage,
+    transport::{bucket, Transport as _},
+};
+
+// Local
+use crate::{
+    api::{user::User, user_builder::UserBuilder},
+    Result,
+};
+
+/// Transport shared by the users of a test
+pub(crate) type Transport = Rc<RefCell<bucket::Client>>;
+
+/// Creates an empty [`Transport`]
+pub(crate) fn new_transport() -> Transport {
+    Rc::new(RefCell::new(bucket::Client::new()))
+}
+
+/// Creates a [`UserBuilder`] with the Ed25519 identity of `seed`, for tests adding options to the
+/// user
+pub(crate) fn new_user_builder<T: Clone>(seed: &str, transport: &T) -> UserBuilder<T>
+where
+    T: for<'a> lets::transport::Transport<'a>,
+{
+    User::builder()
+        .with_identity(Ed25519::from_seed(seed))
+        .with_transport(transport.clone())
+}
+
+/// Creates a user with the Ed25519 identity of `seed`
+pub(crate) fn new_user<T: Clone>(seed: &str, transport: &T) -> User<T>
+where
+    T: for<'a> lets::transport::Transport<'a>,
+{
+    new_user_builder(seed, transport).build()
+}
+
+/// Creates a user without identity, that can only read
+pub(crate) fn new_reader<T: Clone>(transport: &T) -> User<T>
+where
+    T: for<'a> lets::transport::Transport<'a>,
+{
+    User::builder().with_transport(transport.clone()).build()
+}
+
+/// Creates an author with a stream on the `BASE_BRANCH` topic, and a subscriber whose subscription
+/// has been processed by the author
+pub(crate) async fn author_subscriber_fixture() -> Result<(User<Transport>, User<Transport>, Address, Transport)> {
+    let transport = new_transport();
+    let mut author = new_user("author", &transport);
+    let announcement = author.create_stream("BASE_BRANCH").await?;
+    let subscriber = subscriber_fixture("subscriber", &mut author, announcement.address(), transport.clone()).await?;
+    Ok((author, subscriber, announcement.address(), transport))
+}
+
+/// Creates a subscriber with the Ed25519 identity of `seed`, whose subscription has been processed
+/// by `author`
+pub(crate) async fn subscriber_fixture(
+    seed: &str,
+    author: &mut User<Transport>,
+    announcement_link: Address,
+    transport: Transport,
+) -> Result<User<Transport>> {
+    let mut subscriber = new_user(seed, &transport);
+    subscriber.receive_message(announcement_link).await?;
+    let subscription = subscriber.subscribe().await?;
+    author.receive_message(subscription.address()).await?;
+    Ok(subscriber)
+}
+
+/// Bucket transport failing every request while offline
+#[derive(Default)]
+pub(crate) struct IntermittentTransport {
+    pub(crate) bucket: bucket::Client,
+    pub(crate) offline: bool,
+}
+
+#[async_trait(?Send)]
+impl<'a> lets::transport::Transport<'a> for IntermittentTransport {
+    type Msg = TransportMessage;
+    type SendResponse = TransportMessage;
+
+    async fn send_message(&mut self, address: Address, msg: TransportMessage) -> LetsResult<TransportMessage>
+    where
+        'a: 'async_trait,
+    {
+        match self.offline {
+            true => Err(LetsError::AddressError("transport is offline", address)),
+            false => self.bucket.send_message(address, msg).await,
+        }
+    }
+
+    async fn recv_messages(&mut self, address: Address) -> LetsResult<Vec<TransportMessage>>
+    where
+        'a: 'async_trait,
+    {
+        match self.offline {
+            true => Err(LetsError::AddressError("transport is offline", address)),
+            false => self.bucket.recv_messages(address).await,
+        }
+    }
+}
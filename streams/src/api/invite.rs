@@ -0,0 +1,277 @@
+//! Invitations to subscribe to a stream
+//!
+//! The author of a stream creates an [`Invite`] with
+//! [`User::create_invite()`](crate::User::create_invite) and shares the resulting [`InviteToken`]
+//! out of band. The invited users include the token in their subscription with
+//! [`User::subscribe_with_invite()`](crate::User::subscribe_with_invite). The author verifies the
+//! signature of the token, the stream it was created for, its expiry and the number of times it
+//! has been used, and accepts the subscription regardless of its
+//! [`SubscriptionPolicy`](crate::SubscriptionPolicy), reporting the branch the invite grants
+//! access to. The access is granted by the next keyload of the branch, sent for the subscribers
+//! accepted with invites by [`User::grant_invites()`](crate::User::grant_invites).
+//!
+//! ```ddml
+//! message Invite {
+//!     absorb           u8     id[16];
+//!     absorb           u8     stream_address[52];
+//!     absorb           bytes  topic;
+//!     absorb           u64    expiry;
+//!     absorb           size_t max_uses;
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+// 3rd-party
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentVerify, Topic},
+};
+use spongos::ddml::{
+    commands::{sizeof, unwrap, wrap, Absorb},
+    types::{Bytes, NBytes, Size, Uint64},
+};
+
+// Local
+use crate::{Error, Result};
+
+/// Size of the random identifier of an [`Invite`]
+pub const INVITE_ID_SIZE: usize = 16;
+
+/// Invitation to subscribe to a stream, granting access to one of its branches
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Invite {
+    /// Random identifier of the invite, used to count its uses
+    id: [u8; INVITE_ID_SIZE],
+    /// The [`Address`] of the announcement of the stream the invite is for
+    stream_address: Address,
+    /// The [`Topic`] of the branch the invite grants access to
+    topic: Topic,
+    /// Unix timestamp, in seconds, after which the invite is no longer valid
+    expiry: u64,
+    /// Number of subscriptions the invite can be used for
+    max_uses: usize,
+}
+
+impl Invite {
+    /// Creates a new [`Invite`] with a random identifier
+    ///
+    /// # Arguments
+    /// * `stream_address`: The [`Address`] of the announcement of the stream the invite is for
+    /// * `topic`: The [`Topic`] of the branch the invite grants access to
+    /// * `expiry`: Unix timestamp, in seconds, after which the invite is no longer valid
+    /// * `max_uses`: Number of subscriptions the invite can be used for
+    pub(crate) fn new(stream_address: Address, topic: Topic, expiry: u64, max_uses: usize) -> Self {
+        Self {
+            id: StdRng::from_entropy().gen(),
+            stream_address,
+            topic,
+            expiry,
+            max_uses,
+        }
+    }
+
+    /// Returns the random identifier of the invite
+    pub fn id(&self) -> &[u8; INVITE_ID_SIZE] {
+        &self.id
+    }
+
+    /// Returns the [`Address`] of the announcement of the stream the invite is for
+    pub fn stream_address(&self) -> Address {
+        self.stream_address
+    }
+
+    /// Returns the [`Topic`] of the branch the invite grants access to
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Returns the Unix timestamp, in seconds, after which the invite is no longer valid
+    pub fn expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    /// Returns the number of subscriptions the invite can be used for
+    pub fn max_uses(&self) -> usize {
+        self.max_uses
+    }
+
+    /// Returns true if the invite is no longer valid at the provided time
+    ///
+    /// # Arguments
+    /// * `now`: The current Unix timestamp, in seconds
+    pub fn is_expired(&self, now: u64) -> bool {
+        now > self.expiry
+    }
+
+    /// Encodes the invite and signs it, returning the [`InviteToken`] to share with the invited
+    /// users
+    ///
+    /// # Arguments
+    /// * `author`: The [`Identity`] of the author of the stream
+    pub(crate) async fn sign(&self, author: &Identity) -> Result<InviteToken> {
+        let mut ctx = sizeof::Context::new();
+        ctx.absorb(NBytes::new(self.id))?
+            .absorb(&self.stream_address)?
+            .absorb(Bytes::new(&self.topic))?
+            .absorb(Uint64::new(self.expiry))?
+            .absorb(Size::new(self.max_uses))?
+            .sign_sizeof(author)
+            .await?;
+        let mut buf = vec![0; ctx.finalize()];
+
+        let mut ctx = wrap::Context::new(&mut buf[..]);
+        ctx.absorb(NBytes::new(self.id))?
+            .absorb(&self.stream_address)?
+            .absorb(Bytes::new(&self.topic))?
+            .absorb(Uint64::new(self.expiry))?
+            .absorb(Size::new(self.max_uses))?
+            .sign(author)
+            .await?;
+        Ok(InviteToken(buf))
+    }
+
+    /// Decodes an [`InviteToken`] and verifies that it was signed by the author of the stream
+    ///
+    /// # Arguments
+    /// * `token`: The [`InviteToken`] to decode
+    /// * `author`: The [`Identifier`] of the author of the stream
+    pub(crate) async fn verify(token: &InviteToken, author: &Identifier) -> Result<Self> {
+        let mut id = [0u8; INVITE_ID_SIZE];
+        let mut stream_address = Address::default();
+        let mut topic = Vec::new();
+        let mut expiry = Uint64::default();
+        let mut max_uses = Size::default();
+        let mut ctx = unwrap::Context::new(token.as_ref());
+        ctx.absorb(NBytes::new(&mut id))?
+            .absorb(&mut stream_address)?
+            .absorb(Bytes::new(&mut topic))?
+            .absorb(&mut expiry)?
+            .absorb(&mut max_uses)?
+            .verify(author)
+            .await?;
+        Ok(Self {
+            id,
+            stream_address,
+            topic: Topic::try_from(topic).map_err(|e| Error::Wrapped("decode invite topic", e))?,
+            expiry: expiry.inner(),
+            max_uses: max_uses.inner(),
+        })
+    }
+}
+
+/// Signed [`Invite`], shared out of band with the invited users
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InviteToken(Vec<u8>);
+
+impl InviteToken {
+    /// Returns the binary encoding of the token
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for InviteToken {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for InviteToken {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<InviteToken> for Vec<u8> {
+    fn from(token: InviteToken) -> Self {
+        token.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use lets::{id::Permissioned, message::Topic};
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Error, Result, SubscriptionPolicy, SubscriptionStatus,
+    };
+
+    #[tokio::test]
+    async fn invites_bypass_the_subscription_policy_until_used_up() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport)
+            .with_subscription_policy(SubscriptionPolicy::Manual)
+            .build();
+        let mut subscribers = Vec::new();
+        for seed in ["invited", "late", "expired", "misdirected"] {
+            subscribers.push(new_user(seed, &transport));
+        }
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let invite = author.create_invite("BASE_BRANCH", u64::MAX, 1).await?;
+        let expired_invite = author.create_invite("BASE_BRANCH", 0, 1).await?;
+        // Invites signed by the same author for another stream
+        let mut other_stream = new_user("author", &transport);
+        other_stream.create_stream("OTHER_BRANCH").await?;
+        let other_invite = other_stream.create_invite("OTHER_BRANCH", u64::MAX, 1).await?;
+        for subscriber in &mut subscribers {
+            subscriber.receive_message(announcement.address()).await?;
+        }
+        assert!(matches!(
+            subscribers[0].create_invite("BASE_BRANCH", u64::MAX, 1).await,
+            Err(Error::WrongRole(..))
+        ));
+        let invited = subscribers[0].subscribe_with_invite(&invite).await?.address();
+        let late = subscribers[1].subscribe_with_invite(&invite).await?.address();
+        let expired = subscribers[2].subscribe_with_invite(&expired_invite).await?.address();
+        let misdirected = subscribers[3].subscribe_with_invite(&other_invite).await?.address();
+
+        // A valid invite is accepted, and reading it again does not consume it
+        for _ in 0..2 {
+            let message = author.receive_message(invited).await?;
+            let subscription = message.as_subscription().unwrap();
+            assert_eq!(subscription.status, SubscriptionStatus::Accepted);
+            assert_eq!(subscription.invite_topic, Some(Topic::from("BASE_BRANCH")));
+        }
+
+        // Invites used up, expired or for another stream are rejected
+        for address in [late, expired, misdirected] {
+            let message = author.receive_message(address).await?;
+            let subscription = message.as_subscription().unwrap();
+            assert_eq!(subscription.status, SubscriptionStatus::Rejected);
+            assert_eq!(subscription.invite_topic, None);
+        }
+        assert_eq!(author.subscribers().count(), 1);
+        assert_eq!(author.pending_subscriptions().count(), 0);
+
+        // The invited subscriber is granted access to the branch of the invite, once
+        let topic = Topic::from("BASE_BRANCH");
+        assert_eq!(author.grant_invites().await?.len(), 1);
+        assert!(author.grant_invites().await?.is_empty());
+        author.send_signed_packet("BASE_BRANCH", b"public", b"invited").await?;
+        let messages = subscribers[0].fetch_next_messages().await?;
+        assert!(messages[0].is_keyload());
+        assert_eq!(messages[1].masked_payload(), Some(&b"invited"[..]));
+        let invited_id = subscribers[0].identifier().unwrap().clone();
+        assert_eq!(
+            author.permission_of(&invited_id, &topic),
+            Some(Permissioned::Read(&invited_id))
+        );
+        Ok(())
+    }
+}
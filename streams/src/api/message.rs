@@ -1,20 +1,41 @@
 // Rust
-use alloc::vec::Vec;
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 
 // 3rd-party
+#[cfg(any(feature = "json", feature = "cbor"))]
+use serde::de::DeserializeOwned;
 
 // IOTA
 
 // Streams
 use lets::{
-    address::Address,
+    address::{Address, MsgId},
     id::{Identifier, Permissioned, PskId},
-    message::{Message as LetsMessage, PreparsedMessage, Topic, TopicHash, TransportMessage, HDF},
+    message::{Message as LetsMessage, PreparsedMessage, Topic, TopicHash, TransportMessage, HDF, MESSAGE_DIGEST_SIZE},
+    transport::MirrorStatus,
 };
 
 // Local
-use crate::message::{
-    announcement, branch_announcement, keyload, signed_packet, subscription, tagged_packet, unsubscription,
+#[cfg(any(feature = "json", feature = "cbor"))]
+use crate::{api::payload, Result};
+use crate::{
+    api::{
+        branch_metadata::BranchMetadata,
+        commitment::COMMITMENT_DIGEST_SIZE,
+        custom_message::CustomContent,
+        detached::DetachedSignature,
+        notarizer,
+        payload::ContentType,
+        quorum::{Quorum, QuorumApproval},
+        reference::Reference,
+        seen_messages::SEEN_DIGEST_SIZE,
+        subscription_policy::SubscriptionStatus,
+    },
+    message::{
+        announcement, author_rotation, batch_packet, branch_announcement, branch_closure, commitment, countersignature,
+        custom_message, key_update, keyload, legacy, read_marker, reveal, selective_packet, signed_packet,
+        subscription, substream_announcement, tagged_packet, tombstone, unsubscription,
+    },
 };
 
 /// A processed Streams message
@@ -26,6 +47,16 @@ pub struct Message {
     pub header: HDF,
     /// The message payload
     pub content: MessageContent,
+    /// The content digest of the message, the `Blake2b256` hash of its wrapped bytes, with which it
+    /// is fetched by [`User::fetch_by_digest()`](crate::User::fetch_by_digest). None for the
+    /// markers of the messages received out of order, which are not processed yet.
+    pub digest: Option<[u8; MESSAGE_DIGEST_SIZE]>,
+    /// The countersignatures of the message by the witnesses trusted by the [`User`](crate::User),
+    /// known when the message was processed
+    pub countersignatures: Vec<Countersignature>,
+    /// The comparison of the copies of the message published on two networks, when it was received
+    /// with a [mirror](`lets::transport::mirror::Client`) transport
+    pub mirror_status: Option<MirrorStatus>,
 }
 
 impl Message {
@@ -46,6 +77,9 @@ impl Message {
             address,
             header: parts.0,
             content: parts.1.into_content().into(),
+            digest: None,
+            countersignatures: Vec::new(),
+            mirror_status: None,
         }
     }
 
@@ -67,14 +101,180 @@ impl Message {
                 cursor: parts.3,
                 message: parts.1,
             }),
+            digest: None,
+            countersignatures: Vec::new(),
+            mirror_status: None,
         }
     }
 
+    /// Creates a [`Message`] marking a message received before the message it is linked to, yielded
+    /// by [`Messages`](crate::Messages) in relaxed ordering
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `header`: The [header](`HDF`) of the message
+    /// * `awaiting`: The [`Address`] of the message it is linked to
+    pub(crate) fn out_of_order(address: Address, header: HDF, awaiting: Address) -> Self {
+        Self {
+            address,
+            header,
+            content: MessageContent::OutOfOrder(OutOfOrder { awaiting }),
+            digest: None,
+            countersignatures: Vec::new(),
+            mirror_status: None,
+        }
+    }
+
+    /// Creates a [`Message`] reporting that a message already processed was received again. The
+    /// message is not processed again, only its header is decoded.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `preparsed`: The [`PreparsedMessage`] received again
+    /// * `digest`: The digest identifying the message among the processed ones
+    pub(crate) fn duplicate(address: Address, preparsed: PreparsedMessage, digest: [u8; SEEN_DIGEST_SIZE]) -> Self {
+        Self {
+            address,
+            header: preparsed.into_parts().0,
+            content: MessageContent::DuplicateReceived(DuplicateReceived { digest }),
+            digest: None,
+            countersignatures: Vec::new(),
+            mirror_status: None,
+        }
+    }
+
+    /// Creates a read-only [`Message`] from a message decoded from a legacy (v1) channel
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `header`: The decoded [legacy header](`legacy::Header`)
+    /// * `content`: The decoded message content
+    pub(crate) fn from_legacy(address: Address, header: &legacy::Header, content: MessageContent) -> Self {
+        let mut hdf = HDF::default();
+        hdf.version = legacy::LEGACY_STREAMS_VER;
+        hdf.message_type = header.message_type();
+        hdf.linked_msg_address = Some(header.link());
+        hdf.sequence = header.sequence() as usize;
+        if let MessageContent::Legacy(legacy) = &content {
+            hdf.publisher = legacy.publisher_identifier.clone();
+        }
+        Self {
+            address,
+            header: hdf,
+            content,
+            digest: None,
+            countersignatures: Vec::new(),
+            mirror_status: None,
+        }
+    }
+
+    /// Records the content digest of the wrapped bytes the message was processed from
+    ///
+    /// # Arguments
+    /// * `digest`: The content digest of the message
+    pub(crate) fn with_digest(mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+
+    /// Attaches the countersignatures of the message by the trusted witnesses
+    ///
+    /// # Arguments
+    /// * `countersignatures`: The [`Countersignature`]s of the message
+    pub(crate) fn with_countersignatures(mut self, countersignatures: Vec<Countersignature>) -> Self {
+        self.countersignatures = countersignatures;
+        self
+    }
+
+    /// Records the comparison of the copies of the message received from two networks
+    ///
+    /// # Arguments
+    /// * `mirror_status`: The [`MirrorStatus`] reported by the transport, if any
+    pub(crate) fn with_mirror_status(mut self, mirror_status: Option<MirrorStatus>) -> Self {
+        self.mirror_status = mirror_status;
+        self
+    }
+
+    /// Quarantines the content of a packet rejected by a
+    /// [`PayloadValidator`](crate::PayloadValidator)
+    ///
+    /// # Arguments
+    /// * `reason`: The reason of the rejection
+    pub(crate) fn reject(self, reason: String) -> Self {
+        Self {
+            address: self.address,
+            header: self.header,
+            content: MessageContent::Rejected(Rejected {
+                reason,
+                content: Box::new(self.content),
+            }),
+            digest: self.digest,
+            countersignatures: self.countersignatures,
+            mirror_status: self.mirror_status,
+        }
+    }
+
+    /// Withholds the payloads of a packet redacted by a tombstone of the stream author, keeping its
+    /// content available on request
+    ///
+    /// # Arguments
+    /// * `tombstone`: The [`Address`] of the tombstone redacting the packet
+    /// * `reason`: The reason of the redaction
+    pub(crate) fn redact(self, tombstone: Address, reason: String) -> Self {
+        Self {
+            address: self.address,
+            header: self.header,
+            content: MessageContent::Redacted(Redacted {
+                tombstone,
+                reason,
+                content: Box::new(self.content),
+            }),
+            digest: self.digest,
+            countersignatures: self.countersignatures,
+            mirror_status: self.mirror_status,
+        }
+    }
+
+    /// Create an `AccessExpired` message, meaning that the packet was published after the access
+    /// granted to the reader by the last keyload of its branch expired, and its payloads were left
+    /// masked.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the packet
+    /// * `header`: The [header](`HDF`) of the packet
+    /// * `expired_at`: The Unix timestamp, in seconds, at which the access expired
+    pub(crate) fn access_expired(address: Address, header: HDF, expired_at: u64) -> Self {
+        Self {
+            address,
+            header,
+            content: MessageContent::AccessExpired(AccessExpired { expired_at }),
+            digest: None,
+            countersignatures: Vec::new(),
+            mirror_status: None,
+        }
+    }
+
+    /// Returns true if the message is a packet, carrying payloads
+    pub(crate) fn is_packet(&self) -> bool {
+        matches!(
+            self.content,
+            MessageContent::SignedPacket(_)
+                | MessageContent::TaggedPacket(_)
+                | MessageContent::SelectivePacket(_)
+                | MessageContent::BatchPacket(_)
+        )
+    }
+
     /// Returns the [`Address`] of the message
     pub fn address(&self) -> Address {
         self.address
     }
 
+    /// Returns the content digest of the message, if it was processed
+    pub fn digest(&self) -> Option<&[u8; MESSAGE_DIGEST_SIZE]> {
+        self.digest.as_ref()
+    }
+
     /// Returns a reference to the [header](`HDF`) of the message
     pub fn header(&self) -> &HDF {
         &self.header
@@ -90,6 +290,29 @@ impl Message {
         self.header.topic_hash()
     }
 
+    /// Returns a reference to the [`Identifier`] of the publisher of the message
+    pub fn publisher(&self) -> &Identifier {
+        self.header.publisher()
+    }
+
+    /// Returns the sequence number of the message, the cursor of its publisher in the branch when
+    /// it was sent. Announcements and subscriptions are not sequenced by their publisher.
+    pub fn sequence(&self) -> usize {
+        self.header.sequence()
+    }
+
+    /// Returns the [`MsgId`] of the message this message is linked to, if any. The spongos state
+    /// of the linked message is joined when the message is wrapped, which binds the message to it.
+    pub fn linked_msg_address(&self) -> Option<MsgId> {
+        self.header.linked_msg_address()
+    }
+
+    /// Returns the [`Address`] of the message this message is linked to, if any
+    pub fn linked_address(&self) -> Option<Address> {
+        self.linked_msg_address()
+            .map(|msgid| Address::new(self.address.base(), msgid))
+    }
+
     /// Returns true if the message is a [`MessageContent`]`::Announcement`
     pub fn is_announcement(&self) -> bool {
         matches!(self.content, MessageContent::Announcement { .. })
@@ -100,11 +323,51 @@ impl Message {
         matches!(self.content, MessageContent::BranchAnnouncement { .. })
     }
 
+    /// Returns true if the message is a [`MessageContent`]`::BranchClosed`
+    pub fn is_branch_closed(&self) -> bool {
+        matches!(self.content, MessageContent::BranchClosed { .. })
+    }
+
     /// Returns true if the message is a [`MessageContent`]`::Keyload`
     pub fn is_keyload(&self) -> bool {
         matches!(self.content, MessageContent::Keyload { .. })
     }
 
+    /// Returns true if the message is a [`MessageContent`]`::ReadMarker`
+    pub fn is_read_marker(&self) -> bool {
+        matches!(self.content, MessageContent::ReadMarker { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Tombstone`
+    pub fn is_tombstone(&self) -> bool {
+        matches!(self.content, MessageContent::Tombstone { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::AuthorRotation`
+    pub fn is_author_rotation(&self) -> bool {
+        matches!(self.content, MessageContent::AuthorRotation { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Countersignature`
+    pub fn is_countersignature(&self) -> bool {
+        matches!(self.content, MessageContent::Countersignature { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Commitment`
+    pub fn is_commitment(&self) -> bool {
+        matches!(self.content, MessageContent::Commitment { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Reveal`
+    pub fn is_reveal(&self) -> bool {
+        matches!(self.content, MessageContent::Reveal { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Custom`
+    pub fn is_custom(&self) -> bool {
+        matches!(self.content, MessageContent::Custom { .. })
+    }
+
     /// Returns true if the message is a [`MessageContent`]`::SignedPacket`
     pub fn is_signed_packet(&self) -> bool {
         matches!(self.content, MessageContent::SignedPacket { .. })
@@ -115,6 +378,16 @@ impl Message {
         matches!(self.content, MessageContent::TaggedPacket { .. })
     }
 
+    /// Returns true if the message is a [`MessageContent`]`::SelectivePacket`
+    pub fn is_selective_packet(&self) -> bool {
+        matches!(self.content, MessageContent::SelectivePacket { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::BatchPacket`
+    pub fn is_batch_packet(&self) -> bool {
+        matches!(self.content, MessageContent::BatchPacket { .. })
+    }
+
     /// Returns true if the message is a [`MessageContent`]`::Subscription`
     pub fn is_subscription(&self) -> bool {
         matches!(self.content, MessageContent::Subscription { .. })
@@ -125,11 +398,46 @@ impl Message {
         matches!(self.content, MessageContent::Unsubscription { .. })
     }
 
+    /// Returns true if the message is a [`MessageContent`]`::KeyUpdate`
+    pub fn is_key_update(&self) -> bool {
+        matches!(self.content, MessageContent::KeyUpdate { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::SubstreamAnnounced`
+    pub fn is_substream_announcement(&self) -> bool {
+        matches!(self.content, MessageContent::SubstreamAnnounced { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Rejected`
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.content, MessageContent::Rejected { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Redacted`
+    pub fn is_redacted(&self) -> bool {
+        matches!(self.content, MessageContent::Redacted { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::AccessExpired`
+    pub fn is_access_expired(&self) -> bool {
+        matches!(self.content, MessageContent::AccessExpired { .. })
+    }
+
     /// Returns true if the message is a [`MessageContent`]`::Orphan`
     pub fn is_orphan(&self) -> bool {
         matches!(self.content, MessageContent::Orphan { .. })
     }
 
+    /// Returns true if the message is a [`MessageContent`]`::OutOfOrder` marker
+    pub fn is_out_of_order(&self) -> bool {
+        matches!(self.content, MessageContent::OutOfOrder { .. })
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::DuplicateReceived`
+    pub fn is_duplicate(&self) -> bool {
+        matches!(self.content, MessageContent::DuplicateReceived { .. })
+    }
+
     /// If the message is an `Announcement` return it as one
     pub fn as_announcement(&self) -> Option<&Announcement> {
         if let MessageContent::Announcement(announcement) = &self.content {
@@ -148,6 +456,87 @@ impl Message {
         }
     }
 
+    /// If the message is a `BranchClosed` return it as one
+    pub fn as_branch_closed(&self) -> Option<&BranchClosed> {
+        if let MessageContent::BranchClosed(branch_closed) = &self.content {
+            Some(branch_closed)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `ReadMarker` return it as one
+    pub fn as_read_marker(&self) -> Option<&ReadMarker> {
+        if let MessageContent::ReadMarker(read_marker) = &self.content {
+            Some(read_marker)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Tombstone` return it as one
+    pub fn as_tombstone(&self) -> Option<&Tombstone> {
+        if let MessageContent::Tombstone(tombstone) = &self.content {
+            Some(tombstone)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is an `AuthorRotation` return it as one
+    pub fn as_author_rotation(&self) -> Option<&AuthorRotation> {
+        if let MessageContent::AuthorRotation(rotation) = &self.content {
+            Some(rotation)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Countersignature` return it as one
+    pub fn as_countersignature(&self) -> Option<&Countersignature> {
+        if let MessageContent::Countersignature(countersignature) = &self.content {
+            Some(countersignature)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Commitment` return it as one
+    pub fn as_commitment(&self) -> Option<&Commitment> {
+        if let MessageContent::Commitment(commitment) = &self.content {
+            Some(commitment)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Reveal` return it as one
+    pub fn as_reveal(&self) -> Option<&Reveal> {
+        if let MessageContent::Reveal(reveal) = &self.content {
+            Some(reveal)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Custom` message return it as one
+    pub fn as_custom(&self) -> Option<&CustomMessage> {
+        if let MessageContent::Custom(custom) = &self.content {
+            Some(custom)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `SubstreamAnnounced` return it as one
+    pub fn as_substream_announcement(&self) -> Option<&SubstreamAnnounced> {
+        if let MessageContent::SubstreamAnnounced(substream_announced) = &self.content {
+            Some(substream_announced)
+        } else {
+            None
+        }
+    }
+
     /// If the message is a `Keyload` return it as one
     pub fn as_keyload(&self) -> Option<&Keyload> {
         if let MessageContent::Keyload(keyload) = &self.content {
@@ -175,6 +564,24 @@ impl Message {
         }
     }
 
+    /// If the message is a `SelectivePacket` return it as one
+    pub fn as_selective_packet(&self) -> Option<&SelectivePacket> {
+        if let MessageContent::SelectivePacket(selective_packet) = &self.content {
+            Some(selective_packet)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `BatchPacket` return it as one
+    pub fn as_batch_packet(&self) -> Option<&BatchPacket> {
+        if let MessageContent::BatchPacket(batch_packet) = &self.content {
+            Some(batch_packet)
+        } else {
+            None
+        }
+    }
+
     /// If the message is a `Subscription` return it as one
     pub fn as_subscription(&self) -> Option<&Subscription> {
         if let MessageContent::Subscription(subscription) = &self.content {
@@ -193,6 +600,56 @@ impl Message {
         }
     }
 
+    /// If the message is a `KeyUpdate` return it as one
+    pub fn as_key_update(&self) -> Option<&KeyUpdate> {
+        if let MessageContent::KeyUpdate(key_update) = &self.content {
+            Some(key_update)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the message is a [`MessageContent`]`::Legacy`
+    pub fn is_legacy(&self) -> bool {
+        matches!(self.content, MessageContent::Legacy { .. })
+    }
+
+    /// If the message is a `Legacy` message return it as one
+    pub fn as_legacy(&self) -> Option<&Legacy> {
+        if let MessageContent::Legacy(legacy) = &self.content {
+            Some(legacy)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Rejected` packet return it as one
+    pub fn as_rejected(&self) -> Option<&Rejected> {
+        if let MessageContent::Rejected(rejected) = &self.content {
+            Some(rejected)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is a `Redacted` packet return it as one
+    pub fn as_redacted(&self) -> Option<&Redacted> {
+        if let MessageContent::Redacted(redacted) = &self.content {
+            Some(redacted)
+        } else {
+            None
+        }
+    }
+
+    /// If the message is an `AccessExpired` packet return it as one
+    pub fn as_access_expired(&self) -> Option<&AccessExpired> {
+        if let MessageContent::AccessExpired(access_expired) = &self.content {
+            Some(access_expired)
+        } else {
+            None
+        }
+    }
+
     /// If the message is an `Orphan` return it as one
     pub fn as_orphan(&self) -> Option<&Orphan> {
         if let MessageContent::Orphan(orphan) = &self.content {
@@ -204,26 +661,59 @@ impl Message {
 
     /// Get the public payload of the message
     ///
-    /// If the message is a [`MessageContent`]`::TaggedPacket` or [`MessageContent`]`::SignedPacket`
-    /// it returns `Some(payload)`, otherwise returns `None`.  
+    /// If the message is a [`MessageContent`]`::TaggedPacket`, [`MessageContent`]`::SignedPacket`
+    /// or [`MessageContent`]`::Legacy` it returns `Some(payload)`, otherwise returns `None`.  
     pub fn public_payload(&self) -> Option<&[u8]> {
-        match &self.content {
-            MessageContent::TaggedPacket(TaggedPacket { public_payload, .. })
-            | MessageContent::SignedPacket(SignedPacket { public_payload, .. }) => Some(public_payload),
-            _ => None,
-        }
+        self.content.public_payload()
     }
 
     /// Get the masked payload of the message
     ///
-    /// If the message is a [`MessageContent`]`::TaggedPacket` or [`MessageContent`]`::SignedPacket`
-    /// it returns `Some(payload)`, otherwise returns `None`.  
+    /// If the message is a [`MessageContent`]`::TaggedPacket`, [`MessageContent`]`::SignedPacket`
+    /// or [`MessageContent`]`::Legacy` it returns `Some(payload)`, otherwise returns `None`.  
     pub fn masked_payload(&self) -> Option<&[u8]> {
-        match &self.content {
-            MessageContent::TaggedPacket(TaggedPacket { masked_payload, .. })
-            | MessageContent::SignedPacket(SignedPacket { masked_payload, .. }) => Some(masked_payload),
-            _ => None,
-        }
+        self.content.masked_payload()
+    }
+
+    /// Get the detached signature of the masked payload of the message
+    ///
+    /// If the message is a [`MessageContent`]`::SignedPacket` sent with
+    /// [`User::send_detached_signed_packet()`](crate::User::send_detached_signed_packet) it returns
+    /// `Some(signature)`, otherwise returns `None`. The signature can be verified against the
+    /// masked payload and the publisher with [`verify_detached()`](crate::verify_detached).
+    pub fn detached_signature(&self) -> Option<&DetachedSignature> {
+        self.as_signed_packet()
+            .and_then(|signed_packet| signed_packet.detached_signature.as_ref())
+    }
+
+    /// Get the references to the messages cited by the message
+    ///
+    /// If the message is a [`MessageContent`]`::SignedPacket` sent with
+    /// [`User::send_citing_packet()`](crate::User::send_citing_packet) it returns the
+    /// [`Reference`]s of the packet, otherwise returns an empty slice. The cited messages can be
+    /// checked with [`User::verify_reference()`](crate::User::verify_reference).
+    pub fn references(&self) -> &[Reference] {
+        self.as_signed_packet()
+            .map_or(&[], |signed_packet| signed_packet.references.as_slice())
+    }
+
+    /// Get the countersignatures of the message
+    ///
+    /// Returns the [`Countersignature`]s of the message by the witnesses trusted by the
+    /// [`User`](crate::User) (see [`User::add_witness()`](crate::User::add_witness)) that were
+    /// processed before the message, like when the message is read again with
+    /// [`User::fetch_history()`](crate::User::fetch_history), otherwise returns an empty slice.
+    pub fn countersignatures(&self) -> &[Countersignature] {
+        &self.countersignatures
+    }
+
+    /// Get the comparison of the copies of the message published on two networks
+    ///
+    /// If the message was received with a [mirror](`lets::transport::mirror::Client`) transport it
+    /// returns the [`MirrorStatus`] of the copies found at its address on the primary and the
+    /// secondary networks, otherwise returns None.
+    pub fn mirror_status(&self) -> Option<MirrorStatus> {
+        self.mirror_status
     }
 }
 
@@ -231,17 +721,93 @@ impl Message {
 pub enum MessageContent {
     Announcement(Announcement),
     BranchAnnouncement(BranchAnnouncement),
+    BranchClosed(BranchClosed),
     Keyload(Keyload),
     SignedPacket(SignedPacket),
     TaggedPacket(TaggedPacket),
+    SelectivePacket(SelectivePacket),
+    BatchPacket(BatchPacket),
     Subscription(Subscription),
     Unsubscription(Unsubscription),
+    KeyUpdate(KeyUpdate),
+    SubstreamAnnounced(SubstreamAnnounced),
+    ReadMarker(ReadMarker),
+    Tombstone(Tombstone),
+    AuthorRotation(AuthorRotation),
+    Countersignature(Countersignature),
+    Commitment(Commitment),
+    Reveal(Reveal),
+    Custom(CustomMessage),
+    Rejected(Rejected),
+    Redacted(Redacted),
+    AccessExpired(AccessExpired),
     Orphan(Orphan),
+    OutOfOrder(OutOfOrder),
+    DuplicateReceived(DuplicateReceived),
+    Legacy(Legacy),
+}
+
+impl MessageContent {
+    /// Get the public payload of the content
+    ///
+    /// If the content is a [`MessageContent`]`::TaggedPacket`, [`MessageContent`]`::SignedPacket`
+    /// or [`MessageContent`]`::Legacy` it returns `Some(payload)`, otherwise returns `None`.
+    pub fn public_payload(&self) -> Option<&[u8]> {
+        match self {
+            MessageContent::TaggedPacket(TaggedPacket { public_payload, .. })
+            | MessageContent::SignedPacket(SignedPacket { public_payload, .. })
+            | MessageContent::Legacy(Legacy { public_payload, .. }) => Some(public_payload),
+            _ => None,
+        }
+    }
+
+    /// Get the masked payload of the content
+    ///
+    /// If the content is a [`MessageContent`]`::TaggedPacket`, [`MessageContent`]`::SignedPacket`
+    /// or [`MessageContent`]`::Legacy` it returns `Some(payload)`, otherwise returns `None`.
+    pub fn masked_payload(&self) -> Option<&[u8]> {
+        match self {
+            MessageContent::TaggedPacket(TaggedPacket { masked_payload, .. })
+            | MessageContent::SignedPacket(SignedPacket { masked_payload, .. })
+            | MessageContent::Legacy(Legacy { masked_payload, .. }) => Some(masked_payload),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`ContentType`] tag of the masked payload, if any. Only meaningful for packets
+    /// sent with the structured payload helpers, like [`User::send_json()`](crate::User), as the
+    /// first byte of an unstructured payload might collide with a tag.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.masked_payload().and_then(ContentType::of)
+    }
+
+    /// Deserializes the `JSON` masked payload of a packet sent with
+    /// [`User::send_json()`](crate::User)
+    #[cfg(feature = "json")]
+    pub fn json<D>(&self) -> Result<D>
+    where
+        D: DeserializeOwned,
+    {
+        payload::decode_json(self.masked_payload().unwrap_or_default())
+    }
+
+    /// Deserializes the `CBOR` masked payload of a packet sent with
+    /// [`User::send_cbor()`](crate::User)
+    #[cfg(feature = "cbor")]
+    pub fn cbor<D>(&self) -> Result<D>
+    where
+        D: DeserializeOwned,
+    {
+        payload::decode_cbor(self.masked_payload().unwrap_or_default())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Announcement {
     pub author_identifier: Identifier,
+    /// The [`Quorum`] of author keys of the stream, whose approvals of the announcement were
+    /// verified. None if the stream was not announced with a quorum.
+    pub quorum: Option<Quorum>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -249,10 +815,140 @@ pub struct BranchAnnouncement {
     pub topic: Topic,
 }
 
+/// Branch Closure [`Message`], the terminal message of a branch.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BranchClosed {
+    /// The [`Topic`] of the closed branch
+    pub topic: Topic,
+    /// The [`Identifier`] of the administrator closing the branch
+    pub publisher_identifier: Identifier,
+}
+
+/// Read Marker [`Message`], recording the last message processed by a consumer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReadMarker {
+    /// The [`Identifier`] of the consumer publishing the marker
+    pub publisher_identifier: Identifier,
+    /// The [`Address`] of the last message processed by the consumer
+    pub marked_address: Address,
+}
+
+/// Tombstone [`Message`], published by the author of the stream to redact a message of the branch.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tombstone {
+    /// The [`Identifier`] of the author redacting the message
+    pub publisher_identifier: Identifier,
+    /// The [`MsgId`] of the redacted message
+    pub redacted: MsgId,
+    /// The reason of the redaction
+    pub reason: String,
+}
+
+/// Author Rotation [`Message`], published by the author of the stream to hand the authorship of
+/// the stream over to a new identifier. Signed by the previous author, it proves the continuity
+/// between the two keys.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AuthorRotation {
+    /// The [`Identifier`] of the previous author, who signed the message
+    pub previous_author: Identifier,
+    /// The [`Identifier`] of the new author
+    pub new_author: Identifier,
+    /// The approvals of the rotation by the members of the [`Quorum`] of the stream, empty if the
+    /// rotation was not approved by a quorum
+    pub approvals: Vec<QuorumApproval>,
+}
+
+/// Countersignature [`Message`], published by a witness of the stream to attest a packet after its
+/// publication (see [`User::countersign()`](crate::User::countersign)).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Countersignature {
+    /// The [`Identifier`] of the witness, who signed the countersignature
+    pub witness: Identifier,
+    /// The [`MsgId`] of the countersigned packet
+    pub countersigned: MsgId,
+}
+
+/// Commitment [`Message`], binding its publisher to a payload disclosed later by a [`Reveal`]
+/// (see [`User::send_commitment()`](crate::User::send_commitment)).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Commitment {
+    /// The [`Identifier`] of the publisher of the commitment
+    pub publisher_identifier: Identifier,
+    /// The digest of the committed payload (see [`commitment_digest()`](crate::commitment_digest))
+    pub digest: [u8; COMMITMENT_DIGEST_SIZE],
+}
+
+/// Reveal [`Message`], disclosing the payload committed to by an earlier [`Commitment`] of its
+/// publisher (see [`User::send_reveal()`](crate::User::send_reveal)). Reveals are only surfaced
+/// once their payload was verified against the digest of the commitment.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Reveal {
+    /// The [`Identifier`] of the publisher of the commitment and of the reveal
+    pub publisher_identifier: Identifier,
+    /// The [`MsgId`] of the commitment
+    pub commitment: MsgId,
+    /// The revealed payload
+    pub payload: Vec<u8>,
+}
+
+/// Custom [`Message`], of a message type defined by the application (see
+/// [`User::register_message_type()`](crate::User::register_message_type)).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CustomMessage {
+    /// The message type identifier
+    pub message_type: u8,
+    /// The [`Identifier`] of the publisher
+    pub publisher_identifier: Identifier,
+    /// The content of the message, unwrapped by the application
+    content: Arc<dyn CustomContent>,
+}
+
+impl CustomMessage {
+    /// Returns the content of the message if it is a `C`, the content type registered for the
+    /// message type
+    pub fn content<C>(&self) -> Option<&C>
+    where
+        C: CustomContent,
+    {
+        self.content.as_any().downcast_ref()
+    }
+}
+
+/// Substream Announcement [`Message`], anchoring a new stream to the message it is linked to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SubstreamAnnounced {
+    /// The [`Identifier`] of the publisher, which is also the author of the substream
+    pub publisher_identifier: Identifier,
+    /// The [`Address`] of the announcement of the substream
+    pub address: Address,
+    /// The [`Topic`] of the base branch of the substream
+    pub topic: Topic,
+    /// The hash of the announcement of the substream
+    pub announcement_hash: [u8; substream_announcement::ANNOUNCEMENT_HASH_SIZE],
+}
+
+impl SubstreamAnnounced {
+    /// Returns true if a binary message is the announcement anchored by the publisher, which
+    /// readers should check before following the substream from an untrusted source
+    ///
+    /// # Arguments
+    /// * `announcement`: The binary announcement fetched at the address of the substream
+    pub fn anchors(&self, announcement: &TransportMessage) -> bool {
+        notarizer::message_hash(announcement) == self.announcement_hash
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Keyload {
     pub subscribers: Vec<Permissioned<Identifier>>,
     pub psks: Vec<PskId>,
+    /// Whether the keyload switches its branch to forward secrecy mode
+    pub forward_secrecy: bool,
+    /// The description of the branch, if the keyload carries one
+    pub metadata: Option<BranchMetadata>,
+    /// Unix timestamp, in seconds, at which the access granted by the keyload expires, if the
+    /// keyload carries one
+    pub expires_at: Option<u64>,
 }
 
 impl Keyload {
@@ -265,6 +961,12 @@ impl Keyload {
     pub fn includes_psk(&self, psk_id: &PskId) -> bool {
         self.psks.iter().any(|id| id == psk_id)
     }
+
+    /// Returns the [`BranchMetadata`] describing the branch, if the keyload was sent with
+    /// [`User::send_keyload_with_metadata()`](crate::User::send_keyload_with_metadata)
+    pub fn metadata(&self) -> Option<&BranchMetadata> {
+        self.metadata.as_ref()
+    }
 }
 
 /// Signed Packet [`Message`].
@@ -276,6 +978,10 @@ pub struct SignedPacket {
     pub masked_payload: Vec<u8>,
     /// A payload that was not encrypted
     pub public_payload: Vec<u8>,
+    /// The detached signature of the masked payload, if the packet carries one
+    pub detached_signature: Option<DetachedSignature>,
+    /// The references to the messages cited by the packet
+    pub references: Vec<Reference>,
 }
 
 /// Tagged Packet [`Message`].
@@ -287,11 +993,83 @@ pub struct TaggedPacket {
     pub public_payload: Vec<u8>,
 }
 
+/// Selective Packet [`Message`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SelectivePacket {
+    /// The [`Identifier`] of the publisher
+    pub publisher_identifier: Identifier,
+    /// The fields of the packet, in the order they were sent. Fields the reader is not a recipient
+    /// of are `None`.
+    pub fields: Vec<Option<Vec<u8>>>,
+}
+
+impl SelectivePacket {
+    /// Returns the field at the provided index, if it exists and the reader is a recipient of it
+    ///
+    /// # Arguments
+    /// * `index`: The position of the field in the packet
+    pub fn field(&self, index: usize) -> Option<&[u8]> {
+        self.fields.get(index).and_then(Option::as_deref)
+    }
+
+    /// Returns an iterator over the fields the reader is a recipient of, with their position in
+    /// the packet
+    pub fn readable_fields(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter_map(|(index, field)| field.as_deref().map(|field| (index, field)))
+    }
+}
+
+/// Batch Packet [`Message`]. The records of a batch are published in a single message, so they are
+/// read all together or not at all.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BatchPacket {
+    /// The [`Identifier`] of the publisher
+    pub publisher_identifier: Identifier,
+    /// The records of the batch, in the order they were sent
+    pub records: Vec<BatchRecord>,
+}
+
+/// Record of a [`BatchPacket`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BatchRecord {
+    /// A payload that was not encrypted
+    pub public_payload: Vec<u8>,
+    /// A payload that was encrypted
+    pub masked_payload: Vec<u8>,
+}
+
+impl BatchRecord {
+    /// Creates a [`BatchRecord`] from its payloads
+    ///
+    /// # Arguments
+    /// * `public_payload`: The unmasked payload of the record
+    /// * `masked_payload`: The masked payload of the record
+    pub fn new<P, M>(public_payload: P, masked_payload: M) -> Self
+    where
+        P: Into<Vec<u8>>,
+        M: Into<Vec<u8>>,
+    {
+        Self {
+            public_payload: public_payload.into(),
+            masked_payload: masked_payload.into(),
+        }
+    }
+}
+
 /// Subscription [`Message`].
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Subscription {
     /// [`Identifier`] of the subscribing user
     pub subscriber_identifier: Identifier,
+    /// Outcome of the subscription request under the
+    /// [`SubscriptionPolicy`](crate::SubscriptionPolicy) of the reader
+    pub status: SubscriptionStatus,
+    /// The [`Topic`] of the branch granted by the [`Invite`](crate::Invite) of the subscription,
+    /// if the subscription was accepted with a valid invite
+    pub invite_topic: Option<Topic>,
 }
 
 impl Subscription {
@@ -315,6 +1093,66 @@ impl Unsubscription {
     }
 }
 
+/// KeyUpdate [`Message`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyUpdate {
+    /// [`Identifier`] of the user rotating its key exchange key
+    pub identifier: Identifier,
+    /// Number of rotations the announced key results from
+    pub generation: usize,
+    /// The announced key exchange public key
+    pub exchange_key: [u8; 32],
+}
+
+impl KeyUpdate {
+    /// Returns a reference to the [`Identifier`] of the user rotating its key exchange key
+    pub fn identifier(&self) -> &Identifier {
+        &self.identifier
+    }
+}
+
+/// Packet [`Message`] rejected by the [`PayloadValidator`](crate::PayloadValidator) of its branch.
+/// The packet was processed, but its payloads are quarantined: they are not returned by
+/// [`Message::public_payload()`] and [`Message::masked_payload()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rejected {
+    /// The reason of the rejection, as provided by the validator
+    pub reason: String,
+    /// The content of the rejected packet
+    pub content: Box<MessageContent>,
+}
+
+/// Packet [`Message`] redacted by a [`Tombstone`] of the stream author (see
+/// [`User::send_tombstone()`](crate::User::send_tombstone)). The ledger being immutable, the packet
+/// was processed, but its payloads are withheld: they are not returned by
+/// [`Message::public_payload()`] and [`Message::masked_payload()`]. The original content remains
+/// available on request, to verify what was redacted.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Redacted {
+    /// The [`Address`] of the tombstone redacting the packet
+    pub tombstone: Address,
+    /// The reason of the redaction, as provided by the author
+    pub reason: String,
+    /// The original content of the redacted packet
+    content: Box<MessageContent>,
+}
+
+impl Redacted {
+    /// Returns the original content of the redacted packet, withheld by the reading API
+    pub fn original(&self) -> &MessageContent {
+        &self.content
+    }
+}
+
+/// Signed packet [`Message`] published after the access granted to the reader by the last keyload
+/// of its branch expired (see [`User::send_keyload_with_expiry()`](crate::User::send_keyload_with_expiry)).
+/// Its payloads were left masked, and so are the payloads of the packets linked to it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AccessExpired {
+    /// Unix timestamp, in seconds, at which the access expired
+    pub expired_at: u64,
+}
+
 /// Orphan [`Message`].
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Orphan {
@@ -324,10 +1162,42 @@ pub struct Orphan {
     pub cursor: usize,
 }
 
+/// Marker of a [`Message`] received before the message it is linked to, yielded by
+/// [`Messages`](crate::Messages) in [relaxed ordering](crate::Messages::relaxed_ordering). The
+/// message cannot be read yet: it is yielded once the message it is linked to has been read.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OutOfOrder {
+    /// [`Address`] of the message it is linked to, not read yet
+    pub awaiting: Address,
+}
+
+/// [`Message`] received again after being processed. It is not processed again: its publisher
+/// cursor is left untouched and its payloads are not returned.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DuplicateReceived {
+    /// Digest identifying the message among the messages processed by the user
+    pub digest: [u8; SEEN_DIGEST_SIZE],
+}
+
+/// Read-only [`Message`] decoded from a legacy (v1) channel.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Legacy {
+    /// The message type in the legacy protocol
+    pub message_type: u8,
+    /// The [`Identifier`] of the publisher
+    pub publisher_identifier: Identifier,
+    /// A payload that was encrypted
+    pub masked_payload: Vec<u8>,
+    /// A payload that was not encrypted
+    pub public_payload: Vec<u8>,
+}
+
 impl From<announcement::Unwrap> for MessageContent {
     fn from(announce: announcement::Unwrap) -> Self {
+        let (author_identifier, quorum) = announce.into_parts();
         Self::Announcement(Announcement {
-            author_identifier: announce.into_author_id(),
+            author_identifier,
+            quorum,
         })
     }
 }
@@ -340,10 +1210,108 @@ impl<'a> From<branch_announcement::Unwrap<'a>> for MessageContent {
     }
 }
 
+impl<'a> From<branch_closure::Unwrap<'a>> for MessageContent {
+    fn from(branch_closure: branch_closure::Unwrap<'a>) -> Self {
+        let (topic, publisher_identifier) = branch_closure.into_parts();
+        Self::BranchClosed(BranchClosed {
+            topic,
+            publisher_identifier,
+        })
+    }
+}
+
+impl<'a> From<read_marker::Unwrap<'a>> for MessageContent {
+    fn from(read_marker: read_marker::Unwrap<'a>) -> Self {
+        let (publisher_identifier, marked_address) = read_marker.into_parts();
+        Self::ReadMarker(ReadMarker {
+            publisher_identifier,
+            marked_address,
+        })
+    }
+}
+
+impl<'a> From<author_rotation::Unwrap<'a>> for MessageContent {
+    fn from(rotation: author_rotation::Unwrap<'a>) -> Self {
+        let (previous_author, new_author, approvals) = rotation.into_parts();
+        Self::AuthorRotation(AuthorRotation {
+            previous_author,
+            new_author,
+            approvals,
+        })
+    }
+}
+
+impl<'a> From<countersignature::Unwrap<'a>> for MessageContent {
+    fn from(countersignature: countersignature::Unwrap<'a>) -> Self {
+        let (witness, countersigned) = countersignature.into_parts();
+        Self::Countersignature(Countersignature { witness, countersigned })
+    }
+}
+
+impl<'a> From<commitment::Unwrap<'a>> for MessageContent {
+    fn from(commitment: commitment::Unwrap<'a>) -> Self {
+        let (publisher_identifier, digest) = commitment.into_parts();
+        Self::Commitment(Commitment {
+            publisher_identifier,
+            digest,
+        })
+    }
+}
+
+impl<'a> From<reveal::Unwrap<'a>> for MessageContent {
+    fn from(reveal: reveal::Unwrap<'a>) -> Self {
+        let (publisher_identifier, commitment, payload) = reveal.into_parts();
+        Self::Reveal(Reveal {
+            publisher_identifier,
+            commitment,
+            payload,
+        })
+    }
+}
+
+impl<'a> From<tombstone::Unwrap<'a>> for MessageContent {
+    fn from(tombstone: tombstone::Unwrap<'a>) -> Self {
+        let (publisher_identifier, redacted, reason) = tombstone.into_parts();
+        Self::Tombstone(Tombstone {
+            publisher_identifier,
+            redacted,
+            reason,
+        })
+    }
+}
+
+impl<'a, C> From<custom_message::Unwrap<'a, C>> for MessageContent
+where
+    C: CustomContent,
+{
+    fn from(custom: custom_message::Unwrap<'a, C>) -> Self {
+        let (message_type, publisher_identifier, content) = custom.into_parts();
+        Self::Custom(CustomMessage {
+            message_type,
+            publisher_identifier,
+            content: Arc::new(content),
+        })
+    }
+}
+
+impl<'a> From<substream_announcement::Unwrap<'a>> for MessageContent {
+    fn from(substream_announcement: substream_announcement::Unwrap<'a>) -> Self {
+        let (publisher_identifier, address, topic, announcement_hash) = substream_announcement.into_parts();
+        Self::SubstreamAnnounced(SubstreamAnnounced {
+            publisher_identifier,
+            address,
+            topic,
+            announcement_hash,
+        })
+    }
+}
+
 impl<'a> From<subscription::Unwrap<'a>> for MessageContent {
     fn from(subscription: subscription::Unwrap<'a>) -> Self {
         Self::Subscription(Subscription {
             subscriber_identifier: subscription.into_subscriber_identifier(),
+            status: SubscriptionStatus::Accepted,
+            invite_topic: None,
         })
     }
 }
@@ -353,6 +1321,9 @@ impl<'a> From<keyload::Unwrap<'a>> for MessageContent {
         Self::Keyload(Keyload {
             psks: keyload.psks,
             subscribers: keyload.subscribers,
+            forward_secrecy: keyload.forward_secrecy,
+            metadata: keyload.metadata,
+            expires_at: keyload.expires_at,
         })
     }
 }
@@ -361,10 +1332,14 @@ impl<'a> From<signed_packet::Unwrap<'a>> for MessageContent {
     fn from(mut signed_packet: signed_packet::Unwrap<'a>) -> Self {
         let masked_payload = signed_packet.take_masked_payload();
         let public_payload = signed_packet.take_public_payload();
+        let detached_signature = signed_packet.take_detached_signature().map(DetachedSignature::new);
+        let references = signed_packet.take_references();
         Self::SignedPacket(SignedPacket {
             publisher_identifier: signed_packet.into_publisher_identifier(),
             masked_payload,
             public_payload,
+            detached_signature,
+            references,
         })
     }
 }
@@ -378,6 +1353,49 @@ impl<'a> From<tagged_packet::Unwrap<'a>> for MessageContent {
     }
 }
 
+impl<'a> From<selective_packet::Unwrap<'a>> for MessageContent {
+    fn from(mut selective_packet: selective_packet::Unwrap<'a>) -> Self {
+        let fields = selective_packet.take_fields();
+        Self::SelectivePacket(SelectivePacket {
+            publisher_identifier: selective_packet.into_publisher_identifier(),
+            fields,
+        })
+    }
+}
+
+impl<'a> From<batch_packet::Unwrap<'a>> for MessageContent {
+    fn from(batch_packet: batch_packet::Unwrap<'a>) -> Self {
+        let (publisher_identifier, records) = batch_packet.into_parts();
+        Self::BatchPacket(BatchPacket {
+            publisher_identifier,
+            records,
+        })
+    }
+}
+
+impl From<legacy::Announce> for MessageContent {
+    fn from(announce: legacy::Announce) -> Self {
+        Self::Legacy(Legacy {
+            message_type: legacy::ANNOUNCE,
+            publisher_identifier: announce.into_author_id(),
+            masked_payload: Vec::new(),
+            public_payload: Vec::new(),
+        })
+    }
+}
+
+impl<'a> From<legacy::SignedPacket<'a>> for MessageContent {
+    fn from(signed_packet: legacy::SignedPacket<'a>) -> Self {
+        let (publisher_identifier, public_payload, masked_payload) = signed_packet.into_parts();
+        Self::Legacy(Legacy {
+            message_type: legacy::SIGNED_PACKET,
+            publisher_identifier,
+            masked_payload,
+            public_payload,
+        })
+    }
+}
+
 impl<'a> From<unsubscription::Unwrap<'a>> for MessageContent {
     fn from(unsubscription: unsubscription::Unwrap<'a>) -> Self {
         Self::Unsubscription(Unsubscription {
@@ -385,3 +1403,14 @@ impl<'a> From<unsubscription::Unwrap<'a>> for MessageContent {
         })
     }
 }
+
+impl<'a> From<key_update::Unwrap<'a>> for MessageContent {
+    fn from(key_update: key_update::Unwrap<'a>) -> Self {
+        let (identifier, exchange_key) = key_update.into_parts();
+        Self::KeyUpdate(KeyUpdate {
+            identifier,
+            generation: exchange_key.generation,
+            exchange_key: exchange_key.public_key,
+        })
+    }
+}
@@ -214,26 +214,22 @@ mod message_builder_tests {
             received_private_msg.as_signed_packet().unwrap().masked_payload,
             priv_payload.as_bytes()
         );
-        assert!(
-            received_private_msg
-                .as_signed_packet()
-                .unwrap()
-                .public_payload
-                .is_empty()
-        );
+        assert!(received_private_msg
+            .as_signed_packet()
+            .unwrap()
+            .public_payload
+            .is_empty());
 
         assert!(received_public_msg.is_signed_packet());
         assert_eq!(
             received_public_msg.as_signed_packet().unwrap().public_payload,
             pub_payload.as_bytes()
         );
-        assert!(
-            received_public_msg
-                .as_signed_packet()
-                .unwrap()
-                .masked_payload
-                .is_empty()
-        );
+        assert!(received_public_msg
+            .as_signed_packet()
+            .unwrap()
+            .masked_payload
+            .is_empty());
     }
 
     #[tokio::test]
@@ -264,25 +260,21 @@ mod message_builder_tests {
             received_private_msg.as_tagged_packet().unwrap().masked_payload,
             priv_payload.as_bytes()
         );
-        assert!(
-            received_private_msg
-                .as_tagged_packet()
-                .unwrap()
-                .public_payload
-                .is_empty()
-        );
+        assert!(received_private_msg
+            .as_tagged_packet()
+            .unwrap()
+            .public_payload
+            .is_empty());
 
         assert!(received_public_msg.is_tagged_packet());
         assert_eq!(
             received_public_msg.as_tagged_packet().unwrap().public_payload,
             pub_payload.as_bytes()
         );
-        assert!(
-            received_public_msg
-                .as_tagged_packet()
-                .unwrap()
-                .masked_payload
-                .is_empty()
-        );
+        assert!(received_public_msg
+            .as_tagged_packet()
+            .unwrap()
+            .masked_payload
+            .is_empty());
     }
 }
@@ -0,0 +1,159 @@
+//! Screening of the messages before their content is unwrapped
+//!
+//! Anyone can post to the index of a stream message. A [`User`](crate::User) built with
+//! [`UserBuilder::with_message_filter()`](crate::UserBuilder::with_message_filter) hands every
+//! message it processes to its [`MessageFilter`], with the raw [`TransportMessage`] and its decoded
+//! header, before unwrapping its content. Dropped messages are not processed, and messages
+//! sharing their address with dropped ones are still received.
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    id::Identifier,
+    message::{TransportMessage, HDF},
+    sync::{MaybeSend, MaybeSync},
+};
+
+// Local
+
+/// Decision of a [`MessageFilter`] on a message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FilterVerdict {
+    /// The message is unwrapped and processed
+    Accept,
+    /// The message is dropped, for the provided reason
+    Drop(&'static str),
+}
+
+/// Hook screening the messages processed by a [`User`](crate::User) before their content is
+/// unwrapped. Screening runs before any signature is verified, so it should only rely on checks
+/// cheaper than unwrapping the message, like size caps or magic bytes.
+pub trait MessageFilter: MaybeSend + MaybeSync {
+    /// Returns whether a message is processed or dropped
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `msg`: The raw [`TransportMessage`]
+    /// * `header`: The decoded header of the message, `None` for legacy (v1) messages
+    fn screen(&self, address: Address, msg: &TransportMessage, header: Option<&HDF>) -> FilterVerdict;
+}
+
+impl<F> MessageFilter for F
+where
+    F: Fn(Address, &TransportMessage, Option<&HDF>) -> FilterVerdict + MaybeSend + MaybeSync,
+{
+    fn screen(&self, address: Address, msg: &TransportMessage, header: Option<&HDF>) -> FilterVerdict {
+        self(address, msg, header)
+    }
+}
+
+/// [`MessageFilter`] dropping oversized messages and the messages of unknown publishers
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpamFilter {
+    /// The largest size of the accepted messages, in bytes. Unbounded if None.
+    max_size: Option<usize>,
+    /// The publishers whose messages are accepted. Every publisher is accepted if None.
+    publishers: Option<Vec<Identifier>>,
+}
+
+impl SpamFilter {
+    /// Creates a new [`SpamFilter`] accepting every message
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops the messages larger than the provided size
+    ///
+    /// # Arguments
+    /// * `max_size`: The largest size of the accepted messages, in bytes
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Drops the messages of the publishers that are not listed. Legacy (v1) messages, whose
+    /// publisher is not decoded before unwrapping, are dropped as well.
+    ///
+    /// # Arguments
+    /// * `publishers`: The [identifiers](`Identifier`) of the publishers whose messages are
+    ///   accepted
+    pub fn with_allowed_publishers<P>(mut self, publishers: P) -> Self
+    where
+        P: IntoIterator<Item = Identifier>,
+    {
+        self.publishers = Some(publishers.into_iter().collect());
+        self
+    }
+}
+
+impl MessageFilter for SpamFilter {
+    fn screen(&self, _address: Address, msg: &TransportMessage, header: Option<&HDF>) -> FilterVerdict {
+        if self.max_size.map_or(false, |max_size| msg.as_ref().len() > max_size) {
+            return FilterVerdict::Drop("message is too large");
+        }
+        match (&self.publishers, header) {
+            (Some(publishers), Some(header)) if !publishers.contains(header.publisher()) => {
+                FilterVerdict::Drop("publisher is not allowed")
+            }
+            (Some(_), None) => FilterVerdict::Drop("publisher of legacy messages cannot be checked"),
+            _ => FilterVerdict::Accept,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::{
+        address::Address,
+        message::{TransportMessage, HDF},
+        transport::Transport as _,
+    };
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Error, Result,
+    };
+
+    use super::{FilterVerdict, SpamFilter};
+
+    #[tokio::test]
+    async fn spam_at_the_address_of_a_message_is_filtered_out() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        let packet = author.send_signed_packet("BASE_BRANCH", b"public", b"").await?;
+        // Garbage posted at the address of the packet hides it from users without a filter
+        transport
+            .borrow_mut()
+            .send_message(packet.address(), TransportMessage::new(vec![0; 2048]))
+            .await?;
+        assert!(matches!(
+            subscriber.receive_message(packet.address()).await,
+            Err(Error::Transport(..))
+        ));
+
+        subscriber.set_message_filter(SpamFilter::new().with_max_size(1024));
+        let message = subscriber.receive_message(packet.address()).await?;
+        assert_eq!(message.as_signed_packet().unwrap().public_payload, b"public");
+
+        // Messages handed over directly are screened as well
+        subscriber
+            .set_message_filter(|_: Address, _: &TransportMessage, _: Option<&HDF>| FilterVerdict::Drop("closed"));
+        let msg = transport.borrow_mut().recv_messages(packet.address()).await?.remove(0);
+        assert!(matches!(
+            subscriber.handle_message(packet.address(), msg).await,
+            Err(Error::MessageFiltered(_, "closed"))
+        ));
+        Ok(())
+    }
+}
@@ -16,17 +16,22 @@ use hashbrown::HashMap;
 
 // Streams
 use lets::{
-    address::{Address, MsgId},
+    address::{Address, AppAddr, MsgId},
+    error::Error as LetsError,
     id::{Identifier, Permissioned},
     message::{Topic, TransportMessage, HDF},
+    sync::MaybeSend,
     transport::Transport,
 };
 
 // Local
-use crate::api::{
-    message::{Message, MessageContent, Orphan},
-    selector::Selector,
-    user::User,
+use crate::{
+    api::{
+        message::{Message, MessageContent, Orphan},
+        selector::Selector,
+        user::User,
+    },
+    Error,
 };
 
 /// a [`Stream`] over the messages of the channel pending to be fetch from the transport
@@ -112,7 +117,15 @@ use crate::api::{
 /// means any parent message is yielded before its childs. As a consequence, there might be multiple
 /// transport calls before a message is yielded, and several messages can be accumulated in memory
 /// until their turn. Therefore, some jitter might be expected, with a worst case of fetching all
-/// the messages before any is yielded.
+/// the messages before any is yielded. The amount of messages accumulated this way can be bounded
+/// with an [`OrphanLimit`], see [`UserBuilder::with_orphan_limit()`](crate::UserBuilder). With
+/// [`Messages::relaxed_ordering()`], the messages held back are announced by an explicit marker.
+///
+/// By default the cursor of each publisher is advanced one message per transport call, a publisher
+/// that sent many messages since the last traversal taking as many rounds. With a lookahead window
+/// (see [`UserBuilder::with_sync_lookahead()`](crate::UserBuilder)), the next messages of a
+/// publisher are fetched concurrently, in batches growing while the publisher has messages, and
+/// every message found until the first missing one is processed before the next publisher.
 ///
 /// After the last currently available message has been returned, [`Messages::next()`] returns
 /// `None`, at which point the [`StreamExt`] and [`TryStreamExt`] methods will consider the
@@ -125,44 +138,257 @@ use crate::api::{
 /// suggested that, when suitable, use the methods in [`futures::TryStreamExt`] to make the
 /// error-handling much more ergonomic (with the use of `?`) and shortcircuit the
 /// [`futures::Stream`] on the first error.
-pub struct Messages<'a, T>(PinBoxFut<'a, (MessagesState<'a, T>, Option<Result<Message>>)>);
+pub struct Messages<'a, T> {
+    /// Traversal fetching the next message
+    future: PinBoxFut<'a, (MessagesState<'a, T>, Option<Result<Message>>)>,
+    /// Prefix of the topics whose messages are yielded, if any
+    topic_filter: Option<Topic>,
+    /// Whether the [`MessageContent::OutOfOrder`] markers are yielded
+    relaxed: bool,
+}
 
+#[cfg(not(feature = "threadsafe"))]
 type PinBoxFut<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+#[cfg(feature = "threadsafe")]
+type PinBoxFut<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
 struct MessagesState<'a, T> {
     user: &'a mut User<T>,
     ids_stack: Vec<(Topic, Permissioned<Identifier>, usize)>,
     msg_queue: HashMap<MsgId, VecDeque<(MsgId, TransportMessage)>>,
+    /// Messages waiting to be handled. Not bounded by `orphan_limit`: the stage is only filled
+    /// with fetched messages once empty, with at most one run of a publisher ([`MAX_SYNC_RUN`]
+    /// messages), and otherwise only receives the orphans released from `msg_queue`, which
+    /// counted towards the limit while buffered
     stage: VecDeque<(MsgId, TransportMessage)>,
     successful_round: bool,
+    /// Maximum number of orphan messages buffered in `msg_queue`, if any
+    orphan_limit: Option<OrphanLimit>,
+    /// Linked addresses of the buffered orphan messages, in the order they were buffered
+    orphan_order: VecDeque<MsgId>,
+    /// Number of future cursors of a publisher probed at once. One walks the cursors serially.
+    lookahead: usize,
+}
+
+/// Traversal of a [`MessagesState`] detached from its [`User`], so that the user can be released
+/// between two messages and the traversal resumed where it stopped
+#[derive(Default)]
+pub(crate) struct DetachedMessages {
+    ids_stack: Vec<(Topic, Permissioned<Identifier>, usize)>,
+    msg_queue: HashMap<MsgId, VecDeque<(MsgId, TransportMessage)>>,
+    stage: VecDeque<(MsgId, TransportMessage)>,
+    successful_round: bool,
+    orphan_order: VecDeque<MsgId>,
+}
+
+#[cfg(feature = "auto-sync")]
+impl DetachedMessages {
+    /// Fetches the next message of the traversal, resuming it on a [`User`]. The
+    /// [`MessageContent::OutOfOrder`] markers are yielded as well.
+    ///
+    /// # Arguments
+    /// * `user`: The [`User`] the traversal runs on
+    pub(crate) async fn next<T>(&mut self, user: &mut User<T>) -> Option<Result<Message>>
+    where
+        T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
+    {
+        let mut state = MessagesState::attach(user, core::mem::take(self));
+        let next = state.next().await;
+        *self = state.detach();
+        next
+    }
+}
+
+/// Growth bound of the batches of cursors probed while a publisher has messages, as a multiple of
+/// the lookahead window
+const MAX_BATCH_GROWTH: usize = 16;
+/// Messages of a publisher fetched in a single run before the other publishers get their turn
+const MAX_SYNC_RUN: usize = 1024;
+
+/// Policy applied when a new orphan message arrives and the buffer has reached its [`OrphanLimit`]
+///
+/// The cursor of the publisher of an orphan moves past it when it is received, so discarded orphans
+/// are not fetched again by [`Messages`]. In [`Messages::relaxed_ordering()`], the
+/// [`MessageContent::OutOfOrder`] marker of an orphan buffered then dropped holds its address, so
+/// that it can be read with [`User::receive_message()`] once the message it is linked to is
+/// processed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OrphanEviction {
+    /// Discard the oldest buffered orphan to make room for the new one
+    DropOldest,
+    /// Discard the new orphan and yield an [`Error::OrphanLimitExceeded`] wrapped in the stream
+    /// error. The error holds the address of the orphan and of the message it is linked to, so
+    /// that the orphan can be read with [`User::receive_message()`] once that message is processed.
+    Reject,
+}
+
+/// Upper bound on the number of orphan messages (messages whose parent has not been processed yet)
+/// that a [`Messages`] stream keeps in memory. The other messages held by the stream are the ones
+/// fetched in the last transport round, at most one run of a publisher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OrphanLimit {
+    /// Maximum number of buffered orphans
+    max: usize,
+    /// Policy applied when the buffer is full
+    eviction: OrphanEviction,
+}
+
+impl OrphanLimit {
+    /// Creates a new [`OrphanLimit`]
+    ///
+    /// # Arguments
+    /// * `max`: Maximum number of orphan messages buffered at any time
+    /// * `eviction`: The [`OrphanEviction`] policy applied when the buffer is full
+    pub fn new(max: usize, eviction: OrphanEviction) -> Self {
+        Self { max, eviction }
+    }
+
+    /// Returns the maximum number of buffered orphan messages
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns the [`OrphanEviction`] policy
+    pub fn eviction(&self) -> OrphanEviction {
+        self.eviction
+    }
 }
 
 impl<'a, T> MessagesState<'a, T> {
     fn new(user: &'a mut User<T>) -> Self {
+        Self::attach(user, DetachedMessages::default())
+    }
+
+    /// Resumes a detached traversal on a [`User`]
+    fn attach(user: &'a mut User<T>, detached: DetachedMessages) -> Self {
+        let orphan_limit = user.orphan_limit();
+        let lookahead = user.sync_lookahead();
         Self {
             user,
-            ids_stack: Vec::new(),
-            msg_queue: HashMap::new(),
-            stage: VecDeque::new(),
-            successful_round: false,
+            ids_stack: detached.ids_stack,
+            msg_queue: detached.msg_queue,
+            stage: detached.stage,
+            successful_round: detached.successful_round,
+            orphan_limit,
+            orphan_order: detached.orphan_order,
+            lookahead,
+        }
+    }
+
+    /// Detaches the traversal from its [`User`]
+    #[cfg(feature = "auto-sync")]
+    fn detach(self) -> DetachedMessages {
+        DetachedMessages {
+            ids_stack: self.ids_stack,
+            msg_queue: self.msg_queue,
+            stage: self.stage,
+            successful_round: self.successful_round,
+            orphan_order: self.orphan_order,
+        }
+    }
+
+    /// Buffers an orphan message until the message it is linked to is processed
+    fn buffer_orphan(&mut self, linked_msg_address: MsgId, relative_address: MsgId, msg: TransportMessage) {
+        self.msg_queue
+            .entry(linked_msg_address)
+            .or_default()
+            .push_back((relative_address, msg));
+        self.orphan_order.push_back(linked_msg_address);
+    }
+
+    /// Discards the oldest buffered orphan message. Returns false if there was none.
+    fn evict_oldest_orphan(&mut self) -> bool {
+        match self.orphan_order.pop_front() {
+            Some(linked_msg_address) => {
+                if let Some(queue) = self.msg_queue.get_mut(&linked_msg_address) {
+                    queue.pop_front();
+                    if queue.is_empty() {
+                        self.msg_queue.remove(&linked_msg_address);
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the orphan messages linked to the provided address from the buffer
+    fn release_orphans(&mut self, linked_msg_address: MsgId) -> Option<VecDeque<(MsgId, TransportMessage)>> {
+        let msgs = self.msg_queue.remove(&linked_msg_address)?;
+        self.orphan_order.retain(|address| *address != linked_msg_address);
+        Some(msgs)
+    }
+
+    /// Fetches the run of consecutive messages of a publisher following its cursor. The cursors are
+    /// probed in concurrent batches, starting with the lookahead window and doubling while every
+    /// probed cursor has a message. The run ends at the first missing message, the messages found
+    /// after it being fetched again once it is available. Returns the run, and whether it reached
+    /// its end rather than [`MAX_SYNC_RUN`] or a transport failure. Errors if the transport failed
+    /// before any message of the run was fetched, a failure after them being reported once the run
+    /// is resumed from the failed message.
+    ///
+    /// # Arguments
+    /// * `base_address`: The [`AppAddr`] of the stream
+    /// * `topic`: The [`Topic`] of the branch of the cursor
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `cursor`: The cursor of the publisher in the branch
+    async fn fetch_run(
+        &mut self,
+        base_address: AppAddr,
+        topic: &Topic,
+        publisher: &Identifier,
+        cursor: usize,
+    ) -> crate::Result<(Vec<(MsgId, TransportMessage)>, bool)>
+    where
+        T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
+    {
+        let mut run = Vec::new();
+        let mut batch = self.lookahead;
+        loop {
+            let next = cursor + run.len() + 1;
+            let addresses: Vec<Address> = (next..next + batch.min(MAX_SYNC_RUN - run.len()))
+                .map(|seq_num| {
+                    let rel_address = self
+                        .user
+                        .link_generator()
+                        .gen_msg_id(base_address, publisher, topic, seq_num);
+                    Address::new(base_address, rel_address)
+                })
+                .collect();
+            let received = self.user.recv_screened_messages(addresses.clone()).await;
+            for (address, msg) in addresses.into_iter().zip(received) {
+                match msg {
+                    Ok(msg) => run.push((address.relative(), msg)),
+                    Err(e) if is_transport_failure(&e) => match run.is_empty() {
+                        true => return Err(e),
+                        false => return Ok((run, false)),
+                    },
+                    Err(_) => return Ok((run, true)),
+                }
+            }
+            if run.len() >= MAX_SYNC_RUN {
+                return Ok((run, false));
+            }
+            batch = (batch * 2).min(self.lookahead * MAX_BATCH_GROWTH);
         }
     }
 
     /// Fetch the next message of the channel
     ///
     /// See [`Messages`] documentation and examples for more details.
-    #[async_recursion(?Send)]
+    #[cfg_attr(not(feature = "threadsafe"), async_recursion(?Send))]
+    #[cfg_attr(feature = "threadsafe", async_recursion)]
     async fn next(&mut self) -> Option<Result<Message>>
     where
-        T: for<'b> Transport<'b, Msg = TransportMessage>,
+        T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
     {
         if let Some((relative_address, binary_msg)) = self.stage.pop_front() {
             // Drain stage if not empty...
             let address = Address::new(self.user.stream_address()?.base(), relative_address);
-            match self.user.handle_message(address, binary_msg).await {
+            match self.user.handle_fetched_message(address, binary_msg).await {
                 Ok(Message {
                     header:
-                        HDF {
+                        header @ HDF {
                             linked_msg_address: Some(linked_msg_address),
                             ..
                         },
@@ -181,19 +407,42 @@ impl<'a, T> MessagesState<'a, T> {
                     // is already present in the state, but we don't want to couple this iterator to
                     // a memory-intensive storage. Instead, we take the optimistic approach and store
                     // the msg for later if the handling has failed.
-                    self.msg_queue
-                        .entry(linked_msg_address)
-                        .or_default()
-                        .push_back((relative_address, orphaned_msg));
+                    if let Some(limit) = self.orphan_limit {
+                        if self.orphan_order.len() >= limit.max() {
+                            match limit.eviction() {
+                                OrphanEviction::Reject => {
+                                    let awaiting = Address::new(address.base(), linked_msg_address);
+                                    let error = Error::OrphanLimitExceeded(address, awaiting);
+                                    return Some(Err(anyhow::Error::msg(error)));
+                                }
+                                OrphanEviction::DropOldest => {
+                                    if !self.evict_oldest_orphan() {
+                                        // Limit is zero, there is no room for any orphan
+                                        return self.next().await;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    self.buffer_orphan(linked_msg_address, relative_address, orphaned_msg);
 
-                    self.next().await
+                    // The marker is only yielded in relaxed ordering (see `Messages::poll_next()`)
+                    let awaiting = Address::new(address.base(), linked_msg_address);
+                    Some(Ok(Message::out_of_order(address, header, awaiting)))
                 }
+                // Messages received again were already yielded
+                Ok(message) if message.is_duplicate() => self.next().await,
                 Ok(message) => {
                     // Check if message has descendants pending to process and stage them for processing
-                    if let Some(msgs) = self.msg_queue.remove(&message.address().relative()) {
+                    if let Some(msgs) = self.release_orphans(message.address().relative()) {
                         self.stage.extend(msgs);
                     }
 
+                    // Messages read on the device the cursors were imported from only rebuild the state
+                    if self.user.read_before_import(&message) {
+                        return self.next().await;
+                    }
+
                     Some(Ok(message))
                 }
                 // message-Handling errors are a normal execution path, just skip them
@@ -216,43 +465,103 @@ impl<'a, T> MessagesState<'a, T> {
                 }
             };
             let base_address = self.user.stream_address()?.base();
-            let rel_address = MsgId::gen(base_address, publisher.identifier(), &topic, cursor + 1);
-            let address = Address::new(base_address, rel_address);
-
-            match self.user.transport_mut().recv_message(address).await {
-                Ok(msg) => {
-                    self.stage.push_back((address.relative(), msg));
-                    self.successful_round = true;
-                    self.next().await
+            let found = if self.lookahead > 1 {
+                let (run, ended) = match self
+                    .fetch_run(base_address, &topic, publisher.identifier(), cursor)
+                    .await
+                {
+                    Ok(run) => run,
+                    Err(e) => return Some(Err(anyhow::Error::msg(e))),
+                };
+                let found = !run.is_empty();
+                if !ended {
+                    // The rest of the run is fetched once the other publishers had their turn, and
+                    // a transport failure ending the run is reported then
+                    self.ids_stack.insert(0, (topic, publisher, cursor + run.len()));
                 }
-                Err(_e) => {
-                    // Message not found or network error. Right now we are not distinguishing
-                    // between each case, so we must assume it's message not found.
-                    // When we introduce typed error handling and are able to distinguish,
-                    // Return Err(e) if error is network-related or any other transient error
-                    if self.ids_stack.is_empty() && !self.successful_round {
-                        // After trying all ids, none has produced an existing link, end of stream (for now...)
-                        None
-                    } else {
-                        // At least one id is producing existing links. continue...
-                        self.next().await
+                self.stage.extend(run);
+                found
+            } else {
+                let rel_address =
+                    self.user
+                        .link_generator()
+                        .gen_msg_id(base_address, publisher.identifier(), &topic, cursor + 1);
+                let address = Address::new(base_address, rel_address);
+                match self.user.recv_screened_message(address).await {
+                    Ok(msg) => {
+                        self.stage.push_back((address.relative(), msg));
+                        true
                     }
+                    // The cursor is tried again in the next round
+                    Err(e) if is_transport_failure(&e) => return Some(Err(anyhow::Error::msg(e))),
+                    // No message at the address yet, or none of the messages at the address is the
+                    // stream message
+                    Err(_) => false,
                 }
+            };
+
+            if found {
+                self.successful_round = true;
+                self.next().await
+            } else if self.ids_stack.is_empty() && !self.successful_round {
+                // After trying all ids, none has produced an existing link, end of stream (for now...)
+                None
+            } else {
+                // At least one id is producing existing links. continue...
+                self.next().await
             }
         }
     }
 }
 
+/// Returns true if receiving a message failed because of the transport, rather than because the
+/// address holds no message or no stream message
+fn is_transport_failure(error: &Error) -> bool {
+    match error {
+        Error::Transport(_, _, e) => !e.is_not_found() && !matches!(e, LetsError::MultipleMessagesFound(..)),
+        _ => false,
+    }
+}
+
 impl<'a, T> Messages<'a, T>
 where
-    T: for<'b> Transport<'b, Msg = TransportMessage>,
+    T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
 {
     pub(crate) fn new(user: &'a mut User<T>) -> Self {
         let mut state = MessagesState::new(user);
-        Self(Box::pin(async move {
-            let r = state.next().await;
-            (state, r)
-        }))
+        Self {
+            future: Box::pin(async move {
+                let r = state.next().await;
+                (state, r)
+            }),
+            topic_filter: None,
+            relaxed: false,
+        }
+    }
+
+    /// Only yield the messages of the branches whose [`Topic`] falls under a hierarchical prefix,
+    /// such as `factory/line1` for `factory/line1/temp` (see [`Topic::has_prefix()`]).
+    ///
+    /// The messages of the other branches are still processed, keeping the state of the [`User`]
+    /// up to date, but they are not yielded. Messages of unknown branches are discarded as well.
+    ///
+    /// # Arguments
+    /// * `prefix`: The [`Topic`] prefix of the branches to yield the messages of
+    pub fn filter_topic_prefix(mut self, prefix: impl Into<Topic>) -> Self {
+        self.topic_filter = Some(prefix.into());
+        self
+    }
+
+    /// Yield a [`MessageContent::OutOfOrder`] marker as soon as a message is received before the
+    /// message it is linked to, instead of silently holding it back. The message itself is still
+    /// yielded once readable, after the message it is linked to, as it cannot be decoded before.
+    ///
+    /// Suited to feeds where showing that a message is on its way matters more than a strict
+    /// order of the yielded items. A message evicted from the orphan buffer (see [`OrphanLimit`])
+    /// is not yielded afterwards, its marker being the only trace of it.
+    pub fn relaxed_ordering(mut self) -> Self {
+        self.relaxed = true;
+        self
     }
 
     /// "Filter the stream of messages to only those that match the selectors, and return the result
@@ -329,7 +638,7 @@ where
 
 impl<'a, T> From<&'a mut User<T>> for Messages<'a, T>
 where
-    T: for<'b> Transport<'b, Msg = TransportMessage>,
+    T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
 {
     fn from(user: &'a mut User<T>) -> Self {
         Self::new(user)
@@ -338,43 +647,68 @@ where
 
 impl<'a, T> Stream for Messages<'a, T>
 where
-    T: for<'b> Transport<'b, Msg = TransportMessage>,
+    T: for<'b> Transport<'b, Msg = TransportMessage> + MaybeSend,
 {
     type Item = Result<Message>;
 
-    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match self.0.as_mut().poll(ctx) {
-            Poll::Ready((mut state, result)) => {
-                self.set(Messages(Box::pin(async move {
-                    let r = state.next().await;
-                    (state, r)
-                })));
-                Poll::Ready(result)
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.future.as_mut().poll(ctx) {
+                Poll::Ready((mut state, result)) => {
+                    // Messages outside of the topic prefix are processed but not yielded, and the
+                    // out of order markers are only yielded in relaxed ordering
+                    let yielded = match (&this.topic_filter, &result) {
+                        (_, Some(Ok(msg))) if msg.is_out_of_order() && !this.relaxed => false,
+                        (Some(prefix), Some(Ok(msg))) => state
+                            .user
+                            .topic_by_hash(msg.topic_hash())
+                            .map_or(false, |topic| topic.has_prefix(prefix)),
+                        _ => true,
+                    };
+                    this.future = Box::pin(async move {
+                        let r = state.next().await;
+                        (state, r)
+                    });
+                    if yielded {
+                        return Poll::Ready(result);
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
             }
-            Poll::Pending => Poll::Pending,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use alloc::rc::Rc;
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
     use core::cell::RefCell;
 
-    use lets::{address::Address, id::Ed25519, transport::bucket};
+    use async_trait::async_trait;
+    use futures::TryStreamExt;
+    use lets::{
+        address::Address,
+        error::{Error as LetsError, Result as LetsResult},
+        message::TransportMessage,
+        transport::Transport as _,
+    };
 
     use crate::{
         api::{
+            fixtures::{
+                author_subscriber_fixture, new_reader, new_user, new_user_builder, IntermittentTransport, Transport,
+            },
             message::{
                 Message,
-                MessageContent::{BranchAnnouncement, Keyload, SignedPacket},
+                MessageContent::{BranchAnnouncement, Keyload, OutOfOrder, SignedPacket},
             },
             user::User,
         },
-        Result,
+        Error, Result,
     };
 
-    type Transport = Rc<RefCell<bucket::Client>>;
+    use super::{MessagesState, OrphanEviction, OrphanLimit};
 
     #[tokio::test]
     async fn messages_awake_pending_messages_link_to_them_even_if_their_content_is_unreadable() -> Result<()> {
@@ -435,33 +769,307 @@ mod tests {
         Ok(())
     }
 
-    /// Prepare a simple scenario with an author, a subscriber, a channel announcement and a bucket
-    /// transport
-    async fn author_subscriber_fixture() -> Result<(User<Transport>, User<Transport>, Address, Transport)> {
-        let transport = Rc::new(RefCell::new(bucket::Client::new()));
-        let mut author = User::builder()
-            .with_identity(Ed25519::from_seed("author"))
-            .with_transport(transport.clone())
-            .build();
-        let announcement = author.create_stream("BASE_BRANCH").await?;
-        let subscriber =
-            subscriber_fixture("subscriber", &mut author, announcement.address(), transport.clone()).await?;
-        Ok((author, subscriber, announcement.address(), transport))
+    #[tokio::test]
+    async fn relaxed_ordering_marks_messages_received_before_their_predecessor() -> Result<()> {
+        let p = b"payload";
+        let (mut author, mut subscriber1, announcement_link, transport) = author_subscriber_fixture().await?;
+        let mut strict_reader =
+            subscriber_fixture("strict reader", &mut author, announcement_link, transport.clone()).await?;
+        let mut relaxed_reader =
+            subscriber_fixture("relaxed reader", &mut author, announcement_link, transport).await?;
+
+        // Whichever publisher the readers fetch first, one of the messages is received before the
+        // message of the other publisher it is linked to
+        let keyload = author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber1.sync().await?;
+        let packet_1 = subscriber1.send_signed_packet("BASE_BRANCH", &p, &p).await?;
+        let packet_2 = subscriber1.send_signed_packet("BASE_BRANCH", &p, &p).await?;
+        author.sync().await?;
+        let packet_3 = author.send_signed_packet("BASE_BRANCH", &p, &p).await?;
+        let expected = [
+            keyload.address(),
+            packet_1.address(),
+            packet_2.address(),
+            packet_3.address(),
+        ];
+
+        let msgs = strict_reader.fetch_next_messages().await?;
+        assert_eq!(msgs.iter().map(|msg| msg.address).collect::<Vec<_>>(), expected);
+
+        let msgs: Vec<Message> = relaxed_reader.messages().relaxed_ordering().try_collect().await?;
+        let (markers, read): (Vec<_>, Vec<_>) = msgs.iter().enumerate().partition(|(_, msg)| msg.is_out_of_order());
+        assert_eq!(read.iter().map(|(_, msg)| msg.address).collect::<Vec<_>>(), expected);
+        assert_eq!(markers.len(), 1);
+        let (marker_position, marker) = markers[0];
+        let awaiting = match &marker.content {
+            OutOfOrder(out_of_order) => out_of_order.awaiting,
+            _ => unreachable!(),
+        };
+        // The marker is yielded first, then the awaited message and the marked message
+        let position = |address| msgs.iter().rposition(|msg| msg.address == address);
+        assert!(position(awaiting) > Some(marker_position));
+        assert!(position(marker.address) > position(awaiting));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lookahead_reads_the_backlog_of_the_publishers_like_the_serial_walk() -> Result<()> {
+        let p = b"payload";
+        let (mut author, mut subscriber1, announcement_link, transport) = author_subscriber_fixture().await?;
+        let mut serial_reader =
+            subscriber_fixture("serial reader", &mut author, announcement_link, transport.clone()).await?;
+        let mut lookahead_reader =
+            subscriber_fixture("lookahead reader", &mut author, announcement_link, transport).await?;
+        lookahead_reader.set_sync_lookahead(4);
+
+        // More messages than a few growing batches, so that the run of the publisher spans several
+        // of them and ends in the middle of one
+        let keyload = author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber1.sync().await?;
+        let mut expected = vec![keyload.address()];
+        for _ in 0..50 {
+            expected.push(subscriber1.send_signed_packet("BASE_BRANCH", &p, &p).await?.address());
+        }
+        author.sync().await?;
+        expected.push(author.send_signed_packet("BASE_BRANCH", &p, &p).await?.address());
+
+        let msgs = serial_reader.fetch_next_messages().await?;
+        assert_eq!(msgs.iter().map(|msg| msg.address).collect::<Vec<_>>(), expected);
+        let msgs = lookahead_reader.fetch_next_messages().await?;
+        assert_eq!(msgs.iter().map(|msg| msg.address).collect::<Vec<_>>(), expected);
+
+        // The cursors were left at the end of the runs
+        let packet = subscriber1.send_signed_packet("BASE_BRANCH", &p, &p).await?;
+        let msgs = lookahead_reader.fetch_next_messages().await?;
+        assert_eq!(
+            msgs.iter().map(|msg| msg.address).collect::<Vec<_>>(),
+            [packet.address()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orphan_limit_drops_the_oldest_orphans() -> Result<()> {
+        let (mut reader, transport, hidden, orphans) =
+            orphans_fixture(OrphanLimit::new(2, OrphanEviction::DropOldest)).await?;
+        let mut state = MessagesState::new(&mut reader);
+
+        // Every packet of the chain is marked as it arrives, the first ones being dropped to make
+        // room for the last ones
+        let mut marked = Vec::new();
+        while let Some(msg) = state.next().await {
+            let msg = msg?;
+            if msg.is_out_of_order() {
+                marked.push(msg.address);
+            }
+        }
+        assert_eq!(marked, orphans);
+        let buffered: Vec<_> = state
+            .orphan_order
+            .iter()
+            .map(|linked_msg_address| state.msg_queue[linked_msg_address][0].0)
+            .collect();
+        assert_eq!(buffered, [orphans[2].relative(), orphans[3].relative()]);
+
+        // The buffered orphans are linked to the dropped ones, so none of them is read
+        transport.borrow_mut().hidden = None;
+        let msg = state.next().await.unwrap()?;
+        assert_eq!(msg.address, hidden);
+        assert!(state.next().await.is_none());
+        assert_eq!(state.orphan_order.len(), 2);
+
+        // The dropped orphans are read from the address of their marker
+        drop(state);
+        let msg = reader.receive_message(orphans[0]).await?;
+        assert!(matches!(msg.content, SignedPacket(..)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orphan_limit_rejects_the_newest_orphans() -> Result<()> {
+        let (mut reader, transport, hidden, orphans) =
+            orphans_fixture(OrphanLimit::new(2, OrphanEviction::Reject)).await?;
+        let mut state = MessagesState::new(&mut reader);
+
+        // The first packets of the chain fill the buffer and the next ones are rejected
+        let mut marked = Vec::new();
+        let mut rejected = Vec::new();
+        while let Some(msg) = state.next().await {
+            match msg {
+                Ok(msg) if msg.is_out_of_order() => marked.push(msg.address),
+                Ok(_) => {}
+                Err(e) => match e.downcast_ref::<Error>() {
+                    Some(Error::OrphanLimitExceeded(orphan, awaiting)) => rejected.push((*orphan, *awaiting)),
+                    _ => panic!("unexpected error: {}", e),
+                },
+            }
+        }
+        assert_eq!(marked, orphans[..2]);
+        assert_eq!(rejected, [(orphans[2], orphans[1]), (orphans[3], orphans[2])]);
+
+        // The buffered orphans are read once the message they are linked to is
+        transport.borrow_mut().hidden = None;
+        let mut read = Vec::new();
+        while let Some(msg) = state.next().await {
+            read.push(msg?.address);
+        }
+        assert_eq!(read, [hidden, orphans[0], orphans[1]]);
+        assert!(state.orphan_order.is_empty() && state.msg_queue.is_empty());
+
+        // The rejected orphans are read from the address in their error once their parent is
+        drop(state);
+        for (orphan, _) in rejected {
+            let msg = reader.receive_message(orphan).await?;
+            assert!(matches!(msg.content, SignedPacket(..)));
+            assert_eq!(msg.masked_payload(), Some(&b"payload"[..]));
+        }
+        Ok(())
     }
 
-    async fn subscriber_fixture(
-        seed: &str,
-        author: &mut User<Transport>,
-        announcement_link: Address,
-        transport: Transport,
-    ) -> Result<User<Transport>> {
-        let mut subscriber = User::builder()
-            .with_identity(Ed25519::from_seed(seed))
-            .with_transport(transport)
+    #[tokio::test]
+    async fn lookahead_reports_transport_failures_in_the_middle_of_a_run() -> Result<()> {
+        let p = b"payload";
+        let (mut author, mut subscriber1, announcement_link, transport) = author_subscriber_fixture().await?;
+        let reader_transport = Rc::new(RefCell::new(HidingTransport {
+            bucket: transport,
+            hidden: None,
+            failing: None,
+        }));
+        let mut reader = new_user("reader", &reader_transport);
+        reader.set_sync_lookahead(4);
+        reader.receive_message(announcement_link).await?;
+        let subscription = reader.subscribe().await?;
+        author.receive_message(subscription.address()).await?;
+
+        let keyload = author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber1.sync().await?;
+        let mut expected = vec![keyload.address()];
+        for _ in 0..3 {
+            expected.push(subscriber1.send_signed_packet("BASE_BRANCH", &p, &p).await?.address());
+        }
+        reader_transport.borrow_mut().failing = Some(expected[2]);
+
+        // The run stops at the failed message instead of ending as if it had not been published
+        let mut state = MessagesState::new(&mut reader);
+        let mut read = Vec::new();
+        let failure = loop {
+            match state.next().await {
+                Some(Ok(msg)) => read.push(msg.address),
+                Some(Err(e)) => break e,
+                None => panic!("the transport failure was not reported"),
+            }
+        };
+        assert!(matches!(failure.downcast_ref::<Error>(), Some(Error::Transport(..))));
+        assert!(!read.contains(&expected[2]) && !read.contains(&expected[3]));
+
+        // The run is resumed from the failed message once the node is reachable again
+        reader_transport.borrow_mut().failing = None;
+        while let Some(msg) = state.next().await {
+            read.push(msg?.address);
+        }
+        assert_eq!(read, expected);
+        Ok(())
+    }
+
+    /// Bucket transport hiding the message at an address, as if it had not reached the node yet,
+    /// and failing to reach the node for another address
+    struct HidingTransport {
+        bucket: Transport,
+        hidden: Option<Address>,
+        failing: Option<Address>,
+    }
+
+    #[async_trait(?Send)]
+    impl<'a> lets::transport::Transport<'a> for HidingTransport {
+        type Msg = TransportMessage;
+        type SendResponse = TransportMessage;
+
+        async fn send_message(&mut self, address: Address, msg: TransportMessage) -> LetsResult<TransportMessage>
+        where
+            'a: 'async_trait,
+        {
+            self.bucket.send_message(address, msg).await
+        }
+
+        async fn recv_messages(&mut self, address: Address) -> LetsResult<Vec<TransportMessage>>
+        where
+            'a: 'async_trait,
+        {
+            if self.hidden == Some(address) {
+                return Err(LetsError::MessageNotFound(address));
+            }
+            if self.failing == Some(address) {
+                return Err(LetsError::External(anyhow::anyhow!("node unreachable")));
+            }
+            self.bucket.recv_messages(address).await
+        }
+    }
+
+    /// Prepare a reader of a chain of packets of a subscriber, linked to a packet of the author
+    /// hidden from the reader, so that every packet of the chain is an orphan to the reader. Returns
+    /// the reader, its transport, the address of the hidden packet and the addresses of the chain.
+    async fn orphans_fixture(
+        orphan_limit: OrphanLimit,
+    ) -> Result<(
+        User<Rc<RefCell<HidingTransport>>>,
+        Rc<RefCell<HidingTransport>>,
+        Address,
+        Vec<Address>,
+    )> {
+        let p = b"payload";
+        let (mut author, mut subscriber1, announcement_link, transport) = author_subscriber_fixture().await?;
+        let reader_transport = Rc::new(RefCell::new(HidingTransport {
+            bucket: transport,
+            hidden: None,
+            failing: None,
+        }));
+        let mut reader = new_user_builder("reader", &reader_transport)
+            .with_orphan_limit(orphan_limit)
             .build();
-        subscriber.receive_message(announcement_link).await?;
-        let subscription = subscriber.subscribe().await?;
+        reader.receive_message(announcement_link).await?;
+        let subscription = reader.subscribe().await?;
         author.receive_message(subscription.address()).await?;
-        Ok(subscriber)
+
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        let hidden = author.send_signed_packet("BASE_BRANCH", &p, &p).await?.address();
+        subscriber1.sync().await?;
+        let mut orphans = Vec::new();
+        for _ in 0..4 {
+            orphans.push(subscriber1.send_signed_packet("BASE_BRANCH", &p, &p).await?.address());
+        }
+        reader_transport.borrow_mut().hidden = Some(hidden);
+        Ok((reader, reader_transport, hidden, orphans))
+    }
+
+    #[tokio::test]
+    async fn transport_failures_fail_the_messages_stream_instead_of_ending_it() -> Result<()> {
+        let transport = Rc::new(RefCell::new(IntermittentTransport::default()));
+        let mut author = new_user("author", &transport);
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let mut reader = new_reader(&transport);
+        reader.receive_message(announcement.address()).await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"").await?;
+
+        transport.borrow_mut().offline = true;
+        let error = reader.messages().try_next().await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::Transport(
+                _,
+                _,
+                LetsError::AddressError("transport is offline", _)
+            ))
+        ));
+        // Nor is sending to an address that cannot be checked
+        assert!(matches!(
+            author.send_signed_packet("BASE_BRANCH", b"public", b"").await,
+            Err(Error::Transport(_, "check that the address is free", _))
+        ));
+
+        transport.borrow_mut().offline = false;
+        assert_eq!(reader.fetch_next_messages().await?.len(), 1);
+        // Missing messages end the stream
+        assert!(reader.messages().try_next().await?.is_none());
+        Ok(())
     }
 }
@@ -0,0 +1,268 @@
+//! Operational metrics of the users
+//!
+//! A [`Metrics`] registry handed to one or several [users](crate::User) with
+//! [`UserBuilder::with_metrics()`](crate::UserBuilder::with_metrics) or
+//! [`User::set_metrics()`](crate::User::set_metrics) counts the messages they send and process,
+//! the time spent syncing and sending, and the failures of their transport. The registry is
+//! rendered in the Prometheus text exposition format by [`Metrics::render()`], to be served on a
+//! scrape endpoint.
+//!
+//! Durations are measured with a monotonic clock, which requires the `std` feature and is not
+//! available on `wasm32`. Without it, only the counts are recorded.
+
+// Rust
+use alloc::string::String;
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::error::Error as LetsError;
+
+// Local
+
+/// Count and total duration of an operation, rendered as a Prometheus summary
+#[derive(Debug, Default)]
+struct Timing {
+    /// Number of operations completed
+    count: AtomicU64,
+    /// Total duration of the completed operations, in microseconds
+    micros: AtomicU64,
+}
+
+impl Timing {
+    /// Records an operation
+    ///
+    /// # Arguments
+    /// * `elapsed`: The duration of the operation, if it was measured
+    fn record(&self, elapsed: Option<Duration>) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        if let Some(elapsed) = elapsed {
+            self.micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of operations recorded
+    fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total duration of the operations recorded
+    fn total(&self) -> Duration {
+        Duration::from_micros(self.micros.load(Ordering::Relaxed))
+    }
+}
+
+/// Registry of the operational metrics of the users it is handed to. The registry can be shared by
+/// several users through an [`Arc`](alloc::sync::Arc), in which case their metrics are summed.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Messages sent through the transport, with the time spent sending them
+    sends: Timing,
+    /// Messages processed successfully
+    messages_received: AtomicU64,
+    /// Messages whose processing failed
+    messages_rejected: AtomicU64,
+    /// Syncs completed, with the time spent fetching the messages
+    syncs: Timing,
+    /// Failures of the transport
+    transport_errors: AtomicU64,
+}
+
+impl Metrics {
+    /// Creates a new [`Metrics`] registry with every metric at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of messages sent through the transport
+    pub fn messages_sent(&self) -> u64 {
+        self.sends.count()
+    }
+
+    /// Returns the total time spent sending messages through the transport. It includes the proof
+    /// of work of transports computing it while sending, like the tangle clients.
+    pub fn send_duration(&self) -> Duration {
+        self.sends.total()
+    }
+
+    /// Returns the number of messages processed successfully
+    pub fn messages_received(&self) -> u64 {
+        self.messages_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages whose processing failed, including the messages dropped by
+    /// a [`MessageFilter`](crate::MessageFilter), and the packets rejected by a
+    /// [`PayloadValidator`](crate::PayloadValidator)
+    pub fn messages_rejected(&self) -> u64 {
+        self.messages_rejected.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of syncs completed, by [`User::sync()`](crate::User::sync) or
+    /// [`User::fetch_next_messages()`](crate::User::fetch_next_messages)
+    pub fn syncs(&self) -> u64 {
+        self.syncs.count()
+    }
+
+    /// Returns the total time spent in completed syncs
+    pub fn sync_duration(&self) -> Duration {
+        self.syncs.total()
+    }
+
+    /// Returns the number of failures of the transport. Messages missing from the transport are
+    /// not counted, as the end of every sync is reached by looking up a message not yet published.
+    pub fn transport_errors(&self) -> u64 {
+        self.transport_errors.load(Ordering::Relaxed)
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut rendered = String::new();
+        let counters = [
+            (
+                "streams_messages_received_total",
+                "Messages processed successfully",
+                self.messages_received(),
+            ),
+            (
+                "streams_messages_rejected_total",
+                "Messages whose processing failed",
+                self.messages_rejected(),
+            ),
+            (
+                "streams_transport_errors_total",
+                "Failures of the transport",
+                self.transport_errors(),
+            ),
+        ];
+        let summaries = [
+            (
+                "streams_send_duration_seconds",
+                "Time spent sending messages through the transport, including proof of work",
+                &self.sends,
+            ),
+            (
+                "streams_sync_duration_seconds",
+                "Time spent syncing with the channel",
+                &self.syncs,
+            ),
+        ];
+        // Writing into a String cannot fail
+        for (name, help, value) in counters {
+            let _ = write!(
+                rendered,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}\n",
+                name = name,
+                help = help,
+                value = value
+            );
+        }
+        for (name, help, timing) in summaries {
+            let _ = write!(
+                rendered,
+                "# HELP {name} {help}\n# TYPE {name} summary\n{name}_sum {sum}\n{name}_count {count}\n",
+                name = name,
+                help = help,
+                sum = timing.total().as_secs_f64(),
+                count = timing.count()
+            );
+        }
+        rendered
+    }
+
+    /// Records a message sent through the transport
+    ///
+    /// # Arguments
+    /// * `elapsed`: The time spent sending the message, if it was measured
+    pub(crate) fn record_send(&self, elapsed: Option<Duration>) {
+        self.sends.record(elapsed);
+    }
+
+    /// Records the outcome of the processing of a message
+    ///
+    /// # Arguments
+    /// * `accepted`: Whether the message was processed successfully
+    pub(crate) fn record_message(&self, accepted: bool) {
+        let counter = match accepted {
+            true => &self.messages_received,
+            false => &self.messages_rejected,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a completed sync
+    ///
+    /// # Arguments
+    /// * `elapsed`: The time spent syncing, if it was measured
+    pub(crate) fn record_sync(&self, elapsed: Option<Duration>) {
+        self.syncs.record(elapsed);
+    }
+
+    /// Records a failure of the transport, unless the requested message was just missing or the
+    /// address held several messages
+    ///
+    /// # Arguments
+    /// * `error`: The error returned by the transport
+    pub(crate) fn record_transport_error(&self, error: &LetsError) {
+        if !error.is_not_found() && !matches!(error, LetsError::MultipleMessagesFound(..)) {
+            self.transport_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::address::{Address, MsgId};
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Result,
+    };
+
+    use super::Metrics;
+
+    #[tokio::test]
+    async fn metrics_count_the_messages_sent_and_processed() -> Result<()> {
+        let transport = new_transport();
+        let metrics = alloc::sync::Arc::new(Metrics::new());
+        let mut author = new_user_builder("author", &transport)
+            .with_metrics(metrics.clone())
+            .build();
+        let mut subscriber = new_user("subscriber", &transport);
+        subscriber.set_metrics(metrics.clone());
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        let subscription = subscriber.subscribe().await?;
+        author.receive_message(subscription.address()).await?;
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", "public", b"masked").await?;
+        author.send_tagged_packet("BASE_BRANCH", "public", b"masked").await?;
+        assert_eq!(subscriber.sync().await?, 3);
+        assert!(subscriber
+            .receive_message(Address::new(announcement.address().base(), MsgId::new([7; 12])))
+            .await
+            .is_err());
+
+        assert_eq!(metrics.messages_sent(), 5);
+        assert_eq!(metrics.messages_received(), 5);
+        assert_eq!(metrics.messages_rejected(), 0);
+        assert_eq!(metrics.syncs(), 1);
+        // Missing messages are not failures of the transport
+        assert_eq!(metrics.transport_errors(), 0);
+
+        let rendered = metrics.render();
+        assert!(
+            rendered.contains("# TYPE streams_messages_received_total counter\nstreams_messages_received_total 5\n")
+        );
+        assert!(rendered.contains("streams_send_duration_seconds_count 5\n"));
+        assert!(rendered.contains("streams_sync_duration_seconds_count 1\n"));
+        Ok(())
+    }
+}
@@ -1,16 +1,93 @@
+/// Actor owning a User Client on a dedicated task
+#[cfg(feature = "actor")]
+pub mod actor;
+/// Background User Client synchronization
+#[cfg(feature = "auto-sync")]
+pub mod auto_sync;
+/// Upfront decoding of batches of messages
+pub(crate) mod batch;
+/// Human-readable descriptions of branches
+pub mod branch_metadata;
+/// Automatic rotation of branches into epoch branches
+pub mod branch_rotation;
+/// Users of many channels sharing one transport
+pub mod channel_manager;
+/// Reading of the system clock
+pub(crate) mod clock;
+/// Transport-less message encoding and decoding
+pub mod codec;
+/// Delayed disclosure of payloads
+pub mod commitment;
+/// Portable cursors, for continuing to read a stream on another device
+pub mod cursor_export;
 /// Identifier Key storage. Used for keeping track of channel state
 mod cursor_store;
+/// Message types defined by the applications
+pub mod custom_message;
+/// Compact descriptors of channels
+pub mod descriptor;
+/// Detached signatures of packet payloads
+pub mod detached;
+/// Discovery of the streams of an author
+pub mod discovery;
+/// Fixtures shared by the tests of the API
+#[cfg(test)]
+pub(crate) mod fixtures;
+/// Invitations to subscribe to a stream
+pub mod invite;
 
 /// Unwrapped Message Types
 pub mod message;
 /// Message builder for sending payloads
 pub mod message_builder;
+/// Screening of messages before they are unwrapped
+pub mod message_filter;
 /// Message Retrieval
 pub mod messages;
+/// Operational metrics of the users
+pub mod metrics;
+/// Anchoring of branch checkpoints outside of the stream
+pub mod notarizer;
+/// Incremental decoding of large packets
+pub mod packet_reader;
+/// Structured payload encodings
+pub mod payload;
+/// Transformation of the payloads of the packets sent and read
+pub mod payload_middleware;
+/// Validation of the payloads of the packets read
+pub mod payload_validator;
+/// Message provenance audit reports
+pub mod provenance;
+/// Threshold of author keys approving the critical messages of a stream
+pub mod quorum;
+/// Forward secrecy key ratchets
+pub(crate) mod ratchet;
+/// Verifiable citations of messages
+pub mod reference;
+/// Deduplication of the payloads repeated by a publisher
+pub(crate) mod repeated_payloads;
+/// Deterministic replay of the processed messages
+pub mod replay;
+/// Addresses of the next messages of the branches of a stream
+pub mod routing_table;
+/// Async runtimes of the background tasks
+#[cfg(feature = "auto-sync")]
+pub mod runtime;
+/// Detection of the messages received again
+pub(crate) mod seen_messages;
 /// Message Retrieval Filter Selector
 pub(crate) mod selector;
 /// Message Wrapper for Sent Messages
 pub(crate) mod send_response;
+/// Shared User Client handle
+#[cfg(any(feature = "threadsafe", feature = "auto-sync"))]
+pub mod shared_user;
+/// Pluggable storage of the spongos states
+pub mod spongos_store;
+/// Inspection of the user state and of the changes between backups
+pub mod state_summary;
+/// Approval of subscription requests
+pub mod subscription_policy;
 /// User Client
 pub mod user;
 /// User Client Builder
@@ -0,0 +1,358 @@
+//! Notarization of the branches of a stream outside of the stream itself
+//!
+//! A [`User`](crate::User) built with [`UserBuilder::with_notarizer()`](crate::UserBuilder)
+//! hashes every message it publishes or reads. The hashes of the messages of a publisher on a
+//! branch are grouped in windows of `interval` sequence numbers. When a publisher sends the message
+//! closing a window, the digest of the window is anchored as a [`Checkpoint`] through its
+//! [`Notarizer`], for instance as a transaction on a ledger. Readers fetch the checkpoints of the
+//! windows they have read through their own [`Notarizer`] and compare them with the digest of the
+//! messages they received, so a message altered after its publication is detected.
+
+// Rust
+use alloc::{boxed::Box, collections::BTreeMap};
+
+// 3rd-party
+use async_trait::async_trait;
+use hashbrown::HashMap;
+
+// IOTA
+
+// Streams
+use lets::{
+    id::Identifier,
+    message::{Topic, TransportMessage},
+    sync::{MaybeSend, MaybeSync},
+};
+use spongos::{KeccakF1600, Spongos};
+
+// Local
+use crate::{Error, Result};
+
+/// Size of the message hashes and window digests
+pub const DIGEST_SIZE: usize = 32;
+
+/// Digest of a window of messages of a publisher on a branch, anchored outside of the stream
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Checkpoint {
+    /// The [`Topic`] of the branch
+    pub topic: Topic,
+    /// The [`Identifier`] of the publisher of the messages
+    pub publisher: Identifier,
+    /// Sequence number of the first message of the window
+    pub first_sequence: usize,
+    /// Sequence number of the last message of the window, identifying the checkpoint
+    pub last_sequence: usize,
+    /// Digest of the hashes of the messages of the window, in sequence order
+    pub digest: [u8; DIGEST_SIZE],
+}
+
+/// Hook anchoring [checkpoints](`Checkpoint`) of the branches of a stream outside of the stream,
+/// for example on a ledger or a timestamping service, and retrieving them to verify the messages
+/// read from the stream. Every participant verifying the checkpoints must be able to fetch the ones
+/// anchored by the publishers.
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+pub trait Notarizer: MaybeSend + MaybeSync {
+    /// Anchors a [`Checkpoint`] closing a window of messages sent by the [`User`](crate::User)
+    ///
+    /// # Arguments
+    /// * `checkpoint`: The [`Checkpoint`] to anchor
+    async fn anchor(&mut self, checkpoint: Checkpoint) -> Result<()>;
+
+    /// Retrieves the [`Checkpoint`] anchored for the window of a publisher ending at a sequence
+    /// number. Returns `None` if it has not been anchored (yet).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `last_sequence`: The sequence number of the last message of the window
+    async fn fetch(
+        &mut self,
+        topic: &Topic,
+        publisher: &Identifier,
+        last_sequence: usize,
+    ) -> Result<Option<Checkpoint>>;
+}
+
+/// Hashes of the messages of the open windows of a publisher, mapped by the last sequence number
+/// of the window, then by message sequence number
+type Windows = BTreeMap<usize, BTreeMap<usize, [u8; DIGEST_SIZE]>>;
+
+/// Notarization configuration and rolling digests of a [`User`](crate::User)
+pub(crate) struct Notarization {
+    /// The hook anchoring and retrieving the checkpoints
+    notarizer: Box<dyn Notarizer>,
+    /// Number of sequence numbers covered by a window
+    interval: usize,
+    /// The open windows of each publisher, mapped by branch topic and publisher
+    windows: HashMap<(Topic, Identifier), Windows>,
+}
+
+impl Notarization {
+    /// Creates a new [`Notarization`]
+    ///
+    /// # Arguments
+    /// * `notarizer`: The hook anchoring and retrieving the checkpoints
+    /// * `interval`: Number of sequence numbers covered by a window. Must not be 0.
+    pub(crate) fn new(notarizer: Box<dyn Notarizer>, interval: usize) -> Self {
+        assert!(interval > 0, "the notarization interval must not be 0");
+        Self {
+            notarizer,
+            interval,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Returns the last sequence number of the window a sequence number belongs to
+    ///
+    /// # Arguments
+    /// * `sequence`: The sequence number of the message
+    fn window_end(&self, sequence: usize) -> usize {
+        (sequence + self.interval - 1) / self.interval * self.interval
+    }
+
+    /// Records the hash of a message in the window of its publisher
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `sequence`: The sequence number of the message
+    /// * `hash`: The hash of the message, as returned by [`message_hash()`]
+    fn record(&mut self, topic: &Topic, publisher: &Identifier, sequence: usize, hash: [u8; DIGEST_SIZE]) -> usize {
+        let window_end = self.window_end(sequence);
+        self.windows
+            .entry((topic.clone(), publisher.clone()))
+            .or_default()
+            .entry(window_end)
+            .or_default()
+            .insert(sequence, hash);
+        window_end
+    }
+
+    /// Closes a window, dropping it along with the windows preceding it
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `window_end`: The last sequence number of the window
+    fn close(&mut self, topic: &Topic, publisher: &Identifier, window_end: usize) {
+        if let Some(windows) = self.windows.get_mut(&(topic.clone(), publisher.clone())) {
+            *windows = windows.split_off(&(window_end + 1));
+        }
+    }
+
+    /// Returns the hashes of a window of a publisher, if any
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `window_end`: The last sequence number of the window
+    fn window(
+        &self,
+        topic: &Topic,
+        publisher: &Identifier,
+        window_end: usize,
+    ) -> Option<&BTreeMap<usize, [u8; DIGEST_SIZE]>> {
+        self.windows
+            .get(&(topic.clone(), publisher.clone()))
+            .and_then(|windows| windows.get(&window_end))
+    }
+
+    /// Records the hash of a message sent by the [`User`](crate::User), anchoring the checkpoint
+    /// of its window if the message closes it
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the [`User`](crate::User)
+    /// * `sequence`: The sequence number of the message
+    /// * `hash`: The hash of the message, as returned by [`message_hash()`]
+    pub(crate) async fn notarize_sent(
+        &mut self,
+        topic: &Topic,
+        publisher: &Identifier,
+        sequence: usize,
+        hash: [u8; DIGEST_SIZE],
+    ) -> Result<()> {
+        let window_end = self.record(topic, publisher, sequence, hash);
+        if sequence != window_end {
+            return Ok(());
+        }
+        // Ok to unwrap since the hash of the message has just been recorded
+        let window = self.window(topic, publisher, window_end).unwrap();
+        let first_sequence = *window.keys().next().unwrap();
+        let checkpoint = Checkpoint {
+            topic: topic.clone(),
+            publisher: publisher.clone(),
+            first_sequence,
+            last_sequence: window_end,
+            digest: window_digest(window.values()),
+        };
+        self.notarizer.anchor(checkpoint).await?;
+        self.close(topic, publisher, window_end);
+        Ok(())
+    }
+
+    /// Records the hash of a message read by the [`User`](crate::User), verifying its window
+    /// against the anchored checkpoint once the message closing it has been read. Messages can be
+    /// read in any order: the window is verified once every message covered by the checkpoint has
+    /// been read. Windows without an anchored checkpoint are kept open.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `sequence`: The sequence number of the message
+    /// * `hash`: The hash of the message, as returned by [`message_hash()`]
+    pub(crate) async fn notarize_read(
+        &mut self,
+        topic: &Topic,
+        publisher: &Identifier,
+        sequence: usize,
+        hash: [u8; DIGEST_SIZE],
+    ) -> Result<()> {
+        let window_end = self.record(topic, publisher, sequence, hash);
+        // Ok to unwrap since the hash of the message has just been recorded
+        if !self
+            .window(topic, publisher, window_end)
+            .unwrap()
+            .contains_key(&window_end)
+        {
+            return Ok(());
+        }
+        let checkpoint = match self.notarizer.fetch(topic, publisher, window_end).await? {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        let window = self.window(topic, publisher, window_end).unwrap();
+        let covered = checkpoint.first_sequence..=checkpoint.last_sequence;
+        if !covered.clone().all(|sequence| window.contains_key(&sequence)) {
+            return Ok(());
+        }
+        let digest = window_digest(window.range(covered).map(|(_, hash)| hash));
+        if digest != checkpoint.digest {
+            return Err(Error::CheckpointMismatch(topic.clone(), publisher.clone(), window_end));
+        }
+        self.close(topic, publisher, window_end);
+        Ok(())
+    }
+}
+
+/// Returns the hash of a raw message
+///
+/// # Arguments
+/// * `msg`: The raw [`TransportMessage`]
+pub(crate) fn message_hash(msg: &TransportMessage) -> [u8; DIGEST_SIZE] {
+    let mut spongos = Spongos::<KeccakF1600>::init();
+    spongos.absorb(msg);
+    spongos.commit();
+    spongos.squeeze()
+}
+
+/// Returns the digest of the hashes of the messages of a window, in sequence order
+///
+/// # Arguments
+/// * `hashes`: The hashes of the messages of the window, in sequence order
+fn window_digest<'a, I>(hashes: I) -> [u8; DIGEST_SIZE]
+where
+    I: IntoIterator<Item = &'a [u8; DIGEST_SIZE]>,
+{
+    let mut spongos = Spongos::<KeccakF1600>::init();
+    for hash in hashes {
+        spongos.absorb(hash);
+    }
+    spongos.commit();
+    spongos.squeeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+
+    use async_trait::async_trait;
+    use hashbrown::HashMap;
+    use lets::{id::Identifier, message::Topic};
+
+    use crate::{
+        api::fixtures::{new_transport, new_user_builder},
+        Error, Result,
+    };
+
+    use super::{Checkpoint, Notarizer};
+
+    /// Notarizer keeping the anchored checkpoints in memory, shared by the users of a test
+    #[derive(Clone, Default)]
+    struct MemoryNotarizer(Rc<RefCell<HashMap<(Topic, Identifier, usize), Checkpoint>>>);
+
+    #[async_trait(?Send)]
+    impl Notarizer for MemoryNotarizer {
+        async fn anchor(&mut self, checkpoint: Checkpoint) -> Result<()> {
+            let key = (
+                checkpoint.topic.clone(),
+                checkpoint.publisher.clone(),
+                checkpoint.last_sequence,
+            );
+            self.0.borrow_mut().insert(key, checkpoint);
+            Ok(())
+        }
+
+        async fn fetch(
+            &mut self,
+            topic: &Topic,
+            publisher: &Identifier,
+            last_sequence: usize,
+        ) -> Result<Option<Checkpoint>> {
+            let key = (topic.clone(), publisher.clone(), last_sequence);
+            Ok(self.0.borrow().get(&key).cloned())
+        }
+    }
+
+    #[tokio::test]
+    async fn notarized_messages_are_verified_against_checkpoints() -> Result<()> {
+        let transport = new_transport();
+        let notarizer = MemoryNotarizer::default();
+        let mut author = new_user_builder("author", &transport)
+            .with_notarizer(notarizer.clone(), 2)
+            .build();
+        let mut readers = Vec::new();
+        for seed in ["first reader", "second reader"] {
+            readers.push(
+                new_user_builder(seed, &transport)
+                    .with_notarizer(notarizer.clone(), 2)
+                    .build(),
+            );
+        }
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        for reader in &mut readers {
+            reader.receive_message(announcement.address()).await?;
+            reader.subscribe().await?;
+        }
+        author.sync().await?;
+        let mut addresses = vec![author.send_keyload_for_all("BASE_BRANCH").await?.address()];
+        for payload in ["first", "second", "third", "fourth"] {
+            let packet = author.send_signed_packet("BASE_BRANCH", b"public", payload).await?;
+            addresses.push(packet.address());
+        }
+        // The keyload and the packets have sequence numbers 2 to 6, closing 3 windows of 2
+        assert_eq!(notarizer.0.borrow().len(), 3);
+
+        let first_reader = &mut readers[0];
+        for address in &addresses {
+            first_reader.receive_message(*address).await?;
+        }
+
+        // Tampering with the anchored digests is detected by the readers
+        for checkpoint in notarizer.0.borrow_mut().values_mut() {
+            checkpoint.digest = [0; 32];
+        }
+        let second_reader = &mut readers[1];
+        let mut results = Vec::new();
+        for address in &addresses {
+            results.push(second_reader.receive_message(*address).await);
+        }
+        assert!(results
+            .iter()
+            .any(|result| matches!(result, Err(Error::CheckpointMismatch(..)))));
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// Rust
+use alloc::vec::Vec;
+use core::{
+    pin::Pin,
+    task::{self, Poll},
+};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    id::Identifier,
+    message::{ContentUnwrap, ContentVerify, HDF, PCF},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{unwrap, Commit},
+        io,
+    },
+    KeccakF1600, Spongos,
+};
+
+// Local
+use crate::{
+    message::{message_types, signed_packet},
+    Error, Result,
+};
+
+/// Incremental decoding of a signed packet, reading its masked payload in chunks
+///
+/// The message is pulled from an [`io::Source`] as it is decoded, and the masked payload is
+/// decrypted as it is read through [`SignedPacketReader::read()`] or the `AsyncRead`-style
+/// [`SignedPacketReader::poll_read()`], so that neither the message nor its masked payload need to
+/// be held in memory whole. The signature of the packet covers the masked payload and can only be
+/// verified once it has been read: the masked payload must not be trusted until
+/// [`SignedPacketReader::finish()`] succeeds.
+pub struct SignedPacketReader<S> {
+    /// The [`Address`] of the packet
+    address: Address,
+    /// The header of the packet
+    header: HDF,
+    /// The [`Identifier`] of the publisher of the packet
+    publisher: Identifier,
+    /// The unmasked payload of the packet
+    public_payload: Vec<u8>,
+    /// The reader of the masked payload
+    reader: unwrap::MaskedReader<io::Chunked<S>, KeccakF1600>,
+}
+
+impl<S> SignedPacketReader<S>
+where
+    S: io::Source + MaybeSend,
+{
+    /// Decodes the header of a signed packet and its content up to the masked payload.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the packet
+    /// * `source`: The [`io::Source`] of the binary message
+    /// * `len`: The length of the binary message
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the message the packet is linked to
+    /// * `size_limit`: The largest length or item count accepted in the message
+    pub(crate) async fn open(
+        address: Address,
+        source: S,
+        len: usize,
+        mut linked_msg_spongos: Spongos,
+        size_limit: usize,
+    ) -> Result<Self> {
+        let mut ctx = unwrap::Context::new(io::Chunked::new(source, len)).with_size_limit(size_limit);
+        let mut header = HDF::default();
+        ctx.unwrap(&mut header)
+            .await
+            .map_err(|e| Error::Unwrapping("header", address, e.into()))?;
+        if header.message_type() != message_types::SIGNED_PACKET {
+            return Err(Error::MessageTypeUnknown(header.message_type()));
+        }
+
+        // The content is unwrapped from the header state committed, as in a preparsed message
+        let mut pcf = PCF::<()>::default()
+            .with_content(signed_packet::Unwrap::new(&mut linked_msg_spongos).until_masked_payload());
+        ctx.commit()
+            .map_err(|e| Error::Unwrapping("signed packet", address, e.into()))?
+            .unwrap(&mut pcf)
+            .await
+            .map_err(|e| Error::Unwrapping("signed packet", address, e.into()))?;
+        let mut content = pcf.into_content();
+        let public_payload = content.take_public_payload();
+        let publisher = content.into_publisher_identifier();
+
+        let reader =
+            unwrap::MaskedReader::new(ctx).map_err(|e| Error::Unwrapping("signed packet", address, e.into()))?;
+        Ok(Self {
+            address,
+            header,
+            publisher,
+            public_payload,
+            reader,
+        })
+    }
+
+    /// Returns the [`Address`] of the packet
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the header of the packet
+    pub fn header(&self) -> &HDF {
+        &self.header
+    }
+
+    /// Returns the [`Identifier`] of the publisher of the packet, to be trusted once the signature
+    /// has been verified by [`SignedPacketReader::finish()`]
+    pub fn publisher(&self) -> &Identifier {
+        &self.publisher
+    }
+
+    /// Returns the unmasked payload of the packet
+    pub fn public_payload(&self) -> &[u8] {
+        &self.public_payload
+    }
+
+    /// Returns the length of the masked payload
+    pub fn masked_len(&self) -> usize {
+        self.reader.len()
+    }
+
+    /// Returns the number of bytes of the masked payload not read yet
+    pub fn masked_remaining(&self) -> usize {
+        self.reader.remaining()
+    }
+
+    /// Decrypts the next bytes of the masked payload into a buffer, returning the number of bytes
+    /// read. Returns 0 once the whole masked payload has been read.
+    ///
+    /// # Arguments
+    /// * `buf`: The buffer to fill
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let address = self.address;
+        self.reader
+            .read(buf)
+            .map_err(|e| Error::Unwrapping("signed packet", address, e.into()))
+    }
+
+    /// Same as [`SignedPacketReader::read()`], following the signature of `AsyncRead::poll_read`.
+    /// The result is always ready, as the message is pulled synchronously from its source.
+    ///
+    /// # Arguments
+    /// * `cx`: The task context, unused
+    /// * `buf`: The buffer to fill
+    pub fn poll_read(self: Pin<&mut Self>, _cx: &mut task::Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>>
+    where
+        Self: Unpin,
+    {
+        Poll::Ready(self.get_mut().read(buf))
+    }
+
+    /// Decrypts the remainder of the masked payload, if any, and verifies the signature of the
+    /// packet. Returns the [`Spongos`] state of the packet, to decode the messages linked to it.
+    pub async fn finish(self) -> Result<Spongos> {
+        let address = self.address;
+        let mut ctx = self
+            .reader
+            .into_context()
+            .map_err(|e| Error::Unwrapping("signed packet", address, e.into()))?;
+        ctx.verify(&self.publisher)
+            .await
+            .map_err(|e| Error::Unwrapping("signed packet", address, e.into()))?;
+        let (spongos, _) = ctx.finalize();
+        Ok(spongos)
+    }
+}
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+impl<S> futures::io::AsyncRead for SignedPacketReader<S>
+where
+    S: io::Source + MaybeSend + Unpin,
+{
+    fn poll_read(self: Pin<&mut Self>, _cx: &mut task::Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        let read = self
+            .get_mut()
+            .read(buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}", e)));
+        Poll::Ready(read)
+    }
+}
@@ -0,0 +1,123 @@
+//! Structured payloads, tagged with the format they were serialized with
+//!
+//! Payloads sent with [`User::send_json()`](crate::User) or [`User::send_cbor()`](crate::User) are
+//! carried in the masked payload of a signed packet, prefixed by a [`ContentType`] byte. Readers
+//! can dispatch the decoding on [`MessageContent::content_type()`](crate::MessageContent) without
+//! an out-of-band agreement on the payload format.
+
+// Rust
+#[cfg(any(feature = "json", feature = "cbor"))]
+use alloc::{string::ToString, vec::Vec};
+
+// 3rd-party
+#[cfg(any(feature = "json", feature = "cbor"))]
+use serde::{de::DeserializeOwned, Serialize};
+
+// IOTA
+
+// Streams
+
+// Local
+#[cfg(any(feature = "json", feature = "cbor"))]
+use crate::{Error, Result};
+
+/// Serialization format of a structured payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ContentType {
+    /// `JSON` encoded payload
+    Json = 1,
+    /// `CBOR` encoded payload
+    Cbor = 2,
+}
+
+impl ContentType {
+    /// Returns the [`ContentType`] identified by the provided tag byte, if any
+    ///
+    /// # Arguments
+    /// * `tag`: The first byte of a structured payload
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Self::Json),
+            2 => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Returns the tag byte that prefixes the payloads of this [`ContentType`]
+    pub fn tag(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the [`ContentType`] of a structured payload, if it is tagged with a known one
+    ///
+    /// # Arguments
+    /// * `payload`: The structured payload
+    pub(crate) fn of(payload: &[u8]) -> Option<Self> {
+        payload.first().copied().and_then(Self::from_tag)
+    }
+}
+
+/// Serializes a value into a `JSON` payload, prefixed by its [`ContentType`] tag
+///
+/// # Arguments
+/// * `value`: The value to be serialized
+#[cfg(feature = "json")]
+pub(crate) fn encode_json<S>(value: &S) -> Result<Vec<u8>>
+where
+    S: Serialize + ?Sized,
+{
+    let mut payload = vec![ContentType::Json.tag()];
+    payload.extend(serde_json::to_vec(value).map_err(|e| Error::PayloadEncoding("serialize", e.to_string()))?);
+    Ok(payload)
+}
+
+/// Deserializes a value from a `JSON` payload, checking its [`ContentType`] tag
+///
+/// # Arguments
+/// * `payload`: The tagged payload
+#[cfg(feature = "json")]
+pub(crate) fn decode_json<D>(payload: &[u8]) -> Result<D>
+where
+    D: DeserializeOwned,
+{
+    serde_json::from_slice(untag(payload, ContentType::Json)?)
+        .map_err(|e| Error::PayloadEncoding("deserialize", e.to_string()))
+}
+
+/// Serializes a value into a `CBOR` payload, prefixed by its [`ContentType`] tag
+///
+/// # Arguments
+/// * `value`: The value to be serialized
+#[cfg(feature = "cbor")]
+pub(crate) fn encode_cbor<S>(value: &S) -> Result<Vec<u8>>
+where
+    S: Serialize + ?Sized,
+{
+    let mut payload = vec![ContentType::Cbor.tag()];
+    ciborium::ser::into_writer(value, &mut payload).map_err(|e| Error::PayloadEncoding("serialize", e.to_string()))?;
+    Ok(payload)
+}
+
+/// Deserializes a value from a `CBOR` payload, checking its [`ContentType`] tag
+///
+/// # Arguments
+/// * `payload`: The tagged payload
+#[cfg(feature = "cbor")]
+pub(crate) fn decode_cbor<D>(payload: &[u8]) -> Result<D>
+where
+    D: DeserializeOwned,
+{
+    ciborium::de::from_reader(untag(payload, ContentType::Cbor)?)
+        .map_err(|e| Error::PayloadEncoding("deserialize", e.to_string()))
+}
+
+/// Strips the [`ContentType`] tag of a payload, checking it matches the expected one
+#[cfg(any(feature = "json", feature = "cbor"))]
+fn untag(payload: &[u8], expected: ContentType) -> Result<&[u8]> {
+    match payload.split_first() {
+        Some((&tag, body)) if tag == expected.tag() => Ok(body),
+        Some((&tag, _)) => Err(Error::ContentTypeMismatch(expected.tag(), Some(tag))),
+        None => Err(Error::ContentTypeMismatch(expected.tag(), None)),
+    }
+}
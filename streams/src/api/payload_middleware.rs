@@ -0,0 +1,182 @@
+//! Transformation of the payloads of the packets sent and read by a user
+//!
+//! A [`User`](crate::User) built with
+//! [`UserBuilder::with_payload_middleware()`](crate::UserBuilder::with_payload_middleware) passes
+//! the masked payload of every signed and tagged packet it sends through the [`PayloadTransform`]s
+//! of its [`PayloadMiddleware`], in order, like compressing the payload, then encrypting it at the
+//! application level, then appending an external signature. The packets it reads go through the
+//! inverse transforms, in reverse order, once their content is unwrapped. Packets whose payload
+//! cannot be restored are not dropped: they surface as
+//! [`MessageContent::Rejected`](crate::MessageContent::Rejected) with the reason of the failure.
+
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::sync::{MaybeSend, MaybeSync};
+
+// Local
+use crate::Result;
+
+/// Reversible transformation of the masked payloads of the packets, like a compression or an
+/// application-level encryption
+pub trait PayloadTransform: MaybeSend + MaybeSync {
+    /// Transforms the masked payload of a packet before it is sent
+    ///
+    /// # Arguments
+    /// * `payload`: The payload, as returned by the previous transform of the chain
+    fn encode(&self, payload: &[u8]) -> Result<Vec<u8>>;
+
+    /// Restores the masked payload of a packet read, inverting [`PayloadTransform::encode()`]
+    ///
+    /// # Arguments
+    /// * `payload`: The payload, as returned by the inverse of the next transform of the chain
+    fn decode(&self, payload: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Ordered chain of [`PayloadTransform`]s applied to the masked payloads of the packets of a
+/// [`User`](crate::User)
+#[derive(Default)]
+pub struct PayloadMiddleware {
+    /// The transforms, in the order they are applied to the payloads sent
+    transforms: Vec<Box<dyn PayloadTransform>>,
+}
+
+impl PayloadMiddleware {
+    /// Creates a [`PayloadMiddleware`] without transforms, leaving the payloads unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a [`PayloadTransform`] to the chain. It is applied to the payloads sent after the
+    /// transforms already in the chain, and inverted on the payloads read before them.
+    ///
+    /// # Arguments
+    /// * `transform`: The [`PayloadTransform`] to append
+    pub fn with_transform<P>(mut self, transform: P) -> Self
+    where
+        P: PayloadTransform + 'static,
+    {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Returns the number of transforms of the chain
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    /// Returns true if the chain has no transform
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Applies the transforms of the chain to a payload to send, in order
+    ///
+    /// # Arguments
+    /// * `payload`: The masked payload of the packet
+    pub fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        self.transforms
+            .iter()
+            .try_fold(payload.to_vec(), |payload, transform| transform.encode(&payload))
+    }
+
+    /// Inverts the transforms of the chain on a payload read, in reverse order
+    ///
+    /// # Arguments
+    /// * `payload`: The masked payload of the packet
+    pub fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        self.transforms
+            .iter()
+            .rev()
+            .try_fold(payload.to_vec(), |payload, transform| transform.decode(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Error, Message, Result,
+    };
+
+    use super::{PayloadMiddleware, PayloadTransform};
+
+    #[tokio::test]
+    async fn payload_middleware_transforms_payloads_in_order_and_back() -> Result<()> {
+        /// Frames the payloads with a version prefix
+        struct Framing;
+        impl PayloadTransform for Framing {
+            fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+                Ok([&b"v1:"[..], payload].concat())
+            }
+            fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+                payload
+                    .strip_prefix(b"v1:")
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| Error::PayloadEncoding("decode", "unknown frame".into()))
+            }
+        }
+        /// Scrambles the payloads with a key, standing for an application-level encryption
+        struct Scrambling(u8);
+        impl PayloadTransform for Scrambling {
+            fn encode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+                Ok(payload.iter().map(|byte| byte ^ self.0).collect())
+            }
+            fn decode(&self, payload: &[u8]) -> Result<Vec<u8>> {
+                self.encode(payload)
+            }
+        }
+        let middleware = || {
+            PayloadMiddleware::new()
+                .with_transform(Framing)
+                .with_transform(Scrambling(0x5a))
+        };
+
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport)
+            .with_payload_middleware(middleware())
+            .build();
+        let mut reader = new_user_builder("reader", &transport)
+            .with_payload_middleware(middleware())
+            .build();
+        let mut plain_reader = new_user("plain reader", &transport);
+        let mut misordered_reader = new_user_builder("misordered reader", &transport)
+            .with_payload_middleware(
+                PayloadMiddleware::new()
+                    .with_transform(Scrambling(0x5a))
+                    .with_transform(Framing),
+            )
+            .build();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"signed").await?;
+        author.send_tagged_packet("BASE_BRANCH", b"public", b"tagged").await?;
+
+        // Readers sharing the middleware read the original payloads
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages[0].masked_payload(), Some(&b"signed"[..]));
+        assert_eq!(messages[1].masked_payload(), Some(&b"tagged"[..]));
+        assert_eq!(messages[1].public_payload(), Some(&b"public"[..]));
+
+        // The payloads are sent transformed in order: framed, then scrambled
+        plain_reader.receive_message(announcement.address()).await?;
+        let messages = plain_reader.fetch_next_messages().await?;
+        let sent = Scrambling(0x5a).encode(&Framing.encode(b"signed")?)?;
+        assert_eq!(messages[0].masked_payload(), Some(&sent[..]));
+
+        // Payloads that cannot be restored are rejected
+        misordered_reader.receive_message(announcement.address()).await?;
+        let messages = misordered_reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(Message::is_rejected));
+        Ok(())
+    }
+}
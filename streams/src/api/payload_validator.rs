@@ -0,0 +1,101 @@
+//! Validation of the payloads of the packets read by a user
+//!
+//! A [`User`](crate::User) hands the packets it reads in a branch to the [`PayloadValidator`] set
+//! for the branch with [`User::set_payload_validator()`](crate::User::set_payload_validator), once
+//! their content is unwrapped and their signature verified. Packets failing the validation are not
+//! dropped: they are processed like any other message, so the stream carries on past them, and
+//! surface as [`MessageContent::Rejected`](crate::MessageContent::Rejected) with the reason of the
+//! rejection, quarantining their payload.
+
+// Rust
+use alloc::string::String;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::sync::{MaybeSend, MaybeSync};
+
+// Local
+use crate::api::message::Message;
+
+/// Decision of a [`PayloadValidator`] on a packet
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ValidationVerdict {
+    /// The packet is surfaced as is
+    Valid,
+    /// The packet is surfaced as rejected, for the provided reason
+    Invalid(String),
+}
+
+/// Hook validating the payloads of the packets read by a [`User`](crate::User) in a branch, like
+/// checking that they match the schema the producers of the branch agreed on
+pub trait PayloadValidator: MaybeSend + MaybeSync {
+    /// Returns whether the payloads of a packet are valid
+    ///
+    /// # Arguments
+    /// * `message`: The packet read, with its payloads unwrapped
+    fn validate(&self, message: &Message) -> ValidationVerdict;
+}
+
+impl<F> PayloadValidator for F
+where
+    F: Fn(&Message) -> ValidationVerdict + MaybeSend + MaybeSync,
+{
+    fn validate(&self, message: &Message) -> ValidationVerdict {
+        self(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::message::Topic;
+
+    use crate::{
+        api::fixtures::{new_reader, new_transport, new_user},
+        Message, Metrics, Result,
+    };
+
+    use super::ValidationVerdict;
+
+    #[tokio::test]
+    async fn invalid_payloads_are_quarantined_without_stopping_the_stream() -> Result<()> {
+        let transport = new_transport();
+        let mut producer = new_user("producer", &transport);
+        let mut pipeline = new_reader(&transport);
+        let metrics = alloc::sync::Arc::new(Metrics::new());
+        pipeline.set_metrics(metrics.clone());
+        pipeline.set_payload_validator("BASE_BRANCH", |message: &Message| {
+            match message
+                .masked_payload()
+                .map_or(false, |payload| payload.starts_with(b"{"))
+            {
+                true => ValidationVerdict::Valid,
+                false => ValidationVerdict::Invalid("payload is not a JSON object".into()),
+            }
+        });
+
+        let announcement = producer.create_stream("BASE_BRANCH").await?;
+        producer.send_signed_packet("BASE_BRANCH", b"", b"{\"t\": 21}").await?;
+        producer.send_signed_packet("BASE_BRANCH", b"", b"t=22").await?;
+        producer.send_signed_packet("BASE_BRANCH", b"", b"{\"t\": 23}").await?;
+
+        pipeline.receive_message(announcement.address()).await?;
+        let messages = pipeline.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].masked_payload(), Some(&b"{\"t\": 21}"[..]));
+        let rejected = messages[1].as_rejected().unwrap();
+        assert_eq!(rejected.reason, "payload is not a JSON object");
+        assert_eq!(rejected.content.masked_payload(), Some(&b"t=22"[..]));
+        assert_eq!(messages[1].masked_payload(), None);
+        assert_eq!(messages[2].masked_payload(), Some(&b"{\"t\": 23}"[..]));
+        assert_eq!(metrics.messages_rejected(), 1);
+
+        assert!(pipeline.remove_payload_validator(&Topic::from("BASE_BRANCH")));
+        producer.send_signed_packet("BASE_BRANCH", b"", b"t=24").await?;
+        let messages = pipeline.fetch_next_messages().await?;
+        assert!(!messages[0].is_rejected());
+        Ok(())
+    }
+}
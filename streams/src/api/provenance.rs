@@ -0,0 +1,195 @@
+// Rust
+use alloc::{string::String, vec::Vec};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::{Address, MsgId},
+    id::Identifier,
+    message::HDF,
+};
+
+// Local
+use crate::api::message::{Message, MessageContent};
+
+/// Outcome of the verification of a single message of a provenance chain
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProvenanceStatus {
+    /// The message signature was verified against its signer
+    Verified,
+    /// The message was read, but it carries no signature (like tagged packets). Its integrity is
+    /// only bound to the keyload of its branch.
+    Unsigned,
+    /// The message could not be read because the [`Spongos`](spongos::Spongos) state of its
+    /// predecessor is not available, so neither its signature nor its link could be verified
+    Unverifiable,
+    /// Processing the message failed, for instance because its signature does not match its
+    /// publisher. Holds the description of the failure.
+    Failed(String),
+}
+
+/// Provenance of a single message of a chain
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProvenanceEntry {
+    /// The [`Address`] of the message
+    pub address: Address,
+    /// The [`MsgId`] of the message it is linked to. None for the stream announcement.
+    pub linked_msg_address: Option<MsgId>,
+    /// The [`Identifier`] whose signature was verified. None if the message is not signed or could
+    /// not be verified.
+    pub signer: Option<Identifier>,
+    /// The outcome of the verification
+    pub status: ProvenanceStatus,
+}
+
+impl ProvenanceEntry {
+    /// Creates a [`ProvenanceEntry`] from the outcome of processing a message
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `linked_msg_address`: The [`MsgId`] of the message it is linked to
+    /// * `message`: The processed [`Message`], or the description of the failure
+    pub(crate) fn new(
+        address: Address,
+        linked_msg_address: Option<MsgId>,
+        message: core::result::Result<Message, String>,
+    ) -> Self {
+        let (signer, status) = match message {
+            Ok(message) if message.is_orphan() => (None, ProvenanceStatus::Unverifiable),
+            Ok(message) => match signer(&message) {
+                Some(signer) => (Some(signer.clone()), ProvenanceStatus::Verified),
+                None => (None, ProvenanceStatus::Unsigned),
+            },
+            Err(description) => (None, ProvenanceStatus::Failed(description)),
+        };
+        Self {
+            address,
+            linked_msg_address,
+            signer,
+            status,
+        }
+    }
+
+    /// Returns true if the message was read and, if signed, its signature verified
+    pub fn is_verified(&self) -> bool {
+        matches!(self.status, ProvenanceStatus::Verified | ProvenanceStatus::Unsigned)
+    }
+}
+
+/// Report of the verification of a chain of linked messages, from the oldest to the newest
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct ProvenanceReport {
+    /// The provenance of each message of the chain, in publication order
+    entries: Vec<ProvenanceEntry>,
+}
+
+impl ProvenanceReport {
+    /// Creates a new [`ProvenanceReport`]
+    ///
+    /// # Arguments
+    /// * `entries`: The provenance of each message of the chain, in publication order
+    pub(crate) fn new(entries: Vec<ProvenanceEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Returns the provenance of each message of the chain, in publication order
+    pub fn entries(&self) -> &[ProvenanceEntry] {
+        &self.entries
+    }
+
+    /// Returns true if every message of the chain was read and every signature verified
+    pub fn is_verified(&self) -> bool {
+        self.entries.iter().all(ProvenanceEntry::is_verified)
+    }
+
+    /// Returns an iterator over the signer of each message of the chain, in publication order
+    pub fn signers(&self) -> impl Iterator<Item = Option<&Identifier>> + '_ {
+        self.entries.iter().map(|entry| entry.signer.as_ref())
+    }
+}
+
+/// Returns the [`Identifier`] that signed a processed [`Message`], if the message is signed
+///
+/// # Arguments
+/// * `message`: The processed [`Message`]
+fn signer(message: &Message) -> Option<&Identifier> {
+    content_signer(&message.header, &message.content)
+}
+
+/// Returns the [`Identifier`] that signed the content of a processed message, if it is signed
+///
+/// # Arguments
+/// * `header`: The header of the message
+/// * `content`: The content of the message
+fn content_signer<'a>(header: &'a HDF, content: &'a MessageContent) -> Option<&'a Identifier> {
+    match content {
+        MessageContent::Announcement(announcement) => Some(&announcement.author_identifier),
+        MessageContent::BranchAnnouncement(_) | MessageContent::Keyload(_) => Some(header.publisher()),
+        MessageContent::BranchClosed(branch_closed) => Some(&branch_closed.publisher_identifier),
+        MessageContent::SignedPacket(signed_packet) => Some(&signed_packet.publisher_identifier),
+        MessageContent::SelectivePacket(selective_packet) => Some(&selective_packet.publisher_identifier),
+        MessageContent::BatchPacket(batch_packet) => Some(&batch_packet.publisher_identifier),
+        MessageContent::Subscription(subscription) => Some(&subscription.subscriber_identifier),
+        MessageContent::Unsubscription(unsubscription) => Some(&unsubscription.subscriber_identifier),
+        MessageContent::KeyUpdate(key_update) => Some(&key_update.identifier),
+        MessageContent::SubstreamAnnounced(substream) => Some(&substream.publisher_identifier),
+        MessageContent::ReadMarker(read_marker) => Some(&read_marker.publisher_identifier),
+        MessageContent::Tombstone(tombstone) => Some(&tombstone.publisher_identifier),
+        MessageContent::AuthorRotation(rotation) => Some(&rotation.previous_author),
+        MessageContent::Countersignature(countersignature) => Some(&countersignature.witness),
+        MessageContent::Commitment(commitment) => Some(&commitment.publisher_identifier),
+        MessageContent::Reveal(reveal) => Some(&reveal.publisher_identifier),
+        MessageContent::Custom(custom) => Some(&custom.publisher_identifier),
+        MessageContent::Rejected(rejected) => content_signer(header, &rejected.content),
+        MessageContent::Redacted(redacted) => content_signer(header, redacted.original()),
+        MessageContent::Legacy(legacy) => Some(&legacy.publisher_identifier),
+        MessageContent::TaggedPacket(_)
+        | MessageContent::AccessExpired(_)
+        | MessageContent::Orphan(_)
+        | MessageContent::OutOfOrder(_)
+        | MessageContent::DuplicateReceived(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{api::fixtures::author_subscriber_fixture, Error, Result};
+
+    #[tokio::test]
+    async fn chain_verification_reports_signer_of_each_message() -> Result<()> {
+        let (mut author, mut subscriber, _, _) = author_subscriber_fixture().await?;
+        let keyload = author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        author.send_tagged_packet("BASE_BRANCH", b"public", b"tagged").await?;
+        subscriber.sync().await?;
+        let last = subscriber
+            .send_signed_packet("BASE_BRANCH", b"public", b"signed")
+            .await?;
+
+        // The author is not a recipient of its own keyload, so the chain is verified by the subscriber
+        let report = subscriber.verify_chain(keyload.address(), last.address()).await?;
+        assert!(report.is_verified());
+        assert_eq!(
+            report.signers().collect::<Vec<_>>(),
+            [author.identifier(), None, subscriber.identifier()]
+        );
+        assert_eq!(
+            report.entries()[2].linked_msg_address,
+            Some(report.entries()[1].address.relative())
+        );
+
+        // The span must be linked from its newest to its oldest message
+        assert!(matches!(
+            subscriber.verify_chain(last.address(), keyload.address()).await,
+            Err(Error::MessageMissing(..))
+        ));
+
+        // Verifying does not set the cursors back
+        subscriber.send_signed_packet("BASE_BRANCH", b"public", b"next").await?;
+        Ok(())
+    }
+}
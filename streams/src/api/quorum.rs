@@ -0,0 +1,395 @@
+//! Threshold of author keys approving the critical messages of a stream
+//!
+//! A stream operated by a consortium cannot trust a single operator key. A [`Quorum`] of author
+//! keys is embedded in the announcement of the stream with
+//! [`User::create_stream_with_quorum()`](crate::User::create_stream_with_quorum): the announcement
+//! carries the approvals of at least [`Quorum::threshold()`] of its members, and readers reject it
+//! otherwise. From then on, the readers of the stream only accept the author rotations approved by
+//! the threshold of the members, sent with
+//! [`User::rotate_author_with_approvals()`](crate::User::rotate_author_with_approvals), so that no
+//! single key can hand the stream over.
+//!
+//! The quorum only covers these two messages. The other administrative messages of the stream,
+//! the keyloads, branch closures and permission changes, are still signed by the current author key
+//! alone, and readers accept them without any approval of the quorum. A compromised author key can
+//! therefore change who reads and writes the stream until the quorum rotates it away, but it cannot
+//! take the stream over.
+//!
+//! The members approve a [`QuorumProposal`], the digest of the message to be sent, that each of
+//! them derives on its own from the [`Quorum`] and the content of the message, and sign it with
+//! [`QuorumProposal::approve()`]. The [`QuorumApproval`]s are collected by the author out of band.
+//!
+//! ```ddml
+//! type QuorumApproval {
+//!     mask    u8      member;
+//!     mask    bytes   signature;
+//! }
+//! ```
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    id::{Identifier, Identity},
+    message::Topic,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Mask},
+        io,
+        types::Bytes,
+    },
+    error::Result as SpongosResult,
+    KeccakF1600, Spongos, PRP,
+};
+
+// Local
+use crate::{Error, Result};
+
+/// Domain separation tag of the proposals of quorum announcements
+const ANNOUNCEMENT_PROPOSAL_DOMAIN: &[u8] = b"streams quorum announcement";
+/// Domain separation tag of the proposals of approved author rotations
+const ROTATION_PROPOSAL_DOMAIN: &[u8] = b"streams quorum author rotation";
+
+/// Author keys of a stream, of which a threshold must approve its announcement and author rotations
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Quorum {
+    /// The [`Identifier`]s of the members, in the order they were listed
+    members: Vec<Identifier>,
+    /// The number of members whose approval is required
+    threshold: u8,
+}
+
+impl Quorum {
+    /// Creates a new k-of-n [`Quorum`]. Errors if the members are not distinct, if there are more
+    /// than 255 of them, or if the threshold is not between 1 and the number of members.
+    ///
+    /// # Arguments
+    /// * `members`: The [`Identifier`]s of the n members
+    /// * `threshold`: The number k of members whose approval is required
+    pub fn new(members: Vec<Identifier>, threshold: u8) -> Result<Self> {
+        if members.is_empty() || members.len() > u8::MAX as usize {
+            return Err(Error::QuorumInvalid("a quorum has between 1 and 255 members"));
+        }
+        if threshold == 0 || threshold as usize > members.len() {
+            return Err(Error::QuorumInvalid(
+                "the threshold must be between 1 and the number of members",
+            ));
+        }
+        if members
+            .iter()
+            .enumerate()
+            .any(|(i, member)| members[..i].contains(member))
+        {
+            return Err(Error::QuorumInvalid("the members of a quorum must be distinct"));
+        }
+        Ok(Self { members, threshold })
+    }
+
+    /// Returns the [`Identifier`]s of the members
+    pub fn members(&self) -> &[Identifier] {
+        &self.members
+    }
+
+    /// Returns the number of members whose approval is required
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Returns true if an [`Identifier`] is a member of the quorum
+    ///
+    /// # Arguments
+    /// * `identifier`: The [`Identifier`] to look for
+    pub fn contains(&self, identifier: &Identifier) -> bool {
+        self.members.contains(identifier)
+    }
+
+    /// Returns the [`QuorumProposal`] of the announcement of a stream embedding the quorum
+    ///
+    /// # Arguments
+    /// * `author`: The [`Identifier`] of the author sending the announcement
+    /// * `topic`: The [`Topic`] of the base branch of the stream
+    pub fn announcement_proposal(&self, author: &Identifier, topic: &Topic) -> QuorumProposal {
+        let mut proposal = ProposalBuilder::new(ANNOUNCEMENT_PROPOSAL_DOMAIN);
+        proposal.field(author.as_ref()).field(topic.as_ref());
+        self.absorb_into(&mut proposal);
+        proposal.finish()
+    }
+
+    /// Returns the [`QuorumProposal`] of an author rotation in a stream embedding the quorum. The
+    /// proposal covers the sequence number of the rotation, so that its approvals cannot be
+    /// replayed in a later rotation.
+    ///
+    /// # Arguments
+    /// * `stream_address`: The [`Address`] of the announcement of the stream
+    /// * `previous_author`: The [`Identifier`] of the current author, sending the rotation
+    /// * `new_author`: The [`Identifier`] of the new author
+    /// * `sequence`: The sequence number of the rotation message, see
+    ///   [`User::author_rotation_proposal()`](crate::User::author_rotation_proposal)
+    pub fn rotation_proposal(
+        &self,
+        stream_address: Address,
+        previous_author: &Identifier,
+        new_author: &Identifier,
+        sequence: usize,
+    ) -> QuorumProposal {
+        let mut proposal = ProposalBuilder::new(ROTATION_PROPOSAL_DOMAIN);
+        proposal
+            .field(stream_address.base().as_ref())
+            .field(stream_address.relative().as_ref())
+            .field(previous_author.as_ref())
+            .field(new_author.as_ref())
+            .field(&(sequence as u64).to_le_bytes());
+        self.absorb_into(&mut proposal);
+        proposal.finish()
+    }
+
+    /// Checks that the threshold of members approved a proposal. Approvals of non-members, invalid
+    /// signatures and repeated approvals of a member are not counted.
+    ///
+    /// # Arguments
+    /// * `proposal`: The [`QuorumProposal`] of the message
+    /// * `approvals`: The [`QuorumApproval`]s carried by the message
+    pub fn verify(&self, proposal: &QuorumProposal, approvals: &[QuorumApproval]) -> Result<()> {
+        let mut approved: Vec<&Identifier> = Vec::new();
+        for approval in approvals {
+            if self.contains(&approval.member)
+                && !approved.contains(&&approval.member)
+                && approval
+                    .member
+                    .verify_detached(&proposal.0, &approval.signature)
+                    .is_ok()
+            {
+                approved.push(&approval.member);
+            }
+        }
+        if approved.len() < self.threshold as usize {
+            return Err(Error::QuorumNotReached(approved.len(), self.threshold as usize));
+        }
+        Ok(())
+    }
+
+    /// Absorbs the members and the threshold into a proposal, binding it to the quorum
+    ///
+    /// # Arguments
+    /// * `proposal`: The proposal being built
+    fn absorb_into(&self, proposal: &mut ProposalBuilder) {
+        proposal.field(&[self.threshold]);
+        for member in &self.members {
+            proposal.field(member.as_ref());
+        }
+    }
+}
+
+/// Digest of a message submitted to the approval of the members of a [`Quorum`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuorumProposal([u8; 32]);
+
+impl QuorumProposal {
+    /// Approves the proposal, signing it with the [`Identity`] of a member. `DID` identities are
+    /// not supported, as they cannot create detached signatures.
+    ///
+    /// # Arguments
+    /// * `identity`: The [`Identity`] of the member
+    pub fn approve(&self, identity: &Identity) -> Result<QuorumApproval> {
+        let signature = identity
+            .sign_detached(&self.0)
+            .map_err(|e| Error::Wrapped("sign quorum proposal", e))?;
+        Ok(QuorumApproval::new(identity.identifier().clone(), signature))
+    }
+
+    /// Returns the bytes of the digest
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Signature of a [`QuorumProposal`] by a member of a [`Quorum`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct QuorumApproval {
+    /// The [`Identifier`] of the member
+    member: Identifier,
+    /// The detached signature of the proposal
+    signature: Vec<u8>,
+}
+
+impl QuorumApproval {
+    /// Creates a [`QuorumApproval`] from its parts, like an approval received from a member
+    ///
+    /// # Arguments
+    /// * `member`: The [`Identifier`] of the member
+    /// * `signature`: The detached signature of the proposal
+    pub fn new(member: Identifier, signature: Vec<u8>) -> Self {
+        Self { member, signature }
+    }
+
+    /// Returns the [`Identifier`] of the member
+    pub fn member(&self) -> &Identifier {
+        &self.member
+    }
+
+    /// Returns the detached signature of the proposal
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+}
+
+impl Mask<&QuorumApproval> for sizeof::Context {
+    fn mask(&mut self, approval: &QuorumApproval) -> SpongosResult<&mut Self> {
+        self.mask(&approval.member)?.mask(Bytes::new(&approval.signature))
+    }
+}
+
+impl<OS, F> Mask<&QuorumApproval> for wrap::Context<OS, F>
+where
+    F: PRP,
+    OS: io::OStream,
+{
+    fn mask(&mut self, approval: &QuorumApproval) -> SpongosResult<&mut Self> {
+        self.mask(&approval.member)?.mask(Bytes::new(&approval.signature))
+    }
+}
+
+impl<IS, F> Mask<&mut QuorumApproval> for unwrap::Context<IS, F>
+where
+    F: PRP,
+    IS: io::IStream,
+{
+    fn mask(&mut self, approval: &mut QuorumApproval) -> SpongosResult<&mut Self> {
+        self.mask(&mut approval.member)?
+            .mask(Bytes::new(&mut approval.signature))
+    }
+}
+
+/// Hash of the fields of a [`QuorumProposal`], each prefixed by its length
+struct ProposalBuilder(Spongos<KeccakF1600>);
+
+impl ProposalBuilder {
+    /// Starts a proposal in a domain
+    ///
+    /// # Arguments
+    /// * `domain`: The domain separation tag of the kind of message
+    fn new(domain: &[u8]) -> Self {
+        let mut spongos = Spongos::<KeccakF1600>::init();
+        spongos.absorb(domain);
+        Self(spongos)
+    }
+
+    /// Absorbs a field of the message
+    ///
+    /// # Arguments
+    /// * `field`: The encoded field
+    fn field(&mut self, field: &[u8]) -> &mut Self {
+        self.0.absorb((field.len() as u64).to_le_bytes());
+        self.0.absorb(field);
+        self
+    }
+
+    /// Returns the digest of the fields absorbed
+    fn finish(mut self) -> QuorumProposal {
+        self.0.commit();
+        QuorumProposal(self.0.squeeze())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::{
+        id::{Ed25519, Identity},
+        message::Topic,
+    };
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Error, Result, User,
+    };
+
+    use super::Quorum;
+
+    #[tokio::test]
+    async fn quorum_streams_require_the_approval_of_the_threshold_of_members() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("operator a", &transport);
+        let mut successor = new_user("successor", &transport);
+        let mut reader = new_user("reader", &transport);
+        let operator_b: Identity = Ed25519::from_seed("operator b").into();
+        let operator_c: Identity = Ed25519::from_seed("operator c").into();
+        let author_id = author.identifier().unwrap().clone();
+        let members = vec![
+            author_id.clone(),
+            operator_b.identifier().clone(),
+            operator_c.identifier().clone(),
+        ];
+        assert!(matches!(
+            Quorum::new(vec![author_id.clone(), author_id.clone()], 1),
+            Err(Error::QuorumInvalid(_))
+        ));
+        assert!(matches!(Quorum::new(members.clone(), 4), Err(Error::QuorumInvalid(_))));
+        let quorum = Quorum::new(members, 2)?;
+
+        // The announcement is only sent once approved by the threshold of members
+        let topic: Topic = "BASE_BRANCH".into();
+        let proposal = quorum.announcement_proposal(&author_id, &topic);
+        let approvals = [proposal.approve(&operator_b)?, proposal.approve(&operator_c)?];
+        assert!(matches!(
+            author
+                .create_stream_with_quorum(topic.clone(), quorum.clone(), &approvals[..1])
+                .await,
+            Err(Error::QuorumNotReached(1, 2))
+        ));
+        let announcement = author
+            .create_stream_with_quorum(topic.clone(), quorum.clone(), &approvals)
+            .await?;
+        let announced = reader.receive_message(announcement.address()).await?;
+        assert_eq!(announced.as_announcement().unwrap().quorum.as_ref(), Some(&quorum));
+        assert_eq!(reader.quorum(), Some(&quorum));
+        successor.receive_message(announcement.address()).await?;
+
+        // Readers reject the announcements the quorum did not approve
+        let mut rogue = new_user("rogue", &transport);
+        let rogue_announcement = rogue
+            .announce_stream(topic.clone(), 0, Some((quorum.clone(), &approvals[..])))
+            .await?;
+        let mut victim = new_user("victim", &transport);
+        assert!(matches!(
+            victim.receive_message(rogue_announcement.address()).await,
+            Err(Error::QuorumNotReached(0, 2))
+        ));
+
+        // Author rotations require the approval of the quorum too
+        let successor_id = successor.identifier().unwrap().clone();
+        assert!(matches!(
+            author.rotate_author(successor_id.clone()).await,
+            Err(Error::QuorumNotReached(0, 2))
+        ));
+        let proposal = author.author_rotation_proposal(&successor_id)?;
+        let approvals = [proposal.approve(&operator_b)?, proposal.approve(&operator_c)?];
+        assert!(matches!(
+            author
+                .rotate_author_with_approvals(successor_id.clone(), &approvals[..1])
+                .await,
+            Err(Error::QuorumNotReached(1, 2))
+        ));
+        author
+            .rotate_author_with_approvals(successor_id.clone(), &approvals)
+            .await?;
+        assert_eq!(author.author(), Some(&successor_id));
+
+        let rotations = reader.fetch_next_messages().await?;
+        assert_eq!(rotations.len(), 1);
+        assert_eq!(rotations[0].as_author_rotation().unwrap().approvals, approvals);
+        assert_eq!(reader.author(), Some(&successor_id));
+
+        // The quorum is kept in backups
+        let backup = reader.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(restored.quorum(), Some(&quorum));
+        Ok(())
+    }
+}
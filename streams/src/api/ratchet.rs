@@ -0,0 +1,294 @@
+//! Key ratchets of the branches in forward secrecy mode
+//!
+//! A keyload sent by a [`User`](crate::User) built with
+//! [`UserBuilder::forward_secrecy()`](crate::UserBuilder) seeds a chain of keys for every publisher
+//! of the branch. Each packet of a publisher absorbs the key of its sequence number, after which
+//! the chain advances: previous keys cannot be derived from the current chain key. The keys of the
+//! sequence numbers a chain skips, when packets are received out of order, are kept until their
+//! packet is processed, and a chain never skips more than [`MAX_SKIP`] keys at once. Together with
+//! the [`Spongos::ratchet()`] applied to the stored message states, a compromise of the state of a
+//! [`User`](crate::User) does not reveal the packets it had already processed.
+
+// Rust
+use alloc::{collections::BTreeMap, vec::Vec};
+
+// 3rd-party
+use hashbrown::HashMap;
+
+// IOTA
+
+// Streams
+use lets::{id::Identifier, message::Topic};
+use spongos::{KeccakF1600, Spongos};
+
+// Local
+
+/// Size of the chain and message keys
+pub(crate) const RATCHET_KEY_SIZE: usize = 32;
+
+/// Maximum number of message keys a chain derives to reach the key of a message, and of skipped
+/// keys it keeps. Sequence numbers are read from the message headers, so the bound keeps a message
+/// with a forged sequence number from making its readers derive keys endlessly.
+pub(crate) const MAX_SKIP: usize = 1000;
+
+/// Chain of message keys of a publisher on a branch
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Ratchet {
+    /// Sequence number of the next message key the chain can produce
+    sequence: usize,
+    /// Key the next message keys are derived from
+    chain_key: [u8; RATCHET_KEY_SIZE],
+    /// Message keys of the sequence numbers the chain advanced past without their message being
+    /// processed, kept for the messages received out of order
+    skipped: BTreeMap<usize, [u8; RATCHET_KEY_SIZE]>,
+}
+
+impl Ratchet {
+    /// Creates a [`Ratchet`] positioned at the provided sequence number
+    ///
+    /// # Arguments
+    /// * `sequence`: Sequence number of the next message key of the chain
+    /// * `chain_key`: Key the next message keys are derived from
+    pub(crate) fn new(sequence: usize, chain_key: [u8; RATCHET_KEY_SIZE]) -> Self {
+        Self {
+            sequence,
+            chain_key,
+            skipped: BTreeMap::new(),
+        }
+    }
+
+    /// Restores the message keys of the sequence numbers the chain advanced past without their
+    /// message being processed
+    ///
+    /// # Arguments
+    /// * `skipped`: The skipped message keys, mapped by sequence number
+    pub(crate) fn with_skipped(mut self, skipped: BTreeMap<usize, [u8; RATCHET_KEY_SIZE]>) -> Self {
+        self.skipped = skipped;
+        self
+    }
+
+    /// Seeds the chains of the publishers of a branch from the [`Spongos`] state of a keyload.
+    /// Every chain starts at sequence number 0.
+    ///
+    /// # Arguments
+    /// * `keyload_spongos`: The [`Spongos`] state of the keyload, before it is ratcheted
+    /// * `publishers`: The [`Identifier`] of the publishers allowed to write to the branch
+    pub(crate) fn seed<'a, I>(keyload_spongos: &Spongos, publishers: I) -> HashMap<Identifier, Self>
+    where
+        I: IntoIterator<Item = &'a Identifier>,
+    {
+        publishers
+            .into_iter()
+            .map(|publisher| {
+                let mut spongos = *keyload_spongos;
+                spongos.absorb(publisher);
+                spongos.commit();
+                (publisher.clone(), Self::new(0, spongos.squeeze()))
+            })
+            .collect()
+    }
+
+    /// Returns the sequence number of the next message key of the chain
+    pub(crate) fn sequence(&self) -> usize {
+        self.sequence
+    }
+
+    /// Returns the key the next message keys are derived from
+    pub(crate) fn chain_key(&self) -> &[u8; RATCHET_KEY_SIZE] {
+        &self.chain_key
+    }
+
+    /// Returns the message keys of the sequence numbers the chain advanced past without their
+    /// message being processed, mapped by sequence number
+    pub(crate) fn skipped(&self) -> &BTreeMap<usize, [u8; RATCHET_KEY_SIZE]> {
+        &self.skipped
+    }
+
+    /// Returns true if reaching the key of the provided sequence number requires skipping more
+    /// than [`MAX_SKIP`] keys
+    ///
+    /// # Arguments
+    /// * `sequence`: The sequence number of the message
+    pub(crate) fn exceeds_skip(&self, sequence: usize) -> bool {
+        sequence.saturating_sub(self.sequence) > MAX_SKIP
+    }
+
+    /// Returns the message key of the provided sequence number, advancing the chain past it if
+    /// needed. The keys of the sequence numbers skipped on the way are kept, the oldest ones being
+    /// dropped beyond [`MAX_SKIP`] keys. Returns `None` if the key of a past sequence number is no
+    /// longer known, because its message was already processed or its key dropped, or if reaching
+    /// it [exceeds the skip bound](`Ratchet::exceeds_skip`).
+    ///
+    /// # Arguments
+    /// * `sequence`: The sequence number of the message
+    pub(crate) fn message_key(&mut self, sequence: usize) -> Option<[u8; RATCHET_KEY_SIZE]> {
+        if sequence < self.sequence {
+            return self.skipped.remove(&sequence);
+        }
+        if self.exceeds_skip(sequence) {
+            return None;
+        }
+        while self.sequence < sequence {
+            let skipped_sequence = self.sequence;
+            let key = self.step();
+            self.skipped.insert(skipped_sequence, key);
+        }
+        while self.skipped.len() > MAX_SKIP {
+            self.skipped.pop_first();
+        }
+        Some(self.step())
+    }
+
+    /// Replaces the chain key with the next one, returning the message key of the current sequence
+    /// number
+    fn step(&mut self) -> [u8; RATCHET_KEY_SIZE] {
+        let mut spongos = Spongos::<KeccakF1600>::init();
+        spongos.absorb(self.chain_key);
+        spongos.commit();
+        self.chain_key = spongos.squeeze();
+        self.sequence += 1;
+        spongos.squeeze()
+    }
+}
+
+/// Switches a branch in or out of forward secrecy mode after a keyload. In forward secrecy mode,
+/// the ratchets of the publishers of the branch are seeded from the keyload state, which is then
+/// ratcheted itself so that the seeds cannot be recovered from the stored state.
+///
+/// # Arguments
+/// * `ratchets`: The ratchets of the branches, mapped by branch topic
+/// * `topic`: The [`Topic`] of the branch
+/// * `forward_secrecy`: Whether the keyload enables forward secrecy
+/// * `keyload_spongos`: The [`Spongos`] state of the keyload
+/// * `publishers`: The [`Identifier`] of the publishers allowed to write to the branch
+pub(crate) fn apply_keyload<'a, I>(
+    ratchets: &mut HashMap<Topic, HashMap<Identifier, Ratchet>>,
+    topic: &Topic,
+    forward_secrecy: bool,
+    keyload_spongos: &mut Spongos,
+    publishers: I,
+) where
+    I: IntoIterator<Item = &'a Identifier>,
+{
+    if forward_secrecy {
+        ratchets.insert(topic.clone(), Ratchet::seed(keyload_spongos, publishers));
+        keyload_spongos.ratchet();
+    } else {
+        ratchets.remove(topic);
+    }
+}
+
+/// Returns the ratchets of the branches flattened into `(topic, publisher, ratchet)` tuples, the
+/// layout they are backed up with
+pub(crate) fn flatten(ratchets: &HashMap<Topic, HashMap<Identifier, Ratchet>>) -> Vec<(&Topic, &Identifier, &Ratchet)> {
+    ratchets
+        .iter()
+        .flat_map(|(topic, branch)| {
+            branch
+                .iter()
+                .map(move |(publisher, ratchet)| (topic, publisher, ratchet))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use lets::{
+        id::{Ed25519, Identity},
+        message::Topic,
+    };
+    use spongos::Spongos;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Error, Result, User,
+    };
+
+    use super::{Ratchet, MAX_SKIP, RATCHET_KEY_SIZE};
+
+    #[test]
+    fn chains_only_move_forward() {
+        let publisher: Identity = Ed25519::from_seed("publisher").into();
+        let mut keyload_spongos = Spongos::init();
+        keyload_spongos.absorb(b"keyload");
+        keyload_spongos.commit();
+
+        let mut sender = Ratchet::seed(&keyload_spongos, [publisher.identifier()])[publisher.identifier()].clone();
+        let mut reader = sender.clone();
+
+        let first = sender.message_key(1).unwrap();
+        let third = sender.message_key(3).unwrap();
+        assert_ne!(first, third);
+
+        // A reader skipping messages derives the same keys
+        assert_eq!(reader.message_key(3), Some(third));
+        assert_eq!(reader.sequence(), 4);
+        // Keys of processed messages are forgotten
+        assert_eq!(reader.message_key(3), None);
+        assert_eq!(sender.message_key(1), None);
+    }
+
+    #[test]
+    fn skipped_keys_are_kept_for_messages_received_out_of_order() {
+        let mut sender = Ratchet::new(0, [1; RATCHET_KEY_SIZE]);
+        let mut reader = sender.clone();
+        let keys: Vec<_> = (0..4).map(|sequence| sender.message_key(sequence).unwrap()).collect();
+
+        assert_eq!(reader.message_key(3), Some(keys[3]));
+        assert_eq!(reader.skipped().len(), 3);
+        assert_eq!(reader.message_key(1), Some(keys[1]));
+        assert_eq!(reader.message_key(0), Some(keys[0]));
+        assert_eq!(reader.message_key(2), Some(keys[2]));
+        assert!(reader.skipped().is_empty());
+        // Each key is only used once
+        assert_eq!(reader.message_key(1), None);
+    }
+
+    #[test]
+    fn skips_are_bounded() {
+        let mut reader = Ratchet::new(0, [1; RATCHET_KEY_SIZE]);
+        assert!(reader.exceeds_skip(usize::MAX));
+        assert_eq!(reader.message_key(usize::MAX), None);
+        assert_eq!(reader.sequence(), 0);
+
+        // Skipping up to the bound is allowed, the skipped keys being capped as well
+        assert!(reader.message_key(MAX_SKIP).is_some());
+        assert_eq!(reader.skipped().len(), MAX_SKIP);
+        assert!(reader.message_key(2 * MAX_SKIP).is_some());
+        assert_eq!(reader.skipped().len(), MAX_SKIP);
+        assert_eq!(reader.message_key(0), None);
+    }
+
+    #[tokio::test]
+    async fn forward_secret_packets_cannot_be_read_twice() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport).forward_secrecy().build();
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        let first = author.send_signed_packet("BASE_BRANCH", b"public", b"first").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"second").await?;
+
+        subscriber.sync().await?;
+        let topic = Topic::from("BASE_BRANCH");
+        assert!(author.is_forward_secret(&topic));
+        assert!(subscriber.is_forward_secret(&topic));
+
+        // The key of the first packet has been erased once the second one was read
+        assert!(matches!(
+            subscriber.receive_message(first.address()).await,
+            Err(Error::RatchetUnavailable(..))
+        ));
+
+        let backup = author.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(author, restored);
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+//! Verifiable citations of messages of other channels
+//!
+//! A [`Reference`] cites a message by its [`Address`], application address and message id, and by
+//! the digest of its binary encoding. Packets sent with
+//! [`User::send_citing_packet()`](crate::User::send_citing_packet) carry their references masked
+//! and signed along the payload, returned to the readers by
+//! [`Message::references()`](crate::Message::references). The cited message cannot be altered
+//! afterwards without the citation noticing it: readers synced with the channel of the cited
+//! message check it with [`User::verify_reference()`](crate::User::verify_reference), and holders
+//! of the binary message with [`Reference::matches()`].
+//!
+//! ```ddml
+//! type Reference {
+//!     mask    u8  address[52];
+//!     mask    u8  digest[32];
+//! }
+//! ```
+
+// Rust
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{address::Address, message::TransportMessage};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Mask},
+        io,
+        types::NBytes,
+    },
+    error::Result as SpongosResult,
+};
+
+// Local
+use crate::api::notarizer::{self, DIGEST_SIZE};
+
+/// Citation of a message, possibly of another channel, binding its [`Address`] to the digest of its
+/// binary encoding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// The [`Address`] of the cited message
+    address: Address,
+    /// The digest of the binary encoding of the cited message
+    digest: [u8; DIGEST_SIZE],
+}
+
+impl Reference {
+    /// Creates a [`Reference`] from the [`Address`] of a message and the digest of its encoding
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the cited message
+    /// * `digest`: The digest of the binary encoding of the cited message
+    pub fn new(address: Address, digest: [u8; DIGEST_SIZE]) -> Self {
+        Self { address, digest }
+    }
+
+    /// Creates a [`Reference`] citing a binary message
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the cited message
+    /// * `msg`: The binary encoding of the cited message
+    pub fn to_message(address: Address, msg: &TransportMessage) -> Self {
+        Self::new(address, notarizer::message_hash(msg))
+    }
+
+    /// Returns the [`Address`] of the cited message
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the digest of the binary encoding of the cited message
+    pub fn digest(&self) -> &[u8; DIGEST_SIZE] {
+        &self.digest
+    }
+
+    /// Returns true if a binary message is the message cited by the [`Reference`]
+    ///
+    /// # Arguments
+    /// * `msg`: The binary encoding of the message fetched at the cited [`Address`]
+    pub fn matches(&self, msg: &TransportMessage) -> bool {
+        notarizer::message_hash(msg) == self.digest
+    }
+}
+
+impl Mask<&Reference> for sizeof::Context {
+    fn mask(&mut self, reference: &Reference) -> SpongosResult<&mut Self> {
+        self.mask(&reference.address)?.mask(NBytes::new(&reference.digest))
+    }
+}
+
+impl<OS> Mask<&Reference> for wrap::Context<OS>
+where
+    OS: io::OStream,
+{
+    fn mask(&mut self, reference: &Reference) -> SpongosResult<&mut Self> {
+        self.mask(&reference.address)?.mask(NBytes::new(&reference.digest))
+    }
+}
+
+impl<IS> Mask<&mut Reference> for unwrap::Context<IS>
+where
+    IS: io::IStream,
+{
+    fn mask(&mut self, reference: &mut Reference) -> SpongosResult<&mut Self> {
+        self.mask(&mut reference.address)?
+            .mask(NBytes::new(&mut reference.digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::fixtures::{new_reader, new_transport, new_user},
+        Error, Result,
+    };
+
+    use super::Reference;
+
+    #[tokio::test]
+    async fn references_to_other_channels_are_verified_by_their_readers() -> Result<()> {
+        let transport = new_transport();
+        let mut supplier = new_user("supplier", &transport);
+        let mut manufacturer = new_user("manufacturer", &transport);
+        let mut supplier_reader = new_reader(&transport);
+        let mut manufacturer_reader = new_reader(&transport);
+
+        let supplies = supplier.create_stream("LOTS").await?;
+        let lot = supplier.send_signed_packet("LOTS", b"lot", b"steel 42").await?;
+        let products = manufacturer.create_stream("PRODUCTS").await?;
+        let reference = manufacturer.cite(lot.address()).await?;
+        manufacturer
+            .send_citing_packet("PRODUCTS", b"product", b"valve 7", &[reference])
+            .await?;
+
+        manufacturer_reader.receive_message(products.address()).await?;
+        let messages = manufacturer_reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].masked_payload(), Some(&b"valve 7"[..]));
+        let cited = messages[0].references()[0];
+        assert_eq!(cited, reference);
+        assert!(matches!(
+            manufacturer_reader.verify_reference(&cited).await,
+            Err(Error::ReferenceUnverifiable(address)) if address == lot.address()
+        ));
+
+        // The citation is verified by the readers of the channel of the cited message, once synced
+        supplier_reader.receive_message(supplies.address()).await?;
+        assert!(matches!(
+            supplier_reader.verify_reference(&cited).await,
+            Err(Error::ReferenceUnverifiable(_))
+        ));
+        supplier_reader.sync().await?;
+        assert!(supplier_reader.verify_reference(&cited).await?);
+        let altered = Reference::new(lot.address(), [0; 32]);
+        assert!(!supplier_reader.verify_reference(&altered).await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! Deduplication of the payloads repeated by a publisher
+//!
+//! Heartbeat-like publishers send the same payloads over and over. A [`User`](crate::User) built
+//! with [`UserBuilder::with_payload_deduplication()`](crate::UserBuilder::with_payload_deduplication) sends a
+//! signed packet whose payloads are identical to the ones of its last signed packet on the branch
+//! as a repeated packet, carrying the message id of the packet that first carried the payloads and
+//! their digest instead of the payloads. Every user remembers the last payloads of each publisher
+//! on each branch, and restores the payloads of the repeated packets it reads, which are returned
+//! as plain signed packets.
+//!
+//! Repeated packets are signed packets flagging the
+//! [`REPEATED_PAYLOADS`](crate::message::signed_packet::REPEATED_PAYLOADS) extension:
+//!
+//! ```ddml
+//! message SignedPacket {
+//!     join(spongos);
+//!     absorb external    u8      ratchet_key[32]; // forward secrecy branches only
+//!     mask                u8      identifier;
+//!     mask                u8      extensions;     // REPEATED_PAYLOADS
+//!     mask                u8      original[12];
+//!     mask                u8      payload_digest[32];
+//!     commit;
+//!     squeeze external    u8      hash[64];
+//!     ed25519(hash)       u8      signature[64];
+//! }
+//! ```
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::address::MsgId;
+use spongos::{KeccakF1600, Spongos};
+
+// Local
+
+/// Size of the digest of the payloads of a signed packet
+pub(crate) const PAYLOAD_DIGEST_SIZE: usize = 32;
+
+/// Reference of a repeated packet to the packet that first carried its payloads
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct RepeatedPayloads {
+    /// The [`MsgId`] of the packet that first carried the payloads
+    pub(crate) original: MsgId,
+    /// The digest of the payloads
+    pub(crate) digest: [u8; PAYLOAD_DIGEST_SIZE],
+}
+
+/// Payloads of the last signed packet of a publisher on a branch
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct LastPayloads {
+    /// The [`MsgId`] of the packet that first carried the payloads
+    pub(crate) original: MsgId,
+    /// The payload that was not masked
+    pub(crate) public_payload: Vec<u8>,
+    /// The payload that was masked
+    pub(crate) masked_payload: Vec<u8>,
+}
+
+impl LastPayloads {
+    /// Creates the [`LastPayloads`] of a packet
+    ///
+    /// # Arguments
+    /// * `original`: The [`MsgId`] of the packet
+    /// * `public_payload`: The payload that was not masked
+    /// * `masked_payload`: The payload that was masked
+    pub(crate) fn new(original: MsgId, public_payload: Vec<u8>, masked_payload: Vec<u8>) -> Self {
+        Self {
+            original,
+            public_payload,
+            masked_payload,
+        }
+    }
+
+    /// Returns the [`RepeatedPayloads`] a packet repeating the payloads refers to them with
+    pub(crate) fn repeated(&self) -> RepeatedPayloads {
+        RepeatedPayloads {
+            original: self.original,
+            digest: payload_digest(&self.public_payload, &self.masked_payload),
+        }
+    }
+
+    /// Returns true if the payloads are the ones a repeated packet refers to
+    ///
+    /// # Arguments
+    /// * `repeated`: The [`RepeatedPayloads`] of the repeated packet
+    pub(crate) fn matches(&self, repeated: &RepeatedPayloads) -> bool {
+        self.repeated() == *repeated
+    }
+}
+
+/// Returns the digest of the payloads of a signed packet
+///
+/// # Arguments
+/// * `public_payload`: The payload that is not masked
+/// * `masked_payload`: The payload that is masked
+pub(crate) fn payload_digest(public_payload: &[u8], masked_payload: &[u8]) -> [u8; PAYLOAD_DIGEST_SIZE] {
+    let mut spongos = Spongos::<KeccakF1600>::init();
+    spongos.absorb((public_payload.len() as u64).to_be_bytes());
+    spongos.absorb(public_payload);
+    spongos.absorb((masked_payload.len() as u64).to_be_bytes());
+    spongos.absorb(masked_payload);
+    spongos.commit();
+    spongos.squeeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::transport::Transport as _;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Result, User,
+    };
+
+    #[tokio::test]
+    async fn repeated_payloads_are_sent_as_references() -> Result<()> {
+        let mut transport = new_transport();
+        let mut author = new_user_builder("author", &transport)
+            .with_payload_deduplication()
+            .build();
+        let mut reader = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let heartbeat = [7; 512];
+        let first = author.send_signed_packet("BASE_BRANCH", b"beat", heartbeat).await?;
+        let second = author.send_signed_packet("BASE_BRANCH", b"beat", heartbeat).await?;
+        let other = author.send_signed_packet("BASE_BRANCH", b"beat", b"other").await?;
+        let third = author.send_signed_packet("BASE_BRANCH", b"beat", b"other").await?;
+
+        // Only the first packet carries the payloads
+        let first_size = transport.recv_message(first.address()).await?.as_ref().len();
+        let second_size = transport.recv_message(second.address()).await?.as_ref().len();
+        assert!(second_size + heartbeat.len() / 2 < first_size);
+
+        // The payloads of the repeated packets are restored, also after a backup
+        reader.receive_message(announcement.address()).await?;
+        let msg = reader.receive_message(first.address()).await?;
+        assert_eq!(msg.masked_payload(), Some(&heartbeat[..]));
+        let backup = reader.backup("password").await?;
+        let mut reader = User::restore(backup, "password", transport.clone()).await?;
+        let msg = reader.receive_message(second.address()).await?;
+        assert_eq!(msg.public_payload(), Some(&b"beat"[..]));
+        assert_eq!(msg.masked_payload(), Some(&heartbeat[..]));
+        reader.receive_message(other.address()).await?;
+        let msg = reader.receive_message(third.address()).await?;
+        assert_eq!(msg.masked_payload(), Some(&b"other"[..]));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,435 @@
+//! Deterministic replay of the messages processed by a [`User`](crate::User)
+//!
+//! A [`ReplayRecorder`] handed to a [`User`](crate::User) with
+//! [`UserBuilder::with_replay_recorder()`](crate::UserBuilder::with_replay_recorder) or
+//! [`User::set_replay_recorder()`](crate::User::set_replay_recorder) captures the address and raw
+//! bytes of every message the user processes, in processing order, together with the outcome of
+//! the processing. The resulting [`ReplayLog`] can be fed to
+//! [`User::replay()`](crate::User::replay) on a user restored from an earlier backup, which
+//! processes the same messages in the same order without any transport, reproducing the
+//! evolution of the state of the original user offline. The [steps](ReplayStep) of the replay
+//! point out the messages whose outcome differs from the recorded one.
+
+// Rust
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{address::Address, message::TransportMessage};
+use spongos::ddml::{
+    commands::{sizeof, unwrap, wrap, Absorb},
+    types::{Bytes, Uint8},
+};
+
+// Local
+use crate::{api::message::Message, Error, Result};
+
+#[cfg(feature = "std")]
+extern crate std;
+
+/// Layout of the encoded replay logs, written as their first byte
+const REPLAY_LOG_VERSION: u8 = 0;
+
+const ACCEPTED: u8 = 0;
+const REJECTED: u8 = 1;
+
+/// Outcome of the processing of a message
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReplayOutcome {
+    /// The message was processed successfully
+    Accepted,
+    /// The processing of the message failed with the error described
+    Rejected(String),
+}
+
+impl ReplayOutcome {
+    /// Returns true if the message was processed successfully
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, Self::Accepted)
+    }
+}
+
+impl From<&Result<Message>> for ReplayOutcome {
+    fn from(handled: &Result<Message>) -> Self {
+        match handled {
+            Ok(_) => Self::Accepted,
+            Err(e) => Self::Rejected(e.to_string()),
+        }
+    }
+}
+
+/// A message processed by a [`User`](crate::User), with the outcome of its processing
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReplayEntry {
+    /// The address of the message
+    address: Address,
+    /// The raw message, as returned by the transport
+    message: TransportMessage,
+    /// The outcome of the processing of the message
+    outcome: ReplayOutcome,
+}
+
+impl ReplayEntry {
+    /// Creates a new [`ReplayEntry`]
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `message`: The raw [`TransportMessage`]
+    /// * `outcome`: The [`ReplayOutcome`] of the processing of the message
+    pub fn new(address: Address, message: TransportMessage, outcome: ReplayOutcome) -> Self {
+        Self {
+            address,
+            message,
+            outcome,
+        }
+    }
+
+    /// Returns the [`Address`] of the message
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the raw [`TransportMessage`]
+    pub fn message(&self) -> &TransportMessage {
+        &self.message
+    }
+
+    /// Returns the [`ReplayOutcome`] of the processing of the message
+    pub fn outcome(&self) -> &ReplayOutcome {
+        &self.outcome
+    }
+
+    /// Encodes the entry, to be appended to an encoded [`ReplayLog`]
+    fn encode(&self) -> Result<Vec<u8>> {
+        let (outcome, error) = match &self.outcome {
+            ReplayOutcome::Accepted => (ACCEPTED, ""),
+            ReplayOutcome::Rejected(error) => (REJECTED, error.as_str()),
+        };
+        let mut ctx = sizeof::Context::new();
+        ctx.absorb(&self.address)?
+            .absorb(Bytes::new(self.message.as_ref()))?
+            .absorb(Uint8::new(outcome))?
+            .absorb(Bytes::new(error))?;
+        let mut buf = vec![0; ctx.finalize()];
+
+        let mut ctx = wrap::Context::new(&mut buf[..]);
+        ctx.absorb(&self.address)?
+            .absorb(Bytes::new(self.message.as_ref()))?
+            .absorb(Uint8::new(outcome))?
+            .absorb(Bytes::new(error))?;
+        Ok(buf)
+    }
+
+    /// Decodes the entry at the start of an encoded [`ReplayLog`], advancing the context past it
+    ///
+    /// # Arguments
+    /// * `ctx`: The [`unwrap::Context`] reading the encoded log
+    fn decode(ctx: &mut unwrap::Context<&[u8]>) -> Result<Self> {
+        let mut address = Address::default();
+        let mut message = Vec::new();
+        let mut outcome = Uint8::default();
+        let mut error = Vec::new();
+        ctx.absorb(&mut address)?
+            .absorb(Bytes::new(&mut message))?
+            .absorb(&mut outcome)?
+            .absorb(Bytes::new(&mut error))?;
+        let outcome = match outcome.inner() {
+            ACCEPTED => ReplayOutcome::Accepted,
+            _ => ReplayOutcome::Rejected(String::from_utf8_lossy(&error).into_owned()),
+        };
+        Ok(Self::new(address, TransportMessage::new(message), outcome))
+    }
+}
+
+/// The messages processed by a [`User`](crate::User), in processing order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayLog {
+    /// The recorded entries
+    entries: Vec<ReplayEntry>,
+}
+
+impl ReplayLog {
+    /// Creates a new empty [`ReplayLog`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded entries, in processing order
+    pub fn entries(&self) -> &[ReplayEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of recorded entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no entry is recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Appends an entry to the log
+    ///
+    /// # Arguments
+    /// * `entry`: The [`ReplayEntry`] to append
+    pub fn push(&mut self, entry: ReplayEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Encodes the log, in the same layout as the files written by [`ReplayRecorder::create()`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![REPLAY_LOG_VERSION];
+        for entry in &self.entries {
+            bytes.extend(entry.encode()?);
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes an encoded log. A log cut short in the middle of an entry, like a file whose writer
+    /// crashed, fails to decode.
+    ///
+    /// # Arguments
+    /// * `bytes`: The encoded log
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let body = match bytes.split_first() {
+            Some((&REPLAY_LOG_VERSION, body)) => body,
+            Some((&version, _)) => return Err(Error::ReplayLogVersion(version)),
+            None => return Ok(Self::new()),
+        };
+        let mut ctx = unwrap::Context::new(body);
+        let mut log = Self::new();
+        while !ctx.stream().is_empty() {
+            log.push(ReplayEntry::decode(&mut ctx)?);
+        }
+        Ok(log)
+    }
+
+    /// Reads a log from a file written by a [`ReplayRecorder`]
+    ///
+    /// # Arguments
+    /// * `path`: The path of the file
+    #[cfg(feature = "std")]
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let bytes = std::fs::read(path).map_err(|e| io_error("read", e))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Recorder of the messages processed by a [`User`](crate::User), into a [`ReplayLog`] kept in
+/// memory or, with the `std` feature, into a file
+pub struct ReplayRecorder {
+    /// The entries recorded in memory. Stays empty when recording into a file.
+    log: ReplayLog,
+    /// The file the entries are appended to, if any
+    #[cfg(feature = "std")]
+    file: Option<std::fs::File>,
+    /// Number of entries recorded
+    recorded: usize,
+    /// The first failure to write an entry. No further entry is written once writing has failed.
+    write_error: Option<Error>,
+}
+
+impl ReplayRecorder {
+    /// Creates a new [`ReplayRecorder`] keeping the recorded entries in memory
+    pub fn new() -> Self {
+        Self {
+            log: ReplayLog::new(),
+            #[cfg(feature = "std")]
+            file: None,
+            recorded: 0,
+            write_error: None,
+        }
+    }
+
+    /// Creates a new [`ReplayRecorder`] appending the recorded entries to a file, replacing the
+    /// file if it exists. Each entry is written as soon as its message is processed, so that the
+    /// file can be read with [`ReplayLog::open()`] at any time, even after a crash of the
+    /// application.
+    ///
+    /// # Arguments
+    /// * `path`: The path of the file
+    #[cfg(feature = "std")]
+    pub fn create<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        use std::io::Write;
+
+        let mut file = std::fs::File::create(path).map_err(|e| io_error("create", e))?;
+        file.write_all(&[REPLAY_LOG_VERSION])
+            .map_err(|e| io_error("write", e))?;
+        Ok(Self {
+            file: Some(file),
+            ..Self::new()
+        })
+    }
+
+    /// Returns the number of entries recorded
+    pub fn recorded(&self) -> usize {
+        self.recorded
+    }
+
+    /// Returns the first failure to write an entry into the file of the recorder, if any. The
+    /// entries following a failure are not written.
+    pub fn write_error(&self) -> Option<&Error> {
+        self.write_error.as_ref()
+    }
+
+    /// Returns the entries recorded in memory. The log is empty when recording into a file.
+    pub fn log(&self) -> &ReplayLog {
+        &self.log
+    }
+
+    /// Consumes the recorder, returning the entries recorded in memory. The log is empty when
+    /// recording into a file.
+    pub fn into_log(self) -> ReplayLog {
+        self.log
+    }
+
+    /// Records the processing of a message
+    ///
+    /// # Arguments
+    /// * `entry`: The [`ReplayEntry`] of the message
+    pub(crate) fn record(&mut self, entry: ReplayEntry) {
+        self.recorded += 1;
+        #[cfg(feature = "std")]
+        if let Some(file) = &mut self.file {
+            use std::io::Write;
+
+            if self.write_error.is_none() {
+                let written = entry
+                    .encode()
+                    .and_then(|bytes| file.write_all(&bytes).map_err(|e| io_error("write", e)));
+                self.write_error = written.err();
+            }
+            return;
+        }
+        self.log.push(entry);
+    }
+}
+
+impl Default for ReplayRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for ReplayRecorder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ReplayRecorder")
+            .field("recorded", &self.recorded)
+            .field("write_error", &self.write_error)
+            .finish()
+    }
+}
+
+/// A message processed again by [`User::replay()`](crate::User::replay)
+#[derive(Debug)]
+pub struct ReplayStep {
+    /// The address of the message
+    address: Address,
+    /// The outcome recorded when the message was first processed
+    recorded: ReplayOutcome,
+    /// The outcome of the replay
+    replayed: Result<Message>,
+}
+
+impl ReplayStep {
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `recorded`: The [`ReplayOutcome`] recorded when the message was first processed
+    /// * `replayed`: The outcome of the replay
+    pub(crate) fn new(address: Address, recorded: ReplayOutcome, replayed: Result<Message>) -> Self {
+        Self {
+            address,
+            recorded,
+            replayed,
+        }
+    }
+
+    /// Returns the [`Address`] of the message
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the [`ReplayOutcome`] recorded when the message was first processed
+    pub fn recorded(&self) -> &ReplayOutcome {
+        &self.recorded
+    }
+
+    /// Returns the outcome of the replay: the processed [`Message`], or the processing error
+    pub fn replayed(&self) -> &Result<Message> {
+        &self.replayed
+    }
+
+    /// Returns true if the outcome of the replay differs from the recorded outcome, including a
+    /// different processing error
+    pub fn diverged(&self) -> bool {
+        ReplayOutcome::from(&self.replayed) != self.recorded
+    }
+}
+
+/// Wraps an IO error of a replay log file
+///
+/// # Arguments
+/// * `action`: The action that failed
+/// * `error`: The IO error
+#[cfg(feature = "std")]
+fn io_error(action: &'static str, error: std::io::Error) -> Error {
+    Error::External(anyhow::anyhow!("failed to {} replay log: {}", action, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Result, User,
+    };
+
+    use super::{ReplayLog, ReplayRecorder};
+
+    #[tokio::test]
+    async fn replaying_a_recorded_log_reproduces_the_state_of_the_user() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user_builder("reader", &transport)
+            .with_replay_recorder(ReplayRecorder::new())
+            .build();
+        let backup = reader.backup("password").await?;
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let keyload = author.send_keyload_for_all("BASE_BRANCH").await?;
+        let signed = author.send_signed_packet("BASE_BRANCH", "public", b"masked").await?;
+        let tagged = author.send_tagged_packet("BASE_BRANCH", "public", b"masked").await?;
+        reader.receive_message(announcement.address()).await?;
+        // The reader is not granted access to the branch, the packets may be rejected
+        for address in [keyload.address(), signed.address(), tagged.address()] {
+            let _ = reader.receive_message(address).await;
+        }
+
+        let recorder = reader.take_replay_recorder().unwrap();
+        assert_eq!(recorder.recorded(), 4);
+        let log = ReplayLog::from_bytes(&recorder.into_log().to_bytes()?)?;
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.entries()[0].address(), announcement.address());
+
+        // Replayed without any message on the transport
+        let offline = new_transport();
+        let mut replayed = User::restore(backup, "password", offline).await?;
+        let steps = replayed.replay(&log).await;
+        assert_eq!(steps.len(), 4);
+        assert!(steps.iter().all(|step| !step.diverged()));
+        assert_eq!(replayed, reader);
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! Mapping of the branches of a stream to the addresses of their next messages
+//!
+//! Every message of a stream is published at an [`Address`] made of the base address of the stream
+//! and of a relative address derived by the [`LinkGenerator`](lets::address::LinkGenerator) from
+//! its publisher, branch and sequence number. A [`RoutingTable`], obtained with
+//! [`User::routing_table()`](crate::User::routing_table), lists the addresses the
+//! [`Messages`](crate::Messages) streams of a user probe next, publisher by publisher, so that
+//! indexers and caching nodes can watch their tangle indexes, and prefetch the messages, before
+//! the user syncs.
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use lets::{address::Address, id::Identifier, message::Topic};
+
+// Local
+
+/// Addresses of the next messages of the publishers of the branches of a stream
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RoutingTable {
+    /// The [`Address`] of the announcement of the stream
+    stream_address: Address,
+    /// The route of each publisher of each branch
+    routes: Vec<Route>,
+}
+
+/// Addresses of the next messages of a publisher in a branch
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Route {
+    /// The [`Topic`] of the branch
+    topic: Topic,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+    /// The sequence number of the last message of the publisher read in the branch
+    cursor: usize,
+    /// The addresses of the messages following the cursor, in sequence order
+    next_addresses: Vec<Address>,
+}
+
+impl RoutingTable {
+    /// Creates a new [`RoutingTable`] without routes
+    ///
+    /// # Arguments
+    /// * `stream_address`: The [`Address`] of the announcement of the stream
+    pub(crate) fn new(stream_address: Address) -> Self {
+        Self {
+            stream_address,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Adds a [`Route`] to the table
+    ///
+    /// # Arguments
+    /// * `route`: The [`Route`] of a publisher in a branch
+    pub(crate) fn with_route(mut self, route: Route) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    /// Returns the [`Address`] of the announcement of the stream, whose base address is shared by
+    /// all the messages of the stream
+    pub fn stream_address(&self) -> Address {
+        self.stream_address
+    }
+
+    /// Returns an iterator over the routes of the table
+    pub fn routes(&self) -> impl Iterator<Item = &Route> + ExactSizeIterator {
+        self.routes.iter()
+    }
+
+    /// Returns the [`Route`] of a publisher in a branch, if the publisher is followed in the branch
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    pub fn route(&self, topic: &Topic, publisher: &Identifier) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|route| &route.topic == topic && &route.publisher == publisher)
+    }
+
+    /// Returns an iterator over the addresses of the next messages of every route
+    pub fn addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        self.routes
+            .iter()
+            .flat_map(|route| route.next_addresses().iter().copied())
+    }
+
+    /// Returns an iterator over the tangle indexes of the next messages of every route, as
+    /// computed by [`Address::to_msg_index()`]
+    pub fn msg_indexes(&self) -> impl Iterator<Item = [u8; 32]> + '_ {
+        self.addresses().map(Address::to_msg_index)
+    }
+
+    /// Returns the number of routes of the table
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns true if the table has no route
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+impl Route {
+    /// Creates a new [`Route`]
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `cursor`: The sequence number of the last message of the publisher read in the branch
+    /// * `next_addresses`: The addresses of the messages following the cursor, in sequence order
+    pub(crate) fn new(topic: Topic, publisher: Identifier, cursor: usize, next_addresses: Vec<Address>) -> Self {
+        Self {
+            topic,
+            publisher,
+            cursor,
+            next_addresses,
+        }
+    }
+
+    /// Returns the [`Topic`] of the branch
+    pub fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Returns the [`Identifier`] of the publisher
+    pub fn publisher(&self) -> &Identifier {
+        &self.publisher
+    }
+
+    /// Returns the sequence number of the last message of the publisher read in the branch
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the addresses of the messages following the cursor, in sequence order
+    pub fn next_addresses(&self) -> &[Address] {
+        &self.next_addresses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::message::Topic;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Result,
+    };
+
+    #[tokio::test]
+    async fn routing_table_lists_the_addresses_of_the_next_messages() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user("reader", &transport);
+        assert!(reader.routing_table().is_none());
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"", b"first").await?;
+        reader.receive_message(announcement.address()).await?;
+        reader.sync().await?;
+        reader.set_sync_lookahead(3);
+
+        let table = reader.routing_table().unwrap();
+        assert_eq!(table.stream_address(), announcement.address());
+        let route = table
+            .route(&Topic::from("BASE_BRANCH"), author.identifier().unwrap())
+            .unwrap();
+        assert_eq!(route.next_addresses().len(), 3);
+
+        // The next messages of the author are published at the addresses of the route
+        let second = author.send_signed_packet("BASE_BRANCH", b"", b"second").await?;
+        let third = author.send_signed_packet("BASE_BRANCH", b"", b"third").await?;
+        assert_eq!(&route.next_addresses()[..2], [second.address(), third.address()]);
+        assert!(table.msg_indexes().any(|index| index == third.address().to_msg_index()));
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+//! Async runtimes the background tasks of the users run on
+//!
+//! The background tasks, like the one started by
+//! [`User::start_auto_sync_on()`](crate::User::start_auto_sync_on), spawn futures and sleep between
+//! rounds through a [`Runtime`], so that they run on the executor of the application rather than
+//! requiring a specific one. [`TokioRuntime`] or, when targeting `wasm32`, [`WasmRuntime`] is
+//! always available, and [`AsyncStdRuntime`] with the `async-std-runtime` feature.
+//! [`DefaultRuntime`] is the runtime used by
+//! [`User::start_auto_sync()`](crate::User::start_auto_sync).
+//!
+//! The browser event loop runs on a single thread, so the futures spawned on `wasm32` need not be
+//! [`Send`]: the [`TaskSend`] bound of the futures is only [`Send`] on the other targets.
+
+// Rust
+use alloc::boxed::Box;
+use core::{future::Future, pin::Pin, time::Duration};
+
+// 3rd-party
+use futures::{
+    future::{self, Either},
+    pin_mut,
+};
+
+// IOTA
+
+// Streams
+
+// Local
+use crate::Result;
+
+/// Alias of [`Send`], implemented for every type when targeting `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+pub trait TaskSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> TaskSend for T {}
+
+/// Alias of [`Send`], implemented for every type when targeting `wasm32`
+#[cfg(target_arch = "wasm32")]
+pub trait TaskSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> TaskSend for T {}
+
+/// Boxed future run by a [`Runtime`], [`Send`] unless targeting `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+pub type TaskFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Boxed future run by a [`Runtime`], [`Send`] unless targeting `wasm32`
+#[cfg(target_arch = "wasm32")]
+pub type TaskFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Future returned by [`Runtime::sleep()`]
+pub type Sleep = TaskFuture<()>;
+
+/// Executor the background tasks of the users are spawned on, and timer they sleep with
+pub trait Runtime: Clone + Send + Sync + 'static {
+    /// Spawns a future, running it to completion in the background
+    ///
+    /// # Arguments
+    /// * `future`: The future to run
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + TaskSend + 'static;
+
+    /// Returns a future completing once the duration elapsed
+    ///
+    /// # Arguments
+    /// * `duration`: The time to sleep for
+    fn sleep(&self, duration: Duration) -> Sleep;
+
+    /// Awaits a future for at most the provided duration. Returns `None` if the duration elapsed
+    /// first, dropping the future.
+    ///
+    /// # Arguments
+    /// * `duration`: The time to wait for the future
+    /// * `future`: The future to await
+    fn timeout<F>(&self, duration: Duration, future: F) -> TaskFuture<Option<F::Output>>
+    where
+        F: Future + TaskSend + 'static,
+    {
+        let sleep = self.sleep(duration);
+        Box::pin(async move {
+            pin_mut!(future);
+            match future::select(future, sleep).await {
+                Either::Left((output, _)) => Some(output),
+                Either::Right(_) => None,
+            }
+        })
+    }
+}
+
+/// [`Runtime`] spawning on a `tokio` runtime. Sleeps do not require the `time` driver of `tokio`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct TokioRuntime(tokio::runtime::Handle);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TokioRuntime {
+    /// Returns the [`TokioRuntime`] spawning on the `tokio` runtime the caller runs in. Errors with
+    /// [`crate::Error::RuntimeUnavailable`] when called outside of a `tokio` runtime.
+    pub fn current() -> Result<Self> {
+        tokio::runtime::Handle::try_current()
+            .map(Self)
+            .map_err(|_| crate::Error::RuntimeUnavailable("tokio"))
+    }
+
+    /// Creates a [`TokioRuntime`] spawning on the `tokio` runtime of a handle
+    ///
+    /// # Arguments
+    /// * `handle`: The handle of the `tokio` runtime
+    pub fn new(handle: tokio::runtime::Handle) -> Self {
+        Self(handle)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Runtime for TokioRuntime {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + TaskSend + 'static,
+    {
+        self.0.spawn(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(futures_timer::Delay::new(duration))
+    }
+}
+
+/// [`Runtime`] spawning on the `async-std` executor
+#[cfg(all(feature = "async-std-runtime", not(target_arch = "wasm32")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AsyncStdRuntime;
+
+#[cfg(all(feature = "async-std-runtime", not(target_arch = "wasm32")))]
+impl Runtime for AsyncStdRuntime {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + TaskSend + 'static,
+    {
+        async_std::task::spawn(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
+
+/// [`Runtime`] spawning on the event loop of the browser
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WasmRuntime;
+
+#[cfg(target_arch = "wasm32")]
+impl WasmRuntime {
+    /// Returns the [`WasmRuntime`], the event loop of the browser being always available
+    pub fn current() -> Result<Self> {
+        Ok(Self)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Runtime for WasmRuntime {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + TaskSend + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(futures_timer::Delay::new(duration))
+    }
+}
+
+/// [`Runtime`] used when none is provided: [`TokioRuntime`], or [`WasmRuntime`] when targeting
+/// `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+pub type DefaultRuntime = TokioRuntime;
+
+/// [`Runtime`] used when none is provided: [`TokioRuntime`], or [`WasmRuntime`] when targeting
+/// `wasm32`
+#[cfg(target_arch = "wasm32")]
+pub type DefaultRuntime = WasmRuntime;
@@ -0,0 +1,171 @@
+//! Window of the messages processed by a user, to detect the messages received again
+//!
+//! The transport can deliver a message several times, and a [`User`](crate::User) syncing from
+//! several sources, or rereading an address, would otherwise process it again, moving the cursor
+//! of its publisher back and returning it twice. The user remembers the digests of the last
+//! messages it processed and surfaces the messages received again as
+//! [`MessageContent::DuplicateReceived`](crate::MessageContent::DuplicateReceived), without
+//! processing them.
+
+// Rust
+use alloc::collections::VecDeque;
+
+// 3rd-party
+use hashbrown::HashSet;
+
+// IOTA
+
+// Streams
+use lets::{address::Address, message::TransportMessage};
+use spongos::{KeccakF1600, Spongos};
+
+// Local
+
+/// Size of the digest identifying a processed message
+pub const SEEN_DIGEST_SIZE: usize = 32;
+
+/// Number of processed messages remembered by default
+pub(crate) const DEFAULT_REPLAY_WINDOW: usize = 1024;
+
+/// Digests of the last messages processed by a user, oldest first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SeenMessages {
+    /// Number of digests remembered. Zero disables the detection of duplicates.
+    capacity: usize,
+    /// The remembered digests, in processing order
+    order: VecDeque<[u8; SEEN_DIGEST_SIZE]>,
+    /// The remembered digests, for lookups
+    digests: HashSet<[u8; SEEN_DIGEST_SIZE]>,
+}
+
+impl SeenMessages {
+    /// Creates a new empty [`SeenMessages`] window
+    ///
+    /// # Arguments
+    /// * `capacity`: The number of digests remembered
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            digests: HashSet::new(),
+        }
+    }
+
+    /// Returns the number of digests remembered
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the number of digests remembered, forgetting the oldest ones if needed
+    ///
+    /// # Arguments
+    /// * `capacity`: The new number of digests remembered
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Returns the digests remembered, oldest first
+    pub(crate) fn digests(&self) -> impl Iterator<Item = &[u8; SEEN_DIGEST_SIZE]> + ExactSizeIterator {
+        self.order.iter()
+    }
+
+    /// Returns the digest identifying a message at an address, or `None` if the detection of
+    /// duplicates is disabled
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `msg`: The raw [`TransportMessage`]
+    pub(crate) fn digest(&self, address: Address, msg: &TransportMessage) -> Option<[u8; SEEN_DIGEST_SIZE]> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut spongos = Spongos::<KeccakF1600>::init();
+        spongos.absorb(address.base());
+        spongos.absorb(address.relative());
+        spongos.absorb(msg);
+        spongos.commit();
+        Some(spongos.squeeze())
+    }
+
+    /// Returns true if the digest is remembered
+    ///
+    /// # Arguments
+    /// * `digest`: The digest of the message
+    pub(crate) fn contains(&self, digest: &[u8; SEEN_DIGEST_SIZE]) -> bool {
+        self.digests.contains(digest)
+    }
+
+    /// Remembers a digest, forgetting the oldest one if the window is full
+    ///
+    /// # Arguments
+    /// * `digest`: The digest of the processed message
+    pub(crate) fn insert(&mut self, digest: [u8; SEEN_DIGEST_SIZE]) {
+        if self.capacity == 0 || !self.digests.insert(digest) {
+            return;
+        }
+        self.order.push_back(digest);
+        self.evict();
+    }
+
+    /// Forgets the oldest digests beyond the capacity
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.digests.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl Default for SeenMessages {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::fixtures::{new_reader, new_transport, new_user},
+        Result, User,
+    };
+
+    #[tokio::test]
+    async fn messages_received_again_are_reported_as_duplicates() -> Result<()> {
+        let transport = new_transport();
+        let mut bank = new_user("bank", &transport);
+        let mut auditor = new_reader(&transport);
+
+        let announcement = bank.create_stream("LEDGER").await?;
+        let first = bank.send_signed_packet("LEDGER", b"", b"first").await?;
+        bank.send_signed_packet("LEDGER", b"", b"second").await?;
+        auditor.receive_message(announcement.address()).await?;
+        assert_eq!(auditor.fetch_next_messages().await?.len(), 2);
+
+        // The packet is not processed again, the cursor of the bank stays on the second packet
+        let duplicate = auditor.receive_message(first.address()).await?;
+        assert!(duplicate.is_duplicate());
+        assert_eq!(duplicate.masked_payload(), None);
+        let third = bank.send_signed_packet("LEDGER", b"", b"third").await?;
+        let messages = auditor.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].address(), third.address());
+
+        // The window is kept in the backups
+        let backup = auditor.backup("password").await?;
+        let mut restored = User::restore(backup, "password", transport.clone()).await?;
+        assert_eq!(restored.replay_window(), 1024);
+        assert!(restored.receive_message(first.address()).await?.is_duplicate());
+        restored.set_replay_window(1);
+        assert!(restored.receive_message(third.address()).await?.is_duplicate());
+
+        let mut forgetful = User::builder()
+            .with_transport(transport.clone())
+            .with_replay_window(0)
+            .build();
+        forgetful.receive_message(announcement.address()).await?;
+        assert!(!forgetful.receive_message(announcement.address()).await?.is_duplicate());
+        Ok(())
+    }
+}
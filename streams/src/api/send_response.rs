@@ -1,12 +1,17 @@
-use lets::address::Address;
+use lets::{
+    address::Address,
+    message::MESSAGE_DIGEST_SIZE,
+    transport::{SendReceipt, SentBlock},
+};
 
 /// A wrapper for a sent message
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
 pub struct SendResponse<TSR> {
     /// [`Address`] of the message that was sent
     address: Address,
-    /// The Transport Send Response
-    transport_response: TSR,
+    /// The [`SendReceipt`] of the blocks the message was sent as, holding the Transport Send
+    /// Response
+    receipt: SendReceipt<TSR>,
 }
 
 impl<TSR> SendResponse<TSR> {
@@ -14,12 +19,9 @@ impl<TSR> SendResponse<TSR> {
     ///
     /// # Arguments
     /// * `address`: The [`Address`] of the message that was sent
-    /// * `transport_response`: The transport send response
-    pub(crate) fn new(address: Address, transport_response: TSR) -> Self {
-        Self {
-            address,
-            transport_response,
-        }
+    /// * `receipt`: The receipt of the transport for the message
+    pub(crate) fn new(address: Address, receipt: SendReceipt<TSR>) -> Self {
+        Self { address, receipt }
     }
 
     /// Returns the [`Address`] of the message
@@ -29,11 +31,123 @@ impl<TSR> SendResponse<TSR> {
 
     /// Returns a reference to the transport send response of the message
     pub fn response(&self) -> &TSR {
-        &self.transport_response
+        &self.receipt.response
     }
 
     /// Consumes the [`SendResponse`], returning the transport send response of the message
     pub fn into_response(self) -> TSR {
-        self.transport_response
+        self.receipt.response
+    }
+
+    /// Returns the [`SendReceipt`] of the message: the blocks it was sent as, and the index they
+    /// were tagged with
+    pub fn receipt(&self) -> &SendReceipt<TSR> {
+        &self.receipt
+    }
+
+    /// Consumes the [`SendResponse`], returning the [`SendReceipt`] of the message
+    pub fn into_receipt(self) -> SendReceipt<TSR> {
+        self.receipt
+    }
+
+    /// Returns the blocks the message was sent as, with the time they were sent at
+    pub fn blocks(&self) -> &[SentBlock] {
+        &self.receipt.blocks
+    }
+
+    /// Returns the index tag the blocks of the message were published under
+    pub fn index(&self) -> &[u8] {
+        &self.receipt.index
+    }
+
+    /// Returns the content digest of the message, the `Blake2b256` hash of its bytes, matching the
+    /// [`Message::digest()`](crate::Message::digest) of the message read
+    pub fn digest(&self) -> Option<&[u8; MESSAGE_DIGEST_SIZE]> {
+        self.receipt.digest.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
+    use core::cell::RefCell;
+
+    use async_trait::async_trait;
+    use lets::{
+        address::Address,
+        message::TransportMessage,
+        transport::{bucket, SendReceipt, SentBlock, Transport as _},
+    };
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Result,
+    };
+
+    /// Bucket transport reporting every message as published twice, the mirror block being
+    /// timestamped by the transport
+    struct MirroringTransport(bucket::Client);
+
+    #[async_trait(?Send)]
+    impl<'a> lets::transport::Transport<'a> for MirroringTransport {
+        type Msg = TransportMessage;
+        type SendResponse = TransportMessage;
+
+        async fn send_message(
+            &mut self,
+            address: Address,
+            msg: TransportMessage,
+        ) -> lets::error::Result<TransportMessage>
+        where
+            'a: 'async_trait,
+        {
+            self.0.send_message(address, msg).await
+        }
+
+        async fn send_message_receipt(
+            &mut self,
+            address: Address,
+            msg: TransportMessage,
+        ) -> lets::error::Result<SendReceipt<TransportMessage>>
+        where
+            'a: 'async_trait,
+        {
+            let response = self.0.send_message(address, msg).await?;
+            let blocks = vec![SentBlock::new(vec![1], None), SentBlock::new(vec![2], Some(42))];
+            Ok(SendReceipt::new(b"mirrored".to_vec(), blocks, response))
+        }
+
+        async fn recv_messages(&mut self, address: Address) -> lets::error::Result<Vec<TransportMessage>>
+        where
+            'a: 'async_trait,
+        {
+            self.0.recv_messages(address).await
+        }
+    }
+
+    #[tokio::test]
+    async fn send_responses_hold_the_receipt_of_every_block_sent() -> Result<()> {
+        let mut author = new_user("author", &new_transport());
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        assert_eq!(announcement.index(), &announcement.address().to_msg_index()[..]);
+        assert_eq!(announcement.blocks().len(), 1);
+        assert!(announcement.blocks()[0].timestamp.is_some());
+
+        let mut author = new_user(
+            "author",
+            &Rc::new(RefCell::new(MirroringTransport(bucket::Client::new()))),
+        );
+        author.create_stream("BASE_BRANCH").await?;
+        let packet = author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+        assert_eq!(packet.index(), b"mirrored");
+        assert_eq!(
+            packet.blocks().iter().map(|block| block.id.clone()).collect::<Vec<_>>(),
+            [vec![1], vec![2]]
+        );
+        // Only the blocks the transport did not timestamp are stamped with the time of the send
+        assert!(packet.blocks()[0].timestamp.is_some());
+        assert_eq!(packet.blocks()[1].timestamp, Some(42));
+        assert_eq!(packet.response(), &packet.receipt().response);
+        Ok(())
     }
 }
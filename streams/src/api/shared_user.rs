@@ -0,0 +1,146 @@
+// Rust
+use alloc::{sync::Arc, vec::Vec};
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use futures::lock::{Mutex, MutexGuard};
+use lets::{
+    address::Address,
+    message::{Topic, TransportMessage},
+    sync::MaybeSend,
+    transport::Transport,
+};
+
+// Local
+use crate::{
+    api::{message::Message, send_response::SendResponse, user::User},
+    Result,
+};
+
+/// A cloneable handle to a [`User`] that can be shared across tasks and threads
+///
+/// Every clone refers to the same [`User`], guarded by an asynchronous [`Mutex`]. Operations are
+/// serialized: a sender and a receiver running in different tasks take turns on the user state,
+/// which keeps cursors and spongos storage consistent. For operations not covered by the
+/// convenience methods of this handle, [`SharedUser::lock()`] gives exclusive access to the
+/// underlying [`User`].
+pub struct SharedUser<T>(Arc<Mutex<User<T>>>);
+
+impl<T> SharedUser<T> {
+    /// Wraps a [`User`] into a new [`SharedUser`] handle
+    ///
+    /// # Arguments
+    /// * `user`: The [`User`] to be shared
+    pub fn new(user: User<T>) -> Self {
+        Self(Arc::new(Mutex::new(user)))
+    }
+
+    /// Acquires exclusive access to the underlying [`User`], waiting for any other holder of the
+    /// lock to release it
+    pub async fn lock(&self) -> MutexGuard<'_, User<T>> {
+        self.0.lock().await
+    }
+
+    /// Attempts to acquire exclusive access to the underlying [`User`] without waiting. Returns
+    /// `None` if the lock is currently held by another task.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, User<T>>> {
+        self.0.try_lock()
+    }
+
+    /// Returns the underlying [`User`] if this is the last handle referring to it, or the handle
+    /// itself otherwise
+    pub fn try_unwrap(self) -> core::result::Result<User<T>, Self> {
+        Arc::try_unwrap(self.0).map(Mutex::into_inner).map_err(Self)
+    }
+}
+
+impl<T> SharedUser<T>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
+{
+    /// Receive a raw message packet using the internal [`Transport`] client
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to be retrieved.
+    pub async fn receive_message(&self, address: Address) -> Result<Message> {
+        self.lock().await.receive_message(address).await
+    }
+
+    /// Iteratively fetches all the next messages until internal state has caught up
+    ///
+    /// If succeeded, returns the number of messages advanced.
+    pub async fn sync(&self) -> Result<usize> {
+        self.lock().await.sync().await
+    }
+
+    /// Iteratively fetches all the pending messages from the transport
+    pub async fn fetch_next_messages(&self) -> Result<Vec<Message>> {
+        self.lock().await.fetch_next_messages().await
+    }
+}
+
+impl<T, TSR> SharedUser<T>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage, SendResponse = TSR> + MaybeSend,
+{
+    /// Create and send a new Signed Packet message to the specified branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    pub async fn send_signed_packet<P, M, Top>(
+        &self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        self.lock()
+            .await
+            .send_signed_packet(topic, public_payload, masked_payload)
+            .await
+    }
+
+    /// Create and send a new Tagged Packet message to the specified branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    pub async fn send_tagged_packet<P, M, Top>(
+        &self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        self.lock()
+            .await
+            .send_tagged_packet(topic, public_payload, masked_payload)
+            .await
+    }
+}
+
+impl<T> Clone for SharedUser<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<User<T>> for SharedUser<T> {
+    fn from(user: User<T>) -> Self {
+        Self::new(user)
+    }
+}
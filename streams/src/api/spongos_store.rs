@@ -0,0 +1,405 @@
+//! Storage of the [`Spongos`] states of the messages processed by a [`User`](crate::User)
+//!
+//! Messages are unwrapped from the state of the message they are linked to, so the
+//! [`User`](crate::User) keeps the state of the messages it processes. By default the states are
+//! held in memory without bound. A [`LruSpongosStore`] holds only the most recently used states in
+//! memory and spills the others to a backing [`SpongosStore`], like a `DirectorySpongosStore`,
+//! reading them back when a message linked to them is processed.
+
+// Rust
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+
+// 3rd-party
+use hashbrown::HashMap;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::MsgId,
+    sync::{MaybeSend, MaybeSync},
+};
+use spongos::Spongos;
+
+// Local
+use crate::Result;
+
+/// Storage of the [`Spongos`] states of the messages, mapped by message link
+pub trait SpongosStore: MaybeSend + MaybeSync {
+    /// Returns the state stored for a message link, if any
+    ///
+    /// # Arguments
+    /// * `link`: The [`MsgId`] link of the message
+    fn get(&mut self, link: &MsgId) -> Result<Option<Spongos>>;
+
+    /// Stores the state of a message, replacing the previous one if any
+    ///
+    /// # Arguments
+    /// * `link`: The [`MsgId`] link of the message
+    /// * `spongos`: The [`Spongos`] state of the message
+    fn insert(&mut self, link: MsgId, spongos: Spongos) -> Result<()>;
+
+    /// Removes the state stored for a message link, returning it if any
+    ///
+    /// # Arguments
+    /// * `link`: The [`MsgId`] link of the message
+    fn remove(&mut self, link: &MsgId) -> Result<Option<Spongos>>;
+
+    /// Returns every stored state together with its message link, to back up the user
+    fn entries(&self) -> Result<Vec<(MsgId, Spongos)>>;
+}
+
+/// States held in memory without bound, the default storage of a [`User`](crate::User)
+impl SpongosStore for HashMap<MsgId, Spongos> {
+    fn get(&mut self, link: &MsgId) -> Result<Option<Spongos>> {
+        Ok(HashMap::get(self, link).copied())
+    }
+
+    fn insert(&mut self, link: MsgId, spongos: Spongos) -> Result<()> {
+        HashMap::insert(self, link, spongos);
+        Ok(())
+    }
+
+    fn remove(&mut self, link: &MsgId) -> Result<Option<Spongos>> {
+        Ok(HashMap::remove(self, link))
+    }
+
+    fn entries(&self) -> Result<Vec<(MsgId, Spongos)>> {
+        Ok(self.iter().map(|(link, spongos)| (*link, *spongos)).collect())
+    }
+}
+
+impl Default for Box<dyn SpongosStore> {
+    fn default() -> Self {
+        Box::new(HashMap::<MsgId, Spongos>::new())
+    }
+}
+
+/// Stores are equal if they hold the same states, wherever the states are held
+impl PartialEq for Box<dyn SpongosStore> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.entries(), other.entries()) {
+            (Ok(mut entries), Ok(mut other_entries)) => {
+                entries.sort_unstable_by_key(|(link, _)| *link);
+                other_entries.sort_unstable_by_key(|(link, _)| *link);
+                entries == other_entries
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Box<dyn SpongosStore> {}
+
+/// [`SpongosStore`] holding the most recently used states in memory, up to a capacity, and spilling
+/// the least recently used ones to a backing [`SpongosStore`]. States read from the backing store
+/// are moved back into memory.
+pub struct LruSpongosStore<S> {
+    /// Maximum number of states held in memory
+    capacity: usize,
+    /// States held in memory, with their last use
+    cache: HashMap<MsgId, (Spongos, u64)>,
+    /// Links of the states held in memory, mapped by last use
+    uses: BTreeMap<u64, MsgId>,
+    /// Counter of the uses of the states
+    clock: u64,
+    /// Store of the states spilled out of memory
+    spill: S,
+}
+
+impl<S> LruSpongosStore<S> {
+    /// Creates a new [`LruSpongosStore`] with nothing held in memory
+    ///
+    /// # Arguments
+    /// * `capacity`: The maximum number of states held in memory
+    /// * `spill`: The [`SpongosStore`] the least recently used states are spilled to
+    pub fn new(capacity: usize, spill: S) -> Self {
+        Self {
+            capacity,
+            cache: HashMap::new(),
+            uses: BTreeMap::new(),
+            clock: 0,
+            spill,
+        }
+    }
+
+    /// Returns the maximum number of states held in memory
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of states held in memory
+    pub fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Returns a reference to the store the states are spilled to
+    pub fn spill(&self) -> &S {
+        &self.spill
+    }
+
+    /// Holds a state in memory as the most recently used one
+    ///
+    /// # Arguments
+    /// * `link`: The [`MsgId`] link of the message
+    /// * `spongos`: The [`Spongos`] state of the message
+    fn touch(&mut self, link: MsgId, spongos: Spongos) {
+        self.clock += 1;
+        if let Some((_, last_use)) = self.cache.insert(link, (spongos, self.clock)) {
+            self.uses.remove(&last_use);
+        }
+        self.uses.insert(self.clock, link);
+    }
+}
+
+impl<S> LruSpongosStore<S>
+where
+    S: SpongosStore,
+{
+    /// Spills the least recently used states until the states held in memory fit the capacity
+    fn evict(&mut self) -> Result<()> {
+        while self.cache.len() > self.capacity {
+            let (last_use, link) = match self.uses.iter().next() {
+                Some((last_use, link)) => (*last_use, *link),
+                None => break,
+            };
+            if let Some((spongos, _)) = self.cache.get(&link).copied() {
+                // Spill before forgetting the state, so that it is not lost if spilling fails
+                self.spill.insert(link, spongos)?;
+            }
+            self.cache.remove(&link);
+            self.uses.remove(&last_use);
+        }
+        Ok(())
+    }
+}
+
+impl<S> SpongosStore for LruSpongosStore<S>
+where
+    S: SpongosStore,
+{
+    fn get(&mut self, link: &MsgId) -> Result<Option<Spongos>> {
+        if let Some((spongos, _)) = self.cache.get(link).copied() {
+            self.touch(*link, spongos);
+            return Ok(Some(spongos));
+        }
+        match self.spill.remove(link)? {
+            Some(spongos) => {
+                self.touch(*link, spongos);
+                self.evict()?;
+                Ok(Some(spongos))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn insert(&mut self, link: MsgId, spongos: Spongos) -> Result<()> {
+        self.spill.remove(&link)?;
+        self.touch(link, spongos);
+        self.evict()
+    }
+
+    fn remove(&mut self, link: &MsgId) -> Result<Option<Spongos>> {
+        match self.cache.remove(link) {
+            Some((spongos, last_use)) => {
+                self.uses.remove(&last_use);
+                Ok(Some(spongos))
+            }
+            None => self.spill.remove(link),
+        }
+    }
+
+    fn entries(&self) -> Result<Vec<(MsgId, Spongos)>> {
+        let mut entries = self.spill.entries()?;
+        entries.extend(self.cache.iter().map(|(link, (spongos, _))| (*link, *spongos)));
+        Ok(entries)
+    }
+}
+
+#[cfg(feature = "std")]
+pub use directory::DirectorySpongosStore;
+
+#[cfg(feature = "std")]
+mod directory {
+    extern crate std;
+
+    // Rust
+    use alloc::{string::ToString, vec::Vec};
+    use core::str::FromStr;
+    use std::{fs, io, path::PathBuf};
+
+    // 3rd-party
+
+    // IOTA
+
+    // Streams
+    use lets::address::MsgId;
+    use spongos::{
+        ddml::commands::{sizeof, unwrap, wrap, Mask},
+        Spongos,
+    };
+
+    // Local
+    use super::SpongosStore;
+    use crate::{Error, Result};
+
+    /// [`SpongosStore`] persisting each state in a file of a directory, named after the link of
+    /// its message
+    pub struct DirectorySpongosStore {
+        /// The directory holding the states
+        path: PathBuf,
+    }
+
+    impl DirectorySpongosStore {
+        /// Opens a [`DirectorySpongosStore`] in a directory, creating the directory if it does not
+        /// exist. States already present in the directory are kept.
+        ///
+        /// # Arguments
+        /// * `path`: The path of the directory
+        pub fn new<P>(path: P) -> Result<Self>
+        where
+            P: Into<PathBuf>,
+        {
+            let path = path.into();
+            fs::create_dir_all(&path).map_err(|e| io_error("create its directory", e))?;
+            Ok(Self { path })
+        }
+
+        /// Returns the path of the file holding the state of a message
+        ///
+        /// # Arguments
+        /// * `link`: The [`MsgId`] link of the message
+        fn file(&self, link: &MsgId) -> PathBuf {
+            self.path.join(link.to_string())
+        }
+
+        /// Reads the state of a message from its file, if any
+        ///
+        /// # Arguments
+        /// * `link`: The [`MsgId`] link of the message
+        fn read(&self, link: &MsgId) -> Result<Option<Spongos>> {
+            match fs::read(self.file(link)) {
+                Ok(bytes) => {
+                    let mut spongos = Spongos::default();
+                    unwrap::Context::new(&bytes[..]).mask(&mut spongos)?;
+                    Ok(Some(spongos))
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(io_error("read a state", e)),
+            }
+        }
+    }
+
+    impl SpongosStore for DirectorySpongosStore {
+        fn get(&mut self, link: &MsgId) -> Result<Option<Spongos>> {
+            self.read(link)
+        }
+
+        fn insert(&mut self, link: MsgId, spongos: Spongos) -> Result<()> {
+            let mut ctx = sizeof::Context::new();
+            ctx.mask(&spongos)?;
+            let mut bytes = vec![0; ctx.finalize()];
+            wrap::Context::new(&mut bytes[..]).mask(&spongos)?;
+            fs::write(self.file(&link), bytes).map_err(|e| io_error("write a state", e))
+        }
+
+        fn remove(&mut self, link: &MsgId) -> Result<Option<Spongos>> {
+            let spongos = self.read(link)?;
+            if spongos.is_some() {
+                fs::remove_file(self.file(link)).map_err(|e| io_error("remove a state", e))?;
+            }
+            Ok(spongos)
+        }
+
+        fn entries(&self) -> Result<Vec<(MsgId, Spongos)>> {
+            let mut entries = Vec::new();
+            for file in fs::read_dir(&self.path).map_err(|e| io_error("list its directory", e))? {
+                let file = file.map_err(|e| io_error("list its directory", e))?;
+                // Files that are not named after a message link are not states of the store
+                let link = match file.file_name().to_str().map(MsgId::from_str) {
+                    Some(Ok(link)) => link,
+                    _ => continue,
+                };
+                if let Some(spongos) = self.read(&link)? {
+                    entries.push((link, spongos));
+                }
+            }
+            Ok(entries)
+        }
+    }
+
+    /// Wraps an IO error of the store
+    ///
+    /// # Arguments
+    /// * `action`: The action that failed
+    /// * `error`: The IO error
+    fn io_error(action: &'static str, error: io::Error) -> Error {
+        Error::External(anyhow::anyhow!("spongos store failed to {}: {}", action, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use hashbrown::HashMap;
+    use lets::address::MsgId;
+    use spongos::Spongos;
+
+    use super::{LruSpongosStore, SpongosStore};
+    use crate::{
+        api::fixtures::{new_transport, new_user_builder},
+        Result, User,
+    };
+
+    #[test]
+    fn lru_store_spills_the_least_recently_used_states() -> Result<()> {
+        let mut store = LruSpongosStore::new(2, HashMap::<MsgId, Spongos>::new());
+        let links: Vec<MsgId> = (0..3).map(|i| MsgId::new([i; 12])).collect();
+        let mut spongos: Spongos = Spongos::init();
+        for link in &links {
+            spongos.absorb(link);
+            store.insert(*link, spongos)?;
+        }
+        // The first state is spilled when the third one is stored
+        assert_eq!(store.cached(), 2);
+        assert!(store.spill().contains_key(&links[0]));
+
+        // Reading the spilled state moves it back into memory, spilling the least recently used one
+        assert!(store.get(&links[0])?.is_some());
+        assert_eq!(store.cached(), 2);
+        assert!(store.spill().contains_key(&links[1]));
+        assert!(!store.spill().contains_key(&links[0]));
+
+        assert!(store.remove(&links[1])?.is_some());
+        assert!(store.get(&links[1])?.is_none());
+        assert_eq!(store.entries()?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spongos_states_spilled_out_of_memory_are_read_back() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport)
+            .with_spongos_store(LruSpongosStore::new(1, HashMap::<MsgId, Spongos>::new()))
+            .build();
+        let mut subscriber = new_user_builder("subscriber", &transport)
+            .with_spongos_store(LruSpongosStore::new(1, HashMap::<MsgId, Spongos>::new()))
+            .build();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        let subscription = subscriber.subscribe().await?;
+        author.receive_message(subscription.address()).await?;
+        // Keyloads are linked to the announcement, which is spilled as soon as a keyload is stored
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"").await?;
+        assert_eq!(subscriber.sync().await?, 3);
+
+        // Backups include the spilled states
+        let restored = User::restore(author.backup("password").await?, "password", transport).await?;
+        assert_eq!(author, restored);
+        Ok(())
+    }
+}
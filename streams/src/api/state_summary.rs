@@ -0,0 +1,292 @@
+//! Inspection of the state of a user and of the changes between two backups
+//!
+//! The state of a [`User`](crate::User) is opaque: it is only persisted as an encrypted backup. A
+//! [`StateSummary`], returned by [`User::state_summary()`](crate::User::state_summary), describes
+//! the parts of the state relevant to debugging and audits: the branches known to the user, the
+//! permissions and cursors of their publishers, the subscribers and the pre-shared keys. Two
+//! summaries are compared with [`StateSummary::diff()`], and two backups with [`diff()`], resulting
+//! in a [`StateDiff`] listing what changed between them.
+
+// Rust
+use alloc::vec::Vec;
+
+// 3rd-party
+
+// IOTA
+
+// Streams
+use hashbrown::{HashMap, HashSet};
+use lets::{
+    address::{Address, MsgId},
+    id::{Identifier, Permissioned, PskId},
+    message::Topic,
+};
+
+// Local
+use crate::{api::user::User, Result};
+
+/// Summary of the state of a [`User`](crate::User)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateSummary {
+    /// The [`Identifier`] of the user, if it has an identity
+    pub identifier: Option<Identifier>,
+    /// The [`Address`] of the stream announcement, if the user created or joined a stream
+    pub stream_address: Option<Address>,
+    /// The [`Identifier`] of the author of the stream, if the user created or joined a stream
+    pub author_identifier: Option<Identifier>,
+    /// The branches known to the user, mapped by topic
+    pub branches: HashMap<Topic, BranchSummary>,
+    /// The subscribers of the stream
+    pub subscribers: HashSet<Identifier>,
+    /// The subscription requests held for review
+    pub pending_subscriptions: HashSet<Identifier>,
+    /// The identifiers of the pre-shared keys stored by the user
+    pub psks: HashSet<PskId>,
+    /// The number of packets queued and not sent yet
+    pub queued_packets: usize,
+}
+
+/// Summary of a branch known to a [`User`](crate::User)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BranchSummary {
+    /// The permission of each publisher of the branch, mapped by publisher [`Identifier`]
+    pub permissions: HashMap<Identifier, Permissioned<Identifier>>,
+    /// The cursor of each publisher of the branch, mapped by publisher [`Identifier`]
+    pub cursors: HashMap<Identifier, usize>,
+    /// The link of the latest message of the branch, if any
+    pub latest_link: Option<MsgId>,
+    /// Whether the branch was closed
+    pub closed: bool,
+}
+
+/// Change of the cursor of a publisher in a branch
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CursorChange {
+    /// The [`Topic`] of the branch
+    pub topic: Topic,
+    /// The [`Identifier`] of the publisher
+    pub publisher: Identifier,
+    /// The cursor before the change, None if the publisher was not tracked
+    pub from: Option<usize>,
+    /// The cursor after the change, None if the publisher is no longer tracked
+    pub to: Option<usize>,
+}
+
+impl CursorChange {
+    /// Returns true if the cursor moved forward, or started to be tracked
+    pub fn is_advance(&self) -> bool {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => to > from,
+            (None, Some(_)) => true,
+            (_, None) => false,
+        }
+    }
+}
+
+/// Change of the permission of a publisher in a branch
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PermissionChange {
+    /// The [`Topic`] of the branch
+    pub topic: Topic,
+    /// The [`Identifier`] of the publisher
+    pub publisher: Identifier,
+    /// The permission before the change, None if the publisher was not tracked
+    pub from: Option<Permissioned<Identifier>>,
+    /// The permission after the change, None if the publisher is no longer tracked
+    pub to: Option<Permissioned<Identifier>>,
+}
+
+/// Changes between two [`StateSummary`]s, as returned by [`StateSummary::diff()`]. Topics are
+/// listed in alphabetical order, identifiers in their natural order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Whether the stream of the user changed
+    pub stream_changed: bool,
+    /// The branches created
+    pub branches_created: Vec<Topic>,
+    /// The branches no longer known, such as pruned epoch branches
+    pub branches_removed: Vec<Topic>,
+    /// The branches closed
+    pub branches_closed: Vec<Topic>,
+    /// The cursors changed, most of them advanced by the messages read or sent
+    pub cursor_changes: Vec<CursorChange>,
+    /// The permissions changed, by keyloads, subscriptions and unsubscriptions
+    pub permission_changes: Vec<PermissionChange>,
+    /// The subscribers added
+    pub subscribers_added: Vec<Identifier>,
+    /// The subscribers removed
+    pub subscribers_removed: Vec<Identifier>,
+    /// The pre-shared keys added
+    pub psks_added: Vec<PskId>,
+    /// The pre-shared keys removed
+    pub psks_removed: Vec<PskId>,
+}
+
+impl StateDiff {
+    /// Returns true if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns the cursors that moved forward, or started to be tracked
+    pub fn cursors_advanced(&self) -> impl Iterator<Item = &CursorChange> {
+        self.cursor_changes.iter().filter(|change| change.is_advance())
+    }
+}
+
+impl StateSummary {
+    /// Returns the changes from this summary to a later one
+    ///
+    /// # Arguments
+    /// * `later`: The [`StateSummary`] to compare this one to
+    pub fn diff(&self, later: &StateSummary) -> StateDiff {
+        let empty = BranchSummary::default();
+        let mut diff = StateDiff {
+            stream_changed: self.stream_address != later.stream_address,
+            branches_created: sorted_topics(
+                later
+                    .branches
+                    .keys()
+                    .filter(|topic| !self.branches.contains_key(*topic)),
+            ),
+            branches_removed: sorted_topics(
+                self.branches
+                    .keys()
+                    .filter(|topic| !later.branches.contains_key(*topic)),
+            ),
+            branches_closed: sorted_topics(
+                later
+                    .branches
+                    .iter()
+                    .filter(|(topic, branch)| branch.closed && !self.branches.get(*topic).map_or(false, |b| b.closed))
+                    .map(|(topic, _)| topic),
+            ),
+            subscribers_added: sorted(later.subscribers.difference(&self.subscribers)),
+            subscribers_removed: sorted(self.subscribers.difference(&later.subscribers)),
+            psks_added: sorted(later.psks.difference(&self.psks)),
+            psks_removed: sorted(self.psks.difference(&later.psks)),
+            ..StateDiff::default()
+        };
+
+        let mut topics: Vec<&Topic> = self.branches.keys().chain(later.branches.keys()).collect();
+        topics.sort_by(|a, b| a.str().cmp(b.str()));
+        topics.dedup();
+        for topic in topics {
+            let before = self.branches.get(topic).unwrap_or(&empty);
+            let after = later.branches.get(topic).unwrap_or(&empty);
+            let mut publishers: Vec<&Identifier> = before.cursors.keys().chain(after.cursors.keys()).collect();
+            publishers.sort();
+            publishers.dedup();
+            for publisher in publishers {
+                let (from, to) = (before.cursors.get(publisher), after.cursors.get(publisher));
+                if from != to {
+                    diff.cursor_changes.push(CursorChange {
+                        topic: topic.clone(),
+                        publisher: publisher.clone(),
+                        from: from.copied(),
+                        to: to.copied(),
+                    });
+                }
+                let (from, to) = (before.permissions.get(publisher), after.permissions.get(publisher));
+                if from != to {
+                    diff.permission_changes.push(PermissionChange {
+                        topic: topic.clone(),
+                        publisher: publisher.clone(),
+                        from: from.cloned(),
+                        to: to.cloned(),
+                    });
+                }
+            }
+        }
+        diff
+    }
+}
+
+/// Returns the changes between two backups encrypted with the same password. Backups created with
+/// a previous layout are migrated before being compared.
+///
+/// # Arguments
+/// * `backup_a`: The earlier backup
+/// * `backup_b`: The later backup
+/// * `pwd`: The password the backups were encrypted with
+pub async fn diff<A, B, P>(backup_a: A, backup_b: B, pwd: P) -> Result<StateDiff>
+where
+    A: AsRef<[u8]>,
+    B: AsRef<[u8]>,
+    P: AsRef<[u8]>,
+{
+    let a = summarize_backup(backup_a.as_ref(), pwd.as_ref()).await?;
+    let b = summarize_backup(backup_b.as_ref(), pwd.as_ref()).await?;
+    Ok(a.diff(&b))
+}
+
+/// Returns the [`StateSummary`] of a backup, migrating it first if it was created with a previous
+/// layout
+///
+/// # Arguments
+/// * `backup`: The encrypted backup
+/// * `pwd`: The password the backup was encrypted with
+async fn summarize_backup(backup: &[u8], pwd: &[u8]) -> Result<StateSummary> {
+    let version = backup.first().copied().unwrap_or_default();
+    let backup = User::<()>::migrate_backup(backup, version, pwd).await?;
+    let user = User::restore(backup, pwd, ()).await?;
+    Ok(user.state_summary())
+}
+
+/// Returns the topics in alphabetical order
+fn sorted_topics<'a>(topics: impl Iterator<Item = &'a Topic>) -> Vec<Topic> {
+    let mut topics: Vec<Topic> = topics.cloned().collect();
+    topics.sort_by(|a, b| a.str().cmp(b.str()));
+    topics
+}
+
+/// Returns the items in their natural order
+fn sorted<'a, I: Clone + Ord + 'a>(items: impl Iterator<Item = &'a I>) -> Vec<I> {
+    let mut items: Vec<I> = items.cloned().collect();
+    items.sort();
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use lets::message::Topic;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Result,
+    };
+
+    use super::diff;
+
+    #[tokio::test]
+    async fn diff_describes_the_changes_between_backups() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        let before = author.backup("password").await?;
+        let summary = author.state_summary();
+        assert_eq!(summary.stream_address, Some(announcement.address()));
+        assert_eq!(summary.branches.len(), 1);
+
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        author.new_branch("BASE_BRANCH", "BRANCH").await?;
+        author.send_keyload_for_all_rw("BRANCH").await?;
+        let after = author.backup("password").await?;
+
+        let subscriber_id = subscriber.identifier().unwrap().clone();
+        let changes = diff(&before, &after, "password").await?;
+        assert!(!changes.stream_changed);
+        assert_eq!(changes.branches_created, [Topic::from("BRANCH")]);
+        assert_eq!(changes.subscribers_added, [subscriber_id.clone()]);
+        assert!(changes
+            .cursors_advanced()
+            .any(|change| change.topic == Topic::from("BRANCH") && change.publisher == subscriber_id));
+        assert!(changes.branches_removed.is_empty() && changes.subscribers_removed.is_empty());
+        assert!(diff(&after, &after, "password").await?.is_empty());
+        Ok(())
+    }
+}
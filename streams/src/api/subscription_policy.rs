@@ -0,0 +1,111 @@
+// Rust
+use alloc::vec::Vec;
+
+// IOTA
+
+// Streams
+use lets::id::Identifier;
+
+// Local
+
+/// Policy applied by a [`User`](crate::User) to the subscription requests it processes
+///
+/// Accepted subscribers are added to the subscribers of the user, and are included in the keyloads
+/// sent for all subscribers. Subscriptions held for review are kept pending until they are accepted
+/// with [`User::accept_subscription()`](crate::User::accept_subscription) or rejected with
+/// [`User::reject_subscription()`](crate::User::reject_subscription).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SubscriptionPolicy {
+    /// Accept every subscription request
+    AcceptAll,
+    /// Accept the subscription requests of the listed [identifiers](`Identifier`), and reject the
+    /// others
+    Allowlist(Vec<Identifier>),
+    /// Hold every subscription request for manual review
+    Manual,
+}
+
+impl Default for SubscriptionPolicy {
+    fn default() -> Self {
+        Self::AcceptAll
+    }
+}
+
+impl SubscriptionPolicy {
+    /// Returns the [`SubscriptionStatus`] a subscription request gets under the policy
+    ///
+    /// # Arguments
+    /// * `subscriber`: The [`Identifier`] of the subscribing user
+    pub fn review(&self, subscriber: &Identifier) -> SubscriptionStatus {
+        match self {
+            Self::AcceptAll => SubscriptionStatus::Accepted,
+            Self::Allowlist(allowed) if allowed.contains(subscriber) => SubscriptionStatus::Accepted,
+            Self::Allowlist(_) => SubscriptionStatus::Rejected,
+            Self::Manual => SubscriptionStatus::Pending,
+        }
+    }
+}
+
+/// Outcome of a subscription request processed by a [`User`](crate::User)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SubscriptionStatus {
+    /// The subscriber has been added to the subscribers of the user
+    Accepted,
+    /// The subscription request has been discarded
+    Rejected,
+    /// The subscription request is held for manual review
+    Pending,
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user, new_user_builder},
+        Result,
+    };
+
+    use super::{SubscriptionPolicy, SubscriptionStatus};
+
+    #[tokio::test]
+    async fn subscriptions_follow_the_subscription_policy() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport)
+            .with_subscription_policy(SubscriptionPolicy::Manual)
+            .build();
+        let mut subscribers = Vec::new();
+        for seed in ["allowed", "reviewed"] {
+            subscribers.push(new_user(seed, &transport));
+        }
+        let allowed = subscribers[0].identifier().unwrap().clone();
+        let reviewed = subscribers[1].identifier().unwrap().clone();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let mut subscriptions = Vec::new();
+        for subscriber in &mut subscribers {
+            subscriber.receive_message(announcement.address()).await?;
+            subscriptions.push(subscriber.subscribe().await?.address());
+        }
+
+        // Manual review holds every request
+        let message = author.receive_message(subscriptions[1]).await?;
+        assert_eq!(message.as_subscription().unwrap().status, SubscriptionStatus::Pending);
+        assert_eq!(author.pending_subscriptions().collect::<Vec<_>>(), [&reviewed]);
+        assert_eq!(author.subscribers().count(), 0);
+        assert!(author.accept_subscription(&reviewed));
+        assert!(!author.accept_subscription(&reviewed));
+        assert_eq!(author.subscribers().collect::<Vec<_>>(), [&reviewed]);
+
+        // Allowlists reject the identifiers that are not listed
+        author.set_subscription_policy(SubscriptionPolicy::Allowlist(vec![allowed.clone()]));
+        let message = author.receive_message(subscriptions[0]).await?;
+        assert_eq!(message.as_subscription().unwrap().status, SubscriptionStatus::Accepted);
+        author.set_subscription_policy(SubscriptionPolicy::Allowlist(Vec::new()));
+        author.remove_subscriber(&allowed);
+        let message = author.receive_message(subscriptions[0]).await?;
+        assert_eq!(message.as_subscription().unwrap().status, SubscriptionStatus::Rejected);
+        assert_eq!(author.subscribers().count(), 1);
+        Ok(())
+    }
+}
@@ -2,8 +2,10 @@
 use alloc::{
     borrow::ToOwned,
     boxed::Box,
+    collections::BTreeMap,
     format,
     string::{String, ToString},
+    sync::Arc,
     vec::Vec,
 };
 use core::fmt::{Debug, Formatter, Result as FormatResult};
@@ -13,45 +15,94 @@ use async_trait::async_trait;
 use futures::{future, TryStreamExt};
 use hashbrown::{HashMap, HashSet};
 use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(any(feature = "json", feature = "cbor"))]
+use serde::Serialize;
 
 // IOTA
+use crypto::keys::x25519;
 
 // Streams
 use lets::{
-    address::{Address, AppAddr, MsgId},
-    id::{Identifier, Identity, PermissionDuration, Permissioned, Psk, PskId},
+    address::{Address, DefaultLinkGenerator, LinkGenerator, LinkInputs, MsgId},
+    error::Error as LetsError,
+    id::{Identifier, Identity, KeyExchange, PermissionDuration, Permissioned, Psk, PskId, PskTree, SignatureBatch},
     message::{
         ContentSizeof, ContentUnwrap, ContentWrap, Message as LetsMessage, PreparsedMessage, Topic, TopicHash,
-        TransportMessage, HDF, PCF,
+        TransportMessage, HDF, MESSAGE_DIGEST_SIZE, PCF,
     },
-    transport::Transport,
+    sync::{MaybeSend, MaybeSync},
+    transport::{MirrorStatus, SendReceipt, Transport},
 };
 use spongos::{
     ddml::{
         commands::{sizeof, unwrap, wrap, Absorb, Commit, Mask, Squeeze},
         modifiers::External,
-        types::{Mac, Maybe, NBytes, Size, Uint8},
+        types::{Bytes, Mac, Maybe, NBytes, Size, Uint64, Uint8},
     },
     error::{Error as SpongosError, Result as SpongosResult},
     KeccakF1600, Spongos, SpongosRng,
 };
 
 // Local
+#[cfg(any(feature = "json", feature = "cbor"))]
+use crate::api::payload;
 use crate::{
     api::{
-        cursor_store::CursorStore, message::Message, message_builder::MessageBuilder, messages::Messages,
-        send_response::SendResponse, user_builder::UserBuilder,
+        batch::{self, Preparsed},
+        branch_metadata::BranchMetadata,
+        branch_rotation::{self, BranchRotation, Epoch, RotationPeriod},
+        clock::{self, Stopwatch},
+        commitment::{commitment_digest, COMMITMENT_DIGEST_SIZE},
+        cursor_export::CursorExport,
+        cursor_store::CursorStore,
+        custom_message::{CustomContent, CustomMessageType, Registered, CUSTOM_MESSAGE_TYPES},
+        descriptor::ChannelDescriptor,
+        detached, discovery,
+        invite::{Invite, InviteToken, INVITE_ID_SIZE},
+        message::{
+            BatchRecord, Commitment, Countersignature, Message, MessageContent, Orphan, SignedPacket, TaggedPacket,
+        },
+        message_builder::MessageBuilder,
+        message_filter::{FilterVerdict, MessageFilter},
+        messages::{Messages, OrphanLimit},
+        metrics::Metrics,
+        notarizer::{self, Notarization, DIGEST_SIZE},
+        payload_middleware::PayloadMiddleware,
+        payload_validator::{PayloadValidator, ValidationVerdict},
+        provenance::{ProvenanceEntry, ProvenanceReport},
+        quorum::{Quorum, QuorumApproval, QuorumProposal},
+        ratchet::{self, Ratchet, RATCHET_KEY_SIZE},
+        reference::Reference,
+        repeated_payloads::{LastPayloads, RepeatedPayloads},
+        replay::{ReplayEntry, ReplayLog, ReplayOutcome, ReplayRecorder, ReplayStep},
+        routing_table::{Route, RoutingTable},
+        seen_messages::{SeenMessages, SEEN_DIGEST_SIZE},
+        send_response::SendResponse,
+        spongos_store::SpongosStore,
+        state_summary::{BranchSummary, StateSummary},
+        subscription_policy::{SubscriptionPolicy, SubscriptionStatus},
+        user_builder::UserBuilder,
     },
     message::{
-        announcement, branch_announcement, keyload, message_types, signed_packet, subscription, tagged_packet,
-        unsubscription,
+        announcement, author_rotation, batch_packet, branch_announcement, branch_closure, commitment, countersignature,
+        custom_message,
+        key_update::{self, ExchangeKey},
+        keyload, legacy, message_types, read_marker, reveal, selective_packet,
+        signed_packet::{self, PacketSignature},
+        subscription, substream_announcement, tagged_packet, tombstone, unsubscription,
     },
     Error, Result,
 };
 
-const ANN_MESSAGE_NUM: usize = 0; // Announcement is always the first message of authors
-const SUB_MESSAGE_NUM: usize = 0; // Subscription is always the first message of subscribers
-const INIT_MESSAGE_NUM: usize = 1; // First non-reserved message number
+pub(crate) const ANN_MESSAGE_NUM: usize = 0; // Announcement is always the first message of authors
+pub(crate) const SUB_MESSAGE_NUM: usize = 0; // Subscription is always the first message of subscribers
+pub(crate) const INIT_MESSAGE_NUM: usize = 1; // First non-reserved message number
+
+const UNVERSIONED_BACKUP: u8 = 0; // Backups created before the version header was introduced
+const BACKUP_VERSION: u8 = 1; // Current layout of the backup, written as its first byte
+
+const DEFAULT_SEND_QUEUE_LIMIT: usize = 1024; // Packets queued before `User::queue_packet` fails
+pub(crate) const DEFAULT_SYNC_LOOKAHEAD: usize = 1; // Cursors probed at once, one keeps the walk serial
 
 /// The state of a user, mapping publisher cursors and link states for message processing.
 #[derive(PartialEq, Eq, Default)]
@@ -71,6 +122,19 @@ struct State {
     /// None if channel is not created or user is not subscribed.
     author_identifier: Option<Identifier>,
 
+    /// [`Identifiers`](Identifier) the stream author rotated away from, which can no longer
+    /// administer the branches of the stream
+    previous_authors: HashSet<Identifier>,
+
+    /// Whether the stream was announced as strict, rejecting unsigned packets and legacy messages.
+    strict: bool,
+
+    /// [`Quorum`] of author keys the stream was announced with, whose approval the author
+    /// rotations require.
+    ///
+    /// None if the stream was not announced with a quorum.
+    quorum: Option<Quorum>,
+
     /// Users' trusted public keys together with additional sequencing info: (msgid, seq_no) mapped
     /// by branch topic Vec.
     cursor_store: CursorStore,
@@ -81,10 +145,31 @@ struct State {
     /// List of Subscribed [Identifiers](`Identifier`).
     subscribers: HashSet<Identifier>,
 
+    /// [Identifiers](`Identifier`) of the readers registered out of band with
+    /// [`User::register_reader()`], without a Subscribe message. Unlike subscribers, they are only
+    /// ever granted read access by the keyloads sent for all participants.
+    readers: HashSet<Identifier>,
+
+    /// List of the [Identifiers](`Identifier`) of the subscription requests held for manual review
+    /// by the [`SubscriptionPolicy`].
+    pending_subscriptions: HashSet<Identifier>,
+
+    /// [Identifiers](`Identifier`) of the subscribers accepted with each [`Invite`], mapped by
+    /// invite id. Bounds the number of uses of the invites.
+    invite_redemptions: HashMap<[u8; INVITE_ID_SIZE], HashSet<Identifier>>,
+
+    /// [Identifiers](`Identifier`) of the subscribers accepted with an [`Invite`] that no keyload
+    /// of the branch of the invite granted access to yet, mapped by branch topic.
+    invite_grants: HashMap<Topic, HashSet<Identifier>>,
+
     /// Mapping of message links ([`MsgId`]) and [`Spongos`] states. Messages are built from the
     /// [`Spongos`] state of a previous message. If the state for a link is not stored, then a
     /// message cannot be formed or processed.
-    spongos_store: HashMap<MsgId, Spongos>,
+    spongos_store: Box<dyn SpongosStore>,
+
+    /// Position in the stream of the messages whose [`Spongos`] state is stored, mapped by message
+    /// link. Used to prune the states of old messages with [`User::prune()`].
+    spongos_positions: HashMap<MsgId, SpongosPosition>,
 
     base_branch: Topic,
 
@@ -94,8 +179,202 @@ struct State {
     /// stored.
     lean: bool,
 
+    /// Users' keyload checkpointing configuration. If set, the [`Spongos`] states of the packets
+    /// and read markers are dropped once another message is linked to them, and recomputed on
+    /// demand from the last state kept, which is usually the one of the last keyload.
+    keyload_checkpoints: bool,
+
+    /// Links of the stored messages whose [`Spongos`] state can be recomputed, and is dropped once
+    /// another message is linked to them. Only filled if the user checkpoints at keyloads.
+    recomputable: HashSet<MsgId>,
+
     /// List of known branch topics.
     topics: HashSet<Topic>,
+
+    /// List of the branch topics closed by a `BranchClosure`. No further message is published in
+    /// a closed branch.
+    closed_branches: HashSet<Topic>,
+
+    /// Users' forward secrecy configuration. If set, the keyloads sent by the user switch their
+    /// branch to forward secrecy mode.
+    forward_secrecy: bool,
+
+    /// Key ratchets of the publishers of the branches in forward secrecy mode, mapped by branch
+    /// topic.
+    ratchets: HashMap<Topic, HashMap<Identifier, Ratchet>>,
+
+    /// Key exchange keys announced by other users with a `KeyUpdate`, replacing the ones derived
+    /// from their identity, mapped by user [`Identifier`].
+    exchange_keys: HashMap<Identifier, ExchangeKey>,
+
+    /// Users' own rotated key exchange key and its generation.
+    ///
+    /// None if the user has never rotated its key exchange key, in which case the one derived from
+    /// its identity is used.
+    exchange_key: Option<RotatedExchangeKey>,
+
+    /// Packets queued with [`User::queue_packet()`] and not sent yet, in queuing order.
+    send_queue: Vec<QueuedPacket>,
+
+    /// Identifier of the next packet queued with [`User::queue_packet()`].
+    next_queued_packet_id: u64,
+
+    /// Digests of the last messages processed, to detect the messages received again.
+    seen_messages: SeenMessages,
+
+    /// Permissions granted by the last keyload of each branch, mapped by branch topic. Unlike the
+    /// cursor store, it keeps the read-only recipients of the keyload.
+    keyload_permissions: HashMap<Topic, Vec<Permissioned<Identifier>>>,
+
+    /// Payloads of the last signed packet of each publisher, mapped by branch topic, to restore the
+    /// payloads of the repeated packets.
+    last_payloads: HashMap<Topic, HashMap<Identifier, LastPayloads>>,
+
+    /// Current epoch of the branches rotated by the user, mapped by the topic of the rotated
+    /// branch.
+    epochs: HashMap<Topic, Epoch>,
+
+    /// Last read marker of each consumer, mapped by the topic of the branch the markers are
+    /// published in.
+    read_markers: HashMap<Topic, HashMap<Identifier, Address>>,
+
+    /// Unix timestamp, in seconds, at which the access granted by the last keyload of a branch
+    /// expires, mapped by branch topic. Branches whose last keyload carries no expiry are not
+    /// included.
+    access_expirations: HashMap<Topic, u64>,
+
+    /// Packets whose payloads were left masked, as they were published after the access to their
+    /// branch expired, read since the last keyload of the branch. The expiry of the access is
+    /// mapped by the [`MsgId`] of the packet, mapped by branch topic. The packets linked to them
+    /// are withheld as well.
+    withheld_packets: HashMap<Topic, HashMap<MsgId, u64>>,
+
+    /// [`MsgId`] of the tombstone redacting a message and the reason of the redaction, mapped by
+    /// the [`MsgId`] of the redacted message.
+    tombstones: HashMap<MsgId, (MsgId, String)>,
+
+    /// Cursors imported with [`User::import_cursors()`] and not reached yet, mapped by branch topic
+    /// and publisher [`Identifier`]. The messages fetched again up to them are processed, to
+    /// rebuild their [`Spongos`] states, but not yielded by the [`Messages`] streams.
+    imported_cursors: HashMap<Topic, HashMap<Identifier, usize>>,
+
+    /// [Identifiers](`Identifier`) of the witnesses whose countersignatures are accepted, added
+    /// with [`User::add_witness()`].
+    witnesses: HashSet<Identifier>,
+
+    /// [`Countersignature`]s of the witnesses, mapped by the [`MsgId`] of the countersigned packet.
+    countersignatures: HashMap<MsgId, Vec<Countersignature>>,
+
+    /// [`Commitment`]s processed or sent by the user, mapped by their [`MsgId`], against which the
+    /// reveals are verified.
+    commitments: HashMap<MsgId, Commitment>,
+
+    /// [`MsgId`] of the last commitment sent by the user and not revealed yet, mapped by branch
+    /// topic.
+    pending_commitments: HashMap<Topic, MsgId>,
+}
+
+/// Key exchange key of a user, replacing the one derived from its identity since it was rotated
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RotatedExchangeKey {
+    /// Secret key held by the user, and its generation
+    Local(usize, [u8; 32]),
+    /// Key held by the [`KeyExchange`] of the user, and its generation
+    External(usize),
+}
+
+impl RotatedExchangeKey {
+    /// Returns the generation of the key
+    fn generation(&self) -> usize {
+        match self {
+            Self::Local(generation, _) | Self::External(generation) => *generation,
+        }
+    }
+
+    /// Returns the secret key, if held by the user
+    fn secret_key(&self) -> Option<x25519::SecretKey> {
+        match self {
+            Self::Local(_, secret_key) => Some(x25519::SecretKey::from_bytes(*secret_key)),
+            Self::External(_) => None,
+        }
+    }
+}
+
+/// Signed packet queued to be sent by [`User::flush()`], listed by [`User::outbox()`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueuedPacket {
+    /// The identifier of the packet, unique among the packets queued by the user, with which it is
+    /// cancelled by [`User::cancel()`]
+    pub id: u64,
+    /// The [`Topic`] of the branch to send the packet to
+    pub topic: Topic,
+    /// The priority of the packet. Packets of higher priority are sent first.
+    pub priority: u8,
+    /// The unmasked payload of the packet
+    pub public_payload: Vec<u8>,
+    /// The masked payload of the packet
+    pub masked_payload: Vec<u8>,
+}
+
+/// Position in the stream of a message whose [`Spongos`] state is stored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct SpongosPosition {
+    /// Publisher cursor of the message
+    sequence: usize,
+    /// Unix timestamp, in seconds, at which the state was stored. None if no clock was available.
+    stored_at: Option<u64>,
+}
+
+impl SpongosPosition {
+    /// Creates the position of a message whose state is stored at the current time
+    ///
+    /// # Arguments
+    /// * `sequence`: The publisher cursor of the message
+    fn now(sequence: usize) -> Self {
+        Self {
+            sequence,
+            stored_at: clock::unix_time(),
+        }
+    }
+
+    /// Returns true if the message precedes the retention point
+    ///
+    /// # Arguments
+    /// * `retention`: The [`Retention`] point to compare the position to
+    fn precedes(&self, retention: Retention) -> bool {
+        match retention {
+            Retention::Cursor(cursor) => self.sequence < cursor,
+            Retention::Time(time) => self.stored_at.map_or(false, |stored_at| stored_at < time),
+        }
+    }
+}
+
+/// Point of the stream before which [`User::prune()`] drops the state of the messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Retention {
+    /// Messages published with a publisher cursor lower than the provided one
+    Cursor(usize),
+    /// Messages processed before the provided Unix timestamp, in seconds
+    Time(u64),
+}
+
+/// Conflicting announcements or keyloads found at the address of a stream message, reported by
+/// [`User::take_conflicts()`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Conflict {
+    /// The [`Address`] shared by the conflicting messages
+    pub address: Address,
+    /// The headers of the conflicting messages, naming the publisher each of them claims
+    pub headers: Vec<HDF>,
+}
+
+/// Direction in which [`User::fetch_history()`] walks a branch from its anchor message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HistoryDirection {
+    /// Towards the messages published before the anchor
+    Backward,
+    /// Towards the messages published after the anchor
+    Forward,
 }
 
 /// Public `API` Client for participation in a `Streams` channel.
@@ -105,6 +384,53 @@ pub struct User<T> {
     /// The internal [state](`State`) of the user, containing message state mappings and publisher
     /// cursors for message processing.
     state: State,
+    /// Bound on the orphan messages buffered by the [`Messages`] streams of the user. Unbounded if
+    /// None.
+    orphan_limit: Option<OrphanLimit>,
+    /// Number of future cursors of a publisher probed at once by the [`Messages`] streams of the
+    /// user.
+    sync_lookahead: usize,
+    /// Derivation of the addresses of the stream messages.
+    link_generator: Box<dyn LinkGenerator>,
+    /// Anchoring and verification of the checkpoints of the branches. Messages are not notarized
+    /// if None.
+    notarization: Option<Notarization>,
+    /// Bound on the lengths and item counts read from the messages processed by the user.
+    size_limit: usize,
+    /// Policy applied to the subscription requests processed by the user.
+    subscription_policy: SubscriptionPolicy,
+    /// Whether the keyloads sent by the user can grant permissions to identifiers that did not
+    /// subscribe.
+    allow_unsubscribed: bool,
+    /// Screening of the messages before they are unwrapped. Every message is processed if None.
+    message_filter: Option<Box<dyn MessageFilter>>,
+    /// Holder of the key exchange key of the user, performing the key agreements of the keys
+    /// encrypted to it. The key is generated and held by the user if None.
+    key_exchange: Option<Box<dyn KeyExchange>>,
+    /// Whether the messages found at the same address are checked for address collisions.
+    collision_diagnostics: bool,
+    /// Whether the signed packets repeating the payloads of the last packet of the user on their
+    /// branch are sent as repeated packets.
+    payload_deduplication: bool,
+    /// Transformation of the masked payloads of the packets sent and read by the user. Payloads
+    /// are sent and read unchanged if None.
+    payload_middleware: Option<PayloadMiddleware>,
+    /// Validation of the payloads of the packets read in each branch. The packets of the branches
+    /// without validator are not validated.
+    payload_validators: HashMap<Topic, Box<dyn PayloadValidator>>,
+    /// Rotation periods of the branches whose packets are published in epoch branches.
+    branch_rotations: HashMap<Topic, RotationPeriod>,
+    /// Custom message types the messages read by the user are unwrapped as, mapped by their
+    /// message type identifier.
+    custom_message_types: HashMap<u8, Box<dyn CustomMessageType>>,
+    /// Bound on the packets queued with [`User::queue_packet()`] and not sent yet.
+    send_queue_limit: usize,
+    /// Registry the metrics of the user are recorded in. Nothing is recorded if None.
+    metrics: Option<Arc<Metrics>>,
+    /// Recording of the messages processed by the user. Nothing is recorded if None.
+    replay_recorder: Option<ReplayRecorder>,
+    /// Conflicts detected while receiving messages, not yet taken by the application.
+    conflicts: Vec<Conflict>,
 }
 
 impl User<()> {
@@ -122,7 +448,54 @@ impl<T> User<T> {
     /// * `psks`: A list of trusted pre shared keys.
     /// * `transport`: The transport to use for sending and receiving messages.
     /// * `lean`: If true, the client will store only required message states.
-    pub(crate) fn new<Psks>(user_id: Option<Identity>, psks: Psks, transport: T, lean: bool) -> Self
+    /// * `keyload_checkpoints`: If true, the client will keep the message states of the keyloads
+    ///   only, recomputing the others when needed.
+    /// * `orphan_limit`: Bound on the orphan messages buffered while fetching messages.
+    /// * `link_generator`: The [`LinkGenerator`] deriving the addresses of the messages.
+    /// * `forward_secrecy`: If true, the keyloads sent by the client enable forward secrecy.
+    /// * `notarization`: The [`Notarization`] of the branches, if any.
+    /// * `size_limit`: Bound on the lengths and item counts read from the processed messages.
+    /// * `subscription_policy`: The [`SubscriptionPolicy`] applied to subscription requests.
+    /// * `spongos_store`: The [`SpongosStore`] holding the [`Spongos`] states of the messages.
+    /// * `allow_unsubscribed`: If true, keyloads can grant permissions to identifiers that did not
+    ///   subscribe.
+    /// * `message_filter`: The [`MessageFilter`] screening the processed messages, if any.
+    /// * `key_exchange`: The [`KeyExchange`] holding the key exchange key of the user, if any.
+    /// * `metrics`: The [`Metrics`] registry the metrics of the user are recorded in, if any.
+    /// * `replay_recorder`: The [`ReplayRecorder`] capturing the processed messages, if any.
+    /// * `replay_window`: Number of processed messages remembered to detect duplicates.
+    /// * `sync_lookahead`: Number of future cursors of a publisher probed at once while fetching
+    ///   messages.
+    /// * `collision_diagnostics`: If true, the messages found at the same address are checked for
+    ///   address collisions.
+    /// * `payload_deduplication`: If true, signed packets repeating the payloads of the last packet
+    ///   of the user on their branch are sent as repeated packets.
+    /// * `payload_middleware`: The [`PayloadMiddleware`] transforming the masked payloads, if any.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new<Psks>(
+        user_id: Option<Identity>,
+        psks: Psks,
+        transport: T,
+        lean: bool,
+        keyload_checkpoints: bool,
+        orphan_limit: Option<OrphanLimit>,
+        link_generator: Box<dyn LinkGenerator>,
+        forward_secrecy: bool,
+        notarization: Option<Notarization>,
+        size_limit: usize,
+        subscription_policy: SubscriptionPolicy,
+        spongos_store: Box<dyn SpongosStore>,
+        allow_unsubscribed: bool,
+        message_filter: Option<Box<dyn MessageFilter>>,
+        key_exchange: Option<Box<dyn KeyExchange>>,
+        metrics: Option<Arc<Metrics>>,
+        replay_recorder: Option<ReplayRecorder>,
+        replay_window: usize,
+        sync_lookahead: usize,
+        collision_diagnostics: bool,
+        payload_deduplication: bool,
+        payload_middleware: Option<PayloadMiddleware>,
+    ) -> Self
     where
         Psks: IntoIterator<Item = (PskId, Psk)>,
     {
@@ -141,13 +514,62 @@ impl<T> User<T> {
                 cursor_store: CursorStore::new(),
                 psk_store,
                 subscribers,
-                spongos_store: Default::default(),
+                readers: Default::default(),
+                pending_subscriptions: Default::default(),
+                invite_redemptions: Default::default(),
+                invite_grants: Default::default(),
+                spongos_positions: Default::default(),
+                spongos_store,
                 stream_address: None,
                 author_identifier: None,
+                previous_authors: Default::default(),
+                strict: false,
+                quorum: None,
                 base_branch: Default::default(),
                 lean,
+                keyload_checkpoints,
+                recomputable: Default::default(),
                 topics: Default::default(),
+                closed_branches: Default::default(),
+                forward_secrecy,
+                ratchets: Default::default(),
+                exchange_keys: Default::default(),
+                exchange_key: None,
+                send_queue: Vec::new(),
+                next_queued_packet_id: 0,
+                seen_messages: SeenMessages::new(replay_window),
+                keyload_permissions: Default::default(),
+                last_payloads: Default::default(),
+                epochs: Default::default(),
+                read_markers: Default::default(),
+                access_expirations: Default::default(),
+                withheld_packets: Default::default(),
+                tombstones: Default::default(),
+                imported_cursors: Default::default(),
+                witnesses: Default::default(),
+                countersignatures: Default::default(),
+                commitments: Default::default(),
+                pending_commitments: Default::default(),
             },
+            orphan_limit,
+            sync_lookahead,
+            link_generator,
+            notarization,
+            size_limit,
+            subscription_policy,
+            allow_unsubscribed,
+            message_filter,
+            key_exchange,
+            collision_diagnostics,
+            payload_deduplication,
+            payload_middleware,
+            payload_validators: HashMap::new(),
+            branch_rotations: HashMap::new(),
+            custom_message_types: HashMap::new(),
+            send_queue_limit: DEFAULT_SEND_QUEUE_LIMIT,
+            metrics,
+            replay_recorder,
+            conflicts: Vec::new(),
         }
     }
 
@@ -156,6 +578,13 @@ impl<T> User<T> {
         self.identity().map(|id| id.identifier())
     }
 
+    /// Returns true if the [`User`] has no [`Identity`]. A read-only user reads the public branches
+    /// and the branches of its [Pre-Shared Keys](`Psk`), but fails to send any message with
+    /// [`Error::NoIdentity`].
+    pub fn is_read_only(&self) -> bool {
+        self.state.user_id.is_none()
+    }
+
     /// Returns a reference to the [User's](`User`) [`Identity`] if any.
     fn identity(&self) -> Option<&Identity> {
         self.state.user_id.as_ref()
@@ -171,6 +600,40 @@ impl<T> User<T> {
             .and_then(|id| self.state.cursor_store.get_permission(topic, id))
     }
 
+    /// Returns the [permission](`Permissioned`) of an [`Identifier`] on a branch, reflecting the
+    /// last keyload of the branch processed by the [`User`]. Before any keyload, the permissions
+    /// inherited from the parent branch apply. Returns `None` if the identifier has no access to
+    /// the branch.
+    ///
+    /// # Arguments
+    /// * `identifier`: The [`Identifier`] to check
+    /// * `topic`: The [`Topic`] of the branch to check
+    pub fn permission_of(&self, identifier: &Identifier, topic: &Topic) -> Option<Permissioned<&Identifier>> {
+        let tracked = self.state.cursor_store.get_permission(topic, identifier);
+        if let Some(admin) = tracked.filter(|permission| permission.is_admin()) {
+            return Some(admin.as_ref());
+        }
+        match self.state.keyload_permissions.get(topic) {
+            Some(permissions) => permissions
+                .iter()
+                .find(|permission| permission.identifier() == identifier)
+                .map(Permissioned::as_ref),
+            None => tracked.map(Permissioned::as_ref),
+        }
+    }
+
+    /// Returns an iterator over the branches the [`User`] has access to, with its
+    /// [permission](`Permissioned`) on each of them (see [`User::permission_of()`]). Empty if the
+    /// user has no identity.
+    pub fn my_permissions(&self) -> impl Iterator<Item = (&Topic, Permissioned<&Identifier>)> + '_ {
+        self.identifier().into_iter().flat_map(move |identifier| {
+            self.topics().filter_map(move |topic| {
+                self.permission_of(identifier, topic)
+                    .map(|permission| (topic, permission))
+            })
+        })
+    }
+
     /// Returns the [User's](`User`) cursor for a given branch if any
     ///
     /// # Arguments
@@ -199,6 +662,175 @@ impl<T> User<T> {
         self.state.stream_address
     }
 
+    /// Returns the [`Identifier`] of the stream author if any: the author of the announcement, or
+    /// the new author designated by the last author rotation processed (see
+    /// [`User::rotate_author()`])
+    pub fn author(&self) -> Option<&Identifier> {
+        self.state.author_identifier.as_ref()
+    }
+
+    /// Returns the [`Quorum`] of author keys the stream was announced with, if any (see
+    /// [`User::create_stream_with_quorum()`])
+    pub fn quorum(&self) -> Option<&Quorum> {
+        self.state.quorum.as_ref()
+    }
+
+    /// Returns the [`QuorumProposal`] the members of the [`Quorum`] of the stream approve for the
+    /// [`User`], as the current stream author, to hand the authorship of the stream over to a new
+    /// [`Identifier`] with [`User::rotate_author_with_approvals()`]. The proposal covers the
+    /// sequence number of the rotation: the approvals are only valid if the author sends no other
+    /// message in the base branch before the rotation.
+    ///
+    /// # Arguments
+    /// * `new_author`: The [`Identifier`] of the new stream author
+    pub fn author_rotation_proposal(&self, new_author: &Identifier) -> Result<QuorumProposal> {
+        let quorum = self
+            .state
+            .quorum
+            .as_ref()
+            .ok_or(Error::Setup("the stream was not announced with a quorum"))?;
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::NoStream("propose an author rotation"))?;
+        let identifier = self
+            .identifier()
+            .ok_or(Error::NoIdentity("propose an author rotation"))?;
+        if self.state.author_identifier.as_ref() != Some(identifier) {
+            return Err(Error::WrongRole(
+                "author",
+                identifier.clone(),
+                "propose an author rotation",
+            ));
+        }
+        let sequence = self.next_cursor(&self.state.base_branch)?;
+        Ok(quorum.rotation_proposal(stream_address, identifier, new_author, sequence))
+    }
+
+    /// Returns true if the stream was announced as strict with
+    /// [`create_strict_stream()`](User::create_strict_stream). Unsigned packets and legacy
+    /// messages are rejected on strict streams. The PRP is not checked: `KeccakF1600` is the only
+    /// one streams support.
+    pub fn is_strict(&self) -> bool {
+        self.state.strict
+    }
+
+    /// Returns the [`ChannelDescriptor`] of the stream, hinting its base branch, if the stream was
+    /// created or its announcement received. Topics of other branches the joining devices should
+    /// follow can be added with [`ChannelDescriptor::with_topic()`].
+    pub fn descriptor(&self) -> Option<ChannelDescriptor> {
+        let announcement = self.stream_address()?;
+        let author = self.state.author_identifier.clone()?;
+        Some(ChannelDescriptor::new(announcement, author).with_topic(self.base_branch().clone()))
+    }
+
+    /// Returns the cursors of the publishers of the stream read by the [`User`], to be imported
+    /// with [`User::import_cursors()`] by a user of the same identity on another device. Unlike a
+    /// backup, the export holds no keys nor [`Spongos`] states. None if the stream was not created
+    /// or its announcement received.
+    pub fn export_cursors(&self) -> Option<CursorExport> {
+        let stream_address = self.stream_address()?;
+        let mut export = CursorExport::new(stream_address);
+        for (topic, permission, cursor) in self.state.cursor_store.cursors() {
+            export = export.with_cursor(topic.clone(), permission.identifier().clone(), cursor);
+        }
+        // Cursors imported from another device and not reached yet are carried over
+        for (topic, cursors) in &self.state.imported_cursors {
+            for (publisher, &cursor) in cursors {
+                let reached = self.state.cursor_store.get_cursor(topic, publisher);
+                if reached.map_or(true, |reached| reached < cursor) {
+                    export = export.with_cursor(topic.clone(), publisher.clone(), cursor);
+                }
+            }
+        }
+        Some(export)
+    }
+
+    /// Returns the [`RoutingTable`] of the stream: for every publisher of every branch the [`User`]
+    /// reads, the addresses of the next messages its [`Messages`] streams probe, up to the
+    /// [sync lookahead](User::sync_lookahead). Indexers watch the tangle indexes of these addresses
+    /// to prefetch the messages of the stream. None if the stream was not created or its
+    /// announcement received.
+    pub fn routing_table(&self) -> Option<RoutingTable> {
+        let stream_address = self.stream_address()?;
+        let base_address = stream_address.base();
+        let mut table = RoutingTable::new(stream_address);
+        // Read-only permissions cannot publish, their publishers are not probed
+        for (topic, permission, cursor) in self.cursors().filter(|(_, p, _)| !p.is_readonly()) {
+            let publisher = permission.identifier();
+            let next_addresses = (cursor + 1..=cursor + self.sync_lookahead)
+                .map(|seq_num| {
+                    let msgid = self.link_generator.gen_msg_id(base_address, publisher, topic, seq_num);
+                    Address::new(base_address, msgid)
+                })
+                .collect();
+            table = table.with_route(Route::new(topic.clone(), publisher.clone(), cursor, next_addresses));
+        }
+        Some(table)
+    }
+
+    /// Imports the cursors exported with [`User::export_cursors()`], usually by the same reader on
+    /// another device, to continue reading the stream where it stopped. The [`Messages`] streams of
+    /// the [`User`] still fetch the messages published up to the imported cursors, to rebuild their
+    /// [`Spongos`] states, but only yield the messages published after them. Cursors the user
+    /// already reached are ignored.
+    ///
+    /// # Arguments
+    /// * `export`: The [`CursorExport`] to import
+    pub fn import_cursors(&mut self, export: &CursorExport) -> Result<()> {
+        let stream_address = self.stream_address().ok_or(Error::NoStream("import cursors"))?;
+        if export.stream_address() != stream_address {
+            return Err(Error::CursorExportInvalid(
+                "the cursors were exported from another stream",
+            ));
+        }
+        for (topic, publisher, cursor) in export.cursors() {
+            let reached = self.state.cursor_store.get_cursor(topic, publisher);
+            if reached.map_or(false, |reached| reached >= cursor) {
+                continue;
+            }
+            let imported = self
+                .state
+                .imported_cursors
+                .entry(topic.clone())
+                .or_default()
+                .entry(publisher.clone())
+                .or_default();
+            *imported = cursor.max(*imported);
+        }
+        Ok(())
+    }
+
+    /// Returns true if the [`Message`] is published up to the cursor imported for its publisher
+    /// with [`User::import_cursors()`], meaning it was already read on the device the cursors were
+    /// exported from. The imported cursor is dropped once reached.
+    ///
+    /// # Arguments
+    /// * `message`: The processed [`Message`]
+    pub(crate) fn read_before_import(&mut self, message: &Message) -> bool {
+        if self.state.imported_cursors.is_empty() {
+            return false;
+        }
+        let topic = match self.topic_by_hash(message.topic_hash()) {
+            Some(topic) => topic,
+            None => return false,
+        };
+        let cursors = match self.state.imported_cursors.get_mut(&topic) {
+            Some(cursors) => cursors,
+            None => return false,
+        };
+        let cursor = match cursors.get(message.publisher()) {
+            Some(&cursor) => cursor,
+            None => return false,
+        };
+        if message.sequence() >= cursor {
+            cursors.remove(message.publisher());
+            if cursors.is_empty() {
+                self.state.imported_cursors.remove(&topic);
+            }
+        }
+        message.sequence() <= cursor
+    }
+
     /// Returns a reference to the [`User`] transport client.
     pub fn transport(&self) -> &T {
         &self.transport
@@ -209,1517 +841,7650 @@ impl<T> User<T> {
         &mut self.transport
     }
 
-    /// Returns an iterator over all known branch [topics](`Topic`)
-    pub fn topics(&self) -> impl Iterator<Item = &Topic> + ExactSizeIterator {
-        self.state.topics.iter()
+    /// Returns the bound on the orphan messages buffered by the [`Messages`] streams of the user
+    pub fn orphan_limit(&self) -> Option<OrphanLimit> {
+        self.orphan_limit
     }
 
-    /// Iterates through known topics, returning the [`Topic`] that matches the [`TopicHash`]
-    /// provided if any
+    /// Sets the bound on the orphan messages buffered by the [`Messages`] streams of the user. It
+    /// applies to the streams started after the call.
     ///
     /// # Arguments
-    /// * `hash`: The [`TopicHash`] from a message header
-    pub(crate) fn topic_by_hash(&self, hash: &TopicHash) -> Option<Topic> {
-        self.topics().find(|t| &TopicHash::from(*t) == hash).cloned()
+    /// * `orphan_limit`: The new [`OrphanLimit`], or None to buffer orphans without bound
+    pub fn set_orphan_limit(&mut self, orphan_limit: Option<OrphanLimit>) {
+        self.orphan_limit = orphan_limit;
     }
 
-    /// Returns true if [`User`] lean state configuration is true
-    fn lean(&self) -> bool {
-        self.state.lean
+    /// Returns the number of future cursors of a publisher the [`Messages`] streams of the user
+    /// probe at once
+    pub fn sync_lookahead(&self) -> usize {
+        self.sync_lookahead
     }
 
-    /// Returns an iterator over [`CursorStore`], producing tuples of [`Topic`], [`Permissioned`]
-    /// [`Identifier`], and the cursor. Used by [`Messages`] streams to find next messages.
-    pub(crate) fn cursors(&self) -> impl Iterator<Item = (&Topic, &Permissioned<Identifier>, usize)> + '_ {
-        self.state.cursor_store.cursors()
+    /// Sets the number of future cursors of a publisher the [`Messages`] streams of the user probe
+    /// at once. With a window of one, the cursor of each publisher is advanced one message per
+    /// round. Larger windows fetch the next messages of a publisher concurrently and search the end
+    /// of its run exponentially, cutting the round trips to the transport when a publisher sent
+    /// many messages since the last sync. It applies to the streams started after the call.
+    ///
+    /// # Arguments
+    /// * `sync_lookahead`: The number of cursors probed at once, zero being treated as one
+    pub fn set_sync_lookahead(&mut self, sync_lookahead: usize) {
+        self.sync_lookahead = sync_lookahead.max(1);
     }
 
-    /// Returns an iterator over a [`Topic`] mapped branch in [`CursorStore`], producing tuples of
-    /// [`Permissioned`][`Identifier`] and a cursor. Used to carry permissions forward through
-    /// branch declarations. Returns an error if the [`Topic`] is not found in store.
+    /// Returns the number of processed messages the user remembers to detect the messages received
+    /// again
+    pub fn replay_window(&self) -> usize {
+        self.state.seen_messages.capacity()
+    }
+
+    /// Sets the number of processed messages the user remembers to detect the messages received
+    /// again, forgetting the oldest ones if the window shrinks. The window is kept in the backups
+    /// of the user. Zero disables the detection of duplicates.
     ///
     /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch to fetch cursors for
-    fn cursors_by_topic(&self, topic: &Topic) -> Result<impl Iterator<Item = (&Permissioned<Identifier>, &usize)>> {
-        self.state
-            .cursor_store
-            .cursors_by_topic(topic)
-            .ok_or(Error::TopicNotFound(topic.clone()))
+    /// * `replay_window`: The number of processed messages remembered
+    pub fn set_replay_window(&mut self, replay_window: usize) {
+        self.state.seen_messages.set_capacity(replay_window);
     }
 
-    /// Returns an iterator over known subscriber [identifiers](`Identifier`)
-    pub fn subscribers(&self) -> impl Iterator<Item = &Identifier> + Clone + '_ {
-        self.state.subscribers.iter()
+    /// Returns the bound on the lengths and item counts read from the messages processed by the
+    /// user
+    pub fn size_limit(&self) -> usize {
+        self.size_limit
     }
 
-    /// If the subscriber is not readonly and the [`Permissioned`] is not tracked or the
-    /// [`Permissioned`] is tracked and not equal to the provided subscriber [`Permissioned`],
-    /// then the cursor should be stored.
+    /// Sets the bound on the lengths and item counts read from the messages processed by the user.
+    /// Messages with a length-prefixed field or an item count exceeding it are rejected with
+    /// [`SpongosError::InvalidSize`] instead of being allocated.
     ///
-    /// # Arguments:
-    /// * `topic`: The topic of the branch to be stored in.
-    /// * `permission`: The [`Permissioned`] to check.
-    fn should_store_cursor(&self, topic: &Topic, permission: Permissioned<&Identifier>) -> bool {
-        let self_permission = self.state.cursor_store.get_permission(topic, permission.identifier());
-        let tracked_and_equal = self_permission.is_some() && (self_permission.unwrap().as_ref() == permission);
-        !permission.is_readonly() && !tracked_and_equal
+    /// # Arguments
+    /// * `size_limit`: The largest length or item count accepted
+    pub fn set_size_limit(&mut self, size_limit: usize) {
+        self.size_limit = size_limit;
     }
 
-    /// Store a new [`Spongos`] state. If the [`User`] lean state configuration is set to true, and
-    /// if the linked message is not the stream announcement message, remove the previous message
-    /// from store.
-    ///
-    /// # Arguments:
-    /// * `msg_address`: The [`Address`] of the message that we're storing the [`Spongos`] for.
-    /// * `spongos`: The [`Spongos`] state to be stored.
-    /// * `linked_msg_address`: The address of the message that the spongos is linked to.
-    fn store_spongos(&mut self, msg_address: MsgId, spongos: Spongos, linked_msg_address: MsgId) {
-        let is_stream_address = self
-            .stream_address()
-            .map_or(false, |stream_address| stream_address.relative() == linked_msg_address);
-        // Do not remove announcement message from store
-        if self.lean() && !is_stream_address {
-            self.state.spongos_store.remove(&linked_msg_address);
-        }
+    /// Returns the [`SubscriptionPolicy`] applied to the subscription requests processed by the
+    /// user
+    pub fn subscription_policy(&self) -> &SubscriptionPolicy {
+        &self.subscription_policy
+    }
 
-        self.state.spongos_store.insert(msg_address, spongos);
+    /// Sets the [`SubscriptionPolicy`] applied to the subscription requests processed by the user.
+    /// Subscription requests already held for review are kept pending.
+    ///
+    /// # Arguments
+    /// * `subscription_policy`: The [`SubscriptionPolicy`] to apply
+    pub fn set_subscription_policy(&mut self, subscription_policy: SubscriptionPolicy) {
+        self.subscription_policy = subscription_policy;
     }
 
-    /// Store a new subscriber [`Identifier`] in state. Returns true if subscriber was not present.
-    pub fn add_subscriber(&mut self, subscriber: Identifier) -> bool {
-        self.state.subscribers.insert(subscriber)
+    /// Returns true if the keyloads sent by the user can grant permissions to identifiers that did
+    /// not subscribe
+    pub fn allows_unsubscribed(&self) -> bool {
+        self.allow_unsubscribed
     }
 
-    /// Remove a subscriber [`Identifier`] from state. Returns true if the subscriber was present.
-    pub fn remove_subscriber(&mut self, id: &Identifier) -> bool {
-        self.state.subscribers.remove(id)
+    /// Sets whether the keyloads sent by the user can grant permissions to identifiers that did not
+    /// subscribe, like devices provisioned out of band. See
+    /// [`UserBuilder::allow_unsubscribed()`].
+    ///
+    /// # Arguments
+    /// * `allow_unsubscribed`: If true, keyloads are sent to any identifier without checks
+    pub fn set_allow_unsubscribed(&mut self, allow_unsubscribed: bool) {
+        self.allow_unsubscribed = allow_unsubscribed;
     }
 
-    /// Store a new [Pre-Shared Key](`Psk`) in state. Returns true if [`Psk`] was not present.
-    pub fn add_psk(&mut self, psk: Psk) -> bool {
-        self.state.psk_store.insert(psk.to_pskid(), psk).is_none()
+    /// Sets the [`MessageFilter`] screening the messages processed by the user from now on
+    ///
+    /// # Arguments
+    /// * `message_filter`: The [`MessageFilter`] to apply
+    pub fn set_message_filter<F>(&mut self, message_filter: F)
+    where
+        F: MessageFilter + 'static,
+    {
+        self.message_filter = Some(Box::new(message_filter));
     }
 
-    /// Remove a [`Psk`] from state by its [identifier](`PskId`). Returns true if the [`Psk`] was
-    /// present.
-    pub fn remove_psk(&mut self, pskid: PskId) -> bool {
-        self.state.psk_store.remove(&pskid).is_some()
+    /// Sets the [`KeyExchange`] holding the key exchange key of the user. The next rotation of the
+    /// key with [`User::rotate_exchange_key()`] announces the key of the [`KeyExchange`], and the
+    /// keys encrypted to it are recovered through it. Users restored from a backup after rotating
+    /// to a [`KeyExchange`] must set it again before processing messages.
+    ///
+    /// # Arguments
+    /// * `key_exchange`: The [`KeyExchange`] holding the key
+    pub fn set_key_exchange<K>(&mut self, key_exchange: K)
+    where
+        K: KeyExchange + 'static,
+    {
+        self.key_exchange = Some(Box::new(key_exchange));
     }
 
-    /// Sets the latest message link for a specified branch. If the branch does not exist, it is
-    /// created.
+    /// Sets the [`PayloadValidator`] validating the packets read by the user in a branch from now
+    /// on, replacing the previous validator of the branch if any
     ///
     /// # Arguments
     /// * `topic`: The [`Topic`] of the branch
-    /// * `latest_link`: The [`MsgId`] link that will be set
-    fn set_latest_link(&mut self, topic: Topic, latest_link: MsgId) {
-        self.state.cursor_store.set_latest_link(topic, latest_link)
+    /// * `payload_validator`: The [`PayloadValidator`] to apply
+    pub fn set_payload_validator<Top, V>(&mut self, topic: Top, payload_validator: V)
+    where
+        Top: Into<Topic>,
+        V: PayloadValidator + 'static,
+    {
+        self.payload_validators
+            .insert(topic.into(), Box::new(payload_validator));
     }
 
-    /// Returns the latest [`MsgId`] link for a specified branch, if any
+    /// Sets the [`PayloadMiddleware`] transforming the masked payloads of the packets sent and read
+    /// by the user from now on. Users restored from a backup must set it again before reading the
+    /// packets sent through it.
     ///
     /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch
-    fn get_latest_link(&self, topic: &Topic) -> Option<MsgId> {
-        self.state.cursor_store.get_latest_link(topic)
+    /// * `payload_middleware`: The [`PayloadMiddleware`] to apply
+    pub fn set_payload_middleware(&mut self, payload_middleware: PayloadMiddleware) {
+        self.payload_middleware = Some(payload_middleware);
     }
 
-    /// Parse and process a [`TransportMessage`] dependent on its type.
+    /// Removes the [`PayloadValidator`] of a branch. The packets read in the branch from now on are
+    /// no longer validated. Returns true if the branch had a validator.
     ///
     /// # Arguments
-    /// * `address`: The [`Address`] of the message to process
-    /// * `msg`: The raw [`TransportMessage`]
-    pub(crate) async fn handle_message(&mut self, address: Address, msg: TransportMessage) -> Result<Message> {
-        let preparsed = msg
-            .parse_header()
-            .await
-            .map_err(|e| Error::Unwrapping("header", address, e))?;
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn remove_payload_validator(&mut self, topic: &Topic) -> bool {
+        self.payload_validators.remove(topic).is_some()
+    }
 
-        match preparsed.header().message_type() {
-            message_types::ANNOUNCEMENT => self.handle_announcement(address, preparsed).await,
-            message_types::BRANCH_ANNOUNCEMENT => self.handle_branch_announcement(address, preparsed).await,
-            message_types::SUBSCRIPTION => self.handle_subscription(address, preparsed).await,
-            message_types::UNSUBSCRIPTION => self.handle_unsubscription(address, preparsed).await,
-            message_types::KEYLOAD => self.handle_keyload(address, preparsed).await,
-            message_types::SIGNED_PACKET => self.handle_signed_packet(address, preparsed).await,
-            message_types::TAGGED_PACKET => self.handle_tagged_packet(address, preparsed).await,
-            unknown => Err(Error::MessageTypeUnknown(unknown)),
-        }
+    /// Trusts the countersignatures of a witness processed from now on (see
+    /// [`User::countersign()`]). Countersignatures published by other users are rejected. Returns
+    /// true if the witness was not trusted yet.
+    ///
+    /// # Arguments
+    /// * `witness`: The [`Identifier`] of the witness
+    pub fn add_witness(&mut self, witness: Identifier) -> bool {
+        self.state.witnesses.insert(witness)
     }
 
-    /// Processes an announcement message, binding a [`User`] to the stream announced in the
-    /// message.
+    /// Stops trusting the countersignatures of a witness processed from now on. The
+    /// countersignatures already processed are kept. Returns true if the witness was trusted.
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_announcement(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        // Check Topic
-        let publisher = preparsed.header().publisher().clone();
+    /// # Arguments
+    /// * `witness`: The [`Identifier`] of the witness
+    pub fn remove_witness(&mut self, witness: &Identifier) -> bool {
+        self.state.witnesses.remove(witness)
+    }
 
-        // Unwrap message
-        let announcement = announcement::Unwrap::default();
-        let (message, spongos) = preparsed
-            .unwrap(announcement)
-            .await
-            .map_err(|e| Error::Unwrapping("announcement", address, e))?;
+    /// Returns an iterator over the [Identifiers](`Identifier`) of the trusted witnesses
+    pub fn witnesses(&self) -> impl Iterator<Item = &Identifier> + Clone + '_ {
+        self.state.witnesses.iter()
+    }
 
-        let topic = message.payload().content().topic();
-        // Insert new branch into store
-        self.state.cursor_store.new_branch(topic.clone());
-        self.state.topics.insert(topic.clone());
+    /// Returns the [`Countersignature`]s of a packet processed by the [`User`]
+    ///
+    /// # Arguments
+    /// * `msgid`: The [`MsgId`] of the packet
+    pub fn countersignatures(&self, msgid: &MsgId) -> &[Countersignature] {
+        self.state.countersignatures.get(msgid).map_or(&[], Vec::as_slice)
+    }
 
-        // When handling an announcement it means that no cursors have been stored, as no topics are
-        // known yet. The message must be unwrapped to retrieve the initial topic before storing cursors
-        self.state
-            .cursor_store
-            .insert_cursor(topic, Permissioned::Admin(publisher), INIT_MESSAGE_NUM);
+    /// Returns a [`Commitment`] processed or sent by the [`User`]
+    ///
+    /// # Arguments
+    /// * `msgid`: The [`MsgId`] of the commitment
+    pub fn commitment(&self, msgid: &MsgId) -> Option<&Commitment> {
+        self.state.commitments.get(msgid)
+    }
 
-        // Store spongos
-        self.state.spongos_store.insert(address.relative(), spongos);
+    /// Returns the [`MsgId`] of the last commitment sent by the [`User`] in a branch, if it was not
+    /// revealed yet (see [`User::send_reveal()`])
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn pending_commitment(&self, topic: &Topic) -> Option<MsgId> {
+        self.state.pending_commitments.get(topic).copied()
+    }
 
-        // Store message content into stores
-        let author_id = message.payload().content().author_id().clone();
+    /// Registers a custom message type, so that the messages of the type read by the user from now
+    /// on are unwrapped as a `C`, and surface as [`MessageContent::Custom`]. Registering a type
+    /// again replaces its content type. Messages of custom types that are not registered fail to be
+    /// processed, like messages of unknown types.
+    ///
+    /// # Arguments
+    /// * `message_type`: The message type identifier, within [`CUSTOM_MESSAGE_TYPES`]
+    pub fn register_message_type<C>(&mut self, message_type: u8) -> Result<()>
+    where
+        C: CustomContent + Default,
+        for<'b> unwrap::Context<&'b [u8]>: ContentUnwrap<C>,
+    {
+        if !CUSTOM_MESSAGE_TYPES.contains(&message_type) {
+            return Err(Error::Setup("custom message types must be within CUSTOM_MESSAGE_TYPES"));
+        }
+        self.custom_message_types
+            .insert(message_type, Box::new(Registered::<C>::new()));
+        Ok(())
+    }
 
-        // Update branch links
-        self.set_latest_link(topic.clone(), address.relative());
-        self.state.author_identifier = Some(author_id);
-        self.state.base_branch = topic.clone();
-        self.state.stream_address = Some(address);
+    /// Unregisters a custom message type. Returns true if the type was registered.
+    ///
+    /// # Arguments
+    /// * `message_type`: The message type identifier
+    pub fn unregister_message_type(&mut self, message_type: u8) -> bool {
+        self.custom_message_types.remove(&message_type).is_some()
+    }
 
-        Ok(Message::from_lets_message(address, message))
+    /// Sets the [`BranchRotation`] of a branch. While rotated, the packets sent to the branch are
+    /// published in its current epoch branch, a child of the branch named after the UTC start of
+    /// the epoch, such as `telemetry/2024-06`. Once the epoch is over, the next packet starts a new
+    /// epoch branch from the previous one, with a fresh keyload if the previous one had any.
+    /// Setting [`BranchRotation::Never`] publishes the next packets in the branch itself again.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `rotation`: The [`BranchRotation`] to apply
+    pub fn set_branch_rotation<Top>(&mut self, topic: Top, rotation: BranchRotation)
+    where
+        Top: Into<Topic>,
+    {
+        let topic = topic.into();
+        match rotation {
+            BranchRotation::Never => {
+                self.branch_rotations.remove(&topic);
+                self.state.epochs.remove(&topic);
+            }
+            BranchRotation::Every(period) => {
+                self.branch_rotations.insert(topic, period);
+            }
+        }
     }
 
-    /// Processes a branch announcement message, creating a new branch in [`CursorStore`], carrying
-    /// over mapped permissions and cursors from the previous branch.
+    /// Returns the [`BranchRotation`] of a branch
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_branch_announcement(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        // Retrieve header values
-        let prev_topic = self
-            .topic_by_hash(preparsed.header().topic_hash())
-            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn branch_rotation(&self, topic: &Topic) -> BranchRotation {
+        self.branch_rotations
+            .get(topic)
+            .map_or(BranchRotation::Never, |period| BranchRotation::Every(*period))
+    }
 
-        let publisher = preparsed.header().publisher().clone();
-        let cursor = preparsed.header().sequence();
+    /// Returns the [`Topic`] of the current epoch branch of a branch rotated by the user, if an
+    /// epoch has been started
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the rotated branch
+    pub fn current_epoch(&self, topic: &Topic) -> Option<&Topic> {
+        self.state.epochs.get(topic).map(|epoch| &epoch.topic)
+    }
 
-        // From the point of view of cursor tracking, the message exists, regardless of the validity or
-        // accessibility to its content. Therefore we must update the cursor of the publisher before
-        // handling the message
-        let permission = self
-            .state
-            .cursor_store
-            .get_permission(&prev_topic, &publisher)
-            .ok_or(Error::NoCursor(prev_topic.clone()))?
-            .clone();
-        self.state.cursor_store.insert_cursor(&prev_topic, permission, cursor);
+    /// Returns the known epoch branches of a branch, oldest first. Epoch branches are the children
+    /// of the branch named after the start of their epoch, as created by the publishers rotating
+    /// the branch (see [`User::set_branch_rotation()`]).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the rotated branch
+    pub fn epochs(&self, topic: &Topic) -> Vec<&Topic> {
+        let mut epochs: Vec<&Topic> = self
+            .topics()
+            .filter(|epoch| {
+                epoch.parent().as_ref() == Some(topic)
+                    && epoch.segments().last().map_or(false, branch_rotation::is_epoch_label)
+            })
+            .collect();
+        epochs.sort_by(|a, b| a.str().cmp(b.str()));
+        epochs
+    }
 
-        // Unwrap message
-        let linked_msg_address = preparsed
-            .header()
-            .linked_msg_address()
-            .ok_or(Error::NotLinked("branch announcement", address))?;
-        let mut linked_msg_spongos = {
-            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address).copied() {
-                // Spongos must be copied because wrapping mutates it
-                spongos
-            } else {
-                return Ok(Message::orphan(address, preparsed));
+    /// Returns the last read marker of each consumer publishing markers in a branch, as the
+    /// [`Address`] of the last message it processed, including the markers of the [`User`] (see
+    /// [`User::send_read_marker()`])
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the markers are published in
+    pub fn read_markers(&self, topic: &Topic) -> impl Iterator<Item = (&Identifier, &Address)> + '_ {
+        self.state.read_markers.get(topic).into_iter().flatten()
+    }
+
+    /// Returns the last read marker of a consumer in a branch, if any
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the markers are published in
+    /// * `consumer`: The [`Identifier`] of the consumer
+    pub fn read_marker(&self, topic: &Topic, consumer: &Identifier) -> Option<Address> {
+        self.state
+            .read_markers
+            .get(topic)
+            .and_then(|markers| markers.get(consumer))
+            .copied()
+    }
+
+    /// Returns the reason of the redaction of a message, if a tombstone redacting it was processed
+    /// (see [`User::send_tombstone()`])
+    ///
+    /// # Arguments
+    /// * `msgid`: The [`MsgId`] of the message
+    pub fn redaction(&self, msgid: &MsgId) -> Option<&str> {
+        self.state.tombstones.get(msgid).map(|(_, reason)| reason.as_str())
+    }
+
+    /// Withholds the payloads of a packet redacted by a tombstone processed by the [`User`],
+    /// returning it as [`MessageContent::Redacted`]. Packets processed after their tombstone are
+    /// redacted when they are read; this applies the tombstones processed since to the packets
+    /// read before them. Other messages are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `message`: The processed [`Message`]
+    pub fn redact(&self, message: Message) -> Message {
+        if !message.is_packet() {
+            return message;
+        }
+        match self.state.tombstones.get(&message.address().relative()) {
+            Some((tombstone, reason)) => {
+                let tombstone = Address::new(message.address().base(), *tombstone);
+                message.redact(tombstone, reason.clone())
             }
-        };
-        let branch_announcement = branch_announcement::Unwrap::new(&mut linked_msg_spongos);
-        let (message, spongos) = preparsed
-            .unwrap(branch_announcement)
-            .await
-            .map_err(|e| Error::Unwrapping("branch announcement", address, e))?;
+            None => message,
+        }
+    }
 
-        let new_topic = message.payload().content().new_topic();
-        // Store spongos
-        self.store_spongos(address.relative(), spongos, linked_msg_address);
-        // Insert new branch into store
-        self.state.cursor_store.new_branch(new_topic.clone());
-        self.state.topics.insert(new_topic.clone());
-        // Collect permissions from previous branch and clone them into new branch
-        let prev_permissions = self
-            .cursors_by_topic(&prev_topic)?
-            .map(|(id, _)| id.clone())
-            .collect::<Vec<Permissioned<Identifier>>>();
-        for id in prev_permissions {
-            self.state.cursor_store.insert_cursor(new_topic, id, INIT_MESSAGE_NUM);
+    /// Attaches the [`Countersignature`]s processed by the [`User`] to a packet
+    ///
+    /// # Arguments
+    /// * `message`: The processed [`Message`]
+    fn attach_countersignatures(&self, message: Message) -> Message {
+        if !message.is_packet() {
+            return message;
+        }
+        match self.state.countersignatures.get(&message.address().relative()) {
+            Some(countersignatures) => message.with_countersignatures(countersignatures.clone()),
+            None => message,
         }
+    }
 
-        // Update branch links
-        self.set_latest_link(new_topic.clone(), address.relative());
+    /// Records a [`Countersignature`], unless it was already recorded
+    ///
+    /// # Arguments
+    /// * `countersignature`: The [`Countersignature`] to record
+    fn record_countersignature(&mut self, countersignature: Countersignature) {
+        let countersignatures = self
+            .state
+            .countersignatures
+            .entry(countersignature.countersigned)
+            .or_default();
+        if !countersignatures.contains(&countersignature) {
+            countersignatures.push(countersignature);
+        }
+    }
 
-        Ok(Message::from_lets_message(address, message))
+    /// Returns the Unix timestamp, in seconds, at which the access granted by the last keyload of a
+    /// branch, read or sent by the [`User`], expires, if the keyload carries an expiry (see
+    /// [`User::send_keyload_with_expiry()`])
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn access_expiry(&self, topic: &Topic) -> Option<u64> {
+        self.state.access_expirations.get(topic).copied()
+    }
+
+    /// Returns a [`StateSummary`] of the state of the [`User`]: its branches, with the permissions
+    /// and cursors of their publishers, its subscribers and its pre-shared keys. Two summaries are
+    /// compared with [`StateSummary::diff()`].
+    pub fn state_summary(&self) -> StateSummary {
+        let branches = self
+            .state
+            .topics
+            .iter()
+            .map(|topic| {
+                let mut branch = BranchSummary {
+                    latest_link: self.get_latest_link(topic),
+                    closed: self.state.closed_branches.contains(topic),
+                    ..BranchSummary::default()
+                };
+                for (permission, cursor) in self.state.cursor_store.cursors_by_topic(topic).into_iter().flatten() {
+                    branch.cursors.insert(permission.identifier().clone(), *cursor);
+                    branch
+                        .permissions
+                        .insert(permission.identifier().clone(), permission.clone());
+                }
+                (topic.clone(), branch)
+            })
+            .collect();
+        StateSummary {
+            identifier: self.identifier().cloned(),
+            stream_address: self.state.stream_address,
+            author_identifier: self.state.author_identifier.clone(),
+            branches,
+            subscribers: self.state.subscribers.clone(),
+            pending_subscriptions: self.state.pending_subscriptions.clone(),
+            psks: self.state.psk_store.keys().copied().collect(),
+            queued_packets: self.state.send_queue.len(),
+        }
     }
 
-    /// Processes a [`User`] subscription message, storing the subscriber [`Identifier`].
+    /// Sets the number of packets that can be queued with [`User::queue_packet()`] and not sent
+    /// yet. The packets already queued are kept, even if they exceed the new limit.
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_subscription(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        // Cursor is not stored, as cursor is only tracked for subscribers with write permissions
+    /// # Arguments
+    /// * `send_queue_limit`: The largest number of queued packets
+    pub fn set_send_queue_limit(&mut self, send_queue_limit: usize) {
+        self.send_queue_limit = send_queue_limit;
+    }
 
-        // Unwrap message
-        let linked_msg_address = preparsed
-            .header()
-            .linked_msg_address()
-            .ok_or(Error::NotLinked("subscription", address))?;
-        let mut linked_msg_spongos = {
-            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address).copied() {
-                // Spongos must be copied because wrapping mutates it
-                spongos
-            } else {
-                return Ok(Message::orphan(address, preparsed));
-            }
-        };
-        let user_ke_sk = &self
-            .identity()
-            .ok_or(Error::NoIdentity("Derive a secret key"))?
-            .ke_sk()
-            .map_err(|_| Error::NoSecretKey)?;
+    /// Returns the number of packets queued with [`User::queue_packet()`] and not sent yet
+    pub fn queued_packets(&self) -> usize {
+        self.state.send_queue.len()
+    }
 
-        let subscription = subscription::Unwrap::new(&mut linked_msg_spongos, user_ke_sk);
-        let (message, _spongos) = preparsed
-            .unwrap(subscription)
-            .await
-            .map_err(|e| Error::Unwrapping("subscription", address, e))?;
+    /// Returns the packets queued with [`User::queue_packet()`] and not sent yet, in the order the
+    /// next [`User::flush()`] sends them in
+    pub fn outbox(&self) -> Vec<&QueuedPacket> {
+        let mut outbox: Vec<&QueuedPacket> = self.state.send_queue.iter().collect();
+        // The sort is stable, packets of the same priority stay in queuing order
+        outbox.sort_by(|a, b| b.priority.cmp(&a.priority));
+        outbox
+    }
 
-        // Store spongos
-        // Subscription messages are never stored in spongos to maintain consistency about the view of the
-        // set of messages of the stream between all the subscribers and across stateless recovers
+    /// Removes a packet from the queue before it is sent, returning it. Returns None if no queued
+    /// packet has the identifier, like a packet that was already sent.
+    ///
+    /// # Arguments
+    /// * `id`: The identifier of the queued packet, as listed by [`User::outbox()`]
+    pub fn cancel(&mut self, id: u64) -> Option<QueuedPacket> {
+        let index = self.state.send_queue.iter().position(|packet| packet.id == id)?;
+        Some(self.state.send_queue.remove(index))
+    }
 
-        // Store message content into stores
-        let subscriber_identifier = message.payload().content().subscriber_identifier();
-        self.add_subscriber(subscriber_identifier.clone());
+    /// Queues a new Signed Packet, to be sent to the specified branch by the next
+    /// [`User::flush()`]. Queued packets are part of the state of the user, so they are kept in its
+    /// backups until they are sent. They are only wrapped when they are sent, so that they are
+    /// linked to the latest message of their branch, in the order they are sent in.
+    ///
+    /// Returns the number of queued packets. Errors with [`Error::SendQueueFull`] if the queue has
+    /// reached its limit, set with [`User::set_send_queue_limit()`], and with
+    /// [`Error::NoIdentity`] if the user is read-only.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    /// * `priority`: The priority of the packet. Packets of higher priority are sent first, packets
+    ///   of the same priority are sent in the order they are queued in.
+    pub fn queue_packet<P, M, Top>(
+        &mut self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+        priority: u8,
+    ) -> Result<usize>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        if self.is_read_only() {
+            return Err(Error::NoIdentity("queue a packet"));
+        }
+        if self.state.send_queue.len() >= self.send_queue_limit {
+            return Err(Error::SendQueueFull(self.send_queue_limit));
+        }
+        let id = self.state.next_queued_packet_id;
+        self.state.next_queued_packet_id += 1;
+        self.state.send_queue.push(QueuedPacket {
+            id,
+            topic: topic.into(),
+            priority,
+            public_payload: public_payload.as_ref().to_vec(),
+            masked_payload: masked_payload.as_ref().to_vec(),
+        });
+        Ok(self.state.send_queue.len())
+    }
 
-        Ok(Message::from_lets_message(address, message))
+    /// Sets the [`Metrics`] registry the metrics of the user are recorded in from now on. The
+    /// registry can be shared with other users.
+    ///
+    /// # Arguments
+    /// * `metrics`: The [`Metrics`] registry
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.metrics = Some(metrics);
     }
 
-    /// Processes a [`User`] unsubscription message, removing the subscriber [`Identifier`] from
-    /// store.
+    /// Returns the [`Metrics`] registry the metrics of the user are recorded in, if any
+    pub fn metrics(&self) -> Option<&Arc<Metrics>> {
+        self.metrics.as_ref()
+    }
+
+    /// Sets the [`ReplayRecorder`] capturing the messages processed by the user from now on,
+    /// returning the previous one if any
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_unsubscription(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        // Cursor is not stored, as user is unsubscribing
+    /// # Arguments
+    /// * `replay_recorder`: The [`ReplayRecorder`]
+    pub fn set_replay_recorder(&mut self, replay_recorder: ReplayRecorder) -> Option<ReplayRecorder> {
+        self.replay_recorder.replace(replay_recorder)
+    }
 
-        // Unwrap message
-        let linked_msg_address = preparsed
-            .header()
-            .linked_msg_address()
-            .ok_or(Error::NotLinked("unsubscribe", address))?;
-        let mut linked_msg_spongos = {
-            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address) {
-                // Spongos must be cloned because wrapping mutates it
-                *spongos
-            } else {
-                return Ok(Message::orphan(address, preparsed));
-            }
-        };
-        let unsubscription = unsubscription::Unwrap::new(&mut linked_msg_spongos);
-        let (message, spongos) = preparsed
-            .unwrap(unsubscription)
-            .await
-            .map_err(|e| Error::Unwrapping("unsubscribe", address, e))?;
+    /// Returns the [`ReplayRecorder`] capturing the messages processed by the user, if any
+    pub fn replay_recorder(&self) -> Option<&ReplayRecorder> {
+        self.replay_recorder.as_ref()
+    }
 
-        // Store spongos
-        self.store_spongos(address.relative(), spongos, linked_msg_address);
+    /// Removes the [`ReplayRecorder`] of the user, returning it if any. Messages processed from
+    /// now on are no longer recorded.
+    pub fn take_replay_recorder(&mut self) -> Option<ReplayRecorder> {
+        self.replay_recorder.take()
+    }
 
-        // Store message content into stores
-        self.remove_subscriber(message.payload().content().subscriber_identifier());
+    /// Returns true if the messages found at the same address are checked for address collisions
+    pub fn collision_diagnostics(&self) -> bool {
+        self.collision_diagnostics
+    }
 
-        Ok(Message::from_lets_message(address, message))
+    /// Sets whether the messages found at the same address are checked for address collisions. See
+    /// [`UserBuilder::with_collision_diagnostics()`].
+    ///
+    /// # Arguments
+    /// * `collision_diagnostics`: If true, collisions are reported with [`Error::AddressCollision`]
+    pub fn set_collision_diagnostics(&mut self, collision_diagnostics: bool) {
+        self.collision_diagnostics = collision_diagnostics;
     }
 
-    /// Processes a keyload message, updating store to include the contained list of
-    /// [permissions](`Permissioned`). All keyload messages are linked to the announcement
-    /// message to ensure they can always be read by a [`User`] that can sequence up to it.
+    /// Returns true if the signed packets repeating the payloads of the last packet of the user on
+    /// their branch are sent as repeated packets
+    pub fn payload_deduplication(&self) -> bool {
+        self.payload_deduplication
+    }
+
+    /// Sets whether the signed packets repeating the payloads of the last packet of the user on
+    /// their branch are sent as repeated packets. See
+    /// [`UserBuilder::with_payload_deduplication()`].
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_keyload(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        let stream_address = self.stream_address().ok_or(Error::NoStream("handling a keyload"))?;
+    /// # Arguments
+    /// * `payload_deduplication`: If true, repeated payloads are sent as references to the packet
+    ///   that first carried them
+    pub fn set_payload_deduplication(&mut self, payload_deduplication: bool) {
+        self.payload_deduplication = payload_deduplication;
+    }
 
-        let topic = self
-            .topic_by_hash(preparsed.header().topic_hash())
-            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
-        let publisher = preparsed.header().publisher().clone();
-        // Confirm keyload came from administrator
-        if !self
-            .state
-            .cursor_store
-            .get_permission(&topic, &publisher)
-            .ok_or(Error::NoCursor(topic.clone()))?
-            .is_admin()
+    /// Returns the [conflicts](`Conflict`) detected while receiving messages since the last call.
+    /// A conflict is detected when several announcements or keyloads are found at the address of a
+    /// stream message, for instance when an attacker posts their own announcement at the address of
+    /// the stream. The conflicting messages are not processed, and receiving them fails with
+    /// [`Error::Conflict`].
+    pub fn take_conflicts(&mut self) -> Vec<Conflict> {
+        core::mem::take(&mut self.conflicts)
+    }
+
+    /// Screens a message with the [`MessageFilter`] of the user, if any. Errors if the message is
+    /// dropped.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `msg`: The raw [`TransportMessage`]
+    /// * `header`: The decoded header of the message, `None` for legacy (v1) messages
+    fn screen_message(&self, address: Address, msg: &TransportMessage, header: Option<&HDF>) -> Result<()> {
+        match self
+            .message_filter
+            .as_ref()
+            .map(|filter| filter.screen(address, msg, header))
         {
-            return Err(Error::WrongRole("admin", publisher, "receive keyload"));
+            Some(FilterVerdict::Drop(reason)) => Err(Error::MessageFiltered(address, reason)),
+            _ => Ok(()),
         }
-        // From the point of view of cursor tracking, the message exists, regardless of the validity or
-        // accessibility to its content. Therefore we must update the cursor of the publisher before
-        // handling the message
-        self.state
-            .cursor_store
-            .insert_cursor(&topic, Permissioned::Admin(publisher), preparsed.header().sequence());
-
-        // Unwrap message
-        // Ok to unwrap since an author identifier is set at the same time as the stream address
-        let author_identifier = self.state.author_identifier.as_ref().unwrap();
-        let mut announcement_spongos = self
-            .state
-            .spongos_store
-            .get(&stream_address.relative())
-            .copied()
-            .expect("a subscriber that has received an stream announcement must keep its spongos in store");
-
-        // TODO: Remove Psk from Identity and Identifier, and manage it as a complementary permission
-        let keyload = keyload::Unwrap::new(
-            &mut announcement_spongos,
-            self.state.user_id.as_ref(),
-            author_identifier,
-            &self.state.psk_store,
-        );
-        let (message, spongos) = preparsed
-            .unwrap(keyload)
-            .await
-            .map_err(|e| Error::Unwrapping("keyload", address, e))?;
-
-        // Store spongos
-        self.state.spongos_store.insert(address.relative(), spongos);
-
-        let subscribers = message.payload().content().subscribers();
+    }
 
-        // If a branch admin does not include a user in the keyload, any further messages sent by
-        // the user will not be received by the others, so remove them from the publisher pool
-        let stored_subscribers: Vec<(Permissioned<Identifier>, usize)> = self
-            .cursors_by_topic(&topic)?
-            .map(|(perm, cursor)| (perm.clone(), *cursor))
-            .collect();
+    /// Applies the transforms of the [`PayloadMiddleware`] of the user, if any, to the masked
+    /// payload of a packet to send. Returns None if the payload is sent unchanged.
+    ///
+    /// # Arguments
+    /// * `payload`: The masked payload of the packet
+    fn encode_payload(&self, payload: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.payload_middleware
+            .as_ref()
+            .map(|middleware| middleware.encode(payload))
+            .transpose()
+    }
 
-        for (perm, cursor) in stored_subscribers {
-            if !(perm.identifier() == author_identifier
-                || subscribers.iter().any(|p| p.identifier() == perm.identifier()))
-            {
-                self.state
-                    .cursor_store
-                    .insert_cursor(&topic, Permissioned::Read(perm.identifier().clone()), cursor);
+    /// Inverts the transforms of the [`PayloadMiddleware`] of the user, if any, on the masked
+    /// payload of a signed or tagged packet read. Packets whose payload cannot be restored are
+    /// returned as [`MessageContent::Rejected`], other messages are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `message`: The processed [`Message`]
+    fn decode_payload(&self, mut message: Message) -> Message {
+        let middleware = match &self.payload_middleware {
+            Some(middleware) => middleware,
+            None => return message,
+        };
+        let masked_payload = match &mut message.content {
+            MessageContent::SignedPacket(SignedPacket { masked_payload, .. })
+            | MessageContent::TaggedPacket(TaggedPacket { masked_payload, .. }) => masked_payload,
+            _ => return message,
+        };
+        match middleware.decode(masked_payload) {
+            Ok(decoded) => {
+                *masked_payload = decoded;
+                message
             }
+            Err(e) => message.reject(e.to_string()),
         }
+    }
 
-        // Store message content into stores
-        for subscriber in subscribers {
-            if self.should_store_cursor(&topic, subscriber.as_ref()) {
-                self.state
-                    .cursor_store
-                    .insert_cursor(&topic, subscriber.clone(), INIT_MESSAGE_NUM);
-            }
+    /// Validates a packet with the [`PayloadValidator`] of its branch, if any. Invalid packets are
+    /// returned as [`MessageContent::Rejected`], other messages are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `message`: The processed [`Message`]
+    fn validate_payload(&self, message: Message) -> Message {
+        if !message.is_packet() || self.payload_validators.is_empty() {
+            return message;
+        }
+        let verdict = self
+            .topic_by_hash(message.topic_hash())
+            .and_then(|topic| self.payload_validators.get(&topic))
+            .map(|validator| validator.validate(&message));
+        match verdict {
+            Some(ValidationVerdict::Invalid(reason)) => message.reject(reason),
+            _ => message,
         }
-
-        // Have to make message before setting branch links due to immutable borrow in keyload::unwrap
-        let final_message = Message::from_lets_message(address, message);
-        // Update branch links
-        self.set_latest_link(topic, address.relative());
-        Ok(final_message)
     }
 
-    /// Processes a signed packet message, retrieving the public and masked payloads, and verifying
-    /// the message signature against the publisher [`Identifier`].
+    /// Returns the Unix timestamp, in seconds, at which the access of the [`User`] to the packets
+    /// of a publisher on a branch expires, if it does. The packets of the user itself are not
+    /// subject to the expiry, nor are the packets read by the admins of the branch, who grant the
+    /// access.
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_signed_packet(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        let topic = self
-            .topic_by_hash(preparsed.header().topic_hash())
-            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
-        let publisher = preparsed.header().publisher();
-        let permission = self
-            .state
-            .cursor_store
-            .get_permission(&topic, publisher)
-            .ok_or(Error::NoCursor(topic.clone()))?
-            .clone();
-        // From the point of view of cursor tracking, the message exists, regardless of the validity or
-        // accessibility to its content. Therefore we must update the cursor of the publisher before
-        // handling the message
-        self.state
-            .cursor_store
-            .insert_cursor(&topic, permission, preparsed.header().sequence());
-
-        // Unwrap message
-        let linked_msg_address = preparsed
-            .header()
-            .linked_msg_address()
-            .ok_or(Error::NotLinked("signed", address))?;
-        let mut linked_msg_spongos = {
-            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address).copied() {
-                // Spongos must be copied because wrapping mutates it
-                spongos
-            } else {
-                return Ok(Message::orphan(address, preparsed));
-            }
-        };
-        let signed_packet = signed_packet::Unwrap::new(&mut linked_msg_spongos);
-        let (message, spongos) = preparsed
-            .unwrap(signed_packet)
-            .await
-            .map_err(|e| Error::Unwrapping("signed packet", address, e))?;
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher of the packets
+    fn packet_access_expiry(&self, topic: &Topic, publisher: &Identifier) -> Option<u64> {
+        if self.identifier() == Some(publisher) || self.permission(topic).map_or(false, Permissioned::is_admin) {
+            return None;
+        }
+        self.state.access_expirations.get(topic).copied()
+    }
 
-        // Store spongos
-        self.store_spongos(address.relative(), spongos, linked_msg_address);
+    /// Records the outcome of the processing of a message in the [`Metrics`] and the
+    /// [`ReplayRecorder`] of the user, if any
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `raw`: The raw [`TransportMessage`], kept only if the user has a [`ReplayRecorder`]
+    /// * `handled`: The outcome of the processing
+    fn record_handled(&mut self, address: Address, raw: Option<TransportMessage>, handled: &Result<Message>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_message(handled.as_ref().map_or(false, |message| !message.is_rejected()));
+        }
+        if let (Some(recorder), Some(raw)) = (&mut self.replay_recorder, raw) {
+            recorder.record(ReplayEntry::new(address, raw, ReplayOutcome::from(handled)));
+        }
+    }
 
-        // Store message content into stores
-        self.set_latest_link(topic, address.relative());
-        Ok(Message::from_lets_message(address, message))
+    /// Returns a copy of a raw message to be recorded, if the user has a [`ReplayRecorder`]
+    ///
+    /// # Arguments
+    /// * `msg`: The raw [`TransportMessage`]
+    fn replay_copy(&self, msg: &TransportMessage) -> Option<TransportMessage> {
+        self.replay_recorder.as_ref().map(|_| msg.clone())
     }
 
-    /// Processes a tagged packet message, retrieving the public and masked payloads.
+    /// Records a failure of the transport in the [`Metrics`] of the user, if any
     ///
-    /// # Arguments:
-    /// * `address`: The [`Address`] of the message to be processed
-    /// * `preparsed`: The [`PreparsedMessage`] to be processed
-    async fn handle_tagged_packet(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
-        let topic = self
-            .topic_by_hash(preparsed.header().topic_hash())
-            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
-        let publisher = preparsed.header().publisher();
-        let permission = self
-            .state
-            .cursor_store
-            .get_permission(&topic, publisher)
-            .ok_or(Error::NoCursor(topic.clone()))?
-            .clone();
-        // From the point of view of cursor tracking, the message exists, regardless of the validity or
-        // accessibility to its content. Therefore we must update the cursor of the publisher before
-        // handling the message
-        self.state
-            .cursor_store
-            .insert_cursor(&topic, permission, preparsed.header().sequence());
+    /// # Arguments
+    /// * `error`: The error returned by the transport
+    fn record_transport_error(&self, error: &LetsError) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_transport_error(error);
+        }
+    }
 
-        // Unwrap message
-        let linked_msg_address = preparsed
-            .header()
-            .linked_msg_address()
-            .ok_or(Error::NotLinked("tagged", address))?;
-        let mut linked_msg_spongos = {
-            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address).copied() {
-                // Spongos must be copied because wrapping mutates it
-                spongos
-            } else {
-                return Ok(Message::orphan(address, preparsed));
-            }
-        };
-        let tagged_packet = tagged_packet::Unwrap::new(&mut linked_msg_spongos);
-        let (message, spongos) = preparsed
-            .unwrap(tagged_packet)
+    /// Decodes the header of a raw message without processing it, to screen the message. Returns
+    /// `None` for legacy (v1) messages.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message
+    /// * `msg`: The raw [`TransportMessage`]
+    /// * `size_limit`: The largest length or item count accepted in the header
+    async fn preview_header(address: Address, msg: &TransportMessage, size_limit: usize) -> Result<Option<HDF>> {
+        if legacy::is_legacy(msg) {
+            return Ok(None);
+        }
+        let preparsed = msg
+            .clone()
+            .parse_header_with_size_limit(size_limit)
             .await
-            .map_err(|e| Error::Unwrapping("tagged packet", address, e))?;
-
-        // Store spongos
-        self.store_spongos(address.relative(), spongos, linked_msg_address);
-
-        // Store message content into stores
-        self.set_latest_link(topic, address.relative());
+            .map_err(|e| Error::Unwrapping("header", address, e))?;
+        Ok(Some(preparsed.into_parts().0))
+    }
 
-        Ok(Message::from_lets_message(address, message))
+    /// Returns the [`LinkGenerator`] deriving the addresses of the stream messages
+    pub fn link_generator(&self) -> &dyn LinkGenerator {
+        self.link_generator.as_ref()
     }
 
-    /// Creates an encrypted, serialised representation of a [`User`] `State` for backup and
-    /// recovery.
+    /// Replaces the [`LinkGenerator`] deriving the addresses of the stream messages. Needed after
+    /// [restoring](`User::restore`) a [`User`] that was built with a custom generator, as the
+    /// generator is not part of the backup.
     ///
     /// # Arguments
-    /// * `pwd`: The password to encrypt the `State` with
-    pub async fn backup<P>(&mut self, pwd: P) -> Result<Vec<u8>>
+    /// * `link_generator`: The new [`LinkGenerator`]
+    pub fn set_link_generator<G>(&mut self, link_generator: G)
     where
-        P: AsRef<[u8]>,
+        G: LinkGenerator + 'static,
     {
-        let mut ctx = sizeof::Context::new();
-        ctx.sizeof(&self.state).await.map_err(Error::Spongos)?;
-        let buf_size = ctx.finalize() + 32; // State + Mac Size
+        self.link_generator = Box::new(link_generator);
+    }
 
-        let mut buf = vec![0; buf_size];
+    /// Returns an iterator over all known branch [topics](`Topic`)
+    pub fn topics(&self) -> impl Iterator<Item = &Topic> + ExactSizeIterator {
+        self.state.topics.iter()
+    }
 
-        let mut ctx = wrap::Context::new(&mut buf[..]);
-        let key: [u8; 32] = SpongosRng::<KeccakF1600>::new(pwd).gen();
-        ctx.absorb(External::new(&NBytes::new(key)))
-            .map_err(Error::Spongos)?
-            .commit()
-            .map_err(Error::Spongos)?
-            .squeeze(&Mac::new(32))
-            .map_err(Error::Spongos)?;
-        ctx.wrap(&mut self.state).await.map_err(Error::Spongos)?;
-        assert!(
-            ctx.stream().is_empty(),
-            "Missmatch between buffer size expected by SizeOf ({buf_size}) and actual size of Wrap ({})",
-            ctx.stream().len()
-        );
+    /// Returns an iterator over the known branch [topics](`Topic`) falling under a hierarchical
+    /// prefix (see [`Topic::has_prefix()`])
+    ///
+    /// # Arguments
+    /// * `prefix`: The [`Topic`] prefix of the branches
+    pub fn topics_with_prefix<'a>(&'a self, prefix: &'a Topic) -> impl Iterator<Item = &'a Topic> + 'a {
+        self.topics().filter(move |topic| topic.has_prefix(prefix))
+    }
 
-        Ok(buf)
+    /// Returns an iterator over the branch [topics](`Topic`) that have been closed
+    pub fn closed_topics(&self) -> impl Iterator<Item = &Topic> + ExactSizeIterator {
+        self.state.closed_branches.iter()
     }
 
-    /// Restore a [`User`] from an encrypted binary stream using the provided password and transport
-    /// client.
+    /// Returns true if the branch has been closed, in which case no further message can be
+    /// published in it
     ///
     /// # Arguments
-    /// * `backup`: Encrypted binary stream of backed up `State`.
-    /// * `pwd`: The decryption password.
-    /// * `transport`: The transport client for sending and receiving messages.
-    pub async fn restore<B, P>(backup: B, pwd: P, transport: T) -> Result<Self>
-    where
-        P: AsRef<[u8]>,
-        B: AsRef<[u8]>,
-    {
-        let mut ctx = unwrap::Context::new(backup.as_ref());
-        let key: [u8; 32] = SpongosRng::<KeccakF1600>::new(pwd).gen();
-        ctx.absorb(External::new(&NBytes::new(key)))
-            .map_err(Error::Spongos)?
-            .commit()
-            .map_err(Error::Spongos)?
-            .squeeze(&Mac::new(32))
-            .map_err(Error::Spongos)?;
-        let mut state = State::default();
-        ctx.unwrap(&mut state).await.map_err(Error::Spongos)?;
-        Ok(User { transport, state })
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn is_branch_closed(&self, topic: &Topic) -> bool {
+        self.state.closed_branches.contains(topic)
     }
-}
 
-impl<T> User<T>
-where
-    T: for<'a> Transport<'a, Msg = TransportMessage>,
-{
-    /// Receive a raw message packet using the internal [`Transport`] client
+    /// Iterates through known topics, returning the [`Topic`] that matches the [`TopicHash`]
+    /// provided if any. Resolves the branch of a [`Message`] from [`Message::topic_hash()`].
     ///
     /// # Arguments
-    /// * `address`: The [`Address`] of the message to be retrieved.
-    pub async fn receive_message(&mut self, address: Address) -> Result<Message>
-    where
-        T: for<'a> Transport<'a, Msg = TransportMessage>,
-    {
-        let msg = self
-            .transport
-            .recv_message(address)
-            .await
-            .map_err(|e| Error::Transport(address, "receive message", e))?;
-        self.handle_message(address, msg).await
+    /// * `hash`: The [`TopicHash`] from a message header
+    pub fn topic_by_hash(&self, hash: &TopicHash) -> Option<Topic> {
+        self.topics().find(|t| &TopicHash::from(*t) == hash).cloned()
     }
 
-    /// Start a [`Messages`] stream to traverse the channel messages
-    ///
-    /// See the documentation in [`Messages`] for more details and examples.
-    pub fn messages(&mut self) -> Messages<T> {
-        Messages::new(self)
+    /// Returns true if [`User`] lean state configuration is true
+    fn lean(&self) -> bool {
+        self.state.lean
     }
 
-    /// Iteratively fetches all the next messages until internal state has caught up
-    ///
-    /// If succeeded, returns the number of messages advanced.
-    pub async fn sync(&mut self) -> Result<usize> {
-        // ignoring the result is sound as Drain::Error is Infallible
-        self.messages()
-            .try_fold(0, |n, _| future::ok(n + 1))
-            .await
-            .map_err(Error::Messages)
+    /// Returns true if the keyloads sent by the [`User`] enable forward secrecy on their branch
+    pub fn forward_secrecy(&self) -> bool {
+        self.state.forward_secrecy
     }
 
-    /// Iteratively fetches all the pending messages from the transport
+    /// Returns true if the branch is in forward secrecy mode
     ///
-    /// Return a vector with all the messages collected. This is a convenience
-    /// method around the [`Messages`] stream. Check out its docs for more
-    /// advanced usages.
-    pub async fn fetch_next_messages(&mut self) -> Result<Vec<Message>> {
-        self.messages().try_collect().await.map_err(Error::Messages)
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    pub fn is_forward_secret(&self, topic: &Topic) -> bool {
+        self.state.ratchets.contains_key(topic)
     }
-}
 
-impl<T, TSR> User<T>
-where
-    T: for<'a> Transport<'a, Msg = TransportMessage, SendResponse = TSR>,
-{
-    /// Create and send a stream Announcement message, anchoring the stream for others to attach to.
-    /// Errors if the [`User`] is already attached to a stream, or if the message already exists in
-    /// the transport layer.
+    /// Returns a copy of the ratchet of a publisher on a forward secrecy branch, advanced past the
+    /// provided sequence number, together with the message key for it. Returns `None` if the
+    /// branch is not in forward secrecy mode. The ratchet is only committed to state with
+    /// [`User::store_ratchet()`] once the message has been sent or processed. Fails with
+    /// [`Error::RatchetSkipExceeded`] if the sequence number is too far ahead of the ratchet.
     ///
     /// # Arguments
-    /// * `topic`: The [`Topic`] that will be used for the base branch
-    pub async fn create_stream<Top: Into<Topic>>(&mut self, topic: Top) -> Result<SendResponse<TSR>> {
-        // Check conditions
-        if self.stream_address().is_some() {
-            return Err(Error::Setup(
-                "Cannot create a channel, user is already registered to channel",
-            ));
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher of the message
+    /// * `sequence`: The sequence number of the message
+    fn advance_ratchet(
+        &self,
+        topic: &Topic,
+        publisher: &Identifier,
+        sequence: usize,
+    ) -> Result<Option<(Ratchet, [u8; RATCHET_KEY_SIZE])>> {
+        let branch = match self.state.ratchets.get(topic) {
+            Some(branch) => branch,
+            None => return Ok(None),
+        };
+        let mut ratchet = branch
+            .get(publisher)
+            .cloned()
+            .ok_or_else(|| Error::RatchetUnavailable(publisher.clone(), topic.clone(), sequence))?;
+        if ratchet.exceeds_skip(sequence) {
+            return Err(Error::RatchetSkipExceeded(publisher.clone(), topic.clone(), sequence));
         }
-        // Confirm user has identity
-        let identifier = self.identifier().ok_or(Error::NoIdentity("create a stream"))?.clone();
-        // Convert topic
-        let topic = topic.into();
-        // Generate stream address
-        let stream_base_address = AppAddr::gen(&identifier, &topic);
-        let stream_rel_address = MsgId::gen(stream_base_address, &identifier, &topic, INIT_MESSAGE_NUM);
-        let stream_address = Address::new(stream_base_address, stream_rel_address);
+        let key = ratchet
+            .message_key(sequence)
+            .ok_or_else(|| Error::RatchetUnavailable(publisher.clone(), topic.clone(), sequence))?;
+        Ok(Some((ratchet, key)))
+    }
 
-        // Prepare HDF and PCF
-        let header = HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, identifier.clone(), &topic);
-        let content = PCF::new_final_frame().with_content(announcement::Wrap::new(self.identity().unwrap(), &topic));
+    /// Commits the advanced ratchet of a publisher to state, erasing the keys of its processed
+    /// messages
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    /// * `ratchet`: The advanced [`Ratchet`]
+    fn store_ratchet(&mut self, topic: &Topic, publisher: &Identifier, ratchet: Ratchet) {
+        if let Some(branch) = self.state.ratchets.get_mut(topic) {
+            branch.insert(publisher.clone(), ratchet);
+        }
+    }
 
-        // Wrap message
-        let (transport_msg, spongos) = LetsMessage::new(header, content)
-            .wrap()
-            .await
-            .map_err(|e| Error::Wrapped("wrap announce", e))?;
+    /// Returns the [`KeyExchange`] recovering the keys encrypted to the rotated key exchange key of
+    /// the user and its generation, if the key has been rotated
+    ///
+    /// # Arguments
+    /// * `exchange_key`: The [`RotatedExchangeKey`] of the user, if any
+    /// * `secret_key`: The secret key of the user, if it holds it
+    /// * `key_exchange`: The [`KeyExchange`] of the user, if any
+    fn exchange_key<'a>(
+        exchange_key: Option<RotatedExchangeKey>,
+        secret_key: Option<&'a x25519::SecretKey>,
+        key_exchange: Option<&'a dyn KeyExchange>,
+    ) -> Result<Option<(usize, &'a dyn KeyExchange)>> {
+        match (exchange_key, secret_key, key_exchange) {
+            (Some(RotatedExchangeKey::Local(generation, _)), Some(secret_key), _) => {
+                Ok(Some((generation, secret_key as &dyn KeyExchange)))
+            }
+            (Some(RotatedExchangeKey::External(generation)), _, Some(key_exchange)) => {
+                Ok(Some((generation, key_exchange)))
+            }
+            (Some(RotatedExchangeKey::External(_)), _, None) => Err(Error::Setup(
+                "the key exchange key of the user is held by a KeyExchange that is not set",
+            )),
+            _ => Ok(None),
+        }
+    }
 
-        // Attempt to send message
-        if !self.transport.recv_message(stream_address).await.is_err() {
-            return Err(Error::Setup("Cannot create a channel, announce address already in use"));
+    /// Returns the hash of a raw message if the user notarizes its messages
+    ///
+    /// # Arguments
+    /// * `msg`: The raw [`TransportMessage`]
+    fn message_hash(&self, msg: &TransportMessage) -> Option<[u8; DIGEST_SIZE]> {
+        self.notarization.as_ref().map(|_| notarizer::message_hash(msg))
+    }
+
+    /// Records the hash of a message sent by the user, anchoring the checkpoint of its window if
+    /// the message closes it. Announcements and subscriptions are not notarized.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the message
+    /// * `publisher`: The [`Identifier`] of the user
+    /// * `sequence`: The sequence number of the message
+    /// * `hash`: The hash of the message, as returned by [`User::message_hash()`]
+    async fn notarize_sent(
+        &mut self,
+        topic: &Topic,
+        publisher: &Identifier,
+        sequence: usize,
+        hash: Option<[u8; DIGEST_SIZE]>,
+    ) -> Result<()> {
+        match (self.notarization.as_mut(), hash) {
+            (Some(notarization), Some(hash)) if sequence >= INIT_MESSAGE_NUM => {
+                notarization.notarize_sent(topic, publisher, sequence, hash).await
+            }
+            _ => Ok(()),
         }
+    }
 
-        let send_response = self
-            .transport
-            .send_message(stream_address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(stream_address, "send announce message", e))?;
+    /// Records the hash of a message read by the user, verifying its window against the anchored
+    /// checkpoint. Announcements, subscriptions and orphans are not notarized.
+    ///
+    /// # Arguments
+    /// * `message`: The processed [`Message`]
+    /// * `hash`: The hash of the message, as returned by [`User::message_hash()`]
+    async fn notarize_read(&mut self, message: &Message, hash: Option<[u8; DIGEST_SIZE]>) -> Result<()> {
+        let topic = match self.topic_by_hash(message.header.topic_hash()) {
+            Some(topic) => topic,
+            None => return Ok(()),
+        };
+        let sequence = message.header.sequence();
+        match (self.notarization.as_mut(), hash) {
+            (Some(notarization), Some(hash)) if sequence >= INIT_MESSAGE_NUM && !message.is_orphan() => {
+                notarization
+                    .notarize_read(&topic, message.header.publisher(), sequence, hash)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
 
-        // If a message has been sent successfully, insert the base branch into store
-        self.state.cursor_store.new_branch(topic.clone());
-        self.state.topics.insert(topic.clone());
-        // Commit message to stores
+    /// Returns an iterator over [`CursorStore`], producing tuples of [`Topic`], [`Permissioned`]
+    /// [`Identifier`], and the cursor. Used by [`Messages`] streams to find next messages.
+    pub(crate) fn cursors(&self) -> impl Iterator<Item = (&Topic, &Permissioned<Identifier>, usize)> + '_ {
+        self.state.cursor_store.cursors()
+    }
+
+    /// Returns an iterator over a [`Topic`] mapped branch in [`CursorStore`], producing tuples of
+    /// [`Permissioned`][`Identifier`] and a cursor. Used to carry permissions forward through
+    /// branch declarations. Returns an error if the [`Topic`] is not found in store.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to fetch cursors for
+    fn cursors_by_topic(&self, topic: &Topic) -> Result<impl Iterator<Item = (&Permissioned<Identifier>, &usize)>> {
         self.state
             .cursor_store
-            .insert_cursor(&topic, Permissioned::Admin(identifier.clone()), INIT_MESSAGE_NUM);
-        self.state.spongos_store.insert(stream_address.relative(), spongos);
+            .cursors_by_topic(topic)
+            .ok_or(Error::TopicNotFound(topic.clone()))
+    }
 
-        // Update branch links
-        self.set_latest_link(topic.clone(), stream_address.relative());
+    /// Returns an iterator over known subscriber [identifiers](`Identifier`)
+    pub fn subscribers(&self) -> impl Iterator<Item = &Identifier> + Clone + '_ {
+        self.state.subscribers.iter()
+    }
 
-        // Commit Author Identifier and Stream Address to store
-        self.state.stream_address = Some(stream_address);
-        self.state.author_identifier = Some(identifier);
-        self.state.base_branch = topic;
+    /// If the subscriber is not readonly and the [`Permissioned`] is not tracked or the
+    /// [`Permissioned`] is tracked and not equal to the provided subscriber [`Permissioned`],
+    /// then the cursor should be stored.
+    ///
+    /// # Arguments:
+    /// * `topic`: The topic of the branch to be stored in.
+    /// * `permission`: The [`Permissioned`] to check.
+    /// Returns true if a keyload of a branch can grant permissions to an identifier without
+    /// [`UserBuilder::allow_unsubscribed()`]: the user itself, the subscribers and registered
+    /// readers of the stream and the participants already known in the branch
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `recipient`: The [`Identifier`] granted permissions
+    fn is_known_recipient(&self, topic: &Topic, recipient: &Identifier) -> bool {
+        self.identifier() == Some(recipient)
+            || self.state.subscribers.contains(recipient)
+            || self.state.readers.contains(recipient)
+            || self.state.cursor_store.get_permission(topic, recipient).is_some()
+    }
 
-        Ok(SendResponse::new(stream_address, send_response))
+    /// Returns an iterator over the readers registered with [`User::register_reader()`] that are
+    /// neither subscribers nor the user itself, to be granted read access by the keyloads sent for
+    /// all participants
+    fn registered_readers(&self) -> impl Iterator<Item = &Identifier> + '_ {
+        self.state
+            .readers
+            .iter()
+            .filter(|reader| !self.state.subscribers.contains(*reader) && self.identifier() != Some(*reader))
     }
 
-    /// Create and send a new Branch Announcement message, creating a new branch in `CursorStore`
-    /// with the previous branches permissions carried forward.
+    fn should_store_cursor(&self, topic: &Topic, permission: Permissioned<&Identifier>) -> bool {
+        let self_permission = self.state.cursor_store.get_permission(topic, permission.identifier());
+        let tracked_and_equal = self_permission.is_some() && (self_permission.unwrap().as_ref() == permission);
+        !permission.is_readonly() && !tracked_and_equal
+    }
+
+    /// Store a new [`Spongos`] state. If the [`User`] lean state configuration is set to true, and
+    /// if the linked message is not the stream announcement message, remove the previous message
+    /// from store. If the [`User`] checkpoints at keyloads, the state of the previous message is
+    /// removed if it can be recomputed.
     ///
-    /// # Arguments
-    /// * `from_topic`: The [`Topic`] of the branch to generate the new branch from.
-    /// * `to_topic`: The [`Topic`] of the new branch being created.
-    pub async fn new_branch(
+    /// # Arguments:
+    /// * `msg_address`: The [`Address`] of the message that we're storing the [`Spongos`] for.
+    /// * `spongos`: The [`Spongos`] state to be stored.
+    /// * `linked_msg_address`: The address of the message that the spongos is linked to.
+    /// * `sequence`: The publisher cursor of the message.
+    fn store_spongos(
         &mut self,
-        from_topic: impl Into<Topic>,
-        to_topic: impl Into<Topic>,
-    ) -> Result<SendResponse<TSR>> {
-        // Check conditions
-        let stream_address = self
+        msg_address: MsgId,
+        spongos: Spongos,
+        linked_msg_address: MsgId,
+        sequence: usize,
+    ) -> Result<()> {
+        let is_stream_address = self
             .stream_address()
-            .ok_or(Error::Setup("before starting a new branch, the stream must be created"))?;
-        // Confirm user has identity
-        let identifier = self.identifier().ok_or(Error::NoIdentity("create a branch"))?.clone();
-        // Check Topic
-        let topic: Topic = to_topic.into();
-        let prev_topic: Topic = from_topic.into();
-        // Check Permission
-        let permission = self
-            .state
-            .cursor_store
-            .get_permission(&prev_topic, &identifier)
-            .ok_or(Error::NoCursor(topic.clone()))?;
-        if permission.is_readonly() {
-            return Err(Error::WrongRole("ReadWrite", identifier, "make a new branch"));
+            .map_or(false, |stream_address| stream_address.relative() == linked_msg_address);
+        // Do not remove announcement message from store
+        if (self.lean() && !is_stream_address) || self.state.recomputable.remove(&linked_msg_address) {
+            self.state.spongos_store.remove(&linked_msg_address)?;
+            self.state.spongos_positions.remove(&linked_msg_address);
         }
-        let link_to = self
-            .get_latest_link(&prev_topic)
-            .ok_or_else(|| Error::TopicNotFound(prev_topic.clone()))?;
-
-        // Update own's cursor
-        let user_cursor = self
-            .next_cursor(&prev_topic)
-            .map_err(|_| Error::NoCursor(prev_topic.clone()))?;
-        let msgid = MsgId::gen(stream_address.base(), &identifier, &prev_topic, user_cursor);
-        let address = Address::new(stream_address.base(), msgid);
 
-        // Prepare HDF and PCF
-        // Spongos must be copied because wrapping mutates it
-        let mut linked_msg_spongos = self
-            .state
-            .spongos_store
-            .get(&link_to)
-            .copied()
-            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
-        let header = HDF::new(
-            message_types::BRANCH_ANNOUNCEMENT,
-            user_cursor,
-            identifier.clone(),
-            &prev_topic,
-        )
-        .with_linked_msg_address(link_to);
-        let content = PCF::new_final_frame().with_content(branch_announcement::Wrap::new(
-            &mut linked_msg_spongos,
-            self.identity().unwrap(),
-            &topic,
-        ));
+        self.state.spongos_store.insert(msg_address, spongos)?;
+        self.state
+            .spongos_positions
+            .insert(msg_address, SpongosPosition::now(sequence));
+        Ok(())
+    }
 
-        // Wrap message
-        let (transport_msg, spongos) = LetsMessage::new(header, content)
-            .wrap()
-            .await
-            .map_err(|e| Error::Wrapped("wrap new branch", e))?;
+    /// Marks the stored [`Spongos`] state of a packet or a read marker as recomputable, if the
+    /// [`User`] checkpoints at keyloads. The states of the messages of forward secrecy branches
+    /// depend on the ratchets of the publishers, which cannot be set back, so they are always kept.
+    ///
+    /// # Arguments:
+    /// * `topic`: The [`Topic`] of the branch of the message
+    /// * `msg_address`: The [`MsgId`] of the message
+    fn mark_recomputable(&mut self, topic: &Topic, msg_address: MsgId) {
+        if self.state.keyload_checkpoints && !self.state.ratchets.contains_key(topic) {
+            self.state.recomputable.insert(msg_address);
+        }
+    }
 
-        if !self.transport.recv_message(address).await.is_err() {
-            return Err(Error::AddressUsed("new branch", address));
+    /// Returns the [`Spongos`] state of a message. If the [`User`] checkpoints at keyloads and the
+    /// state was dropped, the messages following the last state kept are fetched again and
+    /// unwrapped up to the message, and the recomputed state is stored until another message is
+    /// linked to it. Returns `None` if the state is not stored and cannot be recomputed.
+    ///
+    /// # Arguments:
+    /// * `link`: The [`MsgId`] of the message
+    async fn linked_spongos(&mut self, link: MsgId) -> Result<Option<Spongos>>
+    where
+        T: for<'a> Transport<'a, Msg = TransportMessage>,
+    {
+        if let Some(spongos) = self.state.spongos_store.get(&link)? {
+            return Ok(Some(spongos));
         }
+        let base = match self.stream_address() {
+            Some(stream_address) if self.state.keyload_checkpoints => stream_address.base(),
+            _ => return Ok(None),
+        };
 
-        let send_response = self
-            .transport
-            .send_message(address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(stream_address, "send new branch message", e))?;
+        // Walk the links back to the last state kept, collecting the messages in between
+        let mut intervening = Vec::new();
+        let mut address = Address::new(base, link);
+        let mut spongos = loop {
+            let msg = match self.transport.recv_message(address).await {
+                Ok(msg) if !legacy::is_legacy(&msg) => msg,
+                _ => return Ok(None),
+            };
+            let preparsed = msg
+                .parse_header_with_size_limit(self.size_limit)
+                .await
+                .map_err(|e| Error::Unwrapping("header", address, e))?;
+            let is_recomputable = matches!(
+                preparsed.header().message_type(),
+                message_types::SIGNED_PACKET
+                    | message_types::TAGGED_PACKET
+                    | message_types::BATCH_PACKET
+                    | message_types::READ_MARKER
+            ) && self
+                .topic_by_hash(preparsed.header().topic_hash())
+                .map_or(false, |topic| !self.state.ratchets.contains_key(&topic));
+            let linked_msg_address = match preparsed.header().linked_msg_address() {
+                Some(linked_msg_address) if is_recomputable => linked_msg_address,
+                _ => return Ok(None),
+            };
+            intervening.push((address, preparsed));
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                break spongos;
+            }
+            address = Address::new(base, linked_msg_address);
+        };
 
-        // If message has been sent successfully, create the new branch in store
-        self.state.cursor_store.new_branch(topic.clone());
-        self.state.topics.insert(topic.clone());
-        // Commit message to stores and update cursors
-        self.state.cursor_store.insert_cursor(
-            &prev_topic,
-            Permissioned::Admin(identifier.clone()),
-            self.next_cursor(&prev_topic)?,
-        );
-        self.state.spongos_store.insert(address.relative(), spongos);
-        // Collect permissions from previous branch and clone them into new branch
-        let prev_permissions = self
-            .cursors_by_topic(&prev_topic)?
-            .map(|(id, _)| id.clone())
-            .collect::<Vec<Permissioned<Identifier>>>();
-        for id in prev_permissions {
-            self.state.cursor_store.insert_cursor(&topic, id, INIT_MESSAGE_NUM);
+        // Unwrap the messages again from the oldest one
+        for (address, preparsed) in intervening.into_iter().rev() {
+            spongos = Self::recompute_spongos(address, preparsed, spongos).await?;
         }
-
-        // Update branch links
-        self.state.cursor_store.set_latest_link(topic, address.relative());
-        Ok(SendResponse::new(address, send_response))
+        self.state.spongos_store.insert(link, spongos)?;
+        self.state.recomputable.insert(link);
+        Ok(Some(spongos))
     }
 
-    /// Create and send a new Subscription message, awaiting the stream author's acceptance into the
-    /// stream.
-    pub async fn subscribe(&mut self) -> Result<SendResponse<TSR>> {
-        // Check conditions
-        let stream_address = self
-            .stream_address()
-            .ok_or(Error::Setup("before starting a new branch, the stream must be created"))?;
-        // Confirm user has identity
-        let user_id = self.identity().ok_or(Error::NoIdentity("subscribe"))?;
-        let identifier = user_id.identifier();
-        // Get base branch topic
-        let base_branch = &self.state.base_branch;
-        // Link message to channel announcement
-        let link_to = stream_address.relative();
-        let rel_address = MsgId::gen(stream_address.base(), identifier, base_branch, SUB_MESSAGE_NUM);
+    /// Unwraps a packet or a read marker again from the [`Spongos`] state of the message it is
+    /// linked to, returning the state of the message. The content of the message is discarded.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message
+    /// * `preparsed`: The [`PreparsedMessage`] to unwrap
+    /// * `linked_msg_spongos`: The [`Spongos`] state of the message it is linked to
+    async fn recompute_spongos(
+        address: Address,
+        preparsed: PreparsedMessage,
+        mut linked_msg_spongos: Spongos,
+    ) -> Result<Spongos> {
+        let recomputed = match preparsed.header().message_type() {
+            message_types::TAGGED_PACKET => preparsed
+                .unwrap(tagged_packet::Unwrap::new(&mut linked_msg_spongos))
+                .await
+                .map(|(_, spongos)| spongos),
+            message_types::BATCH_PACKET => preparsed
+                .unwrap(batch_packet::Unwrap::new(&mut linked_msg_spongos))
+                .await
+                .map(|(_, spongos)| spongos),
+            message_types::READ_MARKER => preparsed
+                .unwrap(read_marker::Unwrap::new(&mut linked_msg_spongos))
+                .await
+                .map(|(_, spongos)| spongos),
+            _ => preparsed
+                .unwrap(signed_packet::Unwrap::new(&mut linked_msg_spongos))
+                .await
+                .map(|(_, spongos)| spongos),
+        };
+        recomputed.map_err(|e| Error::Unwrapping("recomputed message", address, e))
+    }
 
-        // Prepare HDF and PCF
-        // Spongos must be copied because wrapping mutates it
-        let mut linked_msg_spongos = self
+    /// Drops the [`Spongos`] states of the messages preceding a retention point, shrinking the
+    /// state of the user and its backups. The states of the stream announcement and of the latest
+    /// message of every branch are kept, so that the user can keep publishing and reading the
+    /// stream. Messages linked to a pruned message can no longer be processed. Returns the number
+    /// of states dropped.
+    ///
+    /// # Arguments
+    /// * `retention`: The [`Retention`] point before which the states are dropped
+    pub fn prune(&mut self, retention: Retention) -> Result<usize> {
+        let latest_links: HashSet<MsgId> = self
             .state
-            .spongos_store
-            .get(&link_to)
-            .copied()
-            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
-        let unsubscribe_key = StdRng::from_entropy().gen();
-        let author_ke_pk = self
+            .topics
+            .iter()
+            .filter_map(|topic| self.get_latest_link(topic))
+            .chain(self.stream_address().map(|address| address.relative()))
+            .collect();
+        let pruned: Vec<MsgId> = self
             .state
-            .author_identifier
-            .as_ref()
-            .unwrap()
-            .ke_pk()
-            .await
-            .map_err(|_| Error::Setup("Failed to generate Public Key from author identifier"))?;
+            .spongos_positions
+            .iter()
+            .filter(|(link, position)| !latest_links.contains(*link) && position.precedes(retention))
+            .map(|(link, _)| *link)
+            .collect();
+        for link in &pruned {
+            self.state.spongos_positions.remove(link);
+            self.state.spongos_store.remove(link)?;
+            self.state.recomputable.remove(link);
+        }
+        Ok(pruned.len())
+    }
 
-        let content = PCF::new_final_frame().with_content(subscription::Wrap::new(
-            &mut linked_msg_spongos,
-            unsubscribe_key,
-            user_id,
-            &author_ke_pk,
-        ));
-        let header = HDF::new(
-            message_types::SUBSCRIPTION,
-            SUB_MESSAGE_NUM,
-            identifier.clone(),
-            base_branch,
-        )
-        .with_linked_msg_address(link_to);
+    /// Replaces the [`SpongosStore`] holding the [`Spongos`] states of the messages, moving the
+    /// states stored so far into the new store. Restored users hold their states in memory until
+    /// another store is set.
+    ///
+    /// # Arguments
+    /// * `spongos_store`: The new [`SpongosStore`]
+    pub fn set_spongos_store<S>(&mut self, mut spongos_store: S) -> Result<()>
+    where
+        S: SpongosStore + 'static,
+    {
+        for (link, spongos) in self.state.spongos_store.entries()? {
+            spongos_store.insert(link, spongos)?;
+        }
+        self.state.spongos_store = Box::new(spongos_store);
+        Ok(())
+    }
 
-        // Wrap message
-        let (transport_msg, _spongos) = LetsMessage::new(header, content)
-            .wrap()
-            .await
-            .map_err(|e| Error::Wrapped("subscribe", e))?;
+    /// Store a new subscriber [`Identifier`] in state. Returns true if subscriber was not present.
+    pub fn add_subscriber(&mut self, subscriber: Identifier) -> bool {
+        self.state.subscribers.insert(subscriber)
+    }
 
-        // Attempt to send message
-        let message_address = Address::new(stream_address.base(), rel_address);
+    /// Remove a subscriber [`Identifier`] from state. Returns true if the subscriber was present.
+    pub fn remove_subscriber(&mut self, id: &Identifier) -> bool {
+        self.state.subscribers.remove(id)
+    }
 
-        // Attempt to send message
-        let has_msg = self.transport.recv_message(message_address).await;
-        if !has_msg.is_err() {
-            return Err(Error::AddressUsed("subscribe", message_address));
-        }
+    /// Registers the [`Identifier`] of a reader whose public key was obtained out of band, granting
+    /// it read access in the keyloads subsequently sent with [`User::send_keyload_for_all()`] and
+    /// [`User::send_keyload_for_all_rw()`], and allowing it as a recipient of [`User::send_keyload()`].
+    /// The reader does not need to publish a Subscribe message, which suits read-only consumers
+    /// that must not write to the Tangle. Returns true if the reader was not registered.
+    ///
+    /// # Arguments
+    /// * `reader`: The [`Identifier`] of the reader
+    pub fn register_reader(&mut self, reader: Identifier) -> bool {
+        self.state.readers.insert(reader)
+    }
 
-        let send_response = self
-            .transport
-            .send_message(message_address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(message_address, "send subscribe message", e))?;
+    /// Unregisters a reader registered with [`User::register_reader()`]. The reader keeps its access
+    /// until the next keyload of each branch. Returns true if the reader was registered.
+    ///
+    /// # Arguments
+    /// * `reader`: The [`Identifier`] of the reader
+    pub fn unregister_reader(&mut self, reader: &Identifier) -> bool {
+        self.state.readers.remove(reader)
+    }
 
-        // If message has been sent successfully, commit message to stores
-        // - Subscription messages are not stored in the cursor store
-        // - Subscription messages are never stored in spongos to maintain consistency about the view of the
-        // set of messages of the stream between all the subscribers and across stateless recovers
-        Ok(SendResponse::new(message_address, send_response))
+    /// Returns an iterator over the [Identifiers](`Identifier`) of the readers registered with
+    /// [`User::register_reader()`]
+    pub fn readers(&self) -> impl Iterator<Item = &Identifier> + Clone + '_ {
+        self.state.readers.iter()
     }
 
-    /// Create and send a new Unsubscription message, informing the stream author that this [`User`]
-    /// instance can be removed from the stream.
-    pub async fn unsubscribe(&mut self) -> Result<SendResponse<TSR>> {
-        // Check conditions
-        let stream_address = self
-            .stream_address()
-            .ok_or(Error::Setup("before unsubscribing, the stream must be created"))?;
-        // Confirm user has identity
-        let user_id = self.identity().ok_or(Error::NoIdentity("unsubscribe"))?;
-        let identifier = user_id.identifier().clone();
-        // Get base branch topic
-        let base_branch = &self.state.base_branch;
-        // Link message to channel announcement
-        let link_to = self
-            .get_latest_link(base_branch)
-            .ok_or_else(|| Error::TopicNotFound(base_branch.clone()))?;
+    /// Returns an iterator over the [Identifiers](`Identifier`) of the subscription requests held
+    /// for manual review by the [`SubscriptionPolicy`]
+    pub fn pending_subscriptions(&self) -> impl Iterator<Item = &Identifier> + ExactSizeIterator {
+        self.state.pending_subscriptions.iter()
+    }
 
-        // Update own's cursor
-        let new_cursor = self.next_cursor(base_branch)?;
-        let rel_address = MsgId::gen(stream_address.base(), &identifier, base_branch, new_cursor);
+    /// Accepts a subscription request held for review, storing the subscriber [`Identifier`] in
+    /// state. Returns true if the subscription request was pending.
+    ///
+    /// # Arguments
+    /// * `id`: The [`Identifier`] of the subscriber
+    pub fn accept_subscription(&mut self, id: &Identifier) -> bool {
+        let pending = self.state.pending_subscriptions.remove(id);
+        if pending {
+            self.add_subscriber(id.clone());
+        }
+        pending
+    }
 
-        // Prepare HDF and PCF
-        // Spongos must be copied because wrapping mutates it
-        let mut linked_msg_spongos = self
-            .state
-            .spongos_store
-            .get(&link_to)
-            .copied()
-            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
-        let content = PCF::new_final_frame().with_content(unsubscription::Wrap::new(&mut linked_msg_spongos, user_id));
-        let header = HDF::new(
-            message_types::UNSUBSCRIPTION,
-            new_cursor,
-            identifier.clone(),
-            base_branch,
-        )
-        .with_linked_msg_address(link_to);
+    /// Rejects a subscription request held for review, discarding it. Returns true if the
+    /// subscription request was pending.
+    ///
+    /// # Arguments
+    /// * `id`: The [`Identifier`] of the subscriber
+    pub fn reject_subscription(&mut self, id: &Identifier) -> bool {
+        self.state.pending_subscriptions.remove(id)
+    }
 
-        // Wrap message
-        let (transport_msg, spongos) = LetsMessage::new(header, content)
-            .wrap()
-            .await
-            .map_err(|e| Error::Wrapped("unsubscribe", e))?;
+    /// Store a new [Pre-Shared Key](`Psk`) in state. Returns true if [`Psk`] was not present.
+    pub fn add_psk(&mut self, psk: Psk) -> bool {
+        self.state.psk_store.insert(psk.to_pskid(), psk).is_none()
+    }
 
-        // Attempt to send message
-        let message_address = Address::new(stream_address.base(), rel_address);
-        if self.transport.recv_message(message_address).await.is_err() {
-            return Err(Error::AddressUsed("unsubscribe", message_address));
-        }
+    /// Remove a [`Psk`] from state by its [identifier](`PskId`). Returns true if the [`Psk`] was
+    /// present.
+    pub fn remove_psk(&mut self, pskid: PskId) -> bool {
+        self.state.psk_store.remove(&pskid).is_some()
+    }
 
-        let send_response = self
-            .transport
-            .send_message(message_address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(stream_address, "send unsubscribe message", e))?;
+    /// Returns an iterator over the [identifiers](`PskId`) of the [Pre-Shared Keys](`Psk`) stored
+    /// in state. The keys themselves are not exposed, and are kept in the backups of the user.
+    pub fn psks(&self) -> impl Iterator<Item = &PskId> + ExactSizeIterator + Clone + '_ {
+        self.state.psk_store.keys()
+    }
 
-        // If message has been sent successfully, commit message to stores
-        let permission = Permissioned::Read(identifier);
-        self.state
-            .cursor_store
-            .insert_cursor(base_branch, permission, new_cursor);
-        self.store_spongos(rel_address, spongos, link_to);
-        Ok(SendResponse::new(message_address, send_response))
+    /// Returns true if a [`Psk`] is stored in state under the provided [identifier](`PskId`)
+    ///
+    /// # Arguments
+    /// * `pskid`: The [`PskId`] of the key
+    pub fn has_psk(&self, pskid: PskId) -> bool {
+        self.state.psk_store.contains_key(&pskid)
     }
 
-    /// Create and send a new Keyload message, updating the read/write permissions for a specified
-    /// branch. All keyload messages are linked to the announcement message to ensure they
-    /// can always be read by a [`User`] that can sequence up to it.
+    /// Sets the latest message link for a specified branch. If the branch does not exist, it is
+    /// created.
     ///
     /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
-    /// * `subscribers`: The updated [`Permissioned`] list for the branch.
-    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branch.
-    pub async fn send_keyload<'a, Subscribers, Psks, Top>(
-        &mut self,
-        topic: Top,
-        subscribers: Subscribers,
-        psk_ids: Psks,
-    ) -> Result<SendResponse<TSR>>
-    where
-        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
-        Subscribers::IntoIter: ExactSizeIterator,
-        Top: Into<Topic>,
-        Psks: IntoIterator<Item = PskId>,
-    {
-        // Check conditions
-        let stream_address = self
-            .stream_address()
-            .ok_or(Error::Setup("before sending a keyload, the stream must be created"))?;
-        // Confirm user has identity
-        let user_id = self.identity().ok_or(Error::NoIdentity("send keyload"))?;
-        let identifier = user_id.identifier().clone();
-        // Check Topic
-        let topic = topic.into();
-        // Check Permission
-        let permission = self.permission(&topic).ok_or(Error::NoCursor(topic.clone()))?;
-        if !permission.is_admin() {
-            return Err(Error::WrongRole("Admin", identifier, "send a keyload"));
-        }
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `latest_link`: The [`MsgId`] link that will be set
+    fn set_latest_link(&mut self, topic: Topic, latest_link: MsgId) {
+        self.state.cursor_store.set_latest_link(topic, latest_link)
+    }
 
-        // Link message to edge of branch
-        let link_to = self
-            .get_latest_link(&topic)
-            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
-        // Update own's cursor
-        let new_cursor = self.next_cursor(&topic)?;
-        let rel_address = MsgId::gen(stream_address.base(), &identifier, &topic, new_cursor);
+    /// Returns the latest [`MsgId`] link for a specified branch, if any
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    fn get_latest_link(&self, topic: &Topic) -> Option<MsgId> {
+        self.state.cursor_store.get_latest_link(topic)
+    }
 
-        // Prepare HDF and PCF
-        // All Keyload messages will attach to stream Announcement message spongos
-        let mut announcement_msg_spongos = self
-            .state
-            .spongos_store
-            .get(&stream_address.relative())
-            .copied()
-            .ok_or(Error::Setup("a user must keep a stream announcement spongos in store"))?;
+    /// Parse and process a [`TransportMessage`] dependent on its type, recording the outcome in the
+    /// [`Metrics`] and the [`ReplayRecorder`] of the user.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to process
+    /// * `msg`: The raw [`TransportMessage`]
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    pub(crate) async fn handle_message(&mut self, address: Address, msg: TransportMessage) -> Result<Message> {
+        let raw = self.replay_copy(&msg);
+        let handled = self.process_message(address, msg).await;
+        self.record_handled(address, raw, &handled);
+        handled
+    }
 
-        let mut rng = StdRng::from_entropy();
-        let encryption_key = rng.gen();
-        let nonce = rng.gen();
-        let psk_ids_with_psks = psk_ids
-            .into_iter()
-            .map(|pskid| Ok((pskid, self.state.psk_store.get(&pskid).ok_or(Error::UnknownPsk(pskid))?)))
-            .collect::<Result<Vec<(_, _)>>>()?; // collect to handle possible error
-        let content = PCF::new_final_frame().with_content(keyload::Wrap::new(
-            &mut announcement_msg_spongos,
-            subscribers.clone().into_iter().collect::<Vec<_>>(),
-            &psk_ids_with_psks,
-            encryption_key,
-            nonce,
-            user_id,
-        ));
-        let header =
-            HDF::new(message_types::KEYLOAD, new_cursor, identifier.clone(), &topic).with_linked_msg_address(link_to);
+    /// Parse and process a [`TransportMessage`] dependent on its type.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to process
+    /// * `msg`: The raw [`TransportMessage`]
+    async fn process_message(&mut self, address: Address, msg: TransportMessage) -> Result<Message> {
+        if legacy::is_legacy(&msg) {
+            return self.handle_legacy_message(address, msg).await;
+        }
 
-        // Wrap message
-        let (transport_msg, spongos) = LetsMessage::new(header, content)
-            .wrap()
+        let hash = self.message_hash(&msg);
+        let preparsed = msg
+            .parse_header_with_size_limit(self.size_limit)
             .await
-            .map_err(|e| Error::Wrapped("send keyload", e))?;
+            .map_err(|e| Error::Unwrapping("header", address, e))?;
+        self.handle_preparsed_message(address, preparsed, hash, None).await
+    }
 
-        // Attempt to send message
-        let message_address = Address::new(stream_address.base(), rel_address);
-        if !self.transport.recv_message(message_address).await.is_err() {
-            return Err(Error::AddressUsed("keyload", message_address));
+    /// Processes a batch of messages, returning the processed messages in the order they were
+    /// given. The headers of the messages are decoded and the messages hashed upfront, since this
+    /// preprocessing does not depend on the state of the [`User`]. With the `parallel-preparse`
+    /// feature, it is run in parallel on the rayon thread pool. The Ed25519 signatures of the
+    /// signed packets are then verified together, in a single batch with the `batch-verification`
+    /// feature, and the contents are decoded in order, as each of them can depend on the messages
+    /// preceding it. Decoding the contents takes most of the processing, and is not parallel.
+    /// Processing stops at the first failure.
+    ///
+    /// # Arguments
+    /// * `msgs`: The [`Address`] and raw [`TransportMessage`] of each message to process
+    pub async fn handle_messages_batch(&mut self, msgs: Vec<(Address, TransportMessage)>) -> Result<Vec<Message>> {
+        let mut raws = self
+            .replay_recorder
+            .as_ref()
+            .map(|_| msgs.iter().map(|(_, msg)| msg.clone()).collect::<Vec<_>>().into_iter());
+        let batch = batch::preparse_batch(msgs, self.size_limit, self.notarization.is_some()).await;
+        let mut verified_signatures = self.verify_batch_signatures(&batch).await;
+        let mut messages = Vec::with_capacity(batch.len());
+        for (address, preparsed) in batch {
+            let raw = raws.as_mut().and_then(Iterator::next);
+            let handled = match preparsed {
+                Preparsed::Legacy(msg) => self.handle_legacy_message(address, msg).await,
+                Preparsed::Message(Ok(preparsed), hash) => {
+                    let verified_signature = verified_signatures.remove(&address);
+                    self.handle_preparsed_message(address, preparsed, hash, verified_signature)
+                        .await
+                }
+                Preparsed::Message(Err(e), _) => Err(e),
+            };
+            self.record_handled(address, raw, &handled);
+            messages.push(handled?);
         }
+        Ok(messages)
+    }
 
-        let send_response = self
-            .transport
-            .send_message(message_address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(stream_address, "send keyload message", e))?;
-
-        // If message has been sent successfully, commit message to stores
-        for subscriber in subscribers {
-            if self.should_store_cursor(&topic, subscriber) {
-                self.state
-                    .cursor_store
-                    .insert_cursor(&topic, subscriber.into(), INIT_MESSAGE_NUM);
+    /// Verifies together the Ed25519 signatures of the signed packets of a batch. The packets are
+    /// unwrapped against the states of the messages they are linked to, found in the store or
+    /// earlier in the batch, without updating the state of the [`User`]. The packets that cannot be
+    /// unwrapped yet, and the packets of forward secrecy branches, whose ratchets only advance when
+    /// they are processed, are verified one by one when processed.
+    ///
+    /// Returns the [`PacketSignature`] of each packet whose signature is valid, by address.
+    ///
+    /// # Arguments
+    /// * `batch`: The preprocessed messages of the batch
+    async fn verify_batch_signatures(&mut self, batch: &[(Address, Preparsed)]) -> HashMap<Address, PacketSignature> {
+        let mut states: HashMap<MsgId, Spongos> = HashMap::new();
+        let mut signatures = SignatureBatch::new();
+        let mut collected = Vec::new();
+        for (address, preparsed) in batch {
+            let preparsed = match preparsed {
+                Preparsed::Message(Ok(preparsed), _) => preparsed,
+                _ => continue,
+            };
+            let message_type = preparsed.header().message_type();
+            let topic = self.topic_by_hash(preparsed.header().topic_hash());
+            let is_forward_secret = topic
+                .as_ref()
+                .map_or(true, |topic| self.state.ratchets.contains_key(topic));
+            let linked_msg_address = match preparsed.header().linked_msg_address() {
+                Some(linked_msg_address) if !is_forward_secret => linked_msg_address,
+                _ => continue,
+            };
+            if message_type != message_types::SIGNED_PACKET {
+                continue;
+            }
+            let linked_msg_spongos = match states.get(&linked_msg_address) {
+                Some(spongos) => Some(spongos.clone()),
+                None => self.state.spongos_store.get(&linked_msg_address).ok().flatten(),
+            };
+            let mut linked_msg_spongos = match linked_msg_spongos {
+                Some(spongos) => spongos,
+                None => continue,
+            };
+
+            // The payloads of the packets published after the access expired are not unmasked
+            let access_expiry = topic
+                .as_ref()
+                .and_then(|topic| self.packet_access_expiry(topic, preparsed.header().publisher()));
+            let signed_packet = signed_packet::Unwrap::new(&mut linked_msg_spongos)
+                .with_access_expiry(access_expiry)
+                .collect_signature();
+            let (message, spongos) = match preparsed.clone().unwrap(signed_packet).await {
+                Ok(unwrapped) => unwrapped,
+                Err(_) => continue,
+            };
+            let mut content = message.into_payload().into_content();
+            if content.is_withheld() {
+                continue;
+            }
+            if let (Some(signature), Identifier::Ed25519(public_key)) =
+                (content.take_signature(), content.into_publisher_identifier())
+            {
+                signatures.push(public_key, signature.hash, signature.signature);
+                collected.push((*address, signature));
             }
+            states.insert(address.relative(), spongos);
         }
-        self.state
-            .cursor_store
-            .insert_cursor(&topic, Permissioned::Admin(identifier), new_cursor);
-        self.store_spongos(rel_address, spongos, link_to);
-        // Update Branch Links
-        self.set_latest_link(topic, message_address.relative());
-        Ok(SendResponse::new(message_address, send_response))
+
+        collected
+            .into_iter()
+            .zip(signatures.verify())
+            .filter(|(_, is_valid)| *is_valid)
+            .map(|(verified, _)| verified)
+            .collect()
     }
 
-    /// Create and send a new Keyload message for all participants, updating the specified branch to
-    /// grant all known subscribers read permissions.
+    /// Processes again the messages of a [`ReplayLog`], in the order they were recorded, without
+    /// using the transport. Replayed on a user restored from a backup taken before the log was
+    /// recorded, the messages reproduce the evolution of the state of the recording user, so that
+    /// issues depending on the order of the messages can be investigated offline. Every message of
+    /// the log is processed, whatever the outcome of the previous ones.
+    ///
+    /// Returns a [`ReplayStep`] for each message, comparing the outcome of the replay with the
+    /// recorded outcome.
     ///
     /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
-    pub async fn send_keyload_for_all<Top>(&mut self, topic: Top) -> Result<SendResponse<TSR>>
-    where
-        Top: Into<Topic> + Clone,
-    {
-        let topic = topic.into();
-        let permission = self.permission(&topic).ok_or(Error::NoCursor(topic.clone()))?;
-        if !permission.is_admin() {
-            return Err(Error::WrongRole(
-                "Admin",
-                permission.identifier().clone(),
-                "send a keyload",
-            ));
+    /// * `log`: The [`ReplayLog`] to replay
+    pub async fn replay(&mut self, log: &ReplayLog) -> Vec<ReplayStep> {
+        let mut steps = Vec::with_capacity(log.len());
+        for entry in log.entries() {
+            let replayed = self.handle_message(entry.address(), entry.message().clone()).await;
+            steps.push(ReplayStep::new(entry.address(), entry.outcome().clone(), replayed));
         }
-        let psks: Vec<PskId> = self.state.psk_store.keys().copied().collect();
-        let subscribers: Vec<Permissioned<Identifier>> = self
-            .subscribers()
-            .map(|s| {
-                if s == permission.identifier() {
-                    Permissioned::Admin(s.clone())
-                } else {
-                    Permissioned::Read(s.clone())
-                }
-            })
-            .collect();
-        self.send_keyload(
-            topic,
-            // Alas, must collect to release the &self immutable borrow
-            subscribers.iter().map(Permissioned::as_ref),
-            psks,
-        )
-        .await
+        steps
     }
 
-    /// Create and send a new Keyload message for all participants, updating the specified branch to
-    /// grant all known subscribers read and write permissions.
+    /// Process a [`PreparsedMessage`] dependent on its type. Messages already processed, within the
+    /// replay window of the user, are not processed again and are returned as
+    /// [`MessageContent::DuplicateReceived`].
     ///
     /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
-    pub async fn send_keyload_for_all_rw<Top>(&mut self, topic: Top) -> Result<SendResponse<TSR>>
-    where
-        Top: Into<Topic> + Clone,
-    {
-        let topic = topic.into();
-        let permission = self.permission(&topic).ok_or(Error::NoCursor(topic.clone()))?;
-        if !permission.is_admin() {
-            return Err(Error::WrongRole(
-                "Admin",
-                permission.identifier().clone(),
-                "send a keyload",
-            ));
-        }
-        let psks: Vec<PskId> = self.state.psk_store.keys().copied().collect();
-        let subscribers: Vec<Permissioned<Identifier>> = self
-            .subscribers()
-            .map(|s| {
-                if s == permission.identifier() {
-                    Permissioned::Admin(s.clone())
-                } else {
-                    Permissioned::ReadWrite(s.clone(), PermissionDuration::Perpetual)
-                }
-            })
-            .collect();
-        self.send_keyload(
-            topic,
-            // Alas, must collect to release the &self immutable borrow
-            subscribers.iter().map(Permissioned::as_ref),
-            psks,
+    /// * `address`: The [`Address`] of the message to process
+    /// * `preparsed`: The message with its header decoded
+    /// * `hash`: The hash of the message, as returned by [`User::message_hash()`]
+    /// * `verified_signature`: The [`PacketSignature`] of the message verified beforehand, if any
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            skip_all,
+            fields(
+                address = %address,
+                message_type = preparsed.header().message_type(),
+                topic = %preparsed.header().topic_hash(),
+                publisher = %preparsed.header().publisher(),
+            )
         )
-        .await
+    )]
+    async fn handle_preparsed_message(
+        &mut self,
+        address: Address,
+        preparsed: PreparsedMessage,
+        hash: Option<[u8; DIGEST_SIZE]>,
+        verified_signature: Option<PacketSignature>,
+    ) -> Result<Message> {
+        let content_digest = preparsed.transport_msg().digest();
+        let digest = self.state.seen_messages.digest(address, preparsed.transport_msg());
+        if let Some(digest) = digest.filter(|digest| self.state.seen_messages.contains(digest)) {
+            return Ok(Message::duplicate(address, preparsed, digest).with_digest(content_digest));
+        }
+        self.screen_message(address, preparsed.transport_msg(), Some(preparsed.header()))?;
+        self.check_strictness(preparsed.header().message_type())?;
+        let message = match preparsed.header().message_type() {
+            message_types::ANNOUNCEMENT => self.handle_announcement(address, preparsed).await,
+            message_types::BRANCH_ANNOUNCEMENT => self.handle_branch_announcement(address, preparsed).await,
+            message_types::BRANCH_CLOSURE => self.handle_branch_closure(address, preparsed).await,
+            message_types::READ_MARKER => self.handle_read_marker(address, preparsed).await,
+            message_types::TOMBSTONE => self.handle_tombstone(address, preparsed).await,
+            message_types::COUNTERSIGNATURE => self.handle_countersignature(address, preparsed).await,
+            message_types::COMMITMENT => self.handle_commitment(address, preparsed).await,
+            message_types::REVEAL => self.handle_reveal(address, preparsed).await,
+            message_types::AUTHOR_ROTATION => self.handle_author_rotation(address, preparsed).await,
+            message_types::SUBSCRIPTION | message_types::INVITED_SUBSCRIPTION => {
+                self.handle_subscription(address, preparsed).await
+            }
+            message_types::UNSUBSCRIPTION => self.handle_unsubscription(address, preparsed).await,
+            message_types::KEYLOAD => self.handle_keyload(address, preparsed).await,
+            message_types::SIGNED_PACKET => self.handle_signed_packet(address, preparsed, verified_signature).await,
+            message_types::TAGGED_PACKET => self.handle_tagged_packet(address, preparsed).await,
+            message_types::SELECTIVE_PACKET => self.handle_selective_packet(address, preparsed).await,
+            message_types::BATCH_PACKET => self.handle_batch_packet(address, preparsed).await,
+            message_types::KEY_UPDATE => self.handle_key_update(address, preparsed).await,
+            message_types::SUBSTREAM_ANNOUNCEMENT => self.handle_substream_announcement(address, preparsed).await,
+            custom if self.custom_message_types.contains_key(&custom) => {
+                self.handle_custom_message(address, preparsed).await
+            }
+            unknown => Err(Error::MessageTypeUnknown(unknown)),
+        }?;
+        self.notarize_read(&message, hash).await?;
+        if let Some(digest) = digest.filter(|_| !message.is_orphan()) {
+            self.state.seen_messages.insert(digest);
+        }
+        let message = self.decode_payload(message.with_digest(content_digest));
+        let message = self.validate_payload(message);
+        let message = self.redact(message);
+        Ok(self.attach_countersignatures(message))
     }
 
-    /// Create a new [`MessageBuilder`] instance.
-    pub fn message<P: Default>(&mut self) -> MessageBuilder<P, T> {
-        MessageBuilder::new(self)
+    /// Processes a message published on a legacy (v1) channel. Legacy messages are read-only: they
+    /// neither bind the [`User`] to the legacy channel nor update its cursors. Only the [`Spongos`]
+    /// state is stored, so that the packets linked to the message can be decoded later on.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `msg`: The raw [`TransportMessage`] to be processed
+    async fn handle_legacy_message(&mut self, address: Address, msg: TransportMessage) -> Result<Message> {
+        self.screen_message(address, &msg, None)?;
+        if let Some(stream_address) = self.state.stream_address.filter(|_| self.state.strict) {
+            if address.base() == stream_address.base() {
+                return Err(Error::StrictChannel("legacy messages", stream_address));
+            }
+        }
+        let mut ctx = unwrap::Context::new(msg.as_ref()).with_size_limit(self.size_limit);
+        let mut header = legacy::Header::default();
+        ctx.unwrap(&mut header)
+            .await
+            .map_err(|e| Error::Unwrapping("legacy header", address, e.into()))?;
+
+        let content: MessageContent = match header.message_type() {
+            legacy::ANNOUNCE => {
+                let mut announce = legacy::Announce::default();
+                ctx.unwrap(&mut announce)
+                    .await
+                    .map_err(|e| Error::Unwrapping("legacy announce", address, e.into()))?;
+                announce.into()
+            }
+            legacy::SIGNED_PACKET => {
+                let mut linked_msg_spongos = {
+                    if let Some(spongos) = self.state.spongos_store.get(&header.link())? {
+                        // Spongos must be copied because unwrapping mutates it
+                        spongos
+                    } else {
+                        let orphan = MessageContent::Orphan(Orphan {
+                            cursor: header.sequence() as usize,
+                            message: msg.clone(),
+                        });
+                        return Ok(Message::from_legacy(address, &header, orphan).with_digest(msg.digest()));
+                    }
+                };
+                let mut signed_packet = legacy::SignedPacket::new(&mut linked_msg_spongos);
+                ctx.unwrap(&mut signed_packet)
+                    .await
+                    .map_err(|e| Error::Unwrapping("legacy signed packet", address, e.into()))?;
+                signed_packet.into()
+            }
+            unknown => return Err(Error::MessageTypeUnknown(unknown)),
+        };
+
+        // Store spongos
+        let (spongos, _) = ctx.finalize();
+        self.state.spongos_store.insert(address.relative(), spongos)?;
+
+        Ok(Message::from_legacy(address, &header, content).with_digest(msg.digest()))
     }
 
-    /// Create and send a new Signed Packet message to the specified branch. The message will
-    /// contain a masked and an unmasked payload. The message will be signed by the [`User`]
-    /// [`Identity`] keys.
+    /// Rejects the messages using weaker options than the current ones on strict streams:
+    /// unsigned packets could otherwise be spoofed by any subscriber holding the key of the branch.
     ///
-    /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch to send the message to.
-    /// * `public_payload`: The unmasked payload of the message.
-    /// * `masked_payload`: The masked payload of the message.
-    pub async fn send_signed_packet<P, M, Top>(
-        &mut self,
-        topic: Top,
-        public_payload: P,
-        masked_payload: M,
-    ) -> Result<SendResponse<TSR>>
-    where
-        M: AsRef<[u8]>,
-        P: AsRef<[u8]>,
-        Top: Into<Topic>,
-    {
-        // Check conditions
-        let stream_address = self.stream_address().ok_or(Error::Setup(
-            "before sending a signed packet, the stream must be created",
-        ))?;
-        let user_id = self.identity().ok_or(Error::NoIdentity("send signed packet"))?;
-        let identifier = user_id.identifier().clone();
+    /// # Arguments:
+    /// * `message_type`: The type of the message to be processed
+    fn check_strictness(&self, message_type: u8) -> Result<()> {
+        match self.state.stream_address {
+            Some(stream_address) if self.state.strict && message_type == message_types::TAGGED_PACKET => {
+                Err(Error::StrictChannel("unsigned packets", stream_address))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Processes an announcement message, binding a [`User`] to the stream announced in the
+    /// message. The flags of the announcement tell whether the stream is strict. Announcements
+    /// carrying a [`Quorum`] are rejected unless the threshold of its members approved them.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_announcement(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
         // Check Topic
-        let topic = topic.into();
-        // Check Permission
+        let publisher = preparsed.header().publisher().clone();
+
+        // Unwrap message
+        let (message, spongos) = preparsed
+            .unwrap(announcement::Unwrap::default())
+            .await
+            .map_err(|e| Error::Unwrapping("announcement", address, e))?;
+
+        let content = message.payload().content();
+        let topic = content.topic();
+        // The stream is only bound once the quorum approved its announcement
+        if let Some(quorum) = content.quorum() {
+            quorum.verify(
+                &quorum.announcement_proposal(content.author_id(), topic),
+                content.approvals(),
+            )?;
+        }
+        // Insert new branch into store
+        self.state.cursor_store.new_branch(topic.clone());
+        self.state.topics.insert(topic.clone());
+
+        // When handling an announcement it means that no cursors have been stored, as no topics are
+        // known yet. The message must be unwrapped to retrieve the initial topic before storing cursors
+        self.state
+            .cursor_store
+            .insert_cursor(topic, Permissioned::Admin(publisher), INIT_MESSAGE_NUM);
+
+        // Store spongos
+        self.state.spongos_store.insert(address.relative(), spongos)?;
+
+        // Store message content into stores
+        let author_id = message.payload().content().author_id().clone();
+
+        // Update branch links
+        self.set_latest_link(topic.clone(), address.relative());
+        self.state.author_identifier = Some(author_id);
+        self.state.strict = message.payload().content().is_strict();
+        self.state.quorum = message.payload().content().quorum().cloned();
+        self.state.base_branch = topic.clone();
+        self.state.stream_address = Some(address);
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a branch announcement message, creating a new branch in [`CursorStore`], carrying
+    /// over mapped permissions and cursors from the previous branch.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_branch_announcement(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        // Retrieve header values
+        let prev_topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+
+        let publisher = preparsed.header().publisher().clone();
+        let cursor = preparsed.header().sequence();
+
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
         let permission = self
             .state
             .cursor_store
-            .get_permission(&topic, &identifier)
-            .ok_or(Error::NoCursor(topic.clone()))?;
-        if permission.is_readonly() {
-            return Err(Error::WrongRole(
-                "ReadWrite",
-                permission.identifier().clone(),
-                "send a signed packet",
-            ));
+            .get_permission(&prev_topic, &publisher)
+            .ok_or(Error::NoCursor(prev_topic.clone()))?
+            .clone();
+        self.state.cursor_store.insert_cursor(&prev_topic, permission, cursor);
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("branch announcement", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let branch_announcement = branch_announcement::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(branch_announcement)
+            .await
+            .map_err(|e| Error::Unwrapping("branch announcement", address, e))?;
+
+        let new_topic = message.payload().content().new_topic();
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        // Insert new branch into store
+        self.state.cursor_store.new_branch(new_topic.clone());
+        self.state.topics.insert(new_topic.clone());
+        // Collect permissions from previous branch and clone them into new branch
+        let prev_permissions = self
+            .cursors_by_topic(&prev_topic)?
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<Permissioned<Identifier>>>();
+        for id in prev_permissions {
+            self.state.cursor_store.insert_cursor(new_topic, id, INIT_MESSAGE_NUM);
+        }
+
+        // Update branch links
+        self.set_latest_link(new_topic.clone(), address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a branch closure message, marking its branch as closed. The closure is only
+    /// accepted from an administrator of the branch.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_branch_closure(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        // Confirm closure came from administrator
+        if !self.is_branch_admin(&topic, &publisher)? {
+            return Err(Error::WrongRole("admin", publisher, "close a branch"));
+        }
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, Permissioned::Admin(publisher), preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("branch closure", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let branch_closure = branch_closure::Unwrap::new(&mut linked_msg_spongos, topic.clone());
+        let (message, spongos) = preparsed
+            .unwrap(branch_closure)
+            .await
+            .map_err(|e| Error::Unwrapping("branch closure", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        // Mark the branch as closed
+        self.state.closed_branches.insert(topic.clone());
+
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a read marker message, recording the last message processed by its publisher, and
+    /// verifying the message signature against the publisher [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_read_marker(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("read marker", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let read_marker = read_marker::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(read_marker)
+            .await
+            .map_err(|e| Error::Unwrapping("read marker", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        self.mark_recomputable(&topic, address.relative());
+        // Record the marker of the publisher
+        let marked_address = message.payload().content().marked_address();
+        self.state
+            .read_markers
+            .entry(topic.clone())
+            .or_default()
+            .insert(publisher, marked_address);
+
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a tombstone message, recording the redaction of the message it designates, and
+    /// verifying the message signature against the stream author [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_tombstone(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        // Confirm tombstone came from the stream author
+        if self.state.author_identifier.as_ref() != Some(&publisher) {
+            return Err(Error::WrongRole("author", publisher, "redact a message"));
+        }
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("tombstone", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let tombstone = tombstone::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(tombstone)
+            .await
+            .map_err(|e| Error::Unwrapping("tombstone", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        // Record the redaction
+        let content = message.payload().content();
+        self.state
+            .tombstones
+            .insert(content.redacted(), (address.relative(), content.reason().to_string()));
+
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a countersignature message, verifying the message signature against the witness
+    /// [`Identifier`], and recording the countersignature of the packet it is linked to. Only the
+    /// countersignatures of the trusted witnesses are accepted.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_countersignature(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+        // Confirm countersignature came from a trusted witness
+        if !self.state.witnesses.contains(&publisher) {
+            return Err(Error::WrongRole("witness", publisher, "countersign a packet"));
+        }
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("countersignature", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let countersignature = countersignature::Unwrap::new(&mut linked_msg_spongos, linked_msg_address);
+        let (message, _) = preparsed
+            .unwrap(countersignature)
+            .await
+            .map_err(|e| Error::Unwrapping("countersignature", address, e))?;
+        let witness = message.payload().content().witness();
+        if witness != &publisher {
+            return Err(Error::WrongRole("witness", witness.clone(), "countersign a packet"));
+        }
+
+        // Record the countersignature. No message is linked to it, so neither its spongos nor the
+        // branch links are updated
+        self.record_countersignature(Countersignature {
+            witness: publisher,
+            countersigned: linked_msg_address,
+        });
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a commitment message, verifying the message signature against the publisher
+    /// [`Identifier`], and recording the digest of the committed payload.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_commitment(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("commitment", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let commitment = commitment::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(commitment)
+            .await
+            .map_err(|e| Error::Unwrapping("commitment", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        // Record the commitment
+        self.state.commitments.insert(
+            address.relative(),
+            Commitment {
+                publisher_identifier: publisher,
+                digest: message.payload().content().digest(),
+            },
+        );
+
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a reveal message, verifying the message signature against the publisher
+    /// [`Identifier`], and checking that the revealed payload matches the digest of the commitment
+    /// of the publisher it designates. The state is updated before the check, so that the messages
+    /// linked to a mismatching reveal can still be read.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_reveal(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("reveal", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let reveal = reveal::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(reveal)
+            .await
+            .map_err(|e| Error::Unwrapping("reveal", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        // Match the reveal with the commitment of its publisher
+        let reveal = message.payload().content();
+        let commitment = self
+            .state
+            .commitments
+            .get(&reveal.commitment())
+            .ok_or(Error::RevealMismatch(address, "the commitment was not processed"))?;
+        if &commitment.publisher_identifier != reveal.publisher() {
+            return Err(Error::RevealMismatch(
+                address,
+                "the commitment was published by another user",
+            ));
+        }
+        if commitment.digest != commitment_digest(reveal.payload()) {
+            return Err(Error::RevealMismatch(
+                address,
+                "the payload does not match the digest of the commitment",
+            ));
+        }
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes an author rotation message, verifying the message signature against the current
+    /// stream author [`Identifier`], and handing the authorship of the stream over to the new
+    /// author it designates. In streams announced with a [`Quorum`], the rotation must carry the
+    /// approvals of the threshold of its members.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_author_rotation(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        // Confirm rotation came from the current stream author
+        if self.state.author_identifier.as_ref() != Some(&publisher) {
+            return Err(Error::WrongRole("author", publisher, "rotate the author key"));
+        }
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("author rotation", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let sequence = preparsed.header().sequence();
+        let (message, _spongos) = preparsed
+            .unwrap(author_rotation::Unwrap::new(&mut linked_msg_spongos))
+            .await
+            .map_err(|e| Error::Unwrapping("author rotation", address, e))?;
+
+        // The signature must be the one of the author the header claims
+        let content = message.payload().content();
+        if content.previous_author() != &publisher {
+            return Err(Error::WrongRole(
+                "author",
+                content.previous_author().clone(),
+                "rotate the author key",
+            ));
+        }
+        if let (Some(quorum), Some(stream_address)) = (&self.state.quorum, self.state.stream_address) {
+            let proposal = quorum.rotation_proposal(stream_address, &publisher, content.new_author(), sequence);
+            quorum.verify(&proposal, content.approvals())?;
+        }
+        self.rotate_author_identifier(content.new_author().clone());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Hands the authorship of the stream over to a new [`Identifier`], granting it the
+    /// administration of the branches administered by the previous author. The cursors the new
+    /// author already has in these branches are kept. The previous author is demoted to a reader of
+    /// every branch and recorded as a previous author, so that its administrative messages are
+    /// rejected from then on.
+    ///
+    /// # Arguments
+    /// * `new_author`: The [`Identifier`] of the new stream author
+    fn rotate_author_identifier(&mut self, new_author: Identifier) {
+        self.state.previous_authors.remove(&new_author);
+        if let Some(previous_author) = self.state.author_identifier.replace(new_author.clone()) {
+            let previous_cursors: Vec<(Topic, bool, usize)> = self
+                .state
+                .cursor_store
+                .cursors()
+                .filter(|(_, permission, _)| permission.identifier() == &previous_author)
+                .map(|(topic, permission, cursor)| (topic.clone(), permission.is_admin(), cursor))
+                .collect();
+            for (topic, administered, cursor) in previous_cursors {
+                if administered {
+                    let new_author_cursor = self
+                        .state
+                        .cursor_store
+                        .get_cursor(&topic, &new_author)
+                        .unwrap_or(INIT_MESSAGE_NUM);
+                    self.state.cursor_store.insert_cursor(
+                        &topic,
+                        Permissioned::Admin(new_author.clone()),
+                        new_author_cursor,
+                    );
+                }
+                self.state
+                    .cursor_store
+                    .insert_cursor(&topic, Permissioned::Read(previous_author.clone()), cursor);
+            }
+            if previous_author != new_author {
+                self.state.previous_authors.insert(previous_author);
+            }
+        }
+    }
+
+    /// Returns true if a publisher administers a branch: it holds the admin permission of the
+    /// branch, and is not a key the stream author rotated away from.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch
+    /// * `publisher`: The [`Identifier`] of the publisher
+    fn is_branch_admin(&self, topic: &Topic, publisher: &Identifier) -> Result<bool> {
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(topic, publisher)
+            .ok_or_else(|| Error::NoCursor(topic.clone()))?;
+        Ok(permission.is_admin() && !self.state.previous_authors.contains(publisher))
+    }
+
+    /// Processes a message of a registered custom message type, unwrapping its content with the
+    /// content type registered for the type, and verifying the message signature against the
+    /// publisher [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_custom_message(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("custom message", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let message_type = preparsed.header().message_type();
+        let (message, spongos) = self
+            .custom_message_types
+            .get(&message_type)
+            .ok_or(Error::MessageTypeUnknown(message_type))?
+            .unwrap(address, &mut linked_msg_spongos, preparsed)
+            .await
+            .map_err(|e| Error::Unwrapping("custom message", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        Ok(message)
+    }
+
+    /// Processes a substream announcement message, surfacing the address of the substream anchored
+    /// to the message it is linked to, and verifying the message signature against the publisher
+    /// [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_substream_announcement(
+        &mut self,
+        address: Address,
+        preparsed: PreparsedMessage,
+    ) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("substream announcement", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let substream_announcement = substream_announcement::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(substream_announcement)
+            .await
+            .map_err(|e| Error::Unwrapping("substream announcement", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a [`User`] subscription message, applying the [`SubscriptionPolicy`] of the user:
+    /// the subscriber [`Identifier`] is stored if accepted, or held for review. The outcome is
+    /// reported in the [`SubscriptionStatus`] of the returned message.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_subscription(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        // Cursor is not stored, as cursor is only tracked for subscribers with write permissions
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("subscription", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let user_ke_sk = &self
+            .identity()
+            .ok_or(Error::NoIdentity("Derive a secret key"))?
+            .ke_sk()
+            .map_err(|_| Error::NoSecretKey)?;
+
+        let mut subscription = subscription::Unwrap::new(&mut linked_msg_spongos, user_ke_sk);
+        if preparsed.header().message_type() == message_types::INVITED_SUBSCRIPTION {
+            subscription = subscription.with_invite();
+        }
+        let (message, _spongos) = preparsed
+            .unwrap(subscription)
+            .await
+            .map_err(|e| Error::Unwrapping("subscription", address, e))?;
+
+        // Store spongos
+        // Subscription messages are never stored in spongos to maintain consistency about the view of the
+        // set of messages of the stream between all the subscribers and across stateless recovers
+
+        // Store message content into stores
+        let subscriber_identifier = message.payload().content().subscriber_identifier().clone();
+        let invite = message
+            .payload()
+            .content()
+            .invite()
+            .map(|token| InviteToken::from(token.to_vec()));
+        let (status, invite_topic) = match invite {
+            // Invited subscriptions bypass the policy, invalid invites are rejected
+            Some(token) => match self.redeem_invite(&token, &subscriber_identifier).await {
+                Ok(invite) => (SubscriptionStatus::Accepted, Some(invite.topic().clone())),
+                Err(_) => (SubscriptionStatus::Rejected, None),
+            },
+            None if self.state.subscribers.contains(&subscriber_identifier) => (SubscriptionStatus::Accepted, None),
+            None => (self.subscription_policy.review(&subscriber_identifier), None),
+        };
+        match status {
+            SubscriptionStatus::Accepted => {
+                // The access to the branch of the invite is granted by its next keyload
+                if let Some(topic) = &invite_topic {
+                    if self.permission_of(&subscriber_identifier, topic).is_none() {
+                        self.state
+                            .invite_grants
+                            .entry(topic.clone())
+                            .or_default()
+                            .insert(subscriber_identifier.clone());
+                    }
+                }
+                self.add_subscriber(subscriber_identifier);
+            }
+            SubscriptionStatus::Pending => {
+                self.state.pending_subscriptions.insert(subscriber_identifier);
+            }
+            SubscriptionStatus::Rejected => {}
+        }
+
+        let mut message = Message::from_lets_message(address, message);
+        if let MessageContent::Subscription(subscription) = &mut message.content {
+            subscription.status = status;
+            subscription.invite_topic = invite_topic;
+        }
+        Ok(message)
+    }
+
+    /// Verifies an [`InviteToken`] included in a subscription, and counts the subscriber among the
+    /// users of the [`Invite`]. Subscribers that already used the invite can use it again, so that
+    /// reading a subscription twice does not consume the invite. Without the `std` feature there is
+    /// no clock to check the expiry of the invite against, and every invite is rejected.
+    ///
+    /// # Arguments:
+    /// * `token`: The [`InviteToken`] included in the subscription
+    /// * `subscriber`: The [`Identifier`] of the subscriber
+    async fn redeem_invite(&mut self, token: &InviteToken, subscriber: &Identifier) -> Result<Invite> {
+        let identifier = self.identifier().ok_or(Error::NoIdentity("verify an invite"))?;
+        let invite = Invite::verify(token, identifier).await?;
+        if self.stream_address() != Some(invite.stream_address()) {
+            return Err(Error::InviteRejected("the invite is for another stream"));
+        }
+        match clock::unix_time() {
+            Some(now) if !invite.is_expired(now) => {}
+            _ => return Err(Error::InviteRejected("the invite has expired")),
+        }
+        if !self.state.topics.contains(invite.topic()) {
+            return Err(Error::TopicNotFound(invite.topic().clone()));
+        }
+        let redemptions = self.state.invite_redemptions.entry(*invite.id()).or_default();
+        if !redemptions.contains(subscriber) && redemptions.len() >= invite.max_uses() {
+            return Err(Error::InviteRejected("the invite has been used too many times"));
+        }
+        redemptions.insert(subscriber.clone());
+        Ok(invite)
+    }
+
+    /// Processes a [`User`] unsubscription message, removing the subscriber [`Identifier`] from
+    /// store.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_unsubscription(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        // Cursor is not stored, as user is unsubscribing
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("unsubscribe", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let unsubscription = unsubscription::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(unsubscription)
+            .await
+            .map_err(|e| Error::Unwrapping("unsubscribe", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+
+        // Store message content into stores
+        let subscriber_identifier = message.payload().content().subscriber_identifier();
+        self.state.pending_subscriptions.remove(subscriber_identifier);
+        self.remove_subscriber(subscriber_identifier);
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a key update message, storing the announced key exchange key of the publisher so
+    /// that further keyloads and selective packets are encrypted to it.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_key_update(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let publisher = preparsed.header().publisher().clone();
+        // Key updates are published in the base branch, update the cursor of the publisher if it is
+        // tracked
+        let base_branch = self.state.base_branch.clone();
+        if let Some(permission) = self
+            .state
+            .cursor_store
+            .get_permission(&base_branch, &publisher)
+            .cloned()
+        {
+            self.state
+                .cursor_store
+                .insert_cursor(&base_branch, permission, preparsed.header().sequence());
+        }
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("key update", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        let key_update = key_update::Unwrap::new(&mut linked_msg_spongos);
+        let (message, spongos) = preparsed
+            .unwrap(key_update)
+            .await
+            .map_err(|e| Error::Unwrapping("key update", address, e))?;
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+
+        // Store message content into stores. Replayed or out of order updates must not replace a
+        // more recent key
+        let content = message.payload().content();
+        let identifier = content.user_identifier().clone();
+        let exchange_key = content.exchange_key();
+        let is_own = self.identifier() == Some(&identifier);
+        let is_newer = self
+            .state
+            .exchange_keys
+            .get(&identifier)
+            .map_or(true, |stored| stored.generation < exchange_key.generation);
+        if !is_own && is_newer {
+            self.state.exchange_keys.insert(identifier, exchange_key);
+        }
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a keyload message, updating store to include the contained list of
+    /// [permissions](`Permissioned`). All keyload messages are linked to the announcement
+    /// message to ensure they can always be read by a [`User`] that can sequence up to it.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_keyload(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let stream_address = self.stream_address().ok_or(Error::NoStream("handling a keyload"))?;
+
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        // Confirm keyload came from administrator
+        if !self.is_branch_admin(&topic, &publisher)? {
+            return Err(Error::WrongRole("admin", publisher, "receive keyload"));
+        }
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state.cursor_store.insert_cursor(
+            &topic,
+            Permissioned::Admin(publisher.clone()),
+            preparsed.header().sequence(),
+        );
+
+        // Unwrap message
+        // Ok to unwrap since an author identifier is set at the same time as the stream address
+        let author_identifier = self.state.author_identifier.as_ref().unwrap();
+        let mut announcement_spongos = self
+            .state
+            .spongos_store
+            .get(&stream_address.relative())?
+            .expect("a subscriber that has received an stream announcement must keep its spongos in store");
+
+        let secret_key = self.state.exchange_key.and_then(|key| key.secret_key());
+        let exchange_key = Self::exchange_key(
+            self.state.exchange_key,
+            secret_key.as_ref(),
+            self.key_exchange.as_deref(),
+        )?;
+        // TODO: Remove Psk from Identity and Identifier, and manage it as a complementary permission
+        let keyload = keyload::Unwrap::new(
+            &mut announcement_spongos,
+            self.state.user_id.as_ref(),
+            author_identifier,
+            &self.state.psk_store,
+        )
+        .with_exchange_key(exchange_key);
+        let (message, mut spongos) = preparsed
+            .unwrap(keyload)
+            .await
+            .map_err(|e| Error::Unwrapping("keyload", address, e))?;
+
+        let content = message.payload().content();
+        let publishers = content
+            .subscribers()
+            .iter()
+            .filter(|subscriber| !subscriber.is_readonly())
+            .map(|subscriber| subscriber.identifier());
+        let publishers: Vec<Identifier> = core::iter::once(&publisher).chain(publishers).cloned().collect();
+        ratchet::apply_keyload(
+            &mut self.state.ratchets,
+            &topic,
+            content.forward_secrecy,
+            &mut spongos,
+            &publishers,
+        );
+
+        // Store spongos
+        self.state.spongos_store.insert(address.relative(), spongos)?;
+        self.state
+            .spongos_positions
+            .insert(address.relative(), SpongosPosition::now(message.header().sequence()));
+
+        let subscribers = message.payload().content().subscribers();
+        self.state
+            .keyload_permissions
+            .insert(topic.clone(), subscribers.to_vec());
+        // The access granted by the keyload replaces the one granted by the previous keyloads, and
+        // the packets linked to the keyload are no longer linked to the withheld ones
+        if self.identifier() != Some(&publisher) {
+            match message.payload().content().expires_at {
+                Some(expires_at) => self.state.access_expirations.insert(topic.clone(), expires_at),
+                None => self.state.access_expirations.remove(&topic),
+            };
+            self.state.withheld_packets.remove(&topic);
+        }
+
+        // If a branch admin does not include a user in the keyload, any further messages sent by
+        // the user will not be received by the others, so remove them from the publisher pool
+        let stored_subscribers: Vec<(Permissioned<Identifier>, usize)> = self
+            .cursors_by_topic(&topic)?
+            .map(|(perm, cursor)| (perm.clone(), *cursor))
+            .collect();
+
+        for (perm, cursor) in stored_subscribers {
+            if !(perm.identifier() == author_identifier
+                || subscribers.iter().any(|p| p.identifier() == perm.identifier()))
+            {
+                self.state
+                    .cursor_store
+                    .insert_cursor(&topic, Permissioned::Read(perm.identifier().clone()), cursor);
+            }
+        }
+
+        // Store message content into stores
+        for subscriber in subscribers {
+            if self.should_store_cursor(&topic, subscriber.as_ref()) {
+                self.state
+                    .cursor_store
+                    .insert_cursor(&topic, subscriber.clone(), INIT_MESSAGE_NUM);
+            }
+        }
+
+        // Have to make message before setting branch links due to immutable borrow in keyload::unwrap
+        let final_message = Message::from_lets_message(address, message);
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(final_message)
+    }
+
+    /// Processes a signed packet message, retrieving the public and masked payloads, and verifying
+    /// the message signature against the publisher [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    /// * `verified_signature`: The [`PacketSignature`] of the packet verified beforehand, if any
+    async fn handle_signed_packet(
+        &mut self,
+        address: Address,
+        preparsed: PreparsedMessage,
+        verified_signature: Option<PacketSignature>,
+    ) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("signed", address))?;
+        // Packets linked to a withheld packet were published after it, once the access expired
+        let withheld_link = self
+            .state
+            .withheld_packets
+            .get(&topic)
+            .and_then(|withheld| withheld.get(&linked_msg_address))
+            .copied();
+        if let Some(expired_at) = withheld_link {
+            if let Some((ratchet, _)) = self.advance_ratchet(&topic, &publisher, preparsed.header().sequence())? {
+                self.store_ratchet(&topic, &publisher, ratchet);
+            }
+            return Ok(self.withhold_packet(topic, address, preparsed.header().clone(), expired_at));
+        }
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        // Advance the ratchet of the publisher on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &publisher, preparsed.header().sequence())?;
+        let access_expiry = self.packet_access_expiry(&topic, &publisher);
+        let signed_packet = signed_packet::Unwrap::new(&mut linked_msg_spongos)
+            .with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key))
+            .with_access_expiry(access_expiry)
+            .with_verified_signature(verified_signature);
+        let (message, mut spongos) = preparsed
+            .unwrap(signed_packet)
+            .await
+            .map_err(|e| Error::Unwrapping("signed packet", address, e))?;
+        if let Some(expired_at) = access_expiry.filter(|_| message.payload().content().is_withheld()) {
+            if let Some((ratchet, _)) = ratchet {
+                self.store_ratchet(&topic, &publisher, ratchet);
+            }
+            let header = message.into_parts().0;
+            return Ok(self.withhold_packet(topic, address, header, expired_at));
+        }
+        let is_plain = message.payload().content().is_plain();
+        // Restore the payloads of repeated packets from the last packet of the publisher
+        let repeated_payloads = match message.payload().content().repeated_payloads() {
+            Some(repeated) => Some(
+                self.state
+                    .last_payloads
+                    .get(&topic)
+                    .and_then(|last_payloads| last_payloads.get(&publisher))
+                    .filter(|last| last.matches(&repeated))
+                    .cloned()
+                    .ok_or(Error::MessageMissing(repeated.original, "payload cache"))?,
+            ),
+            None => None,
+        };
+
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &publisher, ratchet);
+            spongos.ratchet();
+        }
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        self.mark_recomputable(&topic, address.relative());
+
+        let mut message = Message::from_lets_message(address, message);
+        match (repeated_payloads, &mut message.content) {
+            (Some(last), MessageContent::SignedPacket(signed_packet)) => {
+                signed_packet.public_payload = last.public_payload;
+                signed_packet.masked_payload = last.masked_payload;
+            }
+            (None, MessageContent::SignedPacket(signed_packet)) if is_plain => {
+                let last_payloads = LastPayloads::new(
+                    address.relative(),
+                    signed_packet.public_payload.clone(),
+                    signed_packet.masked_payload.clone(),
+                );
+                self.store_last_payloads(&topic, &publisher, Some(last_payloads));
+            }
+            _ => {}
+        }
+
+        // Store message content into stores
+        self.set_latest_link(topic, address.relative());
+        Ok(message)
+    }
+
+    /// Records a signed packet published after the access of the [`User`] to its branch expired,
+    /// whose payloads were left masked, so that the packets linked to it are withheld as well.
+    /// Returns the packet as [`MessageContent::AccessExpired`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the packet
+    /// * `address`: The [`Address`] of the packet
+    /// * `header`: The [header](`HDF`) of the packet
+    /// * `expired_at`: The Unix timestamp, in seconds, at which the access expired
+    fn withhold_packet(&mut self, topic: Topic, address: Address, header: HDF, expired_at: u64) -> Message {
+        self.state
+            .withheld_packets
+            .entry(topic)
+            .or_default()
+            .insert(address.relative(), expired_at);
+        Message::access_expired(address, header, expired_at)
+    }
+
+    /// Processes a selective packet message, decrypting the fields the [`User`] is a recipient of,
+    /// and verifying the message signature against the publisher [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_selective_packet(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("selective", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        // Advance the ratchet of the publisher on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &publisher, preparsed.header().sequence())?;
+        let secret_key = self.state.exchange_key.and_then(|key| key.secret_key());
+        let exchange_key = Self::exchange_key(
+            self.state.exchange_key,
+            secret_key.as_ref(),
+            self.key_exchange.as_deref(),
+        )?;
+        let selective_packet = selective_packet::Unwrap::new(&mut linked_msg_spongos, self.state.user_id.as_ref())
+            .with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key))
+            .with_exchange_key(exchange_key);
+        let (message, mut spongos) = preparsed
+            .unwrap(selective_packet)
+            .await
+            .map_err(|e| Error::Unwrapping("selective packet", address, e))?;
+        // The unwrapped content borrows the identity of the user, release it before updating the
+        // stores
+        let message = Message::from_lets_message(address, message);
+
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &publisher, ratchet);
+            spongos.ratchet();
+        }
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+
+        // Store message content into stores
+        self.set_latest_link(topic, address.relative());
+        Ok(message)
+    }
+
+    /// Processes a batch packet message, retrieving the public and masked payloads of its records,
+    /// and verifying the message signature against the publisher [`Identifier`].
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_batch_packet(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("batch", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        // Advance the ratchet of the publisher on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &publisher, preparsed.header().sequence())?;
+        let batch_packet =
+            batch_packet::Unwrap::new(&mut linked_msg_spongos).with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key));
+        let (message, mut spongos) = preparsed
+            .unwrap(batch_packet)
+            .await
+            .map_err(|e| Error::Unwrapping("batch packet", address, e))?;
+
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &publisher, ratchet);
+            spongos.ratchet();
+        }
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        self.mark_recomputable(&topic, address.relative());
+
+        // Store message content into stores
+        self.set_latest_link(topic, address.relative());
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Processes a tagged packet message, retrieving the public and masked payloads.
+    ///
+    /// # Arguments:
+    /// * `address`: The [`Address`] of the message to be processed
+    /// * `preparsed`: The [`PreparsedMessage`] to be processed
+    async fn handle_tagged_packet(&mut self, address: Address, preparsed: PreparsedMessage) -> Result<Message> {
+        let topic = self
+            .topic_by_hash(preparsed.header().topic_hash())
+            .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+        let publisher = preparsed.header().publisher().clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &publisher)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // From the point of view of cursor tracking, the message exists, regardless of the validity or
+        // accessibility to its content. Therefore we must update the cursor of the publisher before
+        // handling the message
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, permission, preparsed.header().sequence());
+
+        // Unwrap message
+        let linked_msg_address = preparsed
+            .header()
+            .linked_msg_address()
+            .ok_or(Error::NotLinked("tagged", address))?;
+        let mut linked_msg_spongos = {
+            if let Some(spongos) = self.state.spongos_store.get(&linked_msg_address)? {
+                // Spongos must be copied because wrapping mutates it
+                spongos
+            } else {
+                return Ok(Message::orphan(address, preparsed));
+            }
+        };
+        // Advance the ratchet of the publisher on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &publisher, preparsed.header().sequence())?;
+        let tagged_packet =
+            tagged_packet::Unwrap::new(&mut linked_msg_spongos).with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key));
+        let (message, mut spongos) = preparsed
+            .unwrap(tagged_packet)
+            .await
+            .map_err(|e| Error::Unwrapping("tagged packet", address, e))?;
+
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &publisher, ratchet);
+            spongos.ratchet();
+        }
+
+        // Store spongos
+        self.store_spongos(
+            address.relative(),
+            spongos,
+            linked_msg_address,
+            message.header().sequence(),
+        )?;
+        self.mark_recomputable(&topic, address.relative());
+
+        // Store message content into stores
+        self.set_latest_link(topic, address.relative());
+
+        Ok(Message::from_lets_message(address, message))
+    }
+
+    /// Creates an encrypted, serialised representation of a [`User`] `State` for backup and
+    /// recovery. The first byte of the backup holds the version of its layout.
+    ///
+    /// # Arguments
+    /// * `pwd`: The password to encrypt the `State` with
+    pub async fn backup<P>(&mut self, pwd: P) -> Result<Vec<u8>>
+    where
+        P: AsRef<[u8]>,
+    {
+        self.state.backup(pwd).await
+    }
+
+    /// Restore a [`User`] from an encrypted binary stream using the provided password and transport
+    /// client.
+    ///
+    /// # Configuration
+    /// A backup only holds the state of the user. Its configuration is reset to the defaults of
+    /// [`UserBuilder`](crate::UserBuilder): the orphan limit, sync lookahead, [`LinkGenerator`], notarizer, size limit,
+    /// [`SubscriptionPolicy`], acceptance of unsubscribed recipients, message filter, key exchange,
+    /// collision diagnostics, payload deduplication, payload middleware and validators, branch
+    /// rotations, custom message types, send queue limit, metrics and replay recorder are not
+    /// restored. Settings other than the defaults must be applied again with the setters of the
+    /// [`User`], like [`User::set_link_generator()`], before it sends or processes messages: a
+    /// user restored without its custom link generator derives different message addresses.
+    ///
+    /// # Arguments
+    /// * `backup`: Encrypted binary stream of backed up `State`.
+    /// * `pwd`: The decryption password.
+    /// * `transport`: The transport client for sending and receiving messages.
+    ///
+    /// # Errors
+    /// Fails with [`Error::BackupTruncated`] if the backup is empty or shorter than its layout,
+    /// and with [`Error::BackupVersion`] if it was created with another layout.
+    pub async fn restore<B, P>(backup: B, pwd: P, transport: T) -> Result<Self>
+    where
+        P: AsRef<[u8]>,
+        B: AsRef<[u8]>,
+    {
+        let state = match backup.as_ref().split_first() {
+            Some((&BACKUP_VERSION, body)) => State::restore(body, pwd, BACKUP_VERSION).await?,
+            Some((&version, _)) => return Err(Error::BackupVersion(version)),
+            None => return Err(Error::BackupTruncated),
+        };
+        Ok(User {
+            transport,
+            state,
+            orphan_limit: None,
+            sync_lookahead: DEFAULT_SYNC_LOOKAHEAD,
+            link_generator: Box::new(DefaultLinkGenerator),
+            notarization: None,
+            size_limit: unwrap::DEFAULT_SIZE_LIMIT,
+            subscription_policy: SubscriptionPolicy::default(),
+            allow_unsubscribed: false,
+            message_filter: None,
+            key_exchange: None,
+            collision_diagnostics: false,
+            payload_deduplication: false,
+            payload_middleware: None,
+            payload_validators: HashMap::new(),
+            branch_rotations: HashMap::new(),
+            custom_message_types: HashMap::new(),
+            send_queue_limit: DEFAULT_SEND_QUEUE_LIMIT,
+            metrics: None,
+            replay_recorder: None,
+            conflicts: Vec::new(),
+        })
+    }
+
+    /// Converts a backup created with a previous serialization layout into a backup using the
+    /// current layout, so it can be restored with [`User::restore`]. Supported versions are `0`,
+    /// the unversioned layout, whose state is kept and completed with the defaults of the features
+    /// introduced since, and the current version, which is returned unchanged.
+    ///
+    /// # Arguments
+    /// * `old_backup`: Encrypted binary stream of backed up `State` using the previous layout
+    /// * `old_version`: The layout version of `old_backup`
+    /// * `pwd`: The password the backup was encrypted with. The migrated backup uses the same one.
+    pub async fn migrate_backup<B, P>(old_backup: B, old_version: u8, pwd: P) -> Result<Vec<u8>>
+    where
+        P: AsRef<[u8]>,
+        B: AsRef<[u8]>,
+    {
+        match old_version {
+            UNVERSIONED_BACKUP => {
+                let mut state = State::restore(old_backup.as_ref(), pwd.as_ref(), UNVERSIONED_BACKUP).await?;
+                state.backup(pwd).await
+            }
+            BACKUP_VERSION => Ok(old_backup.as_ref().to_vec()),
+            unknown => Err(Error::BackupVersion(unknown)),
+        }
+    }
+}
+
+impl State {
+    /// Derives the backup encryption key from the password
+    ///
+    /// # Arguments
+    /// * `pwd`: The password to derive the key from
+    fn backup_key<P>(pwd: P) -> [u8; 32]
+    where
+        P: AsRef<[u8]>,
+    {
+        SpongosRng::<KeccakF1600>::new(pwd).gen()
+    }
+
+    /// Serialises and encrypts the `State`, prefixing it with the [`BACKUP_VERSION`] byte.
+    ///
+    /// # Arguments
+    /// * `pwd`: The password to encrypt the `State` with
+    async fn backup<P>(&mut self, pwd: P) -> Result<Vec<u8>>
+    where
+        P: AsRef<[u8]>,
+    {
+        let mut ctx = sizeof::Context::new();
+        ctx.sizeof(&*self).await.map_err(Error::Spongos)?;
+        ctx.sizeof(&ExtensionsBackup(self)).await.map_err(Error::Spongos)?;
+        // Version + State + Extensions + Mac Size
+        let buf_size = 1 + ctx.finalize() + 32;
+
+        let mut buf = vec![0; buf_size];
+        buf[0] = BACKUP_VERSION;
+
+        let mut ctx = wrap::Context::new(&mut buf[1..]);
+        let key = Self::backup_key(pwd);
+        ctx.absorb(External::new(&NBytes::new(key)))
+            .map_err(Error::Spongos)?
+            .commit()
+            .map_err(Error::Spongos)?
+            .squeeze(&Mac::new(32))
+            .map_err(Error::Spongos)?;
+        ctx.wrap(self).await.map_err(Error::Spongos)?;
+        ctx.wrap(&mut ExtensionsBackup(self)).await.map_err(Error::Spongos)?;
+        assert!(
+            ctx.stream().is_empty(),
+            "Missmatch between buffer size expected by SizeOf ({buf_size}) and actual size of Wrap ({})",
+            ctx.stream().len()
+        );
+
+        Ok(buf)
+    }
+
+    /// Decrypts and deserialises a `State` from the body of a backup, the version byte excluded.
+    ///
+    /// # Arguments
+    /// * `backup`: Encrypted binary stream of backed up `State`, following the version byte
+    /// * `pwd`: The decryption password.
+    /// * `version`: The layout version of the backup
+    async fn restore<P>(backup: &[u8], pwd: P, version: u8) -> Result<Self>
+    where
+        P: AsRef<[u8]>,
+    {
+        let mut ctx = unwrap::Context::new(backup);
+        let key = Self::backup_key(pwd);
+        ctx.absorb(External::new(&NBytes::new(key)))
+            .map_err(Self::restore_error)?
+            .commit()
+            .map_err(Self::restore_error)?
+            .squeeze(&Mac::new(32))
+            .map_err(Self::restore_error)?;
+        let mut state = State::default();
+        ctx.unwrap(&mut state).await.map_err(Self::restore_error)?;
+        if version >= BACKUP_VERSION {
+            ctx.unwrap(&mut ExtensionsBackup(&mut state))
+                .await
+                .map_err(Self::restore_error)?;
+        }
+        Ok(state)
+    }
+
+    /// Converts an error unwrapping a backup, reporting the backups ending before their layout
+    /// does as truncated
+    ///
+    /// # Arguments
+    /// * `error`: The error unwrapping the backup
+    fn restore_error(error: SpongosError) -> Error {
+        match error {
+            SpongosError::StreamAllocationExceededIn(..) => Error::BackupTruncated,
+            error => Error::Spongos(error),
+        }
+    }
+}
+
+impl<T> User<T>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
+{
+    /// Receive a raw message packet using the internal [`Transport`] client
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to be retrieved.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    pub async fn receive_message(&mut self, address: Address) -> Result<Message>
+    where
+        T: for<'a> Transport<'a, Msg = TransportMessage>,
+    {
+        let msg = self.recv_screened_message(address).await?;
+        self.handle_fetched_message(address, msg).await
+    }
+
+    /// Fetches the message whose content digest is provided, as reported by [`Message::digest()`]
+    /// and [`SendResponse::digest()`], and processes it. Only transports whose index supports
+    /// content addressing look messages up by digest, the others fail with
+    /// [`LetsError::DigestLookupUnsupported`]. Fails with [`Error::DigestMismatch`] if the
+    /// transport returns a message of another digest.
+    ///
+    /// # Arguments
+    /// * `digest`: The content digest of the message
+    pub async fn fetch_by_digest(&mut self, digest: [u8; MESSAGE_DIGEST_SIZE]) -> Result<Message>
+    where
+        T: for<'a> Transport<'a, Msg = TransportMessage>,
+    {
+        let (address, msg) = self
+            .transport
+            .recv_message_by_digest(digest)
+            .await
+            .map_err(|e| Error::Wrapped("receive message by digest", e))?;
+        if msg.digest() != digest {
+            return Err(Error::DigestMismatch(address));
+        }
+        self.handle_fetched_message(address, msg).await
+    }
+
+    /// Checks that the [`Transport`] of the [`User`] can reach the messaging layer, so that services
+    /// can gate their readiness on it. Fails with [`Error::TransportUnhealthy`] otherwise.
+    pub async fn health_check(&mut self) -> Result<()> {
+        self.transport.health_check().await.map_err(Error::TransportUnhealthy)
+    }
+
+    /// Processes a message received from the transport. If the [`User`] checkpoints at keyloads,
+    /// the [`Spongos`] state of the message it is linked to is recomputed first when it was
+    /// dropped. The [`MirrorStatus`] of the message reported by the transport is attached to it.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to process
+    /// * `msg`: The raw [`TransportMessage`]
+    pub(crate) async fn handle_fetched_message(&mut self, address: Address, msg: TransportMessage) -> Result<Message> {
+        if self.state.keyload_checkpoints {
+            // Malformed messages are reported when processed
+            if let Ok(Some(link)) = Self::linked_msg_address(address, &msg).await {
+                self.linked_spongos(link).await?;
+            }
+        }
+        // Taken whatever the outcome, so that the statuses recorded by the transport do not pile up
+        let mirror_status = self.transport.take_mirror_status(address).await;
+        let message = self.handle_message(address, msg).await?;
+        Ok(message.with_mirror_status(mirror_status))
+    }
+
+    /// Receives the message at an address. With a [`MessageFilter`], every message at the address
+    /// is received and the dropped ones are discarded, so that spam posted at the address does not
+    /// hide the stream message. Several announcements or keyloads at the address are reported as a
+    /// [`Conflict`] rather than picking one of them.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to be retrieved.
+    pub(crate) async fn recv_screened_message(&mut self, address: Address) -> Result<TransportMessage> {
+        // Without a filter, a second message is enough to tell that the address is contested
+        let msgs = match self.message_filter {
+            Some(_) => self.transport.recv_messages(address).await,
+            None => self.transport.recv_messages_paged(address, 0, 2).await,
+        };
+        self.screen_received(address, msgs).await
+    }
+
+    /// Receives the messages at several addresses concurrently, screening the messages at each
+    /// address like [`User::recv_screened_message()`]. The results are in the order of the
+    /// addresses.
+    ///
+    /// # Arguments
+    /// * `addresses`: The [`Addresses`](Address) of the messages to be retrieved.
+    pub(crate) async fn recv_screened_messages(&mut self, addresses: Vec<Address>) -> Vec<Result<TransportMessage>> {
+        let limit = match self.message_filter {
+            Some(_) => usize::MAX,
+            None => 2,
+        };
+        let batch = self.transport.recv_messages_batch(addresses.clone(), limit).await;
+        let mut received = Vec::with_capacity(addresses.len());
+        for (address, msgs) in addresses.into_iter().zip(batch) {
+            received.push(self.screen_received(address, msgs).await);
+        }
+        received
+    }
+
+    /// Screens the messages received at an address, returning the stream message among them
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] the messages were received at.
+    /// * `msgs`: The outcome of receiving the messages from the transport.
+    async fn screen_received(
+        &mut self,
+        address: Address,
+        msgs: core::result::Result<Vec<TransportMessage>, LetsError>,
+    ) -> Result<TransportMessage> {
+        let mut msgs = msgs.map_err(|e| {
+            self.record_transport_error(&e);
+            Error::Transport(address, "receive message", e)
+        })?;
+        if self.message_filter.is_none() && msgs.len() == 1 {
+            return Ok(msgs.remove(0));
+        }
+
+        let mut accepted: Vec<(TransportMessage, Option<HDF>)> = Vec::new();
+        for msg in msgs {
+            // Copies of a message do not conflict with it
+            if accepted.iter().any(|(accepted_msg, _)| *accepted_msg == msg) {
+                continue;
+            }
+            match Self::preview_header(address, &msg, self.size_limit).await {
+                Ok(header) => {
+                    if self.screen_message(address, &msg, header.as_ref()).is_ok() {
+                        accepted.push((msg, header));
+                    }
+                }
+                // Messages whose header cannot be decoded are spam as well when spam is filtered
+                Err(_) if self.message_filter.is_some() => {}
+                Err(_) => accepted.push((msg, None)),
+            }
+        }
+
+        match accepted.len() {
+            0 if self.message_filter.is_some() => Err(Error::MessageFiltered(
+                address,
+                "every message at the address was dropped",
+            )),
+            0 => Err(Error::Transport(
+                address,
+                "receive message",
+                LetsError::MessageNotFound(address),
+            )),
+            1 => Ok(accepted.remove(0).0),
+            count => {
+                if self.collision_diagnostics {
+                    let headers = accepted.iter().filter_map(|(_, header)| header.as_ref());
+                    if let Some((first, second)) = self.find_collision(address, headers) {
+                        return Err(Error::AddressCollision(address, first, second));
+                    }
+                }
+                let headers: Vec<HDF> = accepted
+                    .into_iter()
+                    .filter_map(|(_, header)| header)
+                    .filter(|header| {
+                        matches!(
+                            header.message_type(),
+                            message_types::ANNOUNCEMENT | message_types::KEYLOAD
+                        )
+                    })
+                    .collect();
+                if headers.len() > 1 {
+                    let announcements = headers
+                        .iter()
+                        .filter(|h| h.message_type() == message_types::ANNOUNCEMENT)
+                        .count();
+                    let kind = match announcements {
+                        0 => "keyload",
+                        n if n == headers.len() => "announcement",
+                        _ => "announcement and keyload",
+                    };
+                    self.conflicts.push(Conflict { address, headers });
+                    return Err(Error::Conflict(address, kind));
+                }
+                Err(Error::Transport(
+                    address,
+                    "receive message",
+                    LetsError::MultipleMessagesFound(address, count),
+                ))
+            }
+        }
+    }
+
+    /// Recovers the [`LinkInputs`] of the messages found at an address from their headers,
+    /// returning two distinct inputs mapping to the msg_index of the address, if any. Messages
+    /// of unknown branches, or whose inputs map elsewhere, like spam posted at the address, are
+    /// ignored.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] the messages were found at.
+    /// * `headers`: The headers of the messages.
+    fn find_collision<'h>(
+        &self,
+        address: Address,
+        headers: impl IntoIterator<Item = &'h HDF>,
+    ) -> Option<(LinkInputs, LinkInputs)> {
+        let msg_index = address.to_msg_index();
+        let mut colliding: Vec<LinkInputs> = Vec::new();
+        for header in headers {
+            let topic = match self.topic_by_hash(&header.topic_hash) {
+                Some(topic) => topic,
+                None => continue,
+            };
+            // Announcements derive the base address of their stream, the other messages are
+            // published in the stream of the address
+            let (appaddr, seq_num) = match header.message_type() {
+                message_types::ANNOUNCEMENT => (
+                    self.link_generator.gen_app_addr(&header.publisher, &topic),
+                    INIT_MESSAGE_NUM,
+                ),
+                _ => (address.base(), header.sequence),
+            };
+            let inputs = LinkInputs {
+                appaddr,
+                publisher: header.publisher.clone(),
+                topic,
+                seq_num,
+            };
+            if inputs.derive(self.link_generator.as_ref()).to_msg_index() == msg_index && !colliding.contains(&inputs) {
+                colliding.push(inputs);
+            }
+        }
+        let mut colliding = colliding.into_iter();
+        Some((colliding.next()?, colliding.next()?))
+    }
+
+    /// Start a [`Messages`] stream to traverse the channel messages
+    ///
+    /// See the documentation in [`Messages`] for more details and examples.
+    pub fn messages(&mut self) -> Messages<T> {
+        Messages::new(self)
+    }
+
+    /// Iteratively fetches all the next messages until internal state has caught up
+    ///
+    /// If succeeded, returns the number of messages advanced.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn sync(&mut self) -> Result<usize> {
+        let stopwatch = Stopwatch::start();
+        // ignoring the result is sound as Drain::Error is Infallible
+        let synced = self
+            .messages()
+            .try_fold(0, |n, _| future::ok(n + 1))
+            .await
+            .map_err(Error::Messages)?;
+        self.record_sync(&stopwatch);
+        Ok(synced)
+    }
+
+    /// Iteratively fetches all the pending messages from the transport
+    ///
+    /// Return a vector with all the messages collected. This is a convenience
+    /// method around the [`Messages`] stream. Check out its docs for more
+    /// advanced usages.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn fetch_next_messages(&mut self) -> Result<Vec<Message>> {
+        let stopwatch = Stopwatch::start();
+        let messages = self.messages().try_collect().await.map_err(Error::Messages)?;
+        self.record_sync(&stopwatch);
+        Ok(messages)
+    }
+
+    /// Records a completed sync in the [`Metrics`] of the user, if any
+    ///
+    /// # Arguments
+    /// * `stopwatch`: The [`Stopwatch`] started with the sync
+    fn record_sync(&self, stopwatch: &Stopwatch) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_sync(stopwatch.elapsed());
+        }
+    }
+
+    /// Fetches up to `limit` messages of the branch of an anchor message, following the links
+    /// between messages instead of guessing addresses from the publisher cursors. The anchor itself
+    /// is not included, and the messages are returned in publication order, from oldest to newest.
+    ///
+    /// Walking [`HistoryDirection::Backward`] follows the links from the anchor towards the
+    /// announcement of the branch. As messages only link to their predecessor, walking
+    /// [`HistoryDirection::Forward`] follows the links from the latest known message of the branch
+    /// down to the anchor, so the [`User`] should be synced beforehand.
+    ///
+    /// Replaying old messages does not move the cursors of the [`User`] back. A message that
+    /// cannot be read because the [`Spongos`] state of its predecessor is no longer stored (as
+    /// happens with lean users) is returned as an [`Orphan`].
+    ///
+    /// # Arguments
+    /// * `from_address`: The [`Address`] of the anchor message
+    /// * `direction`: The [`HistoryDirection`] in which to walk the branch
+    /// * `limit`: The maximum number of messages to return
+    pub async fn fetch_history(
+        &mut self,
+        from_address: Address,
+        direction: HistoryDirection,
+        limit: usize,
+    ) -> Result<Vec<Message>> {
+        let base_address = from_address.base();
+        let anchor = self.fetch_raw_message(from_address).await?;
+
+        // Raw messages of the page, from newest to oldest
+        let mut chain = Vec::new();
+        match direction {
+            HistoryDirection::Backward => {
+                let mut link = Self::linked_msg_address(from_address, &anchor).await?;
+                while let Some(rel_address) = link.filter(|_| chain.len() < limit) {
+                    let address = Address::new(base_address, rel_address);
+                    let msg = self.fetch_raw_message(address).await?;
+                    link = Self::linked_msg_address(address, &msg).await?;
+                    chain.push((address, msg));
+                }
+            }
+            HistoryDirection::Forward => {
+                let preparsed: PreparsedMessage = anchor
+                    .parse_header()
+                    .await
+                    .map_err(|e| Error::Unwrapping("header", from_address, e))?;
+                let topic = self
+                    .topic_by_hash(preparsed.header().topic_hash())
+                    .ok_or(Error::UnknownTopic(*preparsed.header().topic_hash()))?;
+                let mut link = self.get_latest_link(&topic);
+                loop {
+                    match link {
+                        Some(rel_address) if rel_address == from_address.relative() => break,
+                        Some(rel_address) => {
+                            let address = Address::new(base_address, rel_address);
+                            let msg = self.fetch_raw_message(address).await?;
+                            link = Self::linked_msg_address(address, &msg).await?;
+                            chain.push((address, msg));
+                        }
+                        None => return Err(Error::MessageMissing(from_address.relative(), "branch history")),
+                    }
+                }
+                // Keep only the messages closest to the anchor
+                chain.drain(..chain.len().saturating_sub(limit));
+            }
+        }
+
+        // Messages are processed from oldest to newest, so that each one can be read with the
+        // spongos of its predecessor. Cursors and branch links are restored afterwards, as processing
+        // an old message sets them back to the point in time of that message. The messages were
+        // already processed, so the detection of duplicates is suspended meanwhile
+        let cursor_store = self.state.cursor_store.clone();
+        let subscribers = self.state.subscribers.clone();
+        let seen_messages = core::mem::replace(&mut self.state.seen_messages, SeenMessages::new(0));
+        let mut history = Vec::with_capacity(chain.len());
+        let mut result = Ok(());
+        for (address, msg) in chain.into_iter().rev() {
+            match self.handle_fetched_message(address, msg).await {
+                Ok(message) => history.push(message),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        self.state.cursor_store = cursor_store;
+        self.state.subscribers = subscribers;
+        self.state.seen_messages = seen_messages;
+        result.map(|_| history)
+    }
+
+    /// Re-verifies the signatures and the link continuity of a span of messages, reporting the
+    /// signer of each one. The span is walked backward from `to_address` following the links
+    /// between messages until `from_address`, both included, so `to_address` must descend from
+    /// `from_address`.
+    ///
+    /// Every message is processed again with the [`Spongos`] state of its predecessor, which proves
+    /// it was linked to it, and its signature is verified. Failures are recorded in the
+    /// [`ProvenanceReport`] instead of aborting the verification. The first message of the span can
+    /// only be verified if the [`Spongos`] state of its predecessor is stored, so the [`User`]
+    /// should not be lean. Packets of forward secrecy branches whose keys have been erased cannot
+    /// be read again, so they are reported as failed.
+    ///
+    /// Replaying the messages does not move the cursors of the [`User`] back.
+    ///
+    /// # Arguments
+    /// * `from_address`: The [`Address`] of the oldest message of the span
+    /// * `to_address`: The [`Address`] of the newest message of the span
+    pub async fn verify_chain(&mut self, from_address: Address, to_address: Address) -> Result<ProvenanceReport> {
+        // Raw messages of the span, from newest to oldest
+        let mut chain = Vec::new();
+        let mut address = to_address;
+        loop {
+            let msg = self.fetch_raw_message(address).await?;
+            let link = Self::linked_msg_address(address, &msg).await?;
+            chain.push((address, link, msg));
+            match link {
+                _ if address == from_address => break,
+                Some(rel_address) => address = Address::new(to_address.base(), rel_address),
+                None => return Err(Error::MessageMissing(from_address.relative(), "provenance chain")),
+            }
+        }
+
+        // Messages are processed from oldest to newest, so that each one can be read with the
+        // spongos of its predecessor. Cursors, branch links and ratchets are restored afterwards, as
+        // processing an old message sets them back to the point in time of that message. The
+        // detection of duplicates is suspended meanwhile
+        let cursor_store = self.state.cursor_store.clone();
+        let subscribers = self.state.subscribers.clone();
+        let ratchets = self.state.ratchets.clone();
+        let seen_messages = core::mem::replace(&mut self.state.seen_messages, SeenMessages::new(0));
+        let mut entries = Vec::with_capacity(chain.len());
+        for (address, link, msg) in chain.into_iter().rev() {
+            let message = self
+                .handle_fetched_message(address, msg)
+                .await
+                .map_err(|e| e.to_string());
+            entries.push(ProvenanceEntry::new(address, link, message));
+        }
+        self.state.cursor_store = cursor_store;
+        self.state.subscribers = subscribers;
+        self.state.ratchets = ratchets;
+        self.state.seen_messages = seen_messages;
+        Ok(ProvenanceReport::new(entries))
+    }
+
+    /// Creates a [`Reference`] citing the message at an address, possibly of another channel, to be
+    /// sent with [`User::send_citing_packet()`]. The message is fetched from the transport to
+    /// compute its digest, but not processed.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to cite
+    pub async fn cite(&mut self, address: Address) -> Result<Reference> {
+        let msg = self.fetch_raw_message(address).await?;
+        Ok(Reference::to_message(address, &msg))
+    }
+
+    /// Verifies that the message cited by a [`Reference`] was not altered. The [`User`] must be
+    /// synced with the channel of the cited message, so that the message is known to be genuine:
+    /// the reference is verified by a [`User`] of the other channel when it cites a message of
+    /// another channel.
+    ///
+    /// Returns `true` if a message matching the digest of the reference is found at the cited
+    /// address, and `false` if the message found was altered.
+    /// [`Error::ReferenceUnverifiable`] is returned if the [`User`] did not process the
+    /// cited message.
+    ///
+    /// # Arguments
+    /// * `reference`: The [`Reference`] to the cited message, from [`Message::references()`]
+    pub async fn verify_reference(&mut self, reference: &Reference) -> Result<bool> {
+        let address = reference.address();
+        if self.stream_address().map(|stream_address| stream_address.base()) != Some(address.base()) {
+            return Err(Error::ReferenceUnverifiable(address));
+        }
+        // Every message at the address is compared, so that spam posted at the address does not hide
+        // the cited message
+        let msgs = self.transport.recv_messages(address).await.map_err(|e| {
+            self.record_transport_error(&e);
+            Error::Transport(address, "verify reference", e)
+        })?;
+        let cited = match msgs.into_iter().find(|msg| reference.matches(msg)) {
+            Some(cited) => cited,
+            None => return Ok(false),
+        };
+
+        // The cited message must have been processed, and thus verified, by the user
+        let preparsed: PreparsedMessage = cited
+            .parse_header()
+            .await
+            .map_err(|e| Error::Unwrapping("header", address, e))?;
+        let header = preparsed.header();
+        let processed = self
+            .topic_by_hash(header.topic_hash())
+            .and_then(|topic| self.state.cursor_store.get_cursor(&topic, header.publisher()))
+            .map_or(false, |cursor| cursor >= header.sequence());
+        match processed {
+            true => Ok(true),
+            false => Err(Error::ReferenceUnverifiable(address)),
+        }
+    }
+
+    /// Retrieves a raw message from the transport without processing it
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the message to be retrieved.
+    async fn fetch_raw_message(&mut self, address: Address) -> Result<TransportMessage> {
+        self.recv_screened_message(address).await
+    }
+
+    /// Reads the [`MsgId`] of the message a raw message is linked to, without processing it.
+    /// Returns `None` for messages that are not linked, like the stream announcement.
+    ///
+    /// # Arguments
+    /// * `address`: The [`Address`] of the raw message
+    /// * `msg`: The raw [`TransportMessage`]
+    async fn linked_msg_address(address: Address, msg: &TransportMessage) -> Result<Option<MsgId>> {
+        if legacy::is_legacy(msg) {
+            let mut header = legacy::Header::default();
+            unwrap::Context::new(msg.as_ref())
+                .unwrap(&mut header)
+                .await
+                .map_err(|e| Error::Unwrapping("legacy header", address, e.into()))?;
+            return Ok(match header.message_type() {
+                legacy::ANNOUNCE => None,
+                _ => Some(header.link()),
+            });
+        }
+
+        let preparsed: PreparsedMessage = msg
+            .clone()
+            .parse_header()
+            .await
+            .map_err(|e| Error::Unwrapping("header", address, e))?;
+        Ok(preparsed.header().linked_msg_address())
+    }
+}
+
+impl<T, TSR> User<T>
+where
+    T: for<'a> Transport<'a, Msg = TransportMessage, SendResponse = TSR>,
+{
+    /// Sends a message through the transport, recording it in the [`Metrics`] of the user. Messages
+    /// larger than the [capabilities](`lets::transport::TransportCapabilities`) of the transport
+    /// allow are refused. Only the transport and the metrics are borrowed, so that the state of the
+    /// user can be held across the send.
+    ///
+    /// Returns the [`SendReceipt`] of the transport, the blocks it did not timestamp being stamped
+    /// with the time of the send, and the content digest of the message being recorded.
+    ///
+    /// # Arguments
+    /// * `transport`: The [`Transport`] of the user
+    /// * `metrics`: The [`Metrics`] of the user, if any
+    /// * `address`: The [`Address`] of the message
+    /// * `msg`: The raw [`TransportMessage`] to send
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all, fields(address = %address)))]
+    async fn send_transport_message(
+        transport: &mut T,
+        metrics: Option<&Metrics>,
+        address: Address,
+        msg: TransportMessage,
+    ) -> core::result::Result<SendReceipt<TSR>, LetsError> {
+        // Messages the transport would reject are not sent at all
+        if let Some(max_message_size) = transport.capabilities().await.max_message_size {
+            if msg.as_ref().len() > max_message_size {
+                return Err(LetsError::MessageTooLarge(
+                    address,
+                    msg.as_ref().len(),
+                    max_message_size,
+                ));
+            }
+        }
+        let digest = msg.digest();
+        let stopwatch = Stopwatch::start();
+        let sent = transport.send_message_receipt(address, msg).await;
+        if let Some(metrics) = metrics {
+            match &sent {
+                Ok(_) => metrics.record_send(stopwatch.elapsed()),
+                Err(e) => metrics.record_transport_error(e),
+            }
+        }
+        let mut receipt = sent?.with_digest(digest);
+        let sent_at = clock::unix_time();
+        receipt
+            .blocks
+            .iter_mut()
+            .filter(|block| block.timestamp.is_none())
+            .for_each(|block| block.timestamp = sent_at);
+        Ok(receipt)
+    }
+
+    /// Returns true if a message was already published at an address, so that another message
+    /// cannot be sent to it. Errors if the transport failed to tell.
+    ///
+    /// # Arguments
+    /// * `transport`: The transport the message would be sent with.
+    /// * `address`: The [`Address`] of the message.
+    async fn address_taken(transport: &mut T, address: Address) -> core::result::Result<bool, LetsError> {
+        match transport.recv_message(address).await {
+            Ok(_) | Err(LetsError::MultipleMessagesFound(..)) => Ok(true),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Create and send a stream Announcement message, anchoring the stream for others to attach to.
+    /// Errors if the [`User`] is already attached to a stream, or if the message already exists in
+    /// the transport layer.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] that will be used for the base branch
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn create_stream<Top: Into<Topic>>(&mut self, topic: Top) -> Result<SendResponse<TSR>> {
+        self.announce_stream(topic.into(), 0, None).await
+    }
+
+    /// Create and send the Announcement message of a strict stream. The strictness is embedded in
+    /// the announcement, and the readers of the stream reject the messages using weaker options
+    /// than the current ones, unsigned packets and legacy messages, so that mixed fleets cannot be
+    /// spoofed by downgrading to them. Streams only support the `KeccakF1600` PRP, so there is
+    /// no weaker PRP configuration for strictness to reject. Errors like
+    /// [`create_stream()`](User::create_stream).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] that will be used for the base branch
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn create_strict_stream<Top: Into<Topic>>(&mut self, topic: Top) -> Result<SendResponse<TSR>> {
+        self.announce_stream(topic.into(), announcement::STRICT, None).await
+    }
+
+    /// Create and send the Announcement message of a stream operated by a [`Quorum`] of author
+    /// keys. The announcement carries the quorum and the approvals of its members, collected out of
+    /// band on the [`Quorum::announcement_proposal()`] of the [`User`] and the topic, and readers
+    /// reject it unless the threshold of the members approved it. From then on, the author
+    /// rotations of the stream must be approved by the quorum too, see
+    /// [`rotate_author_with_approvals()`](User::rotate_author_with_approvals). Keyloads, branch
+    /// closures and permission changes are not covered, the author key sends them alone. Errors
+    /// with [`Error::QuorumNotReached`] if the approvals do not reach the threshold, and otherwise
+    /// like [`create_stream()`](User::create_stream).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] that will be used for the base branch
+    /// * `quorum`: The [`Quorum`] of author keys of the stream
+    /// * `approvals`: The [`QuorumApproval`]s of the announcement by the members of the quorum
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn create_stream_with_quorum<Top: Into<Topic>>(
+        &mut self,
+        topic: Top,
+        quorum: Quorum,
+        approvals: &[QuorumApproval],
+    ) -> Result<SendResponse<TSR>> {
+        let topic = topic.into();
+        let identifier = self.identifier().ok_or(Error::NoIdentity("create a stream"))?;
+        quorum.verify(&quorum.announcement_proposal(identifier, &topic), approvals)?;
+        self.announce_stream(topic, 0, Some((quorum, approvals))).await
+    }
+
+    /// Sends the Announcement message of a new stream, carrying the flags of the stream and its
+    /// [`Quorum`] if one is given
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] that will be used for the base branch
+    /// * `flags`: The flags of the stream, such as [`announcement::STRICT`]
+    /// * `quorum`: The [`Quorum`] of the stream and its approvals of the announcement
+    async fn announce_stream(
+        &mut self,
+        topic: Topic,
+        flags: u8,
+        quorum: Option<(Quorum, &[QuorumApproval])>,
+    ) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        if self.stream_address().is_some() {
+            return Err(Error::Setup(
+                "Cannot create a channel, user is already registered to channel",
+            ));
+        }
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("create a stream"))?.clone();
+        // Generate stream address
+        let stream_base_address = self.link_generator.gen_app_addr(&identifier, &topic);
+        let stream_rel_address =
+            self.link_generator
+                .gen_msg_id(stream_base_address, &identifier, &topic, INIT_MESSAGE_NUM);
+        let stream_address = Address::new(stream_base_address, stream_rel_address);
+
+        // Prepare HDF and PCF
+        let mut announcement = announcement::Wrap::new(self.identity().unwrap(), &topic).with_flags(flags);
+        if let Some((quorum, approvals)) = &quorum {
+            announcement = announcement.with_quorum(quorum, approvals);
+        }
+        let header = HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, identifier.clone(), &topic);
+        let content = PCF::new_final_frame().with_content(announcement);
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap announce", e))?;
+
+        // Attempt to send message
+        if Self::address_taken(&mut self.transport, stream_address)
+            .await
+            .map_err(|e| Error::Transport(stream_address, "check that the address is free", e))?
+        {
+            return Err(Error::Setup("Cannot create a channel, announce address already in use"));
+        }
+
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            stream_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send announce message", e))?;
+
+        // If a message has been sent successfully, insert the base branch into store
+        self.state.cursor_store.new_branch(topic.clone());
+        self.state.topics.insert(topic.clone());
+        // Commit message to stores
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, Permissioned::Admin(identifier.clone()), INIT_MESSAGE_NUM);
+        self.state.spongos_store.insert(stream_address.relative(), spongos)?;
+
+        // Update branch links
+        self.set_latest_link(topic.clone(), stream_address.relative());
+
+        // Commit Author Identifier and Stream Address to store
+        self.state.stream_address = Some(stream_address);
+        self.state.author_identifier = Some(identifier);
+        self.state.strict = flags.map_or(false, |flags| flags & announcement::STRICT != 0);
+        self.state.quorum = quorum.map(|(quorum, _)| quorum);
+        self.state.base_branch = topic;
+
+        Ok(SendResponse::new(stream_address, send_response))
+    }
+
+    /// Advertises the stream of the user at its discovery address, so that consumers find the
+    /// stream with [`discover()`](crate::discover) from the [`Identifier`] of the user alone.
+    /// The announcement of the stream is published again at the discovery address. Only the
+    /// stream author can advertise the stream.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn advertise_stream(&mut self) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before advertising a stream, the stream must be created"))?;
+        // Confirm user is the stream author
+        let identifier = self
+            .identifier()
+            .ok_or(Error::NoIdentity("advertise a stream"))?
+            .clone();
+        if self.state.author_identifier.as_ref() != Some(&identifier) {
+            return Err(Error::WrongRole("author", identifier, "advertise a stream"));
+        }
+
+        let announcement = self
+            .transport
+            .recv_message(stream_address)
+            .await
+            .map_err(|e| Error::Transport(stream_address, "receive announce message", e))?;
+        let address = discovery::discovery_address(&identifier);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, announcement)
+                .await
+                .map_err(|e| Error::Transport(address, "advertise stream", e))?;
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a new Branch Announcement message, creating a new branch in `CursorStore`
+    /// with the previous branches permissions carried forward.
+    ///
+    /// # Arguments
+    /// * `from_topic`: The [`Topic`] of the branch to generate the new branch from.
+    /// * `to_topic`: The [`Topic`] of the new branch being created.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn new_branch(
+        &mut self,
+        from_topic: impl Into<Topic>,
+        to_topic: impl Into<Topic>,
+    ) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before starting a new branch, the stream must be created"))?;
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("create a branch"))?.clone();
+        // Check Topic
+        let topic: Topic = to_topic.into();
+        let prev_topic: Topic = from_topic.into();
+        if self.is_branch_closed(&prev_topic) {
+            return Err(Error::BranchClosed(prev_topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&prev_topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?;
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "make a new branch"));
+        }
+        let link_to = self
+            .get_latest_link(&prev_topic)
+            .ok_or_else(|| Error::TopicNotFound(prev_topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self
+            .next_cursor(&prev_topic)
+            .map_err(|_| Error::NoCursor(prev_topic.clone()))?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &prev_topic, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let header = HDF::new(
+            message_types::BRANCH_ANNOUNCEMENT,
+            user_cursor,
+            identifier.clone(),
+            &prev_topic,
+        )
+        .with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(branch_announcement::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+            &topic,
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap new branch", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("new branch", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send new branch message", e))?;
+
+        // If message has been sent successfully, create the new branch in store
+        self.state.cursor_store.new_branch(topic.clone());
+        self.state.topics.insert(topic.clone());
+        // Commit message to stores and update cursors
+        self.state.cursor_store.insert_cursor(
+            &prev_topic,
+            Permissioned::Admin(identifier.clone()),
+            self.next_cursor(&prev_topic)?,
+        );
+        self.state.spongos_store.insert(address.relative(), spongos)?;
+        self.state
+            .spongos_positions
+            .insert(address.relative(), SpongosPosition::now(user_cursor));
+        // Collect permissions from previous branch and clone them into new branch
+        let prev_permissions = self
+            .cursors_by_topic(&prev_topic)?
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<Permissioned<Identifier>>>();
+        for id in prev_permissions {
+            self.state.cursor_store.insert_cursor(&topic, id, INIT_MESSAGE_NUM);
+        }
+
+        // Update branch links
+        self.state.cursor_store.set_latest_link(topic, address.relative());
+        self.notarize_sent(&prev_topic, &identifier, user_cursor, hash).await?;
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create an [`Invite`] to subscribe to the stream, granting access to a branch, and sign it.
+    /// The returned [`InviteToken`] is to be shared out of band with the invited users, who include
+    /// it in their subscription with [`User::subscribe_with_invite()`]. Only the stream author can
+    /// create invites, as subscriptions can only be read by the author. The invite is only valid
+    /// for this stream, whose address is signed along the invite. The subscribers accepted with the
+    /// invite are granted access to the branch by [`User::grant_invites()`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the invite grants access to.
+    /// * `expiry`: Unix timestamp, in seconds, after which the invite is no longer valid.
+    /// * `max_uses`: Number of subscribers that can use the invite.
+    pub async fn create_invite(
+        &mut self,
+        topic: impl Into<Topic>,
+        expiry: u64,
+        max_uses: usize,
+    ) -> Result<InviteToken> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before creating an invite, the stream must be created"))?;
+        // Confirm user is the stream author
+        let user_id = self.identity().ok_or(Error::NoIdentity("create an invite"))?;
+        if self.state.author_identifier.as_ref() != Some(user_id.identifier()) {
+            return Err(Error::WrongRole(
+                "author",
+                user_id.identifier().clone(),
+                "create an invite",
+            ));
+        }
+        // Check Topic
+        let topic: Topic = topic.into();
+        if !self.state.topics.contains(&topic) {
+            return Err(Error::TopicNotFound(topic));
+        }
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+
+        Invite::new(stream_address, topic, expiry, max_uses).sign(user_id).await
+    }
+
+    /// Create and send a Branch Closure message, the terminal message of a branch. Readers
+    /// processing it surface a [`MessageContent::BranchClosed`], and neither the [`User`] nor the
+    /// readers publish further messages in the branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to close.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn close_branch(&mut self, topic: impl Into<Topic>) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before closing a branch, the stream must be created"))?;
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("close a branch"))?.clone();
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?;
+        if !permission.is_admin() {
+            return Err(Error::WrongRole("Admin", identifier, "close a branch"));
+        }
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let header = HDF::new(message_types::BRANCH_CLOSURE, user_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(branch_closure::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap branch closure", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("branch closure", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send branch closure", e))?;
+
+        // If message has been sent successfully, commit message to stores and close the branch
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, Permissioned::Admin(identifier.clone()), user_cursor);
+        self.store_spongos(address.relative(), spongos, link_to, user_cursor)?;
+        self.state.closed_branches.insert(topic.clone());
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a Read Marker message, recording the last message processed by the [`User`].
+    /// The consumers of a group publish their markers in a branch shared by the group, usually a
+    /// private one, and learn how far the others have read with [`User::read_markers()`] once
+    /// they have processed them. Readers surface a [`MessageContent::ReadMarker`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to publish the marker in.
+    /// * `marked_address`: The [`Address`] of the last message processed by the user.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_read_marker(
+        &mut self,
+        topic: impl Into<Topic>,
+        marked_address: Address,
+    ) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before sending a read marker, the stream must be created"))?;
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("send read marker"))?.clone();
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "send a read marker"));
+        }
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let header = HDF::new(message_types::READ_MARKER, user_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(read_marker::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+            &marked_address,
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap read marker", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("read marker", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send read marker", e))?;
+
+        // If message has been sent successfully, commit message to stores and record the marker
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.store_spongos(address.relative(), spongos, link_to, user_cursor)?;
+        self.mark_recomputable(&topic, address.relative());
+        self.state
+            .read_markers
+            .entry(topic.clone())
+            .or_default()
+            .insert(identifier.clone(), marked_address);
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a Tombstone message, redacting a message of a branch. The ledger being
+    /// immutable, the redacted message stays on the Tangle, but the readers processing the tombstone
+    /// withhold its payloads, surfacing it as a [`MessageContent::Redacted`] keeping the original
+    /// content available on request (see [`User::redact()`]). Readers surface the tombstone itself
+    /// as a [`MessageContent::Tombstone`]. Only the stream author can redact messages.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the redacted message.
+    /// * `msgid`: The [`MsgId`] of the redacted message.
+    /// * `reason`: The reason of the redaction, readable by the readers of the branch.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_tombstone(
+        &mut self,
+        topic: impl Into<Topic>,
+        msgid: MsgId,
+        reason: impl Into<String>,
+    ) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before sending a tombstone, the stream must be created"))?;
+        // Confirm user is the stream author
+        let identifier = self.identifier().ok_or(Error::NoIdentity("send tombstone"))?.clone();
+        if self.state.author_identifier.as_ref() != Some(&identifier) {
+            return Err(Error::WrongRole("author", identifier, "redact a message"));
+        }
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let tombstone_msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), tombstone_msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let reason = reason.into();
+        let header = HDF::new(message_types::TOMBSTONE, user_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(tombstone::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+            &msgid,
+            &reason,
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap tombstone", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("tombstone", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send tombstone", e))?;
+
+        // If message has been sent successfully, commit message to stores and record the redaction
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.store_spongos(address.relative(), spongos, link_to, user_cursor)?;
+        self.state.tombstones.insert(msgid, (tombstone_msgid, reason));
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a Countersignature message, attesting a packet of a branch after its
+    /// publication. The message is linked to the packet and signed by the [`User`] as a witness:
+    /// the readers trusting the witness (see [`User::add_witness()`]) attach the countersignature to
+    /// the packet, available with [`Message::countersignatures()`], and surface the message itself
+    /// as a [`MessageContent::Countersignature`]. The following messages of the branch are not
+    /// linked to it. Requires write permission in the branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the countersigned packet.
+    /// * `msgid`: The [`MsgId`] of the countersigned packet.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn countersign(&mut self, topic: impl Into<Topic>, msgid: MsgId) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before countersigning a packet, the stream must be created",
+        ))?;
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("countersign"))?.clone();
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "countersign a packet"));
+        }
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let countersignature_msgid =
+            self.link_generator
+                .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), countersignature_msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(msgid)
+            .await?
+            .ok_or(Error::MessageMissing(msgid, "spongos store"))?;
+        let header = HDF::new(message_types::COUNTERSIGNATURE, user_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(msgid);
+        let content = PCF::new_final_frame().with_content(countersignature::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+        ));
+
+        // Wrap message
+        let (transport_msg, _) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap countersignature", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("countersignature", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send countersignature", e))?;
+
+        // If message has been sent successfully, commit message to stores and record the
+        // countersignature
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.record_countersignature(Countersignature {
+            witness: identifier.clone(),
+            countersigned: msgid,
+        });
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a Commitment message, binding the [`User`] to a payload without disclosing
+    /// it. The payload is disclosed later with [`User::send_reveal()`], which readers verify against
+    /// the digest of the commitment. Only the last commitment of the user in a branch can be
+    /// revealed. Requires write permission in the branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to publish the commitment in.
+    /// * `digest`: The digest of the payload, computed with [`commitment_digest()`].
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_commitment(
+        &mut self,
+        topic: impl Into<Topic>,
+        digest: [u8; COMMITMENT_DIGEST_SIZE],
+    ) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before sending a commitment, the stream must be created"))?;
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("send commitment"))?.clone();
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "send a commitment"));
+        }
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let header = HDF::new(message_types::COMMITMENT, user_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(commitment::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+            &digest,
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap commitment", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("commitment", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send commitment", e))?;
+
+        // If message has been sent successfully, commit message to stores and record the commitment
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.store_spongos(address.relative(), spongos, link_to, user_cursor)?;
+        self.state.commitments.insert(
+            msgid,
+            Commitment {
+                publisher_identifier: identifier.clone(),
+                digest,
+            },
+        );
+        self.state.pending_commitments.insert(topic.clone(), msgid);
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a Reveal message, disclosing the payload of the last commitment sent by the
+    /// [`User`] in a branch (see [`User::send_commitment()`]). The payload is checked against the
+    /// digest of the commitment before being sent, and readers surface the reveal as a
+    /// [`MessageContent::Reveal`] once they verified it the same way.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the commitment.
+    /// * `payload`: The payload committed to.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_reveal<P>(&mut self, topic: impl Into<Topic>, payload: P) -> Result<SendResponse<TSR>>
+    where
+        P: AsRef<[u8]>,
+    {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before sending a reveal, the stream must be created"))?;
+        // Confirm user has identity
+        let identifier = self.identifier().ok_or(Error::NoIdentity("send reveal"))?.clone();
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        let commitment = *self.state.pending_commitments.get(&topic).ok_or(Error::Setup(
+            "before sending a reveal, a commitment must be sent in the branch",
+        ))?;
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "send a reveal"));
+        }
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+
+        // Check that the payload matches the commitment
+        let payload = payload.as_ref();
+        if self.state.commitments.get(&commitment).map(|c| c.digest) != Some(commitment_digest(payload)) {
+            return Err(Error::RevealMismatch(
+                address,
+                "the payload does not match the digest of the commitment",
+            ));
+        }
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let header =
+            HDF::new(message_types::REVEAL, user_cursor, identifier.clone(), &topic).with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(reveal::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+            &commitment,
+            payload,
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap reveal", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("reveal", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send reveal", e))?;
+
+        // If message has been sent successfully, commit message to stores and close the commitment
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.store_spongos(address.relative(), spongos, link_to, user_cursor)?;
+        self.state.pending_commitments.remove(&topic);
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send an Author Rotation message, handing the authorship of the stream over to a
+    /// new [`Identifier`]. The message is signed with the key of the current author, proving the
+    /// continuity between the two keys: readers verify it against the author they know, and accept
+    /// the keyloads, and the other messages reserved to the author, of the new identifier from
+    /// then on. The new author is granted the administration of the branches of the current
+    /// author. The [`User`] of the new author takes over once it processes the message. The
+    /// message is linked to the announcement, so that every reader of the stream can verify it.
+    /// Streams announced with a [`Quorum`] reject the rotations the quorum did not approve, see
+    /// [`rotate_author_with_approvals()`](User::rotate_author_with_approvals).
+    ///
+    /// # Arguments
+    /// * `new_author`: The [`Identifier`] of the new stream author.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn rotate_author(&mut self, new_author: Identifier) -> Result<SendResponse<TSR>> {
+        if let Some(quorum) = &self.state.quorum {
+            return Err(Error::QuorumNotReached(0, quorum.threshold() as usize));
+        }
+        self.send_author_rotation(new_author, None).await
+    }
+
+    /// Create and send an Author Rotation message approved by the [`Quorum`] of the stream, like
+    /// [`rotate_author()`](User::rotate_author). The approvals of the members of the quorum are
+    /// collected out of band on the [`User::author_rotation_proposal()`] of the rotation, and
+    /// carried by the message for the readers to verify. Errors with [`Error::QuorumNotReached`]
+    /// if the approvals do not reach the threshold of the quorum.
+    ///
+    /// # Arguments
+    /// * `new_author`: The [`Identifier`] of the new stream author.
+    /// * `approvals`: The [`QuorumApproval`]s of the rotation by the members of the quorum.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn rotate_author_with_approvals(
+        &mut self,
+        new_author: Identifier,
+        approvals: &[QuorumApproval],
+    ) -> Result<SendResponse<TSR>> {
+        let proposal = self.author_rotation_proposal(&new_author)?;
+        // The proposal is only derived in the streams announced with a quorum
+        if let Some(quorum) = &self.state.quorum {
+            quorum.verify(&proposal, approvals)?;
+        }
+        self.send_author_rotation(new_author, Some(approvals)).await
+    }
+
+    /// Sends an Author Rotation message, carrying the approvals of the quorum if any are given
+    ///
+    /// # Arguments
+    /// * `new_author`: The [`Identifier`] of the new stream author.
+    /// * `approvals`: The [`QuorumApproval`]s of the rotation by the members of the quorum.
+    async fn send_author_rotation(
+        &mut self,
+        new_author: Identifier,
+        approvals: Option<&[QuorumApproval]>,
+    ) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before rotating the author key, the stream must be created",
+        ))?;
+        // Confirm user is the stream author
+        let identifier = self
+            .identifier()
+            .ok_or(Error::NoIdentity("rotate the author key"))?
+            .clone();
+        if self.state.author_identifier.as_ref() != Some(&identifier) {
+            return Err(Error::WrongRole("author", identifier, "rotate the author key"));
+        }
+        let topic = self.state.base_branch.clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        // Link message to channel announcement
+        let link_to = stream_address.relative();
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), rel_address);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .state
+            .spongos_store
+            .get(&link_to)?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let mut rotation = author_rotation::Wrap::new(&mut linked_msg_spongos, self.identity().unwrap(), &new_author);
+        if let Some(approvals) = approvals {
+            rotation = rotation.with_approvals(approvals);
+        }
+        let header = HDF::new(message_types::AUTHOR_ROTATION, user_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(rotation);
+
+        // Wrap message
+        let (transport_msg, _spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap author rotation", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("author rotation", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send author rotation", e))?;
+
+        // If message has been sent successfully, commit message to stores and hand the authorship over
+        // - Author rotation messages are not stored in spongos, no message is linked to them
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        self.rotate_author_identifier(new_author);
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create and send a message of a custom message type, its content wrapped by the
+    /// [`ContentWrap`] implementation of its type. The message is linked to the branch and signed
+    /// like the other messages, and its content is masked under the keyloads of the branch. Readers
+    /// unwrap it once they registered its type with [`User::register_message_type()`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `message_type`: The message type identifier, within [`CUSTOM_MESSAGE_TYPES`].
+    /// * `content`: The content of the message.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_custom_message<Top, C>(
+        &mut self,
+        topic: Top,
+        message_type: u8,
+        mut content: C,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+        C: MaybeSend + MaybeSync,
+        sizeof::Context: ContentSizeof<C>,
+        for<'b> wrap::Context<&'b mut [u8]>: ContentWrap<C>,
+    {
+        // Check conditions
+        if !CUSTOM_MESSAGE_TYPES.contains(&message_type) {
+            return Err(Error::Setup("custom message types must be within CUSTOM_MESSAGE_TYPES"));
+        }
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before sending a custom message, the stream must be created",
+        ))?;
+        // Confirm user has identity
+        let identifier = self
+            .identifier()
+            .ok_or(Error::NoIdentity("send custom message"))?
+            .clone();
+        // Check Topic
+        let topic: Topic = topic.into();
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "send a custom message"));
+        }
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let user_cursor = self.next_cursor(&topic)?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let header = HDF::new(message_type, user_cursor, identifier.clone(), &topic).with_linked_msg_address(link_to);
+        let content = PCF::new_final_frame().with_content(custom_message::Wrap::new(
+            &mut linked_msg_spongos,
+            self.identity().unwrap(),
+            &mut content,
+        ));
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap custom message", e))?;
+
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("custom message", address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response =
+            Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, transport_msg)
+                .await
+                .map_err(|e| Error::Transport(stream_address, "send custom message", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        self.state.cursor_store.insert_cursor(&topic, permission, user_cursor);
+        self.store_spongos(address.relative(), spongos, link_to, user_cursor)?;
+        self.notarize_sent(&topic, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(topic, address.relative());
+        Ok(SendResponse::new(address, send_response))
+    }
+
+    /// Create a new stream, the substream, and anchor it to a message of the stream of the
+    /// [`User`]. The announcement of the substream is signed by the [`Identity`] of the user,
+    /// and a Substream Announcement message linked to the parent message is published in the
+    /// base branch, carrying the address and the hash of the announcement. Readers processing
+    /// it surface a [`MessageContent::SubstreamAnnounced`] to discover the substream from the
+    /// parent message.
+    ///
+    /// The substream is operated by a [`User`] with the same identity, which becomes its author by
+    /// receiving its announcement. Returns the response of the announcement of the substream.
+    ///
+    /// # Arguments
+    /// * `parent`: The [`MsgId`] of the parent message, in the stream of the user.
+    /// * `topic`: The [`Topic`] of the base branch of the substream.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn announce_substream(&mut self, parent: MsgId, topic: impl Into<Topic>) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before announcing a substream, the stream must be created",
+        ))?;
+        // Confirm user has identity
+        let identifier = self
+            .identifier()
+            .ok_or(Error::NoIdentity("announce a substream"))?
+            .clone();
+        // Check Permission in the base branch, where the anchor is published
+        let base_branch = self.state.base_branch.clone();
+        if self.is_branch_closed(&base_branch) {
+            return Err(Error::BranchClosed(base_branch));
+        }
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&base_branch, &identifier)
+            .ok_or(Error::NoCursor(base_branch.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole("ReadWrite", identifier, "announce a substream"));
+        }
+        // Spongos must be copied because wrapping mutates it
+        let mut parent_spongos = self
+            .linked_spongos(parent)
+            .await?
+            .ok_or(Error::MessageMissing(parent, "spongos store"))?;
+
+        // Prepare the announcement of the substream
+        let topic = topic.into();
+        let substream_base_address = self.link_generator.gen_app_addr(&identifier, &topic);
+        let substream_rel_address =
+            self.link_generator
+                .gen_msg_id(substream_base_address, &identifier, &topic, INIT_MESSAGE_NUM);
+        let substream_address = Address::new(substream_base_address, substream_rel_address);
+        let header = HDF::new(message_types::ANNOUNCEMENT, ANN_MESSAGE_NUM, identifier.clone(), &topic);
+        let content = PCF::new_final_frame().with_content(announcement::Wrap::new(self.identity().unwrap(), &topic));
+        let (announcement_msg, _) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap substream announce", e))?;
+        let announcement_hash = notarizer::message_hash(&announcement_msg);
+
+        // Prepare the anchor, linked to the parent message
+        let user_cursor = self.next_cursor(&base_branch)?;
+        let msgid = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &base_branch, user_cursor);
+        let address = Address::new(stream_address.base(), msgid);
+        let header = HDF::new(
+            message_types::SUBSTREAM_ANNOUNCEMENT,
+            user_cursor,
+            identifier.clone(),
+            &base_branch,
+        )
+        .with_linked_msg_address(parent);
+        let content = PCF::new_final_frame().with_content(substream_announcement::Wrap::new(
+            &mut parent_spongos,
+            self.identity().unwrap(),
+            &substream_address,
+            &topic,
+            &announcement_hash,
+        ));
+        let (anchor_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("wrap substream announcement", e))?;
+
+        if Self::address_taken(&mut self.transport, substream_address)
+            .await
+            .map_err(|e| Error::Transport(substream_address, "check that the address is free", e))?
+        {
+            return Err(Error::Setup(
+                "Cannot create a substream, announce address already in use",
+            ));
+        }
+        if Self::address_taken(&mut self.transport, address)
+            .await
+            .map_err(|e| Error::Transport(address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("substream announcement", address));
+        }
+
+        // The substream is announced first, so that it exists once readers discover it
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            substream_address,
+            announcement_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(substream_address, "send substream announce message", e))?;
+        let hash = self.message_hash(&anchor_msg);
+        Self::send_transport_message(&mut self.transport, self.metrics.as_deref(), address, anchor_msg)
+            .await
+            .map_err(|e| Error::Transport(stream_address, "send substream announcement", e))?;
+
+        // If the messages have been sent successfully, commit the anchor to stores
+        self.state
+            .cursor_store
+            .insert_cursor(&base_branch, permission, user_cursor);
+        self.store_spongos(address.relative(), spongos, parent, user_cursor)?;
+        self.notarize_sent(&base_branch, &identifier, user_cursor, hash).await?;
+        // Update branch links
+        self.set_latest_link(base_branch, address.relative());
+        Ok(SendResponse::new(substream_address, send_response))
+    }
+
+    /// Create and send a new Subscription message, awaiting the stream author's acceptance into the
+    /// stream.
+    pub async fn subscribe(&mut self) -> Result<SendResponse<TSR>> {
+        self.send_subscription(None).await
+    }
+
+    /// Create and send a new Subscription message including an [`InviteToken`] of the stream
+    /// author. The author accepts the subscription if the invite is valid, regardless of its
+    /// [`SubscriptionPolicy`].
+    ///
+    /// # Arguments
+    /// * `invite`: The [`InviteToken`] shared by the stream author
+    pub async fn subscribe_with_invite(&mut self, invite: &InviteToken) -> Result<SendResponse<TSR>> {
+        self.send_subscription(Some(invite)).await
+    }
+
+    /// Create and send a new Subscription message, including an [`InviteToken`] if any.
+    ///
+    /// # Arguments
+    /// * `invite`: The [`InviteToken`] shared by the stream author, if any
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    async fn send_subscription(&mut self, invite: Option<&InviteToken>) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before starting a new branch, the stream must be created"))?;
+        // Confirm user has identity
+        let user_id = self.state.user_id.as_ref().ok_or(Error::NoIdentity("subscribe"))?;
+        let identifier = user_id.identifier();
+        // Get base branch topic
+        let base_branch = &self.state.base_branch;
+        // Link message to channel announcement
+        let link_to = stream_address.relative();
+        let rel_address =
+            self.link_generator
+                .gen_msg_id(stream_address.base(), identifier, base_branch, SUB_MESSAGE_NUM);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .state
+            .spongos_store
+            .get(&link_to)?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let unsubscribe_key = StdRng::from_entropy().gen();
+        let author_ke_pk = self
+            .state
+            .author_identifier
+            .as_ref()
+            .unwrap()
+            .ke_pk()
+            .await
+            .map_err(|_| Error::Setup("Failed to generate Public Key from author identifier"))?;
+
+        let mut subscription =
+            subscription::Wrap::new(&mut linked_msg_spongos, unsubscribe_key, user_id, &author_ke_pk);
+        let mut message_type = message_types::SUBSCRIPTION;
+        if let Some(invite) = invite {
+            subscription = subscription.with_invite(invite.as_ref());
+            message_type = message_types::INVITED_SUBSCRIPTION;
+        }
+        let content = PCF::new_final_frame().with_content(subscription);
+        let header =
+            HDF::new(message_type, SUB_MESSAGE_NUM, identifier.clone(), base_branch).with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, _spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("subscribe", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+
+        // Attempt to send message
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("subscribe", message_address));
+        }
+
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(message_address, "send subscribe message", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        // - Subscription messages are not stored in the cursor store
+        // - Subscription messages are never stored in spongos to maintain consistency about the view of the
+        // set of messages of the stream between all the subscribers and across stateless recovers
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Create and send a new Unsubscription message, informing the stream author that this [`User`]
+    /// instance can be removed from the stream.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn unsubscribe(&mut self) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before unsubscribing, the stream must be created"))?;
+        // Confirm user has identity
+        let user_id = self.state.user_id.as_ref().ok_or(Error::NoIdentity("unsubscribe"))?;
+        let identifier = user_id.identifier().clone();
+        // Get base branch topic
+        let base_branch = &self.state.base_branch;
+        // Link message to channel announcement
+        let link_to = self
+            .get_latest_link(base_branch)
+            .ok_or_else(|| Error::TopicNotFound(base_branch.clone()))?;
+
+        // Update own's cursor
+        let new_cursor = self.next_cursor(base_branch)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, base_branch, new_cursor);
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .state
+            .spongos_store
+            .get(&link_to)?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let content = PCF::new_final_frame().with_content(unsubscription::Wrap::new(&mut linked_msg_spongos, user_id));
+        let header = HDF::new(
+            message_types::UNSUBSCRIPTION,
+            new_cursor,
+            identifier.clone(),
+            base_branch,
+        )
+        .with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("unsubscribe", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("unsubscribe", message_address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send unsubscribe message", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        let base_branch = base_branch.clone();
+        let permission = Permissioned::Read(identifier.clone());
+        self.state
+            .cursor_store
+            .insert_cursor(&base_branch, permission, new_cursor);
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.notarize_sent(&base_branch, &identifier, new_cursor, hash).await?;
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Create and send a new KeyUpdate message, replacing the key this [`User`] uses for key
+    /// exchange with a freshly generated one, or with the key of its [`KeyExchange`] if set.
+    /// Further keyloads and selective packets from users that have processed the update are
+    /// encrypted to the new key, so a leaked key exchange key can be retired without creating a
+    /// new identity.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn rotate_exchange_key(&mut self) -> Result<SendResponse<TSR>> {
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before rotating the key exchange key, the stream must be created",
+        ))?;
+        // Confirm user has identity
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("rotate the key exchange key"))?;
+        let identifier = user_id.identifier().clone();
+        // Get base branch topic
+        let base_branch = &self.state.base_branch;
+        // Link message to edge of base branch
+        let link_to = self
+            .get_latest_link(base_branch)
+            .ok_or_else(|| Error::TopicNotFound(base_branch.clone()))?;
+
+        // Update own's cursor
+        let new_cursor = self.next_cursor(base_branch)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, base_branch, new_cursor);
+
+        // Generate the new key, unless it is held by the key exchange of the user
+        let generation = self.state.exchange_key.map_or(0, |key| key.generation()) + 1;
+        let (rotated_key, public_key) = match &self.key_exchange {
+            Some(key_exchange) => (RotatedExchangeKey::External(generation), key_exchange.public_key()),
+            None => {
+                let secret_key = x25519::SecretKey::generate_with(&mut StdRng::from_entropy());
+                (
+                    RotatedExchangeKey::Local(generation, secret_key.to_bytes()),
+                    secret_key.public_key(),
+                )
+            }
+        };
+        let exchange_key = ExchangeKey::new(generation, public_key.to_bytes());
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .state
+            .spongos_store
+            .get(&link_to)?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let content =
+            PCF::new_final_frame().with_content(key_update::Wrap::new(&mut linked_msg_spongos, user_id, exchange_key));
+        let header = HDF::new(message_types::KEY_UPDATE, new_cursor, identifier.clone(), base_branch)
+            .with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("key update", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("key update", message_address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send key update message", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        let base_branch = self.state.base_branch.clone();
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&base_branch, &identifier)
+            .cloned()
+            .unwrap_or_else(|| Permissioned::Read(identifier.clone()));
+        self.state
+            .cursor_store
+            .insert_cursor(&base_branch, permission, new_cursor);
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.state.exchange_key = Some(rotated_key);
+        self.notarize_sent(&base_branch, &identifier, new_cursor, hash).await?;
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Create and send a new Keyload message, updating the read/write permissions for a specified
+    /// branch. All keyload messages are linked to the announcement message to ensure they
+    /// can always be read by a [`User`] that can sequence up to it.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `subscribers`: The updated [`Permissioned`] list for the branch. Unless the user is built
+    ///   with [`UserBuilder::allow_unsubscribed()`], it is restricted to the subscribers of the
+    ///   stream and the participants already known in the branch, failing with
+    ///   [`Error::NotSubscribed`] otherwise.
+    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branch.
+    pub async fn send_keyload<'a, Subscribers, Psks, Top>(
+        &mut self,
+        topic: Top,
+        subscribers: Subscribers,
+        psk_ids: Psks,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
+        Subscribers::IntoIter: ExactSizeIterator,
+        Top: Into<Topic>,
+        Psks: IntoIterator<Item = PskId>,
+    {
+        self.send_keyload_content(topic.into(), subscribers, psk_ids, None, None)
+            .await
+    }
+
+    /// Create and send a new Keyload message, like [`User::send_keyload()`], describing the branch
+    /// with [`BranchMetadata`]. The metadata is masked with the key of the branch, so only the
+    /// recipients of the keyload read it, with
+    /// [`Keyload::metadata()`](crate::api::message::Keyload::metadata).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `subscribers`: The updated [`Permissioned`] list for the branch.
+    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branch.
+    /// * `metadata`: The [`BranchMetadata`] describing the branch.
+    pub async fn send_keyload_with_metadata<'a, Subscribers, Psks, Top>(
+        &mut self,
+        topic: Top,
+        subscribers: Subscribers,
+        psk_ids: Psks,
+        metadata: &BranchMetadata,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
+        Subscribers::IntoIter: ExactSizeIterator,
+        Top: Into<Topic>,
+        Psks: IntoIterator<Item = PskId>,
+    {
+        self.send_keyload_content(topic.into(), subscribers, psk_ids, Some(metadata), None)
+            .await
+    }
+
+    /// Create and send a new Keyload message, like [`User::send_keyload()`], granting access to the
+    /// branch until an expiry. The expiry is masked with the key of the branch.
+    ///
+    /// While the access expires, the publishers of the branch stamp their signed packets with the
+    /// time they publish them at. The recipients of the keyload compare that time, signed along
+    /// the packet, with the expiry, so that the packets published before the expiry stay readable
+    /// whenever they are read. The packets published after the expiry, the packets without a
+    /// publish time and the packets linked to them are read as [`MessageContent::AccessExpired`]:
+    /// their payloads are left masked. The packets linked to a fresh keyload are read again. The
+    /// admins of the branch are not subject to the expiry, and neither are the other packet types.
+    ///
+    /// Without the `std` feature there is no clock to read: the packets are not stamped, and are
+    /// therefore withheld from the recipients of an expiring keyload.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `subscribers`: The updated [`Permissioned`] list for the branch.
+    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branch.
+    /// * `expires_at`: The Unix timestamp, in seconds, at which the access expires.
+    pub async fn send_keyload_with_expiry<'a, Subscribers, Psks, Top>(
+        &mut self,
+        topic: Top,
+        subscribers: Subscribers,
+        psk_ids: Psks,
+        expires_at: u64,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
+        Subscribers::IntoIter: ExactSizeIterator,
+        Top: Into<Topic>,
+        Psks: IntoIterator<Item = PskId>,
+    {
+        self.send_keyload_content(topic.into(), subscribers, psk_ids, None, Some(expires_at))
+            .await
+    }
+
+    /// Create and send a new Keyload message, like [`User::send_keyload()`], both describing the
+    /// branch, like [`User::send_keyload_with_metadata()`], and granting access to the branch until
+    /// an expiry, like [`User::send_keyload_with_expiry()`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `subscribers`: The updated [`Permissioned`] list for the branch.
+    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branch.
+    /// * `metadata`: The [`BranchMetadata`] describing the branch.
+    /// * `expires_at`: The Unix timestamp, in seconds, at which the access expires.
+    pub async fn send_keyload_with_metadata_and_expiry<'a, Subscribers, Psks, Top>(
+        &mut self,
+        topic: Top,
+        subscribers: Subscribers,
+        psk_ids: Psks,
+        metadata: &BranchMetadata,
+        expires_at: u64,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
+        Subscribers::IntoIter: ExactSizeIterator,
+        Top: Into<Topic>,
+        Psks: IntoIterator<Item = PskId>,
+    {
+        self.send_keyload_content(topic.into(), subscribers, psk_ids, Some(metadata), Some(expires_at))
+            .await
+    }
+
+    /// Create and send a new Keyload message, optionally describing the branch and granting access
+    /// until an expiry.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `subscribers`: The updated [`Permissioned`] list for the branch.
+    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branch.
+    /// * `metadata`: The [`BranchMetadata`] describing the branch, if any.
+    /// * `expires_at`: The Unix timestamp, in seconds, at which the access expires, if any.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    async fn send_keyload_content<'a, Subscribers, Psks>(
+        &mut self,
+        topic: Topic,
+        subscribers: Subscribers,
+        psk_ids: Psks,
+        metadata: Option<&BranchMetadata>,
+        expires_at: Option<u64>,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
+        Subscribers::IntoIter: ExactSizeIterator,
+        Psks: IntoIterator<Item = PskId>,
+    {
+        // Check conditions
+        let stream_address = self
+            .stream_address()
+            .ok_or(Error::Setup("before sending a keyload, the stream must be created"))?;
+        // Confirm user has identity
+        let user_id = self.state.user_id.as_ref().ok_or(Error::NoIdentity("send keyload"))?;
+        let identifier = user_id.identifier().clone();
+        // Check Topic
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self.permission(&topic).ok_or(Error::NoCursor(topic.clone()))?;
+        if !permission.is_admin() {
+            return Err(Error::WrongRole("Admin", identifier, "send a keyload"));
+        }
+        // Check Recipients
+        if !self.allow_unsubscribed {
+            let unknown = subscribers
+                .clone()
+                .into_iter()
+                .map(|subscriber| *subscriber.identifier())
+                .find(|recipient| !self.is_known_recipient(&topic, recipient));
+            if let Some(recipient) = unknown {
+                return Err(Error::NotSubscribed(recipient.clone()));
+            }
+        }
+
+        // Link message to edge of branch
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+        // Update own's cursor
+        let new_cursor = self.next_cursor(&topic)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, new_cursor);
+
+        // Prepare HDF and PCF
+        // All Keyload messages will attach to stream Announcement message spongos
+        let mut announcement_msg_spongos = self
+            .state
+            .spongos_store
+            .get(&stream_address.relative())?
+            .ok_or(Error::Setup("a user must keep a stream announcement spongos in store"))?;
+
+        let mut rng = StdRng::from_entropy();
+        let encryption_key = rng.gen();
+        let nonce = rng.gen();
+        let psk_ids_with_psks = psk_ids
+            .into_iter()
+            .map(|pskid| Ok((pskid, self.state.psk_store.get(&pskid).ok_or(Error::UnknownPsk(pskid))?)))
+            .collect::<Result<Vec<(_, _)>>>()?; // collect to handle possible error
+        let mut keyload = keyload::Wrap::new(
+            &mut announcement_msg_spongos,
+            subscribers.clone().into_iter().collect::<Vec<_>>(),
+            &psk_ids_with_psks,
+            encryption_key,
+            nonce,
+            user_id,
+            self.state.forward_secrecy,
+        )
+        .with_exchange_keys(&self.state.exchange_keys);
+        if let Some(metadata) = metadata {
+            keyload = keyload.with_metadata(metadata);
+        }
+        if let Some(expires_at) = expires_at {
+            keyload = keyload.with_expiry(expires_at);
+        }
+        let content = PCF::new_final_frame().with_content(keyload);
+        let header =
+            HDF::new(message_types::KEYLOAD, new_cursor, identifier.clone(), &topic).with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, mut spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("send keyload", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("keyload", message_address));
+        }
+
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send keyload message", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        self.state
+            .keyload_permissions
+            .insert(topic.clone(), subscribers.clone().into_iter().map(Into::into).collect());
+        // The subscribers accepted with an invite to the branch are granted access by the keyload
+        if let Some(invited) = self.state.invite_grants.get_mut(&topic) {
+            for subscriber in subscribers.clone() {
+                invited.remove(*subscriber.identifier());
+            }
+            if invited.is_empty() {
+                self.state.invite_grants.remove(&topic);
+            }
+        }
+        // The publishers of the branch, the sender included, stamp their packets while the access
+        // granted by the keyload expires
+        match expires_at {
+            Some(expires_at) => self.state.access_expirations.insert(topic.clone(), expires_at),
+            None => self.state.access_expirations.remove(&topic),
+        };
+        let publishers = subscribers
+            .clone()
+            .into_iter()
+            .filter(|subscriber| !subscriber.is_readonly())
+            .map(|subscriber| *subscriber.identifier());
+        let publishers: Vec<&Identifier> = core::iter::once(&identifier).chain(publishers).collect();
+        ratchet::apply_keyload(
+            &mut self.state.ratchets,
+            &topic,
+            self.state.forward_secrecy,
+            &mut spongos,
+            publishers,
+        );
+        for subscriber in subscribers {
+            if self.should_store_cursor(&topic, subscriber) {
+                self.state
+                    .cursor_store
+                    .insert_cursor(&topic, subscriber.into(), INIT_MESSAGE_NUM);
+            }
+        }
+        self.state
+            .cursor_store
+            .insert_cursor(&topic, Permissioned::Admin(identifier.clone()), new_cursor);
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.notarize_sent(&topic, &identifier, new_cursor, hash).await?;
+        // Update Branch Links
+        self.set_latest_link(topic, message_address.relative());
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Create and send a new Keyload message to every open branch falling under a hierarchical
+    /// prefix (see [`Topic::has_prefix()`]) that the [`User`] administers, granting the same
+    /// permissions on the whole hierarchy. The other branches under the prefix are left untouched.
+    ///
+    /// # Arguments
+    /// * `prefix`: The [`Topic`] prefix of the branches the permissions will be updated for.
+    /// * `subscribers`: The updated [`Permissioned`] list for the branches.
+    /// * `psk_ids`: A list of [Psk Id's](`PskId`) with read access for the branches.
+    pub async fn send_keyload_for_prefix<'a, Subscribers, Psks, Top>(
+        &mut self,
+        prefix: Top,
+        subscribers: Subscribers,
+        psk_ids: Psks,
+    ) -> Result<Vec<SendResponse<TSR>>>
+    where
+        Subscribers: IntoIterator<Item = Permissioned<&'a Identifier>> + Clone,
+        Subscribers::IntoIter: ExactSizeIterator,
+        Top: Into<Topic>,
+        Psks: IntoIterator<Item = PskId> + Clone,
+    {
+        let prefix = prefix.into();
+        // Alas, must collect to release the &self immutable borrow
+        let topics: Vec<Topic> = self
+            .topics_with_prefix(&prefix)
+            .filter(|topic| !self.is_branch_closed(topic))
+            .filter(|topic| self.permission(topic).map_or(false, Permissioned::is_admin))
+            .cloned()
+            .collect();
+        if topics.is_empty() {
+            return Err(Error::TopicNotFound(prefix));
+        }
+
+        let mut responses = Vec::with_capacity(topics.len());
+        for topic in topics {
+            responses.push(self.send_keyload(topic, subscribers.clone(), psk_ids.clone()).await?);
+        }
+        Ok(responses)
+    }
+
+    /// Create and send a new Keyload message granting read access to a branch to the holders of
+    /// [Pre-Shared Keys](`Psk`) only. Subscribers lose their permissions on the branch, apart from
+    /// the [`User`] sending the keyload.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `psk_ids`: The [identifiers](`PskId`) of the keys granted read access, which must be
+    ///   stored in state (see [`User::add_psk()`]).
+    pub async fn send_keyload_for_psks<Top, Psks>(&mut self, topic: Top, psk_ids: Psks) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+        Psks: IntoIterator<Item = PskId>,
+    {
+        self.send_keyload(topic, core::iter::empty::<Permissioned<&Identifier>>(), psk_ids)
+            .await
+    }
+
+    /// Create and send a new Keyload message granting read access to a branch to the nodes of a
+    /// [`PskTree`], like the devices of a fleet provisioned with [`PskTree::derive()`]. The
+    /// [`Psk`]s of the nodes are derived and stored in state, so that they are kept in the backups
+    /// of the user and addressed again by [`User::send_keyload_for_all()`]. The [`Psk`]s of the
+    /// revoked nodes are removed from state instead, and the revoked nodes lose read access to the
+    /// branch.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    /// * `tree`: The [`PskTree`] the [`Psk`]s are derived from.
+    /// * `paths`: The slash separated paths of the nodes in the tree.
+    /// * `revoked`: The slash separated paths of the revoked nodes and subtrees.
+    pub async fn send_keyload_for_psk_tree<'p, Top, Paths>(
+        &mut self,
+        topic: Top,
+        tree: &PskTree,
+        paths: Paths,
+        revoked: &[&str],
+    ) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+        Paths: IntoIterator<Item = &'p str>,
+    {
+        let mut psk_ids = Vec::new();
+        for path in paths {
+            let psk = tree.derive(path);
+            if PskTree::is_revoked(path, revoked) {
+                self.remove_psk(psk.to_pskid());
+            } else {
+                self.add_psk(psk);
+                psk_ids.push(psk.to_pskid());
+            }
+        }
+        self.send_keyload_for_psks(topic, psk_ids).await
+    }
+
+    /// Create and send a new Keyload message for all participants, updating the specified branch to
+    /// grant all known subscribers read permissions.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    pub async fn send_keyload_for_all<Top>(&mut self, topic: Top) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic> + Clone,
+    {
+        let topic = topic.into();
+        let permission = self.permission(&topic).ok_or(Error::NoCursor(topic.clone()))?;
+        if !permission.is_admin() {
+            return Err(Error::WrongRole(
+                "Admin",
+                permission.identifier().clone(),
+                "send a keyload",
+            ));
+        }
+        let psks: Vec<PskId> = self.state.psk_store.keys().copied().collect();
+        let subscribers: Vec<Permissioned<Identifier>> = self
+            .subscribers()
+            .map(|s| {
+                if s == permission.identifier() {
+                    Permissioned::Admin(s.clone())
+                } else {
+                    Permissioned::Read(s.clone())
+                }
+            })
+            .chain(self.registered_readers().map(|r| Permissioned::Read(r.clone())))
+            .collect();
+        self.send_keyload(
+            topic,
+            // Alas, must collect to release the &self immutable borrow
+            subscribers.iter().map(Permissioned::as_ref),
+            psks,
+        )
+        .await
+    }
+
+    /// Grants the subscribers accepted with an [`Invite`] access to the branch of the invite. For
+    /// each branch with invited subscribers not granted access yet, a keyload is sent with the
+    /// permissions of the last keyload of the branch, the invited subscribers given read access,
+    /// and the [`Psk`]s of the user. Invited subscribers already granted access by a keyload are
+    /// not granted access again.
+    ///
+    /// Returns the [`SendResponse`] of each keyload sent.
+    pub async fn grant_invites(&mut self) -> Result<Vec<SendResponse<TSR>>> {
+        let topics: Vec<Topic> = self.state.invite_grants.keys().cloned().collect();
+        let mut sent = Vec::with_capacity(topics.len());
+        for topic in topics {
+            let invited = self.state.invite_grants.get(&topic).cloned().unwrap_or_default();
+            let mut permissions = self.state.keyload_permissions.get(&topic).cloned().unwrap_or_default();
+            let granted: Vec<Permissioned<Identifier>> = invited
+                .into_iter()
+                .filter(|subscriber| {
+                    permissions
+                        .iter()
+                        .all(|permission| permission.identifier() != subscriber)
+                })
+                .map(Permissioned::Read)
+                .collect();
+            permissions.extend(granted);
+            let psks: Vec<PskId> = self.state.psk_store.keys().copied().collect();
+            let send_response = self
+                .send_keyload(topic, permissions.iter().map(Permissioned::as_ref), psks)
+                .await?;
+            sent.push(send_response);
+        }
+        Ok(sent)
+    }
+
+    /// Create and send a new Keyload message for all participants, updating the specified branch to
+    /// grant all known subscribers read and write permissions.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the permissions will be updated for.
+    pub async fn send_keyload_for_all_rw<Top>(&mut self, topic: Top) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic> + Clone,
+    {
+        let topic = topic.into();
+        let permission = self.permission(&topic).ok_or(Error::NoCursor(topic.clone()))?;
+        if !permission.is_admin() {
+            return Err(Error::WrongRole(
+                "Admin",
+                permission.identifier().clone(),
+                "send a keyload",
+            ));
+        }
+        let psks: Vec<PskId> = self.state.psk_store.keys().copied().collect();
+        let subscribers: Vec<Permissioned<Identifier>> = self
+            .subscribers()
+            .map(|s| {
+                if s == permission.identifier() {
+                    Permissioned::Admin(s.clone())
+                } else {
+                    Permissioned::ReadWrite(s.clone(), PermissionDuration::Perpetual)
+                }
+            })
+            // Readers registered out of band are never granted write access
+            .chain(self.registered_readers().map(|r| Permissioned::Read(r.clone())))
+            .collect();
+        self.send_keyload(
+            topic,
+            // Alas, must collect to release the &self immutable borrow
+            subscribers.iter().map(Permissioned::as_ref),
+            psks,
+        )
+        .await
+    }
+
+    /// Create a new [`MessageBuilder`] instance.
+    pub fn message<P: Default>(&mut self) -> MessageBuilder<P, T> {
+        MessageBuilder::new(self)
+    }
+
+    /// Create and send a new Signed Packet message to the specified branch. The message will
+    /// contain a masked and an unmasked payload. The message will be signed by the [`User`]
+    /// [`Identity`] keys.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    pub async fn send_signed_packet<P, M, Top>(
+        &mut self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        self.send_signed_packet_content(
+            topic.into(),
+            public_payload.as_ref(),
+            masked_payload.as_ref(),
+            false,
+            &[],
+        )
+        .await
+    }
+
+    /// Create and send a new Signed Packet message to the specified branch, carrying in addition a
+    /// [`DetachedSignature`](crate::DetachedSignature) of the masked payload. Readers get the
+    /// signature with [`Message::detached_signature()`], so that third parties can verify the
+    /// authorship of the masked payload with [`verify_detached()`](crate::verify_detached),
+    /// without the state of the channel.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message, covered by the detached signature.
+    pub async fn send_detached_signed_packet<P, M, Top>(
+        &mut self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        self.send_signed_packet_content(
+            topic.into(),
+            public_payload.as_ref(),
+            masked_payload.as_ref(),
+            true,
+            &[],
+        )
+        .await
+    }
+
+    /// Create and send a new Signed Packet message to the specified branch, citing messages,
+    /// possibly of other channels, through [`Reference`]s masked and signed along the payload.
+    /// Readers get the references with [`Message::references()`], and check that the cited messages
+    /// were not altered with [`User::verify_reference()`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    /// * `references`: The [`Reference`]s to the cited messages, created with [`User::cite()`].
+    pub async fn send_citing_packet<P, M, Top>(
+        &mut self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+        references: &[Reference],
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        self.send_signed_packet_content(
+            topic.into(),
+            public_payload.as_ref(),
+            masked_payload.as_ref(),
+            false,
+            references,
+        )
+        .await
+    }
+
+    /// Sends the packets queued with [`User::queue_packet()`], by decreasing priority. Each packet
+    /// is linked to the packet sent before it in its branch, so that the cursors of the user and
+    /// the links of the branches follow the order the packets are sent in.
+    ///
+    /// Flushing stops at the first packet the transport fails to send, typically because it is
+    /// unreachable: that packet and the following ones stay queued for the next flush. A packet
+    /// failing for another reason, like a packet queued to a closed branch or larger than the
+    /// transport accepts, is removed from the queue and its error returned, the packets sent before
+    /// it being removed as well.
+    ///
+    /// Returns the [`SendResponse`] of each packet sent, in the order they were sent in.
+    pub async fn flush(&mut self) -> Result<Vec<SendResponse<TSR>>> {
+        let mut sent = Vec::new();
+        while let Some(index) = self.next_queued_packet() {
+            // The packet is taken out of the queue while it is sent, as sending borrows the user
+            let packet = self.state.send_queue.remove(index);
+            let send_response = self
+                .send_signed_packet_content(
+                    packet.topic.clone(),
+                    &packet.public_payload,
+                    &packet.masked_payload,
+                    false,
+                    &[],
+                )
+                .await;
+            match send_response {
+                Ok(send_response) => sent.push(send_response),
+                Err(e @ Error::Transport(_, _, LetsError::MessageTooLarge(..))) => return Err(e),
+                Err(Error::Transport(..)) => {
+                    self.state.send_queue.insert(index, packet);
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Returns the index of the next queued packet to send: the first queued packet of the highest
+    /// priority, if any
+    fn next_queued_packet(&self) -> Option<usize> {
+        self.state
+            .send_queue
+            .iter()
+            .enumerate()
+            .max_by(|(i, a), (j, b)| a.priority.cmp(&b.priority).then(j.cmp(i)))
+            .map(|(index, _)| index)
+    }
+
+    /// Create and send a new Signed Packet message, including a detached signature of the masked
+    /// payload if requested, or the references to the cited messages otherwise.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    /// * `detached`: Whether the packet carries a detached signature of the masked payload.
+    /// * `references`: The [`Reference`]s to the messages cited by the packet, if not detached.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    async fn send_signed_packet_content(
+        &mut self,
+        topic: Topic,
+        public_payload: &[u8],
+        masked_payload: &[u8],
+        detached: bool,
+        references: &[Reference],
+    ) -> Result<SendResponse<TSR>> {
+        let topic = self.rotated_topic(topic).await?;
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before sending a signed packet, the stream must be created",
+        ))?;
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send signed packet"))?;
+        let identifier = user_id.identifier().clone();
+        let detached_signature = match detached {
+            true => Some(detached::sign_detached(user_id, masked_payload)?),
+            false => None,
+        };
+        // The detached signature covers the payload restored by the readers, not the transformed one
+        let encoded_payload = self.encode_payload(masked_payload)?;
+        let masked_payload = encoded_payload.as_deref().unwrap_or(masked_payload);
+        // Check Topic
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole(
+                "ReadWrite",
+                permission.identifier().clone(),
+                "send a signed packet",
+            ));
+        }
+        // Link message to latest message in branch
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+        // Update own's cursor
+        let new_cursor = self.next_cursor(&topic)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, new_cursor);
+
+        // Advance own ratchet on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &identifier, new_cursor)?;
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        // The lookup of the linked state may update the store, so the identity is borrowed again
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send signed packet"))?;
+
+        let repeated = self.repeated_payloads(&topic, &identifier, public_payload, masked_payload);
+        // Packets are stamped with their publish time while the access to the branch expires, so
+        // that readers tell the packets published before the expiry from the ones published after
+        let published_at = self
+            .state
+            .access_expirations
+            .contains_key(&topic)
+            .then(clock::unix_time)
+            .flatten();
+        let mut signed_packet =
+            signed_packet::Wrap::new(&mut linked_msg_spongos, &(*user_id), public_payload, masked_payload)
+                .with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key))
+                .with_published_at(published_at);
+        if let Some(detached_signature) = &detached_signature {
+            signed_packet = signed_packet.with_detached_signature(detached_signature.as_bytes());
+        }
+        if !references.is_empty() {
+            signed_packet = signed_packet.with_references(references);
+        }
+        // Only the payloads of plain packets are remembered by the readers and repeated
+        let repeated = repeated.filter(|_| detached_signature.is_none() && references.is_empty());
+        if let Some(repeated) = repeated {
+            signed_packet = signed_packet.with_repeated_payloads(repeated);
+        }
+        let is_plain = detached_signature.is_none() && references.is_empty() && repeated.is_none();
+        let content = PCF::new_final_frame().with_content(signed_packet);
+        let header = HDF::new(message_types::SIGNED_PACKET, new_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, mut spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("send signed packet", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("signed packet", message_address));
+        }
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send signed packet", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        self.state.cursor_store.insert_cursor(&topic, permission, new_cursor);
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &identifier, ratchet);
+            spongos.ratchet();
+        }
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.mark_recomputable(&topic, rel_address);
+        self.notarize_sent(&topic, &identifier, new_cursor, hash).await?;
+        if is_plain {
+            // Readers remember the payloads of every plain signed packet. Own payloads are only
+            // remembered while deduplicating, and forgotten otherwise so that a later repeated
+            // packet never refers to a packet readers no longer remember
+            let last_payloads = self
+                .payload_deduplication
+                .then(|| LastPayloads::new(rel_address, public_payload.to_vec(), masked_payload.to_vec()));
+            self.store_last_payloads(&topic, &identifier, last_payloads);
+        }
+        // Update Branch Links
+        self.set_latest_link(topic, message_address.relative());
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Returns the reference to the packet that first carried the payloads, if the payloads are
+    /// deduplicated and repeat the ones of the last signed packet of the publisher on the branch
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the packet
+    /// * `publisher`: The [`Identifier`] of the publisher of the packet
+    /// * `public_payload`: The payload of the packet that is not masked
+    /// * `masked_payload`: The payload of the packet that is masked
+    fn repeated_payloads(
+        &self,
+        topic: &Topic,
+        publisher: &Identifier,
+        public_payload: &[u8],
+        masked_payload: &[u8],
+    ) -> Option<RepeatedPayloads> {
+        if !self.payload_deduplication {
+            return None;
+        }
+        self.state
+            .last_payloads
+            .get(topic)
+            .and_then(|last_payloads| last_payloads.get(publisher))
+            .filter(|last| last.public_payload == public_payload && last.masked_payload == masked_payload)
+            .map(LastPayloads::repeated)
+    }
+
+    /// Stores the payloads of the last signed packet of a publisher on a branch, forgetting the
+    /// previous ones
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch of the packet
+    /// * `publisher`: The [`Identifier`] of the publisher of the packet
+    /// * `last_payloads`: The [`LastPayloads`] of the packet, None to only forget the previous ones
+    fn store_last_payloads(&mut self, topic: &Topic, publisher: &Identifier, last_payloads: Option<LastPayloads>) {
+        match last_payloads {
+            Some(last_payloads) => {
+                self.state
+                    .last_payloads
+                    .entry(topic.clone())
+                    .or_default()
+                    .insert(publisher.clone(), last_payloads);
+            }
+            None => {
+                if let Some(branch_payloads) = self.state.last_payloads.get_mut(topic) {
+                    branch_payloads.remove(publisher);
+                    if branch_payloads.is_empty() {
+                        self.state.last_payloads.remove(topic);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the [`Topic`] of the branch the packets sent to a branch are published in: the
+    /// current epoch branch if the branch is rotated, starting a new epoch first if the current one
+    /// is over, or the branch itself otherwise. A new epoch branch is started from the previous
+    /// epoch branch, and gets a keyload granting the permissions of the last keyload of the
+    /// previous epoch to its recipients and to the [`Psk`]s of the user, if there was any.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch the packet is sent to
+    async fn rotated_topic(&mut self, topic: Topic) -> Result<Topic> {
+        let period = match self.branch_rotations.get(&topic) {
+            Some(period) => *period,
+            None => return Ok(topic),
+        };
+        let now = clock::unix_time();
+        let (previous, index) = match self.state.epochs.get(&topic) {
+            Some(epoch) => {
+                let cursor = self.cursor(&epoch.topic).unwrap_or_default();
+                if !epoch.is_over(period, now, cursor) {
+                    return Ok(epoch.topic.clone());
+                }
+                (epoch.topic.clone(), epoch.index + 1)
+            }
+            None => (topic.clone(), 0),
+        };
+        let label = branch_rotation::epoch_label(period, now, index);
+        let mut duplicates = 0;
+        let mut epoch_topic = branch_rotation::epoch_topic(&topic, &label, duplicates);
+        while self.state.topics.contains(&epoch_topic) {
+            duplicates += 1;
+            epoch_topic = branch_rotation::epoch_topic(&topic, &label, duplicates);
+        }
+
+        self.new_branch(previous.clone(), epoch_topic.clone()).await?;
+        if let Some(permissions) = self.state.keyload_permissions.get(&previous).cloned() {
+            let psks: Vec<PskId> = self.state.psk_store.keys().copied().collect();
+            self.send_keyload(epoch_topic.clone(), permissions.iter().map(Permissioned::as_ref), psks)
+                .await?;
+        }
+        let epoch = Epoch {
+            topic: epoch_topic.clone(),
+            index,
+            started_at: now,
+            first_cursor: self.cursor(&epoch_topic).unwrap_or_default(),
+        };
+        self.state.epochs.insert(topic, epoch);
+        Ok(epoch_topic)
+    }
+
+    /// Serialize a value as `JSON` and send it as the masked payload of a new Signed Packet
+    /// message to the specified branch. The payload is tagged with its
+    /// [`ContentType`](crate::ContentType), so that readers can decode it with
+    /// [`MessageContent::json()`](crate::MessageContent).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `value`: The value to be serialized.
+    #[cfg(feature = "json")]
+    pub async fn send_json<Top, S>(&mut self, topic: Top, value: &S) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+        S: Serialize + ?Sized,
+    {
+        let payload = payload::encode_json(value)?;
+        self.send_signed_packet(topic, b"", payload).await
+    }
+
+    /// Serialize a value as `CBOR` and send it as the masked payload of a new Signed Packet
+    /// message to the specified branch. The payload is tagged with its
+    /// [`ContentType`](crate::ContentType), so that readers can decode it with
+    /// [`MessageContent::cbor()`](crate::MessageContent).
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `value`: The value to be serialized.
+    #[cfg(feature = "cbor")]
+    pub async fn send_cbor<Top, S>(&mut self, topic: Top, value: &S) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+        S: Serialize + ?Sized,
+    {
+        let payload = payload::encode_cbor(value)?;
+        self.send_signed_packet(topic, b"", payload).await
+    }
+
+    /// Create and send a new Batch Packet message to the specified branch, carrying a batch of
+    /// records. The records are published in a single message signed by the [`User`] [`Identity`]
+    /// keys, so readers either receive all of them, as a [`MessageContent`]`::BatchPacket`, or none
+    /// of them. The whole batch must fit in a message of the [`Transport`].
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `payloads`: The public and masked payloads of each record of the batch.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_batch<Top, I, P, M>(&mut self, topic: Top, payloads: I) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+        I: IntoIterator<Item = (P, M)>,
+        P: Into<Vec<u8>>,
+        M: Into<Vec<u8>>,
+    {
+        let records: Vec<BatchRecord> = payloads
+            .into_iter()
+            .map(|(public_payload, masked_payload)| BatchRecord::new(public_payload, masked_payload))
+            .collect();
+        if records.is_empty() {
+            return Err(Error::PayloadEmpty);
+        }
+        let topic = self.rotated_topic(topic.into()).await?;
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before sending a batch packet, the stream must be created",
+        ))?;
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send batch packet"))?;
+        let identifier = user_id.identifier().clone();
+        // Check Topic
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole(
+                "ReadWrite",
+                permission.identifier().clone(),
+                "send a batch packet",
+            ));
+        }
+        // Link message to latest message in branch
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+        // Update own's cursor
+        let new_cursor = self.next_cursor(&topic)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, new_cursor);
+
+        // Advance own ratchet on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &identifier, new_cursor)?;
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        // The lookup of the linked state may update the store, so the identity is borrowed again
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send batch packet"))?;
+        let content = PCF::new_final_frame().with_content(
+            batch_packet::Wrap::new(&mut linked_msg_spongos, &(*user_id), &records)
+                .with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key)),
+        );
+        let header = HDF::new(message_types::BATCH_PACKET, new_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, mut spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("send batch packet", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("batch packet", message_address));
+        }
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send batch packet", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        self.state.cursor_store.insert_cursor(&topic, permission, new_cursor);
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &identifier, ratchet);
+            spongos.ratchet();
+        }
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.mark_recomputable(&topic, rel_address);
+        self.notarize_sent(&topic, &identifier, new_cursor, hash).await?;
+        // Update Branch Links
+        self.set_latest_link(topic, message_address.relative());
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Create and send a new Selective Packet message to the specified branch. Each field of the
+    /// message is encrypted with its own key, shared only with the recipients of the field, so that
+    /// a single packet can disclose different data to different readers. Readers that are not a
+    /// recipient of a field still receive the packet and verify its signature, but the field is
+    /// returned to them as `None`. The message will be signed by the [`User`] [`Identity`] keys.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `fields`: The fields of the message, each with the [`Identifier`] of its recipients.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_selective_packet<Top>(
+        &mut self,
+        topic: Top,
+        fields: Vec<(Vec<Identifier>, Vec<u8>)>,
+    ) -> Result<SendResponse<TSR>>
+    where
+        Top: Into<Topic>,
+    {
+        let topic = self.rotated_topic(topic.into()).await?;
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before sending a selective packet, the stream must be created",
+        ))?;
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send selective packet"))?;
+        let identifier = user_id.identifier().clone();
+        // Check Topic
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole(
+                "ReadWrite",
+                permission.identifier().clone(),
+                "send a selective packet",
+            ));
+        }
+        // Link message to latest message in branch
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+        // Update own's cursor
+        let new_cursor = self.next_cursor(&topic)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, new_cursor);
+
+        // Advance own ratchet on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &identifier, new_cursor)?;
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        // The lookup of the linked state may update the store, so the identity is borrowed again
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send selective packet"))?;
+
+        // Every field is encrypted with its own key
+        let mut rng = StdRng::from_entropy();
+        let keys: Vec<[u8; selective_packet::KEY_SIZE]> = fields.iter().map(|_| rng.gen()).collect();
+        let content = PCF::new_final_frame().with_content(
+            selective_packet::Wrap::new(&mut linked_msg_spongos, &(*user_id), &fields, &keys)
+                .with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key))
+                .with_exchange_keys(&self.state.exchange_keys),
+        );
+        let header = HDF::new(message_types::SELECTIVE_PACKET, new_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, mut spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("send selective packet", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("selective packet", message_address));
+        }
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send selective packet", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        self.state.cursor_store.insert_cursor(&topic, permission, new_cursor);
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &identifier, ratchet);
+            spongos.ratchet();
+        }
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.notarize_sent(&topic, &identifier, new_cursor, hash).await?;
+        // Update Branch Links
+        self.set_latest_link(topic, message_address.relative());
+        Ok(SendResponse::new(message_address, send_response))
+    }
+
+    /// Create and send a new Tagged Packet message to the specified branch. The message will
+    /// contain a masked and an unmasked payload.
+    ///
+    /// # Arguments
+    /// * `topic`: The [`Topic`] of the branch to send the message to.
+    /// * `public_payload`: The unmasked payload of the message.
+    /// * `masked_payload`: The masked payload of the message.
+    #[cfg_attr(feature = "trace", tracing::instrument(skip_all))]
+    pub async fn send_tagged_packet<P, M, Top>(
+        &mut self,
+        topic: Top,
+        public_payload: P,
+        masked_payload: M,
+    ) -> Result<SendResponse<TSR>>
+    where
+        M: AsRef<[u8]>,
+        P: AsRef<[u8]>,
+        Top: Into<Topic>,
+    {
+        let topic = self.rotated_topic(topic.into()).await?;
+        // Check conditions
+        let stream_address = self.stream_address().ok_or(Error::Setup(
+            "before sending a tagged packet, the stream must be created",
+        ))?;
+        if self.is_strict() {
+            return Err(Error::StrictChannel("unsigned packets", stream_address));
+        }
+        let encoded_payload = self.encode_payload(masked_payload.as_ref())?;
+        let masked_payload = encoded_payload.as_deref().unwrap_or(masked_payload.as_ref());
+        let user_id = self
+            .state
+            .user_id
+            .as_ref()
+            .ok_or(Error::NoIdentity("send tagged packet"))?;
+        let identifier = user_id.identifier().clone();
+        // Check Topic
+        if self.is_branch_closed(&topic) {
+            return Err(Error::BranchClosed(topic));
+        }
+        // Check Permission
+        let permission = self
+            .state
+            .cursor_store
+            .get_permission(&topic, &identifier)
+            .ok_or(Error::NoCursor(topic.clone()))?
+            .clone();
+        if permission.is_readonly() {
+            return Err(Error::WrongRole(
+                "ReadWrite",
+                permission.identifier().clone(),
+                "send a tagged packet",
+            ));
+        }
+        // Link message to latest message in branch
+        let link_to = self
+            .get_latest_link(&topic)
+            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
+
+        // Update own's cursor
+        let new_cursor = self.next_cursor(&topic)?;
+        let rel_address = self
+            .link_generator
+            .gen_msg_id(stream_address.base(), &identifier, &topic, new_cursor);
+
+        // Advance own ratchet on forward secrecy branches
+        let ratchet = self.advance_ratchet(&topic, &identifier, new_cursor)?;
+
+        // Prepare HDF and PCF
+        // Spongos must be copied because wrapping mutates it
+        let mut linked_msg_spongos = self
+            .linked_spongos(link_to)
+            .await?
+            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        let content = PCF::new_final_frame().with_content(
+            tagged_packet::Wrap::new(&mut linked_msg_spongos, public_payload.as_ref(), masked_payload)
+                .with_ratchet_key(ratchet.as_ref().map(|(_, key)| *key)),
+        );
+        let header = HDF::new(message_types::TAGGED_PACKET, new_cursor, identifier.clone(), &topic)
+            .with_linked_msg_address(link_to);
+
+        // Wrap message
+        let (transport_msg, mut spongos) = LetsMessage::new(header, content)
+            .wrap()
+            .await
+            .map_err(|e| Error::Wrapped("send tagged packet", e))?;
+
+        // Attempt to send message
+        let message_address = Address::new(stream_address.base(), rel_address);
+        if Self::address_taken(&mut self.transport, message_address)
+            .await
+            .map_err(|e| Error::Transport(message_address, "check that the address is free", e))?
+        {
+            return Err(Error::AddressUsed("tagged packet", message_address));
+        }
+        let hash = self.message_hash(&transport_msg);
+        let send_response = Self::send_transport_message(
+            &mut self.transport,
+            self.metrics.as_deref(),
+            message_address,
+            transport_msg,
+        )
+        .await
+        .map_err(|e| Error::Transport(stream_address, "send tagged packet", e))?;
+
+        // If message has been sent successfully, commit message to stores
+        self.state.cursor_store.insert_cursor(&topic, permission, new_cursor);
+        if let Some((ratchet, _)) = ratchet {
+            self.store_ratchet(&topic, &identifier, ratchet);
+            spongos.ratchet();
+        }
+        self.store_spongos(rel_address, spongos, link_to, new_cursor)?;
+        self.mark_recomputable(&topic, rel_address);
+        self.notarize_sent(&topic, &identifier, new_cursor, hash).await?;
+        // Update Branch Links
+        self.set_latest_link(topic, rel_address);
+        Ok(SendResponse::new(message_address, send_response))
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl ContentSizeof<State> for sizeof::Context {
+    async fn sizeof(&mut self, user_state: &State) -> SpongosResult<&mut Self> {
+        self.mask(Maybe::new(user_state.user_id.as_ref()))?
+            .mask(Maybe::new(user_state.stream_address.as_ref()))?
+            .mask(Maybe::new(user_state.author_identifier.as_ref()))?
+            .mask(&user_state.base_branch)?;
+
+        let spongos_states = user_state
+            .spongos_store
+            .entries()
+            .map_err(|e| SpongosError::Context("read the spongos store", e.to_string()))?;
+        self.mask(Size::new(spongos_states.len()))?;
+        for (address, spongos) in &spongos_states {
+            self.mask(address)?.mask(spongos)?;
+        }
+
+        // Only keep topics that exist in cursor store, any others serve no purpose
+        let topics = user_state
+            .topics
+            .iter()
+            .filter(|t| user_state.cursor_store.get_latest_link(t).is_some());
+        let amount_topics = topics.clone().count();
+        self.mask(Size::new(amount_topics))?;
+
+        for topic in topics {
+            self.mask(topic)?;
+            let latest_link = user_state
+                .cursor_store
+                .get_latest_link(topic)
+                .ok_or(SpongosError::InvalidAction(
+                    "calculate sizeof for topic latest link",
+                    topic.to_string(),
+                    "No Cursor".to_owned(),
+                ))?;
+            self.mask(&latest_link)?;
+
+            let cursors: Vec<(&Permissioned<Identifier>, &usize)> = user_state
+                .cursor_store
+                .cursors_by_topic(topic)
+                .ok_or(SpongosError::InvalidAction(
+                    "get cursor for topic",
+                    topic.to_string(),
+                    "No Cursor".to_owned(),
+                ))?
+                .collect();
+            let amount_cursors = cursors.len();
+            self.mask(Size::new(amount_cursors))?;
+            for (subscriber, cursor) in cursors {
+                self.mask(subscriber)?.mask(Size::new(*cursor))?;
+            }
+        }
+
+        let subs = &user_state.subscribers;
+        let amount_subs = subs.len();
+        self.mask(Size::new(amount_subs))?;
+        for subscriber in subs {
+            self.mask(subscriber)?;
+        }
+
+        let psks = user_state.psk_store.iter();
+        let amount_psks = psks.len();
+        self.mask(Size::new(amount_psks))?;
+        for (pskid, psk) in psks {
+            self.mask(pskid)?.mask(psk)?;
+        }
+
+        let lean = if user_state.lean { 1 } else { 0 };
+        self.mask(Uint8::new(lean))?;
+
+        self.commit()?.squeeze(Mac::new(32))
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentWrap<State> for wrap::Context<&'a mut [u8]> {
+    async fn wrap(&mut self, user_state: &mut State) -> SpongosResult<&mut Self> {
+        self.mask(Maybe::new(user_state.user_id.as_ref()))?
+            .mask(Maybe::new(user_state.stream_address.as_ref()))?
+            .mask(Maybe::new(user_state.author_identifier.as_ref()))?
+            .mask(&user_state.base_branch)?;
+
+        let spongos_states = user_state
+            .spongos_store
+            .entries()
+            .map_err(|e| SpongosError::Context("read the spongos store", e.to_string()))?;
+        self.mask(Size::new(spongos_states.len()))?;
+        for (address, spongos) in &spongos_states {
+            self.mask(address)?.mask(spongos)?;
+        }
+
+        // Only keep topics that exist in cursor store, any others serve no purpose
+        let topics = user_state
+            .topics
+            .iter()
+            .filter(|t| user_state.cursor_store.get_latest_link(t).is_some());
+        let amount_topics = topics.clone().count();
+        self.mask(Size::new(amount_topics))?;
+
+        for topic in topics {
+            self.mask(topic)?;
+            let latest_link = user_state
+                .cursor_store
+                .get_latest_link(topic)
+                .ok_or(SpongosError::InvalidAction(
+                    "get latest link topic for wrap",
+                    topic.to_string(),
+                    "No latest link".to_owned(),
+                ))?;
+            self.mask(&latest_link)?;
+
+            let cursors: Vec<(&Permissioned<Identifier>, &usize)> = user_state
+                .cursor_store
+                .cursors_by_topic(topic)
+                .ok_or(SpongosError::InvalidAction(
+                    "get cursor for topic",
+                    topic.to_string(),
+                    "No cursor found".to_owned(),
+                ))?
+                .collect();
+            let amount_cursors = cursors.len();
+            self.mask(Size::new(amount_cursors))?;
+            for (subscriber, cursor) in cursors {
+                self.mask(subscriber)?.mask(Size::new(*cursor))?;
+            }
+        }
+
+        let subs = &user_state.subscribers;
+        let amount_subs = subs.len();
+        self.mask(Size::new(amount_subs))?;
+        for subscriber in subs {
+            self.mask(subscriber)?;
+        }
+
+        let psks = user_state.psk_store.iter();
+        let amount_psks = psks.len();
+        self.mask(Size::new(amount_psks))?;
+        for (pskid, psk) in psks {
+            self.mask(pskid)?.mask(psk)?;
+        }
+
+        let lean = if user_state.lean { 1 } else { 0 };
+        self.mask(Uint8::new(lean))?;
+
+        self.commit()?.squeeze(Mac::new(32))
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentUnwrap<State> for unwrap::Context<&'a [u8]> {
+    async fn unwrap(&mut self, user_state: &mut State) -> SpongosResult<&mut Self> {
+        self.mask(Maybe::new(&mut user_state.user_id))?
+            .mask(Maybe::new(&mut user_state.stream_address))?
+            .mask(Maybe::new(&mut user_state.author_identifier))?
+            .mask(&mut user_state.base_branch)?;
+
+        let mut amount_spongos = Size::default();
+        self.mask(&mut amount_spongos)?;
+        for _ in 0..amount_spongos.inner() {
+            let mut address = MsgId::default();
+            let mut spongos = Spongos::default();
+            self.mask(&mut address)?.mask(&mut spongos)?;
+            user_state
+                .spongos_store
+                .insert(address, spongos)
+                .map_err(|e| SpongosError::Context("write the spongos store", e.to_string()))?;
+        }
+
+        let mut amount_topics = Size::default();
+        self.mask(&mut amount_topics)?;
+
+        for _ in 0..amount_topics.inner() {
+            let mut topic = Topic::default();
+            self.mask(&mut topic)?;
+            let mut latest_link = MsgId::default();
+            self.mask(&mut latest_link)?;
+
+            user_state.topics.insert(topic.clone());
+            user_state.cursor_store.set_latest_link(topic.clone(), latest_link);
+
+            let mut amount_cursors = Size::default();
+            self.mask(&mut amount_cursors)?;
+            for _ in 0..amount_cursors.inner() {
+                let mut subscriber = Permissioned::default();
+                let mut cursor = Size::default();
+                self.mask(&mut subscriber)?.mask(&mut cursor)?;
+                user_state
+                    .cursor_store
+                    .insert_cursor(&topic, subscriber, cursor.inner());
+            }
+        }
+
+        let mut amount_subs = Size::default();
+        self.mask(&mut amount_subs)?;
+        for _ in 0..amount_subs.inner() {
+            let mut subscriber = Identifier::default();
+            self.mask(&mut subscriber)?;
+            user_state.subscribers.insert(subscriber);
+        }
+
+        let mut amount_psks = Size::default();
+        self.mask(&mut amount_psks)?;
+        for _ in 0..amount_psks.inner() {
+            let mut pskid = PskId::default();
+            let mut psk = Psk::default();
+            self.mask(&mut pskid)?.mask(&mut psk)?;
+            user_state.psk_store.insert(pskid, psk);
+        }
+
+        let mut lean = Uint8::new(0);
+        self.mask(&mut lean)?;
+        user_state.lean = lean.inner() == 1;
+
+        self.commit()?.squeeze(Mac::new(32))
+    }
+}
+
+/// Section of a backup following the `State` section since [`BACKUP_VERSION`] 1, holding the state
+/// of the features introduced after the unversioned layout. An unversioned backup only holds the
+/// `State` section, this one is left to its defaults when the backup is migrated.
+struct ExtensionsBackup<'a>(&'a mut State);
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<ExtensionsBackup<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, backup: &ExtensionsBackup<'a>) -> SpongosResult<&mut Self> {
+        // Forward secrecy
+        let forward_secrecy = if backup.0.forward_secrecy { 1 } else { 0 };
+        let ratchets = ratchet::flatten(&backup.0.ratchets);
+        self.mask(Uint8::new(forward_secrecy))?
+            .mask(Size::new(ratchets.len()))?;
+        for (topic, publisher, ratchet) in ratchets {
+            self.mask(topic)?
+                .mask(publisher)?
+                .mask(Size::new(ratchet.sequence()))?
+                .mask(NBytes::new(ratchet.chain_key()))?
+                .mask(Size::new(ratchet.skipped().len()))?;
+            for (sequence, key) in ratchet.skipped() {
+                self.mask(Size::new(*sequence))?.mask(NBytes::new(key))?;
+            }
+        }
+
+        // Key exchange
+        self.mask(Size::new(backup.0.exchange_keys.len()))?;
+        for (identifier, exchange_key) in &backup.0.exchange_keys {
+            self.mask(identifier)?
+                .mask(Size::new(exchange_key.generation))?
+                .mask(NBytes::new(exchange_key.public_key))?;
+        }
+        match backup.0.exchange_key {
+            Some(RotatedExchangeKey::Local(generation, secret_key)) => {
+                self.mask(Uint8::new(1))?
+                    .mask(Size::new(generation))?
+                    .mask(NBytes::new(secret_key))?;
+            }
+            Some(RotatedExchangeKey::External(generation)) => {
+                self.mask(Uint8::new(2))?.mask(Size::new(generation))?;
+            }
+            None => {
+                self.mask(Uint8::new(0))?;
+            }
+        }
+
+        // Closed branches
+        self.mask(Size::new(backup.0.closed_branches.len()))?;
+        for topic in &backup.0.closed_branches {
+            self.mask(topic)?;
+        }
+
+        // Pending subscriptions
+        self.mask(Size::new(backup.0.pending_subscriptions.len()))?;
+        for subscriber in &backup.0.pending_subscriptions {
+            self.mask(subscriber)?;
+        }
+
+        // Invites
+        self.mask(Size::new(backup.0.invite_redemptions.len()))?;
+        for (id, subscribers) in &backup.0.invite_redemptions {
+            self.mask(NBytes::new(id))?.mask(Size::new(subscribers.len()))?;
+            for subscriber in subscribers {
+                self.mask(subscriber)?;
+            }
+        }
+        self.mask(Size::new(backup.0.invite_grants.len()))?;
+        for (topic, subscribers) in &backup.0.invite_grants {
+            self.mask(topic)?.mask(Size::new(subscribers.len()))?;
+            for subscriber in subscribers {
+                self.mask(subscriber)?;
+            }
+        }
+
+        // Spongos positions
+        self.mask(Size::new(backup.0.spongos_positions.len()))?;
+        for (link, position) in &backup.0.spongos_positions {
+            self.mask(link)?
+                .mask(Size::new(position.sequence))?
+                .mask(Maybe::new(position.stored_at.map(Uint64::new)))?;
+        }
+
+        // Send queue
+        self.mask(Size::new(backup.0.send_queue.len()))?;
+        for packet in &backup.0.send_queue {
+            self.mask(Uint64::new(packet.id))?
+                .mask(&packet.topic)?
+                .mask(Uint8::new(packet.priority))?
+                .mask(Bytes::new(&packet.public_payload))?
+                .mask(Bytes::new(&packet.masked_payload))?;
+        }
+
+        // Replay window
+        let seen_messages = &backup.0.seen_messages;
+        self.mask(Size::new(seen_messages.capacity()))?
+            .mask(Size::new(seen_messages.digests().len()))?;
+        for digest in seen_messages.digests() {
+            self.mask(NBytes::new(digest))?;
+        }
+
+        // Keyload permissions
+        self.mask(Size::new(backup.0.keyload_permissions.len()))?;
+        for (topic, permissions) in &backup.0.keyload_permissions {
+            self.mask(topic)?.mask(Size::new(permissions.len()))?;
+            for permission in permissions {
+                self.mask(permission)?;
+            }
+        }
+
+        // Last payloads
+        self.mask(Size::new(backup.0.last_payloads.len()))?;
+        for (topic, last_payloads) in &backup.0.last_payloads {
+            self.mask(topic)?.mask(Size::new(last_payloads.len()))?;
+            for (publisher, last) in last_payloads {
+                self.mask(publisher)?
+                    .mask(&last.original)?
+                    .mask(Bytes::new(&last.public_payload))?
+                    .mask(Bytes::new(&last.masked_payload))?;
+            }
+        }
+
+        // Epochs
+        self.mask(Size::new(backup.0.epochs.len()))?;
+        for (topic, epoch) in &backup.0.epochs {
+            self.mask(topic)?
+                .mask(&epoch.topic)?
+                .mask(Size::new(epoch.index))?
+                .mask(Maybe::new(epoch.started_at.map(Uint64::new)))?
+                .mask(Size::new(epoch.first_cursor))?;
+        }
+
+        // Read markers
+        self.mask(Size::new(backup.0.read_markers.len()))?;
+        for (topic, markers) in &backup.0.read_markers {
+            self.mask(topic)?.mask(Size::new(markers.len()))?;
+            for (consumer, marked_address) in markers {
+                self.mask(consumer)?.mask(marked_address)?;
+            }
+        }
+
+        // Access expirations
+        self.mask(Size::new(backup.0.access_expirations.len()))?;
+        for (topic, expires_at) in &backup.0.access_expirations {
+            self.mask(topic)?.mask(Uint64::new(*expires_at))?;
+        }
+        self.mask(Size::new(backup.0.withheld_packets.len()))?;
+        for (topic, withheld) in &backup.0.withheld_packets {
+            self.mask(topic)?.mask(Size::new(withheld.len()))?;
+            for (packet, expired_at) in withheld {
+                self.mask(packet)?.mask(Uint64::new(*expired_at))?;
+            }
+        }
+
+        // Keyload checkpoints
+        let keyload_checkpoints = if backup.0.keyload_checkpoints { 1 } else { 0 };
+        self.mask(Uint8::new(keyload_checkpoints))?
+            .mask(Size::new(backup.0.recomputable.len()))?;
+        for link in &backup.0.recomputable {
+            self.mask(link)?;
+        }
+
+        // Strictness
+        let strict = if backup.0.strict { 1 } else { 0 };
+        self.mask(Uint8::new(strict))?;
+
+        // Outbox
+        self.mask(Uint64::new(backup.0.next_queued_packet_id))?;
+
+        // Registered readers
+        self.mask(Size::new(backup.0.readers.len()))?;
+        for reader in &backup.0.readers {
+            self.mask(reader)?;
+        }
+
+        // Tombstones
+        self.mask(Size::new(backup.0.tombstones.len()))?;
+        for (redacted, (tombstone, reason)) in &backup.0.tombstones {
+            self.mask(redacted)?.mask(tombstone)?.mask(Bytes::new(reason))?;
+        }
+
+        // Imported cursors
+        self.mask(Size::new(backup.0.imported_cursors.len()))?;
+        for (topic, cursors) in &backup.0.imported_cursors {
+            self.mask(topic)?.mask(Size::new(cursors.len()))?;
+            for (publisher, cursor) in cursors {
+                self.mask(publisher)?.mask(Size::new(*cursor))?;
+            }
+        }
+
+        // Quorum
+        let members = backup.0.quorum.as_ref().map_or(&[][..], Quorum::members);
+        self.mask(Size::new(members.len()))?;
+        for member in members {
+            self.mask(member)?;
+        }
+        let threshold = backup.0.quorum.as_ref().map_or(0, Quorum::threshold);
+        self.mask(Uint8::new(threshold))?;
+
+        // Previous authors
+        self.mask(Size::new(backup.0.previous_authors.len()))?;
+        for previous_author in &backup.0.previous_authors {
+            self.mask(previous_author)?;
+        }
+
+        // Countersignatures
+        self.mask(Size::new(backup.0.witnesses.len()))?;
+        for witness in &backup.0.witnesses {
+            self.mask(witness)?;
+        }
+        self.mask(Size::new(backup.0.countersignatures.len()))?;
+        for (countersigned, countersignatures) in &backup.0.countersignatures {
+            self.mask(countersigned)?.mask(Size::new(countersignatures.len()))?;
+            for countersignature in countersignatures {
+                self.mask(&countersignature.witness)?;
+            }
+        }
+
+        // Commitments
+        self.mask(Size::new(backup.0.commitments.len()))?;
+        for (msgid, commitment) in &backup.0.commitments {
+            self.mask(msgid)?
+                .mask(&commitment.publisher_identifier)?
+                .mask(NBytes::new(&commitment.digest))?;
+        }
+        self.mask(Size::new(backup.0.pending_commitments.len()))?;
+        for (topic, msgid) in &backup.0.pending_commitments {
+            self.mask(topic)?.mask(msgid)?;
+        }
+
+        self.commit()?.squeeze(Mac::new(32))
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, 'b> ContentWrap<ExtensionsBackup<'b>> for wrap::Context<&'a mut [u8]> {
+    async fn wrap(&mut self, backup: &mut ExtensionsBackup<'b>) -> SpongosResult<&mut Self> {
+        // Forward secrecy
+        let forward_secrecy = if backup.0.forward_secrecy { 1 } else { 0 };
+        let ratchets = ratchet::flatten(&backup.0.ratchets);
+        self.mask(Uint8::new(forward_secrecy))?
+            .mask(Size::new(ratchets.len()))?;
+        for (topic, publisher, ratchet) in ratchets {
+            self.mask(topic)?
+                .mask(publisher)?
+                .mask(Size::new(ratchet.sequence()))?
+                .mask(NBytes::new(ratchet.chain_key()))?
+                .mask(Size::new(ratchet.skipped().len()))?;
+            for (sequence, key) in ratchet.skipped() {
+                self.mask(Size::new(*sequence))?.mask(NBytes::new(key))?;
+            }
+        }
+
+        // Key exchange
+        self.mask(Size::new(backup.0.exchange_keys.len()))?;
+        for (identifier, exchange_key) in &backup.0.exchange_keys {
+            self.mask(identifier)?
+                .mask(Size::new(exchange_key.generation))?
+                .mask(NBytes::new(exchange_key.public_key))?;
+        }
+        match backup.0.exchange_key {
+            Some(RotatedExchangeKey::Local(generation, secret_key)) => {
+                self.mask(Uint8::new(1))?
+                    .mask(Size::new(generation))?
+                    .mask(NBytes::new(secret_key))?;
+            }
+            Some(RotatedExchangeKey::External(generation)) => {
+                self.mask(Uint8::new(2))?.mask(Size::new(generation))?;
+            }
+            None => {
+                self.mask(Uint8::new(0))?;
+            }
+        }
+
+        // Closed branches
+        self.mask(Size::new(backup.0.closed_branches.len()))?;
+        for topic in &backup.0.closed_branches {
+            self.mask(topic)?;
+        }
+
+        // Pending subscriptions
+        self.mask(Size::new(backup.0.pending_subscriptions.len()))?;
+        for subscriber in &backup.0.pending_subscriptions {
+            self.mask(subscriber)?;
+        }
+
+        // Invites
+        self.mask(Size::new(backup.0.invite_redemptions.len()))?;
+        for (id, subscribers) in &backup.0.invite_redemptions {
+            self.mask(NBytes::new(id))?.mask(Size::new(subscribers.len()))?;
+            for subscriber in subscribers {
+                self.mask(subscriber)?;
+            }
+        }
+        self.mask(Size::new(backup.0.invite_grants.len()))?;
+        for (topic, subscribers) in &backup.0.invite_grants {
+            self.mask(topic)?.mask(Size::new(subscribers.len()))?;
+            for subscriber in subscribers {
+                self.mask(subscriber)?;
+            }
+        }
+
+        // Spongos positions
+        self.mask(Size::new(backup.0.spongos_positions.len()))?;
+        for (link, position) in &backup.0.spongos_positions {
+            self.mask(link)?
+                .mask(Size::new(position.sequence))?
+                .mask(Maybe::new(position.stored_at.map(Uint64::new)))?;
+        }
+
+        // Send queue
+        self.mask(Size::new(backup.0.send_queue.len()))?;
+        for packet in &backup.0.send_queue {
+            self.mask(Uint64::new(packet.id))?
+                .mask(&packet.topic)?
+                .mask(Uint8::new(packet.priority))?
+                .mask(Bytes::new(&packet.public_payload))?
+                .mask(Bytes::new(&packet.masked_payload))?;
+        }
+
+        // Replay window
+        let seen_messages = &backup.0.seen_messages;
+        self.mask(Size::new(seen_messages.capacity()))?
+            .mask(Size::new(seen_messages.digests().len()))?;
+        for digest in seen_messages.digests() {
+            self.mask(NBytes::new(digest))?;
+        }
+
+        // Keyload permissions
+        self.mask(Size::new(backup.0.keyload_permissions.len()))?;
+        for (topic, permissions) in &backup.0.keyload_permissions {
+            self.mask(topic)?.mask(Size::new(permissions.len()))?;
+            for permission in permissions {
+                self.mask(permission)?;
+            }
+        }
+
+        // Last payloads
+        self.mask(Size::new(backup.0.last_payloads.len()))?;
+        for (topic, last_payloads) in &backup.0.last_payloads {
+            self.mask(topic)?.mask(Size::new(last_payloads.len()))?;
+            for (publisher, last) in last_payloads {
+                self.mask(publisher)?
+                    .mask(&last.original)?
+                    .mask(Bytes::new(&last.public_payload))?
+                    .mask(Bytes::new(&last.masked_payload))?;
+            }
+        }
+
+        // Epochs
+        self.mask(Size::new(backup.0.epochs.len()))?;
+        for (topic, epoch) in &backup.0.epochs {
+            self.mask(topic)?
+                .mask(&epoch.topic)?
+                .mask(Size::new(epoch.index))?
+                .mask(Maybe::new(epoch.started_at.map(Uint64::new)))?
+                .mask(Size::new(epoch.first_cursor))?;
+        }
+
+        // Read markers
+        self.mask(Size::new(backup.0.read_markers.len()))?;
+        for (topic, markers) in &backup.0.read_markers {
+            self.mask(topic)?.mask(Size::new(markers.len()))?;
+            for (consumer, marked_address) in markers {
+                self.mask(consumer)?.mask(marked_address)?;
+            }
+        }
+
+        // Access expirations
+        self.mask(Size::new(backup.0.access_expirations.len()))?;
+        for (topic, expires_at) in &backup.0.access_expirations {
+            self.mask(topic)?.mask(Uint64::new(*expires_at))?;
+        }
+        self.mask(Size::new(backup.0.withheld_packets.len()))?;
+        for (topic, withheld) in &backup.0.withheld_packets {
+            self.mask(topic)?.mask(Size::new(withheld.len()))?;
+            for (packet, expired_at) in withheld {
+                self.mask(packet)?.mask(Uint64::new(*expired_at))?;
+            }
         }
-        // Link message to latest message in branch
-        let link_to = self
-            .get_latest_link(&topic)
-            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
-        // Update own's cursor
-        let new_cursor = self.next_cursor(&topic)?;
-        let rel_address = MsgId::gen(stream_address.base(), &identifier, &topic, new_cursor);
 
-        // Prepare HDF and PCF
-        // Spongos must be copied because wrapping mutates it
-        let mut linked_msg_spongos = self
-            .state
-            .spongos_store
-            .get(&link_to)
-            .copied()
-            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
+        // Keyload checkpoints
+        let keyload_checkpoints = if backup.0.keyload_checkpoints { 1 } else { 0 };
+        self.mask(Uint8::new(keyload_checkpoints))?
+            .mask(Size::new(backup.0.recomputable.len()))?;
+        for link in &backup.0.recomputable {
+            self.mask(link)?;
+        }
 
-        let content = PCF::new_final_frame().with_content(signed_packet::Wrap::new(
-            &mut linked_msg_spongos,
-            &(*user_id),
-            public_payload.as_ref(),
-            masked_payload.as_ref(),
-        ));
-        let header = HDF::new(message_types::SIGNED_PACKET, new_cursor, identifier.clone(), &topic)
-            .with_linked_msg_address(link_to);
+        // Strictness
+        let strict = if backup.0.strict { 1 } else { 0 };
+        self.mask(Uint8::new(strict))?;
 
-        // Wrap message
-        let (transport_msg, spongos) = LetsMessage::new(header, content)
-            .wrap()
-            .await
-            .map_err(|e| Error::Wrapped("send signed packet", e))?;
+        // Outbox
+        self.mask(Uint64::new(backup.0.next_queued_packet_id))?;
 
-        // Attempt to send message
-        let message_address = Address::new(stream_address.base(), rel_address);
-        if !self.transport.recv_message(message_address).await.is_err() {
-            return Err(Error::AddressUsed("signed packet", message_address));
+        // Registered readers
+        self.mask(Size::new(backup.0.readers.len()))?;
+        for reader in &backup.0.readers {
+            self.mask(reader)?;
         }
-        let send_response = self
-            .transport
-            .send_message(message_address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(stream_address, "send signed packet", e))?;
 
-        // If message has been sent successfully, commit message to stores
-        self.state
-            .cursor_store
-            .insert_cursor(&topic, permission.clone(), new_cursor);
-        self.store_spongos(rel_address, spongos, link_to);
-        // Update Branch Links
-        self.set_latest_link(topic, message_address.relative());
-        Ok(SendResponse::new(message_address, send_response))
-    }
+        // Tombstones
+        self.mask(Size::new(backup.0.tombstones.len()))?;
+        for (redacted, (tombstone, reason)) in &backup.0.tombstones {
+            self.mask(redacted)?.mask(tombstone)?.mask(Bytes::new(reason))?;
+        }
 
-    /// Create and send a new Tagged Packet message to the specified branch. The message will
-    /// contain a masked and an unmasked payload.
-    ///
-    /// # Arguments
-    /// * `topic`: The [`Topic`] of the branch to send the message to.
-    /// * `public_payload`: The unmasked payload of the message.
-    /// * `masked_payload`: The masked payload of the message.
-    pub async fn send_tagged_packet<P, M, Top>(
-        &mut self,
-        topic: Top,
-        public_payload: P,
-        masked_payload: M,
-    ) -> Result<SendResponse<TSR>>
-    where
-        M: AsRef<[u8]>,
-        P: AsRef<[u8]>,
-        Top: Into<Topic>,
-    {
-        // Check conditions
-        let stream_address = self.stream_address().ok_or(Error::Setup(
-            "before sending a tagged packet, the stream must be created",
-        ))?;
-        let user_id = self.identity().ok_or(Error::NoIdentity("send tagged packet"))?;
-        let identifier = user_id.identifier().clone();
-        // Check Topic
-        let topic = topic.into();
-        // Check Permission
-        let permission = self
-            .state
-            .cursor_store
-            .get_permission(&topic, &identifier)
-            .ok_or(Error::NoCursor(topic.clone()))?;
-        if permission.is_readonly() {
-            return Err(Error::WrongRole(
-                "ReadWrite",
-                permission.identifier().clone(),
-                "send a tagged packet",
-            ));
+        // Imported cursors
+        self.mask(Size::new(backup.0.imported_cursors.len()))?;
+        for (topic, cursors) in &backup.0.imported_cursors {
+            self.mask(topic)?.mask(Size::new(cursors.len()))?;
+            for (publisher, cursor) in cursors {
+                self.mask(publisher)?.mask(Size::new(*cursor))?;
+            }
         }
-        // Link message to latest message in branch
-        let link_to = self
-            .get_latest_link(&topic)
-            .ok_or_else(|| Error::TopicNotFound(topic.clone()))?;
 
-        // Update own's cursor
-        let new_cursor = self.next_cursor(&topic)?;
-        let rel_address = MsgId::gen(stream_address.base(), &identifier, &topic, new_cursor);
+        // Quorum
+        let members = backup.0.quorum.as_ref().map_or(&[][..], Quorum::members);
+        self.mask(Size::new(members.len()))?;
+        for member in members {
+            self.mask(member)?;
+        }
+        let threshold = backup.0.quorum.as_ref().map_or(0, Quorum::threshold);
+        self.mask(Uint8::new(threshold))?;
 
-        // Prepare HDF and PCF
-        // Spongos must be copied because wrapping mutates it
-        let mut linked_msg_spongos = self
-            .state
-            .spongos_store
-            .get(&link_to)
-            .copied()
-            .ok_or(Error::MessageMissing(link_to, "spongos store"))?;
-        let content = PCF::new_final_frame().with_content(tagged_packet::Wrap::new(
-            &mut linked_msg_spongos,
-            public_payload.as_ref(),
-            masked_payload.as_ref(),
-        ));
-        let header = HDF::new(message_types::TAGGED_PACKET, new_cursor, identifier.clone(), &topic)
-            .with_linked_msg_address(link_to);
+        // Previous authors
+        self.mask(Size::new(backup.0.previous_authors.len()))?;
+        for previous_author in &backup.0.previous_authors {
+            self.mask(previous_author)?;
+        }
 
-        // Wrap message
-        let (transport_msg, spongos) = LetsMessage::new(header, content)
-            .wrap()
-            .await
-            .map_err(|e| Error::Wrapped("send tagged packet", e))?;
+        // Countersignatures
+        self.mask(Size::new(backup.0.witnesses.len()))?;
+        for witness in &backup.0.witnesses {
+            self.mask(witness)?;
+        }
+        self.mask(Size::new(backup.0.countersignatures.len()))?;
+        for (countersigned, countersignatures) in &backup.0.countersignatures {
+            self.mask(countersigned)?.mask(Size::new(countersignatures.len()))?;
+            for countersignature in countersignatures {
+                self.mask(&countersignature.witness)?;
+            }
+        }
 
-        // Attempt to send message
-        let message_address = Address::new(stream_address.base(), rel_address);
-        if !self.transport.recv_message(message_address).await.is_err() {
-            return Err(Error::AddressUsed("tagged packet", message_address));
+        // Commitments
+        self.mask(Size::new(backup.0.commitments.len()))?;
+        for (msgid, commitment) in &backup.0.commitments {
+            self.mask(msgid)?
+                .mask(&commitment.publisher_identifier)?
+                .mask(NBytes::new(&commitment.digest))?;
+        }
+        self.mask(Size::new(backup.0.pending_commitments.len()))?;
+        for (topic, msgid) in &backup.0.pending_commitments {
+            self.mask(topic)?.mask(msgid)?;
         }
-        let send_response = self
-            .transport
-            .send_message(message_address, transport_msg)
-            .await
-            .map_err(|e| Error::Transport(stream_address, "send tagged packet", e))?;
 
-        // If message has been sent successfully, commit message to stores
-        self.state
-            .cursor_store
-            .insert_cursor(&topic, permission.clone(), new_cursor);
-        self.store_spongos(rel_address, spongos, link_to);
-        // Update Branch Links
-        self.set_latest_link(topic, rel_address);
-        Ok(SendResponse::new(message_address, send_response))
+        self.commit()?.squeeze(Mac::new(32))
     }
 }
 
-#[async_trait(?Send)]
-impl ContentSizeof<State> for sizeof::Context {
-    async fn sizeof(&mut self, user_state: &State) -> SpongosResult<&mut Self> {
-        self.mask(Maybe::new(user_state.user_id.as_ref()))?
-            .mask(Maybe::new(user_state.stream_address.as_ref()))?
-            .mask(Maybe::new(user_state.author_identifier.as_ref()))?
-            .mask(&user_state.base_branch)?;
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, 'b> ContentUnwrap<ExtensionsBackup<'b>> for unwrap::Context<&'a [u8]> {
+    async fn unwrap(&mut self, backup: &mut ExtensionsBackup<'b>) -> SpongosResult<&mut Self> {
+        // Forward secrecy
+        let mut forward_secrecy = Uint8::new(0);
+        let mut amount_ratchets = Size::default();
+        self.mask(&mut forward_secrecy)?.mask(&mut amount_ratchets)?;
+        backup.0.forward_secrecy = forward_secrecy.inner() == 1;
+        for _ in 0..amount_ratchets.inner() {
+            let mut topic = Topic::default();
+            let mut publisher = Identifier::default();
+            let mut sequence = Size::default();
+            let mut chain_key = [0u8; RATCHET_KEY_SIZE];
+            let mut amount_skipped = Size::default();
+            self.mask(&mut topic)?
+                .mask(&mut publisher)?
+                .mask(&mut sequence)?
+                .mask(NBytes::new(&mut chain_key))?
+                .mask(&mut amount_skipped)?;
+            let mut skipped = BTreeMap::new();
+            for _ in 0..amount_skipped.inner() {
+                let mut skipped_sequence = Size::default();
+                let mut key = [0u8; RATCHET_KEY_SIZE];
+                self.mask(&mut skipped_sequence)?.mask(NBytes::new(&mut key))?;
+                skipped.insert(skipped_sequence.inner(), key);
+            }
+            let ratchet = Ratchet::new(sequence.inner(), chain_key).with_skipped(skipped);
+            backup.0.ratchets.entry(topic).or_default().insert(publisher, ratchet);
+        }
 
-        let amount_spongos = user_state.spongos_store.len();
-        self.mask(Size::new(amount_spongos))?;
-        for (address, spongos) in &user_state.spongos_store {
-            self.mask(address)?.mask(spongos)?;
+        // Key exchange
+        let mut amount_exchange_keys = Size::default();
+        self.mask(&mut amount_exchange_keys)?;
+        for _ in 0..amount_exchange_keys.inner() {
+            let mut identifier = Identifier::default();
+            let mut generation = Size::default();
+            let mut public_key = [0u8; x25519::PUBLIC_KEY_LENGTH];
+            self.mask(&mut identifier)?
+                .mask(&mut generation)?
+                .mask(NBytes::new(&mut public_key))?;
+            backup
+                .0
+                .exchange_keys
+                .insert(identifier, ExchangeKey::new(generation.inner(), public_key));
+        }
+        let mut exchange_key_kind = Uint8::new(0);
+        self.mask(&mut exchange_key_kind)?;
+        match exchange_key_kind.inner() {
+            0 => {}
+            1 => {
+                let mut generation = Size::default();
+                let mut secret_key = [0u8; x25519::SECRET_KEY_LENGTH];
+                self.mask(&mut generation)?.mask(NBytes::new(&mut secret_key))?;
+                backup.0.exchange_key = Some(RotatedExchangeKey::Local(generation.inner(), secret_key));
+            }
+            2 => {
+                let mut generation = Size::default();
+                self.mask(&mut generation)?;
+                backup.0.exchange_key = Some(RotatedExchangeKey::External(generation.inner()));
+            }
+            o => return Err(SpongosError::InvalidOption("exchange key", o)),
         }
 
-        // Only keep topics that exist in cursor store, any others serve no purpose
-        let topics = user_state
-            .topics
-            .iter()
-            .filter(|t| user_state.cursor_store.get_latest_link(t).is_some());
-        let amount_topics = topics.clone().count();
-        self.mask(Size::new(amount_topics))?;
+        // Closed branches
+        let mut amount_closed_branches = Size::default();
+        self.mask(&mut amount_closed_branches)?;
+        for _ in 0..amount_closed_branches.inner() {
+            let mut topic = Topic::default();
+            self.mask(&mut topic)?;
+            backup.0.closed_branches.insert(topic);
+        }
 
-        for topic in topics {
-            self.mask(topic)?;
-            let latest_link = user_state
-                .cursor_store
-                .get_latest_link(topic)
-                .ok_or(SpongosError::InvalidAction(
-                    "calculate sizeof for topic latest link",
-                    topic.to_string(),
-                    "No Cursor".to_owned(),
-                ))?;
-            self.mask(&latest_link)?;
+        // Pending subscriptions
+        let mut amount_pending_subscriptions = Size::default();
+        self.mask(&mut amount_pending_subscriptions)?;
+        for _ in 0..amount_pending_subscriptions.inner() {
+            let mut subscriber = Identifier::default();
+            self.mask(&mut subscriber)?;
+            backup.0.pending_subscriptions.insert(subscriber);
+        }
 
-            let cursors: Vec<(&Permissioned<Identifier>, &usize)> = user_state
-                .cursor_store
-                .cursors_by_topic(topic)
-                .ok_or(SpongosError::InvalidAction(
-                    "get cursor for topic",
-                    topic.to_string(),
-                    "No Cursor".to_owned(),
-                ))?
-                .collect();
-            let amount_cursors = cursors.len();
-            self.mask(Size::new(amount_cursors))?;
-            for (subscriber, cursor) in cursors {
-                self.mask(subscriber)?.mask(Size::new(*cursor))?;
+        // Invites
+        let mut amount_invites = Size::default();
+        self.mask(&mut amount_invites)?;
+        for _ in 0..amount_invites.inner() {
+            let mut id = [0u8; INVITE_ID_SIZE];
+            let mut amount_subscribers = Size::default();
+            self.mask(NBytes::new(&mut id))?.mask(&mut amount_subscribers)?;
+            let subscribers = backup.0.invite_redemptions.entry(id).or_default();
+            for _ in 0..amount_subscribers.inner() {
+                let mut subscriber = Identifier::default();
+                self.mask(&mut subscriber)?;
+                subscribers.insert(subscriber);
             }
         }
-
-        let subs = &user_state.subscribers;
-        let amount_subs = subs.len();
-        self.mask(Size::new(amount_subs))?;
-        for subscriber in subs {
-            self.mask(subscriber)?;
+        let mut amount_topics = Size::default();
+        self.mask(&mut amount_topics)?;
+        for _ in 0..amount_topics.inner() {
+            let mut topic = Topic::default();
+            let mut amount_subscribers = Size::default();
+            self.mask(&mut topic)?.mask(&mut amount_subscribers)?;
+            let subscribers = backup.0.invite_grants.entry(topic).or_default();
+            for _ in 0..amount_subscribers.inner() {
+                let mut subscriber = Identifier::default();
+                self.mask(&mut subscriber)?;
+                subscribers.insert(subscriber);
+            }
         }
 
-        let psks = user_state.psk_store.iter();
-        let amount_psks = psks.len();
-        self.mask(Size::new(amount_psks))?;
-        for (pskid, psk) in psks {
-            self.mask(pskid)?.mask(psk)?;
+        // Spongos positions
+        let mut amount_positions = Size::default();
+        self.mask(&mut amount_positions)?;
+        for _ in 0..amount_positions.inner() {
+            let mut link = MsgId::default();
+            let mut sequence = Size::default();
+            let mut stored_at: Option<Uint64> = None;
+            self.mask(&mut link)?
+                .mask(&mut sequence)?
+                .mask(Maybe::new(&mut stored_at))?;
+            let position = SpongosPosition {
+                sequence: sequence.inner(),
+                stored_at: stored_at.map(|stored_at| stored_at.inner()),
+            };
+            backup.0.spongos_positions.insert(link, position);
         }
 
-        let lean = if user_state.lean { 1 } else { 0 };
-        self.mask(Uint8::new(lean))?;
+        // Send queue
+        let mut amount_packets = Size::default();
+        self.mask(&mut amount_packets)?;
+        for _ in 0..amount_packets.inner() {
+            let mut packet = QueuedPacket::default();
+            let mut id = Uint64::default();
+            let mut priority = Uint8::new(0);
+            self.mask(&mut id)?
+                .mask(&mut packet.topic)?
+                .mask(&mut priority)?
+                .mask(Bytes::new(&mut packet.public_payload))?
+                .mask(Bytes::new(&mut packet.masked_payload))?;
+            packet.id = id.inner();
+            packet.priority = priority.inner();
+            backup.0.send_queue.push(packet);
+        }
 
-        self.commit()?.squeeze(Mac::new(32))
-    }
-}
+        // Replay window
+        let mut capacity = Size::default();
+        let mut amount_digests = Size::default();
+        self.mask(&mut capacity)?.mask(&mut amount_digests)?;
+        let mut seen_messages = SeenMessages::new(capacity.inner());
+        for _ in 0..amount_digests.inner() {
+            let mut digest = [0; SEEN_DIGEST_SIZE];
+            self.mask(NBytes::new(&mut digest))?;
+            seen_messages.insert(digest);
+        }
+        backup.0.seen_messages = seen_messages;
 
-#[async_trait(?Send)]
-impl<'a> ContentWrap<State> for wrap::Context<&'a mut [u8]> {
-    async fn wrap(&mut self, user_state: &mut State) -> SpongosResult<&mut Self> {
-        self.mask(Maybe::new(user_state.user_id.as_ref()))?
-            .mask(Maybe::new(user_state.stream_address.as_ref()))?
-            .mask(Maybe::new(user_state.author_identifier.as_ref()))?
-            .mask(&user_state.base_branch)?;
+        // Keyload permissions
+        let mut amount_topics = Size::default();
+        self.mask(&mut amount_topics)?;
+        for _ in 0..amount_topics.inner() {
+            let mut topic = Topic::default();
+            let mut amount_permissions = Size::default();
+            self.mask(&mut topic)?.mask(&mut amount_permissions)?;
+            let mut permissions = Vec::with_capacity(amount_permissions.inner());
+            for _ in 0..amount_permissions.inner() {
+                let mut permission = Permissioned::default();
+                self.mask(&mut permission)?;
+                permissions.push(permission);
+            }
+            backup.0.keyload_permissions.insert(topic, permissions);
+        }
 
-        let amount_spongos = user_state.spongos_store.len();
-        self.mask(Size::new(amount_spongos))?;
-        for (address, spongos) in &user_state.spongos_store {
-            self.mask(address)?.mask(spongos)?;
+        // Last payloads
+        let mut amount_topics = Size::default();
+        self.mask(&mut amount_topics)?;
+        for _ in 0..amount_topics.inner() {
+            let mut topic = Topic::default();
+            let mut amount_publishers = Size::default();
+            self.mask(&mut topic)?.mask(&mut amount_publishers)?;
+            let mut last_payloads = HashMap::new();
+            for _ in 0..amount_publishers.inner() {
+                let mut publisher = Identifier::default();
+                let mut last = LastPayloads::default();
+                self.mask(&mut publisher)?
+                    .mask(&mut last.original)?
+                    .mask(Bytes::new(&mut last.public_payload))?
+                    .mask(Bytes::new(&mut last.masked_payload))?;
+                last_payloads.insert(publisher, last);
+            }
+            backup.0.last_payloads.insert(topic, last_payloads);
         }
 
-        // Only keep topics that exist in cursor store, any others serve no purpose
-        let topics = user_state
-            .topics
-            .iter()
-            .filter(|t| user_state.cursor_store.get_latest_link(t).is_some());
-        let amount_topics = topics.clone().count();
-        self.mask(Size::new(amount_topics))?;
+        // Epochs
+        let mut amount_epochs = Size::default();
+        self.mask(&mut amount_epochs)?;
+        for _ in 0..amount_epochs.inner() {
+            let mut topic = Topic::default();
+            let mut epoch_topic = Topic::default();
+            let mut index = Size::default();
+            let mut started_at: Option<Uint64> = None;
+            let mut first_cursor = Size::default();
+            self.mask(&mut topic)?
+                .mask(&mut epoch_topic)?
+                .mask(&mut index)?
+                .mask(Maybe::new(&mut started_at))?
+                .mask(&mut first_cursor)?;
+            let epoch = Epoch {
+                topic: epoch_topic,
+                index: index.inner(),
+                started_at: started_at.map(|started_at| started_at.inner()),
+                first_cursor: first_cursor.inner(),
+            };
+            backup.0.epochs.insert(topic, epoch);
+        }
 
-        for topic in topics {
-            self.mask(topic)?;
-            let latest_link = user_state
-                .cursor_store
-                .get_latest_link(topic)
-                .ok_or(SpongosError::InvalidAction(
-                    "get latest link topic for wrap",
-                    topic.to_string(),
-                    "No latest link".to_owned(),
-                ))?;
-            self.mask(&latest_link)?;
+        // Read markers
+        let mut amount_topics = Size::default();
+        self.mask(&mut amount_topics)?;
+        for _ in 0..amount_topics.inner() {
+            let mut topic = Topic::default();
+            let mut amount_markers = Size::default();
+            self.mask(&mut topic)?.mask(&mut amount_markers)?;
+            let mut markers = HashMap::new();
+            for _ in 0..amount_markers.inner() {
+                let mut consumer = Identifier::default();
+                let mut marked_address = Address::default();
+                self.mask(&mut consumer)?.mask(&mut marked_address)?;
+                markers.insert(consumer, marked_address);
+            }
+            backup.0.read_markers.insert(topic, markers);
+        }
 
-            let cursors: Vec<(&Permissioned<Identifier>, &usize)> = user_state
-                .cursor_store
-                .cursors_by_topic(topic)
-                .ok_or(SpongosError::InvalidAction(
-                    "get cursor for topic",
-                    topic.to_string(),
-                    "No cursor found".to_owned(),
-                ))?
-                .collect();
-            let amount_cursors = cursors.len();
-            self.mask(Size::new(amount_cursors))?;
-            for (subscriber, cursor) in cursors {
-                self.mask(subscriber)?.mask(Size::new(*cursor))?;
+        // Access expirations
+        let mut amount_expirations = Size::default();
+        self.mask(&mut amount_expirations)?;
+        for _ in 0..amount_expirations.inner() {
+            let mut topic = Topic::default();
+            let mut expires_at = Uint64::default();
+            self.mask(&mut topic)?.mask(&mut expires_at)?;
+            backup.0.access_expirations.insert(topic, expires_at.inner());
+        }
+        let mut amount_topics = Size::default();
+        self.mask(&mut amount_topics)?;
+        for _ in 0..amount_topics.inner() {
+            let mut topic = Topic::default();
+            let mut amount_withheld = Size::default();
+            self.mask(&mut topic)?.mask(&mut amount_withheld)?;
+            let mut withheld = HashMap::new();
+            for _ in 0..amount_withheld.inner() {
+                let mut packet = MsgId::default();
+                let mut expired_at = Uint64::default();
+                self.mask(&mut packet)?.mask(&mut expired_at)?;
+                withheld.insert(packet, expired_at.inner());
             }
+            backup.0.withheld_packets.insert(topic, withheld);
         }
 
-        let subs = &user_state.subscribers;
-        let amount_subs = subs.len();
-        self.mask(Size::new(amount_subs))?;
-        for subscriber in subs {
-            self.mask(subscriber)?;
+        // Keyload checkpoints
+        let mut keyload_checkpoints = Uint8::new(0);
+        let mut amount_links = Size::default();
+        self.mask(&mut keyload_checkpoints)?.mask(&mut amount_links)?;
+        backup.0.keyload_checkpoints = keyload_checkpoints.inner() == 1;
+        for _ in 0..amount_links.inner() {
+            let mut link = MsgId::default();
+            self.mask(&mut link)?;
+            backup.0.recomputable.insert(link);
         }
 
-        let psks = user_state.psk_store.iter();
-        let amount_psks = psks.len();
-        self.mask(Size::new(amount_psks))?;
-        for (pskid, psk) in psks {
-            self.mask(pskid)?.mask(psk)?;
+        // Strictness
+        let mut strict = Uint8::new(0);
+        self.mask(&mut strict)?;
+        backup.0.strict = strict.inner() == 1;
+
+        // Outbox
+        let mut next_queued_packet_id = Uint64::default();
+        self.mask(&mut next_queued_packet_id)?;
+        backup.0.next_queued_packet_id = next_queued_packet_id.inner();
+
+        // Registered readers
+        let mut amount_readers = Size::default();
+        self.mask(&mut amount_readers)?;
+        for _ in 0..amount_readers.inner() {
+            let mut reader = Identifier::default();
+            self.mask(&mut reader)?;
+            backup.0.readers.insert(reader);
         }
 
-        let lean = if user_state.lean { 1 } else { 0 };
-        self.mask(Uint8::new(lean))?;
-
-        self.commit()?.squeeze(Mac::new(32))
-    }
-}
-
-#[async_trait(?Send)]
-impl<'a> ContentUnwrap<State> for unwrap::Context<&'a [u8]> {
-    async fn unwrap(&mut self, user_state: &mut State) -> SpongosResult<&mut Self> {
-        self.mask(Maybe::new(&mut user_state.user_id))?
-            .mask(Maybe::new(&mut user_state.stream_address))?
-            .mask(Maybe::new(&mut user_state.author_identifier))?
-            .mask(&mut user_state.base_branch)?;
-
-        let mut amount_spongos = Size::default();
-        self.mask(&mut amount_spongos)?;
-        for _ in 0..amount_spongos.inner() {
-            let mut address = MsgId::default();
-            let mut spongos = Spongos::default();
-            self.mask(&mut address)?.mask(&mut spongos)?;
-            user_state.spongos_store.insert(address, spongos);
+        // Tombstones
+        let mut amount_tombstones = Size::default();
+        self.mask(&mut amount_tombstones)?;
+        for _ in 0..amount_tombstones.inner() {
+            let mut redacted = MsgId::default();
+            let mut tombstone = MsgId::default();
+            let mut reason = Vec::new();
+            self.mask(&mut redacted)?
+                .mask(&mut tombstone)?
+                .mask(Bytes::new(&mut reason))?;
+            let reason = String::from_utf8(reason).map_err(|e| SpongosError::Context("Mask", e.to_string()))?;
+            backup.0.tombstones.insert(redacted, (tombstone, reason));
         }
 
+        // Imported cursors
         let mut amount_topics = Size::default();
         self.mask(&mut amount_topics)?;
-
         for _ in 0..amount_topics.inner() {
             let mut topic = Topic::default();
-            self.mask(&mut topic)?;
-            let mut latest_link = MsgId::default();
-            self.mask(&mut latest_link)?;
-
-            user_state.topics.insert(topic.clone());
-            user_state.cursor_store.set_latest_link(topic.clone(), latest_link);
-
             let mut amount_cursors = Size::default();
-            self.mask(&mut amount_cursors)?;
+            self.mask(&mut topic)?.mask(&mut amount_cursors)?;
+            let cursors = backup.0.imported_cursors.entry(topic).or_default();
             for _ in 0..amount_cursors.inner() {
-                let mut subscriber = Permissioned::default();
+                let mut publisher = Identifier::default();
                 let mut cursor = Size::default();
-                self.mask(&mut subscriber)?.mask(&mut cursor)?;
-                user_state
-                    .cursor_store
-                    .insert_cursor(&topic, subscriber, cursor.inner());
+                self.mask(&mut publisher)?.mask(&mut cursor)?;
+                cursors.insert(publisher, cursor.inner());
             }
         }
 
-        let mut amount_subs = Size::default();
-        self.mask(&mut amount_subs)?;
-        for _ in 0..amount_subs.inner() {
-            let mut subscriber = Identifier::default();
-            self.mask(&mut subscriber)?;
-            user_state.subscribers.insert(subscriber);
+        // Quorum
+        let mut amount_members = Size::default();
+        self.mask(&mut amount_members)?;
+        let mut members = Vec::with_capacity(amount_members.inner());
+        for _ in 0..amount_members.inner() {
+            let mut member = Identifier::default();
+            self.mask(&mut member)?;
+            members.push(member);
+        }
+        let mut threshold = Uint8::new(0);
+        self.mask(&mut threshold)?;
+        if !members.is_empty() {
+            let quorum =
+                Quorum::new(members, threshold.inner()).map_err(|e| SpongosError::Context("Quorum", e.to_string()))?;
+            backup.0.quorum = Some(quorum);
         }
 
-        let mut amount_psks = Size::default();
-        self.mask(&mut amount_psks)?;
-        for _ in 0..amount_psks.inner() {
-            let mut pskid = PskId::default();
-            let mut psk = Psk::default();
-            self.mask(&mut pskid)?.mask(&mut psk)?;
-            user_state.psk_store.insert(pskid, psk);
+        // Previous authors
+        let mut amount_previous_authors = Size::default();
+        self.mask(&mut amount_previous_authors)?;
+        for _ in 0..amount_previous_authors.inner() {
+            let mut previous_author = Identifier::default();
+            self.mask(&mut previous_author)?;
+            backup.0.previous_authors.insert(previous_author);
         }
 
-        let mut lean = Uint8::new(0);
-        self.mask(&mut lean)?;
-        user_state.lean = lean.inner() == 1;
+        // Countersignatures
+        let mut amount_witnesses = Size::default();
+        self.mask(&mut amount_witnesses)?;
+        for _ in 0..amount_witnesses.inner() {
+            let mut witness = Identifier::default();
+            self.mask(&mut witness)?;
+            backup.0.witnesses.insert(witness);
+        }
+        let mut amount_countersigned = Size::default();
+        self.mask(&mut amount_countersigned)?;
+        for _ in 0..amount_countersigned.inner() {
+            let mut countersigned = MsgId::default();
+            let mut amount_countersignatures = Size::default();
+            self.mask(&mut countersigned)?.mask(&mut amount_countersignatures)?;
+            let mut countersignatures = Vec::with_capacity(amount_countersignatures.inner());
+            for _ in 0..amount_countersignatures.inner() {
+                let mut witness = Identifier::default();
+                self.mask(&mut witness)?;
+                countersignatures.push(Countersignature { witness, countersigned });
+            }
+            backup.0.countersignatures.insert(countersigned, countersignatures);
+        }
+
+        // Commitments
+        let mut amount_commitments = Size::default();
+        self.mask(&mut amount_commitments)?;
+        for _ in 0..amount_commitments.inner() {
+            let mut msgid = MsgId::default();
+            let mut publisher_identifier = Identifier::default();
+            let mut digest = [0; COMMITMENT_DIGEST_SIZE];
+            self.mask(&mut msgid)?
+                .mask(&mut publisher_identifier)?
+                .mask(NBytes::new(&mut digest))?;
+            backup.0.commitments.insert(
+                msgid,
+                Commitment {
+                    publisher_identifier,
+                    digest,
+                },
+            );
+        }
+        let mut amount_pending = Size::default();
+        self.mask(&mut amount_pending)?;
+        for _ in 0..amount_pending.inner() {
+            let mut topic = Topic::default();
+            let mut msgid = MsgId::default();
+            self.mask(&mut topic)?.mask(&mut msgid)?;
+            backup.0.pending_commitments.insert(topic, msgid);
+        }
 
         self.commit()?.squeeze(Mac::new(32))
     }
@@ -1740,8 +8505,10 @@ impl<T> Debug for User<T> {
                 .collect::<String>(),
             self.state
                 .spongos_store
-                .keys()
-                .map(|key| format!("\t<{}>\n", key))
+                .entries()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, _)| format!("\t<{}>\n", key))
                 .collect::<String>(),
             self.state.lean
         )
@@ -1761,3 +8528,1432 @@ impl<T> PartialEq for User<T> {
 /// this fact is that two users with the same identity but different transport configurations are
 /// considered equal
 impl<T> Eq for User<T> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, vec::Vec};
+    use core::{cell::RefCell, time::Duration};
+
+    use async_trait::async_trait;
+    use crypto::keys::x25519;
+    use futures::TryStreamExt;
+    use lets::{
+        address::{Address, AppAddr, LinkGenerator, MsgId},
+        error::Error as LetsError,
+        id::{Identifier, Permissioned, Psk, PskTree},
+        message::{Topic, TransportMessage},
+        transport::{bucket, mirror, MirrorStatus, Transport as _, TransportCapabilities},
+    };
+    use spongos::error::Error as SpongosError;
+
+    use crate::{
+        api::fixtures::{
+            author_subscriber_fixture, new_reader, new_transport, new_user, new_user_builder, IntermittentTransport,
+            Transport,
+        },
+        Countersignature, Error, Result,
+    };
+
+    use super::{HistoryDirection, Retention, User, BACKUP_VERSION, UNVERSIONED_BACKUP};
+
+    #[tokio::test]
+    async fn unversioned_backup_can_be_migrated_and_restored() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        author.create_stream("BASE_BRANCH").await?;
+
+        let backup = author.backup("password").await?;
+        assert_eq!(backup[0], BACKUP_VERSION);
+        // The unversioned layout is the current one without the version byte. The sections that
+        // follow the state are ignored when restoring it
+        let unversioned = &backup[1..];
+
+        assert!(User::<Transport>::restore(unversioned, "password", transport.clone())
+            .await
+            .is_err());
+
+        let migrated = User::<Transport>::migrate_backup(unversioned, UNVERSIONED_BACKUP, "password").await?;
+        let restored = User::restore(migrated, "password", transport).await?;
+        assert_eq!(author, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn empty_and_truncated_backups_are_reported() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        author.create_stream("BASE_BRANCH").await?;
+        let backup = author.backup("password").await?;
+
+        assert!(matches!(
+            User::<Transport>::restore(&[], "password", transport.clone()).await,
+            Err(Error::BackupTruncated)
+        ));
+        assert!(matches!(
+            User::<Transport>::restore(&backup[..backup.len() / 2], "password", transport).await,
+            Err(Error::BackupTruncated)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn history_follows_links_in_both_directions() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        author.create_stream("BASE_BRANCH").await?;
+        let mut packets = Vec::new();
+        for payload in ["first", "second", "third", "fourth"] {
+            let packet = author.send_signed_packet("BASE_BRANCH", payload, b"").await?;
+            packets.push(packet.address());
+        }
+        let payloads = |messages: Vec<crate::Message>| {
+            messages
+                .into_iter()
+                .map(|msg| msg.as_signed_packet().unwrap().public_payload.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let previous = author.fetch_history(packets[3], HistoryDirection::Backward, 2).await?;
+        assert_eq!(payloads(previous), [b"second".to_vec(), b"third".to_vec()]);
+
+        let next = author.fetch_history(packets[0], HistoryDirection::Forward, 2).await?;
+        assert_eq!(payloads(next), [b"second".to_vec(), b"third".to_vec()]);
+
+        // Replaying the history must not set the cursor of the author back
+        author.send_signed_packet("BASE_BRANCH", "fifth", b"").await?;
+        Ok(())
+    }
+
+    /// Numbers the messages of a single publisher stream sequentially
+    struct SequentialLinks;
+
+    impl LinkGenerator for SequentialLinks {
+        fn gen_msg_id(&self, _appaddr: AppAddr, _identifier: &Identifier, _topic: &Topic, seq_num: usize) -> MsgId {
+            let mut id = [0; 12];
+            id[4..].copy_from_slice(&(seq_num as u64).to_be_bytes());
+            MsgId::new(id)
+        }
+    }
+
+    #[tokio::test]
+    async fn collision_diagnostics_report_the_inputs_of_colliding_messages() -> Result<()> {
+        let mut transport = new_transport();
+        let mut other_transport = new_transport();
+        let user = |seed: &str, transport: &Transport| {
+            new_user_builder(seed, transport)
+                .with_link_generator(SequentialLinks)
+                .build()
+        };
+        let mut author = user("author", &transport);
+        let mut alice = user("alice", &transport);
+        let mut bob = user("bob", &other_transport);
+
+        // The subscriptions of alice and bob are both derived at sequence 0, ignoring their
+        // publisher. Bob subscribes through another transport, as his subscription would otherwise
+        // be refused, and his message is copied to the address of the subscription of alice.
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let announcement_msg = transport.recv_message(announcement.address()).await?;
+        other_transport
+            .send_message(announcement.address(), announcement_msg)
+            .await?;
+        alice.receive_message(announcement.address()).await?;
+        bob.receive_message(announcement.address()).await?;
+        let subscription = alice.subscribe().await?.address();
+        let bob_subscription = bob.subscribe().await?.address();
+        assert_eq!(subscription, bob_subscription);
+        let bob_msg = other_transport.recv_message(bob_subscription).await?;
+        transport.send_message(subscription, bob_msg).await?;
+
+        let error = author.receive_message(subscription).await.unwrap_err();
+        assert!(matches!(error, Error::Transport(..)));
+
+        author.set_collision_diagnostics(true);
+        match author.receive_message(subscription).await {
+            Err(Error::AddressCollision(address, first, second)) => {
+                assert_eq!(address, subscription);
+                let mut publishers = [first.publisher, second.publisher];
+                publishers.sort();
+                let mut expected = [alice.identifier().unwrap().clone(), bob.identifier().unwrap().clone()];
+                expected.sort();
+                assert_eq!(publishers, expected);
+                assert_eq!((first.seq_num, second.seq_num), (0, 0));
+                assert_eq!(first.topic, Topic::from("BASE_BRANCH"));
+            }
+            other => panic!(
+                "expected an address collision, got {:?}",
+                other.map(|msg| msg.address())
+            ),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn custom_link_generator_is_used_by_publishers_and_readers() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport)
+            .with_link_generator(SequentialLinks)
+            .build();
+        let mut reader = User::builder()
+            .with_transport(transport)
+            .with_link_generator(SequentialLinks)
+            .build();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        assert_eq!(
+            announcement.address().relative(),
+            MsgId::new([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1])
+        );
+        let packet = author.send_signed_packet("BASE_BRANCH", b"public", b"").await?;
+        assert_eq!(
+            packet.address().relative(),
+            MsgId::new([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2])
+        );
+
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].address(), packet.address());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn selective_packet_fields_are_only_readable_by_their_recipients() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut alice = new_user("alice", &transport);
+        let mut bob = new_user("bob", &transport);
+        let mut outsider = new_reader(&transport);
+        let alice_id = alice.identifier().unwrap().clone();
+        let bob_id = bob.identifier().unwrap().clone();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let fields = vec![
+            (vec![alice_id.clone()], b"for alice".to_vec()),
+            (vec![bob_id.clone()], b"for bob".to_vec()),
+            (vec![alice_id, bob_id], b"for both".to_vec()),
+        ];
+        author.send_selective_packet("BASE_BRANCH", fields).await?;
+
+        let mut readable = Vec::new();
+        for reader in [&mut alice, &mut bob, &mut outsider] {
+            reader.receive_message(announcement.address()).await?;
+            let messages = reader.fetch_next_messages().await?;
+            assert_eq!(messages.len(), 1);
+            readable.push(messages[0].as_selective_packet().unwrap().fields.clone());
+        }
+        assert_eq!(
+            readable[0],
+            [Some(b"for alice".to_vec()), None, Some(b"for both".to_vec())]
+        );
+        assert_eq!(
+            readable[1],
+            [None, Some(b"for bob".to_vec()), Some(b"for both".to_vec())]
+        );
+        assert_eq!(readable[2], [None, None, None]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fields_exceeding_the_size_limit_are_rejected() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut subscriber = new_user_builder("subscriber", &transport).with_size_limit(512).build();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        let packet = author
+            .send_signed_packet("BASE_BRANCH", b"public", vec![0u8; 1000])
+            .await?;
+
+        let result = subscriber.receive_message(packet.address()).await;
+        assert!(matches!(
+            result,
+            Err(Error::Unwrapping(
+                _,
+                _,
+                LetsError::Spongos(SpongosError::InvalidSize("bytes", 512, 1000))
+            ))
+        ));
+
+        subscriber.set_size_limit(1000);
+        let message = subscriber.receive_message(packet.address()).await?;
+        assert_eq!(message.as_signed_packet().unwrap().masked_payload.len(), 1000);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batches_are_processed_in_order() -> Result<()> {
+        let (mut author, mut subscriber, announcement_link, _) = author_subscriber_fixture().await?;
+        let mut addresses = vec![author.send_keyload_for_all("BASE_BRANCH").await?.address()];
+        for i in 0..5u8 {
+            let packet = author.send_signed_packet("BASE_BRANCH", b"public", [i]).await?;
+            addresses.push(packet.address());
+        }
+
+        let mut batch = Vec::new();
+        for address in addresses {
+            batch.push((address, subscriber.fetch_raw_message(address).await?));
+        }
+        let messages = subscriber.handle_messages_batch(batch).await?;
+        assert_eq!(messages.len(), 6);
+        assert!(messages[0].is_keyload());
+        for (i, message) in messages[1..].iter().enumerate() {
+            assert_eq!(message.as_signed_packet().unwrap().masked_payload, [i as u8]);
+        }
+
+        // Packets whose signature is forged stop the batch, even when verified along valid packets
+        let mut addresses = Vec::new();
+        for i in 0..4u8 {
+            let packet = author.send_signed_packet("BASE_BRANCH", b"public", [i]).await?;
+            addresses.push(packet.address());
+        }
+        let mut batch = Vec::new();
+        for address in &addresses {
+            batch.push((*address, subscriber.fetch_raw_message(*address).await?));
+        }
+        let mut forged = Vec::<u8>::from(batch[2].1.clone());
+        *forged.last_mut().unwrap() ^= 1;
+        batch[2].1 = TransportMessage::new(forged);
+        let result = subscriber.handle_messages_batch(batch).await;
+        assert!(matches!(result, Err(Error::Unwrapping("signed packet", address, _)) if address == addresses[2]));
+
+        // Headers that cannot be decoded stop the batch
+        let result = subscriber
+            .handle_messages_batch(vec![(announcement_link, TransportMessage::new(vec![0; 10]))])
+            .await;
+        assert!(matches!(result, Err(Error::Unwrapping("header", ..))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn strict_streams_reject_unsigned_packets() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_strict_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        assert!(subscriber.is_strict());
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"signed").await?;
+        assert!(matches!(
+            author.send_tagged_packet("BASE_BRANCH", b"public", b"tagged").await,
+            Err(Error::StrictChannel("unsigned packets", _))
+        ));
+
+        // A publisher ignoring the strictness of the stream cannot downgrade its readers
+        author.state.strict = false;
+        let tagged = author.send_tagged_packet("BASE_BRANCH", b"public", b"tagged").await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        assert!(messages.iter().any(|message| message.is_signed_packet()));
+        assert!(!messages.iter().any(|message| message.is_tagged_packet()));
+        assert!(matches!(
+            subscriber.receive_message(tagged.address()).await,
+            Err(Error::StrictChannel("unsigned packets", address)) if address == announcement.address()
+        ));
+
+        let backup = subscriber.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert!(restored.is_strict());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keyload_checkpoints_recompute_dropped_states() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user_builder("author", &transport).keyload_checkpoints().build();
+        let mut subscriber = new_user_builder("subscriber", &transport).keyload_checkpoints().build();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        let keyload = author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        let first = author.send_signed_packet("BASE_BRANCH", b"public", b"first").await?;
+        let second = author.send_signed_packet("BASE_BRANCH", b"public", b"second").await?;
+        let third = author.send_tagged_packet("BASE_BRANCH", b"public", b"third").await?;
+        subscriber.sync().await?;
+
+        // Only the states of the keyload and of the latest packet are kept besides the announcement
+        for user in [&mut author, &mut subscriber] {
+            assert!(user.state.spongos_store.get(&keyload.address().relative())?.is_some());
+            assert!(user.state.spongos_store.get(&first.address().relative())?.is_none());
+            assert!(user.state.spongos_store.get(&second.address().relative())?.is_none());
+            assert!(user.state.spongos_store.get(&third.address().relative())?.is_some());
+        }
+
+        // Reading the second packet again recomputes the state of the first one from the keyload
+        let report = subscriber.verify_chain(second.address(), third.address()).await?;
+        assert!(report.is_verified());
+        assert!(subscriber
+            .state
+            .spongos_store
+            .get(&second.address().relative())?
+            .is_none());
+
+        let backup = subscriber.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(subscriber, restored);
+
+        author.send_signed_packet("BASE_BRANCH", b"public", b"fourth").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keyloads_are_encrypted_to_rotated_exchange_keys() -> Result<()> {
+        let (mut author, mut subscriber, _, transport) = author_subscriber_fixture().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber.sync().await?;
+
+        subscriber.rotate_exchange_key().await?;
+        let messages = author.fetch_next_messages().await?;
+        let key_update = messages.last().unwrap().as_key_update().unwrap();
+        assert_eq!(key_update.identifier(), subscriber.identifier().unwrap());
+        assert_eq!(key_update.generation, 1);
+
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        let packet = messages.last().unwrap().as_signed_packet().unwrap();
+        assert_eq!(packet.masked_payload, b"masked");
+
+        let backup = subscriber.backup("password").await?;
+        let restored = User::restore(backup, "password", transport.clone()).await?;
+        assert_eq!(subscriber, restored);
+        let backup = author.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(author, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keyloads_are_decrypted_by_the_key_exchange() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let key_exchange = x25519::SecretKey::from_bytes([7; x25519::SECRET_KEY_LENGTH]);
+        let public_key = key_exchange.public_key();
+        let mut subscriber = new_user_builder("subscriber", &transport)
+            .with_key_exchange(key_exchange)
+            .build();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber.sync().await?;
+
+        // The key of the key exchange is announced instead of a generated one
+        subscriber.rotate_exchange_key().await?;
+        let messages = author.fetch_next_messages().await?;
+        let key_update = messages.last().unwrap().as_key_update().unwrap();
+        assert_eq!(key_update.exchange_key, public_key.to_bytes());
+
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        let packet = messages.last().unwrap().as_signed_packet().unwrap();
+        assert_eq!(packet.masked_payload, b"masked");
+
+        // The key exchange is not part of the backup, and must be set again
+        let backup = subscriber.backup("password").await?;
+        let mut restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(subscriber, restored);
+        let keyload = author.send_keyload_for_all("BASE_BRANCH").await?;
+        assert!(matches!(
+            restored.receive_message(keyload.address()).await,
+            Err(Error::Setup(_))
+        ));
+        restored.set_key_exchange(x25519::SecretKey::from_bytes([7; x25519::SECRET_KEY_LENGTH]));
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"restored").await?;
+        let messages = restored.fetch_next_messages().await?;
+        let packet = messages.last().unwrap().as_signed_packet().unwrap();
+        assert_eq!(packet.masked_payload, b"restored");
+        Ok(())
+    }
+
+    #[cfg(feature = "post-quantum")]
+    #[tokio::test]
+    async fn dilithium_publishers_are_verified_by_readers() -> Result<()> {
+        use lets::id::Dilithium;
+
+        let transport = new_transport();
+        let mut author = User::builder()
+            .with_identity(Dilithium::generate())
+            .with_transport(transport.clone())
+            .build();
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+
+        let messages = subscriber.fetch_next_messages().await?;
+        let packet = messages.last().unwrap().as_signed_packet().unwrap();
+        assert!(packet.publisher_identifier.is_dilithium());
+        assert_eq!(packet.masked_payload, b"masked");
+
+        let backup = author.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(author, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closed_branches_are_surfaced_to_readers() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_reader(&transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let branch = author.new_branch("BASE_BRANCH", "CLOSED_BRANCH").await?;
+        let closure = author.close_branch("CLOSED_BRANCH").await?;
+        assert!(author.is_branch_closed(&Topic::from("CLOSED_BRANCH")));
+        assert!(matches!(
+            author.send_signed_packet("CLOSED_BRANCH", b"public", b"").await,
+            Err(Error::BranchClosed(_))
+        ));
+        assert!(matches!(
+            author.close_branch("CLOSED_BRANCH").await,
+            Err(Error::BranchClosed(_))
+        ));
+        // Other branches are left open
+        author.send_signed_packet("BASE_BRANCH", b"public", b"").await?;
+
+        reader.receive_message(announcement.address()).await?;
+        reader.receive_message(branch.address()).await?;
+        let message = reader.receive_message(closure.address()).await?;
+        let closed = message
+            .as_branch_closed()
+            .expect("closure should be surfaced as a closed branch");
+        assert_eq!(closed.topic, Topic::from("CLOSED_BRANCH"));
+        assert_eq!(Some(&closed.publisher_identifier), author.identifier());
+        assert!(reader.is_branch_closed(&Topic::from("CLOSED_BRANCH")));
+
+        let restored = User::restore(author.backup("password").await?, "password", transport).await?;
+        assert!(restored.is_branch_closed(&Topic::from("CLOSED_BRANCH")));
+        assert_eq!(restored.closed_topics().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keyloads_and_reads_can_target_a_topic_prefix() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_stream("factory").await?;
+        subscriber.receive_message(announcement.address()).await?;
+        subscriber.subscribe().await?;
+        author.sync().await?;
+        author.new_branch("factory", "factory/line1").await?;
+        author.new_branch("factory/line1", "factory/line1/temp").await?;
+        author.new_branch("factory", "factory/line10").await?;
+
+        let subscriber_id = subscriber.identifier().unwrap().clone();
+        let keyloads = author
+            .send_keyload_for_prefix("factory/line1", vec![Permissioned::Read(&subscriber_id)], vec![])
+            .await?;
+        assert_eq!(keyloads.len(), 2);
+        assert!(matches!(
+            author
+                .send_keyload_for_prefix("warehouse", vec![Permissioned::Read(&subscriber_id)], vec![])
+                .await,
+            Err(Error::TopicNotFound(_))
+        ));
+        for topic in ["factory", "factory/line1", "factory/line1/temp", "factory/line10"] {
+            author.send_signed_packet(topic, topic, b"").await?;
+        }
+
+        let prefix = Topic::from("factory/line1");
+        let messages = subscriber
+            .messages()
+            .filter_topic_prefix(prefix.clone())
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert!(messages
+            .iter()
+            .all(|msg| subscriber.topic_by_hash(msg.topic_hash()).unwrap().has_prefix(&prefix)));
+        assert_eq!(messages.iter().filter(|msg| msg.is_keyload()).count(), 2);
+        let packets = messages
+            .iter()
+            .filter_map(|msg| msg.as_signed_packet())
+            .map(|packet| packet.public_payload.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(packets.len(), 2);
+        assert!(packets.contains(&b"factory/line1".to_vec()));
+        assert!(packets.contains(&b"factory/line1/temp".to_vec()));
+        assert_eq!(subscriber.topics_with_prefix(&prefix).count(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_the_state_needed_to_continue_the_stream() -> Result<()> {
+        let (mut author, mut subscriber, _, _) = author_subscriber_fixture().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        for payload in ["first", "second", "third"] {
+            author.send_signed_packet("BASE_BRANCH", payload, b"").await?;
+        }
+        subscriber.sync().await?;
+
+        let backup = author.backup("password").await?;
+        assert_eq!(author.prune(Retention::Cursor(0))?, 0);
+        // The states of the keyload and of the first two packets are dropped, the latest packet is kept
+        assert_eq!(author.prune(Retention::Cursor(usize::MAX))?, 3);
+        assert_eq!(subscriber.prune(Retention::Time(u64::MAX))?, 3);
+        assert!(author.backup("password").await?.len() < backup.len());
+
+        let packet = author.send_signed_packet("BASE_BRANCH", "fourth", b"").await?;
+        let message = subscriber.receive_message(packet.address()).await?;
+        assert_eq!(message.as_signed_packet().unwrap().public_payload, b"fourth");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ordering_metadata_is_exposed_on_messages() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_reader(&transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let first = author.send_signed_packet("BASE_BRANCH", b"first", b"").await?;
+        let second = author.send_signed_packet("BASE_BRANCH", b"second", b"").await?;
+
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].address(), first.address());
+        assert_eq!(messages[1].address(), second.address());
+        for message in &messages {
+            assert_eq!(message.publisher(), author.identifier().unwrap());
+            assert_eq!(message.linked_address(), Some(announcement.address()));
+            assert_eq!(
+                reader.topic_by_hash(message.topic_hash()),
+                Some(Topic::from("BASE_BRANCH"))
+            );
+        }
+        assert_eq!(messages[1].sequence(), messages[0].sequence() + 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conflicting_announcements_are_reported() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut attacker = new_user("attacker", &bucket::Client::new());
+        let mut subscriber = new_user("subscriber", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        // Copies of the announcement do not conflict with it
+        let copy = transport.borrow_mut().recv_message(announcement.address()).await?;
+        transport
+            .borrow_mut()
+            .send_message(announcement.address(), copy)
+            .await?;
+        assert!(subscriber.receive_message(announcement.address()).await.is_ok());
+
+        // The attacker posts their own announcement at the address of the stream
+        let forged = attacker.create_stream("BASE_BRANCH").await?;
+        let forged = attacker.transport_mut().recv_message(forged.address()).await?;
+        transport
+            .borrow_mut()
+            .send_message(announcement.address(), forged)
+            .await?;
+        let mut newcomer = new_user("newcomer", &transport);
+        assert!(matches!(
+            newcomer.receive_message(announcement.address()).await,
+            Err(Error::Conflict(_, "announcement"))
+        ));
+        let conflicts = newcomer.take_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].address, announcement.address());
+        let publishers: Vec<&Identifier> = conflicts[0].headers.iter().map(|header| header.publisher()).collect();
+        assert!(publishers.contains(&author.identifier().unwrap()));
+        assert!(publishers.contains(&attacker.identifier().unwrap()));
+        assert!(newcomer.take_conflicts().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn psks_are_kept_in_backups_and_addressed_by_keyloads() -> Result<()> {
+        let transport = new_transport();
+        let psk = Psk::from_seed("psk");
+        let mut author = new_user("author", &transport);
+        let mut reader = new_reader(&transport);
+        assert!(author.add_psk(psk));
+        assert!(!author.add_psk(psk));
+        assert!(reader.add_psk(psk));
+        assert!(author.has_psk(psk.to_pskid()));
+        assert_eq!(author.psks().copied().collect::<Vec<_>>(), vec![psk.to_pskid()]);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        assert!(matches!(
+            author
+                .send_keyload_for_psks("BASE_BRANCH", [Psk::from_seed("unknown").to_pskid()])
+                .await,
+            Err(Error::UnknownPsk(_))
+        ));
+        author.send_keyload_for_psks("BASE_BRANCH", [psk.to_pskid()]).await?;
+        author.send_signed_packet("BASE_BRANCH", "public", b"masked").await?;
+
+        let backup = author.backup("password").await?;
+        let mut restored = User::restore(backup, "password", transport.clone()).await?;
+        assert!(restored.has_psk(psk.to_pskid()));
+        assert!(restored.remove_psk(psk.to_pskid()));
+        assert_eq!(restored.psks().len(), 0);
+
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].masked_payload(), Some(&b"masked"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn substreams_are_discovered_from_their_parent_message() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_reader(&transport);
+
+        let announcement = author.create_stream("ASSETS").await?;
+        let asset = author.send_signed_packet("ASSETS", b"asset", b"pump").await?;
+        let substream = author.announce_substream(asset.address().relative(), "SENSORS").await?;
+        author.send_signed_packet("ASSETS", b"asset", b"valve").await?;
+
+        // The substream is operated by a user with the identity of the author
+        let mut sensors = new_user("author", &transport);
+        sensors.receive_message(substream.address()).await?;
+        sensors.send_signed_packet("SENSORS", b"temperature", b"21").await?;
+
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].linked_msg_address(), Some(asset.address().relative()));
+        let anchor = messages[1].as_substream_announcement().unwrap();
+        assert_eq!(anchor.address, substream.address());
+        assert_eq!(anchor.topic, Topic::from("SENSORS"));
+        assert_eq!(Some(&anchor.publisher_identifier), author.identifier());
+        let fetched = transport.borrow_mut().recv_message(anchor.address).await?;
+        assert!(anchor.anchors(&fetched));
+        assert!(!anchor.anchors(&transport.borrow_mut().recv_message(announcement.address()).await?));
+
+        let mut sensor_reader = new_reader(&transport);
+        sensor_reader.receive_message(anchor.address).await?;
+        let readings = sensor_reader.fetch_next_messages().await?;
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].masked_payload(), Some(&b"21"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queued_packets_are_flushed_by_priority_once_online() -> Result<()> {
+        let transport = Rc::new(RefCell::new(IntermittentTransport::default()));
+        let mut device = new_user("device", &transport);
+        let announcement = device.create_stream("BASE_BRANCH").await?;
+        device.set_send_queue_limit(3);
+
+        transport.borrow_mut().offline = true;
+        device.queue_packet("BASE_BRANCH", b"", b"reading 1", 0)?;
+        device.queue_packet("BASE_BRANCH", b"", b"alarm", 1)?;
+        assert_eq!(device.queue_packet("BASE_BRANCH", b"", b"reading 2", 0)?, 3);
+        assert!(matches!(
+            device.queue_packet("BASE_BRANCH", b"", b"reading 3", 0),
+            Err(Error::SendQueueFull(3))
+        ));
+        assert!(device.flush().await?.is_empty());
+        assert_eq!(device.queued_packets(), 3);
+
+        // The queue is kept across restarts of the device
+        let backup = device.backup("password").await?;
+        let mut device = User::restore(backup, "password", transport.clone()).await?;
+        assert_eq!(device.queued_packets(), 3);
+
+        // A bad reading is cancelled before the device is back online
+        let outbox: Vec<(u64, &[u8])> = device
+            .outbox()
+            .into_iter()
+            .map(|packet| (packet.id, &packet.masked_payload[..]))
+            .collect();
+        assert_eq!(outbox, [(1, &b"alarm"[..]), (0, b"reading 1"), (2, b"reading 2")]);
+        assert_eq!(
+            device.cancel(2).map(|packet| packet.masked_payload),
+            Some(b"reading 2".to_vec())
+        );
+        assert!(device.cancel(2).is_none());
+        device.queue_packet("BASE_BRANCH", b"", b"reading 2 corrected", 0)?;
+        assert_eq!(device.outbox().last().map(|packet| packet.id), Some(3));
+
+        transport.borrow_mut().offline = false;
+        assert_eq!(device.flush().await?.len(), 3);
+        assert_eq!(device.queued_packets(), 0);
+
+        let mut reader = new_reader(&transport);
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        let payloads: Vec<&[u8]> = messages.iter().filter_map(|message| message.masked_payload()).collect();
+        assert_eq!(payloads, [&b"alarm"[..], b"reading 1", b"reading 2 corrected"]);
+        Ok(())
+    }
+
+    /// Bucket transport reporting a maximum message size
+    struct SizeLimitedTransport {
+        bucket: bucket::Client,
+        max_message_size: usize,
+    }
+
+    #[async_trait(?Send)]
+    impl<'a> lets::transport::Transport<'a> for SizeLimitedTransport {
+        type Msg = TransportMessage;
+        type SendResponse = TransportMessage;
+
+        async fn send_message(
+            &mut self,
+            address: Address,
+            msg: TransportMessage,
+        ) -> lets::error::Result<TransportMessage>
+        where
+            'a: 'async_trait,
+        {
+            self.bucket.send_message(address, msg).await
+        }
+
+        async fn recv_messages(&mut self, address: Address) -> lets::error::Result<Vec<TransportMessage>>
+        where
+            'a: 'async_trait,
+        {
+            self.bucket.recv_messages(address).await
+        }
+
+        async fn capabilities(&mut self) -> TransportCapabilities {
+            TransportCapabilities {
+                max_message_size: Some(self.max_message_size),
+                ..TransportCapabilities::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn messages_larger_than_the_transport_accepts_are_not_sent() -> Result<()> {
+        let transport = Rc::new(RefCell::new(SizeLimitedTransport {
+            bucket: bucket::Client::new(),
+            max_message_size: 1024,
+        }));
+        let mut author = new_user("author", &transport);
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"", &[0; 512]).await?;
+        assert!(matches!(
+            author.send_signed_packet("BASE_BRANCH", b"", &[0; 2048]).await,
+            Err(Error::Transport(_, _, LetsError::MessageTooLarge(_, size, 1024))) if size > 2048
+        ));
+
+        // The refused packet does not break the chain of the author
+        author.send_signed_packet("BASE_BRANCH", b"", b"small").await?;
+        let mut reader = new_reader(&transport);
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].masked_payload(), Some(&b"small"[..]));
+        Ok(())
+    }
+
+    /// Bucket transport whose node reports itself unhealthy
+    struct UnhealthyTransport(bucket::Client);
+
+    #[async_trait(?Send)]
+    impl<'a> lets::transport::Transport<'a> for UnhealthyTransport {
+        type Msg = TransportMessage;
+        type SendResponse = TransportMessage;
+
+        async fn send_message(
+            &mut self,
+            address: Address,
+            msg: TransportMessage,
+        ) -> lets::error::Result<TransportMessage>
+        where
+            'a: 'async_trait,
+        {
+            self.0.send_message(address, msg).await
+        }
+
+        async fn recv_messages(&mut self, address: Address) -> lets::error::Result<Vec<TransportMessage>>
+        where
+            'a: 'async_trait,
+        {
+            self.0.recv_messages(address).await
+        }
+
+        async fn health_check(&mut self) -> lets::error::Result<()> {
+            Err(LetsError::Unhealthy("http://localhost:14265".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn health_checks_report_the_state_of_the_transport() -> Result<()> {
+        let mut healthy = User::builder().with_transport(bucket::Client::new()).build();
+        healthy.health_check().await?;
+
+        let mut unhealthy = User::builder()
+            .with_transport(UnhealthyTransport(bucket::Client::new()))
+            .build();
+        assert!(matches!(
+            unhealthy.health_check().await,
+            Err(Error::TransportUnhealthy(LetsError::Unhealthy(_)))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn psk_trees_address_the_devices_of_a_fleet() -> Result<()> {
+        let transport = new_transport();
+        let tree = PskTree::new("fleet master secret");
+        assert_eq!(tree.subtree("line1").derive("device1"), tree.derive("line1/device1"));
+        assert_ne!(tree.derive("line1/device1"), tree.derive("line1/device2"));
+        assert!(PskTree::is_revoked("line2/device3", &["line2"]));
+        assert!(!PskTree::is_revoked("line20/device3", &["line2"]));
+
+        let mut author = new_user("author", &transport);
+        let mut device1 = new_reader(&transport);
+        device1.add_psk(tree.derive("line1/device1"));
+        let devices = ["line1/device1", "line1/device2", "line2/device3"];
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        author
+            .send_keyload_for_psk_tree("BASE_BRANCH", &tree, devices, &[])
+            .await?;
+        assert_eq!(author.psks().len(), 3);
+        author
+            .send_keyload_for_psk_tree("BASE_BRANCH", &tree, devices, &["line2"])
+            .await?;
+        assert_eq!(author.psks().len(), 2);
+        assert!(!author.has_psk(tree.derive_id("line2/device3")));
+        author.send_signed_packet("BASE_BRANCH", "public", b"masked").await?;
+
+        device1.receive_message(announcement.address()).await?;
+        let messages = device1.fetch_next_messages().await?;
+        assert_eq!(messages.last().unwrap().masked_payload(), Some(&b"masked"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keyloads_reach_unsubscribed_identifiers_only_if_allowed() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut device = new_user("device", &transport);
+        let device_id = device.identifier().unwrap().clone();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        assert!(matches!(
+            author
+                .send_keyload("BASE_BRANCH", [Permissioned::Read(&device_id)], [])
+                .await,
+            Err(Error::NotSubscribed(id)) if id == device_id
+        ));
+
+        author.set_allow_unsubscribed(true);
+        assert!(author.allows_unsubscribed());
+        author
+            .send_keyload("BASE_BRANCH", [Permissioned::Read(&device_id)], [])
+            .await?;
+        author.send_signed_packet("BASE_BRANCH", "public", b"masked").await?;
+
+        // The device never sent a subscription, but can read the packets of the branch
+        device.receive_message(announcement.address()).await?;
+        let messages = device.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].masked_payload(), Some(&b"masked"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn registered_readers_are_granted_read_access_without_subscribing() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user("reader", &transport);
+        let reader_id = reader.identifier().unwrap().clone();
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        assert!(author.register_reader(reader_id.clone()));
+        assert!(!author.register_reader(reader_id.clone()));
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", "public", b"masked").await?;
+
+        // The reader never sent a subscription, but can read the packets of the branch
+        reader.receive_message(announcement.address()).await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].masked_payload(), Some(&b"masked"[..]));
+        // Registered readers are never granted write access
+        assert!(matches!(
+            author.permission_of(&reader_id, &"BASE_BRANCH".into()),
+            Some(Permissioned::Read(_))
+        ));
+
+        // The registration survives backups
+        let backup = author.backup("password").await?;
+        let mut restored = User::restore(backup, "password", transport.clone()).await?;
+        assert_eq!(restored.readers().collect::<Vec<_>>(), [&reader_id]);
+        assert!(restored.unregister_reader(&reader_id));
+        assert_eq!(restored.readers().count(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn permissions_reflect_the_last_keyload() -> Result<()> {
+        let (mut author, mut subscriber, _, transport) = author_subscriber_fixture().await?;
+        let topic = Topic::from("BASE_BRANCH");
+        let author_id = author.identifier().unwrap().clone();
+        let subscriber_id = subscriber.identifier().unwrap().clone();
+        assert_eq!(subscriber.my_permissions().count(), 0);
+
+        // Read-only recipients are known, although they are not tracked as publishers
+        author.send_keyload_for_all("BASE_BRANCH").await?;
+        subscriber.sync().await?;
+        assert_eq!(
+            subscriber.my_permissions().collect::<Vec<_>>(),
+            [(&topic, Permissioned::Read(&subscriber_id))]
+        );
+        assert_eq!(
+            author.permission_of(&subscriber_id, &topic),
+            Some(Permissioned::Read(&subscriber_id))
+        );
+        assert_eq!(
+            subscriber.permission_of(&author_id, &topic),
+            Some(Permissioned::Admin(&author_id))
+        );
+
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        subscriber.sync().await?;
+        assert!(matches!(
+            subscriber.permission_of(&subscriber_id, &topic),
+            Some(Permissioned::ReadWrite(..))
+        ));
+
+        // Recipients left out of a keyload lose their permissions
+        author
+            .send_keyload("BASE_BRANCH", core::iter::empty::<Permissioned<&Identifier>>(), [])
+            .await?;
+        subscriber.sync().await?;
+        assert_eq!(subscriber.permission_of(&subscriber_id, &topic), None);
+        assert_eq!(author.permission_of(&subscriber_id, &topic), None);
+
+        let backup = subscriber.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(subscriber, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_only_users_read_public_and_psk_branches() -> Result<()> {
+        let transport = new_transport();
+        let psk = Psk::from_seed("analytics psk");
+        let mut author = new_user_builder("author", &transport)
+            .with_psk(psk.to_pskid(), psk)
+            .build();
+
+        let announcement = author.create_stream("PUBLIC").await?;
+        author.send_signed_packet("PUBLIC", b"", b"public").await?;
+        let mut reader = User::builder()
+            .with_transport(transport.clone())
+            .with_psk(psk.to_pskid(), psk)
+            .read_only(announcement.address())
+            .await?;
+        assert!(reader.is_read_only());
+        assert!(reader.identifier().is_none());
+
+        author.new_branch("PUBLIC", "PRIVATE").await?;
+        author.send_keyload_for_psks("PRIVATE", [psk.to_pskid()]).await?;
+        author.send_signed_packet("PRIVATE", b"", b"private").await?;
+        author.send_signed_packet("PUBLIC", b"", b"public again").await?;
+        let messages = reader.fetch_next_messages().await?;
+        let payloads: Vec<&[u8]> = messages.iter().filter_map(|message| message.masked_payload()).collect();
+        assert!(payloads.contains(&&b"private"[..]));
+        assert!(payloads.contains(&&b"public again"[..]));
+
+        assert!(matches!(
+            reader.send_signed_packet("PUBLIC", b"", b"nope").await,
+            Err(Error::NoIdentity(_))
+        ));
+        assert!(matches!(
+            reader.queue_packet("PUBLIC", b"", b"nope", 0),
+            Err(Error::NoIdentity(_))
+        ));
+        assert!(matches!(
+            new_user_builder("reader", &transport)
+                .read_only(announcement.address())
+                .await,
+            Err(Error::Setup(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_markers_coordinate_the_consumers_of_a_group() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut consumer_a = new_user("consumer a", &transport);
+        let mut consumer_b = new_user("consumer b", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        consumer_a.receive_message(announcement.address()).await?;
+        consumer_b.receive_message(announcement.address()).await?;
+        consumer_a.subscribe().await?;
+        consumer_b.subscribe().await?;
+        author.sync().await?;
+        author.new_branch("BASE_BRANCH", "group").await?;
+        author.send_keyload_for_all_rw("group").await?;
+        let packet = author.send_signed_packet("BASE_BRANCH", b"", b"work").await?;
+        consumer_a.sync().await?;
+        consumer_b.sync().await?;
+        let group = Topic::from("group");
+        let consumer_a_id = consumer_a.identifier().unwrap().clone();
+
+        // The marker of a consumer is read by the other consumers of the group
+        consumer_a.send_read_marker("group", packet.address()).await?;
+        assert_eq!(consumer_a.read_marker(&group, &consumer_a_id), Some(packet.address()));
+        let messages = consumer_b.fetch_next_messages().await?;
+        let marker = messages.iter().find_map(|msg| msg.as_read_marker()).unwrap();
+        assert_eq!(marker.publisher_identifier, consumer_a_id);
+        assert_eq!(marker.marked_address, packet.address());
+        assert_eq!(
+            consumer_b.read_markers(&group).collect::<Vec<_>>(),
+            [(&consumer_a_id, &packet.address())]
+        );
+
+        // Markers are kept in backups
+        let backup = consumer_b.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(restored.read_marker(&group, &consumer_a_id), Some(packet.address()));
+        assert_eq!(consumer_b, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tombstones_redact_the_payloads_of_packets() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        reader.receive_message(announcement.address()).await?;
+        let packet = author
+            .send_signed_packet("BASE_BRANCH", b"public", b"personal data")
+            .await?;
+        let msgid = packet.address().relative();
+        let tombstone = author.send_tombstone("BASE_BRANCH", msgid, "erasure request").await?;
+        assert_eq!(author.redaction(&msgid), Some("erasure request"));
+
+        // The packet is read before its tombstone, which redacts it afterwards
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].masked_payload(), Some(&b"personal data"[..]));
+        let redaction = messages[1].as_tombstone().unwrap();
+        assert_eq!(redaction.redacted, msgid);
+        assert_eq!(redaction.reason, "erasure request");
+        assert_eq!(reader.redaction(&msgid), Some("erasure request"));
+
+        // The payloads are withheld, the original content stays available on request
+        let redacted = reader.redact(messages[0].clone());
+        assert_eq!(redacted.masked_payload(), None);
+        assert_eq!(redacted.public_payload(), None);
+        let redacted = redacted.as_redacted().unwrap();
+        assert_eq!(redacted.tombstone, tombstone.address());
+        assert_eq!(redacted.original().masked_payload(), Some(&b"personal data"[..]));
+
+        // Only the author redacts messages
+        assert!(matches!(
+            reader.send_tombstone("BASE_BRANCH", msgid, "no reason").await,
+            Err(Error::WrongRole(..))
+        ));
+
+        // Redactions are kept in backups
+        let backup = reader.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(restored.redaction(&msgid), Some("erasure request"));
+        assert_eq!(reader, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn countersignatures_of_trusted_witnesses_are_attached_to_packets() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut witness = new_user("witness", &transport);
+        let mut reader = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        witness.receive_message(announcement.address()).await?;
+        reader.receive_message(announcement.address()).await?;
+        witness.subscribe().await?;
+        reader.subscribe().await?;
+        author.sync().await?;
+        let witness_id = witness.identifier().unwrap().clone();
+        let keyload = author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        let packet = author.send_signed_packet("BASE_BRANCH", b"reading", b"42").await?;
+        let msgid = packet.address().relative();
+
+        // The packet is published before being countersigned
+        witness.sync().await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert!(messages.iter().all(|message| message.countersignatures().is_empty()));
+        let countersignature = witness.countersign("BASE_BRANCH", msgid).await?;
+        let expected = Countersignature {
+            witness: witness_id.clone(),
+            countersigned: msgid,
+        };
+        assert_eq!(witness.countersignatures(&msgid), &[expected.clone()]);
+
+        // Readers trusting the witness record the countersignature
+        assert!(reader.add_witness(witness_id.clone()));
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].as_countersignature(), Some(&expected));
+        assert_eq!(reader.countersignatures(&msgid), &[expected.clone()]);
+
+        // And attach it to the packet when it is read again
+        let history = reader
+            .fetch_history(keyload.address(), HistoryDirection::Forward, 10)
+            .await?;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].address(), packet.address());
+        assert_eq!(history[0].countersignatures(), &[expected.clone()]);
+
+        // Users not trusting the witness reject its countersignatures
+        assert!(matches!(
+            author.receive_message(countersignature.address()).await,
+            Err(Error::WrongRole("witness", _, _))
+        ));
+        assert!(author.countersignatures(&msgid).is_empty());
+
+        // Witnesses and countersignatures are kept in backups
+        let backup = reader.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(restored.witnesses().collect::<Vec<_>>(), vec![&witness_id]);
+        assert_eq!(restored.countersignatures(&msgid), &[expected]);
+        assert_eq!(reader, restored);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn access_granted_by_an_expiring_keyload_lapses() -> Result<()> {
+        let (mut author, mut subscriber, _, transport) = author_subscriber_fixture().await?;
+        let subscriber_id = subscriber.identifier().unwrap().clone();
+        let topic = Topic::from("BASE_BRANCH");
+
+        // Packets are read while the access lasts
+        author
+            .send_keyload_with_expiry("BASE_BRANCH", [Permissioned::Read(&subscriber_id)], [], u64::MAX)
+            .await?;
+        author.send_signed_packet("BASE_BRANCH", b"", b"before expiry").await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        assert_eq!(messages[0].as_keyload().unwrap().expires_at, Some(u64::MAX));
+        assert_eq!(subscriber.access_expiry(&topic), Some(u64::MAX));
+        assert_eq!(messages[1].masked_payload(), Some(&b"before expiry"[..]));
+
+        // The payloads of the packets published once it expired are left masked, as are the ones
+        // of the packets linked to them
+        author
+            .send_keyload_with_expiry("BASE_BRANCH", [Permissioned::Read(&subscriber_id)], [], 1)
+            .await?;
+        author
+            .send_signed_packet("BASE_BRANCH", b"public", b"after expiry")
+            .await?;
+        author
+            .send_signed_packet("BASE_BRANCH", b"public", b"linked to an expired packet")
+            .await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        assert_eq!(
+            messages[1].as_access_expired().map(|expired| expired.expired_at),
+            Some(1)
+        );
+        assert_eq!(messages[1].public_payload(), None);
+        assert_eq!(
+            messages[2].as_access_expired().map(|expired| expired.expired_at),
+            Some(1)
+        );
+
+        // The author granting the access records it as well, to stamp its packets
+        assert_eq!(author.access_expiry(&topic), Some(1));
+
+        // The expiry is kept in backups
+        let backup = subscriber.backup("password").await?;
+        let restored = User::restore(backup, "password", transport).await?;
+        assert_eq!(restored.access_expiry(&topic), Some(1));
+
+        // Until a fresh keyload is read
+        author
+            .send_keyload("BASE_BRANCH", [Permissioned::Read(&subscriber_id)], [])
+            .await?;
+        author.send_signed_packet("BASE_BRANCH", b"", b"renewed").await?;
+        let messages = subscriber.fetch_next_messages().await?;
+        assert_eq!(subscriber.access_expiry(&topic), None);
+        assert_eq!(messages[1].masked_payload(), Some(&b"renewed"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn messages_of_slow_links_are_read_once_their_latency_elapses() -> Result<()> {
+        let latency = bucket::Latency::new(Duration::from_millis(500), Duration::from_millis(250));
+        let transport = Rc::new(RefCell::new(bucket::Client::new().with_latency(latency)));
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        assert!(reader.receive_message(announcement.address()).await.is_err());
+        transport.borrow_mut().advance(Duration::from_millis(750));
+        reader.receive_message(announcement.address()).await?;
+
+        // Packets in flight are not found yet, the stream ends without them
+        author.send_signed_packet("BASE_BRANCH", b"", b"slow").await?;
+        assert!(reader.fetch_next_messages().await?.is_empty());
+        assert_eq!(transport.borrow().in_flight(), 1);
+        transport.borrow_mut().advance(Duration::from_millis(750));
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].masked_payload(), Some(&b"slow"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn messages_are_fetched_by_their_content_digest() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let packet = author
+            .send_signed_packet("BASE_BRANCH", b"", b"keyed by digest")
+            .await?;
+        let digest = *packet.digest().unwrap();
+
+        // The sender and the readers agree on the digest of the message
+        reader.receive_message(announcement.address()).await?;
+        let read = reader.fetch_by_digest(digest).await?;
+        assert_eq!(read.address(), packet.address());
+        assert_eq!(read.digest(), Some(&digest));
+        assert_eq!(read.masked_payload(), Some(&b"keyed by digest"[..]));
+        assert_eq!(
+            transport.borrow_mut().recv_message(packet.address()).await?.digest(),
+            digest
+        );
+
+        assert!(matches!(
+            reader.fetch_by_digest([0; 32]).await,
+            Err(Error::Wrapped(_, LetsError::DigestNotFound(_)))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rotated_author_key_takes_over_the_stream() -> Result<()> {
+        let transport = new_transport();
+        let mut author = new_user("author", &transport);
+        let mut successor = new_user("successor", &transport);
+        let mut reader = new_user("reader", &transport);
+
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        successor.receive_message(announcement.address()).await?;
+        reader.receive_message(announcement.address()).await?;
+
+        // A copy of the previous author that never learned of the rotation
+        let backup = author.backup("password").await?;
+        let mut rogue_transport = new_transport();
+        let mut rogue = User::restore(backup, "password", rogue_transport.clone()).await?;
+
+        let successor_id = successor.identifier().unwrap().clone();
+        author.rotate_author(successor_id.clone()).await?;
+        assert_eq!(author.author(), Some(&successor_id));
+        let author_id = author.identifier().unwrap().clone();
+        assert!(matches!(
+            author.rotate_author(author_id).await,
+            Err(Error::WrongRole("author", _, _))
+        ));
+        // The previous author is demoted in every branch
+        assert!(matches!(
+            author.send_keyload_for_all("BASE_BRANCH").await,
+            Err(Error::WrongRole(..))
+        ));
+
+        // The successor takes over once it verified the rotation signed by the previous author
+        let rotations = successor.fetch_next_messages().await?;
+        assert_eq!(rotations.len(), 1);
+        let rotation = rotations[0].as_author_rotation().unwrap();
+        assert_eq!(rotation.new_author, successor_id);
+        assert_eq!(successor.author(), Some(&successor_id));
+        successor.add_subscriber(reader.identifier().unwrap().clone());
+        successor.send_keyload_for_all("BASE_BRANCH").await?;
+        successor
+            .send_signed_packet("BASE_BRANCH", b"", b"from the new author")
+            .await?;
+
+        // Readers accept the keyloads of the new author after the rotation
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].is_author_rotation());
+        assert!(messages[1].is_keyload());
+        assert_eq!(messages[2].masked_payload(), Some(&b"from the new author"[..]));
+        assert_eq!(reader.author(), Some(&successor_id));
+
+        // Keyloads still signed with the previous author key are rejected
+        rogue.add_subscriber(reader.identifier().unwrap().clone());
+        let rogue_keyload = rogue.send_keyload_for_all("BASE_BRANCH").await?;
+        let msg = rogue_transport.recv_message(rogue_keyload.address()).await?;
+        assert!(matches!(
+            reader.handle_message(rogue_keyload.address(), msg).await,
+            Err(Error::WrongRole("admin", _, _))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn copies_of_messages_published_on_two_networks_are_compared() -> Result<()> {
+        let transport = Rc::new(RefCell::new(mirror::Client::new(
+            bucket::Client::new(),
+            bucket::Client::new(),
+        )));
+        let mut author = new_user("author", &transport);
+        let mut reader = new_user("reader", &transport);
+
+        // Messages sent through the mirror transport are found on both networks
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let received = reader.receive_message(announcement.address()).await?;
+        assert_eq!(received.mirror_status(), Some(MirrorStatus::Matching));
+        reader.subscribe().await?;
+        author.sync().await?;
+        author.send_keyload_for_all_rw("BASE_BRANCH").await?;
+        author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 2);
+        assert!(messages
+            .iter()
+            .all(|message| message.mirror_status() == Some(MirrorStatus::Matching)));
+
+        // Copies differing between the networks are reported, the message of the primary network
+        // being processed
+        let packet = author.send_signed_packet("BASE_BRANCH", b"public", b"masked").await?;
+        transport
+            .borrow_mut()
+            .secondary_mut()
+            .send_message(packet.address(), TransportMessage::new(vec![1, 2, 3]))
+            .await?;
+        let messages = reader.fetch_next_messages().await?;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].address(), packet.address());
+        assert!(messages[0].as_signed_packet().is_some());
+        assert_eq!(messages[0].mirror_status(), Some(MirrorStatus::Mismatching));
+
+        // Messages received with a single network have no status
+        let single = new_transport();
+        let mut author = new_user("author", &single);
+        let mut reader = new_reader(&single);
+        let announcement = author.create_stream("BASE_BRANCH").await?;
+        let received = reader.receive_message(announcement.address()).await?;
+        assert_eq!(received.mirror_status(), None);
+        Ok(())
+    }
+}
@@ -1,21 +1,38 @@
 // Rust
-use alloc::vec::Vec;
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 
 // IOTA
 
 // Streams
 use lets::{
-    address::Address,
-    id::{Identity, Psk, PskId},
+    address::{Address, DefaultLinkGenerator, LinkGenerator},
+    id::{Identity, KeyExchange, Psk, PskId},
     message::TransportMessage,
+    sync::MaybeSend,
     transport::Transport,
 };
+use spongos::ddml::commands::unwrap;
 
 #[cfg(feature = "utangle-client")]
 use lets::transport::utangle;
 
 // Local
-use crate::{api::user::User, Result};
+use crate::{
+    api::{
+        descriptor::ChannelDescriptor,
+        message_filter::MessageFilter,
+        messages::OrphanLimit,
+        metrics::Metrics,
+        notarizer::{Notarization, Notarizer},
+        payload_middleware::PayloadMiddleware,
+        replay::ReplayRecorder,
+        seen_messages::DEFAULT_REPLAY_WINDOW,
+        spongos_store::SpongosStore,
+        subscription_policy::SubscriptionPolicy,
+        user::{User, DEFAULT_SYNC_LOOKAHEAD},
+    },
+    Error, Result,
+};
 
 /// Builder instance for a Streams [`User`].
 pub struct UserBuilder<T> {
@@ -27,6 +44,42 @@ pub struct UserBuilder<T> {
     psks: Vec<(PskId, Psk)>,
     /// Spongos Storage Type.
     lean: bool,
+    /// Whether only the spongos states of the keyloads are kept
+    keyload_checkpoints: bool,
+    /// Bound on buffered orphan messages.
+    orphan_limit: Option<OrphanLimit>,
+    /// Message address derivation.
+    link_generator: Option<Box<dyn LinkGenerator>>,
+    /// Forward secrecy of the keyloads.
+    forward_secrecy: bool,
+    /// Notarization of the branches.
+    notarization: Option<Notarization>,
+    /// Bound on the lengths and item counts read from messages.
+    size_limit: usize,
+    /// Approval of subscription requests.
+    subscription_policy: SubscriptionPolicy,
+    /// Storage of the spongos states.
+    spongos_store: Option<Box<dyn SpongosStore>>,
+    /// Keyloads to identifiers that did not subscribe.
+    allow_unsubscribed: bool,
+    /// Screening of the messages before they are unwrapped.
+    message_filter: Option<Box<dyn MessageFilter>>,
+    /// Holder of the key exchange key.
+    key_exchange: Option<Box<dyn KeyExchange>>,
+    /// Registry the metrics are recorded in.
+    metrics: Option<Arc<Metrics>>,
+    /// Recording of the processed messages.
+    replay_recorder: Option<ReplayRecorder>,
+    /// Processed messages remembered to detect duplicates.
+    replay_window: usize,
+    /// Future cursors of a publisher probed at once while syncing.
+    sync_lookahead: usize,
+    /// Checking of the messages found at the same address for address collisions.
+    collision_diagnostics: bool,
+    /// Sending of the repeated payloads as references to the packet that first carried them.
+    payload_deduplication: bool,
+    /// Transformation of the masked payloads of the packets sent and read.
+    payload_middleware: Option<PayloadMiddleware>,
 }
 
 impl Default for UserBuilder<()> {
@@ -36,6 +89,24 @@ impl Default for UserBuilder<()> {
             transport: (),
             psks: Default::default(),
             lean: false,
+            keyload_checkpoints: false,
+            orphan_limit: None,
+            link_generator: None,
+            forward_secrecy: false,
+            notarization: None,
+            size_limit: unwrap::DEFAULT_SIZE_LIMIT,
+            subscription_policy: SubscriptionPolicy::default(),
+            spongos_store: None,
+            allow_unsubscribed: false,
+            message_filter: None,
+            key_exchange: None,
+            metrics: None,
+            replay_recorder: None,
+            replay_window: DEFAULT_REPLAY_WINDOW,
+            sync_lookahead: DEFAULT_SYNC_LOOKAHEAD,
+            collision_diagnostics: false,
+            payload_deduplication: false,
+            payload_middleware: None,
         }
     }
 }
@@ -66,6 +137,223 @@ impl<T> UserBuilder<T> {
         self
     }
 
+    /// Set the User Builder to keep the spongos states of the keyloads only, besides the states of
+    /// the stream announcement and of the latest message of every branch. The states of the other
+    /// messages are recomputed when needed, by fetching and processing again the messages
+    /// following the last keyload, trading processing time for a smaller state on constrained
+    /// devices.
+    pub fn keyload_checkpoints(mut self) -> Self {
+        self.keyload_checkpoints = true;
+        self
+    }
+
+    /// Set the User Builder forward secrecy state to true. The keyloads sent by the User switch
+    /// their branch to forward secrecy mode: the keys of the publishers of the branch advance with
+    /// every packet, so that a compromise of the state of a reader does not reveal the packets it
+    /// already processed. The keys of the packets skipped by a reader are kept until the packets are
+    /// read, so packets received out of order can still be read, as long as a packet is at most
+    /// 1000 packets ahead of the last one read from its publisher. The key of a packet is erased
+    /// once it is read.
+    pub fn forward_secrecy(mut self) -> Self {
+        self.forward_secrecy = true;
+        self
+    }
+
+    /// Allow the keyloads sent by the User to grant permissions to identifiers that never
+    /// subscribed to the stream, like the identifiers of devices provisioned out of band before
+    /// they come online. By default, keyloads are restricted to the subscribers of the stream
+    /// and the participants already known in the branch.
+    pub fn allow_unsubscribed(mut self) -> Self {
+        self.allow_unsubscribed = true;
+        self
+    }
+
+    /// Bound the number of orphan messages buffered by the [`Messages`](crate::Messages) streams of
+    /// the User. Orphans are buffered without bound if not set.
+    ///
+    /// # Arguments
+    /// * `orphan_limit` - The [`OrphanLimit`] and eviction policy to apply
+    pub fn with_orphan_limit(mut self, orphan_limit: OrphanLimit) -> Self {
+        self.orphan_limit = Some(orphan_limit);
+        self
+    }
+
+    /// Inject a [`LinkGenerator`] deriving the addresses of the stream messages into the User
+    /// Builder. Every participant of a stream must use the same generator. Defaults to
+    /// [`DefaultLinkGenerator`], which derives the addresses expected by IOTA Tangle transports.
+    /// The generator only changes how addresses are derived, they remain [`Address`]es.
+    ///
+    /// # Arguments
+    /// * `link_generator` - The [`LinkGenerator`] to be used by the Streams User
+    pub fn with_link_generator<G>(mut self, link_generator: G) -> Self
+    where
+        G: LinkGenerator + 'static,
+    {
+        self.link_generator = Some(Box::new(link_generator));
+        self
+    }
+
+    /// Inject a [`Notarizer`] into the User Builder. The User hashes the messages it sends and
+    /// reads, anchors a [`Checkpoint`](crate::Checkpoint) through the notarizer every `interval`
+    /// messages it sends on a branch, and verifies the messages it reads against the checkpoints
+    /// anchored by their publishers.
+    ///
+    /// # Arguments
+    /// * `notarizer` - The [`Notarizer`] anchoring and retrieving the checkpoints
+    /// * `interval` - Number of messages of a publisher on a branch covered by a checkpoint. Must
+    ///   not be 0.
+    pub fn with_notarizer<N>(mut self, notarizer: N, interval: usize) -> Self
+    where
+        N: Notarizer + 'static,
+    {
+        self.notarization = Some(Notarization::new(Box::new(notarizer), interval));
+        self
+    }
+
+    /// Bound the lengths and item counts the User reads from the messages it processes, such as
+    /// payload sizes or the number of subscribers of a keyload. Messages exceeding it are rejected
+    /// instead of being allocated. Defaults to
+    /// [`DEFAULT_SIZE_LIMIT`](spongos::ddml::commands::unwrap::DEFAULT_SIZE_LIMIT).
+    ///
+    /// # Arguments
+    /// * `size_limit` - The largest length or item count accepted
+    pub fn with_size_limit(mut self, size_limit: usize) -> Self {
+        self.size_limit = size_limit;
+        self
+    }
+
+    /// Set the [`SubscriptionPolicy`] the User applies to the subscription requests it processes.
+    /// Defaults to [`SubscriptionPolicy::AcceptAll`].
+    ///
+    /// # Arguments
+    /// * `subscription_policy` - The [`SubscriptionPolicy`] to apply
+    pub fn with_subscription_policy(mut self, subscription_policy: SubscriptionPolicy) -> Self {
+        self.subscription_policy = subscription_policy;
+        self
+    }
+
+    /// Set the [`SpongosStore`] holding the spongos states of the messages processed by the User,
+    /// for example a [`LruSpongosStore`](crate::LruSpongosStore) bounding the states held in
+    /// memory. Defaults to holding every state in memory.
+    ///
+    /// # Arguments
+    /// * `spongos_store` - The [`SpongosStore`] holding the states
+    pub fn with_spongos_store<S>(mut self, spongos_store: S) -> Self
+    where
+        S: SpongosStore + 'static,
+    {
+        self.spongos_store = Some(Box::new(spongos_store));
+        self
+    }
+
+    /// Set the [`MessageFilter`] screening the messages processed by the User before they are
+    /// unwrapped, for example a [`SpamFilter`](crate::SpamFilter) dropping oversized messages.
+    /// Defaults to processing every message.
+    ///
+    /// # Arguments
+    /// * `message_filter` - The [`MessageFilter`] screening the messages
+    pub fn with_message_filter<F>(mut self, message_filter: F) -> Self
+    where
+        F: MessageFilter + 'static,
+    {
+        self.message_filter = Some(Box::new(message_filter));
+        self
+    }
+
+    /// Set the [`KeyExchange`] holding the key exchange key of the User, for example a key held by a
+    /// KMS or an HSM. Once the User rotates its key exchange key with
+    /// [`User::rotate_exchange_key()`], the key of the [`KeyExchange`] is announced and the keys of
+    /// the keyloads and selective packets encrypted to it are recovered through it, without
+    /// exporting its secret. Defaults to generating the rotated keys in the User.
+    ///
+    /// # Arguments
+    /// * `key_exchange` - The [`KeyExchange`] holding the key
+    pub fn with_key_exchange<K>(mut self, key_exchange: K) -> Self
+    where
+        K: KeyExchange + 'static,
+    {
+        self.key_exchange = Some(Box::new(key_exchange));
+        self
+    }
+
+    /// Set the [`Metrics`] registry the User records its message counts, sync and send durations
+    /// and transport failures in. The registry can be shared by several users. Defaults to
+    /// recording nothing.
+    ///
+    /// # Arguments
+    /// * `metrics` - The [`Metrics`] registry
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Set the [`ReplayRecorder`] capturing the messages processed by the User with the outcome of
+    /// their processing, to reproduce them offline with [`User::replay()`]. Defaults to recording
+    /// nothing.
+    ///
+    /// # Arguments
+    /// * `replay_recorder` - The [`ReplayRecorder`] capturing the messages
+    pub fn with_replay_recorder(mut self, replay_recorder: ReplayRecorder) -> Self {
+        self.replay_recorder = Some(replay_recorder);
+        self
+    }
+
+    /// Set the number of processed messages the User remembers to detect the messages received
+    /// again, which are returned as
+    /// [`MessageContent::DuplicateReceived`](crate::MessageContent::DuplicateReceived) without
+    /// being processed again. Defaults to the last 1024 messages, zero disables the detection.
+    ///
+    /// # Arguments
+    /// * `replay_window` - The number of processed messages remembered
+    pub fn with_replay_window(mut self, replay_window: usize) -> Self {
+        self.replay_window = replay_window;
+        self
+    }
+
+    /// Set the number of future cursors of a publisher the User probes at once while syncing.
+    /// Larger windows fetch the backlog of a publisher concurrently instead of one message per
+    /// round trip. Defaults to one, advancing each cursor one message at a time.
+    ///
+    /// # Arguments
+    /// * `sync_lookahead` - The number of cursors probed at once, zero being treated as one
+    pub fn with_sync_lookahead(mut self, sync_lookahead: usize) -> Self {
+        self.sync_lookahead = sync_lookahead.max(1);
+        self
+    }
+
+    /// Check the messages found at the same address for address collisions. When several messages
+    /// are found where a single one is expected, the [`LinkGenerator`] inputs of each of them are
+    /// recovered from their headers, and if distinct inputs map to the msg_index of the address,
+    /// receiving fails with [`Error::AddressCollision`](crate::Error::AddressCollision) naming
+    /// both inputs, instead of a generic transport error. Meant for diagnosing custom link
+    /// generators.
+    pub fn with_collision_diagnostics(mut self) -> Self {
+        self.collision_diagnostics = true;
+        self
+    }
+
+    /// Send the signed packets whose payloads are identical to the ones of the last signed packet
+    /// of the user on their branch as repeated packets, carrying the message id of the packet that
+    /// first carried the payloads and their digest instead of the payloads. Readers restore the
+    /// payloads transparently. Meant for heartbeat-like publishers repeating the same payloads.
+    pub fn with_payload_deduplication(mut self) -> Self {
+        self.payload_deduplication = true;
+        self
+    }
+
+    /// Set the [`PayloadMiddleware`] transforming the masked payloads of the signed and tagged
+    /// packets of the User: its transforms are applied in order to the payloads sent, like
+    /// compressing, then encrypting at the application level, then signing externally, and
+    /// inverted in reverse order on the payloads read. Defaults to sending and reading the payloads
+    /// unchanged.
+    ///
+    /// # Arguments
+    /// * `payload_middleware` - The [`PayloadMiddleware`] chaining the transforms
+    pub fn with_payload_middleware(mut self, payload_middleware: PayloadMiddleware) -> Self {
+        self.payload_middleware = Some(payload_middleware);
+        self
+    }
+
     /// Inject [`Transport`] Client instance into the User Builder
     ///
     /// # Arguments
@@ -79,6 +367,24 @@ impl<T> UserBuilder<T> {
             id: self.id,
             psks: self.psks,
             lean: self.lean,
+            keyload_checkpoints: self.keyload_checkpoints,
+            orphan_limit: self.orphan_limit,
+            link_generator: self.link_generator,
+            forward_secrecy: self.forward_secrecy,
+            notarization: self.notarization,
+            size_limit: self.size_limit,
+            subscription_policy: self.subscription_policy,
+            spongos_store: self.spongos_store,
+            allow_unsubscribed: self.allow_unsubscribed,
+            message_filter: self.message_filter,
+            key_exchange: self.key_exchange,
+            metrics: self.metrics,
+            replay_recorder: self.replay_recorder,
+            replay_window: self.replay_window,
+            sync_lookahead: self.sync_lookahead,
+            collision_diagnostics: self.collision_diagnostics,
+            payload_deduplication: self.payload_deduplication,
+            payload_middleware: self.payload_middleware,
         }
     }
 
@@ -140,7 +446,30 @@ impl<T> UserBuilder<T> {
         T: IntoTransport<Trans>,
         Trans: for<'a> Transport<'a>,
     {
-        User::new(self.id, self.psks, self.transport.into(), self.lean)
+        User::new(
+            self.id,
+            self.psks,
+            self.transport.into(),
+            self.lean,
+            self.keyload_checkpoints,
+            self.orphan_limit,
+            self.link_generator.unwrap_or_else(|| Box::new(DefaultLinkGenerator)),
+            self.forward_secrecy,
+            self.notarization,
+            self.size_limit,
+            self.subscription_policy,
+            self.spongos_store.unwrap_or_default(),
+            self.allow_unsubscribed,
+            self.message_filter,
+            self.key_exchange,
+            self.metrics,
+            self.replay_recorder,
+            self.replay_window,
+            self.sync_lookahead,
+            self.collision_diagnostics,
+            self.payload_deduplication,
+            self.payload_middleware,
+        )
     }
 
     /// Recover a user instance from the builder parameters.
@@ -158,9 +487,9 @@ impl<T> UserBuilder<T> {
     /// need to be reapplied manually.
     ///
     /// # Errors
-    /// This function will produce errors if the [`User`] tries to recover their
-    /// instance without a proper [`Identity`]. It will also return an error
-    /// if the provided announcement link is not present on the transport layer.
+    /// This function will produce errors if the provided announcement link is
+    /// not present on the transport layer. A [`User`] recovered without an
+    /// [`Identity`] is read-only (see [`UserBuilder::read_only()`]).
     ///
     /// # Example
     /// ```
@@ -194,13 +523,60 @@ impl<T> UserBuilder<T> {
     pub async fn recover<Trans>(self, announcement: Address) -> Result<User<Trans>>
     where
         T: IntoTransport<Trans>,
-        Trans: for<'a> Transport<'a, Msg = TransportMessage>,
+        Trans: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
     {
         let mut user = self.build();
         user.receive_message(announcement).await?;
         user.sync().await?;
         Ok(user)
     }
+
+    /// Build a read-only user following the channel of an announcement, without an [`Identity`].
+    /// The user receives the announcement and syncs, reading the public branches and the branches
+    /// of the [Pre-Shared Keys](`Psk`) added to the builder. Sending a message fails with
+    /// [`Error::NoIdentity`], so readers do not need to generate a throwaway seed.
+    ///
+    /// # Arguments
+    /// * `announcement` - The [`Address`] of the announcement of the channel
+    ///
+    /// # Errors
+    /// This function will produce errors if an [`Identity`] was injected into the builder, or if
+    /// the announcement is not present on the transport layer.
+    pub async fn read_only<Trans>(self, announcement: Address) -> Result<User<Trans>>
+    where
+        T: IntoTransport<Trans>,
+        Trans: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
+    {
+        if self.id.is_some() {
+            return Err(Error::Setup("a read-only user is built without an identity"));
+        }
+        self.recover(announcement).await
+    }
+
+    /// Build a user joining the channel of a [`ChannelDescriptor`], shared by its author when
+    /// pairing a device. The announcement of the channel is received, but the user is not synced.
+    ///
+    /// # Arguments
+    /// * `descriptor` - The [`ChannelDescriptor`] of the channel
+    ///
+    /// # Errors
+    /// This function will produce errors if the announcement is not present on the transport layer,
+    /// or if it was not published by the author of the descriptor.
+    pub async fn from_descriptor<Trans>(self, descriptor: &ChannelDescriptor) -> Result<User<Trans>>
+    where
+        T: IntoTransport<Trans>,
+        Trans: for<'a> Transport<'a, Msg = TransportMessage> + MaybeSend,
+    {
+        let mut user = self.build();
+        let announcement = user.receive_message(descriptor.announcement()).await?;
+        if !announcement.is_announcement() || announcement.publisher() != descriptor.author() {
+            return Err(Error::DescriptorMismatch(
+                descriptor.announcement(),
+                descriptor.author().clone(),
+            ));
+        }
+        Ok(user)
+    }
 }
 
 pub trait IntoTransport<T>
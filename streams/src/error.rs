@@ -1,6 +1,7 @@
 //! Stream Errors
 
 // Rust
+use alloc::string::String;
 use core::{array::TryFromSliceError, fmt::Debug};
 
 // 3rd-party
@@ -9,8 +10,8 @@ use thiserror_no_std::Error;
 
 // Streams
 use lets::{
-    address::{Address, MsgId},
-    error::Error as LetsError,
+    address::{Address, LinkInputs, MsgId},
+    error::{CompactError, Error as LetsError},
     id::{Identifier, PskId},
     message::{Topic, TopicHash},
 };
@@ -21,16 +22,69 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
 /// Error type of the streams crate.
+///
+/// Every error carries a stable numeric [code](Error::code), in the `2000` range, and a
+/// [remediation hint](Error::hint), so that applications and foreign language bindings can react to
+/// categories of errors without parsing their descriptions. With the `std` feature, the LETS error
+/// causing an error, with its own code in the `1000` range, is available as its `source()`.
 #[allow(clippy::large_enum_variant)]
 pub enum Error {
     //////////
     // Streams
     //////////
+    #[error("The actor owning the user stopped running, the command cannot be performed")]
+    ActorStopped,
+
     #[error(
         "Address already taken. The address '{1}' where the {0} message is being sent already contains some data, possibly spam."
     )]
     AddressUsed(&'static str, Address),
 
+    #[error(
+        "Address collision at '{0}'. The messages of {1} and of {2} map to the same msg_index, the link generator must derive distinct addresses for them"
+    )]
+    AddressCollision(Address, LinkInputs, LinkInputs),
+
+    #[error(
+        "Backup version {0} is not supported. Backups created with a previous layout must be converted with `User::migrate_backup` before restoring them"
+    )]
+    BackupVersion(u8),
+
+    #[error("The backup is empty or truncated, it ends before the user state it holds")]
+    BackupTruncated,
+
+    #[error("Branch '{0}' is closed, no further message can be published in it")]
+    BranchClosed(Topic),
+
+    #[error(
+        "The messages of publisher '{1}' in branch '{0}' up to sequence {2} do not match the checkpoint anchored by the publisher"
+    )]
+    CheckpointMismatch(Topic, Identifier, usize),
+
+    #[error("Conflicting {1} messages found at address {0}")]
+    Conflict(Address, &'static str),
+
+    #[error("Unexpected payload content type {1:?}, expected content type {0}")]
+    ContentTypeMismatch(u8, Option<u8>),
+
+    #[error("Invalid cursor export: {0}")]
+    CursorExportInvalid(&'static str),
+
+    #[error("The message received at address '{0}' does not match the content digest it was looked up by")]
+    DigestMismatch(Address),
+
+    #[error("Invalid channel descriptor: {0}")]
+    DescriptorInvalid(&'static str),
+
+    #[error("The announcement at address '{0}' was not published by the author of the channel descriptor, {1}")]
+    DescriptorMismatch(Address, Identifier),
+
+    #[error("Invite rejected: {0}")]
+    InviteRejected(&'static str),
+
+    #[error("Message at address {0} was dropped by the message filter: {1}")]
+    MessageFiltered(Address, &'static str),
+
     #[error("Unexpected message type {0}")]
     MessageTypeUnknown(u8),
 
@@ -62,18 +116,70 @@ Any {0} message must be linked to a previous message by including the address of
     )]
     NotLinked(&'static str, Address),
 
+    #[error(
+        "Identifier {0} did not subscribe to the stream. Keyloads can only grant permissions to identifiers that did not subscribe if the user allows it"
+    )]
+    NotSubscribed(Identifier),
+
+    #[error(
+        "Reference to message '{0:#?}' cannot be verified. A reference can only be verified by a user that processed the cited message"
+    )]
+    ReferenceUnverifiable(Address),
+
+    #[error("The send queue is full ({0} packets). Packets can only be queued once the queued ones are flushed")]
+    SendQueueFull(usize),
+
+    #[error(
+        "The buffer of orphan messages is full. The message '{0}' is discarded until the message '{1}' it is linked to is processed, it can then be read with User::receive_message()"
+    )]
+    OrphanLimitExceeded(Address, Address),
+
     #[error("A payload must be specified in order to send a message")]
     PayloadEmpty,
 
+    #[error("Failed to {0} structured payload: {1}")]
+    PayloadEncoding(&'static str, String),
+
+    #[error("No key ratchet of publisher {0:?} on forward secrecy branch {1} can derive the key of message {2}")]
+    RatchetUnavailable(Identifier, Topic, usize),
+
+    #[error(
+        "Message {2} of publisher {0:?} on forward secrecy branch {1} is too far ahead of the key ratchet of the publisher, deriving its key would skip more than 1000 keys"
+    )]
+    RatchetSkipExceeded(Identifier, Topic, usize),
+
+    #[error("Invalid quorum: {0}")]
+    QuorumInvalid(&'static str),
+
+    #[error(
+        "Quorum not reached: {0} valid approvals out of the {1} required. Quorum streams only accept announcements and author rotations approved by the threshold of their members"
+    )]
+    QuorumNotReached(usize, usize),
+
+    #[error("Replay log version {0} is not supported")]
+    ReplayLogVersion(u8),
+
+    #[error("The reveal at address '{0}' does not match the commitment of its publisher: {1}")]
+    RevealMismatch(Address, &'static str),
+
+    #[error("No {0} runtime is running on the current thread, the background task cannot be spawned")]
+    RuntimeUnavailable(&'static str),
+
     #[error("Setup error: {0}")]
     Setup(&'static str),
 
+    #[error("The strict channel {1} forbids {0}")]
+    StrictChannel(&'static str, Address),
+
     #[error("Topic {0} not found in store")]
     TopicNotFound(Topic),
 
     #[error("Transport error while trying to {1} for address {0}; Error: {2}")]
     Transport(Address, &'static str, LetsError),
 
+    #[error("The transport cannot reach the messaging layer; Error: {0}")]
+    TransportUnhealthy(LetsError),
+
     #[error("PSK by id {0} is not known")]
     UnknownPsk(PskId),
 
@@ -97,6 +203,216 @@ Any {0} message must be linked to a previous message by including the address of
     Wrapped(&'static str, lets::error::Error),
 }
 
+impl Error {
+    /// Returns the stable numeric code of the error. Codes are never reused for another kind of
+    /// error, even when a variant is removed.
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::AddressUsed(..) => 2001,
+            Self::BackupVersion(..) => 2002,
+            Self::BranchClosed(..) => 2003,
+            Self::CheckpointMismatch(..) => 2004,
+            Self::Conflict(..) => 2005,
+            Self::ContentTypeMismatch(..) => 2006,
+            Self::InviteRejected(..) => 2007,
+            Self::MessageFiltered(..) => 2008,
+            Self::MessageTypeUnknown(..) => 2009,
+            Self::MessageMissing(..) => 2010,
+            Self::Messages(..) => 2011,
+            Self::NoCursor(..) => 2012,
+            Self::NoIdentity(..) => 2013,
+            Self::NoSecretKey => 2014,
+            Self::NoStream(..) => 2015,
+            Self::NotLinked(..) => 2016,
+            Self::OrphanLimitExceeded(..) => 2017,
+            Self::PayloadEmpty => 2018,
+            Self::PayloadEncoding(..) => 2019,
+            Self::RatchetUnavailable(..) => 2020,
+            Self::ReplayLogVersion(..) => 2021,
+            Self::Setup(..) => 2022,
+            Self::TopicNotFound(..) => 2023,
+            Self::Transport(..) => 2024,
+            Self::UnknownPsk(..) => 2025,
+            Self::UnknownTopic(..) => 2026,
+            Self::Unwrapping(..) => 2027,
+            Self::WrongRole(..) => 2028,
+            Self::Spongos(..) => 2029,
+            Self::External(..) => 2030,
+            Self::Wrapped(..) => 2031,
+            Self::NotSubscribed(..) => 2032,
+            Self::ReferenceUnverifiable(..) => 2033,
+            Self::SendQueueFull(..) => 2034,
+            Self::DescriptorInvalid(..) => 2035,
+            Self::DescriptorMismatch(..) => 2036,
+            Self::AddressCollision(..) => 2037,
+            Self::TransportUnhealthy(..) => 2038,
+            Self::StrictChannel(..) => 2039,
+            Self::CursorExportInvalid(..) => 2040,
+            Self::DigestMismatch(..) => 2041,
+            Self::ActorStopped => 2042,
+            Self::QuorumInvalid(..) => 2043,
+            Self::QuorumNotReached(..) => 2044,
+            Self::RevealMismatch(..) => 2045,
+            Self::BackupTruncated => 2046,
+            Self::RatchetSkipExceeded(..) => 2047,
+            Self::RuntimeUnavailable(..) => 2048,
+        }
+    }
+
+    /// Returns a hint on how to remedy the error. Transport errors return the hint of the
+    /// underlying LETS error.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::AddressUsed(..) => {
+                "the address was taken by another message, possibly spam: publish the message again"
+            }
+            Self::BackupVersion(..) => "convert the backup with `User::migrate_backup` before restoring it",
+            Self::BackupTruncated => "restore the complete backup, as returned by `User::backup`",
+            Self::BranchClosed(..) => "publish in another branch, or create a new branch",
+            Self::CheckpointMismatch(..) => {
+                "the messages of the publisher were altered or withheld: do not trust them and contact the publisher"
+            }
+            Self::Conflict(..) => "inspect the conflicts returned by `User::take_conflicts` and remove the spam",
+            Self::ContentTypeMismatch(..) => "read the payload with the content type it was published with",
+            Self::InviteRejected(..) => "ask the author of the stream for a new invite",
+            Self::MessageFiltered(..) => "adjust the message filter of the user if the message is legitimate",
+            Self::MessageTypeUnknown(..) => "upgrade the library, the message was published by a newer version",
+            Self::MessageMissing(..) => "sync the user, the message it is linked to was not processed yet",
+            Self::Messages(..) => "see the source of the error, then sync the user again",
+            Self::NoCursor(..) => "ask the author of the stream for write permission in the branch",
+            Self::NoIdentity(..) => "build the user with an identity",
+            Self::NoSecretKey => "build the user with an identity holding its secret key",
+            Self::NoStream(..) => "create a stream, or receive the announcement of an existing stream, first",
+            Self::NotLinked(..) => "sync the user, the message it is linked to was not processed yet",
+            Self::OrphanLimitExceeded(..) => {
+                "read the message with `User::receive_message` once its parent is processed, or raise the orphan limit"
+            }
+            Self::PayloadEmpty => "provide a public or a masked payload",
+            Self::PayloadEncoding(..) => "check that the payload matches the structure it is encoded or decoded as",
+            Self::RatchetUnavailable(..) => {
+                "the key of the message was discarded by forward secrecy and cannot be recovered"
+            }
+            Self::RatchetSkipExceeded(..) => {
+                "the sequence number of the message is likely forged: ignore it and keep reading the branch"
+            }
+            Self::ReplayLogVersion(..) => "read the replay log with the version of the library that recorded it",
+            Self::RuntimeUnavailable(..) => "start the background task from within the runtime, or pass a runtime to it",
+            Self::Setup(..) => "check the configuration of the user",
+            Self::StrictChannel(..) => {
+                "the message may be spoofed: strict channels only accept signed messages in the current format"
+            }
+            Self::TopicNotFound(..) => "create the branch, or sync the user to receive its announcement",
+            Self::Transport(_, _, error) | Self::TransportUnhealthy(error) => error.hint(),
+            Self::UnknownPsk(..) => "store the pre shared key in the user with `User::add_psk`",
+            Self::UnknownTopic(..) => "sync the user to receive the announcement of the branch",
+            Self::Unwrapping(..) => {
+                "the message is malformed or was altered, or the user has no access to the branch it belongs to"
+            }
+            Self::WrongRole(..) => "ask the author of the stream for the required permission",
+            Self::Spongos(..) => "the message is malformed or was altered, or the wrong keys are used to read it",
+            Self::External(..) => "see the source of the error for details",
+            Self::Wrapped(_, error) => error.hint(),
+            Self::NotSubscribed(..) => {
+                "add the identifier with `User::add_subscriber`, or build the user with `UserBuilder::allow_unsubscribed`"
+            }
+            Self::ReferenceUnverifiable(..) => {
+                "sync a user of the channel of the cited message, and verify the reference with that user"
+            }
+            Self::SendQueueFull(..) => "flush the queue once the transport is reachable, or raise its limit",
+            Self::DescriptorInvalid(..) => "scan the descriptor again, it was truncated or altered",
+            Self::DescriptorMismatch(..) => {
+                "ask the author for a new descriptor, this one does not match the channel it points to"
+            }
+            Self::AddressCollision(..) => {
+                "use a link generator whose addresses do not collide, the colliding messages cannot be read"
+            }
+            Self::DigestMismatch(..) => "the transport returned another message than the one requested: do not trust it",
+            Self::CursorExportInvalid(..) => {
+                "export the cursors again from a user of the same stream, these were truncated or altered"
+            }
+            Self::ActorStopped => "spawn a new actor for the user, the previous one ended or panicked",
+            Self::QuorumInvalid(..) => "list distinct members, with a threshold between 1 and the number of members",
+            Self::QuorumNotReached(..) => {
+                "collect the approvals of more members, on the proposal of the message actually sent"
+            }
+            Self::RevealMismatch(..) => "reveal the exact payload committed to, once the commitment was processed",
+        }
+    }
+
+    /// Returns a static summary of the error, without the values it carries. Unlike the description
+    /// of the error, the summary is rendered without any formatting machinery or allocation. See
+    /// [`Error::compact()`].
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Self::AddressUsed(..) => "address already taken",
+            Self::BackupVersion(..) => "unsupported backup version",
+            Self::BackupTruncated => "truncated backup",
+            Self::BranchClosed(..) => "branch closed",
+            Self::CheckpointMismatch(..) => "checkpoint mismatch",
+            Self::Conflict(..) => "conflicting messages",
+            Self::ContentTypeMismatch(..) => "content type mismatch",
+            Self::InviteRejected(..) => "invite rejected",
+            Self::MessageFiltered(..) => "message filtered",
+            Self::MessageTypeUnknown(..) => "unknown message type",
+            Self::MessageMissing(..) => "message missing",
+            Self::Messages(..) => "failed to get messages",
+            Self::NoCursor(..) => "no cursor in branch",
+            Self::NoIdentity(..) => "no identity",
+            Self::NoSecretKey => "no secret key",
+            Self::NoStream(..) => "not connected to a stream",
+            Self::NotLinked(..) => "message not linked",
+            Self::OrphanLimitExceeded(..) => "orphan buffer full",
+            Self::PayloadEmpty => "empty payload",
+            Self::PayloadEncoding(..) => "structured payload error",
+            Self::RatchetUnavailable(..) => "key ratchet unavailable",
+            Self::RatchetSkipExceeded(..) => "key ratchet skip exceeded",
+            Self::ReplayLogVersion(..) => "unsupported replay log version",
+            Self::RuntimeUnavailable(..) => "runtime unavailable",
+            Self::Setup(..) => "setup error",
+            Self::StrictChannel(..) => "forbidden by strict channel",
+            Self::TopicNotFound(..) => "topic not found",
+            Self::Transport(..) => "transport error",
+            Self::TransportUnhealthy(..) => "transport unhealthy",
+            Self::UnknownPsk(..) => "unknown PSK",
+            Self::UnknownTopic(..) => "unknown topic",
+            Self::Unwrapping(..) => "unwrapping error",
+            Self::WrongRole(..) => "missing role",
+            Self::Spongos(..) => "spongos error",
+            Self::External(..) => "external error",
+            Self::Wrapped(..) => "internal error",
+            Self::NotSubscribed(..) => "identifier not subscribed",
+            Self::ReferenceUnverifiable(..) => "reference unverifiable",
+            Self::SendQueueFull(..) => "send queue full",
+            Self::DescriptorInvalid(..) => "invalid channel descriptor",
+            Self::DescriptorMismatch(..) => "channel descriptor mismatch",
+            Self::AddressCollision(..) => "address collision",
+            Self::DigestMismatch(..) => "content digest mismatch",
+            Self::CursorExportInvalid(..) => "invalid cursor export",
+            Self::ActorStopped => "actor stopped",
+            Self::QuorumInvalid(..) => "invalid quorum",
+            Self::QuorumNotReached(..) => "quorum not reached",
+            Self::RevealMismatch(..) => "reveal mismatch",
+        }
+    }
+
+    /// Returns the compact rendering of the error: its code and its [summary](Error::summary),
+    /// followed by those of the LETS error causing it, if any.
+    ///
+    /// Under `std`, the [`Display`](core::fmt::Display) implementation of the error describes it
+    /// with the values it carries. Embedded builds rendering their errors only through
+    /// [`CompactError`] leave the formatting machinery of `core` out of their binary.
+    pub fn compact(&self) -> CompactError {
+        let compact = CompactError::new(self.code(), self.summary());
+        match self {
+            Self::Transport(_, _, error)
+            | Self::TransportUnhealthy(error)
+            | Self::Unwrapping(_, _, error)
+            | Self::Wrapped(_, error) => compact.with_cause(error.compact()),
+            _ => compact,
+        }
+    }
+}
+
 impl From<SpongosError> for Error {
     fn from(error: SpongosError) -> Self {
         Self::Spongos(error)
@@ -121,4 +437,45 @@ impl From<anyhow::Error> for Error {
 extern crate std;
 
 #[cfg(feature = "std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(_, _, error)
+            | Self::TransportUnhealthy(error)
+            | Self::Unwrapping(_, _, error)
+            | Self::Wrapped(_, error) => Some(error),
+            Self::Spongos(error) => Some(error),
+            Self::Messages(error) | Self::External(error) => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use lets::address::Address;
+
+    use crate::{
+        api::fixtures::{new_transport, new_user},
+        Result,
+    };
+
+    #[tokio::test]
+    async fn errors_render_compactly_with_their_cause() -> Result<()> {
+        let mut reader = new_user("reader", &new_transport());
+
+        let error = reader.receive_message(Address::default()).await.unwrap_err();
+        let compact = error.compact();
+        assert_eq!((compact.code(), compact.cause_code()), (2024, Some(1107)));
+        assert_eq!(compact.to_string(), "E2024: transport error (E1107: message not found)");
+        assert_eq!(
+            compact
+                .localized(|code| (code == 2024).then(|| "erreur de transport"))
+                .to_string(),
+            "E2024: erreur de transport (E1107: message not found)"
+        );
+        Ok(())
+    }
+}
@@ -37,6 +37,11 @@
 #[macro_use]
 extern crate alloc;
 
+// The background tasks run on multithreaded executors outside of the browser, so their futures must
+// be `Send`
+#[cfg(all(feature = "auto-sync", not(feature = "threadsafe"), not(target_arch = "wasm32")))]
+compile_error!("the `auto-sync` feature requires the `threadsafe` feature outside of wasm32");
+
 /// Protocol message types and encodings
 mod message;
 
@@ -44,17 +49,71 @@ mod message;
 mod api;
 
 pub use api::{
-    message::{Message, MessageContent},
+    branch_metadata::BranchMetadata,
+    branch_rotation::{BranchRotation, RotationPeriod},
+    channel_manager::{ChannelManager, ChannelMessages},
+    codec::MessageCodec,
+    commitment::{commitment_digest, COMMITMENT_DIGEST_SIZE},
+    cursor_export::CursorExport,
+    custom_message::{CustomContent, CUSTOM_MESSAGE_TYPES},
+    descriptor::ChannelDescriptor,
+    detached::{verify_detached, DetachedSignature},
+    discovery::{discover, discovery_address},
+    invite::{Invite, InviteToken},
+    message::{
+        AccessExpired, AuthorRotation, BatchPacket, BatchRecord, BranchClosed, Commitment, Countersignature,
+        CustomMessage, DuplicateReceived, KeyUpdate, Message, MessageContent, OutOfOrder, ReadMarker, Redacted,
+        Rejected, Reveal, SelectivePacket, SubstreamAnnounced, Tombstone,
+    },
     message_builder::MessageBuilder,
-    messages::Messages,
+    message_filter::{FilterVerdict, MessageFilter, SpamFilter},
+    messages::{Messages, OrphanEviction, OrphanLimit},
+    metrics::Metrics,
+    notarizer::{Checkpoint, Notarizer},
+    packet_reader::SignedPacketReader,
+    payload::ContentType,
+    payload_middleware::{PayloadMiddleware, PayloadTransform},
+    payload_validator::{PayloadValidator, ValidationVerdict},
+    provenance::{ProvenanceEntry, ProvenanceReport, ProvenanceStatus},
+    quorum::{Quorum, QuorumApproval, QuorumProposal},
+    reference::Reference,
+    replay::{ReplayEntry, ReplayLog, ReplayOutcome, ReplayRecorder, ReplayStep},
+    routing_table::{Route, RoutingTable},
     selector::Selector,
     send_response::SendResponse,
-    user::User,
+    spongos_store::{LruSpongosStore, SpongosStore},
+    state_summary::{diff, BranchSummary, CursorChange, PermissionChange, StateDiff, StateSummary},
+    subscription_policy::{SubscriptionPolicy, SubscriptionStatus},
+    user::{Conflict, HistoryDirection, QueuedPacket, Retention, User},
     user_builder::UserBuilder,
 };
 
+#[cfg(feature = "actor")]
+pub use api::actor::{UserActor, UserHandle};
+#[cfg(feature = "auto-sync")]
+pub use api::auto_sync::AutoSync;
+#[cfg(all(feature = "async-std-runtime", not(target_arch = "wasm32")))]
+pub use api::runtime::AsyncStdRuntime;
+#[cfg(all(feature = "auto-sync", not(target_arch = "wasm32")))]
+pub use api::runtime::TokioRuntime;
+#[cfg(all(feature = "auto-sync", target_arch = "wasm32"))]
+pub use api::runtime::WasmRuntime;
+#[cfg(feature = "auto-sync")]
+pub use api::runtime::{DefaultRuntime, Runtime, Sleep, TaskFuture, TaskSend};
+#[cfg(any(feature = "threadsafe", feature = "auto-sync"))]
+pub use api::shared_user::SharedUser;
+#[cfg(feature = "std")]
+pub use api::spongos_store::DirectorySpongosStore;
+
 /// Errors for Streams
 mod error;
 pub use error::{Error, Result};
+pub use lets::error::{CompactError, ErrorCatalog};
 
-pub use lets::{address::Address, id, message::TransportMessage, transport};
+pub use lets::{
+    address::Address,
+    id,
+    message::{ContentSizeof, ContentUnwrap, ContentWrap, TransportMessage, HDF},
+    transport,
+};
+pub use spongos::{ddml::layout::LayoutDescriptor, Spongos};
@@ -5,10 +5,23 @@
 //! It announces the stream owner's identifier. The `Announcement` message is similar to
 //! a self-signed certificate in a conventional PKI.
 //!
+//! Announcements also carry the flags of the channel, such as [`STRICT`], which makes readers
+//! reject the messages using weaker options than the current ones, and [`QUORUM`], which announces
+//! the [`Quorum`] of author keys of the channel and the approvals of the announcement by its
+//! members.
+//!
 //! ```ddml
 //! message Announcement {
 //!     mask             u8     identifier;
 //!     mask             u8     topic;
+//!     mask             u8     flags;
+//!     mask             size_t n_members;      // QUORUM only
+//!     repeated(n_members):
+//!       mask           u8     member;
+//!     mask             u8     threshold;      // QUORUM only
+//!     mask             size_t n_approvals;    // QUORUM only
+//!     repeated(n_approvals):
+//!       QuorumApproval approval;
 //!     commit;
 //!     squeeze          u8     hash[64];
 //!     ed25519(hash)           sig;
@@ -16,7 +29,7 @@
 //! ```
 
 // Rust
-use alloc::boxed::Box;
+use alloc::{boxed::Box, string::ToString, vec::Vec};
 
 // 3rd-party
 use async_trait::async_trait;
@@ -27,17 +40,28 @@ use async_trait::async_trait;
 use lets::{
     id::{Identifier, Identity},
     message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap, Topic},
+    sync::MaybeSend,
 };
 use spongos::{
     ddml::{
         commands::{sizeof, unwrap, wrap, Commit, Mask},
         io,
+        types::{Size, Uint8},
     },
-    error::Result,
+    error::{Error as SpongosError, Result},
     PRP,
 };
 
 // Local
+use crate::api::quorum::{Quorum, QuorumApproval};
+
+/// Flag of the channels rejecting unsigned packets and legacy messages, so that they cannot be
+/// spoofed by downgrading to weaker options
+pub(crate) const STRICT: u8 = 1;
+/// Flag of the channels announced with a [`Quorum`] of author keys
+pub(crate) const QUORUM: u8 = 1 << 1;
+/// The flags known to this version
+const FLAGS: u8 = STRICT | QUORUM;
 
 /// A struct that holds references needed for announcement message encoding
 pub(crate) struct Wrap<'a> {
@@ -45,6 +69,10 @@ pub(crate) struct Wrap<'a> {
     user_id: &'a Identity,
     /// The [`Topic`] of the base branch of the stream
     topic: &'a Topic,
+    /// The flags of the channel
+    flags: u8,
+    /// The [`Quorum`] of the channel and its approvals of the announcement, if it has one
+    quorum: Option<(&'a Quorum, &'a [QuorumApproval])>,
 }
 
 impl<'a> Wrap<'a> {
@@ -54,33 +82,81 @@ impl<'a> Wrap<'a> {
     /// * `user_id`: The [`Identity`] of the sender
     /// * `topic`: The base branch [`Topic`] for the stream
     pub(crate) fn new(user_id: &'a Identity, topic: &'a Topic) -> Self {
-        Self { user_id, topic }
+        Self {
+            user_id,
+            topic,
+            flags: 0,
+            quorum: None,
+        }
+    }
+
+    /// Includes the flags of the channel in the announcement
+    ///
+    /// # Arguments
+    /// * `flags`: The flags of the channel, such as [`STRICT`]
+    pub(crate) fn with_flags(mut self, flags: u8) -> Self {
+        self.flags |= flags & !QUORUM;
+        self
+    }
+
+    /// Includes the [`Quorum`] of the channel and the approvals of its members in the announcement,
+    /// following the flags of the channel
+    ///
+    /// # Arguments
+    /// * `quorum`: The [`Quorum`] of author keys of the channel
+    /// * `approvals`: The [`QuorumApproval`]s of the announcement
+    pub(crate) fn with_quorum(mut self, quorum: &'a Quorum, approvals: &'a [QuorumApproval]) -> Self {
+        self.flags |= QUORUM;
+        self.quorum = Some((quorum, approvals));
+        self
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     async fn sizeof(&mut self, announcement: &Wrap<'a>) -> Result<&mut Self> {
         self.mask(announcement.user_id.identifier())?
             .mask(announcement.topic)?
-            .sign_sizeof(announcement.user_id)
-            .await?
-            .commit()?;
+            .mask(Uint8::new(announcement.flags))?;
+        if let Some((quorum, approvals)) = announcement.quorum {
+            self.mask(Size::new(quorum.members().len()))?;
+            for member in quorum.members() {
+                self.mask(member)?;
+            }
+            self.mask(Uint8::new(quorum.threshold()))?
+                .mask(Size::new(approvals.len()))?;
+            for approval in approvals {
+                self.mask(approval)?;
+            }
+        }
+        self.sign_sizeof(announcement.user_id).await?.commit()?;
         Ok(self)
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
 where
-    OS: io::OStream,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, announcement: &mut Wrap<'a>) -> Result<&mut Self> {
         self.mask(announcement.user_id.identifier())?
             .mask(announcement.topic)?
-            .sign(announcement.user_id)
-            .await?
-            .commit()?;
+            .mask(Uint8::new(announcement.flags))?;
+        if let Some((quorum, approvals)) = announcement.quorum {
+            self.mask(Size::new(quorum.members().len()))?;
+            for member in quorum.members() {
+                self.mask(member)?;
+            }
+            self.mask(Uint8::new(quorum.threshold()))?
+                .mask(Size::new(approvals.len()))?;
+            for approval in approvals {
+                self.mask(approval)?;
+            }
+        }
+        self.sign(announcement.user_id).await?.commit()?;
         Ok(self)
     }
 }
@@ -92,17 +168,33 @@ pub(crate) struct Unwrap {
     author_id: Identifier,
     /// The base branch [`Topic`] of the stream
     topic: Topic,
+    /// The flags of the channel
+    flags: u8,
+    /// The [`Quorum`] of author keys of the channel, if it has one
+    quorum: Option<Quorum>,
+    /// The approvals of the announcement by the members of the [`Quorum`]
+    approvals: Vec<QuorumApproval>,
 }
 
 impl Default for Unwrap {
     fn default() -> Self {
         let author_id = Default::default();
         let topic = Default::default();
-        Self { author_id, topic }
+        Self {
+            author_id,
+            topic,
+            flags: 0,
+            quorum: None,
+            approvals: Vec::new(),
+        }
     }
 }
 
 impl Unwrap {
+    /// Returns true if the announcement flags the channel as [`STRICT`].
+    pub(crate) fn is_strict(&self) -> bool {
+        self.flags & STRICT != 0
+    }
     /// Returns a reference to the [`Identifier`] of the author.
     pub(crate) fn author_id(&self) -> &Identifier {
         &self.author_id
@@ -111,24 +203,64 @@ impl Unwrap {
     pub(crate) fn topic(&self) -> &Topic {
         &self.topic
     }
-    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the author.
-    pub(crate) fn into_author_id(self) -> Identifier {
-        self.author_id
+    /// Returns the [`Quorum`] of author keys of the channel, if the announcement carries one.
+    pub(crate) fn quorum(&self) -> Option<&Quorum> {
+        self.quorum.as_ref()
+    }
+    /// Returns the approvals of the announcement by the members of its [`Quorum`], empty if the
+    /// announcement carries no quorum.
+    pub(crate) fn approvals(&self) -> &[QuorumApproval] {
+        &self.approvals
+    }
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the author and the [`Quorum`] of the
+    /// channel, if any.
+    pub(crate) fn into_parts(self) -> (Identifier, Option<Quorum>) {
+        (self.author_id, self.quorum)
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<IS, F> ContentUnwrap<Unwrap> for unwrap::Context<IS, F>
 where
-    F: PRP,
-    IS: io::IStream,
+    F: PRP + MaybeSend,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, announcement: &mut Unwrap) -> Result<&mut Self> {
+        let mut flags = Uint8::new(0);
         self.mask(&mut announcement.author_id)?
             .mask(&mut announcement.topic)?
-            .verify(&announcement.author_id)
-            .await?
-            .commit()?;
+            .mask(&mut flags)?;
+        announcement.flags = flags.inner();
+        if announcement.flags & !FLAGS != 0 {
+            return Err(SpongosError::InvalidOption("announcement flags", announcement.flags));
+        }
+        if announcement.flags & QUORUM != 0 {
+            let mut members_count = Size::default();
+            self.mask(&mut members_count)?
+                .check_size("quorum members", members_count.inner())?;
+            let mut members = Vec::with_capacity(members_count.inner());
+            for _ in 0..members_count.inner() {
+                let mut member = Identifier::default();
+                self.mask(&mut member)?;
+                members.push(member);
+            }
+            let mut threshold = Uint8::new(0);
+            let mut approvals_count = Size::default();
+            self.mask(&mut threshold)?
+                .mask(&mut approvals_count)?
+                .check_size("quorum approvals", approvals_count.inner())?;
+            announcement
+                .approvals
+                .resize(approvals_count.inner(), QuorumApproval::default());
+            for approval in announcement.approvals.iter_mut() {
+                self.mask(approval)?;
+            }
+            let quorum =
+                Quorum::new(members, threshold.inner()).map_err(|e| SpongosError::Context("Quorum", e.to_string()))?;
+            announcement.quorum = Some(quorum);
+        }
+        self.verify(&announcement.author_id).await?.commit()?;
         Ok(self)
     }
 }
@@ -0,0 +1,195 @@
+//! `AuthorRotation` message _wrapping_ and _unwrapping_.
+//!
+//! The `AuthorRotation` message is published by the author of a stream to hand the authorship of
+//! the stream over to a new identifier. Signed by the key of the current author, it is the proof of
+//! continuity between the two keys: readers verify it against the author they know, and accept the
+//! keyloads and the other author messages of the new identifier from then on. Successive rotations
+//! form a chain back to the author of the announcement. The message is linked to the announcement,
+//! so that every reader of the stream can unwrap it. In the streams announced with a
+//! [`Quorum`](crate::Quorum) of author keys, author rotations carry in addition the approvals of
+//! the rotation by the members of the quorum, and none in the other streams.
+//!
+//! ```ddml
+//! message AuthorRotation {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     mask             u8     new_author;
+//!     mask             size_t n_approvals;
+//!     repeated(n_approvals):
+//!       QuorumApproval approval;
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+        types::Size,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+use crate::api::quorum::QuorumApproval;
+
+/// A struct that holds references needed for author rotation message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the current stream author
+    user_id: &'a Identity,
+    /// The [`Identifier`] of the new stream author
+    new_author: &'a Identifier,
+    /// The approvals of the rotation by the members of the quorum, if the stream has one
+    approvals: &'a [QuorumApproval],
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for an author rotation message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the current stream author
+    /// * `new_author`: The [`Identifier`] of the new stream author
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity, new_author: &'a Identifier) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            new_author,
+            approvals: &[],
+        }
+    }
+
+    /// Includes the approvals of the rotation by the members of the quorum of the stream
+    ///
+    /// # Arguments
+    /// * `approvals`: The [`QuorumApproval`]s of the rotation
+    pub(crate) fn with_approvals(mut self, approvals: &'a [QuorumApproval]) -> Self {
+        self.approvals = approvals;
+        self
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, rotation: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(rotation.user_id.identifier())?.mask(rotation.new_author)?;
+        self.mask(Size::new(rotation.approvals.len()))?;
+        for approval in rotation.approvals {
+            self.mask(approval)?;
+        }
+        self.sign_sizeof(rotation.user_id).await?.commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, rotation: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(rotation.initial_state)?
+            .mask(rotation.user_id.identifier())?
+            .mask(rotation.new_author)?;
+        self.mask(Size::new(rotation.approvals.len()))?;
+        for approval in rotation.approvals {
+            self.mask(approval)?;
+        }
+        self.sign(rotation.user_id).await?.commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for author rotation message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the previous stream author
+    previous_author: Identifier,
+    /// The [`Identifier`] of the new stream author
+    new_author: Identifier,
+    /// The approvals of the rotation by the members of the quorum
+    approvals: Vec<QuorumApproval>,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for an author rotation message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            previous_author: Identifier::default(),
+            new_author: Identifier::default(),
+            approvals: Vec::new(),
+        }
+    }
+
+    /// Returns the [`Identifier`] of the previous stream author, who signed the message
+    pub(crate) fn previous_author(&self) -> &Identifier {
+        &self.previous_author
+    }
+
+    /// Returns the [`Identifier`] of the new stream author
+    pub(crate) fn new_author(&self) -> &Identifier {
+        &self.new_author
+    }
+
+    /// Returns the approvals of the rotation by the members of the quorum, empty if the rotation
+    /// carries none
+    pub(crate) fn approvals(&self) -> &[QuorumApproval] {
+        &self.approvals
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`]s of the previous and of the new stream
+    /// authors, and the approvals of the rotation
+    pub(crate) fn into_parts(self) -> (Identifier, Identifier, Vec<QuorumApproval>) {
+        (self.previous_author, self.new_author, self.approvals)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, rotation: &mut Unwrap) -> Result<&mut Self> {
+        self.join(rotation.initial_state)?
+            .mask(&mut rotation.previous_author)?
+            .mask(&mut rotation.new_author)?;
+        let mut approvals_count = Size::default();
+        self.mask(&mut approvals_count)?
+            .check_size("quorum approvals", approvals_count.inner())?;
+        rotation
+            .approvals
+            .resize(approvals_count.inner(), QuorumApproval::default());
+        for approval in rotation.approvals.iter_mut() {
+            self.mask(approval)?;
+        }
+        self.verify(&rotation.previous_author).await?.commit()?;
+        Ok(self)
+    }
+}
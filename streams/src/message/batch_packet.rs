@@ -0,0 +1,195 @@
+//! `BatchPacket` message _wrapping_ and _unwrapping_.
+//!
+//! `BatchPacket` messages carry a batch of records, each one with a plain and a masked payload,
+//! signed by the sender as a whole. As the records travel in a single message, readers either
+//! receive all the records of a batch, once the message is published and verified, or none of them.
+//!
+//! ```ddml
+//! message BatchPacket {
+//!     join(spongos);
+//!     absorb external     u8      ratchet_key[32]; // forward secrecy branches only
+//!     mask                u8      identifier;
+//!     mask                u8      size(n_records);
+//!     repeated(n_records):
+//!       absorb            uint    public_size;
+//!       absorb            u8      public_payload[public_size];
+//!       mask              uint    masked_size;
+//!       mask              u8      masked_payload[masked_size];
+//!     commit;
+//!     squeeze external    u8      hash[64];
+//!     ed25519(hash)       u8      signature[64];
+//! }
+//! ```
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Absorb, Join, Mask},
+        io,
+        modifiers::External,
+        types::{Bytes, NBytes, Size},
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+use crate::api::message::BatchRecord;
+
+/// A struct that holds references needed for batch packet message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// The records of the batch, in the order they are published
+    records: &'a [BatchRecord],
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a batch packet message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    /// * `records`: The records of the batch
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity, records: &'a [BatchRecord]) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            records,
+            ratchet_key: None,
+        }
+    }
+
+    /// Absorbs the key of the publisher ratchet after the join, if the branch of the packet is in
+    /// forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, batch_packet: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(batch_packet.user_id.identifier())?
+            .mask(Size::new(batch_packet.records.len()))?;
+        for record in batch_packet.records {
+            self.absorb(Bytes::new(&record.public_payload))?
+                .mask(Bytes::new(&record.masked_payload))?;
+        }
+        self.sign_sizeof(batch_packet.user_id).await?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, batch_packet: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(batch_packet.initial_state)?;
+        if let Some(ratchet_key) = &batch_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        self.mask(batch_packet.user_id.identifier())?
+            .mask(Size::new(batch_packet.records.len()))?;
+        for record in batch_packet.records {
+            self.absorb(Bytes::new(&record.public_payload))?
+                .mask(Bytes::new(&record.masked_payload))?;
+        }
+        self.sign(batch_packet.user_id).await?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for batch packet message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the publisher
+    publisher_id: Identifier,
+    /// The records of the batch
+    records: Vec<BatchRecord>,
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a batch packet message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The base [`Spongos`] state that the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher_id: Identifier::default(),
+            records: Vec::new(),
+            ratchet_key: None,
+        }
+    }
+
+    /// Absorbs the expected key of the publisher ratchet after the join, if the branch of the
+    /// packet is in forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher and the records of
+    /// the batch
+    pub(crate) fn into_parts(self) -> (Identifier, Vec<BatchRecord>) {
+        (self.publisher_id, self.records)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, batch_packet: &mut Unwrap) -> Result<&mut Self> {
+        self.join(batch_packet.initial_state)?;
+        if let Some(ratchet_key) = &batch_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        let mut records_count = Size::default();
+        self.mask(&mut batch_packet.publisher_id)?
+            .mask(&mut records_count)?
+            .check_size("batch packet records", records_count.inner())?;
+        batch_packet
+            .records
+            .resize(records_count.inner(), BatchRecord::default());
+        for record in batch_packet.records.iter_mut() {
+            self.absorb(Bytes::new(&mut record.public_payload))?
+                .mask(Bytes::new(&mut record.masked_payload))?;
+        }
+        self.verify(&batch_packet.publisher_id).await?;
+        Ok(self)
+    }
+}
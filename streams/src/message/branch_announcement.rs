@@ -29,6 +29,7 @@ use async_trait::async_trait;
 use lets::{
     id::{Identifier, Identity},
     message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap, Topic},
+    sync::MaybeSend,
 };
 use spongos::{
     ddml::{
@@ -67,7 +68,8 @@ impl<'a> Wrap<'a> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     async fn sizeof(&mut self, announcement: &Wrap<'a>) -> Result<&mut Self> {
         self.mask(announcement.user_id.identifier())?
@@ -79,10 +81,11 @@ impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
 where
-    OS: io::OStream,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, announcement: &mut Wrap<'a>) -> Result<&mut Self> {
         self.join(announcement.initial_state)?
@@ -126,10 +129,11 @@ impl<'a> Unwrap<'a> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
 where
-    IS: io::IStream,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, announcement: &mut Unwrap) -> Result<&mut Self> {
         let mut author_id = Identifier::default();
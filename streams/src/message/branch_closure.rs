@@ -0,0 +1,134 @@
+//! `BranchClosure` message _wrapping_ and _unwrapping_.
+//!
+//! The `BranchClosure` message is the terminal message of a branch. It is published in the branch
+//! being closed, which is identified by the topic hash of the message header, and informs the
+//! readers that no further message will be published in it.
+//!
+//! ```ddml
+//! message BranchClosure {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap, Topic},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// A struct that holds references needed for branch closure message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a branch closure message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity) -> Self {
+        Self { initial_state, user_id }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, closure: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(closure.user_id.identifier())?
+            .sign_sizeof(closure.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, closure: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(closure.initial_state)?
+            .mask(closure.user_id.identifier())?
+            .sign(closure.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for branch closure message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Topic`] of the closed branch, resolved from the message header
+    topic: Topic,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a branch closure message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `topic`: The [`Topic`] of the branch the message is published in
+    pub(crate) fn new(initial_state: &'a mut Spongos, topic: Topic) -> Self {
+        Self {
+            initial_state,
+            topic,
+            publisher: Identifier::default(),
+        }
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Topic`] of the closed branch and the [`Identifier`]
+    /// of the publisher
+    pub(crate) fn into_parts(self) -> (Topic, Identifier) {
+        (self.topic, self.publisher)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, closure: &mut Unwrap) -> Result<&mut Self> {
+        self.join(closure.initial_state)?
+            .mask(&mut closure.publisher)?
+            .verify(&closure.publisher)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
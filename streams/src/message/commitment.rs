@@ -0,0 +1,155 @@
+//! `Commitment` message _wrapping_ and _unwrapping_.
+//!
+//! The `Commitment` message is published by a user to commit to a payload without disclosing it.
+//! It carries the digest of the payload (see [`commitment_digest()`](crate::commitment_digest)),
+//! which the readers keep to verify the `Reveal` message later disclosing the payload.
+//!
+//! ```ddml
+//! message Commitment {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     mask             u8     digest[32];
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+        types::NBytes,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+use crate::api::commitment::COMMITMENT_DIGEST_SIZE;
+
+/// A struct that holds references needed for commitment message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// The digest of the committed payload
+    digest: &'a [u8; COMMITMENT_DIGEST_SIZE],
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a commitment message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    /// * `digest`: The digest of the committed payload
+    pub(crate) fn new(
+        initial_state: &'a mut Spongos,
+        user_id: &'a Identity,
+        digest: &'a [u8; COMMITMENT_DIGEST_SIZE],
+    ) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            digest,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, commitment: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(commitment.user_id.identifier())?
+            .mask(NBytes::new(commitment.digest))?
+            .sign_sizeof(commitment.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, commitment: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(commitment.initial_state)?
+            .mask(commitment.user_id.identifier())?
+            .mask(NBytes::new(commitment.digest))?
+            .sign(commitment.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for commitment message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+    /// The digest of the committed payload
+    digest: [u8; COMMITMENT_DIGEST_SIZE],
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a commitment message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher: Identifier::default(),
+            digest: [0; COMMITMENT_DIGEST_SIZE],
+        }
+    }
+
+    /// Returns the digest of the committed payload
+    pub(crate) fn digest(&self) -> [u8; COMMITMENT_DIGEST_SIZE] {
+        self.digest
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher and the digest of the
+    /// committed payload
+    pub(crate) fn into_parts(self) -> (Identifier, [u8; COMMITMENT_DIGEST_SIZE]) {
+        (self.publisher, self.digest)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, commitment: &mut Unwrap) -> Result<&mut Self> {
+        self.join(commitment.initial_state)?
+            .mask(&mut commitment.publisher)?
+            .mask(NBytes::new(&mut commitment.digest))?
+            .verify(&commitment.publisher)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
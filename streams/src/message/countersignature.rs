@@ -0,0 +1,141 @@
+//! `Countersignature` message _wrapping_ and _unwrapping_.
+//!
+//! The `Countersignature` message is published by a witness of a stream to attest a packet after
+//! its publication. It is linked to the countersigned packet and joined to its [`Spongos`] state,
+//! so that the signature of the witness covers the packet itself. Readers only accept the
+//! countersignatures of the witnesses they trust, and attach them to the countersigned packet.
+//!
+//! ```ddml
+//! message Countersignature {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::MsgId,
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// A struct that holds references needed for countersignature message encoding
+pub(crate) struct Wrap<'a> {
+    /// The [`Spongos`] state of the countersigned packet, that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the witness
+    user_id: &'a Identity,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a countersignature message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The [`Spongos`] state of the countersigned packet
+    /// * `user_id`: The [`Identity`] of the witness
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity) -> Self {
+        Self { initial_state, user_id }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, countersignature: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(countersignature.user_id.identifier())?
+            .sign_sizeof(countersignature.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, countersignature: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(countersignature.initial_state)?
+            .mask(countersignature.user_id.identifier())?
+            .sign(countersignature.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for countersignature message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The [`Spongos`] state of the countersigned packet, that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`MsgId`] of the countersigned packet
+    countersigned: MsgId,
+    /// The [`Identifier`] of the witness
+    witness: Identifier,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a countersignature message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The [`Spongos`] state of the countersigned packet
+    /// * `countersigned`: The [`MsgId`] of the countersigned packet, the message is linked to
+    pub(crate) fn new(initial_state: &'a mut Spongos, countersigned: MsgId) -> Self {
+        Self {
+            initial_state,
+            countersigned,
+            witness: Identifier::default(),
+        }
+    }
+
+    /// Returns the [`Identifier`] of the witness, who signed the message
+    pub(crate) fn witness(&self) -> &Identifier {
+        &self.witness
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the witness and the [`MsgId`] of the
+    /// countersigned packet
+    pub(crate) fn into_parts(self) -> (Identifier, MsgId) {
+        (self.witness, self.countersigned)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, countersignature: &mut Unwrap) -> Result<&mut Self> {
+        self.join(countersignature.initial_state)?
+            .mask(&mut countersignature.witness)?
+            .verify(&countersignature.witness)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
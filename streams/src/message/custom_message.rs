@@ -0,0 +1,159 @@
+//! Custom message _wrapping_ and _unwrapping_.
+//!
+//! Custom messages carry the content of the message types defined by the applications. The content
+//! is wrapped by the [`ContentWrap`] implementation of the application, between the identifier of
+//! the publisher and its signature, so that it is linked and protected by the keyloads of its
+//! branch like the other messages.
+//!
+//! ```ddml
+//! message Custom {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     content                 content; // wrapped by the application
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::{MaybeSend, MaybeSync},
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// A struct that holds references needed for custom message encoding
+pub(crate) struct Wrap<'a, C> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// The content of the message, wrapped by the application
+    content: &'a mut C,
+}
+
+impl<'a, C> Wrap<'a, C> {
+    /// Creates a new [`Wrap`] struct for a custom message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    /// * `content`: The content of the message
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity, content: &'a mut C) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            content,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, C> ContentSizeof<Wrap<'a, C>> for sizeof::Context
+where
+    C: MaybeSend + MaybeSync,
+    sizeof::Context: ContentSizeof<C>,
+{
+    async fn sizeof(&mut self, custom: &Wrap<'a, C>) -> Result<&mut Self> {
+        self.mask(custom.user_id.identifier())?;
+        self.sizeof(&*custom.content)
+            .await?
+            .sign_sizeof(custom.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, C, OS> ContentWrap<Wrap<'a, C>> for wrap::Context<OS>
+where
+    C: MaybeSend + MaybeSync,
+    OS: io::OStream + MaybeSend,
+    wrap::Context<OS>: ContentWrap<C>,
+{
+    async fn wrap(&mut self, custom: &mut Wrap<'a, C>) -> Result<&mut Self> {
+        self.join(custom.initial_state)?.mask(custom.user_id.identifier())?;
+        self.wrap(custom.content).await?.sign(custom.user_id).await?.commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for custom message decoding
+pub(crate) struct Unwrap<'a, C> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The message type identifier, read from the header of the message
+    message_type: u8,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+    /// The content of the message, unwrapped by the application
+    content: C,
+}
+
+impl<'a, C> Unwrap<'a, C>
+where
+    C: Default,
+{
+    /// Creates a new [`Unwrap`] struct for a custom message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `message_type`: The message type identifier of the message
+    pub(crate) fn new(initial_state: &'a mut Spongos, message_type: u8) -> Self {
+        Self {
+            initial_state,
+            message_type,
+            publisher: Identifier::default(),
+            content: C::default(),
+        }
+    }
+}
+
+impl<'a, C> Unwrap<'a, C> {
+    /// Consumes the [`Unwrap`], returning the message type identifier, the [`Identifier`] of the
+    /// publisher and the content of the message
+    pub(crate) fn into_parts(self) -> (u8, Identifier, C) {
+        (self.message_type, self.publisher, self.content)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, C, IS> ContentUnwrap<Unwrap<'a, C>> for unwrap::Context<IS>
+where
+    C: MaybeSend,
+    IS: io::IStream + MaybeSend,
+    unwrap::Context<IS>: ContentUnwrap<C>,
+{
+    async fn unwrap(&mut self, custom: &mut Unwrap<'a, C>) -> Result<&mut Self> {
+        self.join(custom.initial_state)?.mask(&mut custom.publisher)?;
+        self.unwrap(&mut custom.content)
+            .await?
+            .verify(&custom.publisher)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
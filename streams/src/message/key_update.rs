@@ -0,0 +1,185 @@
+//! `KeyUpdate` message content. This message is published by a user replacing the key it uses for
+//! key exchange, so that keyloads and selective packets are encrypted to the new key instead of the
+//! one derived from its identity. The new public key is signed by the identity of the user.
+//!
+//! ```ddml
+//! message KeyUpdate {
+//!     join(spongos);
+//!     mask                    u8      identifier;
+//!     absorb                  u8      size(generation);
+//!     absorb                  u8      exchange_key[32];
+//!     commit;
+//!     squeeze external        u8      hash[64];
+//!     ed25519(hash)           u8      signature[64];
+//! }
+//! ```
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+use crypto::keys::x25519;
+
+// Streams
+use lets::{
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Absorb, Commit, Join, Mask},
+        io,
+        types::{NBytes, Size},
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// Key exchange public key announced by a user with a `KeyUpdate`, replacing the one derived from
+/// its identity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct ExchangeKey {
+    /// Number of rotations the key results from. Generation 0 is the key derived from the identity.
+    pub(crate) generation: usize,
+    /// The public key recipients encrypt to
+    pub(crate) public_key: [u8; x25519::PUBLIC_KEY_LENGTH],
+}
+
+impl ExchangeKey {
+    /// Creates a new [`ExchangeKey`]
+    ///
+    /// # Arguments
+    /// * `generation`: Number of rotations the key results from
+    /// * `public_key`: The public key recipients encrypt to
+    pub(crate) fn new(generation: usize, public_key: [u8; x25519::PUBLIC_KEY_LENGTH]) -> Self {
+        Self { generation, public_key }
+    }
+
+    /// Returns the [`x25519::PublicKey`] recipients encrypt to
+    pub(crate) fn public_key(&self) -> x25519::PublicKey {
+        x25519::PublicKey::from(self.public_key)
+    }
+}
+
+/// A struct that holds references needed for key update message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the user rotating its key
+    user_id: &'a Identity,
+    /// The new key exchange public key
+    exchange_key: ExchangeKey,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a key update message
+    ///
+    /// # Arguments:
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the user rotating its key.
+    /// * `exchange_key`: The new key exchange public key and its generation.
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity, exchange_key: ExchangeKey) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            exchange_key,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, key_update: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(key_update.user_id.identifier())?
+            .absorb(Size::new(key_update.exchange_key.generation))?
+            .absorb(NBytes::new(key_update.exchange_key.public_key))?
+            .commit()?
+            .sign_sizeof(key_update.user_id)
+            .await?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, key_update: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(key_update.initial_state)?
+            .mask(key_update.user_id.identifier())?
+            .absorb(Size::new(key_update.exchange_key.generation))?
+            .absorb(NBytes::new(key_update.exchange_key.public_key))?
+            .commit()?
+            .sign(key_update.user_id)
+            .await?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for key update message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the user rotating its key
+    user_id: Identifier,
+    /// The new key exchange public key
+    exchange_key: ExchangeKey,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a key update message
+    ///
+    /// # Arguments:
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            user_id: Identifier::default(),
+            exchange_key: ExchangeKey::default(),
+        }
+    }
+
+    /// Returns a reference to the [`Identifier`] of the user rotating its key
+    pub(crate) fn user_identifier(&self) -> &Identifier {
+        &self.user_id
+    }
+
+    /// Returns the announced [`ExchangeKey`]
+    pub(crate) fn exchange_key(&self) -> ExchangeKey {
+        self.exchange_key
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the user and its new
+    /// [`ExchangeKey`]
+    pub(crate) fn into_parts(self) -> (Identifier, ExchangeKey) {
+        (self.user_id, self.exchange_key)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, key_update: &mut Unwrap<'a>) -> Result<&mut Self> {
+        let mut generation = Size::default();
+        self.join(key_update.initial_state)?
+            .mask(&mut key_update.user_id)?
+            .absorb(&mut generation)?
+            .absorb(NBytes::new(&mut key_update.exchange_key.public_key))?
+            .commit()?
+            .verify(&key_update.user_id)
+            .await?;
+        key_update.exchange_key.generation = generation.inner();
+        Ok(self)
+    }
+}
@@ -3,14 +3,20 @@
 //! The `Keyload` message is the means to securely exchange the encryption key of a branch with a
 //! set of subscribers and pre shared keys.
 //!
+//! The extensions of a keyload are flagged after the key, masked with it so that only the recipients
+//! of the keyload read them, each flag announcing an optional field: the description of the branch
+//! ([`METADATA`]) and the expiry of the access granted by the keyload ([`EXPIRY`]).
+//!
 //! ```ddml
 //! message Keyload {
 //!     join(spongos);
 //!     absorb                      u8  nonce[32];
+//!     absorb                      u8  forward_secrecy;
 //!     absorb                      u8  size(n_subscribers);
 //!     repeated(n_subscribers):
 //!       fork;
 //!       mask                      u8  permissioned;
+//!       mask                      u8  size(exchange_key_generation);
 //!       x25519(pub/priv_key)      u8  x25519_pubkey[32];
 //!     absorb                      u8  size(n_psks);
 //!     repeated(n_psks):
@@ -21,6 +27,10 @@
 //!       mask                      u8  key[32];
 //!     absorb external             u8  key[32];
 //!     commit;
+//!     mask                        u8  extensions;
+//!     mask                        u8  metadata;   // METADATA only
+//!     mask                        u64 expires_at; // EXPIRY only
+//!     commit;
 //!     squeeze external            u8  hash[64];
 //!     ed25519(hash)               u8  signature[64];
 //!     commit;
@@ -39,27 +49,36 @@ use hashbrown::HashMap;
 
 // Streams
 use lets::{
-    id::{Identifier, Identity, Permissioned, Psk, PskId},
+    id::{Identifier, Identity, KeyExchange, Permissioned, Psk, PskId},
     message::{
         self, ContentDecrypt, ContentEncrypt, ContentEncryptSizeOf, ContentSign, ContentSignSizeof, ContentVerify,
     },
+    sync::{MaybeSend, MaybeSync},
 };
 use spongos::{
     ddml::{
-        commands::{sizeof, unwrap, wrap, Absorb, Commit, Fork, Join, Mask},
+        commands::{sizeof, unwrap, wrap, Absorb, Commit, Fork, Join, Mask, X25519},
         io,
         modifiers::External,
-        types::{NBytes, Size},
+        types::{NBytes, Size, Uint64, Uint8},
     },
-    error::Result,
+    error::{Error as SpongosError, Result},
     Spongos,
 };
 
 // Local
+use crate::{api::branch_metadata::BranchMetadata, message::key_update::ExchangeKey};
 
 const NONCE_SIZE: usize = 16;
 const KEY_SIZE: usize = 32;
 
+/// Extension flag of the keyloads carrying a description of their branch
+pub(crate) const METADATA: u8 = 1;
+/// Extension flag of the keyloads granting access until an expiry
+pub(crate) const EXPIRY: u8 = 1 << 1;
+/// The extension flags known to this version
+const EXTENSIONS: u8 = METADATA | EXPIRY;
+
 /// A struct that holds references needed for keyload message encoding
 pub(crate) struct Wrap<'a, 'b, Subscribers, Psks> {
     /// The base [`Spongos`] state that the message will be joined to
@@ -68,12 +87,21 @@ pub(crate) struct Wrap<'a, 'b, Subscribers, Psks> {
     nonce: [u8; NONCE_SIZE],
     /// A key that will be shared with intended subscribers
     key: [u8; KEY_SIZE],
+    /// Whether the publishers of the branch ratchet their keys after this keyload
+    forward_secrecy: bool,
     /// An iterator of [`Permissioned`] subscribers to be included in the key exchange
     subscribers: Subscribers,
     /// An iterator of [`Psks`] to mask the key with
     psks: Psks,
     /// The [`Identity`] of the stream author
     author_id: &'a Identity,
+    /// The key exchange keys announced by the subscribers, replacing the ones derived from their
+    /// identity
+    exchange_keys: Option<&'a HashMap<Identifier, ExchangeKey>>,
+    /// The description of the branch, readable by the recipients of the keyload
+    metadata: Option<&'a BranchMetadata>,
+    /// Unix timestamp, in seconds, at which the access granted by the keyload expires
+    expires_at: Option<u64>,
     // panthom subscriber's lifetime needed because we cannot add lifetime parameters to `ContentWrap` trait method.
     // subscribers need a different lifetime because they are provided directly from downstream. They are not stored by
     // the user instance thus they don't share its lifetime
@@ -91,6 +119,8 @@ impl<'a, 'b, Subscribers, Psks> Wrap<'a, 'b, Subscribers, Psks> {
     /// * `nonce`: A random number that is used to ensure that the same message is not encrypted
     ///   twice.
     /// * `author_id`: The [`Identity`] of the author of the message.
+    /// * `forward_secrecy`: Whether the publishers of the branch ratchet their keys after the
+    ///   keyload.
     pub(crate) fn new(
         initial_state: &'a mut Spongos,
         subscribers: Subscribers,
@@ -98,6 +128,7 @@ impl<'a, 'b, Subscribers, Psks> Wrap<'a, 'b, Subscribers, Psks> {
         key: [u8; KEY_SIZE],
         nonce: [u8; NONCE_SIZE],
         author_id: &'a Identity,
+        forward_secrecy: bool,
     ) -> Self
     where
         Subscribers: IntoIterator<Item = Permissioned<&'b Identifier>>,
@@ -110,33 +141,96 @@ impl<'a, 'b, Subscribers, Psks> Wrap<'a, 'b, Subscribers, Psks> {
             subscribers,
             psks,
             key,
+            forward_secrecy,
             nonce,
             author_id,
+            exchange_keys: None,
+            metadata: None,
+            expires_at: None,
             subscribers_lifetime: PhantomData,
         }
     }
+
+    /// Encrypts the key for the subscribers that announced a key exchange key with a `KeyUpdate`
+    /// to that key, instead of the one derived from their identity
+    ///
+    /// # Arguments
+    /// * `exchange_keys`: The announced key exchange keys, mapped by subscriber [`Identifier`]
+    pub(crate) fn with_exchange_keys(mut self, exchange_keys: &'a HashMap<Identifier, ExchangeKey>) -> Self {
+        self.exchange_keys = Some(exchange_keys);
+        self
+    }
+
+    /// Includes the description of the branch, masked with the key of the branch
+    ///
+    /// # Arguments
+    /// * `metadata`: The [`BranchMetadata`] of the branch
+    pub(crate) fn with_metadata(mut self, metadata: &'a BranchMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Includes the expiry of the access granted by the keyload, masked with the key of the branch
+    ///
+    /// # Arguments
+    /// * `expires_at`: The Unix timestamp, in seconds, at which the access expires
+    pub(crate) fn with_expiry(mut self, expires_at: u64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Returns the [`ExchangeKey`] announced by a subscriber, if any
+    fn exchange_key(&self, subscriber: &Identifier) -> Option<ExchangeKey> {
+        self.exchange_keys.and_then(|keys| keys.get(subscriber)).copied()
+    }
+
+    /// Returns the extension flags of the optional fields included in the keyload
+    fn extensions(&self) -> u8 {
+        let mut extensions = 0;
+        if self.metadata.is_some() {
+            extensions |= METADATA;
+        }
+        if self.expires_at.is_some() {
+            extensions |= EXPIRY;
+        }
+        extensions
+    }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, 'b, Subscribers, Psks> message::ContentSizeof<Wrap<'a, 'b, Subscribers, Psks>> for sizeof::Context
 where
-    Subscribers: IntoIterator<Item = Permissioned<&'b Identifier>> + Clone,
-    Subscribers::IntoIter: ExactSizeIterator,
-    Psks: IntoIterator<Item = &'a (PskId, &'a Psk)> + Clone,
-    Psks::IntoIter: ExactSizeIterator,
+    Subscribers: IntoIterator<Item = Permissioned<&'b Identifier>> + Clone + MaybeSync,
+    Subscribers::IntoIter: ExactSizeIterator + MaybeSend,
+    Psks: IntoIterator<Item = &'a (PskId, &'a Psk)> + Clone + MaybeSync,
+    Psks::IntoIter: ExactSizeIterator + MaybeSend,
 {
     async fn sizeof(&mut self, keyload: &Wrap<'a, 'b, Subscribers, Psks>) -> Result<&mut sizeof::Context> {
         let subscribers = keyload.subscribers.clone().into_iter();
         let psks = keyload.psks.clone().into_iter();
         let n_subscribers = Size::new(subscribers.len());
         let n_psks = Size::new(psks.len());
-        self.absorb(NBytes::new(keyload.nonce))?.absorb(n_subscribers)?;
+        self.absorb(NBytes::new(keyload.nonce))?
+            .absorb(Uint8::new(keyload.forward_secrecy as u8))?
+            .absorb(n_subscribers)?;
         // Loop through provided identifiers, masking the shared key for each one
         for subscriber in subscribers {
-            self.fork()
-                .mask(&subscriber)?
-                .encrypt_sizeof(subscriber.identifier(), &keyload.key)
-                .await?;
+            match keyload.exchange_key(subscriber.identifier()) {
+                Some(exchange_key) => {
+                    self.fork()
+                        .mask(&subscriber)?
+                        .mask(Size::new(exchange_key.generation))?
+                        .x25519(&exchange_key.public_key(), NBytes::new(&keyload.key))?;
+                }
+                None => {
+                    self.fork()
+                        .mask(&subscriber)?
+                        .mask(Size::new(0))?
+                        .encrypt_sizeof(subscriber.identifier(), &keyload.key)
+                        .await?;
+                }
+            }
         }
         self.absorb(n_psks)?;
         // Loop through provided pskids, masking the shared key for each one
@@ -148,21 +242,28 @@ where
                 .mask(NBytes::new(&keyload.key))?;
         }
         self.absorb(External::new(&NBytes::new(&keyload.key)))?
-            .sign_sizeof(keyload.author_id)
-            .await?
-            .commit()?;
+            .commit()?
+            .mask(Uint8::new(keyload.extensions()))?;
+        if let Some(metadata) = keyload.metadata {
+            self.mask(metadata)?;
+        }
+        if let Some(expires_at) = keyload.expires_at {
+            self.mask(Uint64::new(expires_at))?;
+        }
+        self.sign_sizeof(keyload.author_id).await?.commit()?;
         Ok(self)
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, 'b, OS, Subscribers, Psks> message::ContentWrap<Wrap<'a, 'b, Subscribers, Psks>> for wrap::Context<OS>
 where
-    Subscribers: IntoIterator<Item = Permissioned<&'b Identifier>> + Clone,
-    Subscribers::IntoIter: ExactSizeIterator,
-    Psks: IntoIterator<Item = &'a (PskId, &'a Psk)> + Clone,
-    Psks::IntoIter: ExactSizeIterator,
-    OS: io::OStream,
+    Subscribers: IntoIterator<Item = Permissioned<&'b Identifier>> + Clone + MaybeSend,
+    Subscribers::IntoIter: ExactSizeIterator + MaybeSend,
+    Psks: IntoIterator<Item = &'a (PskId, &'a Psk)> + Clone + MaybeSend,
+    Psks::IntoIter: ExactSizeIterator + MaybeSend,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, keyload: &mut Wrap<'a, 'b, Subscribers, Psks>) -> Result<&mut Self> {
         let subscribers = keyload.subscribers.clone().into_iter();
@@ -171,13 +272,25 @@ where
         let n_psks = Size::new(psks.len());
         self.join(keyload.initial_state)?
             .absorb(NBytes::new(keyload.nonce))?
+            .absorb(Uint8::new(keyload.forward_secrecy as u8))?
             .absorb(n_subscribers)?;
         // Loop through provided identifiers, masking the shared key for each one
         for subscriber in subscribers {
-            self.fork()
-                .mask(&subscriber)?
-                .encrypt(subscriber.identifier(), &keyload.key)
-                .await?;
+            match keyload.exchange_key(subscriber.identifier()) {
+                Some(exchange_key) => {
+                    self.fork()
+                        .mask(&subscriber)?
+                        .mask(Size::new(exchange_key.generation))?
+                        .x25519(&exchange_key.public_key(), NBytes::new(&keyload.key))?;
+                }
+                None => {
+                    self.fork()
+                        .mask(&subscriber)?
+                        .mask(Size::new(0))?
+                        .encrypt(subscriber.identifier(), &keyload.key)
+                        .await?;
+                }
+            }
         }
         self.absorb(n_psks)?;
         // Loop through provided pskids, masking the shared key for each one
@@ -189,9 +302,15 @@ where
                 .mask(NBytes::new(&keyload.key))?;
         }
         self.absorb(External::new(&NBytes::new(&keyload.key)))?
-            .sign(keyload.author_id)
-            .await?
-            .commit()?;
+            .commit()?
+            .mask(Uint8::new(keyload.extensions()))?;
+        if let Some(metadata) = keyload.metadata {
+            self.mask(metadata)?;
+        }
+        if let Some(expires_at) = keyload.expires_at {
+            self.mask(Uint64::new(expires_at))?;
+        }
+        self.sign(keyload.author_id).await?.commit()?;
         Ok(self)
     }
 }
@@ -204,12 +323,22 @@ pub(crate) struct Unwrap<'a> {
     pub(crate) subscribers: Vec<Permissioned<Identifier>>,
     /// Successfully found [`PskId`]'s in store
     pub(crate) psks: Vec<PskId>,
+    /// Whether the publishers of the branch ratchet their keys after this keyload
+    pub(crate) forward_secrecy: bool,
+    /// The description of the branch, if the keyload carries one and the reader recovered its key
+    pub(crate) metadata: Option<BranchMetadata>,
+    /// Unix timestamp, in seconds, at which the access granted by the keyload expires, if the
+    /// keyload carries one and the reader recovered its key
+    pub(crate) expires_at: Option<u64>,
     /// A reference to user stored [`PskId`] to [`Psk`] mapping
     psk_store: &'a HashMap<PskId, Psk>,
     /// The [`Identifier`] of the admin
     author_id: &'a Identifier,
     /// The [`Identity`] of the reader
     user_id: Option<&'a Identity>,
+    /// The [`KeyExchange`] holding the current key exchange key of the reader and its generation, if
+    /// it has been rotated
+    exchange_key: Option<(usize, &'a dyn KeyExchange)>,
 }
 
 impl<'a> Unwrap<'a> {
@@ -229,51 +358,81 @@ impl<'a> Unwrap<'a> {
             initial_state,
             subscribers: Vec::default(),
             psks: Vec::default(),
+            forward_secrecy: false,
+            metadata: None,
+            expires_at: None,
             psk_store,
             author_id,
             user_id,
+            exchange_key: None,
         }
     }
 
+    /// Decrypts the key with a rotated key exchange key of the reader, instead of the one derived
+    /// from its identity
+    ///
+    /// # Arguments
+    /// * `exchange_key`: The current key exchange key of the reader and its generation
+    pub(crate) fn with_exchange_key(mut self, exchange_key: Option<(usize, &'a dyn KeyExchange)>) -> Self {
+        self.exchange_key = exchange_key;
+        self
+    }
+
     /// Returns a reference to the list of granted [`Permissioned`] subscribers
     pub(crate) fn subscribers(&self) -> &[Permissioned<Identifier>] {
         &self.subscribers
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, IS> message::ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
 where
-    IS: io::IStream,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, keyload: &mut Unwrap<'a>) -> Result<&mut Self> {
         let mut nonce = [0u8; NONCE_SIZE];
         let mut key: Option<[u8; KEY_SIZE]> = None;
+        let mut forward_secrecy = Uint8::default();
         let mut n_subscribers = Size::default();
         let mut n_psks = Size::default();
         self.join(keyload.initial_state)?
             .absorb(NBytes::new(&mut nonce))?
-            .absorb(&mut n_subscribers)?;
+            .absorb(&mut forward_secrecy)?
+            .absorb(&mut n_subscribers)?
+            .check_size("keyload subscribers", n_subscribers.inner())?;
+        keyload.forward_secrecy = forward_secrecy.inner() == 1;
 
         for _ in 0..n_subscribers.inner() {
             let mut fork = self.fork();
             // Loop through provided number of identifiers and subsequent keys
             let mut subscriber_id = Permissioned::<Identifier>::default();
-            fork.mask(&mut subscriber_id)?;
+            let mut generation = Size::default();
+            fork.mask(&mut subscriber_id)?.mask(&mut generation)?;
 
             if key.is_none() && keyload.user_id.is_some() {
                 let user_id = keyload.user_id.unwrap();
-                if subscriber_id.identifier() == user_id.identifier() {
-                    fork.decrypt(user_id, key.get_or_insert([0u8; KEY_SIZE])).await?;
-                } else {
-                    fork.drop(KEY_SIZE + x25519::PUBLIC_KEY_LENGTH)?;
+                // The key can only be recovered if it was encrypted to the current key exchange key of
+                // the reader
+                match &keyload.exchange_key {
+                    Some((current, exchange_key))
+                        if subscriber_id.identifier() == user_id.identifier() && generation.inner() == *current =>
+                    {
+                        fork.decrypt(*exchange_key, key.get_or_insert([0u8; KEY_SIZE])).await?;
+                    }
+                    None if subscriber_id.identifier() == user_id.identifier() && generation.inner() == 0 => {
+                        fork.decrypt(user_id, key.get_or_insert([0u8; KEY_SIZE])).await?;
+                    }
+                    _ => {
+                        fork.drop(KEY_SIZE + x25519::PUBLIC_KEY_LENGTH)?;
+                    }
                 }
             } else {
                 fork.drop(KEY_SIZE + x25519::PUBLIC_KEY_LENGTH)?;
             }
             keyload.subscribers.push(subscriber_id);
         }
-        self.absorb(&mut n_psks)?;
+        self.absorb(&mut n_psks)?.check_size("keyload psks", n_psks.inner())?;
 
         for _ in 0..n_psks.inner() {
             let mut fork = self.fork();
@@ -300,9 +459,25 @@ where
         }
 
         if let Some(key) = key {
+            let mut extensions = Uint8::new(0);
             self.absorb(External::new(&NBytes::new(&key)))?
-                .verify(keyload.author_id)
-                .await?;
+                .commit()?
+                .mask(&mut extensions)?;
+            let extensions = extensions.inner();
+            if extensions & !EXTENSIONS != 0 {
+                return Err(SpongosError::InvalidOption("keyload extensions", extensions));
+            }
+            if extensions & METADATA != 0 {
+                let mut metadata = BranchMetadata::default();
+                self.mask(&mut metadata)?;
+                keyload.metadata = Some(metadata);
+            }
+            if extensions & EXPIRY != 0 {
+                let mut expires_at = Uint64::default();
+                self.mask(&mut expires_at)?;
+                keyload.expires_at = Some(expires_at.inner());
+            }
+            self.verify(keyload.author_id).await?;
         }
         self.commit()?;
         Ok(self)
@@ -0,0 +1,246 @@
+//! Read-only decoding of legacy (v1) `iota-streams-app-channels` messages.
+//!
+//! Only the message types needed to follow the public trail of an old channel are supported: the
+//! `Announce` message that opens the channel and the `SignedPacket` messages published on it.
+//! Legacy messages are identified by the version byte of their header, and decoded using the same
+//! `DDML` commands as the current protocol.
+//!
+//! ```ddml
+//! message Header {
+//!     absorb          u8      encoding;
+//!     absorb          u8      version;
+//!     skip            u8      content_type_and_payload_length[2];
+//!     absorb external u8      content_type << 4;
+//!     absorb          u8      frame_type;
+//!     skip            u8      payload_frame_count[3];
+//!     absorb          u8      link[12];
+//!     skip            u64     seq_num;
+//!     absorb          u8      pcf_frame_type;
+//!     skip            u8      payload_frame_num[3];
+//! }
+//!
+//! message Announce {
+//!     absorb          u8      ed25519_pubkey[32];
+//!     absorb          u8      flags;
+//!     commit;
+//!     squeeze external u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//!
+//! message SignedPacket {
+//!     join(link);
+//!     absorb          u8      ed25519_pubkey[32];
+//!     absorb          bytes   public_payload;
+//!     mask            bytes   masked_payload;
+//!     commit;
+//!     squeeze external u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+use crypto::signatures::ed25519;
+
+// Streams
+use lets::{
+    address::MsgId,
+    id::Identifier,
+    message::{ContentUnwrap, TransportMessage},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{unwrap, Absorb, Commit, Ed25519, Guard, Join, Mask, Skip, Squeeze},
+        io,
+        modifiers::External,
+        types::{Bytes, NBytes, Uint64, Uint8},
+    },
+    error::{Error as SpongosError, Result},
+    Spongos,
+};
+
+// Local
+
+/// Streams version number of the legacy protocol
+pub(crate) const LEGACY_STREAMS_VER: u8 = 1;
+
+/// Legacy Announce Message Type
+pub(crate) const ANNOUNCE: u8 = 0;
+/// Legacy Signed Packet Message Type
+pub(crate) const SIGNED_PACKET: u8 = 3;
+
+/// Returns true if the version byte of the binary message belongs to the legacy protocol
+///
+/// # Arguments
+/// * `msg`: The raw [`TransportMessage`] retrieved from transport
+pub(crate) fn is_legacy(msg: &TransportMessage) -> bool {
+    msg.as_ref().get(1) == Some(&LEGACY_STREAMS_VER)
+}
+
+/// Verifies the legacy ed25519 signature over the current state of the context
+fn verify<IS: io::IStream>(ctx: &mut unwrap::Context<IS>, public_key: &ed25519::PublicKey) -> Result<()> {
+    let mut hash = External::new(NBytes::new([0; 64]));
+    ctx.commit()?
+        .squeeze(hash.as_mut())?
+        .ed25519(public_key, hash.as_ref())?;
+    Ok(())
+}
+
+/// Reads a raw ed25519 public key as it was encoded by the legacy protocol
+fn absorb_public_key<IS: io::IStream>(ctx: &mut unwrap::Context<IS>) -> Result<ed25519::PublicKey> {
+    let mut bytes = NBytes::<[u8; ed25519::PUBLIC_KEY_LENGTH]>::default();
+    ctx.absorb(bytes.as_mut())?;
+    ed25519::PublicKey::try_from_bytes(*bytes.inner()).map_err(SpongosError::PublicKeyGenerationFailure)
+}
+
+/// A struct that holds the placeholders needed for legacy header decoding
+#[derive(Default)]
+pub(crate) struct Header {
+    /// Legacy message type
+    message_type: u8,
+    /// Relative address of the message this one is linked to
+    link: MsgId,
+    /// Sequence number of the message
+    sequence: u64,
+}
+
+impl Header {
+    /// Returns the legacy message type
+    pub(crate) fn message_type(&self) -> u8 {
+        self.message_type
+    }
+
+    /// Returns the relative address of the linked message
+    pub(crate) fn link(&self) -> MsgId {
+        self.link
+    }
+
+    /// Returns the sequence number of the message
+    pub(crate) fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<IS> ContentUnwrap<Header> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, header: &mut Header) -> Result<&mut Self> {
+        let mut encoding = Uint8::default();
+        let mut version = Uint8::default();
+        let mut message_type_and_payload_length = NBytes::<[u8; 2]>::default();
+        let mut frame_type = Uint8::default();
+        let mut payload_frame_count = NBytes::<[u8; 3]>::default();
+        let mut seq_num = Uint64::default();
+        let mut pcf_frame_type = Uint8::default();
+        let mut payload_frame_num = NBytes::<[u8; 3]>::default();
+
+        self.absorb(&mut encoding)?
+            .absorb(&mut version)?
+            .guard(
+                version.inner() == LEGACY_STREAMS_VER,
+                SpongosError::Version("Legacy Msg", version.inner()),
+            )?
+            .skip(message_type_and_payload_length.as_mut())?
+            .absorb(External::new(Uint8::new(
+                message_type_and_payload_length[0] & 0b11110000,
+            )))?
+            .absorb(&mut frame_type)?
+            .skip(payload_frame_count.as_mut())?
+            .absorb(&mut header.link)?
+            .skip(&mut seq_num)?
+            .absorb(&mut pcf_frame_type)?
+            .skip(payload_frame_num.as_mut())?;
+
+        header.message_type = message_type_and_payload_length[0] >> 4;
+        header.sequence = seq_num.inner();
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for legacy announce message decoding
+#[derive(Default)]
+pub(crate) struct Announce {
+    /// The [`Identifier`] of the channel author
+    author_id: Identifier,
+}
+
+impl Announce {
+    /// Consumes the [`Announce`], returning the author [`Identifier`]
+    pub(crate) fn into_author_id(self) -> Identifier {
+        self.author_id
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<IS> ContentUnwrap<Announce> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, announce: &mut Announce) -> Result<&mut Self> {
+        let public_key = absorb_public_key(self)?;
+        let mut flags = Uint8::default();
+        self.absorb(&mut flags)?;
+        verify(self, &public_key)?;
+        announce.author_id = Identifier::Ed25519(public_key);
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for legacy signed packet message decoding
+pub(crate) struct SignedPacket<'a> {
+    /// The [`Spongos`] state of the linked message
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the publisher
+    publisher_id: Identifier,
+    /// A payload that was not masked
+    public_payload: Vec<u8>,
+    /// A payload that was masked
+    masked_payload: Vec<u8>,
+}
+
+impl<'a> SignedPacket<'a> {
+    /// Creates a new [`SignedPacket`] struct for a legacy signed packet message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The [`Spongos`] state of the linked message
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher_id: Default::default(),
+            public_payload: Default::default(),
+            masked_payload: Default::default(),
+        }
+    }
+
+    /// Consumes the [`SignedPacket`], returning the publisher [`Identifier`] and the public and
+    /// masked payloads
+    pub(crate) fn into_parts(self) -> (Identifier, Vec<u8>, Vec<u8>) {
+        (self.publisher_id, self.public_payload, self.masked_payload)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<SignedPacket<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, signed_packet: &mut SignedPacket<'a>) -> Result<&mut Self> {
+        self.join(signed_packet.initial_state)?;
+        let public_key = absorb_public_key(self)?;
+        self.absorb(Bytes::new(&mut signed_packet.public_payload))?
+            .mask(Bytes::new(&mut signed_packet.masked_payload))?;
+        verify(self, &public_key)?;
+        signed_packet.publisher_id = Identifier::Ed25519(public_key);
+        Ok(self)
+    }
+}
@@ -12,3 +12,27 @@ pub(crate) const TAGGED_PACKET: u8 = 4;
 pub(crate) const SUBSCRIPTION: u8 = 5;
 /// Unsubscribe Message Type
 pub(crate) const UNSUBSCRIPTION: u8 = 6;
+/// Selective Packet Message Type
+pub(crate) const SELECTIVE_PACKET: u8 = 7;
+/// Key Update Message Type
+pub(crate) const KEY_UPDATE: u8 = 8;
+/// Branch Closure Message Type
+pub(crate) const BRANCH_CLOSURE: u8 = 9;
+/// Subscribe Message Type, for subscriptions carrying an invite
+pub(crate) const INVITED_SUBSCRIPTION: u8 = 10;
+/// Substream Announcement Message Type
+pub(crate) const SUBSTREAM_ANNOUNCEMENT: u8 = 11;
+/// Batch Packet Message Type
+pub(crate) const BATCH_PACKET: u8 = 12;
+/// Read Marker Message Type
+pub(crate) const READ_MARKER: u8 = 13;
+/// Tombstone Message Type
+pub(crate) const TOMBSTONE: u8 = 14;
+/// Author Rotation Message Type
+pub(crate) const AUTHOR_ROTATION: u8 = 15;
+/// Countersignature Message Type
+pub(crate) const COUNTERSIGNATURE: u8 = 16;
+/// Commitment Message Type
+pub(crate) const COMMITMENT: u8 = 17;
+/// Reveal Message Type
+pub(crate) const REVEAL: u8 = 18;
@@ -12,14 +12,56 @@ pub(crate) mod signed_packet;
 /// TaggedPacket message.
 pub(crate) mod tagged_packet;
 
+/// SelectivePacket message.
+pub(crate) mod selective_packet;
+
 /// Subscribe message.
 pub(crate) mod subscription;
 
 /// Unsubscribe message.
 pub(crate) mod unsubscription;
 
+/// KeyUpdate message.
+pub(crate) mod key_update;
+
 /// Message type constants
 pub(crate) mod message_types;
 
 /// BranchAnnouncement message.
 pub(crate) mod branch_announcement;
+
+/// BranchClosure message.
+pub(crate) mod branch_closure;
+
+/// SubstreamAnnouncement message.
+pub(crate) mod substream_announcement;
+
+/// BatchPacket message.
+pub(crate) mod batch_packet;
+
+/// ReadMarker message.
+pub(crate) mod read_marker;
+
+/// Tombstone message.
+pub(crate) mod tombstone;
+
+/// AuthorRotation message.
+pub(crate) mod author_rotation;
+
+/// Countersignature message.
+pub(crate) mod countersignature;
+
+/// Commitment message.
+pub(crate) mod commitment;
+
+/// Reveal message.
+pub(crate) mod reveal;
+
+/// Custom message, of a message type defined by the application.
+pub(crate) mod custom_message;
+
+/// Legacy (v1) message decoding.
+pub(crate) mod legacy;
+
+#[cfg(test)]
+mod tests;
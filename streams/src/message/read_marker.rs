@@ -0,0 +1,150 @@
+//! `ReadMarker` message _wrapping_ and _unwrapping_.
+//!
+//! The `ReadMarker` message records the last message processed by a consumer. Consumers of a group
+//! publish their markers in a branch shared by the group, usually private, so that each of them
+//! learns how far the others have read.
+//!
+//! ```ddml
+//! message ReadMarker {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     mask             u8     marked_address[52];
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// A struct that holds references needed for read marker message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// The [`Address`] of the last message processed by the publisher
+    marked_address: &'a Address,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a read marker message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    /// * `marked_address`: The [`Address`] of the last message processed by the publisher
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: &'a Identity, marked_address: &'a Address) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            marked_address,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, read_marker: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(read_marker.user_id.identifier())?
+            .mask(read_marker.marked_address)?
+            .sign_sizeof(read_marker.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, read_marker: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(read_marker.initial_state)?
+            .mask(read_marker.user_id.identifier())?
+            .mask(read_marker.marked_address)?
+            .sign(read_marker.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for read marker message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+    /// The [`Address`] of the last message processed by the publisher
+    marked_address: Address,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a read marker message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher: Identifier::default(),
+            marked_address: Address::default(),
+        }
+    }
+
+    /// Returns the [`Address`] of the last message processed by the publisher
+    pub(crate) fn marked_address(&self) -> Address {
+        self.marked_address
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher and the [`Address`]
+    /// of the last message it processed
+    pub(crate) fn into_parts(self) -> (Identifier, Address) {
+        (self.publisher, self.marked_address)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, read_marker: &mut Unwrap) -> Result<&mut Self> {
+        self.join(read_marker.initial_state)?
+            .mask(&mut read_marker.publisher)?
+            .mask(&mut read_marker.marked_address)?
+            .verify(&read_marker.publisher)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
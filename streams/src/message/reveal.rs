@@ -0,0 +1,177 @@
+//! `Reveal` message _wrapping_ and _unwrapping_.
+//!
+//! The `Reveal` message discloses the payload a user committed to in an earlier `Commitment`
+//! message of the same branch, which it designates. Readers check that the digest of the payload
+//! matches the one of the commitment.
+//!
+//! ```ddml
+//! message Reveal {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     mask             u8     commitment_msgid[12];
+//!     mask             bytes  payload;
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::MsgId,
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+        types::Bytes,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// A struct that holds references needed for reveal message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// The [`MsgId`] of the commitment the payload was committed to in
+    commitment: &'a MsgId,
+    /// The revealed payload
+    payload: &'a [u8],
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a reveal message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    /// * `commitment`: The [`MsgId`] of the commitment
+    /// * `payload`: The revealed payload
+    pub(crate) fn new(
+        initial_state: &'a mut Spongos,
+        user_id: &'a Identity,
+        commitment: &'a MsgId,
+        payload: &'a [u8],
+    ) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            commitment,
+            payload,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, reveal: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(reveal.user_id.identifier())?
+            .mask(reveal.commitment)?
+            .mask(Bytes::new(reveal.payload))?
+            .sign_sizeof(reveal.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, reveal: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(reveal.initial_state)?
+            .mask(reveal.user_id.identifier())?
+            .mask(reveal.commitment)?
+            .mask(Bytes::new(reveal.payload))?
+            .sign(reveal.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for reveal message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+    /// The [`MsgId`] of the commitment the payload was committed to in
+    commitment: MsgId,
+    /// The revealed payload
+    payload: Vec<u8>,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a reveal message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher: Identifier::default(),
+            commitment: MsgId::default(),
+            payload: Vec::new(),
+        }
+    }
+
+    /// Returns the [`Identifier`] of the publisher
+    pub(crate) fn publisher(&self) -> &Identifier {
+        &self.publisher
+    }
+
+    /// Returns the [`MsgId`] of the commitment
+    pub(crate) fn commitment(&self) -> MsgId {
+        self.commitment
+    }
+
+    /// Returns the revealed payload
+    pub(crate) fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher, the [`MsgId`] of the
+    /// commitment and the revealed payload
+    pub(crate) fn into_parts(self) -> (Identifier, MsgId, Vec<u8>) {
+        (self.publisher, self.commitment, self.payload)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, reveal: &mut Unwrap) -> Result<&mut Self> {
+        self.join(reveal.initial_state)?
+            .mask(&mut reveal.publisher)?
+            .mask(&mut reveal.commitment)?
+            .mask(Bytes::new(&mut reveal.payload))?
+            .verify(&reveal.publisher)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
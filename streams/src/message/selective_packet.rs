@@ -0,0 +1,372 @@
+//! `SelectivePacket` message _wrapping_ and _unwrapping_.
+//!
+//! `SelectivePacket` messages carry a list of fields, each one encrypted to its own set of
+//! recipients, signed by the sender. Every field is masked with a fresh key, which is shared with
+//! the recipients of the field the same way a `Keyload` shares the branch key with subscribers.
+//! Readers recover the fields they are a recipient of, and skip the others.
+//!
+//! The fields are masked in forks of the message state, so each one is followed by a tag squeezed
+//! from its fork. The tags are absorbed in clear by the main state, binding the ciphertexts to the
+//! signature for every reader, whether or not it can decrypt them.
+//!
+//! ```ddml
+//! message SelectivePacket {
+//!     join(spongos);
+//!     absorb external             u8  ratchet_key[32]; // forward secrecy branches only
+//!     mask                        u8  identifier;
+//!     absorb                      u8  size(n_fields);
+//!     repeated(n_fields):
+//!       absorb                    u8  size(n_recipients);
+//!       repeated(n_recipients):
+//!         fork;
+//!         mask                    u8  recipient;
+//!         mask                    u8  size(exchange_key_generation);
+//!         x25519(pub/priv_key)    u8  x25519_pubkey[32];
+//!       absorb                    u8  size(field_size);
+//!       fork;
+//!       absorb external           u8  key[32];
+//!       commit;
+//!       mask                      u8  field[field_size];
+//!       commit;
+//!       squeeze external          u8  tag[32];
+//!       absorb                    u8  tag[32];
+//!     commit;
+//!     squeeze external            u8  hash[64];
+//!     ed25519(hash)               u8  signature[64];
+//! }
+//! ```
+// Rust
+use alloc::{boxed::Box, vec::Vec};
+
+// 3rd-party
+use async_trait::async_trait;
+use hashbrown::HashMap;
+
+// IOTA
+use crypto::keys::x25519;
+
+// Streams
+use lets::{
+    id::{Identifier, Identity, KeyExchange},
+    message::{
+        ContentDecrypt, ContentEncrypt, ContentEncryptSizeOf, ContentSign, ContentSignSizeof, ContentSizeof,
+        ContentUnwrap, ContentVerify, ContentWrap,
+    },
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Absorb, Commit, Fork, Join, Mask, Squeeze, X25519},
+        io,
+        modifiers::External,
+        types::{NBytes, Size},
+    },
+    error::{Error as SpongosError, Result},
+    Spongos,
+};
+
+// Local
+use crate::message::key_update::ExchangeKey;
+
+/// Size of the key each field is masked with
+pub(crate) const KEY_SIZE: usize = 32;
+/// Size of the tag binding each masked field to the signature
+const TAG_SIZE: usize = 32;
+
+/// A struct that holds references needed for selective packet message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The fields of the packet, with the [`Identifier`] of their recipients
+    fields: &'a [(Vec<Identifier>, Vec<u8>)],
+    /// The keys the fields will be masked with, one per field
+    keys: &'a [[u8; KEY_SIZE]],
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
+    /// The key exchange keys announced by the recipients, replacing the ones derived from their
+    /// identity
+    exchange_keys: Option<&'a HashMap<Identifier, ExchangeKey>>,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a selective packet message
+    ///
+    /// # Arguments:
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publishing user.
+    /// * `fields`: The fields of the packet, each with the recipients allowed to read it.
+    /// * `keys`: The keys used to mask the fields, one per field.
+    pub(crate) fn new(
+        initial_state: &'a mut Spongos,
+        user_id: &'a Identity,
+        fields: &'a [(Vec<Identifier>, Vec<u8>)],
+        keys: &'a [[u8; KEY_SIZE]],
+    ) -> Self {
+        debug_assert_eq!(fields.len(), keys.len(), "every field must have its own key");
+        Self {
+            initial_state,
+            fields,
+            keys,
+            user_id,
+            ratchet_key: None,
+            exchange_keys: None,
+        }
+    }
+
+    /// Encrypts the field keys for the recipients that announced a key exchange key with a
+    /// `KeyUpdate` to that key, instead of the one derived from their identity
+    ///
+    /// # Arguments
+    /// * `exchange_keys`: The announced key exchange keys, mapped by recipient [`Identifier`]
+    pub(crate) fn with_exchange_keys(mut self, exchange_keys: &'a HashMap<Identifier, ExchangeKey>) -> Self {
+        self.exchange_keys = Some(exchange_keys);
+        self
+    }
+
+    /// Returns the [`ExchangeKey`] announced by a recipient, if any
+    fn exchange_key(&self, recipient: &Identifier) -> Option<ExchangeKey> {
+        self.exchange_keys.and_then(|keys| keys.get(recipient)).copied()
+    }
+
+    /// Absorbs the key of the publisher ratchet after the join, if the branch of the packet is in
+    /// forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, selective_packet: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(selective_packet.user_id.identifier())?
+            .absorb(Size::new(selective_packet.fields.len()))?;
+        for ((recipients, field), key) in selective_packet.fields.iter().zip(selective_packet.keys) {
+            self.absorb(Size::new(recipients.len()))?;
+            // Loop through the recipients of the field, masking its key for each one
+            for recipient in recipients {
+                match selective_packet.exchange_key(recipient) {
+                    Some(exchange_key) => {
+                        self.fork()
+                            .mask(recipient)?
+                            .mask(Size::new(exchange_key.generation))?
+                            .x25519(&exchange_key.public_key(), NBytes::new(key))?;
+                    }
+                    None => {
+                        self.fork()
+                            .mask(recipient)?
+                            .mask(Size::new(0))?
+                            .encrypt_sizeof(recipient, key)
+                            .await?;
+                    }
+                }
+            }
+            self.absorb(Size::new(field.len()))?
+                .fork()
+                .absorb(External::new(&NBytes::new(key)))?
+                .commit()?
+                .mask(NBytes::new(field))?;
+            self.absorb(NBytes::new([0u8; TAG_SIZE]))?;
+        }
+        self.sign_sizeof(selective_packet.user_id).await?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, selective_packet: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(selective_packet.initial_state)?;
+        if let Some(ratchet_key) = &selective_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        self.mask(selective_packet.user_id.identifier())?
+            .absorb(Size::new(selective_packet.fields.len()))?;
+        for ((recipients, field), key) in selective_packet.fields.iter().zip(selective_packet.keys) {
+            self.absorb(Size::new(recipients.len()))?;
+            // Loop through the recipients of the field, masking its key for each one
+            for recipient in recipients {
+                match selective_packet.exchange_key(recipient) {
+                    Some(exchange_key) => {
+                        self.fork()
+                            .mask(recipient)?
+                            .mask(Size::new(exchange_key.generation))?
+                            .x25519(&exchange_key.public_key(), NBytes::new(key))?;
+                    }
+                    None => {
+                        self.fork()
+                            .mask(recipient)?
+                            .mask(Size::new(0))?
+                            .encrypt(recipient, key)
+                            .await?;
+                    }
+                }
+            }
+            let mut tag = NBytes::new([0u8; TAG_SIZE]);
+            self.absorb(Size::new(field.len()))?
+                .fork()
+                .absorb(External::new(&NBytes::new(key)))?
+                .commit()?
+                .mask(NBytes::new(field))?
+                .commit()?
+                .squeeze(External::new(&mut tag))?;
+            self.absorb(tag)?;
+        }
+        self.sign(selective_packet.user_id).await?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for selective packet message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The fields of the packet, `None` for those the reader is not a recipient of
+    fields: Vec<Option<Vec<u8>>>,
+    /// The [`Identity`] of the reader
+    user_id: Option<&'a Identity>,
+    /// The [`Identifier`] of the publisher
+    publisher_id: Identifier,
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
+    /// The [`KeyExchange`] holding the current key exchange key of the reader and its generation, if
+    /// it has been rotated
+    exchange_key: Option<(usize, &'a dyn KeyExchange)>,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a selective packet message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The base [`Spongos`] state that the message will be joined to
+    /// * `user_id`: The optional [`Identity`] of the reading user
+    pub(crate) fn new(initial_state: &'a mut Spongos, user_id: Option<&'a Identity>) -> Self {
+        Self {
+            initial_state,
+            fields: Vec::default(),
+            user_id,
+            publisher_id: Identifier::default(),
+            ratchet_key: None,
+            exchange_key: None,
+        }
+    }
+
+    /// Decrypts the field keys with a rotated key exchange key of the reader, instead of the one
+    /// derived from its identity
+    ///
+    /// # Arguments
+    /// * `exchange_key`: The current key exchange key of the reader and its generation
+    pub(crate) fn with_exchange_key(mut self, exchange_key: Option<(usize, &'a dyn KeyExchange)>) -> Self {
+        self.exchange_key = exchange_key;
+        self
+    }
+
+    /// Absorbs the expected key of the publisher ratchet after the join, if the branch of the
+    /// packet is in forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher
+    pub(crate) fn into_publisher_identifier(self) -> Identifier {
+        self.publisher_id
+    }
+
+    /// Takes the fields of the packet from the [`Unwrap`]
+    pub(crate) fn take_fields(&mut self) -> Vec<Option<Vec<u8>>> {
+        core::mem::take(&mut self.fields)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, selective_packet: &mut Unwrap<'a>) -> Result<&mut Self> {
+        let mut n_fields = Size::default();
+        self.join(selective_packet.initial_state)?;
+        if let Some(ratchet_key) = &selective_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        self.mask(&mut selective_packet.publisher_id)?
+            .absorb(&mut n_fields)?
+            .check_size("selective packet fields", n_fields.inner())?;
+
+        for _ in 0..n_fields.inner() {
+            let mut key: Option<[u8; KEY_SIZE]> = None;
+            let mut n_recipients = Size::default();
+            self.absorb(&mut n_recipients)?
+                .check_size("selective packet recipients", n_recipients.inner())?;
+            for _ in 0..n_recipients.inner() {
+                let mut fork = self.fork();
+                // Loop through the recipients of the field and their masked keys
+                let mut recipient = Identifier::default();
+                let mut generation = Size::default();
+                fork.mask(&mut recipient)?.mask(&mut generation)?;
+                let is_reader = matches!(
+                    selective_packet.user_id,
+                    Some(user_id) if key.is_none() && &recipient == user_id.identifier()
+                );
+                // The key can only be recovered if it was encrypted to the current key exchange key of the
+                // reader
+                match (selective_packet.user_id, &selective_packet.exchange_key) {
+                    (Some(_), Some((current, exchange_key))) if is_reader && generation.inner() == *current => {
+                        fork.decrypt(*exchange_key, key.get_or_insert([0u8; KEY_SIZE])).await?;
+                    }
+                    (Some(user_id), None) if is_reader && generation.inner() == 0 => {
+                        fork.decrypt(user_id, key.get_or_insert([0u8; KEY_SIZE])).await?;
+                    }
+                    _ => {
+                        fork.drop(KEY_SIZE + x25519::PUBLIC_KEY_LENGTH)?;
+                    }
+                }
+            }
+
+            let mut field_size = Size::default();
+            self.absorb(&mut field_size)?
+                .check_size("selective packet field", field_size.inner())?;
+            let field = match key {
+                Some(key) => {
+                    let mut field = vec![0u8; field_size.inner()];
+                    let mut expected_tag = NBytes::new([0u8; TAG_SIZE]);
+                    self.fork()
+                        .absorb(External::new(&NBytes::new(key)))?
+                        .commit()?
+                        .mask(NBytes::new(&mut field))?
+                        .commit()?
+                        .squeeze(External::new(&mut expected_tag))?;
+                    let mut tag = [0u8; TAG_SIZE];
+                    self.absorb(NBytes::new(&mut tag))?;
+                    if &tag != expected_tag.inner() {
+                        return Err(SpongosError::BadMac);
+                    }
+                    Some(field)
+                }
+                None => {
+                    self.drop(field_size.inner())?.absorb(NBytes::new([0u8; TAG_SIZE]))?;
+                    None
+                }
+            };
+            selective_packet.fields.push(field);
+        }
+
+        self.verify(&selective_packet.publisher_id).await?;
+        Ok(self)
+    }
+}
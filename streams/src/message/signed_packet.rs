@@ -2,14 +2,35 @@
 //!
 //! `SignedPacket` messages contain a plain and a masked payload, signed by the sender.
 //!
+//! The extensions of a packet are flagged after the identifier of its publisher, each flag
+//! announcing an optional field: a detached signature of the masked payload, verifiable without the
+//! state of the channel ([`DETACHED_SIGNATURE`]), the [`Reference`]s to messages cited by the
+//! packet, possibly of other channels ([`REFERENCES`]), and the reference to the previous packet of
+//! the publisher whose payloads the packet repeats, carried instead of the payloads
+//! ([`REPEATED_PAYLOADS`], see [`repeated_payloads`](crate::api::repeated_payloads)).
+//!
+//! Packets published on a branch whose access expires carry their publish time ([`PUBLISHED_AT`]),
+//! signed along the packet, so that readers compare it to the expiry of their access. It precedes
+//! the payloads, so that the payloads of a packet published after the expiry are never unmasked.
+//!
 //! ```ddml
 //! message SignedPacket {
 //!     join(spongos);
+//!     absorb external    u8      ratchet_key[32]; // forward secrecy branches only
 //!     mask                u8      identifier;
-//!     absorb              uint    public_size;
-//!     absorb              u8      public_payload[public_size];
-//!     mask                uint    masked_size;
-//!     mask                u8      masked_payload[masked_size];
+//!     mask                u8      extensions;
+//!     mask                u64     published_at;           // PUBLISHED_AT only
+//!     absorb              uint    public_size;            // unless REPEATED_PAYLOADS
+//!     absorb              u8      public_payload[public_size]; // unless REPEATED_PAYLOADS
+//!     mask                uint    masked_size;            // unless REPEATED_PAYLOADS
+//!     mask                u8      masked_payload[masked_size]; // unless REPEATED_PAYLOADS
+//!     mask                u8      original[12];           // REPEATED_PAYLOADS only
+//!     mask                u8      payload_digest[32];     // REPEATED_PAYLOADS only
+//!     mask                bytes   detached_signature;     // DETACHED_SIGNATURE only
+//!     mask                u8      size(n_references);     // REFERENCES only
+//!     repeated(n_references):
+//!       mask              u8      reference_address[52];
+//!       mask              u8      reference_digest[32];
 //!     commit;
 //!     squeeze external    u8      hash[64];
 //!     ed25519(hash)       u8      signature[64];
@@ -22,23 +43,38 @@ use alloc::{boxed::Box, vec::Vec};
 use async_trait::async_trait;
 
 // IOTA
+use crypto::signatures::ed25519;
 
 // Streams
 use lets::{
     id::{Identifier, Identity},
     message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
 };
 use spongos::{
     ddml::{
-        commands::{sizeof, unwrap, wrap, Absorb, Join, Mask},
+        commands::{sizeof, unwrap, wrap, Absorb, Commit, Join, Mask, Skip, Squeeze},
         io,
-        types::Bytes,
+        modifiers::External,
+        types::{Bytes, NBytes, Size, Uint64, Uint8},
     },
-    error::Result,
+    error::{Error as SpongosError, Result},
     Spongos,
 };
 
 // Local
+use crate::api::{reference::Reference, repeated_payloads::RepeatedPayloads};
+
+/// Extension flag of the packets repeating the payloads of the previous packet of their publisher
+pub(crate) const REPEATED_PAYLOADS: u8 = 1;
+/// Extension flag of the packets carrying a detached signature of their masked payload
+pub(crate) const DETACHED_SIGNATURE: u8 = 1 << 1;
+/// Extension flag of the packets citing messages through [`Reference`]s
+pub(crate) const REFERENCES: u8 = 1 << 2;
+/// Extension flag of the packets carrying their publish time
+pub(crate) const PUBLISHED_AT: u8 = 1 << 3;
+/// The extension flags known to this version
+const EXTENSIONS: u8 = REPEATED_PAYLOADS | DETACHED_SIGNATURE | REFERENCES | PUBLISHED_AT;
 
 /// A struct that holds references needed for signed packet message encoding
 pub(crate) struct Wrap<'a> {
@@ -50,6 +86,16 @@ pub(crate) struct Wrap<'a> {
     masked_payload: &'a [u8],
     /// The [`Identity`] of the publisher
     user_id: &'a Identity,
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
+    /// Detached signature of the masked payload, to include in the packet
+    detached_signature: Option<&'a [u8]>,
+    /// Messages cited by the packet, to include in the packet
+    references: Option<&'a [Reference]>,
+    /// Reference to the packet that first carried the payloads, replacing the payloads
+    repeated: Option<RepeatedPayloads>,
+    /// Unix timestamp, in seconds, at which the packet is published, to include in the packet
+    published_at: Option<u64>,
 }
 
 impl<'a> Wrap<'a> {
@@ -71,38 +117,164 @@ impl<'a> Wrap<'a> {
             user_id,
             public_payload,
             masked_payload,
+            ratchet_key: None,
+            detached_signature: None,
+            references: None,
+            repeated: None,
+            published_at: None,
+        }
+    }
+
+    /// Absorbs the key of the publisher ratchet after the join, if the branch of the packet is in
+    /// forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+
+    /// Includes a detached signature of the masked payload in the packet
+    ///
+    /// # Arguments
+    /// * `detached_signature`: The bytes of the detached signature
+    pub(crate) fn with_detached_signature(mut self, detached_signature: &'a [u8]) -> Self {
+        self.detached_signature = Some(detached_signature);
+        self
+    }
+
+    /// Includes the references to the messages cited by the packet
+    ///
+    /// # Arguments
+    /// * `references`: The [`Reference`]s to the cited messages
+    pub(crate) fn with_references(mut self, references: &'a [Reference]) -> Self {
+        self.references = Some(references);
+        self
+    }
+
+    /// Replaces the payloads of the packet with a reference to the packet that first carried them
+    ///
+    /// # Arguments
+    /// * `repeated`: The [`RepeatedPayloads`] referring to the original packet
+    pub(crate) fn with_repeated_payloads(mut self, repeated: RepeatedPayloads) -> Self {
+        self.repeated = Some(repeated);
+        self
+    }
+
+    /// Includes the publish time of the packet, if any
+    ///
+    /// # Arguments
+    /// * `published_at`: The Unix timestamp, in seconds, at which the packet is published
+    pub(crate) fn with_published_at(mut self, published_at: Option<u64>) -> Self {
+        self.published_at = published_at;
+        self
+    }
+
+    /// Returns the extension flags of the optional fields included in the packet
+    fn extensions(&self) -> u8 {
+        let mut extensions = 0;
+        if self.repeated.is_some() {
+            extensions |= REPEATED_PAYLOADS;
+        }
+        if self.detached_signature.is_some() {
+            extensions |= DETACHED_SIGNATURE;
+        }
+        if self.references.is_some() {
+            extensions |= REFERENCES;
         }
+        if self.published_at.is_some() {
+            extensions |= PUBLISHED_AT;
+        }
+        extensions
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     async fn sizeof(&mut self, signed_packet: &Wrap<'a>) -> Result<&mut Self> {
         self.mask(signed_packet.user_id.identifier())?
-            .absorb(Bytes::new(signed_packet.public_payload))?
-            .mask(Bytes::new(signed_packet.masked_payload))?
-            .sign_sizeof(signed_packet.user_id)
-            .await?;
+            .mask(Uint8::new(signed_packet.extensions()))?;
+        if let Some(published_at) = signed_packet.published_at {
+            self.mask(Uint64::new(published_at))?;
+        }
+        match &signed_packet.repeated {
+            Some(repeated) => self.mask(&repeated.original)?.mask(NBytes::new(&repeated.digest))?,
+            None => self
+                .absorb(Bytes::new(signed_packet.public_payload))?
+                .mask(Bytes::new(signed_packet.masked_payload))?,
+        };
+        if let Some(detached_signature) = signed_packet.detached_signature {
+            self.mask(Bytes::new(detached_signature))?;
+        }
+        if let Some(references) = signed_packet.references {
+            self.mask(Size::new(references.len()))?;
+            for reference in references {
+                self.mask(reference)?;
+            }
+        }
+        self.sign_sizeof(signed_packet.user_id).await?;
         Ok(self)
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
 where
-    OS: io::OStream,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, signed_packet: &mut Wrap<'a>) -> Result<&mut Self> {
-        self.join(signed_packet.initial_state)?
-            .mask(signed_packet.user_id.identifier())?
-            .absorb(Bytes::new(signed_packet.public_payload))?
-            .mask(Bytes::new(signed_packet.masked_payload))?
-            .sign(signed_packet.user_id)
-            .await?;
+        self.join(signed_packet.initial_state)?;
+        if let Some(ratchet_key) = &signed_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        self.mask(signed_packet.user_id.identifier())?
+            .mask(Uint8::new(signed_packet.extensions()))?;
+        if let Some(published_at) = signed_packet.published_at {
+            self.mask(Uint64::new(published_at))?;
+        }
+        match &signed_packet.repeated {
+            Some(repeated) => self.mask(&repeated.original)?.mask(NBytes::new(&repeated.digest))?,
+            None => self
+                .absorb(Bytes::new(signed_packet.public_payload))?
+                .mask(Bytes::new(signed_packet.masked_payload))?,
+        };
+        if let Some(detached_signature) = signed_packet.detached_signature {
+            self.mask(Bytes::new(detached_signature))?;
+        }
+        if let Some(references) = signed_packet.references {
+            self.mask(Size::new(references.len()))?;
+            for reference in references {
+                self.mask(reference)?;
+            }
+        }
+        self.sign(signed_packet.user_id).await?;
         Ok(self)
     }
 }
 
+/// The hash of the spongos state of a signed packet and its Ed25519 signature
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct PacketSignature {
+    /// The hash signed by the publisher
+    pub(crate) hash: [u8; 64],
+    /// The signature of the hash
+    pub(crate) signature: [u8; ed25519::SIGNATURE_LENGTH],
+}
+
+/// Handling of the Ed25519 signature of a signed packet
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum SignatureCheck {
+    /// The signature is verified while unwrapping the packet
+    Verify,
+    /// The signature is read with the hash it signs, to be verified afterwards
+    Collect(Option<PacketSignature>),
+    /// The signature was verified beforehand, along the hash it signs
+    Verified(PacketSignature),
+}
+
 /// A struct that holds the placeholders needed for signed packet message decoding
 #[derive(PartialEq, Eq, Hash)]
 pub(crate) struct Unwrap<'a> {
@@ -114,6 +286,28 @@ pub(crate) struct Unwrap<'a> {
     masked_payload: Vec<u8>,
     /// The [`Identifier`] of the publisher
     publisher_id: Identifier,
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
+    /// Stop before the masked payload, leaving it and the signature to be read incrementally
+    until_masked_payload: bool,
+    /// The extension flags of the optional fields carried by the packet
+    extensions: u8,
+    /// Detached signature of the masked payload, if the packet carries one
+    detached_signature: Option<Vec<u8>>,
+    /// Messages cited by the packet, if any
+    references: Option<Vec<Reference>>,
+    /// Reference to the packet that first carried the payloads, if the packet repeats them
+    repeated: Option<RepeatedPayloads>,
+    /// Unix timestamp, in seconds, at which the packet was published, if it carries it
+    published_at: Option<u64>,
+    /// Unix timestamp, in seconds, at which the access of the reader to the branch expires, if it
+    /// does
+    access_expiry: Option<u64>,
+    /// Whether the unwrapping stopped before the payloads, as the packet was published after the
+    /// access of the reader expired
+    withheld: bool,
+    /// Handling of the signature of Ed25519 publishers
+    signature_check: SignatureCheck,
 }
 
 impl<'a> Unwrap<'a> {
@@ -127,9 +321,111 @@ impl<'a> Unwrap<'a> {
             public_payload: Default::default(),
             masked_payload: Default::default(),
             publisher_id: Identifier::default(),
+            ratchet_key: None,
+            until_masked_payload: false,
+            extensions: 0,
+            detached_signature: None,
+            references: None,
+            repeated: None,
+            published_at: None,
+            access_expiry: None,
+            withheld: false,
+            signature_check: SignatureCheck::Verify,
         }
     }
 
+    /// Reads the signature of an Ed25519 publisher without verifying it, so that it can be taken
+    /// with [`Unwrap::take_signature()`] and verified along other signatures
+    pub(crate) fn collect_signature(mut self) -> Self {
+        self.signature_check = SignatureCheck::Collect(None);
+        self
+    }
+
+    /// Skips the verification of the signature of an Ed25519 publisher if it matches a signature
+    /// verified beforehand. A signature that does not match is verified as usual.
+    ///
+    /// # Arguments
+    /// * `signature`: The [`PacketSignature`] verified beforehand, if any
+    pub(crate) fn with_verified_signature(mut self, signature: Option<PacketSignature>) -> Self {
+        if let Some(signature) = signature {
+            self.signature_check = SignatureCheck::Verified(signature);
+        }
+        self
+    }
+
+    /// Takes the unverified signature read from the packet, if it was collected
+    pub(crate) fn take_signature(&mut self) -> Option<PacketSignature> {
+        match &mut self.signature_check {
+            SignatureCheck::Collect(signature) => signature.take(),
+            _ => None,
+        }
+    }
+
+    /// Stops the unwrapping before the masked payload, so that the masked payload can be read
+    /// with an [`unwrap::MaskedReader`] and the signature verified afterwards. Packets carrying
+    /// optional fields other than their publish time are rejected, as these fields are not read
+    /// incrementally.
+    pub(crate) fn until_masked_payload(mut self) -> Self {
+        self.until_masked_payload = true;
+        self
+    }
+
+    /// Stops the unwrapping before the payloads if the packet was published once the access of the
+    /// reader expired, or carries no publish time to tell. The payloads are then left masked, and
+    /// neither the packet nor its signature is read any further.
+    ///
+    /// # Arguments
+    /// * `access_expiry`: The Unix timestamp, in seconds, at which the access of the reader
+    ///   expires, if it does
+    pub(crate) fn with_access_expiry(mut self, access_expiry: Option<u64>) -> Self {
+        self.access_expiry = access_expiry;
+        self
+    }
+
+    /// Absorbs the expected key of the publisher ratchet after the join, if the branch of the
+    /// packet is in forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+
+    /// Takes the detached signature of the masked payload from the [`Unwrap`], if any
+    pub(crate) fn take_detached_signature(&mut self) -> Option<Vec<u8>> {
+        self.detached_signature.take()
+    }
+
+    /// Takes the references to the messages cited by the packet from the [`Unwrap`], empty if the
+    /// packet does not cite any message
+    pub(crate) fn take_references(&mut self) -> Vec<Reference> {
+        self.references.take().unwrap_or_default()
+    }
+
+    /// Returns the reference to the packet that first carried the payloads, if the packet is a
+    /// repeated packet
+    pub(crate) fn repeated_payloads(&self) -> Option<RepeatedPayloads> {
+        self.repeated
+    }
+
+    /// Returns true if the packet carries its payloads and none of the optional fields, apart from
+    /// its publish time
+    pub(crate) fn is_plain(&self) -> bool {
+        self.extensions & !PUBLISHED_AT == 0
+    }
+
+    /// Returns the Unix timestamp, in seconds, at which the packet was published, if it carries it
+    pub(crate) fn published_at(&self) -> Option<u64> {
+        self.published_at
+    }
+
+    /// Returns true if the unwrapping stopped before the payloads, as the packet was published
+    /// after the access of the reader expired (see [`Unwrap::with_access_expiry()`])
+    pub(crate) fn is_withheld(&self) -> bool {
+        self.withheld
+    }
+
     /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher
     pub(crate) fn into_publisher_identifier(self) -> Identifier {
         self.publisher_id
@@ -146,18 +442,107 @@ impl<'a> Unwrap<'a> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
 where
-    IS: io::IStream,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, signed_packet: &mut Unwrap) -> Result<&mut Self> {
-        self.join(signed_packet.initial_state)?
-            .mask(&mut signed_packet.publisher_id)?
-            .absorb(Bytes::new(&mut signed_packet.public_payload))?
-            .mask(Bytes::new(&mut signed_packet.masked_payload))?
-            .verify(&signed_packet.publisher_id)
-            .await?;
+        self.join(signed_packet.initial_state)?;
+        if let Some(ratchet_key) = &signed_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        let mut extensions = Uint8::new(0);
+        self.mask(&mut signed_packet.publisher_id)?.mask(&mut extensions)?;
+        let extensions = extensions.inner();
+        if extensions & !EXTENSIONS != 0 {
+            return Err(SpongosError::InvalidOption("signed packet extensions", extensions));
+        }
+        // Only plain packets are read incrementally, the optional fields follow the masked payload
+        if signed_packet.until_masked_payload && extensions & !PUBLISHED_AT != 0 {
+            return Err(SpongosError::InvalidOption(
+                "incrementally read signed packet",
+                extensions,
+            ));
+        }
+        signed_packet.extensions = extensions;
+        if extensions & PUBLISHED_AT != 0 {
+            let mut published_at = Uint64::default();
+            self.mask(&mut published_at)?;
+            signed_packet.published_at = Some(published_at.inner());
+        }
+        // Packets published after the access expired are left masked, packets without a publish
+        // time may have been as well
+        if let Some(access_expiry) = signed_packet.access_expiry {
+            if signed_packet
+                .published_at
+                .map_or(true, |published_at| published_at >= access_expiry)
+            {
+                signed_packet.withheld = true;
+                return Ok(self);
+            }
+        }
+        if extensions & REPEATED_PAYLOADS != 0 {
+            signed_packet.repeated = Some(RepeatedPayloads::default());
+        }
+        if extensions & DETACHED_SIGNATURE != 0 {
+            signed_packet.detached_signature = Some(Vec::new());
+        }
+        if extensions & REFERENCES != 0 {
+            signed_packet.references = Some(Vec::new());
+        }
+        if let Some(repeated) = &mut signed_packet.repeated {
+            self.mask(&mut repeated.original)?
+                .mask(NBytes::new(&mut repeated.digest))?;
+        } else {
+            self.absorb(Bytes::new(&mut signed_packet.public_payload))?;
+            if signed_packet.until_masked_payload {
+                return Ok(self);
+            }
+            self.mask(Bytes::new(&mut signed_packet.masked_payload))?;
+        }
+        if let Some(detached_signature) = &mut signed_packet.detached_signature {
+            self.mask(Bytes::new(detached_signature))?;
+        }
+        if let Some(references) = &mut signed_packet.references {
+            let mut references_count = Size::default();
+            self.mask(&mut references_count)?
+                .check_size("signed packet references", references_count.inner())?;
+            references.resize(references_count.inner(), Reference::default());
+            for reference in references.iter_mut() {
+                self.mask(reference)?;
+            }
+        }
+        let public_key = match &signed_packet.publisher_id {
+            Identifier::Ed25519(public_key) if signed_packet.signature_check != SignatureCheck::Verify => public_key,
+            publisher_id => {
+                self.verify(publisher_id).await?;
+                return Ok(self);
+            }
+        };
+
+        // Read the signature as it is verified by the identifier
+        let mut oneof = Uint8::default();
+        let mut hash = [0u8; 64];
+        let mut signature = [0u8; ed25519::SIGNATURE_LENGTH];
+        self.absorb(&mut oneof)?;
+        if oneof.inner() != 0 {
+            return Err(SpongosError::InvalidOption("ed25519 signature", oneof.inner()));
+        }
+        self.commit()?
+            .squeeze(External::new(&mut NBytes::new(&mut hash)))?
+            .skip(NBytes::new(&mut signature))?;
+        let read = PacketSignature { hash, signature };
+        match &mut signed_packet.signature_check {
+            SignatureCheck::Collect(collected) => *collected = Some(read),
+            SignatureCheck::Verified(verified) if *verified == read => {}
+            _ => {
+                if !public_key.verify(&ed25519::Signature::from_bytes(signature), &hash) {
+                    return Err(SpongosError::SignatureMismatch);
+                }
+            }
+        }
         Ok(self)
     }
 }
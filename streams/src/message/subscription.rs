@@ -11,6 +11,8 @@
 //! Subscriber must trust channel owner's Ed25519 public key in order to
 //! maintain privacy.
 //!
+//! Subscriptions using an invite of the channel owner carry the invite token, and are published
+//! with their own message type so that they are told apart from plain subscriptions.
 //!
 //! ```ddml
 //! message Subscribe {
@@ -18,13 +20,14 @@
 //!     x25519(pub/priv_key)    u8      x25519_auth_pubkey[32];
 //!     commit;
 //!     mask                    u8      identifier;
+//!     mask                    bytes   invite; // invited subscriptions only
 //!     commit;
 //!     squeeze external        u8      hash[64];
 //!     ed25519(hash)           u8      signature[64];
 //! }
 //! ```
 // Rust
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 
 // 3rd-party
 use async_trait::async_trait;
@@ -36,12 +39,13 @@ use crypto::keys::x25519;
 use lets::{
     id::{Identifier, Identity},
     message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
 };
 use spongos::{
     ddml::{
         commands::{sizeof, unwrap, wrap, Join, Mask, X25519},
         io,
-        types::NBytes,
+        types::{Bytes, NBytes},
     },
     error::Result,
     Spongos,
@@ -57,6 +61,8 @@ pub(crate) struct Wrap<'a> {
     subscriber_id: &'a Identity,
     /// The authors [`x25519::PublicKey`]
     author_ke_pk: &'a x25519::PublicKey,
+    /// The encoded invite token of the subscription, if any
+    invite: Option<&'a [u8]>,
 }
 
 impl<'a> Wrap<'a> {
@@ -78,32 +84,48 @@ impl<'a> Wrap<'a> {
             unsubscribe_key,
             subscriber_id,
             author_ke_pk,
+            invite: None,
         }
     }
+
+    /// Includes an encoded invite token in the subscription
+    ///
+    /// # Arguments
+    /// * `invite`: The encoded invite token
+    pub(crate) fn with_invite(mut self, invite: &'a [u8]) -> Self {
+        self.invite = Some(invite);
+        self
+    }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     async fn sizeof(&mut self, subscription: &Wrap<'a>) -> Result<&mut Self> {
         self.x25519(subscription.author_ke_pk, NBytes::new(subscription.unsubscribe_key))?
-            .mask(subscription.subscriber_id.identifier())?
-            .sign_sizeof(subscription.subscriber_id)
-            .await?;
+            .mask(subscription.subscriber_id.identifier())?;
+        if let Some(invite) = subscription.invite {
+            self.mask(Bytes::new(invite))?;
+        }
+        self.sign_sizeof(subscription.subscriber_id).await?;
         Ok(self)
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
 where
-    OS: io::OStream,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, subscription: &mut Wrap<'a>) -> Result<&mut Self> {
         self.join(subscription.initial_state)?
             .x25519(subscription.author_ke_pk, NBytes::new(subscription.unsubscribe_key))?
-            .mask(subscription.subscriber_id.identifier())?
-            .sign(subscription.subscriber_id)
-            .await?;
+            .mask(subscription.subscriber_id.identifier())?;
+        if let Some(invite) = subscription.invite {
+            self.mask(Bytes::new(invite))?;
+        }
+        self.sign(subscription.subscriber_id).await?;
         Ok(self)
     }
 }
@@ -118,6 +140,8 @@ pub(crate) struct Unwrap<'a> {
     subscriber_identifier: Identifier,
     /// The author's [x25519::SecretKey`]
     author_ke_sk: &'a x25519::SecretKey,
+    /// The encoded invite token of the subscription, for invited subscriptions
+    invite: Option<Vec<u8>>,
 }
 
 impl<'a> Unwrap<'a> {
@@ -132,9 +156,21 @@ impl<'a> Unwrap<'a> {
             unsubscribe_key: Default::default(),
             subscriber_identifier: Default::default(),
             author_ke_sk,
+            invite: None,
         }
     }
 
+    /// Expects an encoded invite token in the subscription
+    pub(crate) fn with_invite(mut self) -> Self {
+        self.invite = Some(Vec::new());
+        self
+    }
+
+    /// Returns the encoded invite token of the subscription, if any
+    pub(crate) fn invite(&self) -> Option<&[u8]> {
+        self.invite.as_deref()
+    }
+
     /// Returns a reference to the [`Identifier`] of the subsriber
     pub(crate) fn subscriber_identifier(&self) -> &Identifier {
         &self.subscriber_identifier
@@ -146,10 +182,11 @@ impl<'a> Unwrap<'a> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
 where
-    IS: io::IStream,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, subscription: &mut Unwrap<'a>) -> Result<&mut Self> {
         self.join(subscription.initial_state)?
@@ -157,9 +194,11 @@ where
                 subscription.author_ke_sk,
                 NBytes::new(&mut subscription.unsubscribe_key),
             )?
-            .mask(&mut subscription.subscriber_identifier)?
-            .verify(&subscription.subscriber_identifier)
-            .await?;
+            .mask(&mut subscription.subscriber_identifier)?;
+        if let Some(invite) = &mut subscription.invite {
+            self.mask(Bytes::new(invite))?;
+        }
+        self.verify(&subscription.subscriber_identifier).await?;
         Ok(self)
     }
 }
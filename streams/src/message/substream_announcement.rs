@@ -0,0 +1,186 @@
+//! `SubstreamAnnouncement` message _wrapping_ and _unwrapping_.
+//!
+//! The `SubstreamAnnouncement` message anchors a new stream, the substream, to a message of the
+//! stream it is published in, the parent message it is linked to.
+//!
+//! It informs of the address and base branch [`Topic`] of the announcement of the substream, as
+//! well as of the hash of that announcement, so that readers can check that the announcement they
+//! fetch is the one anchored by the publisher.
+//!
+//! ```ddml
+//! message SubstreamAnnouncement {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     mask             u8     announcement_address[52];
+//!     mask             u8     topic;
+//!     mask             u8     announcement_hash[32];
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//!     commit;
+//! }
+//! ```
+
+// Rust
+use alloc::boxed::Box;
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::Address,
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap, Topic},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+        types::NBytes,
+    },
+    error::Result,
+    Spongos,
+};
+
+// Local
+
+/// Size of the hash of the announcement of a substream
+pub(crate) const ANNOUNCEMENT_HASH_SIZE: usize = 32;
+
+/// A struct that holds references needed for substream announcement message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the publisher
+    user_id: &'a Identity,
+    /// The [`Address`] of the announcement of the substream
+    announcement_address: &'a Address,
+    /// The base branch [`Topic`] of the substream
+    topic: &'a Topic,
+    /// The hash of the announcement of the substream
+    announcement_hash: &'a [u8; ANNOUNCEMENT_HASH_SIZE],
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a substream announcement message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the publisher
+    /// * `announcement_address`: The [`Address`] of the announcement of the substream
+    /// * `topic`: The base branch [`Topic`] of the substream
+    /// * `announcement_hash`: The hash of the announcement of the substream
+    pub(crate) fn new(
+        initial_state: &'a mut Spongos,
+        user_id: &'a Identity,
+        announcement_address: &'a Address,
+        topic: &'a Topic,
+        announcement_hash: &'a [u8; ANNOUNCEMENT_HASH_SIZE],
+    ) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            announcement_address,
+            topic,
+            announcement_hash,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, announcement: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(announcement.user_id.identifier())?
+            .mask(announcement.announcement_address)?
+            .mask(announcement.topic)?
+            .mask(NBytes::new(announcement.announcement_hash))?
+            .sign_sizeof(announcement.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, announcement: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(announcement.initial_state)?
+            .mask(announcement.user_id.identifier())?
+            .mask(announcement.announcement_address)?
+            .mask(announcement.topic)?
+            .mask(NBytes::new(announcement.announcement_hash))?
+            .sign(announcement.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for substream announcement message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the publisher
+    publisher: Identifier,
+    /// The [`Address`] of the announcement of the substream
+    announcement_address: Address,
+    /// The base branch [`Topic`] of the substream
+    topic: Topic,
+    /// The hash of the announcement of the substream
+    announcement_hash: [u8; ANNOUNCEMENT_HASH_SIZE],
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a substream announcement message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher: Identifier::default(),
+            announcement_address: Address::default(),
+            topic: Topic::default(),
+            announcement_hash: [0; ANNOUNCEMENT_HASH_SIZE],
+        }
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the publisher, the [`Address`] and
+    /// base branch [`Topic`] of the substream announcement, and the hash of the announcement
+    pub(crate) fn into_parts(self) -> (Identifier, Address, Topic, [u8; ANNOUNCEMENT_HASH_SIZE]) {
+        (
+            self.publisher,
+            self.announcement_address,
+            self.topic,
+            self.announcement_hash,
+        )
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, announcement: &mut Unwrap) -> Result<&mut Self> {
+        self.join(announcement.initial_state)?
+            .mask(&mut announcement.publisher)?
+            .mask(&mut announcement.announcement_address)?
+            .mask(&mut announcement.topic)?
+            .mask(NBytes::new(&mut announcement.announcement_hash))?
+            .verify(&announcement.publisher)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
@@ -7,6 +7,7 @@
 //! ```ddml
 //! message TaggedPacket {
 //!     join(spongos);
+//!     absorb external u8 ratchet_key[32]; // forward secrecy branches only
 //!     absorb bytes public_payload;
 //!     mask bytes masked_payload;
 //!     commit;
@@ -22,12 +23,16 @@ use async_trait::async_trait;
 // IOTA
 
 // Streams
-use lets::message::{ContentSizeof, ContentUnwrap, ContentWrap};
+use lets::{
+    message::{ContentSizeof, ContentUnwrap, ContentWrap},
+    sync::MaybeSend,
+};
 use spongos::{
     ddml::{
         commands::{sizeof, unwrap, wrap, Absorb, Commit, Join, Mask, Squeeze},
         io,
-        types::{Bytes, Mac},
+        modifiers::External,
+        types::{Bytes, Mac, NBytes},
     },
     error::Result,
     Spongos,
@@ -46,6 +51,8 @@ pub(crate) struct Wrap<'a> {
     public_payload: &'a [u8],
     /// Payload slice that will be masked
     masked_payload: &'a [u8],
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
 }
 
 impl<'a> Wrap<'a> {
@@ -60,11 +67,23 @@ impl<'a> Wrap<'a> {
             initial_state,
             public_payload,
             masked_payload,
+            ratchet_key: None,
         }
     }
+
+    /// Absorbs the key of the publisher ratchet after the join, if the branch of the packet is in
+    /// forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     async fn sizeof(&mut self, tagged_packet: &Wrap<'a>) -> Result<&mut Self> {
         self.absorb(Bytes::new(tagged_packet.public_payload))?
@@ -75,14 +94,18 @@ impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
 where
-    OS: io::OStream,
+    OS: io::OStream + MaybeSend,
 {
     async fn wrap(&mut self, tagged_packet: &mut Wrap<'a>) -> Result<&mut Self> {
-        self.join(tagged_packet.initial_state)?
-            .absorb(Bytes::new(tagged_packet.public_payload))?
+        self.join(tagged_packet.initial_state)?;
+        if let Some(ratchet_key) = &tagged_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        self.absorb(Bytes::new(tagged_packet.public_payload))?
             .mask(Bytes::new(tagged_packet.masked_payload))?
             .commit()?
             .squeeze(&MAC)?;
@@ -98,6 +121,8 @@ pub(crate) struct Unwrap<'a> {
     public_payload: Vec<u8>,
     /// A payload that was masked
     masked_payload: Vec<u8>,
+    /// Key of the publisher ratchet absorbed after the join, on forward secrecy branches
+    ratchet_key: Option<[u8; 32]>,
 }
 
 impl<'a> Unwrap<'a> {
@@ -110,9 +135,20 @@ impl<'a> Unwrap<'a> {
             initial_state,
             public_payload: Default::default(),
             masked_payload: Default::default(),
+            ratchet_key: None,
         }
     }
 
+    /// Absorbs the expected key of the publisher ratchet after the join, if the branch of the
+    /// packet is in forward secrecy mode
+    ///
+    /// # Arguments
+    /// * `ratchet_key`: The message key derived from the ratchet of the publisher
+    pub(crate) fn with_ratchet_key(mut self, ratchet_key: Option<[u8; 32]>) -> Self {
+        self.ratchet_key = ratchet_key;
+        self
+    }
+
     /// Takes the payload that was masked from the [`Unwrap`]
     pub(crate) fn take_masked_payload(&mut self) -> Vec<u8> {
         core::mem::take(&mut self.masked_payload)
@@ -124,14 +160,18 @@ impl<'a> Unwrap<'a> {
     }
 }
 
-#[async_trait(?Send)]
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
 impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
 where
-    IS: io::IStream,
+    IS: io::IStream + MaybeSend,
 {
     async fn unwrap(&mut self, tagged_packet: &mut Unwrap<'a>) -> Result<&mut Self> {
-        self.join(tagged_packet.initial_state)?
-            .absorb(Bytes::new(&mut tagged_packet.public_payload))?
+        self.join(tagged_packet.initial_state)?;
+        if let Some(ratchet_key) = &tagged_packet.ratchet_key {
+            self.absorb(External::new(&NBytes::new(ratchet_key)))?;
+        }
+        self.absorb(Bytes::new(&mut tagged_packet.public_payload))?
             .mask(Bytes::new(&mut tagged_packet.masked_payload))?
             .commit()?
             .squeeze(&MAC)?;
@@ -0,0 +1,640 @@
+//! Property-based round trips of the message encodings, robustness of their decoding against
+//! arbitrary and corrupted bytes, reuse of the signatures verified in batches, and decoding of the
+//! legacy (v1) messages
+
+// Rust
+use alloc::{string::String, vec::Vec};
+
+// 3rd-party
+use futures::executor::block_on;
+use hashbrown::HashMap;
+use proptest::{collection::vec, prelude::*, sample::Index};
+
+// IOTA
+use crypto::signatures::ed25519;
+
+// Streams
+use lets::{
+    address::MsgId,
+    error::{Error as LetsError, Result as LetsResult},
+    id::{Ed25519, Identifier, Identity, PermissionDuration, Permissioned, Psk},
+    message::{ContentUnwrap, Message as LetsMessage, PreparsedMessage, Topic, TransportMessage, HDF, PCF},
+};
+use spongos::{
+    ddml::{
+        commands::{unwrap, wrap, Absorb, Commit, Ed25519 as _, Join, Mask, Skip, Squeeze},
+        io,
+        modifiers::External,
+        types::{Bytes, NBytes, Uint64, Uint8},
+    },
+    error::{Error as SpongosError, Result as SpongosResult},
+    KeccakF1600, Spongos, SpongosRng,
+};
+
+// Local
+use crate::message::{announcement, keyload, legacy, message_types, signed_packet, subscription};
+
+fn seed() -> impl Strategy<Value = String> {
+    "[A-Z9]{1,27}"
+}
+
+fn topic() -> impl Strategy<Value = Topic> {
+    "[A-Za-z0-9_]{1,32}".prop_map(Topic::from)
+}
+
+fn identity(seed: &str) -> Identity {
+    Ed25519::from_seed(seed).into()
+}
+
+fn permissioned(kind: u8, identifier: Identifier) -> Permissioned<Identifier> {
+    match kind % 3 {
+        0 => Permissioned::Read(identifier),
+        1 => Permissioned::ReadWrite(identifier, PermissionDuration::Perpetual),
+        _ => Permissioned::Admin(identifier),
+    }
+}
+
+async fn preparse(msg: TransportMessage) -> LetsResult<PreparsedMessage> {
+    msg.parse_header().await
+}
+
+async fn wrap_announcement(author: &Identity, topic: &Topic) -> (TransportMessage, Spongos) {
+    let header = HDF::new(message_types::ANNOUNCEMENT, 0, author.identifier().clone(), topic);
+    let content = PCF::new_final_frame().with_content(announcement::Wrap::new(author, topic));
+    LetsMessage::new(header, content).wrap().await.unwrap()
+}
+
+async fn wrap_keyload(
+    mut linked_msg_spongos: Spongos,
+    author: &Identity,
+    topic: &Topic,
+    subscribers: &[Permissioned<Identifier>],
+    psk: &Psk,
+    forward_secrecy: bool,
+) -> (TransportMessage, Spongos) {
+    let psks = vec![(psk.to_pskid(), psk)];
+    let header = HDF::new(message_types::KEYLOAD, 2, author.identifier().clone(), topic);
+    let content = PCF::new_final_frame().with_content(keyload::Wrap::new(
+        &mut linked_msg_spongos,
+        subscribers.iter().map(Permissioned::as_ref).collect::<Vec<_>>(),
+        &psks,
+        [7; 32],
+        [3; 16],
+        author,
+        forward_secrecy,
+    ));
+    LetsMessage::new(header, content).wrap().await.unwrap()
+}
+
+async fn wrap_subscription(
+    mut linked_msg_spongos: Spongos,
+    author: &Identity,
+    subscriber: &Identity,
+    topic: &Topic,
+    unsubscribe_key: [u8; 32],
+) -> (TransportMessage, Spongos) {
+    let author_ke_pk = author.identifier().ke_pk().await.unwrap();
+    let header = HDF::new(message_types::SUBSCRIPTION, 0, subscriber.identifier().clone(), topic);
+    let content = PCF::new_final_frame().with_content(subscription::Wrap::new(
+        &mut linked_msg_spongos,
+        unsubscribe_key,
+        subscriber,
+        &author_ke_pk,
+    ));
+    LetsMessage::new(header, content).wrap().await.unwrap()
+}
+
+async fn wrap_signed_packet(
+    mut linked_msg_spongos: Spongos,
+    publisher: &Identity,
+    topic: &Topic,
+    public_payload: &[u8],
+    masked_payload: &[u8],
+) -> (TransportMessage, Spongos) {
+    let header = HDF::new(message_types::SIGNED_PACKET, 3, publisher.identifier().clone(), topic);
+    let content = PCF::new_final_frame().with_content(signed_packet::Wrap::new(
+        &mut linked_msg_spongos,
+        publisher,
+        public_payload,
+        masked_payload,
+    ));
+    LetsMessage::new(header, content).wrap().await.unwrap()
+}
+
+/// Decodes a message as every message type, discarding the results: decoding must fail cleanly
+/// instead of panicking
+async fn unwrap_all(msg: TransportMessage, linked_msg_spongos: Spongos, author: &Identity, reader: &Identity) {
+    let preparsed = match preparse(msg.clone()).await {
+        Ok(preparsed) => preparsed,
+        Err(_) => return,
+    };
+    let _ = format!("{:?}", preparsed);
+    let _ = preparsed.unwrap(announcement::Unwrap::default()).await;
+
+    let psk_store = HashMap::new();
+    let mut spongos = linked_msg_spongos;
+    let keyload = keyload::Unwrap::new(&mut spongos, Some(reader), author.identifier(), &psk_store);
+    let _ = preparse(msg.clone()).await.unwrap().unwrap(keyload).await;
+
+    let author_ke_sk = author.ke_sk().unwrap();
+    let mut spongos = linked_msg_spongos;
+    let subscription = subscription::Unwrap::new(&mut spongos, &author_ke_sk);
+    let _ = preparse(msg.clone()).await.unwrap().unwrap(subscription).await;
+
+    let mut spongos = linked_msg_spongos;
+    let signed_packet = signed_packet::Unwrap::new(&mut spongos);
+    let _ = preparse(msg).await.unwrap().unwrap(signed_packet).await;
+}
+
+proptest! {
+    #[test]
+    fn announcement_round_trip(author_seed in seed(), topic in topic()) {
+        block_on(async {
+            let author = identity(&author_seed);
+            let (msg, writer_spongos) = wrap_announcement(&author, &topic).await;
+            let (message, reader_spongos) = preparse(msg)
+                .await
+                .unwrap()
+                .unwrap(announcement::Unwrap::default())
+                .await
+                .unwrap();
+            let content = message.into_payload().into_content();
+            prop_assert_eq!(content.author_id(), author.identifier());
+            prop_assert_eq!(content.topic(), &topic);
+            prop_assert!(reader_spongos == writer_spongos);
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn keyload_round_trip(
+        author_seed in seed(),
+        subscriber_seeds in vec((seed(), any::<u8>()), 0..6),
+        psk in any::<[u8; 32]>(),
+        forward_secrecy in any::<bool>(),
+        topic in topic(),
+    ) {
+        block_on(async {
+            let author = identity(&author_seed);
+            let subscribers = subscriber_seeds
+                .iter()
+                .map(|(seed, _)| identity(seed))
+                .collect::<Vec<_>>();
+            let permissions = subscribers
+                .iter()
+                .zip(&subscriber_seeds)
+                .map(|(subscriber, (_, kind))| permissioned(*kind, subscriber.identifier().clone()))
+                .collect::<Vec<_>>();
+            let psk = Psk::new(psk);
+            let (_, announcement_spongos) = wrap_announcement(&author, &topic).await;
+            let (msg, writer_spongos) =
+                wrap_keyload(announcement_spongos, &author, &topic, &permissions, &psk, forward_secrecy).await;
+
+            // Every subscriber recovers the key and the permissions
+            let no_psks = HashMap::new();
+            for subscriber in &subscribers {
+                let mut spongos = announcement_spongos;
+                let keyload = keyload::Unwrap::new(&mut spongos, Some(subscriber), author.identifier(), &no_psks);
+                let (message, reader_spongos) = preparse(msg.clone()).await.unwrap().unwrap(keyload).await.unwrap();
+                let content = message.into_payload().into_content();
+                prop_assert_eq!(content.subscribers(), &permissions[..]);
+                prop_assert_eq!(content.forward_secrecy, forward_secrecy);
+                prop_assert!(reader_spongos == writer_spongos);
+            }
+
+            // So does a holder of the PSK
+            let psk_store = vec![(psk.to_pskid(), psk)].into_iter().collect::<HashMap<_, _>>();
+            let mut spongos = announcement_spongos;
+            let keyload = keyload::Unwrap::new(&mut spongos, None, author.identifier(), &psk_store);
+            let (message, reader_spongos) = preparse(msg.clone()).await.unwrap().unwrap(keyload).await.unwrap();
+            prop_assert_eq!(message.into_payload().into_content().psks, vec![psk.to_pskid()]);
+            prop_assert!(reader_spongos == writer_spongos);
+
+            // But not an outsider
+            let outsider = identity("outsider");
+            let mut spongos = announcement_spongos;
+            let keyload = keyload::Unwrap::new(&mut spongos, Some(&outsider), author.identifier(), &no_psks);
+            let (_, reader_spongos) = preparse(msg).await.unwrap().unwrap(keyload).await.unwrap();
+            prop_assert!(reader_spongos != writer_spongos);
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn subscription_round_trip(
+        author_seed in seed(),
+        subscriber_seed in seed(),
+        unsubscribe_key in any::<[u8; 32]>(),
+        topic in topic(),
+    ) {
+        block_on(async {
+            let author = identity(&author_seed);
+            let subscriber = identity(&subscriber_seed);
+            let (_, announcement_spongos) = wrap_announcement(&author, &topic).await;
+            let (msg, _) = wrap_subscription(announcement_spongos, &author, &subscriber, &topic, unsubscribe_key).await;
+
+            let author_ke_sk = author.ke_sk().unwrap();
+            let mut spongos = announcement_spongos;
+            let subscription = subscription::Unwrap::new(&mut spongos, &author_ke_sk);
+            let (message, _) = preparse(msg).await.unwrap().unwrap(subscription).await.unwrap();
+            prop_assert_eq!(
+                message.into_payload().into_content().subscriber_identifier(),
+                subscriber.identifier()
+            );
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn signed_packet_round_trip(
+        publisher_seed in seed(),
+        public_payload in vec(any::<u8>(), 0..512),
+        masked_payload in vec(any::<u8>(), 0..512),
+        topic in topic(),
+    ) {
+        block_on(async {
+            let publisher = identity(&publisher_seed);
+            let (_, announcement_spongos) = wrap_announcement(&publisher, &topic).await;
+            let (msg, writer_spongos) =
+                wrap_signed_packet(announcement_spongos, &publisher, &topic, &public_payload, &masked_payload).await;
+
+            let mut spongos = announcement_spongos;
+            let signed_packet = signed_packet::Unwrap::new(&mut spongos);
+            let (message, reader_spongos) = preparse(msg).await.unwrap().unwrap(signed_packet).await.unwrap();
+            let mut content = message.into_payload().into_content();
+            prop_assert_eq!(content.take_public_payload(), public_payload);
+            prop_assert_eq!(content.take_masked_payload(), masked_payload);
+            prop_assert_eq!(&content.into_publisher_identifier(), publisher.identifier());
+            prop_assert!(reader_spongos == writer_spongos);
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn unwrap_never_panics_on_arbitrary_bytes(bytes in vec(any::<u8>(), 0..1024)) {
+        block_on(async {
+            let author = identity("AUTHOR");
+            let reader = identity("READER");
+            let topic = Topic::from("BASE_BRANCH");
+            let (_, announcement_spongos) = wrap_announcement(&author, &topic).await;
+            unwrap_all(TransportMessage::new(bytes), announcement_spongos, &author, &reader).await;
+        });
+    }
+
+    #[test]
+    fn unwrap_never_panics_on_corrupted_messages(
+        kind in 0..4usize,
+        cut in any::<Index>(),
+        flips in vec((any::<Index>(), 1..=u8::MAX), 0..4),
+    ) {
+        block_on(async {
+            let author = identity("AUTHOR");
+            let reader = identity("READER");
+            let topic = Topic::from("BASE_BRANCH");
+            let (announcement, announcement_spongos) = wrap_announcement(&author, &topic).await;
+            let (msg, _) = match kind {
+                0 => (announcement, announcement_spongos),
+                1 => {
+                    let subscribers = [Permissioned::Read(reader.identifier().clone())];
+                    let psk = Psk::from_seed("PSK");
+                    wrap_keyload(announcement_spongos, &author, &topic, &subscribers, &psk, true).await
+                }
+                2 => wrap_subscription(announcement_spongos, &author, &reader, &topic, [1; 32]).await,
+                _ => wrap_signed_packet(announcement_spongos, &author, &topic, b"PUBLIC", b"MASKED").await,
+            };
+
+            let mut bytes = msg.as_ref().to_vec();
+            bytes.truncate(cut.index(bytes.len() + 1));
+            if !bytes.is_empty() {
+                for (position, flip) in &flips {
+                    let position = position.index(bytes.len());
+                    bytes[position] ^= flip;
+                }
+            }
+            unwrap_all(TransportMessage::new(bytes), announcement_spongos, &author, &reader).await;
+        });
+    }
+}
+
+/// Unwraps a signed packet, collecting its signature instead of verifying it
+async fn collect_signature(msg: TransportMessage, mut linked_msg_spongos: Spongos) -> signed_packet::PacketSignature {
+    let signed_packet = signed_packet::Unwrap::new(&mut linked_msg_spongos).collect_signature();
+    let (message, _) = preparse(msg).await.unwrap().unwrap(signed_packet).await.unwrap();
+    message.into_payload().into_content().take_signature().unwrap()
+}
+
+/// Unwraps a signed packet, skipping the verification of the `verified` signature
+async fn unwrap_verified(
+    msg: TransportMessage,
+    mut linked_msg_spongos: Spongos,
+    verified: signed_packet::PacketSignature,
+) -> LetsResult<()> {
+    let signed_packet = signed_packet::Unwrap::new(&mut linked_msg_spongos).with_verified_signature(Some(verified));
+    preparse(msg).await?.unwrap(signed_packet).await.map(|_| ())
+}
+
+/// Replaces the last byte of the signature of a message
+fn forge(msg: &TransportMessage) -> TransportMessage {
+    let mut bytes = msg.as_ref().to_vec();
+    *bytes.last_mut().unwrap() ^= 1;
+    TransportMessage::new(bytes)
+}
+
+#[test]
+fn verified_signatures_are_not_reused_for_other_packets() {
+    block_on(async {
+        let alice = identity("ALICE");
+        let bob = identity("BOB");
+        let topic = Topic::from("BASE_BRANCH");
+        let (_, announcement_spongos) = wrap_announcement(&alice, &topic).await;
+        let (first, _) = wrap_signed_packet(announcement_spongos, &alice, &topic, b"PUBLIC", b"FIRST").await;
+        let (second, _) = wrap_signed_packet(announcement_spongos, &alice, &topic, b"PUBLIC", b"SECOND").await;
+        let (by_bob, _) = wrap_signed_packet(announcement_spongos, &bob, &topic, b"PUBLIC", b"FIRST").await;
+        let verified = collect_signature(first.clone(), announcement_spongos).await;
+
+        // The verified signature is reused for its own packet without being verified again
+        assert!(unwrap_verified(first, announcement_spongos, verified).await.is_ok());
+
+        // The packets whose signature differs are verified as usual
+        assert!(unwrap_verified(second.clone(), announcement_spongos, verified)
+            .await
+            .is_ok());
+        for msg in [second, by_bob] {
+            let forged = forge(&msg);
+            let signature = collect_signature(msg, announcement_spongos).await;
+            let forged_signature = collect_signature(forged.clone(), announcement_spongos).await;
+            assert!(signature.hash == forged_signature.hash && signature.signature != forged_signature.signature);
+            assert!(matches!(
+                unwrap_verified(forged, announcement_spongos, verified).await,
+                Err(LetsError::Spongos(SpongosError::SignatureMismatch))
+            ));
+        }
+    });
+}
+
+/// Legacy (v1) Keyload Message Type, whose content is not decoded
+const LEGACY_KEYLOAD: u8 = 1;
+
+/// Size of a legacy message header
+const LEGACY_HEADER_SIZE: usize = 32;
+
+fn legacy_key(seed: &str) -> ed25519::SecretKey {
+    ed25519::SecretKey::generate_with(&mut SpongosRng::<KeccakF1600>::new(seed))
+}
+
+/// Encodes a legacy header the way the v1 channels did
+fn wrap_legacy_header<OS: io::OStream>(
+    ctx: &mut wrap::Context<OS>,
+    message_type: u8,
+    link: &MsgId,
+    sequence: u64,
+) -> SpongosResult<()> {
+    ctx.absorb(Uint8::new(0))?
+        .absorb(Uint8::new(legacy::LEGACY_STREAMS_VER))?
+        .skip(NBytes::new([message_type << 4, 0]))?
+        .absorb(External::new(Uint8::new(message_type << 4)))?
+        .absorb(Uint8::new(0))?
+        .skip(NBytes::new([0, 0, 1]))?
+        .absorb(link)?
+        .skip(Uint64::new(sequence))?
+        .absorb(Uint8::new(0))?
+        .skip(NBytes::new([0, 0, 0]))?;
+    Ok(())
+}
+
+/// Signs the state of a legacy message the way the v1 channels did
+fn sign_legacy<OS: io::OStream>(ctx: &mut wrap::Context<OS>, secret_key: &ed25519::SecretKey) -> SpongosResult<()> {
+    let mut hash = External::new(NBytes::new([0; 64]));
+    ctx.commit()?
+        .squeeze(hash.as_mut())?
+        .ed25519(secret_key, hash.as_ref())?;
+    Ok(())
+}
+
+/// Encodes a legacy message, returning its bytes and the [`Spongos`] state the following messages
+/// are linked to
+fn wrap_legacy<W>(write: W) -> (TransportMessage, Spongos)
+where
+    W: FnOnce(&mut wrap::Context<&mut [u8]>) -> SpongosResult<()>,
+{
+    let mut buf = vec![0; 2048];
+    let mut ctx = wrap::Context::new(&mut buf[..]);
+    write(&mut ctx).unwrap();
+    let size = 2048 - ctx.stream().len();
+    let spongos = ctx.finalize();
+    buf.truncate(size);
+    (TransportMessage::new(buf), spongos)
+}
+
+fn wrap_legacy_announce(author: &ed25519::SecretKey) -> (TransportMessage, Spongos) {
+    wrap_legacy(|ctx| {
+        wrap_legacy_header(ctx, legacy::ANNOUNCE, &MsgId::default(), 0)?;
+        ctx.absorb(&author.public_key())?.absorb(Uint8::new(0))?;
+        sign_legacy(ctx, author)
+    })
+}
+
+fn wrap_legacy_signed_packet(
+    mut linked_msg_spongos: Spongos,
+    link: &MsgId,
+    sequence: u64,
+    publisher: &ed25519::SecretKey,
+    public_payload: &[u8],
+    masked_payload: &[u8],
+) -> (TransportMessage, Spongos) {
+    wrap_legacy(|ctx| {
+        wrap_legacy_header(ctx, legacy::SIGNED_PACKET, link, sequence)?;
+        ctx.join(&mut linked_msg_spongos)?
+            .absorb(&publisher.public_key())?
+            .absorb(Bytes::new(public_payload))?
+            .mask(Bytes::new(masked_payload))?;
+        sign_legacy(ctx, publisher)
+    })
+}
+
+async fn unwrap_legacy_announce(msg: &TransportMessage) -> SpongosResult<(legacy::Header, Identifier, Spongos)> {
+    let mut ctx = unwrap::Context::new(msg.as_ref());
+    let mut header = legacy::Header::default();
+    let mut announce = legacy::Announce::default();
+    ctx.unwrap(&mut header).await?.unwrap(&mut announce).await?;
+    Ok((header, announce.into_author_id(), ctx.finalize().0))
+}
+
+async fn unwrap_legacy_signed_packet(
+    msg: &TransportMessage,
+    mut linked_msg_spongos: Spongos,
+) -> SpongosResult<(legacy::Header, Identifier, Vec<u8>, Vec<u8>)> {
+    let mut ctx = unwrap::Context::new(msg.as_ref());
+    let mut header = legacy::Header::default();
+    ctx.unwrap(&mut header).await?;
+    let mut signed_packet = legacy::SignedPacket::new(&mut linked_msg_spongos);
+    ctx.unwrap(&mut signed_packet).await?;
+    let (publisher, public_payload, masked_payload) = signed_packet.into_parts();
+    Ok((header, publisher, public_payload, masked_payload))
+}
+
+proptest! {
+    #[test]
+    fn legacy_round_trip(
+        author_seed in seed(),
+        public_payload in vec(any::<u8>(), 0..512),
+        masked_payload in vec(any::<u8>(), 0..512),
+        link in any::<[u8; 12]>(),
+        sequence in any::<u64>(),
+    ) {
+        block_on(async {
+            let author = legacy_key(&author_seed);
+            let author_id = Identifier::Ed25519(author.public_key());
+            let (announce, writer_spongos) = wrap_legacy_announce(&author);
+            prop_assert!(legacy::is_legacy(&announce));
+            let (header, announcer, reader_spongos) = unwrap_legacy_announce(&announce).await.unwrap();
+            prop_assert_eq!(header.message_type(), legacy::ANNOUNCE);
+            prop_assert_eq!(&announcer, &author_id);
+            prop_assert!(reader_spongos == writer_spongos);
+
+            let link = MsgId::from(link);
+            let (packet, _) =
+                wrap_legacy_signed_packet(writer_spongos, &link, sequence, &author, &public_payload, &masked_payload);
+            prop_assert!(legacy::is_legacy(&packet));
+            let (header, publisher, public, masked) =
+                unwrap_legacy_signed_packet(&packet, reader_spongos).await.unwrap();
+            prop_assert_eq!(header.message_type(), legacy::SIGNED_PACKET);
+            prop_assert_eq!(header.link(), link);
+            prop_assert_eq!(header.sequence(), sequence);
+            prop_assert_eq!(&publisher, &author_id);
+            prop_assert_eq!(public, public_payload);
+            prop_assert_eq!(masked, masked_payload);
+            Ok(())
+        })?;
+    }
+}
+
+#[test]
+fn legacy_channel_fixture_is_decoded() {
+    block_on(async {
+        // A channel opened by an author, with a packet of a subscriber linked to the announce
+        let author = legacy_key("LEGACYAUTHOR");
+        let subscriber = legacy_key("LEGACYSUBSCRIBER");
+        let announce_link = MsgId::from([1; 12]);
+        let (announce, announce_spongos) = wrap_legacy_announce(&author);
+        let (packet, _) = wrap_legacy_signed_packet(
+            announce_spongos,
+            &announce_link,
+            2,
+            &subscriber,
+            b"public legacy payload",
+            b"masked legacy payload",
+        );
+
+        // The bytes are laid out as the v1 channels laid them out
+        assert_eq!(announce.as_ref().len(), LEGACY_HEADER_SIZE + 32 + 1 + 64);
+        assert_eq!(
+            &announce.as_ref()[..3],
+            &[0, legacy::LEGACY_STREAMS_VER, legacy::ANNOUNCE << 4]
+        );
+        assert_eq!(
+            &packet.as_ref()[..3],
+            &[0, legacy::LEGACY_STREAMS_VER, legacy::SIGNED_PACKET << 4]
+        );
+        assert_eq!(&packet.as_ref()[8..20], announce_link.as_ref());
+        assert_eq!(&packet.as_ref()[20..28], &2u64.to_be_bytes());
+        assert_eq!(
+            &packet.as_ref()[LEGACY_HEADER_SIZE..LEGACY_HEADER_SIZE + 32],
+            subscriber.public_key().as_slice()
+        );
+
+        let (_, announcer, spongos) = unwrap_legacy_announce(&announce).await.unwrap();
+        assert_eq!(announcer, Identifier::Ed25519(author.public_key()));
+        let (header, publisher, public_payload, masked_payload) =
+            unwrap_legacy_signed_packet(&packet, spongos).await.unwrap();
+        assert_eq!(header.link(), announce_link);
+        assert_eq!(header.sequence(), 2);
+        assert_eq!(publisher, Identifier::Ed25519(subscriber.public_key()));
+        assert_eq!(public_payload, b"public legacy payload");
+        assert_eq!(masked_payload, b"masked legacy payload");
+
+        // The masked payload is not readable without the state of the announce
+        assert!(!packet
+            .as_ref()
+            .windows(masked_payload.len())
+            .any(|window| window == masked_payload));
+    });
+}
+
+#[test]
+fn legacy_keyload_header_is_decoded_but_not_its_content() {
+    block_on(async {
+        let author = legacy_key("LEGACYAUTHOR");
+        let (_, announce_spongos) = wrap_legacy_announce(&author);
+        let mut linked_msg_spongos = announce_spongos;
+        let (keyload, _) = wrap_legacy(|ctx| {
+            wrap_legacy_header(ctx, LEGACY_KEYLOAD, &MsgId::from([1; 12]), 1)?;
+            ctx.join(&mut linked_msg_spongos)?
+                .absorb(NBytes::new([7; 16]))?
+                .mask(NBytes::new([3; 32]))?
+                .commit()?;
+            Ok(())
+        });
+
+        let mut header = legacy::Header::default();
+        unwrap::Context::new(keyload.as_ref())
+            .unwrap(&mut header)
+            .await
+            .unwrap();
+        assert_eq!(header.message_type(), LEGACY_KEYLOAD);
+        assert_eq!(header.sequence(), 1);
+        assert!(unwrap_legacy_announce(&keyload).await.is_err());
+        assert!(unwrap_legacy_signed_packet(&keyload, announce_spongos).await.is_err());
+    });
+}
+
+#[test]
+fn malformed_legacy_messages_are_rejected() {
+    block_on(async {
+        let author = legacy_key("LEGACYAUTHOR");
+        let link = MsgId::from([1; 12]);
+        let (announce, announce_spongos) = wrap_legacy_announce(&author);
+        let (packet, _) = wrap_legacy_signed_packet(announce_spongos, &link, 1, &author, b"PUBLIC", b"MASKED");
+        let tampered = |msg: &TransportMessage, position: usize| {
+            let mut bytes = msg.as_ref().to_vec();
+            bytes[position] ^= 1;
+            TransportMessage::new(bytes)
+        };
+
+        // Truncated messages
+        for size in [0, 1, LEGACY_HEADER_SIZE, announce.as_ref().len() - 1] {
+            let truncated = TransportMessage::new(announce.as_ref()[..size].to_vec());
+            assert!(unwrap_legacy_announce(&truncated).await.is_err());
+        }
+        let truncated = TransportMessage::new(packet.as_ref()[..packet.as_ref().len() - 1].to_vec());
+        assert!(unwrap_legacy_signed_packet(&truncated, announce_spongos).await.is_err());
+
+        // Another version
+        let current = tampered(&announce, 1);
+        assert!(!legacy::is_legacy(&current));
+        assert!(matches!(
+            unwrap_legacy_announce(&current).await,
+            Err(SpongosError::Version("Legacy Msg", 0))
+        ));
+
+        // Forged signatures and contents
+        let last = announce.as_ref().len() - 1;
+        assert!(unwrap_legacy_announce(&tampered(&announce, last)).await.is_err());
+        assert!(unwrap_legacy_announce(&tampered(&announce, LEGACY_HEADER_SIZE + 32))
+            .await
+            .is_err());
+        let last = packet.as_ref().len() - 1;
+        assert!(unwrap_legacy_signed_packet(&tampered(&packet, last), announce_spongos)
+            .await
+            .is_err());
+        assert!(
+            unwrap_legacy_signed_packet(&tampered(&packet, last - 64), announce_spongos)
+                .await
+                .is_err()
+        );
+
+        // A packet linked to another message
+        let (_, other_spongos) = wrap_legacy_announce(&legacy_key("OTHERAUTHOR"));
+        assert!(unwrap_legacy_signed_packet(&packet, other_spongos).await.is_err());
+    });
+}
@@ -0,0 +1,178 @@
+//! `Tombstone` message _wrapping_ and _unwrapping_.
+//!
+//! The `Tombstone` message is published by the author of a stream to redact a message of one of
+//! its branches. Ledger data being immutable, the redacted message stays on the Tangle: readers
+//! withhold its payloads, while the original content remains available on request.
+//!
+//! ```ddml
+//! message Tombstone {
+//!     join(spongos);
+//!     mask             u8     identifier;
+//!     mask             u8     redacted_msgid[12];
+//!     mask             bytes  reason;
+//!     commit;
+//!     squeeze          u8     hash[64];
+//!     ed25519(hash)           sig;
+//! }
+//! ```
+
+// Rust
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+// 3rd-party
+use async_trait::async_trait;
+
+// IOTA
+
+// Streams
+use lets::{
+    address::MsgId,
+    id::{Identifier, Identity},
+    message::{ContentSign, ContentSignSizeof, ContentSizeof, ContentUnwrap, ContentVerify, ContentWrap},
+    sync::MaybeSend,
+};
+use spongos::{
+    ddml::{
+        commands::{sizeof, unwrap, wrap, Commit, Join, Mask},
+        io,
+        types::Bytes,
+    },
+    error::{Error as SpongosError, Result},
+    Spongos,
+};
+
+// Local
+
+/// A struct that holds references needed for tombstone message encoding
+pub(crate) struct Wrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identity`] of the stream author
+    user_id: &'a Identity,
+    /// The [`MsgId`] of the redacted message
+    redacted: &'a MsgId,
+    /// The reason of the redaction
+    reason: &'a str,
+}
+
+impl<'a> Wrap<'a> {
+    /// Creates a new [`Wrap`] struct for a tombstone message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    /// * `user_id`: The [`Identity`] of the stream author
+    /// * `redacted`: The [`MsgId`] of the redacted message
+    /// * `reason`: The reason of the redaction
+    pub(crate) fn new(
+        initial_state: &'a mut Spongos,
+        user_id: &'a Identity,
+        redacted: &'a MsgId,
+        reason: &'a str,
+    ) -> Self {
+        Self {
+            initial_state,
+            user_id,
+            redacted,
+            reason,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a> ContentSizeof<Wrap<'a>> for sizeof::Context {
+    async fn sizeof(&mut self, tombstone: &Wrap<'a>) -> Result<&mut Self> {
+        self.mask(tombstone.user_id.identifier())?
+            .mask(tombstone.redacted)?
+            .mask(Bytes::new(tombstone.reason))?
+            .sign_sizeof(tombstone.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, OS> ContentWrap<Wrap<'a>> for wrap::Context<OS>
+where
+    OS: io::OStream + MaybeSend,
+{
+    async fn wrap(&mut self, tombstone: &mut Wrap<'a>) -> Result<&mut Self> {
+        self.join(tombstone.initial_state)?
+            .mask(tombstone.user_id.identifier())?
+            .mask(tombstone.redacted)?
+            .mask(Bytes::new(tombstone.reason))?
+            .sign(tombstone.user_id)
+            .await?
+            .commit()?;
+        Ok(self)
+    }
+}
+
+/// A struct that holds the placeholders needed for tombstone message decoding
+pub(crate) struct Unwrap<'a> {
+    /// The base [`Spongos`] state that the message will be joined to
+    initial_state: &'a mut Spongos,
+    /// The [`Identifier`] of the stream author
+    publisher: Identifier,
+    /// The [`MsgId`] of the redacted message
+    redacted: MsgId,
+    /// The reason of the redaction
+    reason: String,
+}
+
+impl<'a> Unwrap<'a> {
+    /// Creates a new [`Unwrap`] struct for a tombstone message
+    ///
+    /// # Arguments
+    /// * `initial_state`: The initial [`Spongos`] state the message will be joined to
+    pub(crate) fn new(initial_state: &'a mut Spongos) -> Self {
+        Self {
+            initial_state,
+            publisher: Identifier::default(),
+            redacted: MsgId::default(),
+            reason: String::new(),
+        }
+    }
+
+    /// Returns the [`MsgId`] of the redacted message
+    pub(crate) fn redacted(&self) -> MsgId {
+        self.redacted
+    }
+
+    /// Returns the reason of the redaction
+    pub(crate) fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Consumes the [`Unwrap`], returning the [`Identifier`] of the stream author, the [`MsgId`] of
+    /// the redacted message and the reason of the redaction
+    pub(crate) fn into_parts(self) -> (Identifier, MsgId, String) {
+        (self.publisher, self.redacted, self.reason)
+    }
+}
+
+#[cfg_attr(not(feature = "threadsafe"), async_trait(?Send))]
+#[cfg_attr(feature = "threadsafe", async_trait)]
+impl<'a, IS> ContentUnwrap<Unwrap<'a>> for unwrap::Context<IS>
+where
+    IS: io::IStream + MaybeSend,
+{
+    async fn unwrap(&mut self, tombstone: &mut Unwrap) -> Result<&mut Self> {
+        let mut reason = Vec::new();
+        self.join(tombstone.initial_state)?
+            .mask(&mut tombstone.publisher)?
+            .mask(&mut tombstone.redacted)?
+            .mask(Bytes::new(&mut reason))?
+            .verify(&tombstone.publisher)
+            .await?
+            .commit()?;
+        tombstone.reason = String::from_utf8(reason).map_err(|e| SpongosError::Context("Mask", e.to_string()))?;
+        Ok(self)
+    }
+}